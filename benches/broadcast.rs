@@ -0,0 +1,92 @@
+//! Allocations and time for broadcasting puts to many peers: connections with
+//! an `Outbox`, which share each serialized frame, versus plain channels,
+//! which get a copy per peer
+//!
+//! Run with `cargo bench --bench broadcast`. Allocations are counted by a
+//! wrapper around the system allocator.
+
+use chia_bls::SecretKey;
+use gun::core::GunCore;
+use gun::dam::{Mesh, Outbox, Peer};
+use serde_json::json;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+const PUTS: usize = 1000;
+const PEERS: usize = 10;
+
+/// System allocator that counts allocations and bytes allocated
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+async fn run(name: &str, outbox: bool) {
+    let key = SecretKey::from_seed(&[1u8; 32]);
+    let mesh = Mesh::new(Arc::new(GunCore::new()), key.clone(), key.public_key(), None);
+
+    // Receivers are kept, not drained, so every frame stays queued
+    let mut outboxes = Vec::new();
+    let mut channels = Vec::new();
+    for i in 0..PEERS {
+        let mut peer = Peer::new(format!("ws://bench/{}", i));
+        if outbox {
+            let (tx, rx) = Outbox::new();
+            peer.set_sender(tx);
+            outboxes.push(rx);
+        } else {
+            let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+            peer.set_sender(tx);
+            channels.push(rx);
+        }
+        mesh.hi(peer).await.unwrap();
+    }
+
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let allocated = ALLOCATED.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for i in 0..PUTS {
+        let put = json!({ "put": { "counter": { "_": { "#": "counter", ">": { "n": i } }, "n": i } } });
+        mesh.say(&put, None).await.unwrap();
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "{:<8} {} puts to {} peers: {:>8} allocations, {:>10} bytes allocated, {:?}",
+        name,
+        PUTS,
+        PEERS,
+        ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+        ALLOCATED.load(Ordering::Relaxed) - allocated,
+        elapsed
+    );
+}
+
+#[tokio::main]
+async fn main() {
+    run("channel", false).await;
+    run("outbox", true).await;
+}
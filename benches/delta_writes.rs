@@ -0,0 +1,77 @@
+//! Bytes written for single-key updates to a large node: whole-node `put`
+//! versus `put_delta`
+//!
+//! Run with `cargo bench --bench delta_writes`. Bytes written are read from
+//! `/proc/self/io` (`wchar`), so numbers are only reported on Linux.
+
+use gun::state::{Node, State};
+use gun::storage::{SledStorage, Storage};
+use serde_json::json;
+use std::time::Instant;
+
+const NODE_KEYS: usize = 10_000;
+const UPDATES: usize = 100;
+
+/// Bytes this process has passed to write(2) so far
+fn bytes_written() -> Option<u64> {
+    let io = std::fs::read_to_string("/proc/self/io").ok()?;
+    io.lines()
+        .find_map(|line| line.strip_prefix("wchar: "))
+        .and_then(|n| n.trim().parse().ok())
+}
+
+fn large_node() -> Node {
+    let mut node = Node::with_soul("index".to_string());
+    for i in 0..NODE_KEYS {
+        let key = format!("item{}", i);
+        State::ify(&mut node, Some(&key), Some(1.0), Some(json!({"#": key})), Some("index"));
+    }
+    node
+}
+
+async fn run(name: &str, delta: bool) {
+    let dir = std::env::temp_dir().join(format!("gun_bench_delta_{}", name));
+    let _ = std::fs::remove_dir_all(&dir);
+    let storage = SledStorage::new(dir.to_str().unwrap()).unwrap();
+    let mut node = large_node();
+    storage.put("index", &node).await.unwrap();
+
+    let before = bytes_written();
+    let start = Instant::now();
+    for i in 0..UPDATES {
+        let key = format!("item{}", i);
+        let value = json!(i);
+        let state = 2.0 + i as f64;
+        if delta {
+            storage
+                .put_delta("index", &[(key, value, state)])
+                .await
+                .unwrap();
+        } else {
+            State::ify(&mut node, Some(&key), Some(state), Some(value), Some("index"));
+            storage.put("index", &node).await.unwrap();
+        }
+    }
+    let elapsed = start.elapsed();
+
+    match (before, bytes_written()) {
+        (Some(before), Some(after)) => println!(
+            "{:<10} {} updates: {:>12} bytes written, {:?}",
+            name,
+            UPDATES,
+            after - before,
+            elapsed
+        ),
+        _ => println!("{:<10} {} updates: {:?}", name, UPDATES, elapsed),
+    }
+
+    drop(storage);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::main]
+async fn main() {
+    println!("Node with {} keys, {} single-key updates (SledStorage)", NODE_KEYS, UPDATES);
+    run("put", false).await;
+    run("put_delta", true).await;
+}
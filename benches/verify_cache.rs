@@ -0,0 +1,103 @@
+//! Time spent verifying a replayed traffic trace, with and without the
+//! verification caches: every message arrives from several peers, as on a
+//! relay, and signed user-space values are verified again on each read
+//!
+//! Run with `cargo bench --bench verify_cache`. The runtime has one thread,
+//! so the times are the CPU the checks took.
+
+use chia_bls::SecretKey;
+use gun::core::GunCore;
+use gun::dam::{Mesh, Peer};
+use gun::sea::{pair, sign, verify, verify_cache};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Instant;
+
+const MESSAGES: usize = 200;
+const COPIES: usize = 5;
+const VALUES: usize = 200;
+const READS: usize = 5;
+
+fn mesh(seed: u8) -> Mesh {
+    let key = SecretKey::from_seed(&[seed; 32]);
+    Mesh::new(Arc::new(GunCore::new()), key.clone(), key.public_key(), None)
+}
+
+/// Frames as an origin mesh sends them
+async fn trace() -> Vec<String> {
+    let origin = mesh(1);
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+    let mut peer = Peer::new("ws://bench/relay".to_string());
+    peer.set_sender(tx);
+    origin.hi(peer).await.unwrap();
+    while rx.try_recv().is_ok() {}
+
+    let mut frames = Vec::with_capacity(MESSAGES);
+    for i in 0..MESSAGES {
+        let put = json!({ "put": { "counter": { "_": { "#": "counter", ">": { "n": i } }, "n": i } } });
+        origin.say(&put, None).await.unwrap();
+        while let Ok(frame) = rx.try_recv() {
+            frames.push(frame);
+        }
+    }
+    frames
+}
+
+async fn replay(name: &str, frames: &[String], relay: Mesh) {
+    let mut peers = Vec::new();
+    let mut channels = Vec::new();
+    for i in 0..COPIES {
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+        let mut peer = Peer::new(format!("ws://bench/{}", i));
+        peer.set_sender(tx);
+        relay.hi(peer.clone()).await.unwrap();
+        peers.push(peer);
+        channels.push(rx);
+    }
+
+    let start = Instant::now();
+    for frame in frames {
+        for peer in &peers {
+            relay.hear(frame, Some(peer)).await.unwrap();
+        }
+    }
+    let elapsed = start.elapsed();
+
+    let stats = relay.stats();
+    println!(
+        "BLS {:<9} {} messages x {} copies: {:>5} cache hits, {:?}",
+        name,
+        frames.len(),
+        COPIES,
+        stats.signature_cache_hits,
+        elapsed
+    );
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let frames = trace().await;
+    replay("uncached", &frames, mesh(2).with_verify_cache(0)).await;
+    replay("cached", &frames, mesh(3)).await;
+
+    // SEA: the first read of each value verifies it, later reads hit the cache
+    let author = pair().await.unwrap();
+    let mut values = Vec::with_capacity(VALUES);
+    for i in 0..VALUES {
+        values.push(sign(&json!({ "n": i }), &author).await.unwrap());
+    }
+    for read in 0..READS {
+        let hits = verify_cache().stats().hits;
+        let start = Instant::now();
+        for value in &values {
+            verify(value, &author.pub_key).await.unwrap();
+        }
+        println!(
+            "SEA read {}   {} values: {:>5} cache hits, {:?}",
+            read + 1,
+            VALUES,
+            verify_cache().stats().hits - hits,
+            start.elapsed()
+        );
+    }
+}
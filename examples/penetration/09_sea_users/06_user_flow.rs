@@ -35,7 +35,7 @@ async fn main() {
                     
                     // Step 3: Recall
                     println!("\n--- Step 3: Recall ---");
-                    match recall(Some(chain.clone()), None).await {
+                    match recall(Some(chain.clone()), None, None).await {
                         Ok(Some(recalled)) => {
                             println!("✓ Session recalled");
                             if recalled.pair.pub_key == pub_key {
@@ -16,12 +16,12 @@ async fn main() {
         Ok(pair1) => {
             match pair().await {
                 Ok(pair2) => {
-                    if let (Some(ref epub1), Some(ref epriv1)) = (pair1.epub_key.as_ref(), pair1.epriv_key.as_ref()) {
-                        if let (Some(ref epub2), Some(ref epriv2)) = (pair2.epub_key.as_ref(), pair2.epriv_key.as_ref()) {
+                    if let (Some(ref epub1), Some(_)) = (pair1.epub_key.as_ref(), pair1.epriv_key.as_ref()) {
+                        if let (Some(ref epub2), Some(_)) = (pair2.epub_key.as_ref(), pair2.epriv_key.as_ref()) {
                             println!("\n--- Test: ECDH secret derivation ---");
-                            match secret(epub1, epriv2, epub2).await {
+                            match secret(epub1, &pair2).await {
                                 Ok(secret1) => {
-                                    match secret(epub2, epriv1, epub1).await {
+                                    match secret(epub2, &pair1).await {
                                         Ok(secret2) => {
                                             if secret1 == secret2 {
                                                 println!("✓ Secret derivation: Success (secrets match)");
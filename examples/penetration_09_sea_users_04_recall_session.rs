@@ -29,7 +29,7 @@ async fn main() {
             
             // Test: Recall session
             println!("\n--- Test: Recall session ---");
-            match recall(Some(chain.clone()), None).await {
+            match recall(Some(chain.clone()), None, None).await {
                 Ok(Some(recalled)) => {
                     println!("✓ Recall session: Success");
                     println!("  - Pub key: {}...", &recalled.pair.pub_key[..20.min(recalled.pair.pub_key.len())]);
@@ -29,7 +29,7 @@ async fn main() {
             // Note: Expiration is handled by the recall function checking expiry timestamps
             // This test documents current behavior
             println!("\n--- Test: Recall (may be expired) ---");
-            match recall(Some(chain.clone()), None).await {
+            match recall(Some(chain.clone()), None, None).await {
                 Ok(_) => {
                     println!("✓ Recall: Success (session may not be expired yet)");
                     success_count += 1;
@@ -6,9 +6,12 @@ use gun::webrtc::WebRTCOptions;
 /// - Initial connection through relay (for signaling)
 /// - WebRTC peer discovery and connection establishment
 /// - Direct P2P data exchange through WebRTC data channels
-/// - NAT traversal using STUN servers
+/// - NAT traversal using STUN servers, and a TURN server if one is given
 ///
 /// Run with: `cargo run --example two_clients_webrtc`
+///
+/// Behind symmetric NATs, add a TURN server:
+/// `GUN_TURN_URL=turn:turn.example.com:3478 GUN_TURN_USERNAME=user GUN_TURN_CREDENTIAL=secret cargo run --example two_clients_webrtc`
 use gun::{Gun, GunOptions};
 use chia_bls::SecretKey;
 use serde_json::json;
@@ -30,18 +33,20 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Change this URL to use a different relay server
     const RELAY_URL: &str = "http://dig-relay-prod.eba-2cmanxbe.us-east-1.elasticbeanstalk.com/gun";
 
-    // Create WebRTC options with STUN servers for NAT traversal
+    // Create WebRTC options with STUN servers for NAT traversal, plus the
+    // TURN server from GUN_TURN_URL if set
     let webrtc_options = WebRTCOptions {
         enabled: true,
         max_connections: 10,
         ..Default::default()
-    };
+    }
+    .with_turn_from_env();
 
     println!("WebRTC Configuration:");
     println!("  - Enabled: {}", webrtc_options.enabled);
     println!("  - Max connections: {}", webrtc_options.max_connections);
     println!(
-        "  - STUN servers: {} configured",
+        "  - STUN/TURN servers: {} configured",
         webrtc_options.ice_servers.len()
     );
     println!();
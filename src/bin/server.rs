@@ -4,6 +4,9 @@ use std::sync::Arc;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(feature = "log-subscriber")]
+    gun::init_logging();
+
     println!("Gun.rs server starting...");
 
     // Generate BLS key pair for the server
@@ -1,5 +1,7 @@
 use crate::core::GunCore;
 use crate::error::GunResult;
+use crate::persist::StorageWrite;
+use crate::sea::{parse_user_soul, user_soul, ParsedUserSoul, User};
 use crate::state::Node;
 use crate::valid::valid;
 use serde_json::Value;
@@ -16,6 +18,18 @@ pub struct Chain {
     pub parent: Option<Arc<Chain>>,
     pub id: u64,
     listener_ids: Arc<parking_lot::Mutex<HashSet<u64>>>, // Track listener IDs for off()
+    user: Option<Arc<User>>, // Signed-in user whose space this chain signs writes into (see Gun::user)
+}
+
+/// What a read learned about the value it delivers, besides the value
+///
+/// Passed to [`Chain::once_with_meta`] and [`Chain::on_with_meta`] callbacks.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReadMeta {
+    /// The value (every key, for a node) was read from user space (`~pub`
+    /// souls) and signed by its owner, or by a writer their certificate
+    /// allows. Unsigned values, and values outside user space, are not.
+    pub verified: bool,
 }
 
 impl Chain {
@@ -28,11 +42,13 @@ impl Chain {
             parent: None,
             id,
             listener_ids: Arc::new(parking_lot::Mutex::new(HashSet::new())),
+            user: None,
         }
     }
 
     pub fn with_soul(core: Arc<GunCore>, soul: String, parent: Option<Arc<Chain>>) -> Self {
         let id = core.next_chain_id();
+        let user = parent.as_ref().and_then(|p| p.user.clone());
         Self {
             core,
             soul: Some(soul),
@@ -40,11 +56,13 @@ impl Chain {
             parent,
             id,
             listener_ids: Arc::new(parking_lot::Mutex::new(HashSet::new())),
+            user,
         }
     }
 
     pub fn with_key(core: Arc<GunCore>, key: String, parent: Arc<Chain>) -> Self {
         let id = core.next_chain_id();
+        let user = parent.user.clone();
         Self {
             core,
             soul: None, // Don't inherit parent's soul - each get() creates a new chain without a soul
@@ -53,12 +71,24 @@ impl Chain {
             parent: Some(parent),
             id,
             listener_ids: Arc::new(parking_lot::Mutex::new(HashSet::new())),
+            user,
         }
     }
 
+    /// Sign what this chain, and chains from it, write into `user`'s space
+    pub(crate) fn with_user(mut self, user: Arc<User>) -> Self {
+        self.user = Some(user);
+        self
+    }
+
     /// Get a property or node by key
     /// Based on Gun.js chain.get()
+    ///
+    /// On the root chain (see `Gun::root`), `key` is a soul, as with `Gun::get`.
     pub fn get(&self, key: &str) -> Arc<Chain> {
+        if self.soul.is_none() && self.key.is_none() && self.parent.is_none() {
+            return Arc::new(Chain::with_soul(self.core.clone(), key.to_string(), Some(Arc::new(self.clone()))));
+        }
         Arc::new(Chain::with_key(
             self.core.clone(),
             key.to_string(),
@@ -74,38 +104,12 @@ impl Chain {
 
         // Check for content addressing (hash verification for #hash souls)
         if let Some(ref soul) = self.soul {
-            if soul.starts_with('#') {
-                // Content addressing: verify hash matches content
-                let hash_suffix = soul.strip_prefix('#').unwrap_or(soul); // Remove '#' prefix
-                let data_string = serde_json::to_string(&data)
-                    .map_err(crate::error::GunError::Serialization)?;
-                let hash_result = crate::sea::work(
-                    data_string.as_bytes(),
-                    None,
-                    crate::sea::WorkOptions {
-                        name: Some("SHA-256".to_string()),
-                        encode: Some("base64".to_string()),
-                        ..Default::default()
-                    },
-                )
-                .await
-                .map_err(|e| crate::error::GunError::Crypto(e.to_string()))?;
-
-                // Compare hash (support both base64 and hex)
-                use base64::Engine as _;
-                let hash_matches = hash_result == hash_suffix
-                    || hex::encode(
-                        base64::engine::general_purpose::STANDARD_NO_PAD
-                            .decode(&hash_result)
-                            .unwrap_or_default(),
-                    ) == hash_suffix;
-
-                if !hash_matches {
-                    return Err(crate::error::GunError::InvalidData(format!(
-                        "Content hash mismatch: expected {}, got {}",
-                        hash_suffix, hash_result
-                    )));
-                }
+            if !crate::valid::content_matches(soul, &data) {
+                return Err(crate::error::GunError::InvalidData(format!(
+                    "Content hash mismatch: expected {}, got {}",
+                    soul.strip_prefix('#').unwrap_or(soul),
+                    crate::valid::content_hash(&data)
+                )));
             }
         }
 
@@ -155,18 +159,20 @@ impl Chain {
                 
                 if let Some(parent_soul) = found_parent_soul {
                     // Store primitive value directly in parent node
-                    let mut parent_node = self.core.graph.get(&parent_soul)
+                    let mut parent_node = self.core.get_node(&parent_soul).await?
                         .unwrap_or_else(|| Node::with_soul(parent_soul.clone()));
                     let state = self.core.state.next();
+                    let data = self.seal(&parent_soul, key, data, state).await?;
                     parent_node.data.insert(key.clone(), data.clone());
                     crate::state::State::ify(&mut parent_node, Some(key), Some(state), Some(data.clone()), Some(&parent_soul));
                     self.core.graph.put(&parent_soul, parent_node.clone())?;
                     self.emit_update(&parent_soul, &parent_node.data);
                     
-                    // Store in persistent storage if available
-                    if let Some(storage) = &self.core.storage {
-                        storage.put(&parent_soul, &parent_node).await?;
-                    }
+                    // Store in persistent storage if available (only the changed key)
+                    self.core.persist(StorageWrite::Delta {
+                        soul: parent_soul.clone(),
+                        changed: vec![(key.clone(), data.clone(), state)],
+                    }).await?;
                     
                     return Ok(Arc::new(self.clone()));
                 }
@@ -181,16 +187,19 @@ impl Chain {
         // Create or update node
         let mut node = self
             .core
-            .graph
-            .get(&soul)
+            .get_node(&soul)
+            .await?
             .unwrap_or_else(|| Node::with_soul(soul.clone()));
 
         // Merge data into node
+        let mut changed = Vec::new();
         if let Some(key) = &self.key {
             // Setting a property
             let state = self.core.state.next();
+            let data = self.seal(&soul, key, data, state).await?;
             node.data.insert(key.clone(), data.clone());
-            crate::state::State::ify(&mut node, Some(key), Some(state), Some(data), Some(&soul));
+            crate::state::State::ify(&mut node, Some(key), Some(state), Some(data.clone()), Some(&soul));
+            changed.push((key.clone(), data, state));
         } else {
             // Setting the whole node - but data is not an object here, so this shouldn't happen
             // This case is handled above in put_object
@@ -203,9 +212,7 @@ impl Chain {
         self.emit_update(&soul, &node.data);
 
         // Store in persistent storage if available
-        if let Some(storage) = &self.core.storage {
-            storage.put(&soul, &node).await?;
-        }
+        self.core.persist(StorageWrite::Delta { soul: soul.clone(), changed }).await?;
 
         Ok(Arc::new(Chain::with_soul(
             self.core.clone(),
@@ -217,50 +224,20 @@ impl Chain {
     /// Helper to put an object (node) with proper traversal
     async fn put_object(&self, map: serde_json::Map<String, Value>) -> GunResult<Arc<Chain>> {
         // Parse soul and check for expiration (<? suffix)
+        // Check for expiration suffix: soul<?3600 (expires after 3600 seconds)
         let (soul, expiration_seconds) = match &self.soul {
-            Some(s) => {
-                // Check for expiration suffix: soul<?3600 (expires after 3600 seconds)
-                if let Some(exp_pos) = s.find("<?") {
-                    let soul_part = &s[..exp_pos];
-                    let exp_part = &s[exp_pos + 2..];
-                    if let Ok(exp_secs) = exp_part.parse::<f64>() {
-                        (soul_part.to_string(), Some(exp_secs))
-                    } else {
-                        (s.clone(), None)
-                    }
-                } else {
-                    (s.clone(), None)
-                }
-            }
-            None => (self.core.uuid(None), None),
+            Some(s) => Self::split_expiration(s),
+            None => (self.nested_soul().unwrap_or_else(|| self.core.uuid(None)), None),
         };
 
-        // Check expiration if present (for object puts, we check all keys)
-        if let Some(exp_secs) = expiration_seconds {
-            let now = chrono::Utc::now().timestamp_millis() as f64;
-            let expiration_time = now - (exp_secs * 1000.0);
-            if let Some(node) = self.core.graph.get(&soul) {
-                for key in map.keys() {
-                    if let Some(state) = crate::state::State::is(&Some(node.clone()), key) {
-                        if state < expiration_time {
-                            // Data is expired, reject
-                            return Err(crate::error::GunError::InvalidData(format!(
-                                "Data expired for key {}: state {} is older than expiration {}",
-                                key, state, expiration_time
-                            )));
-                        }
-                    }
-                }
-            }
-        }
-
         let mut node = self
             .core
-            .graph
-            .get(&soul)
+            .get_node(&soul)
+            .await?
             .unwrap_or_else(|| Node::with_soul(soul.clone()));
 
         // Process each key-value pair
+        let mut changed = Vec::with_capacity(map.len());
         for (k, v) in map {
             let state = self.core.state.next();
 
@@ -277,45 +254,67 @@ impl Chain {
                         self.core.graph.put(&ref_soul, placeholder)?;
                     }
                     // Store as soul reference
-                    node.data
-                        .insert(k.clone(), serde_json::json!({"#": ref_soul}));
+                    let link = self.seal(&soul, &k, serde_json::json!({"#": ref_soul}), state).await?;
+                    node.data.insert(k.clone(), link);
                 }
                 _ => {
                     // Regular value - but if it's an object, we should store it as-is
                     // Nested objects are stored directly in the node, not as separate nodes
                     // This allows get("level1") to work by extracting from the parent node
-                    node.data.insert(k.clone(), v.clone());
+                    let sealed = self.seal(&soul, &k, v, state).await?;
+                    node.data.insert(k.clone(), sealed);
                 }
             }
 
-            crate::state::State::ify(&mut node, Some(&k), Some(state), Some(v), Some(&soul));
+            let v = node.data.get(&k).cloned();
+            crate::state::State::ify(&mut node, Some(&k), Some(state), v, Some(&soul));
+            if let Some(value) = node.data.get(&k) {
+                changed.push((k.clone(), value.clone(), state));
+            }
+
+            // Record when this key expires so reads hide it and the sweep removes it
+            if let Some(exp_secs) = expiration_seconds {
+                let now = chrono::Utc::now().timestamp_millis() as f64;
+                node.set_expiry(&k, now + exp_secs * 1000.0);
+            }
         }
 
         self.core.graph.put(&soul, node.clone())?;
+        if expiration_seconds.is_some() {
+            self.core.track_expiry(&soul);
+        }
         self.emit_update(&soul, &node.data);
 
-        if let Some(storage) = &self.core.storage {
-            storage.put(&soul, &node).await?;
+        if expiration_seconds.is_some() {
+            // Expiry times live in node metadata, which deltas don't carry
+            self.core.persist(StorageWrite::Put { soul: soul.clone(), node }).await?;
+        } else {
+            self.core.persist(StorageWrite::Delta { soul: soul.clone(), changed }).await?;
         }
 
         // If we have a key, we need to store the soul reference in the parent node
         // This allows once() to find the data later via path resolution
         if let Some(key) = &self.key {
             if let Some(parent) = &self.parent {
-                eprintln!("DEBUG: put_object() storing soul reference: key={}, soul={}, parent_soul={:?}", key, soul, parent.soul);
+                tracing::trace!(key = %key, soul = %soul, parent_soul = ?parent.soul, "put_object() storing soul reference");
                 // Try to resolve or create parent node
                 if let Some(parent_soul) = &parent.soul {
                     // Parent has a soul, store reference there
                     // Create parent node if it doesn't exist
-                    let mut parent_node = self.core.graph.get(parent_soul)
+                    let mut parent_node = self.core.get_node(parent_soul).await?
                         .unwrap_or_else(|| Node::with_soul(parent_soul.clone()));
                     let state = self.core.state.next();
-                    let soul_ref = serde_json::json!({"#": soul});
-                    eprintln!("DEBUG: Storing soul reference in parent: parent_soul={}, key={}, soul_ref={}", parent_soul, key, serde_json::to_string(&soul_ref).unwrap_or_default());
+                    let soul_ref = self.seal(parent_soul, key, serde_json::json!({"#": soul}), state).await?;
+                    tracing::trace!(parent_soul = %parent_soul, key = %key, soul_ref = %soul_ref, "Storing soul reference in parent");
                     parent_node.data.insert(key.clone(), soul_ref.clone());
-                    crate::state::State::ify(&mut parent_node, Some(key), Some(state), Some(soul_ref), Some(parent_soul));
+                    crate::state::State::ify(&mut parent_node, Some(key), Some(state), Some(soul_ref.clone()), Some(parent_soul));
                     self.core.graph.put(parent_soul, parent_node.clone())?;
                     self.emit_update(parent_soul, &parent_node.data);
+
+                    self.core.persist(StorageWrite::Delta {
+                        soul: parent_soul.clone(),
+                        changed: vec![(key.clone(), soul_ref, state)],
+                    }).await?;
                 } else {
                     // Parent has no soul - create one for it
                     // Use the parent's key if available, otherwise use a deterministic approach
@@ -333,16 +332,21 @@ impl Chain {
                     };
                     
                     // Get or create parent node
-                    let mut parent_node = self.core.graph.get(&parent_soul)
+                    let mut parent_node = self.core.get_node(&parent_soul).await?
                         .unwrap_or_else(|| Node::with_soul(parent_soul.clone()));
                     
                     // Store the soul reference in the parent node
                     let state = self.core.state.next();
                     let soul_ref = serde_json::json!({"#": soul});
                     parent_node.data.insert(key.clone(), soul_ref.clone());
-                    crate::state::State::ify(&mut parent_node, Some(key), Some(state), Some(soul_ref), Some(&parent_soul));
+                    crate::state::State::ify(&mut parent_node, Some(key), Some(state), Some(soul_ref.clone()), Some(&parent_soul));
                     self.core.graph.put(&parent_soul, parent_node.clone())?;
                     self.emit_update(&parent_soul, &parent_node.data);
+
+                    self.core.persist(StorageWrite::Delta {
+                        soul: parent_soul.clone(),
+                        changed: vec![(key.clone(), soul_ref, state)],
+                    }).await?;
                 }
             }
         }
@@ -356,28 +360,175 @@ impl Chain {
 
     /// Emit update event for listeners (synchronous)
     fn emit_update(&self, soul: &str, data: &serde_json::Map<String, Value>) {
-        let event_type = format!("node_update:{}", soul);
-        let event = crate::events::Event {
-            event_type: event_type.clone(),
-            data: serde_json::Value::Object(data.clone()),
+        self.core.emit_update(soul, data);
+    }
+
+    /// `value` as written to `key` of `soul`: SEA-signed, as Gun.js writes
+    /// it, if `soul` is in the space of the user this chain writes as
+    ///
+    /// A link to the node nested under `key` (see `nested_soul`) is written
+    /// as it is, so paths still resolve through it. The user dropping their
+    /// own link from an alias signs the `null`. Both are what
+    /// [`verify_user_put`](crate::sea::verify_user_put) accepts.
+    async fn seal(&self, soul: &str, key: &str, value: Value, state: f64) -> GunResult<Value> {
+        let Some(auth) = self.user.as_ref().and_then(|user| user.auth()) else {
+            return Ok(value);
         };
-        self.core.events.emit(&event);
-        
-        // Also emit graph_update for listeners that don't have a specific soul yet
-        self.core.events.emit(&crate::events::Event {
-            event_type: "graph_update".to_string(),
-            data: serde_json::Value::Object(data.clone()),
-        });
-        
-        // Also emit network_sync event for Gun to handle
-        let network_event = crate::events::Event {
-            event_type: "network_sync".to_string(),
-            data: serde_json::json!({
-                "soul": soul,
-                "data": serde_json::Value::Object(data.clone())
-            }),
+        let in_space = matches!(
+            parse_user_soul(soul),
+            Some(ParsedUserSoul::User { pub_key, .. }) if pub_key == auth.pair.pub_key
+        );
+        let nested = value.get("#").and_then(|v| v.as_str()) == Some(&format!("{}/{}", soul, key));
+        let unlinks_alias =
+            soul.starts_with("~@") && value.is_null() && user_soul(&auth.pair.pub_key).ok().as_deref() == Some(key);
+        if !((in_space && !nested) || unlinks_alias) {
+            return Ok(value);
+        }
+        Ok(crate::sea::sign_put(soul, key, &value, state, &auth.pair, None).await?)
+    }
+
+    /// The soul of a node put at this chain's key of a user space node,
+    /// `~pub/key` as Gun.js nests them, so its link needn't be signed;
+    /// nodes elsewhere get random souls
+    fn nested_soul(&self) -> Option<String> {
+        let parent_soul = self.parent.as_ref()?.soul.as_ref()?;
+        let key = self.key.as_ref()?;
+        matches!(parse_user_soul(parent_soul), Some(ParsedUserSoul::User { .. }))
+            .then(|| format!("{}/{}", parent_soul, key))
+    }
+
+    /// The node a value read along `path` (see `read_through_path`) comes
+    /// from, and its key there; no key if the value is the node itself
+    fn home(core: &GunCore, path: Option<&(String, Vec<String>)>) -> Option<(String, Option<String>)> {
+        let (root_soul, keys) = path?;
+        let mut soul = root_soul.clone();
+        for (i, k) in keys.iter().enumerate() {
+            let node = core.graph.get(&soul)?;
+            match node.data.get(k).and_then(|v| v.get("#")).and_then(|v| v.as_str()) {
+                Some(next) => soul = next.to_string(),
+                None if i + 1 == keys.len() => return Some((soul, Some(k.clone()))),
+                None => return None,
+            }
+        }
+        Some((soul, None))
+    }
+
+    /// `value`, read along `path` (see `read_through_path`), with signed
+    /// user space data unwrapped and checked
+    ///
+    /// A node is opened key by key, and verified if every key is.
+    fn open(core: &GunCore, path: Option<&(String, Vec<String>)>, value: Value) -> (Value, ReadMeta) {
+        let Some((soul, key)) = Self::home(core, path).filter(|(soul, _)| soul.starts_with('~')) else {
+            return (value, ReadMeta::default());
         };
-        self.core.events.emit(&network_event);
+        let Some(node) = core.graph.get(&soul) else {
+            return (value, ReadMeta::default());
+        };
+        let states = node.meta.get(">");
+
+        let open = |key: &str, value: &Value| {
+            crate::sea::open_user_value_sync(&soul, key, value, states.and_then(|s| s.get(key)))
+        };
+        match (key, value) {
+            (Some(key), value) => {
+                let (value, verified) = open(&key, &value);
+                (value, ReadMeta { verified })
+            }
+            (None, Value::Object(fields)) => {
+                let mut verified = !fields.is_empty();
+                let fields = fields
+                    .into_iter()
+                    .map(|(k, v)| {
+                        let (v, ok) = open(&k, &v);
+                        verified &= ok;
+                        (k, v)
+                    })
+                    .collect();
+                (Value::Object(fields), ReadMeta { verified })
+            }
+            (None, value) => (value, ReadMeta::default()),
+        }
+    }
+
+    /// Split a `soul<?seconds` expiration suffix off a soul
+    ///
+    /// Returns the bare soul and the expiration in seconds, if the suffix is present
+    /// and numeric.
+    fn split_expiration(soul: &str) -> (String, Option<f64>) {
+        if let Some(exp_pos) = soul.find("<?") {
+            if let Ok(exp_secs) = soul[exp_pos + 2..].parse::<f64>() {
+                return (soul[..exp_pos].to_string(), Some(exp_secs));
+            }
+        }
+        (soul.to_string(), None)
+    }
+
+    /// Nearest soul and the keys leading from it down to this chain
+    ///
+    /// For `gun.get("a").get("b").get("c")` this is `("a", ["b", "c"])`.
+    /// Returns `None` if no chain in the ancestry has a soul.
+    fn read_through_path(&self) -> Option<(String, Vec<String>)> {
+        let mut keys = Vec::new();
+        let mut current: Option<&Chain> = Some(self);
+        while let Some(c) = current {
+            if let Some(soul) = &c.soul {
+                keys.reverse();
+                return Some((Self::split_expiration(soul).0, keys));
+            }
+            if let Some(key) = &c.key {
+                keys.push(key.clone());
+            }
+            current = c.parent.as_deref();
+        }
+        None
+    }
+
+    /// Load every node along a path from persistent storage into the graph
+    ///
+    /// Starts at `root_soul` and follows soul references for each key, reading
+    /// through to storage for any node missing from the in-memory graph. Stops
+    /// quietly where the path ends (missing node, or a key that isn't a reference).
+    async fn read_through(core: &GunCore, root_soul: String, keys: Vec<String>) -> GunResult<()> {
+        let mut soul = root_soul;
+        for key in keys {
+            let node = match core.get_node(&soul).await? {
+                Some(node) => node,
+                None => return Ok(()),
+            };
+            match node.data.get(&key).and_then(|v| v.get("#")).and_then(|v| v.as_str()) {
+                Some(next) => soul = next.to_string(),
+                None => return Ok(()),
+            }
+        }
+        core.get_node(&soul).await?;
+        Ok(())
+    }
+
+    /// Current value for `on()`'s initial callback, from the in-memory graph only
+    fn initial_value(
+        core: &GunCore,
+        resolved_soul: &str,
+        key: &Option<String>,
+        parent_soul: Option<&str>,
+    ) -> Option<Value> {
+        if let Some(node) = core.graph.get(resolved_soul) {
+            return match key {
+                Some(k) => node.data.get(k).cloned(),
+                None => Some(serde_json::to_value(&node.data).unwrap_or(Value::Null)),
+            };
+        }
+
+        // Check parent node for the key
+        let k = key.as_ref()?;
+        let parent_node = core.graph.get(parent_soul?)?;
+        let value = parent_node.data.get(k)?;
+        // Resolve soul references to the referenced node's data
+        if let Some(soul_str) = value.get("#").and_then(|v| v.as_str()) {
+            if let Some(ref_node) = core.graph.get(soul_str) {
+                return Some(serde_json::to_value(&ref_node.data).unwrap_or(Value::Null));
+            }
+        }
+        Some(value.clone())
     }
 
     /// Subscribe to updates on this node/property
@@ -427,6 +578,30 @@ impl Chain {
     /// # }
     /// ```
     pub fn on<F>(&self, callback: F) -> Arc<Chain>
+    where
+        F: Fn(Value, Option<String>) + Send + Sync + Clone + 'static,
+    {
+        self.on_with_meta(move |value, key, _meta| callback(value, key))
+    }
+
+    /// Subscribe to updates, with what is known about each value
+    ///
+    /// Like [`on`](Self::on); the callback also gets a [`ReadMeta`] telling
+    /// whether the value was signed by the owner of the user space it lives in.
+    pub fn on_with_meta<F>(&self, callback: F) -> Arc<Chain>
+    where
+        F: Fn(Value, Option<String>, ReadMeta) + Send + Sync + Clone + 'static,
+    {
+        let core = self.core.clone();
+        let path = self.read_through_path();
+        self.on_raw(move |value, key| {
+            let (value, meta) = Self::open(&core, path.as_ref(), value);
+            callback(value, key, meta)
+        })
+    }
+
+    /// `on()`, delivering values as stored
+    fn on_raw<F>(&self, callback: F) -> Arc<Chain>
     where
         F: Fn(Value, Option<String>) + Send + Sync + Clone + 'static,
     {
@@ -442,7 +617,7 @@ impl Chain {
         // Try to resolve soul from path if we don't have one (similar to once())
         // For on(), we always listen to parent node updates if we have a key but no soul
         let resolved_soul = if let Some(ref s) = &soul {
-            s.clone()
+            Self::split_expiration(s).0
         } else if let Some(ref k) = &key {
             // Try to resolve path by checking parent node
             if let Some(parent) = &self.parent {
@@ -484,64 +659,33 @@ impl Chain {
         
         // Call callback with current data if available (before setting up listener)
         if resolved_soul != "graph_update" {
-            if let Some(node) = self.core.graph.get(&resolved_soul) {
-                if let Some(ref k) = &key {
-                    if let Some(value) = node.data.get(k) {
-                        callback(value.clone(), Some(k.clone()));
-                        *prev_value.lock() = Some(value.clone());
+            let parent_soul = self.parent.as_ref().and_then(|p| p.read_through_path()).map(|(ps, _)| ps);
+            if let Some(value) = Self::initial_value(&core, &resolved_soul, &key, parent_soul.as_deref()) {
+                callback(value.clone(), key.clone());
+                *prev_value.lock() = Some(value);
+            } else if let (Some((root_soul, keys)), Ok(handle)) =
+                (self.read_through_path(), tokio::runtime::Handle::try_current())
+            {
+                // Not in memory yet - read through to storage in the background and
+                // deliver the stored value unless a live update got there first
+                let core = core.clone();
+                let key = key.clone();
+                let prev_value = prev_value.clone();
+                let callback = callback.clone();
+                let resolved_soul = resolved_soul.clone();
+                handle.spawn(async move {
+                    if let Err(e) = Self::read_through(&core, root_soul, keys).await {
+                        tracing::warn!(soul = %resolved_soul, error = %e, "Error reading from storage");
+                        return;
                     }
-                } else {
-                    let node_data = serde_json::to_value(&node.data).unwrap_or(Value::Null);
-                    callback(node_data.clone(), None);
-                    *prev_value.lock() = Some(node_data);
-                }
-            } else if let Some(ref k) = &key {
-                // Check parent node for the key
-                if let Some(parent) = &self.parent {
-                    // Try to resolve parent soul by walking up the chain
-                    let mut current_parent: Option<&Chain> = Some(parent.as_ref());
-                    let mut found_parent_soul: Option<String> = None;
-                    
-                    while let Some(p) = current_parent {
-                        if let Some(ps) = &p.soul {
-                            found_parent_soul = Some(ps.clone());
-                            break;
-                        }
-                        current_parent = p.parent.as_deref();
-                    }
-                    
-                    if let Some(parent_soul) = found_parent_soul {
-                        if let Some(parent_node) = self.core.graph.get(&parent_soul) {
-                            if let Some(value) = parent_node.data.get(k) {
-                                // Check if it's a soul reference or nested object
-                                if let Some(obj) = value.as_object() {
-                                    if let Some(soul_ref) = obj.get("#") {
-                                        if let Some(soul_str) = soul_ref.as_str() {
-                                            if let Some(ref_node) = self.core.graph.get(soul_str) {
-                                                let node_data = serde_json::to_value(&ref_node.data).unwrap_or(Value::Null);
-                                                callback(node_data.clone(), Some(k.clone()));
-                                                *prev_value.lock() = Some(node_data);
-                                            } else {
-                                                callback(value.clone(), Some(k.clone()));
-                                                *prev_value.lock() = Some(value.clone());
-                                            }
-                                        } else {
-                                            callback(value.clone(), Some(k.clone()));
-                                            *prev_value.lock() = Some(value.clone());
-                                        }
-                                    } else {
-                                        // Nested object
-                                        callback(value.clone(), Some(k.clone()));
-                                        *prev_value.lock() = Some(value.clone());
-                                    }
-                                } else {
-                                    callback(value.clone(), Some(k.clone()));
-                                    *prev_value.lock() = Some(value.clone());
-                                }
-                            }
+                    if let Some(value) = Self::initial_value(&core, &resolved_soul, &key, parent_soul.as_deref()) {
+                        let mut prev = prev_value.lock();
+                        if prev.is_none() {
+                            callback(value.clone(), key);
+                            *prev = Some(value);
                         }
                     }
-                }
+                });
             }
         }
         
@@ -631,6 +775,9 @@ impl Chain {
 
         let listener_id = self.core.events.on(&event_type, cb);
         listener_ids.lock().insert(listener_id);
+        if let Some(ref soul) = resolved_soul_for_cb {
+            core.subscribe(soul);
+        }
         
         // Also try removing from graph_update as fallback when off() is called
         // Store the event type we used (we'll try both in off())
@@ -681,10 +828,137 @@ impl Chain {
     where
         F: FnOnce(Value, Option<String>),
     {
+        self.once_with_meta(|value, key, _meta| callback(value, key)).await
+    }
+
+    /// Get data once, with what is known about it
+    ///
+    /// Like [`once`](Self::once); the callback also gets a [`ReadMeta`]
+    /// telling whether the value was signed by the owner of the user space
+    /// it lives in.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # async fn example(gun: gun::Gun, alice_pub: &str) -> Result<(), Box<dyn std::error::Error>> {
+    /// gun.get(&format!("~{}", alice_pub)).get("name").once_with_meta(|name, _key, meta| {
+    ///     if meta.verified {
+    ///         println!("Alice says her name is {}", name);
+    ///     }
+    /// }).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn once_with_meta<F>(&self, callback: F) -> GunResult<Arc<Chain>>
+    where
+        F: FnOnce(Value, Option<String>, ReadMeta),
+    {
+        self.once_raw(|value, key| {
+            let (value, meta) = Self::open(&self.core, self.read_through_path().as_ref(), value);
+            callback(value, key, meta)
+        })
+        .await
+    }
+
+    /// Write `value` encrypted for the holder of `their_epub`
+    /// Based on Gun.js `put(await SEA.encrypt(value, await SEA.secret(epub, pair)))`
+    ///
+    /// Encrypted with the secret shared between the signed-in user (see
+    /// `Gun::user`) and `their_epub`, in the Gun.js SEA format, and signed
+    /// like any other value written into the user's space. The recipient
+    /// reads it with [`once_decrypted`](Self::once_decrypted).
+    ///
+    /// # Errors
+    /// `GunError::Crypto` if nobody is signed in through this chain, and
+    /// `GunError::Sea` if `their_epub` isn't a valid key.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # async fn example(gun: gun::Gun, bob_epub: &str) -> Result<(), Box<dyn std::error::Error>> {
+    /// gun.user().auth("alice", "secure_password").await?;
+    /// gun.user().get("note_for_bob")?.put_encrypted_for(bob_epub, serde_json::json!("hi bob")).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn put_encrypted_for(&self, their_epub: &str, value: Value) -> GunResult<Arc<Chain>> {
+        let auth = self
+            .user
+            .as_ref()
+            .and_then(|user| user.auth())
+            .ok_or_else(|| crate::error::GunError::Crypto("User is not authenticated".to_string()))?;
+        let secret = crate::sea::secret(their_epub, &auth.pair).await?;
+        let encrypted = crate::sea::encrypt_with(&value, &secret).await?;
+        self.put(encrypted).await
+    }
+
+    /// Read a value encrypted for `pair`, decrypted
+    ///
+    /// For values [`put_encrypted_for`](Self::put_encrypted_for) wrote: the
+    /// writer is the owner of the user space the value is in, and their epub
+    /// is read from their `~pub` node. Values `pair` encrypted for itself
+    /// (`sea::encrypt` without an epub) are decrypted too. The callback gets
+    /// `Value::Null` if there is no value.
+    ///
+    /// # Errors
+    /// `GunError::Crypto` if the value can't be decrypted with `pair`.
+    pub async fn once_decrypted<F>(&self, pair: &crate::sea::KeyPair, callback: F) -> GunResult<Arc<Chain>>
+    where
+        F: FnOnce(Value, Option<String>),
+    {
+        let mut read = None;
+        self.once(|value, key| read = Some((value, key))).await?;
+        let (value, key) = read.unwrap_or((Value::Null, self.key.clone()));
+        if value.is_null() {
+            callback(value, key);
+            return Ok(Arc::new(self.clone()));
+        }
+
+        // Shared with the writer, else our own
+        let mut keys = Vec::new();
+        if let Some(writer_epub) = self.writer_epub().await? {
+            if let Ok(secret) = crate::sea::secret(&writer_epub, pair).await {
+                keys.push(secret);
+            }
+        }
+        keys.extend(pair.epriv_key.clone());
+        for k in &keys {
+            if let Ok(decrypted) = crate::sea::decrypt_with(&value, k).await {
+                callback(decrypted, key);
+                return Ok(Arc::new(self.clone()));
+            }
+        }
+        Err(crate::error::GunError::Crypto("Value can't be decrypted with this pair".to_string()))
+    }
+
+    /// Epub of the owner of the user space this chain's value is in
+    async fn writer_epub(&self) -> GunResult<Option<String>> {
+        let soul = match Self::home(&self.core, self.read_through_path().as_ref()) {
+            Some((soul, _)) => soul,
+            None => return Ok(None),
+        };
+        let Some(ParsedUserSoul::User { pub_key: owner, .. }) = parse_user_soul(&soul) else {
+            return Ok(None);
+        };
+        let mut epub = None;
+        let account = Chain::with_soul(self.core.clone(), user_soul(&owner)?, None);
+        Arc::new(account).get("epub").once(|value, _key| epub = value.as_str().map(str::to_string)).await?;
+        Ok(epub)
+    }
+
+    /// `once()`, delivering values as stored
+    async fn once_raw<F>(&self, callback: F) -> GunResult<Arc<Chain>>
+    where
+        F: FnOnce(Value, Option<String>),
+    {
+        // Load anything persisted along this path before looking at the graph,
+        // so data from a previous run is served from disk instead of the network
+        if let Some((root_soul, keys)) = self.read_through_path() {
+            Self::read_through(&self.core, root_soul, keys).await?;
+        }
+
         // Try to resolve soul from path if we don't have one
         let mut resolved_soul_opt: Option<String> = None;
         let soul = match &self.soul {
-            Some(s) => s.clone(),
+            Some(s) => Self::split_expiration(s).0,
             None => {
                 // Try to resolve path by checking if we can find data through the parent chain
                 // This handles cases like: gun.get("test").get("read_test").put(obj).once(...)
@@ -700,43 +974,43 @@ impl Chain {
                                             if let Some(soul_str) = soul_ref.as_str() {
                                                 // Found a soul reference, use it
                                                 let resolved_soul = soul_str.to_string();
-                                                eprintln!("DEBUG: once() resolved path to soul: {}", resolved_soul);
+                                                tracing::trace!(soul = %resolved_soul, "once() resolved path to soul");
                                                 // Continue with the resolved soul
                                                 if let Some(node) = self.core.graph.get(&resolved_soul) {
                                                     let node_data = serde_json::to_value(&node.data).unwrap_or(Value::Null);
-                                                    eprintln!("DEBUG: once() found node locally, calling callback with data");
+                                                    tracing::trace!("once() found node locally");
                                                     callback(node_data, self.key.clone());
                                                     return Ok(Arc::new(self.clone()));
                                                 } else {
-                                                    eprintln!("DEBUG: once() resolved soul {} but node not found locally, will request from network", resolved_soul);
+                                                    tracing::trace!(soul = %resolved_soul, "once() resolved soul not found locally, will request from network");
                                                     // Store resolved soul for network request
                                                     resolved_soul_opt = Some(resolved_soul);
                                                 }
                                             } else {
                                                 // Object but no "#" key - it's a nested object, return it directly
-                                                eprintln!("DEBUG: once() found nested object in parent node");
+                                                tracing::trace!("once() found nested object in parent node");
                                                 callback(value.clone(), self.key.clone());
                                                 return Ok(Arc::new(self.clone()));
                                             }
                                         } else {
                                             // Object but no "#" key - it's a nested object, return it directly
-                                            eprintln!("DEBUG: once() found nested object in parent node");
+                                            tracing::trace!("once() found nested object in parent node");
                                             callback(value.clone(), self.key.clone());
                                             return Ok(Arc::new(self.clone()));
                                         }
                                     } else {
                                         // Not a soul reference - could be a nested object or primitive value
                                         // Return the value directly
-                                        eprintln!("DEBUG: once() found value directly in parent node (not a soul reference): {:?}", if value.is_object() { "object" } else { "primitive" });
+                                        tracing::trace!(kind = if value.is_object() { "object" } else { "primitive" }, "once() found value directly in parent node");
                                         callback(value.clone(), self.key.clone());
                                         return Ok(Arc::new(self.clone()));
                                     }
                                 } else {
-                                    eprintln!("DEBUG: once() key {} not found in parent node {}, will request from network", key, parent_soul);
+                                    tracing::trace!(key = %key, parent_soul = %parent_soul, "once() key not found in parent node, will request from network");
                                     // Key not found in parent - might be nested deeper, need to wait for network
                                 }
                             } else {
-                                eprintln!("DEBUG: once() parent node {} not found, will request parent node from network first", parent_soul);
+                                tracing::trace!(parent_soul = %parent_soul, "once() parent node not found, will request it from network first");
                                 // Request parent node first
                                 let get_request = serde_json::json!({
                                     "get": {
@@ -751,7 +1025,7 @@ impl Chain {
                         } else {
                             // Parent has no soul - try to resolve it by walking up the chain
                             // or by looking for the parent node in the graph
-                            eprintln!("DEBUG: once() parent has no soul, trying to resolve parent node");
+                            tracing::trace!("once() parent has no soul, trying to resolve parent node");
                             
                             // Walk up the parent chain to find a node with a soul
                             let mut current_parent: Option<&Chain> = Some(parent.as_ref());
@@ -781,13 +1055,13 @@ impl Chain {
                                                         if let Some(ref_node) = self.core.graph.get(soul_str) {
                                                             // The key might be in the referenced node
                                                             if let Some(nested_value) = ref_node.data.get(key) {
-                                                                eprintln!("DEBUG: once() found key '{}' in referenced node {}", key, soul_str);
+                                                                tracing::trace!(key = %key, soul = %soul_str, "once() found key in referenced node");
                                                                 callback(nested_value.clone(), self.key.clone());
                                                                 return Ok(Arc::new(self.clone()));
                                                             } else {
                                                                 // Return the whole node data
                                                                 let node_data = serde_json::to_value(&ref_node.data).unwrap_or(Value::Null);
-                                                                eprintln!("DEBUG: once() found soul reference '{}' in resolved parent node {}, returning node data", key, parent_soul);
+                                                                tracing::trace!(key = %key, parent_soul = %parent_soul, "once() found soul reference in resolved parent node");
                                                                 callback(node_data, self.key.clone());
                                                                 return Ok(Arc::new(self.clone()));
                                                             }
@@ -795,13 +1069,13 @@ impl Chain {
                                                     }
                                                 } else {
                                                     // It's a nested object - return it directly
-                                                    eprintln!("DEBUG: once() extracting nested object '{}' from resolved parent node {}", key, parent_soul);
+                                                    tracing::trace!(key = %key, parent_soul = %parent_soul, "once() extracting nested object from resolved parent node");
                                                     callback(value.clone(), self.key.clone());
                                                     return Ok(Arc::new(self.clone()));
                                                 }
                                             } else {
                                                 // Primitive value - return it
-                                                eprintln!("DEBUG: once() extracting primitive '{}' from resolved parent node {}", key, parent_soul);
+                                                tracing::trace!(key = %key, parent_soul = %parent_soul, "once() extracting primitive from resolved parent node");
                                                 callback(value.clone(), self.key.clone());
                                                 return Ok(Arc::new(self.clone()));
                                             }
@@ -816,7 +1090,7 @@ impl Chain {
                                                         // Found a soul reference - check if the referenced node has our key
                                                         if let Some(ref_node) = self.core.graph.get(soul_str) {
                                                             if let Some(nested_value) = ref_node.data.get(key) {
-                                                                eprintln!("DEBUG: once() found key '{}' in node {} referenced by parent key '{}'", key, soul_str, parent_key);
+                                                                tracing::trace!(key = %key, soul = %soul_str, parent_key = %parent_key, "once() found key in node referenced by parent key");
                                                                 callback(nested_value.clone(), self.key.clone());
                                                                 return Ok(Arc::new(self.clone()));
                                                             }
@@ -845,7 +1119,7 @@ impl Chain {
                                                                         }
                                                                         
                                                                         if let Some(deep_value) = find_in_nested(nested_obj, key) {
-                                                                            eprintln!("DEBUG: once() found deeply nested key '{}' in nested object '{}' in node {}", key, ref_key, soul_str);
+                                                                            tracing::trace!(key = %key, nested = %ref_key, soul = %soul_str, "once() found deeply nested key");
                                                                             callback(deep_value.clone(), self.key.clone());
                                                                             return Ok(Arc::new(self.clone()));
                                                                         }
@@ -860,7 +1134,7 @@ impl Chain {
                                                     if let Some(nested_obj) = parent_value.as_object() {
                                                         if nested_obj.get(key).is_some() {
                                                             if let Some(deep_value) = nested_obj.get(key) {
-                                                                eprintln!("DEBUG: once() found key '{}' in nested object '{}' in parent node {}", key, parent_key, parent_soul);
+                                                                tracing::trace!(key = %key, nested = %parent_key, parent_soul = %parent_soul, "once() found key in nested object in parent node");
                                                                 callback(deep_value.clone(), self.key.clone());
                                                                 return Ok(Arc::new(self.clone()));
                                                             }
@@ -870,16 +1144,16 @@ impl Chain {
                                             }
                                         }
                                         
-                                        eprintln!("DEBUG: once() key '{}' not found in resolved parent node {} or its referenced nodes", key, parent_soul);
+                                        tracing::trace!(key = %key, parent_soul = %parent_soul, "once() key not found in resolved parent node or its referenced nodes");
                                     }
                                 } else {
-                                    eprintln!("DEBUG: once() resolved parent soul {} but node not found in graph", parent_soul);
+                                    tracing::trace!(parent_soul = %parent_soul, "once() resolved parent soul not found in graph");
                                 }
                             } else {
-                                eprintln!("DEBUG: once() could not resolve parent soul by walking up chain");
+                                tracing::trace!("once() could not resolve parent soul by walking up chain");
                             }
                             
-                            eprintln!("DEBUG: once() parent has no soul, will try to wait for network data");
+                            tracing::trace!("once() parent has no soul, will wait for network data");
                         }
                     }
                 }
@@ -1008,20 +1282,20 @@ impl Chain {
                                         if let Some(soul_str) = obj.get("#").and_then(|v| v.as_str()) {
                                             if let Some(ref_node) = self.core.graph.get(soul_str) {
                                                 let node_data = serde_json::to_value(&ref_node.data).unwrap_or(Value::Null);
-                                                eprintln!("DEBUG: once() found soul reference '{}' in parent node {}", key, parent_soul);
+                                                tracing::trace!(key = %key, parent_soul = %parent_soul, "once() found soul reference in parent node");
                                                 callback(node_data, self.key.clone());
                                                 return Ok(Arc::new(self.clone()));
                                             }
                                         }
                                     } else {
                                         // It's a nested object - return it directly
-                                        eprintln!("DEBUG: once() extracting nested object '{}' from parent node {}", key, parent_soul);
+                                        tracing::trace!(key = %key, parent_soul = %parent_soul, "once() extracting nested object from parent node");
                                         callback(value.clone(), self.key.clone());
                                         return Ok(Arc::new(self.clone()));
                                     }
                                 } else {
                                     // Primitive value - return it
-                                    eprintln!("DEBUG: once() extracting primitive '{}' from parent node {}", key, parent_soul);
+                                    tracing::trace!(key = %key, parent_soul = %parent_soul, "once() extracting primitive from parent node");
                                     callback(value.clone(), self.key.clone());
                                     return Ok(Arc::new(self.clone()));
                                 }
@@ -1046,25 +1320,25 @@ impl Chain {
             return Ok(Arc::new(self.clone()));
         }
 
-        // Data not found locally - request from network
-        // Set up a one-time listener for this soul
-        let event_type = format!("node_update:{}", soul);
-        
-        // Use shared data location since oneshot::Sender can't be cloned
-        let data_received: Arc<parking_lot::Mutex<Option<serde_json::Value>>> = Arc::new(parking_lot::Mutex::new(None));
-        let data_received_clone = data_received.clone();
-        let data_ready: Arc<parking_lot::Mutex<bool>> = Arc::new(parking_lot::Mutex::new(false));
-        let data_ready_clone = data_ready.clone();
-        
-        // Register a one-time listener (using Fn closure, not FnOnce)
-        let listener_id = self.core.events.on(&event_type, Box::new(move |event: &crate::events::Event| {
-            // Extract the data from the event
-            let data = event.data.clone();
-            *data_received_clone.lock() = Some(data);
-            *data_ready_clone.lock() = true;
+        // Data not found locally - ask the network. Nothing listening for get
+        // requests means there is no mesh to ask.
+        if self.core.events.listener_count("get_request") == 0 {
+            callback(Value::Null, self.key.clone());
+            return Ok(Arc::new(self.clone()));
+        }
+
+        // The mesh emits `get_reply:{req}` once a response carrying this
+        // request's `@` has been merged into the graph
+        let request_id = self.core.random_id(12);
+        let reply_event = format!("get_reply:{}", request_id);
+        let (reply_tx, reply_rx) = tokio::sync::oneshot::channel::<()>();
+        let reply_tx = parking_lot::Mutex::new(Some(reply_tx));
+        let listener_id = self.core.events.on(&reply_event, Box::new(move |_event: &crate::events::Event| {
+            if let Some(tx) = reply_tx.lock().take() {
+                let _ = tx.send(());
+            }
         }));
-        
-        // Emit a get request event that the mesh can listen to
+
         // Include key if we have one (for nested properties)
         let mut get_obj = serde_json::json!({
             "#": soul
@@ -1073,62 +1347,28 @@ impl Chain {
             get_obj["."] = serde_json::Value::String(key.clone());
         }
         let get_request = serde_json::json!({
-            "get": get_obj
+            "get": get_obj,
+            "req": request_id
         });
-        eprintln!("DEBUG: Emitting get_request for soul {} with key {:?}", soul, self.key);
+        tracing::trace!(soul = %soul, key = ?self.key, "Emitting get_request");
         self.core.events.emit(&crate::events::Event {
             event_type: "get_request".to_string(),
             data: get_request,
         });
-        
-        // Wait for response with timeout (20 seconds for network sync - increased for relay server delays)
+
+        // Wait for the answer (20 seconds for network sync - relays can be slow)
         let timeout_duration = tokio::time::Duration::from_secs(20);
-        let start = std::time::Instant::now();
-        
-        // Poll for data with timeout
-        loop {
-            if start.elapsed() > timeout_duration {
-                eprintln!("DEBUG: once() timeout waiting for data for soul {}", soul);
-                break; // Timeout
-            }
-            
-            let ready = *data_ready.lock();
-            if ready {
-                eprintln!("DEBUG: once() data received for soul {}", soul);
-                break; // Data received
-            }
-            
-            // Check periodically if data arrived in graph (in case event wasn't emitted)
-            if let Some(node) = self.core.graph.get(&soul) {
-                let value = if let Some(key) = &self.key {
-                    node.data.get(key).cloned().unwrap_or(Value::Null)
-                } else {
-                    serde_json::to_value(&node.data).unwrap_or(Value::Null)
-                };
-                if !value.is_null() {
-                    eprintln!("DEBUG: once() found data in graph for soul {}, calling callback", soul);
-                    *data_received.lock() = Some(value.clone());
-                    *data_ready.lock() = true;
-                    break;
-                }
-            }
-            
-            tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+        if tokio::time::timeout(timeout_duration, reply_rx).await.is_err() {
+            tracing::debug!(soul = %soul, "once() timed out waiting for data");
         }
-        
-        // Remove listener
-        self.core.events.off(&event_type, listener_id);
-        
-        // Process result
-        let received_data = data_received.lock().take();
-        let value = if let Some(data) = received_data {
-            if let Some(key) = &self.key {
-                data.get(key).cloned().unwrap_or(Value::Null)
-            } else {
-                data
-            }
-        } else {
-            Value::Null
+        self.core.events.off(&reply_event, listener_id);
+
+        let value = match self.core.graph.get(&soul) {
+            Some(node) => match &self.key {
+                Some(key) => node.data.get(key).cloned().unwrap_or(Value::Null),
+                None => serde_json::to_value(&node.data).unwrap_or(Value::Null),
+            },
+            None => Value::Null,
         };
         callback(value, self.key.clone());
 
@@ -1216,6 +1456,7 @@ impl Chain {
 
             let listener_id = self.core.events.on(&event_type, cb);
             listener_ids.lock().insert(listener_id);
+            self.core.subscribe(&Self::split_expiration(&resolved_soul).0);
 
             // Also call for current data if available
             if let Some(node) = self.core.graph.get(&resolved_soul) {
@@ -1278,9 +1519,7 @@ impl Chain {
                             );
                         }
                         self.core.graph.put(&new_soul, node.clone())?;
-                        if let Some(storage) = &self.core.storage {
-                            storage.put(&new_soul, &node).await?;
-                        }
+                        self.core.persist(StorageWrite::Put { soul: new_soul.clone(), node }).await?;
                     }
                     Some(new_soul)
                 } else {
@@ -1318,9 +1557,11 @@ impl Chain {
             self.core.graph.put(&set_soul, set_node.clone())?;
             self.emit_update(&set_soul, &set_node.data);
 
-            if let Some(storage) = &self.core.storage {
-                storage.put(&set_soul, &set_node).await?;
-            }
+            // Sets grow large; only write the new entry
+            self.core.persist(StorageWrite::Delta {
+                soul: set_soul.clone(),
+                changed: vec![(key, serde_json::json!({"#": ref_soul}), state)],
+            }).await?;
 
             Ok(Arc::new(Chain::with_soul(
                 self.core.clone(),
@@ -1396,6 +1637,9 @@ impl Chain {
         for id in ids.iter() {
             self.core.events.off(&event_type, *id);
         }
+        if resolved_soul != "graph_update" {
+            self.core.unsubscribe(&Self::split_expiration(&resolved_soul).0, ids.len());
+        }
         
         // Also try removing from graph_update as fallback (in case listener was registered with different type)
         if event_type != "graph_update" {
@@ -1418,6 +1662,7 @@ impl Clone for Chain {
             parent: self.parent.clone(),
             id: self.id,
             listener_ids: self.listener_ids.clone(),
+            user: self.user.clone(),
         }
     }
 }
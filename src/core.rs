@@ -1,9 +1,42 @@
 use crate::dup::Dup;
+use crate::error::GunResult;
 use crate::events::EventEmitter;
 use crate::graph::Graph;
-use crate::state::State;
-use crate::storage::Storage;
+use crate::persist::{PersistMessage, SharedStorage, StorageWrite};
+use crate::state::{Node, State};
+use crate::storage::{
+    MemoryStorage, Storage, DEFAULT_MEMORY_STORAGE_MAX_BYTES, DEFAULT_MEMORY_STORAGE_MAX_ENTRIES,
+};
+use futures::future::join_all;
+use parking_lot::RwLock;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::future::Future;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+
+/// Number of nodes read from storage concurrently while preloading
+const PRELOAD_BATCH_SIZE: usize = 100;
+
+/// Preload progress callback, called after each batch with `(processed, total)` souls
+pub type PreloadProgress = Arc<dyn Fn(usize, usize) + Send + Sync>;
+
+/// Which stored nodes to load into the graph on startup
+///
+/// See [`GunCore::preload`] and [`GunOptions::preload`](crate::GunOptions::preload).
+#[derive(Clone, Debug, PartialEq)]
+pub enum PreloadMode {
+    /// Start with an empty graph and read through to storage on demand
+    None,
+    /// Load only these souls
+    Souls(Vec<String>),
+    /// Load every stored node, up to `max_nodes` so huge stores don't exhaust memory
+    All { max_nodes: usize },
+}
 
 /// Core Gun instance structure
 ///
@@ -11,7 +44,7 @@ use std::sync::Arc;
 /// - **Graph**: In-memory storage of all nodes
 /// - **State**: Timestamp generation for conflict resolution
 /// - **Events**: Event system for reactive updates
-/// - **Storage**: Optional persistent storage backend
+/// - **Storage**: Storage backend (in-memory unless persistence is configured)
 /// - **Dedup**: Message deduplication for network operations
 ///
 /// Based on Gun.js `root.js` and `core.js`. This is an internal structure
@@ -30,28 +63,35 @@ pub struct GunCore {
     pub graph: Arc<Graph>,
     pub state: Arc<State>,
     pub events: Arc<EventEmitter>,
-    pub storage: Option<Arc<dyn Storage>>,
+    storage: SharedStorage, // Swappable, see set_storage()
+    persist_tx: RwLock<Option<mpsc::Sender<PersistMessage>>>, // Background writer, if started
     pub id_counter: Arc<std::sync::atomic::AtomicU64>,
     pub dup: Arc<tokio::sync::RwLock<Dup>>, // Message deduplication for DAM
+    expiring: RwLock<HashSet<String>>, // Souls with keys that have a `<?` expiry
+    subscriptions: RwLock<BTreeMap<String, usize>>, // Souls with live on() listeners, and how many
+    tasks: TaskTracker, // Background tasks of this instance (see spawn())
+    cancel: CancellationToken, // Ends tasks started with spawn_until_cancelled()
+    started: Instant,
 }
 
 impl GunCore {
     /// Create a new GunCore instance without persistent storage
     ///
-    /// This creates an in-memory only instance. Use [`with_storage`](Self::with_storage)
-    /// to enable persistent storage.
+    /// This creates an in-memory only instance, backed by a capped
+    /// [`MemoryStorage`]. Use [`with_storage`](Self::with_storage) to enable
+    /// persistent storage.
     ///
     /// # Returns
     /// A new `GunCore` instance with no persistent storage.
     pub fn new() -> Self {
-        Self {
-            graph: Arc::new(Graph::new()),
-            state: Arc::new(State::new()),
-            events: Arc::new(EventEmitter::new()),
-            storage: None,
-            id_counter: Arc::new(std::sync::atomic::AtomicU64::new(0)),
-            dup: Arc::new(tokio::sync::RwLock::new(Dup::new_default())),
-        }
+        Self::with_storage(Arc::new(Self::default_storage()))
+    }
+
+    /// The ephemeral backend used when no storage is configured
+    pub fn default_storage() -> MemoryStorage {
+        MemoryStorage::new()
+            .with_max_entries(DEFAULT_MEMORY_STORAGE_MAX_ENTRIES)
+            .with_max_bytes(DEFAULT_MEMORY_STORAGE_MAX_BYTES)
     }
 
     /// Create a new GunCore instance with persistent storage
@@ -84,12 +124,341 @@ impl GunCore {
             graph: Arc::new(Graph::new()),
             state: Arc::new(State::new()),
             events: Arc::new(EventEmitter::new()),
-            storage: Some(storage),
+            storage: Arc::new(RwLock::new(storage)),
+            persist_tx: RwLock::new(None),
             id_counter: Arc::new(std::sync::atomic::AtomicU64::new(0)),
             dup: Arc::new(tokio::sync::RwLock::new(Dup::new_default())),
+            expiring: RwLock::new(HashSet::new()),
+            subscriptions: RwLock::new(BTreeMap::new()),
+            tasks: TaskTracker::new(),
+            cancel: CancellationToken::new(),
+            started: Instant::now(),
         }
     }
 
+    /// Spawn a background task of this instance
+    ///
+    /// The task is counted in [`task_count`](Self::task_count) until it
+    /// finishes, and [`wait_for_tasks`](Self::wait_for_tasks) waits for it.
+    /// For tasks that end on their own: sends, timers, connections.
+    pub fn spawn<F>(&self, task: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.tasks.spawn(task)
+    }
+
+    /// Spawn a background task that is dropped once the instance is
+    /// [`cancel`](Self::cancel)led
+    ///
+    /// For loops and long waits: periodic sweeps, signaling, reply timeouts.
+    pub fn spawn_until_cancelled<F>(&self, task: F) -> JoinHandle<()>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let cancel = self.cancel.clone();
+        self.tasks.spawn(async move {
+            cancel.run_until_cancelled(task).await;
+        })
+    }
+
+    /// Drop every task started with [`spawn_until_cancelled`](Self::spawn_until_cancelled),
+    /// and those started after
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    /// Whether [`cancel`](Self::cancel) was called
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// Wait until [`cancel`](Self::cancel) is called
+    pub async fn cancelled(&self) {
+        self.cancel.cancelled().await;
+    }
+
+    /// Background tasks of this instance still running
+    pub fn task_count(&self) -> usize {
+        self.tasks.len()
+    }
+
+    /// Wait until every background task has finished
+    ///
+    /// Tasks spawned while waiting are waited for too.
+    pub async fn wait_for_tasks(&self) {
+        self.tasks.close();
+        self.tasks.wait().await;
+    }
+
+    /// Get the current storage backend
+    pub fn storage(&self) -> Arc<dyn Storage> {
+        self.storage.read().clone()
+    }
+
+    /// Replace the storage backend
+    ///
+    /// The swap is atomic: every storage operation sees either the old or the new
+    /// backend. Operations already in flight on the old backend complete there.
+    /// Use [`Gun::migrate_storage`](crate::Gun::migrate_storage) to copy data first.
+    ///
+    /// # Returns
+    /// The previous backend.
+    pub fn set_storage(&self, storage: Arc<dyn Storage>) -> Arc<dyn Storage> {
+        std::mem::replace(&mut *self.storage.write(), storage)
+    }
+
+    /// Move storage writes to a background task
+    ///
+    /// From now on [`persist`](Self::persist) queues writes instead of awaiting
+    /// them; see [`crate::persist`]. Must be called inside a Tokio runtime.
+    ///
+    /// # Returns
+    /// The task's handle. It finishes after this core is dropped and the queue
+    /// has drained.
+    pub fn start_background_persistence(&self) -> tokio::task::JoinHandle<()> {
+        let (tx, handle) = crate::persist::spawn(&self.tasks, self.storage.clone(), self.events.clone());
+        *self.persist_tx.write() = Some(tx);
+        handle
+    }
+
+    /// Stop the background writer started by
+    /// [`start_background_persistence`](Self::start_background_persistence)
+    ///
+    /// It writes out what is still queued, then ends; writes after this are
+    /// applied before [`persist`](Self::persist) returns.
+    pub fn stop_background_persistence(&self) {
+        self.persist_tx.write().take();
+    }
+
+    /// Persist a change to the storage backend
+    ///
+    /// With background persistence started the write is queued and this returns
+    /// immediately (waiting only if the queue is full); failures are retried and
+    /// then reported as a [`STORAGE_ERROR_EVENT`](crate::persist::STORAGE_ERROR_EVENT).
+    /// Otherwise the write is applied before returning.
+    ///
+    /// # Errors
+    /// Returns the storage error when writing synchronously.
+    pub async fn persist(&self, write: StorageWrite) -> GunResult<()> {
+        let tx = self.persist_tx.read().clone();
+        let write = match tx {
+            Some(tx) => match tx.send(PersistMessage::Write(write)).await {
+                Ok(()) => return Ok(()),
+                // Writer is gone; fall back to writing here
+                Err(mpsc::error::SendError(PersistMessage::Write(write))) => write,
+                Err(_) => return Ok(()),
+            },
+            None => write,
+        };
+        write.apply(self.storage().as_ref()).await
+    }
+
+    /// Wait until every write queued so far has been applied
+    ///
+    /// Returns immediately when writes aren't queued.
+    pub async fn flush(&self) {
+        let tx = self.persist_tx.read().clone();
+        if let Some(tx) = tx {
+            let (done_tx, done_rx) = oneshot::channel();
+            if tx.send(PersistMessage::Flush(done_tx)).await.is_ok() {
+                let _ = done_rx.await;
+            }
+        }
+    }
+
+    /// Get a node, reading through to persistent storage on a graph miss
+    ///
+    /// If the node isn't in the in-memory graph but the storage backend holds it,
+    /// the stored copy is merged into the graph (so any update that raced in from
+    /// the network still wins by state) and the merged node is returned.
+    ///
+    /// # Arguments
+    /// * `soul` - The unique identifier of the node
+    ///
+    /// # Returns
+    /// `Ok(Some(node))` if found in the graph or storage, `Ok(None)` if neither has it,
+    /// or `GunError` if the storage read fails.
+    pub async fn get_node(&self, soul: &str) -> GunResult<Option<Node>> {
+        if let Some(node) = self.graph.get(soul) {
+            return Ok(Some(node));
+        }
+
+        match self.storage().get(soul).await? {
+            Some(node) => Ok(Some(self.load_stored(soul, &node)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Merge a node read from storage into the graph, returning what reads should see
+    fn load_stored(&self, soul: &str, node: &Node) -> GunResult<Node> {
+        let merged = self.graph.merge(soul, node, || self.state.next())?;
+        if merged.has_expiry() {
+            self.track_expiry(soul);
+            return Ok(merged.without_expired(now_millis()));
+        }
+        Ok(merged)
+    }
+
+    /// Load nodes from persistent storage into the in-memory graph
+    ///
+    /// Warms the graph so the first read of each soul (including gets from
+    /// peers) is answered from memory. Stored nodes are merged, so anything
+    /// newer already in the graph wins by state.
+    ///
+    /// # Arguments
+    /// * `mode` - Which souls to load
+    /// * `progress` - Called after each batch with `(processed, total)` souls
+    ///
+    /// # Returns
+    /// The number of nodes loaded, or `GunError` if storage fails.
+    pub async fn preload(
+        &self,
+        mode: &PreloadMode,
+        progress: Option<&PreloadProgress>,
+    ) -> GunResult<usize> {
+        let storage = self.storage();
+        let souls = match mode {
+            PreloadMode::None => return Ok(0),
+            PreloadMode::Souls(souls) => souls.clone(),
+            PreloadMode::All { max_nodes } => {
+                let mut souls = storage.list().await?;
+                if souls.len() > *max_nodes {
+                    // Sorted so a capped preload picks the same souls every start
+                    souls.sort();
+                    souls.truncate(*max_nodes);
+                }
+                souls
+            }
+        };
+
+        let total = souls.len();
+        let mut processed = 0;
+        let mut loaded = 0;
+        for batch in souls.chunks(PRELOAD_BATCH_SIZE) {
+            let nodes = join_all(batch.iter().map(|soul| storage.get(soul))).await;
+            for (soul, node) in batch.iter().zip(nodes) {
+                if let Some(node) = node? {
+                    self.load_stored(soul, &node)?;
+                    loaded += 1;
+                }
+            }
+            processed += batch.len();
+            if let Some(progress) = progress {
+                progress(processed, total);
+            }
+        }
+
+        Ok(loaded)
+    }
+
+    /// Register a soul as having keys with an expiry, so the sweep visits it
+    pub(crate) fn track_expiry(&self, soul: &str) {
+        self.expiring.write().insert(soul.to_string());
+    }
+
+    /// Count an `on()` listener on a soul
+    pub(crate) fn subscribe(&self, soul: &str) {
+        *self.subscriptions.write().entry(soul.to_string()).or_default() += 1;
+    }
+
+    /// Forget `count` `on()` listeners on a soul
+    pub(crate) fn unsubscribe(&self, soul: &str, count: usize) {
+        let mut subscriptions = self.subscriptions.write();
+        if let Some(listeners) = subscriptions.get_mut(soul) {
+            *listeners = listeners.saturating_sub(count);
+            if *listeners == 0 {
+                subscriptions.remove(soul);
+            }
+        }
+    }
+
+    /// Souls with at least one live `on()` listener, in order
+    ///
+    /// The mesh asks each new or reconnected peer for these, so subscribers
+    /// catch up on changes made while they were away.
+    pub fn subscriptions(&self) -> Vec<String> {
+        self.subscriptions.read().keys().cloned().collect()
+    }
+
+    /// Live `on()` listeners, over all souls
+    pub fn subscription_count(&self) -> usize {
+        self.subscriptions.read().values().sum()
+    }
+
+    /// Time since this instance was created
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Tombstone every expired key and clean it out of storage
+    ///
+    /// Visits the nodes written through `soul<?seconds` chains (or loaded from
+    /// storage with an expiry), sets each expired key to `null` with a fresh state,
+    /// and emits the usual update events so subscribers and peers learn about the
+    /// removal. Nodes left with nothing but tombstones are deleted from storage;
+    /// others are re-persisted without the expired values.
+    ///
+    /// Called periodically by [`Gun`](crate::Gun) when an expiry sweep interval is set.
+    ///
+    /// # Returns
+    /// The number of keys tombstoned, or `GunError` if storage fails.
+    pub async fn sweep_expired(&self) -> GunResult<usize> {
+        let souls: Vec<String> = self.expiring.read().iter().cloned().collect();
+        let now = now_millis();
+        let mut swept = 0;
+
+        for soul in souls {
+            if let Some((node, keys)) = self.graph.expire(&soul, now, || self.state.next()) {
+                swept += keys.len();
+                self.emit_update(&soul, &node.data);
+
+                if node.data.values().all(Value::is_null) {
+                    self.persist(StorageWrite::Delete { soul: soul.clone() }).await?;
+                } else {
+                    self.persist(StorageWrite::Put { soul: soul.clone(), node }).await?;
+                }
+            }
+
+            let still_expiring = matches!(self.graph.get(&soul), Some(node) if node.has_expiry());
+            if !still_expiring {
+                self.expiring.write().remove(&soul);
+            }
+        }
+
+        Ok(swept)
+    }
+
+    /// Emit update events for a changed node
+    ///
+    /// Emits `node_update:{soul}` and `graph_update` for local listeners and
+    /// `network_sync` so the node is sent to connected peers.
+    pub(crate) fn emit_update(&self, soul: &str, data: &serde_json::Map<String, Value>) {
+        let event_type = format!("node_update:{}", soul);
+        let event = crate::events::Event {
+            event_type: event_type.clone(),
+            data: Value::Object(data.clone()),
+        };
+        self.events.emit(&event);
+
+        // Also emit graph_update for listeners that don't have a specific soul yet
+        self.events.emit(&crate::events::Event {
+            event_type: "graph_update".to_string(),
+            data: Value::Object(data.clone()),
+        });
+
+        // Also emit network_sync event for Gun to handle
+        let network_event = crate::events::Event {
+            event_type: "network_sync".to_string(),
+            data: serde_json::json!({
+                "soul": soul,
+                "data": Value::Object(data.clone())
+            }),
+        };
+        self.events.emit(&network_event);
+    }
+
     /// Generate a new soul (UUID) for a node
     ///
     /// Souls are unique identifiers for nodes in the Gun graph. They combine:
@@ -182,6 +551,11 @@ impl GunCore {
     }
 }
 
+/// Current time in milliseconds since the Unix epoch
+fn now_millis() -> f64 {
+    chrono::Utc::now().timestamp_millis() as f64
+}
+
 impl Default for GunCore {
     fn default() -> Self {
         Self::new()
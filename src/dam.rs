@@ -15,9 +15,22 @@
 //!
 //! All DAM messages include:
 //! - `#`: Message ID (SHA256 hash of message without signatures)
-//! - `sigs`: Array of BLS signatures and public keys
+//! - `sigs`: `{"sig": ..., "pubkeys": [...]}`, the BLS signatures of every peer
+//!   that passed the message on aggregated into one, and those peers' public keys
+//!   (older peers send an array of `{"sig", "pubkey"}` entries, which is still
+//!   accepted; see [`MeshOptions::legacy_sigs`])
 //! - Message payload (e.g., `put`, `get`, `dam`)
 //!
+//! With signatures not required (see [`Mesh::with_require_signatures`]), messages
+//! from stock Gun.js peers carry no `sigs` and a random `#`, used only for dedup.
+//!
+//! Replies (get responses and put acks) also carry `@`: the `#` of the message
+//! they answer, which [`Mesh::ask`] uses to route them to the waiting request.
+//!
+//! With [`PexOptions::enabled`], peers also exchange the addresses they have
+//! connected to in a `{"dam": "pex", "peers": [...]}` message sent on hi, and
+//! dial some of the ones they learn (see [`PEX_DIAL_EVENT`]).
+//!
 //! ## Peer Management
 //!
 //! The `Mesh` struct manages all peer connections:
@@ -27,15 +40,22 @@
 //! - Manages peer public keys for verification
 
 use crate::core::GunCore;
-use crate::dup::Dup;
+use crate::dup::{Dup, DupOptions, DupStats};
 use crate::error::GunResult;
-use crate::types::MessagePredicate;
-use chia_bls::{PublicKey, SecretKey, Signature, sign, verify};
+use crate::types::{MessagePredicate, PredicateContext, PredicateDecision, PublicKeyBytes};
+use crate::verify_cache::{VerifyCache, DEFAULT_VERIFY_CACHE_AGE};
+use crate::websocket::PeerStatus;
+use crate::wire::{self, WireEncoding};
+use chia_bls::{PublicKey, SecretKey, Signature, aggregate_verify, sign};
+use futures_util::future::BoxFuture;
+use parking_lot::Mutex;
+use serde::Serialize;
 use serde_json::Value;
 use sha2::{Sha256, Digest};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::sync::{mpsc, RwLock};
+use tokio::sync::{mpsc, oneshot, watch, RwLock};
 
 /// Represents a peer connection in the DAM mesh
 ///
@@ -51,20 +71,29 @@ use tokio::sync::{mpsc, RwLock};
 /// - `batch`, `tail`, `queue`: Message batching and queuing
 /// - `last`: Last message ID sent (for ordering)
 /// - `retry`, `tried`: Retry logic for connection attempts
+/// - `tags`: Rooms the peer is in, for [`Mesh::say_to_tag`]
+/// - `encoding`: How frames to and from the peer are encoded, see [`crate::wire`]
 #[derive(Clone, Debug)]
 pub struct Peer {
-    pub id: String,
+    pub id: PeerId,
     pub url: String,
     pub pid: Option<String>,                       // peer ID for DAM
-    pub tx: Option<mpsc::UnboundedSender<String>>, // WebSocket message sender
+    pub tx: Option<PeerSender>,                    // WebSocket message sender
     pub batch: Option<String>,                     // batched messages
     pub tail: usize,                               // batch size
     pub queue: Vec<String>,                        // queued messages
     pub last: Option<String>,                      // last message ID sent
     pub retry: i32,
     pub tried: Option<u64>, // timestamp
+    pub last_seen: Option<std::time::Instant>,     // last frame (or keepalive) from the peer
+    pub tags: HashSet<String>,                     // rooms the peer joined, or we put it in
+    pub rtt: Option<std::time::Duration>,          // round trip of the last answered ping
+    pub encoding: WireEncoding,                    // frame encoding agreed in the `?` handshake
 }
 
+/// ID of a peer in the mesh, `peer_{n}`, as [`Peer::id`] holds it
+pub type PeerId = String;
+
 impl Peer {
     pub fn new(url: String) -> Self {
         use std::sync::atomic::{AtomicU64, Ordering};
@@ -81,18 +110,25 @@ impl Peer {
             last: None,
             retry: 60,
             tried: None,
+            last_seen: None,
+            tags: HashSet::new(),
+            rtt: None,
+            encoding: WireEncoding::Json,
         }
     }
 
     /// Set the WebSocket message sender
-    pub fn set_sender(&mut self, tx: mpsc::UnboundedSender<String>) {
-        self.tx = Some(tx);
+    ///
+    /// A plain channel sender is a single lane; pass the sender from
+    /// [`Outbox::new`] to have control messages, acks and gets overtake puts.
+    pub fn set_sender(&mut self, tx: impl Into<PeerSender>) {
+        self.tx = Some(tx.into());
     }
 
     /// Send a message through the WebSocket connection
     pub async fn send(&self, message: &str) -> GunResult<()> {
         if let Some(ref tx) = self.tx {
-            tx.send(message.to_string()).map_err(|e| {
+            tx.send(message).map_err(|e| {
                 crate::error::GunError::Network(format!("Failed to send message: {}", e))
             })?;
         } else {
@@ -105,6 +141,717 @@ impl Peer {
     }
 }
 
+/// Frames waiting in each lane of a connection's [`Outbox`]
+#[derive(Debug, Default)]
+struct LaneDepths {
+    priority: AtomicUsize,
+    bulk: AtomicUsize,
+}
+
+/// A serialized message, shared by every peer connection it's sent on
+pub type Frame = Arc<str>;
+
+/// Another way to reach a peer, tried before its WebSocket connection
+///
+/// Called with each frame for the peer; an error makes the mesh fall back to
+/// the WebSocket sender for that frame. See [`Mesh::add_transport`].
+pub type PeerTransport = Arc<dyn Fn(Frame) -> BoxFuture<'static, GunResult<()>> + Send + Sync>;
+
+/// Bulk lane of a [`PeerSender`]
+#[derive(Clone, Debug)]
+enum Lane {
+    /// A plain channel sender; each frame is copied into its own `String`
+    Text(mpsc::UnboundedSender<String>),
+    /// An [`Outbox`] lane, which takes the shared frame as is
+    Shared(mpsc::UnboundedSender<Frame>),
+}
+
+impl Lane {
+    fn send(&self, frame: Frame) -> Result<(), mpsc::error::SendError<Frame>> {
+        match self {
+            Lane::Text(tx) => tx.send(frame.to_string()).map_err(|_| mpsc::error::SendError(frame)),
+            Lane::Shared(tx) => tx.send(frame),
+        }
+    }
+}
+
+/// Sending end of a peer connection
+///
+/// Made from a plain channel sender, everything goes out in order. Made with
+/// [`Outbox::new`], it has a second, priority lane for control messages
+/// (`dam` `?`, `!` and `bye`), acks and gets, which the connection's writer
+/// drains before the bulk lane, so a backlog of puts can't hold them up. An
+/// outbox also takes [`Frame`]s without copying them, so a broadcast is
+/// serialized once however many peers it goes to.
+#[derive(Clone, Debug)]
+pub struct PeerSender {
+    bulk: Lane,
+    priority: Option<(mpsc::UnboundedSender<Frame>, Arc<LaneDepths>)>,
+    msgpack: Option<Arc<AtomicBool>>,
+}
+
+impl PeerSender {
+    /// Queue a frame on the bulk lane
+    pub fn send(&self, frame: impl Into<Frame>) -> Result<(), mpsc::error::SendError<Frame>> {
+        self.bulk.send(frame.into())?;
+        if let Some((_, depths)) = &self.priority {
+            depths.bulk.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    /// Queue a frame on the priority lane (the bulk lane of a single-lane sender)
+    pub fn send_priority(&self, frame: impl Into<Frame>) -> Result<(), mpsc::error::SendError<Frame>> {
+        match &self.priority {
+            Some((priority, depths)) => {
+                priority.send(frame.into())?;
+                depths.priority.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+            None => self.bulk.send(frame.into()),
+        }
+    }
+
+    /// Have the writer encode frames as `encoding`
+    ///
+    /// Returns whether the connection now uses it: a single lane only
+    /// carries JSON text.
+    pub fn set_encoding(&self, encoding: WireEncoding) -> bool {
+        match &self.msgpack {
+            Some(msgpack) => {
+                msgpack.store(encoding == WireEncoding::MessagePack, Ordering::Relaxed);
+                true
+            }
+            None => encoding == WireEncoding::Json,
+        }
+    }
+
+    /// Frames not yet taken by the writer, as `(priority, bulk)`; `None` for a single lane
+    fn depths(&self) -> Option<(usize, usize)> {
+        self.priority.as_ref().map(|(_, depths)| {
+            (depths.priority.load(Ordering::Relaxed), depths.bulk.load(Ordering::Relaxed))
+        })
+    }
+}
+
+impl From<mpsc::UnboundedSender<String>> for PeerSender {
+    fn from(bulk: mpsc::UnboundedSender<String>) -> Self {
+        Self { bulk: Lane::Text(bulk), priority: None, msgpack: None }
+    }
+}
+
+/// Receiving end of a two-lane peer connection, read by its writer task
+pub struct Outbox {
+    priority: mpsc::UnboundedReceiver<Frame>,
+    bulk: mpsc::UnboundedReceiver<Frame>,
+    depths: Arc<LaneDepths>,
+    msgpack: Arc<AtomicBool>,
+}
+
+impl Outbox {
+    /// A connection's outbox and the sender to give the mesh for it
+    pub fn new() -> (PeerSender, Outbox) {
+        let (priority_tx, priority) = mpsc::unbounded_channel();
+        let (bulk_tx, bulk) = mpsc::unbounded_channel();
+        let depths = Arc::new(LaneDepths::default());
+        let msgpack = Arc::new(AtomicBool::new(false));
+        let sender = PeerSender {
+            bulk: Lane::Shared(bulk_tx),
+            priority: Some((priority_tx, depths.clone())),
+            msgpack: Some(msgpack.clone()),
+        };
+        (sender, Outbox { priority, bulk, depths, msgpack })
+    }
+
+    /// How the writer should encode frames, as set by [`PeerSender::set_encoding`]
+    pub fn encoding(&self) -> WireEncoding {
+        if self.msgpack.load(Ordering::Relaxed) {
+            WireEncoding::MessagePack
+        } else {
+            WireEncoding::Json
+        }
+    }
+
+    /// The next frame to write, priority lane first
+    ///
+    /// Returns `None` once every sender is gone and both lanes are empty.
+    pub async fn recv(&mut self) -> Option<Frame> {
+        tokio::select! {
+            biased;
+            Some(frame) = self.priority.recv() => {
+                self.depths.priority.fetch_sub(1, Ordering::Relaxed);
+                Some(frame)
+            }
+            frame = self.bulk.recv() => {
+                if frame.is_some() {
+                    self.depths.bulk.fetch_sub(1, Ordering::Relaxed);
+                    return frame;
+                }
+                // Bulk lane closed; whatever is left on the priority lane still goes
+                let frame = self.priority.recv().await;
+                if frame.is_some() {
+                    self.depths.priority.fetch_sub(1, Ordering::Relaxed);
+                }
+                frame
+            }
+        }
+    }
+}
+
+/// Frames waiting on a two-lane peer connection, see [`PeerSender`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct LaneStats {
+    /// Control messages, acks and gets
+    pub priority: usize,
+    /// Everything else
+    pub bulk: usize,
+}
+
+/// A message we sent, signed and serialized once for every peer it goes to
+struct Sealed {
+    /// The message's `#`
+    id: String,
+    frame: Frame,
+    priority: bool,
+}
+
+/// Whether a message goes on the priority lane: control messages, acks and gets
+fn is_priority(msg: &Value) -> bool {
+    match msg.get("dam").and_then(|v| v.as_str()) {
+        Some(dam) => matches!(dam, "?" | "!" | "bye" | "join" | "leave"),
+        None => msg.get("get").is_some() || (msg.get("@").is_some() && msg.get("put").is_none()),
+    }
+}
+
+/// Event emitted when a dropped peer connection has been re-established
+///
+/// The event data is `{"peer": id, "url": ..., "attempts": n}`.
+pub const PEER_RECONNECTED_EVENT: &str = "peer_reconnected";
+
+/// Event emitted when a peer is disconnected for sending too many bad messages
+///
+/// The event data is `{"peer": id, "url": ..., "score": n, "invalid_hash": n,
+/// "invalid_sig": n, "rejected": n, "oversized": n, "ban_ms": ms}`; `ban_ms` is
+/// `0` when the peer's host wasn't banned.
+pub const PEER_BANNED_EVENT: &str = "peer_banned";
+
+/// Event emitted when peer exchange picks an address to dial
+///
+/// The event data is `{"url": ..., "from": peer_id}`. [`Gun`](crate::Gun)
+/// listens for it and connects with a [`WebSocketClient`](crate::websocket::WebSocketClient).
+pub const PEX_DIAL_EVENT: &str = "pex_dial";
+
+/// Event emitted when a peer sends a `dam: "!"` error
+///
+/// The event data is `{"peer": id, "err": text}`, plus `"@"` with the ID of
+/// our message the error is about when the peer said which one.
+pub const PEER_ERROR_EVENT: &str = "peer_error";
+
+/// Event emitted when a connected peer is dropped for sending nothing for `lack` ms
+///
+/// The event data is `{"peer": id, "url": ..., "idle_ms": ms}`.
+pub const PEER_TIMEOUT_EVENT: &str = "peer_timeout";
+
+/// Event emitted when a peer tells us its DAM pid in the `?` handshake
+///
+/// The event data is `{"peer": id, "pid": pid}`. Reconnects say `?` again,
+/// so this fires for each of their connections.
+pub const PEER_HI_EVENT: &str = "peer_hi";
+
+/// Event emitted when a peer comes online or goes offline, see [`PeerLifecycleEvent`]
+///
+/// The event data is `{"peer": id, "url": ..., "pid": pid, "state": ...}`,
+/// the state being `"connected"` (the peer answered the `?` handshake),
+/// `"disconnected"` (it said `bye`, was removed or its connection dropped),
+/// `"timeout"` (it was dropped for sending nothing for `lack` ms) or
+/// `"reconnected"`, the last with `"attempts"`. `pid` is `null` when the
+/// peer never told us. Each peer goes offline once for each time it came
+/// online.
+pub const PEER_LIFECYCLE_EVENT: &str = "peer_lifecycle";
+
+/// What happened to a peer, see [`PeerLifecycleEvent`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PeerTransition {
+    /// The peer answered the `?` handshake
+    Connected,
+    /// The peer said `bye`, was removed or its connection dropped
+    Disconnected,
+    /// The peer was dropped for sending nothing for `lack` ms
+    TimedOut,
+    /// A dropped connection to the peer is back, after `attempts` tries
+    Reconnected { attempts: u32 },
+}
+
+/// A peer coming online or going offline, see `Gun::on_peer`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PeerLifecycleEvent {
+    pub peer: PeerId,
+    pub url: String,
+    pub pid: Option<String>,
+    pub transition: PeerTransition,
+}
+
+impl PeerLifecycleEvent {
+    /// Read the data of a [`PEER_LIFECYCLE_EVENT`]
+    pub fn from_event_data(data: &Value) -> Option<Self> {
+        let transition = match data.get("state")?.as_str()? {
+            "connected" => PeerTransition::Connected,
+            "disconnected" => PeerTransition::Disconnected,
+            "timeout" => PeerTransition::TimedOut,
+            "reconnected" => PeerTransition::Reconnected {
+                attempts: data.get("attempts").and_then(|v| v.as_u64()).unwrap_or_default() as u32,
+            },
+            _ => return None,
+        };
+        Some(Self {
+            peer: data.get("peer")?.as_str()?.to_string(),
+            url: data.get("url")?.as_str()?.to_string(),
+            pid: data.get("pid").and_then(|v| v.as_str()).map(str::to_string),
+            transition,
+        })
+    }
+}
+
+/// Event emitted when a message carries WebRTC signaling: `ok.rtc`, or
+/// `rtc` in a `dam: "rtc"` message
+///
+/// The event data is the message. [`WebRTCManager::start_signaling`](crate::webrtc::WebRTCManager::start_signaling)
+/// listens for it.
+pub const RTC_SIGNAL_EVENT: &str = "rtc_signal";
+
+/// Event emitted when a peer sends a content-addressed (`#hash`) node whose
+/// data doesn't hash to its soul
+///
+/// The event data is `{"peer": id, "soul": soul, "hash": hash, "@": msg_id}`,
+/// `hash` being [`content_hash`](crate::valid::content_hash) of what was
+/// sent. The message is dropped without being forwarded.
+pub const CONTENT_HASH_MISMATCH_EVENT: &str = "content_hash_mismatch";
+
+/// Kinds of bad messages counted against a peer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Misbehavior {
+    /// `#` doesn't match the hash of the message, or a content-addressed
+    /// node the hash in its soul
+    InvalidHash,
+    /// Missing, malformed or failing signatures
+    InvalidSignature,
+    /// Turned down by the message predicate
+    Rejected,
+    /// Larger than `max_message_size`
+    Oversized,
+}
+
+/// Bad messages received from one peer
+///
+/// Hash, signature and size failures weigh 2 points each; predicate rejections,
+/// which may be a policy mismatch rather than malice, weigh 1. A peer whose
+/// [`score`](Self::score) reaches `MeshOptions::max_score` is disconnected.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct PeerScore {
+    pub invalid_hash: u64,
+    pub invalid_sig: u64,
+    pub rejected: u64,
+    pub oversized: u64,
+}
+
+impl PeerScore {
+    /// Weighted sum of the counters
+    pub fn score(&self) -> u64 {
+        2 * self.invalid_hash + 2 * self.invalid_sig + self.rejected + 2 * self.oversized
+    }
+
+    fn record(&mut self, kind: Misbehavior) {
+        match kind {
+            Misbehavior::InvalidHash => self.invalid_hash += 1,
+            Misbehavior::InvalidSignature => self.invalid_sig += 1,
+            Misbehavior::Rejected => self.rejected += 1,
+            Misbehavior::Oversized => self.oversized += 1,
+        }
+    }
+}
+
+/// Mesh traffic counters at a point in time, returned by [`Mesh::stats`]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct MeshStats {
+    /// Messages received, counting each message of a batch
+    pub messages_heard: u64,
+    /// Messages sent with `say()` or `ask()`, including replies and acks
+    pub messages_said: u64,
+    /// Copies of received messages passed on to other peers
+    pub messages_forwarded: u64,
+    /// Bytes of the frames received
+    pub bytes_in: u64,
+    /// Bytes of the frames handed to peer connections
+    pub bytes_out: u64,
+    /// Messages dropped as already seen
+    pub duplicates: u64,
+    /// Frames that aren't JSON, and messages that aren't objects or lack a `#`
+    pub invalid_messages: u64,
+    /// Messages whose handling failed with an error, e.g. replying to a
+    /// closed connection; the rest of their batch is still handled
+    pub messages_failed: u64,
+    /// Messages dropped because `#` doesn't match their hash
+    pub hash_failures: u64,
+    /// Messages dropped for missing or invalid signatures
+    pub signature_failures: u64,
+    /// Signature checks answered from the verification cache
+    pub signature_cache_hits: u64,
+    /// `put` messages sent, including get responses
+    pub puts_sent: u64,
+    /// `put` messages received and merged into the graph without error
+    pub puts_applied: u64,
+    /// Gets answered with data
+    pub gets_answered: u64,
+    /// Messages dropped from full queues of disconnected peers
+    pub queue_dropped: u64,
+    /// Queued puts merged into others
+    pub queue_coalesced: u64,
+    /// Messages sent in chunks for being larger than `chunk_size`
+    pub chunked_sent: u64,
+    /// Chunked messages received whole
+    pub chunked_received: u64,
+    /// Chunked messages dropped for not arriving whole within `chunk_timeout`
+    pub chunked_expired: u64,
+    /// Messages dropped because their origin key isn't allowed to write
+    pub origin_rejected: u64,
+    /// Messages not forwarded because their `ttl` ran out
+    pub ttl_expired: u64,
+    /// Frames sent over a peer's alternate transport, e.g. a WebRTC data channel
+    pub transport_sent: u64,
+    /// Frames sent over WebSocket after the alternate transport failed
+    pub transport_fallbacks: u64,
+    /// Gets sent by `ask_get()`, counting a batch once
+    pub gets_sent: u64,
+    /// `ask_get()` calls that joined an identical get already on its way
+    pub gets_coalesced: u64,
+    /// `ask_get()` calls answered "not found" from the cache of missing souls
+    pub gets_known_missing: u64,
+    /// Messages waiting for a disconnected peer to come back, by peer ID
+    pub queued: HashMap<String, usize>,
+    /// Frames not yet written to each two-lane connection, by peer ID
+    pub lanes: HashMap<String, LaneStats>,
+    /// Round trip of the last answered keepalive ping, by peer ID
+    pub rtt: HashMap<String, std::time::Duration>,
+    /// Frame encoding of each connected peer, by peer ID
+    pub encodings: HashMap<String, WireEncoding>,
+    /// Peers past the `?` handshake and not gone since
+    pub peers: usize,
+    /// Size and counters of the message ID deduplication table
+    pub dedup: DupStats,
+}
+
+/// Counters behind [`MeshStats`]; updated without taking any lock
+#[derive(Default)]
+struct MeshMetrics {
+    messages_heard: AtomicU64,
+    messages_said: AtomicU64,
+    messages_forwarded: AtomicU64,
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+    duplicates: AtomicU64,
+    invalid_messages: AtomicU64,
+    messages_failed: AtomicU64,
+    hash_failures: AtomicU64,
+    signature_failures: AtomicU64,
+    signature_cache_hits: AtomicU64,
+    puts_sent: AtomicU64,
+    puts_applied: AtomicU64,
+    gets_answered: AtomicU64,
+    queue_dropped: AtomicU64,
+    queue_coalesced: AtomicU64,
+    chunked_sent: AtomicU64,
+    chunked_received: AtomicU64,
+    chunked_expired: AtomicU64,
+    origin_rejected: AtomicU64,
+    ttl_expired: AtomicU64,
+    transport_sent: AtomicU64,
+    transport_fallbacks: AtomicU64,
+    gets_sent: AtomicU64,
+    gets_coalesced: AtomicU64,
+    gets_known_missing: AtomicU64,
+}
+
+impl MeshMetrics {
+    fn add(counter: &AtomicU64, n: usize) {
+        counter.fetch_add(n as u64, Ordering::Relaxed);
+    }
+
+    fn snapshot(
+        &self,
+        queued: HashMap<String, usize>,
+        lanes: HashMap<String, LaneStats>,
+        rtt: HashMap<String, std::time::Duration>,
+        encodings: HashMap<String, WireEncoding>,
+        peers: usize,
+        dedup: DupStats,
+    ) -> MeshStats {
+        MeshStats {
+            messages_heard: self.messages_heard.load(Ordering::Relaxed),
+            messages_said: self.messages_said.load(Ordering::Relaxed),
+            messages_forwarded: self.messages_forwarded.load(Ordering::Relaxed),
+            bytes_in: self.bytes_in.load(Ordering::Relaxed),
+            bytes_out: self.bytes_out.load(Ordering::Relaxed),
+            duplicates: self.duplicates.load(Ordering::Relaxed),
+            invalid_messages: self.invalid_messages.load(Ordering::Relaxed),
+            messages_failed: self.messages_failed.load(Ordering::Relaxed),
+            hash_failures: self.hash_failures.load(Ordering::Relaxed),
+            signature_failures: self.signature_failures.load(Ordering::Relaxed),
+            signature_cache_hits: self.signature_cache_hits.load(Ordering::Relaxed),
+            puts_sent: self.puts_sent.load(Ordering::Relaxed),
+            puts_applied: self.puts_applied.load(Ordering::Relaxed),
+            gets_answered: self.gets_answered.load(Ordering::Relaxed),
+            queue_dropped: self.queue_dropped.load(Ordering::Relaxed),
+            queue_coalesced: self.queue_coalesced.load(Ordering::Relaxed),
+            chunked_sent: self.chunked_sent.load(Ordering::Relaxed),
+            chunked_received: self.chunked_received.load(Ordering::Relaxed),
+            chunked_expired: self.chunked_expired.load(Ordering::Relaxed),
+            origin_rejected: self.origin_rejected.load(Ordering::Relaxed),
+            ttl_expired: self.ttl_expired.load(Ordering::Relaxed),
+            transport_sent: self.transport_sent.load(Ordering::Relaxed),
+            transport_fallbacks: self.transport_fallbacks.load(Ordering::Relaxed),
+            gets_sent: self.gets_sent.load(Ordering::Relaxed),
+            gets_coalesced: self.gets_coalesced.load(Ordering::Relaxed),
+            gets_known_missing: self.gets_known_missing.load(Ordering::Relaxed),
+            queued,
+            lanes,
+            rtt,
+            encodings,
+            peers,
+            dedup,
+        }
+    }
+}
+
+/// A put's nodes by soul, as Gun.js sends them
+///
+/// Older gun.rs peers answer gets with a flat `{ put: { "#": soul, ...data } }`;
+/// that is nested under its soul.
+fn put_nodes(put: &Value) -> Value {
+    match put.get("#").and_then(|v| v.as_str()) {
+        Some(soul) => {
+            let mut data = put.as_object().cloned().unwrap_or_default();
+            data.remove("#");
+            serde_json::json!({ soul: data })
+        }
+        None => put.clone(),
+    }
+}
+
+/// Decode a message's `sigs` into one aggregate signature and its signers' keys
+///
+/// Reads both `{"sig": aggregate, "pubkeys": [...]}` and the older
+/// `[{"sig", "pubkey"}, ...]` array, whose signatures are aggregated here.
+fn read_sigs(sigs: &Value) -> Result<(Signature, Vec<PublicKey>), String> {
+    fn signature(value: Option<&Value>) -> Result<Signature, String> {
+        let bytes = hex::decode(value.and_then(|v| v.as_str()).ok_or("missing sig")?)
+            .map_err(|e| format!("invalid signature hex: {}", e))?;
+        let bytes: [u8; 96] = bytes
+            .try_into()
+            .map_err(|b: Vec<u8>| format!("invalid signature length: {} bytes, expected 96", b.len()))?;
+        Signature::from_bytes(&bytes).map_err(|e| format!("invalid signature: {:?}", e))
+    }
+    fn public_key(value: Option<&Value>) -> Result<PublicKey, String> {
+        let bytes = hex::decode(value.and_then(|v| v.as_str()).ok_or("missing pubkey")?)
+            .map_err(|e| format!("invalid public key hex: {}", e))?;
+        let bytes: [u8; 48] = bytes
+            .try_into()
+            .map_err(|b: Vec<u8>| format!("invalid public key length: {} bytes, expected 48", b.len()))?;
+        PublicKey::from_bytes(&bytes).map_err(|e| format!("invalid public key: {:?}", e))
+    }
+
+    let (aggregate, pubkeys) = match sigs {
+        Value::Array(entries) => {
+            let mut aggregate = Signature::default();
+            let mut pubkeys = Vec::with_capacity(entries.len());
+            for entry in entries {
+                aggregate += &signature(entry.get("sig"))?;
+                pubkeys.push(public_key(entry.get("pubkey"))?);
+            }
+            (aggregate, pubkeys)
+        }
+        Value::Object(_) => {
+            let pubkeys = sigs
+                .get("pubkeys")
+                .and_then(|v| v.as_array())
+                .ok_or("missing pubkeys")?
+                .iter()
+                .map(|pk| public_key(Some(pk)))
+                .collect::<Result<Vec<_>, _>>()?;
+            (signature(sigs.get("sig"))?, pubkeys)
+        }
+        _ => return Err("sigs is neither an array nor an object".to_string()),
+    };
+    if pubkeys.is_empty() {
+        return Err("no signatures".to_string());
+    }
+    Ok((aggregate, pubkeys))
+}
+
+/// The key a message's `from` names as its origin, if it is one
+fn from_key(from: &Value) -> Option<PublicKey> {
+    let bytes: [u8; 48] = hex::decode(from.as_str()?).ok()?.try_into().ok()?;
+    PublicKey::from_bytes(&bytes).ok()
+}
+
+/// Whether a DAM message is about the link it arrived on and must not be forwarded
+///
+/// Forwarding a `?` would make other peers take the sender's pid for ours,
+/// forwarding a bye would make them drop us instead of the sender,
+/// forwarding a pex would make the whole mesh dial the same addresses, and
+/// an error is only meant for the peer that sent what caused it.
+///
+/// Since they can't loop, these skip dedup: a reconnecting peer's `?` repeats
+/// its previous one word for word and must still be answered.
+fn stays_on_link(msg: &Value) -> bool {
+    matches!(
+        msg.get("dam").and_then(|v| v.as_str()),
+        Some("?") | Some("!") | Some("bye") | Some("pex") | Some("join") | Some("leave")
+    )
+}
+
+/// Whether `nonce` is `bits` of proof of work on the message `msg_id`: the
+/// SHA-256 of the ID followed by the nonce in decimal starts with `bits`
+/// zero bits
+fn pow_ok(msg_id: &str, nonce: u64, bits: u8) -> bool {
+    let hash = Sha256::new().chain_update(msg_id).chain_update(nonce.to_string()).finalize();
+    let mut zeros = 0;
+    for byte in hash {
+        zeros += byte.leading_zeros();
+        if byte != 0 {
+            break;
+        }
+    }
+    zeros >= u32::from(bits)
+}
+
+/// Most leading zero bits of proof of work a mesh can ask for: about 16
+/// million hashes per message on average, already seconds of work
+pub const MAX_POW_BITS: u8 = 24;
+
+/// The first nonce that is `bits` of proof of work on `msg_id`
+///
+/// Gives up after 256 times the expected number of tries, which only a bug
+/// or a difficulty past [`MAX_POW_BITS`] reaches.
+fn pow_nonce(msg_id: &str, bits: u8) -> Option<u64> {
+    let tries = 1u64.checked_shl(u32::from(bits) + 8).unwrap_or(u64::MAX);
+    (0..tries).find(|&nonce| pow_ok(msg_id, nonce, bits))
+}
+
+/// The single-soul gets a get message asks for
+///
+/// Accepts one get object, one with an array of souls in `"#"` (the other
+/// fields apply to each soul), or an array of get objects.
+fn expand_get(get: &Value) -> Vec<serde_json::Map<String, Value>> {
+    match get {
+        Value::Array(gets) => gets.iter().flat_map(expand_get).collect(),
+        Value::Object(obj) => match obj.get("#") {
+            Some(Value::Array(souls)) => souls
+                .iter()
+                .map(|soul| {
+                    let mut single = obj.clone();
+                    single.insert("#".to_string(), soul.clone());
+                    single
+                })
+                .collect(),
+            _ => vec![obj.clone()],
+        },
+        _ => Vec::new(),
+    }
+}
+
+/// Whether a key matches the `"."` lex of a get: an exact key, or an object of
+/// `"="` (exact), `"*"` (prefix), `">"` (at least) and `"<"` (at most) constraints
+fn lex_match(lex: &Value, key: &str) -> bool {
+    match lex {
+        Value::String(exact) => key == exact,
+        Value::Object(lex) => {
+            let text = |name: &str| lex.get(name).and_then(|v| v.as_str());
+            text("=").is_none_or(|exact| key == exact)
+                && text("*").is_none_or(|prefix| key.starts_with(prefix))
+                && text(">").is_none_or(|min| key >= min)
+                && text("<").is_none_or(|max| key <= max)
+        }
+        _ => false,
+    }
+}
+
+/// Whether peer exchange may share or dial `url`
+///
+/// Only `ws`, `wss`, `http` and `https` URLs with a host qualify. Multicast,
+/// broadcast and unspecified addresses never do; loopback, private, link-local
+/// and `localhost`/`.local` hosts only with `allow_private`.
+fn pex_dialable(url: &str, allow_private: bool) -> bool {
+    let Ok(parsed) = url::Url::parse(url) else {
+        return false;
+    };
+    if !matches!(parsed.scheme(), "ws" | "wss" | "http" | "https") {
+        return false;
+    }
+    let private = match parsed.host() {
+        Some(url::Host::Ipv4(ip)) => {
+            if ip.is_multicast() || ip.is_broadcast() || ip.is_unspecified() {
+                return false;
+            }
+            ip.is_loopback() || ip.is_private() || ip.is_link_local()
+        }
+        Some(url::Host::Ipv6(ip)) => {
+            if ip.is_multicast() || ip.is_unspecified() {
+                return false;
+            }
+            let first = ip.segments()[0];
+            ip.is_loopback()
+                || (first & 0xfe00) == 0xfc00 // unique local
+                || (first & 0xffc0) == 0xfe80 // link-local
+        }
+        Some(url::Host::Domain(domain)) => {
+            let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+            domain == "localhost" || domain.ends_with(".localhost") || domain.ends_with(".local")
+        }
+        None => return false,
+    };
+    allow_private || !private
+}
+
+/// Most addresses peer exchange remembers; later ones are ignored until some expire
+const MAX_PEX_ADDRESSES: usize = 1000;
+
+/// Most rooms a peer can join with `dam: "join"`; later ones are ignored
+const MAX_PEER_TAGS: usize = 256;
+
+/// A peer URL in the `ws`/`wss` form connections are registered under
+fn ws_url(url: &str) -> String {
+    url.replace("http://", "ws://").replace("https://", "wss://")
+}
+
+/// Merge a node in put format into `target`, key by key, keeping the value
+/// with the highest state (the incoming one on a tie)
+fn merge_wire_node(target: &mut Value, node: &Value) {
+    let Some(node) = node.as_object() else {
+        return;
+    };
+    let states = node.get("_").and_then(|meta| meta.get(">"));
+    for (key, value) in node {
+        if key == "_" {
+            continue;
+        }
+        let state = states.and_then(|s| s.get(key)).cloned().unwrap_or(Value::from(0));
+        let current = target["_"][">"].get(key).and_then(|v| v.as_f64());
+        if current.is_none_or(|current| state.as_f64().unwrap_or(0.0) >= current) {
+            target["_"][">"][key] = state;
+            target[key] = value.clone();
+        }
+    }
+}
+
+/// Host part of a peer URL; bans apply to the host, since incoming peers
+/// reconnect from a new port each time
+fn peer_host(url: &str) -> String {
+    url::Url::parse(url)
+        .ok()
+        .and_then(|parsed| parsed.host_str().map(str::to_string))
+        .unwrap_or_else(|| url.to_string())
+}
+
 /// DAM Mesh - handles message routing and peer communication
 ///
 /// The mesh is the central coordinator for all peer-to-peer communication in Gun.
@@ -143,18 +890,245 @@ pub struct Mesh {
     public_key: PublicKey,        // BLS public key (our own, for reference)
     peer_public_keys: Arc<RwLock<HashMap<String, PublicKey>>>, // Map peer_id -> public_key for verification
     message_predicate: Option<MessagePredicate>, // Optional predicate for custom message filtering
+    pending: Arc<Mutex<HashMap<String, PendingRequest>>>, // Requests from ask() awaiting a reply, by message ID
+    closing: watch::Sender<bool>, // Set by shutdown() to stop reconnects
+    scores: Arc<Mutex<HashMap<String, PeerScore>>>, // Bad messages per peer ID
+    banned: Arc<Mutex<HashMap<String, std::time::Instant>>>, // Banned hosts and when the ban ends
+    require_signatures: bool, // Reject unsigned messages (see with_require_signatures)
+    signed_peers: HashSet<String>, // Hosts that must sign even when require_signatures is off
+    get_batch: Arc<Mutex<Vec<QueuedGet>>>, // Gets from ask_get() waiting to be sent together
+    inflight: Arc<Mutex<HashMap<GetKey, Vec<oneshot::Sender<Value>>>>>, // Callers of ask_get() waiting on a get already asked for
+    missing: Arc<Mutex<HashMap<GetKey, std::time::Instant>>>, // Gets nobody answered, and until when to not ask again
+    addresses: Arc<Mutex<HashMap<String, Address>>>, // Peer exchange address book, by URL
+    dials: Arc<Mutex<HashMap<String, PeerStatus>>>, // How connecting to each dialed URL went
+    removed: Arc<Mutex<HashSet<String>>>, // URLs dropped with remove_peer, not to be dialed again
+    metrics: Arc<MeshMetrics>, // Traffic counters (see stats())
+    pids: Arc<Mutex<HashMap<String, String>>>, // DAM pid -> peer ID of its current connection
+    online: Arc<Mutex<HashSet<PeerId>>>, // Peers announced online and not offline since (see PEER_LIFECYCLE_EVENT)
+    chunks: Arc<Mutex<HashMap<(String, String), Assembly>>>, // Chunked messages being received, by (peer ID, chunk ID)
+    key_access: Arc<Mutex<KeyAccess>>, // Origin keys allowed or denied (see with_peer_allowlist)
+    rooms: Arc<Mutex<BTreeSet<String>>>, // Rooms we joined, announced to every peer (see join)
+    transports: Arc<Mutex<HashMap<String, AltTransport>>>, // Alternate transports, by DAM pid (see add_transport)
+    secure_user_space: bool, // Check writes to `~` souls (see with_secure_user_space)
+    pow_bits: Option<u8>, // Proof of work messages carry and must carry (see with_pow_bits)
+    verified: Arc<VerifyCache>, // BLS results by message hash, signature and signers (see with_verify_cache)
+}
+
+/// A peer's alternate transport, see [`Mesh::add_transport`]
+struct AltTransport {
+    send: PeerTransport,
+    peer_id: Option<String>, // The peer added for it, when the pid had no connection of its own
+}
+
+/// Which origin keys may write through this mesh
+#[derive(Clone, Debug, Default)]
+struct KeyAccess {
+    allow: Option<HashSet<PublicKeyBytes>>, // None: every key not denied
+    deny: HashSet<PublicKeyBytes>,
+}
+
+impl KeyAccess {
+    fn permits(&self, key: &PublicKeyBytes) -> bool {
+        !self.deny.contains(key) && self.allow.as_ref().is_none_or(|allow| allow.contains(key))
+    }
+}
+
+/// A message arriving in chunks, see [`MeshOptions::chunk_size`]
+struct Assembly {
+    of: u64,
+    parts: BTreeMap<u64, String>,
+    bytes: usize,
+    started: std::time::Instant,
+}
+
+/// A request sent with [`Mesh::ask`] that hasn't been answered yet
+struct PendingRequest {
+    tx: oneshot::Sender<Value>,
+    sent: std::time::Instant,
+    get: Option<Frame>, // the request as sent, for gets, to ask again after a reconnect
+}
+
+/// A get from [`Mesh::ask_get`] waiting for its batch to be sent
+///
+/// Gets for one soul (and key) keep their callers in [`Mesh::inflight`]
+/// instead, so later identical gets can join them; `tx` is `None` for those.
+struct QueuedGet {
+    get: Value,
+    tx: Option<oneshot::Sender<Value>>,
+}
+
+/// The soul and key (`.`) a get is for
+type GetKey = (String, Option<String>);
+
+/// The soul and key of a plain `{"#": soul}` or `{"#": soul, ".": key}` get
+///
+/// Gets with lex filters, byte limits or several souls have no key and are
+/// never coalesced or cached.
+fn get_key(get: &Value) -> Option<GetKey> {
+    let obj = get.as_object()?;
+    if obj.keys().any(|field| field != "#" && field != ".") {
+        return None;
+    }
+    let soul = obj.get("#")?.as_str()?.to_string();
+    match obj.get(".") {
+        None => Some((soul, None)),
+        Some(Value::String(key)) => Some((soul, Some(key.clone()))),
+        Some(_) => None,
+    }
+}
+
+/// What peer exchange knows about one peer URL
+#[derive(Clone, Debug, Default)]
+struct Address {
+    last_ok: Option<std::time::Instant>,   // last successful dial
+    last_dial: Option<std::time::Instant>, // last dial started by peer exchange
+    failures: u32,                         // failed dials since the last success
+    learned: Option<std::time::Instant>,   // last time a peer shared it
+}
+
+impl Address {
+    /// Latest time anything was heard about the address
+    fn last_seen(&self) -> Option<std::time::Instant> {
+        [self.last_ok, self.last_dial, self.learned].into_iter().flatten().max()
+    }
+}
+
+/// Options for the peer-exchange extension
+///
+/// With `enabled`, every new peer is sent a `{"dam": "pex", "peers": [...]}`
+/// message listing up to `max_shared` URLs we dialed successfully within the
+/// last `ttl` ms. Addresses received that way are checked and up to `max_dials`
+/// of them are dialed, as long as fewer than `max_peers` peers are connected.
+/// An address that failed `max_failures` dials in a row is neither shared nor
+/// dialed again until it expires, `ttl` ms after it was last seen.
+///
+/// Loopback, private and link-local addresses are only shared or dialed with
+/// `allow_private`; multicast, broadcast and unspecified addresses never are.
+#[derive(Clone, Debug)]
+pub struct PexOptions {
+    pub enabled: bool,        // off by default
+    pub max_peers: usize,     // stop dialing exchanged addresses at this many connected peers
+    pub max_shared: usize,    // addresses per pex message
+    pub max_dials: usize,     // addresses dialed per pex message received
+    pub max_failures: u32,    // failed dials in a row before an address is dropped
+    pub ttl: u64,             // ms an address is remembered after it was last seen
+    pub allow_private: bool,  // share and dial loopback/private/link-local addresses
+}
+
+impl Default for PexOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_peers: 8,
+            max_shared: 10,
+            max_dials: 3,
+            max_failures: 3,
+            ttl: 60 * 60 * 1000,
+            allow_private: false,
+        }
+    }
+}
+
+/// How a disconnected peer's full message queue makes room
+///
+/// Either way, queued puts are merged into one put on flush (see
+/// `MeshOptions::queue_max`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QueuePolicy {
+    /// Drop the oldest queued messages
+    #[default]
+    DropOldest,
+    /// Merge the queued puts first, keeping the newest state of each key, and
+    /// only drop the oldest messages if the queue is still full
+    CollapsePuts,
 }
 
 /// Configuration options for the DAM mesh
 ///
 /// These options control message batching, size limits, and retry behavior.
+///
+/// With `gap` above zero, messages are batched per peer like Gun.js: the first
+/// message goes out at once and opens a `gap`-millisecond window; messages sent
+/// to the same peer during the window are collected into one JSON array frame,
+/// sent when the window ends or earlier once it would exceed `pack_size` bytes.
+///
+/// Messages with a bad hash or signature, over `max_message_size`, or turned
+/// down by the message predicate count against the sending peer (see
+/// [`PeerScore`]). At `max_score` the peer is disconnected and, with `ban_time`
+/// set, its host is refused until the ban ends.
+///
+/// Messages for a disconnected peer are queued until it reconnects, at most
+/// `queue_max` of them; beyond that `queue_policy` decides what goes. When the
+/// queue is flushed, duplicates are dropped and the queued puts are merged into
+/// a single put holding the newest state of every key. Replies, and puts
+/// still awaiting an ack from [`ask`](Mesh::ask), are sent as they are.
+///
+/// Every peer a message passes through adds its BLS signature to the message's
+/// single aggregate signature, so a message grows by one 48-byte key per hop
+/// and is checked with one aggregate verification. With `legacy_sigs`, new
+/// signatures go into the per-signer array earlier versions expect instead,
+/// for meshes where not every peer has been upgraded yet.
+///
+/// Messages larger than `chunk_size` are split into `{"dam": "chunk", "id",
+/// "seq", "of", "data"}` pieces, each holding part of the message's JSON text,
+/// so they fit through transports with smaller frame limits. The receiver
+/// puts them back together and checks the whole message as usual; pieces
+/// still missing after `chunk_timeout` ms are given up on. A peer's partly
+/// received messages together may not exceed `max_message_size`.
+///
+/// Messages carry a `ttl`: how many more times they may be forwarded. We send
+/// ours with `ttl`, every forward decrements it and a message arriving at `0`
+/// is processed but not passed on. Like `#` and `sigs` it isn't hashed or
+/// signed, since relays change it. Messages without one (from older peers)
+/// get `ttl`, and larger values are lowered to it.
+///
+/// A connected peer we hear nothing from (not even a keepalive) for `lack`
+/// ms is dropped by [`Mesh::start_pruning`]. WebSocket connections send a ping
+/// every `ping_interval` ms, which keeps quiet but healthy peers (and idle
+/// connections through load balancers) alive, and measures the round trip.
+/// A connection whose last `max_missed_pongs` pings went unanswered is closed
+/// as dead, and clients reconnect. Keep `ping_interval` below `lack`.
+///
+/// Each attempt to connect to a peer (DNS lookup, TCP, TLS and the WebSocket
+/// upgrade together) is given up after `connect_timeout` ms, so unroutable
+/// addresses fail fast instead of waiting for the OS timeout.
+///
+/// Connections a [`WebSocketServer`](crate::websocket::WebSocketServer)
+/// accepts may not send a frame over `max_frame_size` bytes or a message over
+/// `max_message_size`: the frame header is enough to refuse one, before its
+/// payload is buffered, and the connection is closed with code 1009. A
+/// connection sending more than `max_bytes_per_sec` frame bytes within a
+/// second is closed with code 1008. Keep that above `max_frame_size`.
+///
+/// With `msgpack` set, our `?` handshake lists `enc: ["msgpack", "json"]`, and
+/// WebSocket connections to peers that list MessagePack too switch to binary
+/// MessagePack frames, about a third smaller (see [`crate::wire`]). Everyone
+/// else, Gun.js included, keeps getting JSON.
 #[derive(Clone, Debug)]
 pub struct MeshOptions {
     pub max_message_size: usize, // default 300MB * 0.3
-    pub pack_size: usize,        // batch size
-    pub gap: u64,                // batching delay in ms
-    pub retry: i32,
-    pub lack: u64, // lack timeout
+    pub pack_size: usize,        // max bytes of messages per batch
+    pub gap: u64,                // batching window in ms (0 = send every message immediately)
+    pub retry: i32,              // reconnect attempts after a connection drops
+    pub retry_wait: u64,         // first reconnect delay in ms, doubled per attempt
+    pub lack: u64, // ms to wait for acks; also how long a connected peer may stay silent
+    pub max_score: u64,          // misbehavior score that gets a peer disconnected (0 = never)
+    pub ban_time: u64,           // ms a disconnected peer's host is refused (0 = no ban)
+    pub get_gap: u64,            // ms ask_get() waits to send gets together (0 = send each at once)
+    pub queue_max: usize,        // messages queued per disconnected peer (0 = unbounded)
+    pub queue_policy: QueuePolicy, // what to do when a peer's queue is full
+    pub pex: PexOptions,         // peer exchange (off by default)
+    pub legacy_sigs: bool,       // sign with the per-signer `sigs` array older peers read
+    pub chunk_size: usize,       // larger messages are sent in chunks of this many bytes (0 = never)
+    pub chunk_timeout: u64,      // ms a chunked message has to arrive whole
+    pub ttl: u64,                // times a message may be forwarded
+    pub miss_ttl: u64,           // ms an unanswered get isn't asked again (0 = always ask)
+    pub ping_interval: u64,      // ms between WebSocket pings (0 = no pings)
+    pub connect_timeout: u64,    // ms a WebSocket connection attempt may take (0 = no limit)
+    pub max_missed_pongs: u32,   // unanswered pings in a row that close a connection (0 = never)
+    pub msgpack: bool,           // offer MessagePack frames in the `?` handshake
+    pub max_frame_size: usize,   // largest WebSocket frame a server connection reads (0 = no limit)
+    pub max_bytes_per_sec: u64,  // frame bytes a server connection may send per second (0 = no limit)
 }
 
 impl Default for MeshOptions {
@@ -164,7 +1138,25 @@ impl Default for MeshOptions {
             pack_size: ((300_000_000.0 * 0.3 * 0.01 * 0.01) as usize),
             gap: 0,
             retry: 60,
+            retry_wait: 1000,
             lack: 9000,
+            max_score: 10,
+            ban_time: 0,
+            get_gap: 5,
+            queue_max: 1000,
+            queue_policy: QueuePolicy::DropOldest,
+            pex: PexOptions::default(),
+            legacy_sigs: false,
+            chunk_size: 1_000_000,
+            chunk_timeout: 30_000,
+            ttl: 16,
+            miss_ttl: 5_000,
+            ping_interval: 4_500,
+            connect_timeout: 5_000,
+            max_missed_pongs: 3,
+            msgpack: false,
+            max_frame_size: 16 << 20,
+            max_bytes_per_sec: 0,
         }
     }
 }
@@ -183,7 +1175,191 @@ impl Mesh {
             public_key,
             peer_public_keys: Arc::new(RwLock::new(HashMap::new())),
             message_predicate,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            closing: watch::channel(false).0,
+            scores: Arc::new(Mutex::new(HashMap::new())),
+            banned: Arc::new(Mutex::new(HashMap::new())),
+            require_signatures: true,
+            signed_peers: HashSet::new(),
+            get_batch: Arc::new(Mutex::new(Vec::new())),
+            inflight: Arc::new(Mutex::new(HashMap::new())),
+            missing: Arc::new(Mutex::new(HashMap::new())),
+            addresses: Arc::new(Mutex::new(HashMap::new())),
+            dials: Arc::new(Mutex::new(HashMap::new())),
+            removed: Arc::new(Mutex::new(HashSet::new())),
+            metrics: Arc::new(MeshMetrics::default()),
+            pids: Arc::new(Mutex::new(HashMap::new())),
+            online: Arc::new(Mutex::new(HashSet::new())),
+            chunks: Arc::new(Mutex::new(HashMap::new())),
+            key_access: Arc::new(Mutex::new(KeyAccess::default())),
+            rooms: Arc::new(Mutex::new(BTreeSet::new())),
+            transports: Arc::new(Mutex::new(HashMap::new())),
+            secure_user_space: false,
+            pow_bits: None,
+            verified: Arc::new(VerifyCache::default()),
+        }
+    }
+
+    /// Use custom mesh options (batching, size limits, retries)
+    pub fn with_options(mut self, opt: MeshOptions) -> Self {
+        self.opt = opt;
+        self
+    }
+
+    /// The mesh options in use
+    pub fn options(&self) -> &MeshOptions {
+        &self.opt
+    }
+
+    /// The core this mesh syncs, which also runs its background tasks
+    pub(crate) fn core(&self) -> &Arc<GunCore> {
+        &self.core
+    }
+
+    /// Bound the table of seen message ids (size and expiry)
+    pub fn with_dup_options(mut self, options: DupOptions) -> Self {
+        self.dup = Arc::new(RwLock::new(Dup::with_options(options)));
+        self
+    }
+
+    /// Accept unsigned messages, for meshes with stock Gun.js peers (default: `true`)
+    ///
+    /// With `false`, messages without `sigs` skip hash and signature
+    /// checks: their `#` is only used for dedup. Anyone can then inject or forge
+    /// data through such a peer, so only turn this off for peers you trust or
+    /// data you validate yourself (e.g. SEA-signed nodes). Messages that do carry
+    /// signatures are still verified, and our own messages go out unsigned unless
+    /// some peers are listed in [`with_signed_peers`](Self::with_signed_peers).
+    pub fn with_require_signatures(mut self, require: bool) -> Self {
+        self.require_signatures = require;
+        self
+    }
+
+    /// Peers (by URL; matched on host) whose messages must be signed even when
+    /// signatures aren't otherwise required
+    ///
+    /// Lets a mixed mesh accept stock Gun.js peers while still holding other
+    /// gun.rs peers to BLS signatures. Unsigned messages are not forwarded to them.
+    pub fn with_signed_peers(mut self, urls: Vec<String>) -> Self {
+        self.signed_peers = urls.iter().map(|url| peer_host(url)).collect();
+        self
+    }
+
+    /// Only merge writes to user space (`~` souls) their owner signed
+    /// (default: `false`)
+    ///
+    /// Each key put into a `~pub` soul must be SEA-signed by pub, or by a
+    /// writer pub's certificate allows; `~@alias` souls may only link to
+    /// accounts. Other keys of the put are still merged, and the sender is
+    /// acked with an error naming the rejected ones. See
+    /// [`sea::verify_user_put`](crate::sea::verify_user_put).
+    pub fn with_secure_user_space(mut self, secure: bool) -> Self {
+        self.secure_user_space = secure;
+        self
+    }
+
+    /// Put `bits` of proof of work on every message, and require it on
+    /// incoming ones (default: `None`, no work)
+    ///
+    /// A message's `work` is a nonce such that the SHA-256 of its `#` and
+    /// the nonce in decimal starts with `bits` zero bits. Messages that
+    /// stay on one link (`?`, `!`, `bye`, `pex`, `join`, `leave`) need
+    /// none. A message without enough work is dropped, and the sender gets
+    /// a `dam: "!"` error with the difficulty in `pow`. Each extra bit
+    /// doubles the senders' work; checking costs one hash.
+    ///
+    /// # Errors
+    /// `GunError::Config` with `ConfigError::PowBitsTooHigh` if `bits` is
+    /// above [`MAX_POW_BITS`], where sending would take minutes per message.
+    pub fn with_pow_bits(mut self, bits: Option<u8>) -> GunResult<Self> {
+        if let Some(bits) = bits.filter(|&bits| bits > MAX_POW_BITS) {
+            return Err(crate::error::ConfigError::PowBitsTooHigh(bits).into());
+        }
+        self.pow_bits = bits;
+        Ok(self)
+    }
+
+    /// Keep the results of up to `max_entries` signature checks (default:
+    /// [`DEFAULT_VERIFY_CACHE_ENTRIES`](crate::verify_cache::DEFAULT_VERIFY_CACHE_ENTRIES);
+    /// 0 turns the cache off)
+    ///
+    /// Copies of a message arriving from several peers are verified before
+    /// deduplication drops them; with the cache, the pairing check runs once
+    /// per message, signature and set of signers. Results are kept for
+    /// [`DEFAULT_VERIFY_CACHE_AGE`] at most.
+    pub fn with_verify_cache(mut self, max_entries: usize) -> Self {
+        self.verified = Arc::new(VerifyCache::new(max_entries, DEFAULT_VERIFY_CACHE_AGE));
+        self
+    }
+
+    /// Only accept messages whose origin is one of `keys`
+    ///
+    /// The origin is the signer a message names in its `from`, which is
+    /// hashed and signed with the rest; signed messages without one have no
+    /// origin either.
+    /// `None` accepts every origin that isn't denied. Messages without an
+    /// origin are dropped while an allowlist is set. DAM control messages
+    /// (`?`, `!`, `bye`, `pex`) come from the neighbour itself and always pass,
+    /// so relays outside the list can still carry allowed origins' messages.
+    pub fn with_peer_allowlist(self, keys: Option<HashSet<PublicKeyBytes>>) -> Self {
+        self.key_access.lock().allow = keys;
+        self
+    }
+
+    /// Drop messages signed by any of `keys`, as origin or as a relay
+    pub fn with_peer_denylist(self, keys: HashSet<PublicKeyBytes>) -> Self {
+        self.key_access.lock().deny = keys;
+        self
+    }
+
+    /// Let `key` write again: lifts a denial and adds it to the allowlist, if one is set
+    pub fn allow_key(&self, key: PublicKeyBytes) {
+        let mut access = self.key_access.lock();
+        access.deny.remove(&key);
+        if let Some(allow) = access.allow.as_mut() {
+            allow.insert(key);
+        }
+        tracing::info!(key = %hex::encode(key), "Origin key allowed");
+    }
+
+    /// Stop accepting messages from `key` as origin, effective immediately
+    pub fn deny_key(&self, key: PublicKeyBytes) {
+        let mut access = self.key_access.lock();
+        if let Some(allow) = access.allow.as_mut() {
+            allow.remove(&key);
+        }
+        access.deny.insert(key);
+        tracing::info!(key = %hex::encode(key), "Origin key denied");
+    }
+
+    /// Whether a message from `origin`, signed by `signers`, may be accepted
+    ///
+    /// Rejections are counted but not scored: the sending peer may only be
+    /// relaying the message.
+    fn origin_permitted(&self, origin: Option<&PublicKey>, signers: &[PublicKey], msg_id: &str, peer: Option<&Peer>) -> bool {
+        let access = self.key_access.lock();
+        let denied = signers.iter().any(|key| access.deny.contains(&key.to_bytes()));
+        let permitted = !denied
+            && match origin {
+                Some(key) => access.permits(&key.to_bytes()),
+                None => access.allow.is_none(),
+            };
+        if !permitted {
+            MeshMetrics::add(&self.metrics.origin_rejected, 1);
+            tracing::debug!(
+                peer_id = ?peer.map(|p| &p.id),
+                msg_id = %msg_id,
+                origin = ?origin.map(|key| hex::encode(key.to_bytes())),
+                "Origin key not allowed"
+            );
         }
+        permitted
+    }
+
+    /// Whether messages from `peer` must carry valid signatures
+    fn requires_signatures(&self, peer: Option<&Peer>) -> bool {
+        self.require_signatures
+            || peer.is_some_and(|p| self.signed_peers.contains(&peer_host(&p.url)))
     }
 
     /// Handle incoming message (matches mesh.hear)
@@ -192,8 +1368,19 @@ impl Mesh {
             return Ok(());
         }
 
+        MeshMetrics::add(&self.metrics.bytes_in, raw.len());
+        // The caller's Peer is a copy from when the connection opened; the mesh's
+        // own copy carries the pid learned since in the `?` exchange
+        let known = match peer {
+            Some(p) => self.get_peer(&p.id).await,
+            None => None,
+        };
+        let peer = known.as_ref().or(peer);
+        if let Some(p) = known.as_ref() {
+            self.touch(&p.id).await;
+        }
         let peer_id = peer.map(|p| p.id.clone()).unwrap_or_else(|| "unknown".to_string());
-        eprintln!("DEBUG: mesh.hear() received message from peer {}: {}", peer_id, raw.chars().take(200).collect::<String>());
+        tracing::trace!(peer_id = %peer_id, bytes = raw.len(), raw = %raw.chars().take(200).collect::<String>(), "Received message");
 
         // Check message size
         if raw.len() > self.opt.max_message_size {
@@ -207,39 +1394,106 @@ impl Mesh {
                 )
                 .await?;
             }
-            return Ok(());
+            return self.misbehaved(peer, Misbehavior::Oversized).await;
         }
 
+        let msg: Value = match serde_json::from_str(raw) {
+            Ok(msg) => msg,
+            Err(e) => return self.invalid(peer, &format!("Invalid JSON: {}", e)).await,
+        };
+
         // Handle batched messages (JSON array)
-        if raw.starts_with('[') {
-            let messages: Vec<Value> = serde_json::from_str(raw)?;
-            eprintln!("DEBUG: Processing {} batched messages from peer {}", messages.len(), peer_id);
+        if let Value::Array(messages) = msg {
+            tracing::trace!(peer_id = %peer_id, count = messages.len(), "Processing batched messages");
             for msg in messages {
-                self.hear_one(&msg, peer).await?;
+                // One failing message doesn't drop the rest of the batch
+                if let Err(e) = self.hear_one(&msg, peer).await {
+                    MeshMetrics::add(&self.metrics.messages_failed, 1);
+                    tracing::warn!(peer_id = %peer_id, error = %e, "Error handling batched message");
+                }
             }
             return Ok(());
         }
 
         // Handle single message
-        let msg: Value = serde_json::from_str(raw)?;
-        self.hear_one(&msg, peer).await?;
+        if let Err(e) = self.hear_one(&msg, peer).await {
+            MeshMetrics::add(&self.metrics.messages_failed, 1);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Drop a frame or message we can't make sense of
+    ///
+    /// Counts it in `invalid_messages` and tells the peer with a `dam: "!"`.
+    pub(crate) async fn invalid(&self, peer: Option<&Peer>, err: &str) -> GunResult<()> {
+        MeshMetrics::add(&self.metrics.invalid_messages, 1);
+        tracing::debug!(peer_id = ?peer.map(|p| &p.id), error = %err, "Invalid message");
+        if let Some(p) = peer {
+            self.say(&serde_json::json!({ "dam": "!", "err": err }), Some(p)).await?;
+        }
         Ok(())
     }
 
     /// Handle a single message (matches mesh.hear.one)
+    #[tracing::instrument(
+        level = "trace",
+        skip_all,
+        fields(peer_id = peer.map(|p| p.id.as_str()), msg_id = msg.get("#").and_then(|v| v.as_str()))
+    )]
     async fn hear_one(&self, msg: &Value, peer: Option<&Peer>) -> GunResult<()> {
+        // Pieces of a chunked message wait for the rest; the whole message then
+        // goes through the usual checks
+        let whole;
+        let msg = if msg.get("dam").and_then(|v| v.as_str()) == Some("chunk") {
+            match self.hear_chunk(msg, peer).await? {
+                Some(assembled) => {
+                    whole = assembled;
+                    &whole
+                }
+                None => return Ok(()),
+            }
+        } else {
+            msg
+        };
+
+        MeshMetrics::add(&self.metrics.messages_heard, 1);
+        if !msg.is_object() {
+            return self.invalid(peer, "Message is not an object").await;
+        }
         // Get message ID (should be SHA256 hash of message without sigs)
-        let msg_id = msg
-            .get("#")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string())
-            .ok_or_else(|| {
-                crate::error::GunError::Network("Message missing ID (#) field".to_string())
-            })?;
+        let Some(msg_id) = msg.get("#").and_then(|v| v.as_str()).map(str::to_string) else {
+            return self.invalid(peer, "Message missing ID (#) field").await;
+        };
+
+        // Proof of work is checked first, as it's the cheapest check
+        if let Some(bits) = self.pow_bits.filter(|_| !stays_on_link(msg)) {
+            let worked = msg.get("work").and_then(|v| v.as_u64()).is_some_and(|nonce| pow_ok(&msg_id, nonce, bits));
+            if !worked {
+                MeshMetrics::add(&self.metrics.invalid_messages, 1);
+                tracing::debug!(peer_id = ?peer.map(|p| &p.id), msg_id = %msg_id, bits, "Message without enough proof of work");
+                if let Some(p) = peer {
+                    let err = format!("Proof of work required: {} bits", bits);
+                    self.say(&serde_json::json!({ "dam": "!", "err": err, "pow": bits, "@": msg_id }), Some(p)).await?;
+                }
+                return Ok(());
+            }
+        }
+
+        if msg.get("sigs").is_none() && !self.requires_signatures(peer) {
+            return self.hear_unsigned(msg, &msg_id, peer).await;
+        }
 
-        // Create message bytes for verification (without sigs field)
+        // Create message bytes for verification (without the ID and sigs fields,
+        // matching what say() hashed before adding them)
         let mut msg_for_hash = msg.clone();
-        msg_for_hash.as_object_mut().unwrap().remove("sigs");
+        let Some(fields) = msg_for_hash.as_object_mut() else {
+            return self.invalid(peer, "Message is not an object").await;
+        };
+        let sigs = fields.remove("sigs");
+        let work = fields.remove("work");
+        fields.remove("#");
+        fields.remove("ttl");
         let msg_bytes = serde_json::to_vec(&msg_for_hash)?;
         
         // Verify that the message ID matches the SHA256 hash of the message (without sigs)
@@ -249,99 +1503,71 @@ impl Mesh {
         let computed_hash_hex = hex::encode(computed_hash);
         
         if msg_id != computed_hash_hex {
-            eprintln!("DEBUG: Message ID hash mismatch. Expected: {}, Got: {}", computed_hash_hex, msg_id);
-            return Ok(()); // Reject message with invalid hash
+            tracing::debug!(msg_id = %msg_id, expected = %computed_hash_hex, "Message ID hash mismatch");
+            return self.misbehaved(peer, Misbehavior::InvalidHash).await;
         }
 
-        // Verify all signatures in the aggregate before processing
-        let sigs_array = if let Some(sigs) = msg.get("sigs").and_then(|v| v.as_array()) {
-            if sigs.is_empty() {
-                eprintln!("DEBUG: Message missing signatures from peer {:?}", peer.map(|p| &p.id));
-                return Ok(()); // Reject message without signatures
+        // Verify the signatures: one aggregate pairing check over every signer
+        let (signature, verified_pubkeys) = match msg.get("sigs").map(read_sigs) {
+            Some(Ok(sigs)) => sigs,
+            Some(Err(e)) => {
+                tracing::debug!(peer_id = ?peer.map(|p| &p.id), msg_id = %msg_id, error = %e, "Invalid signatures");
+                return self.misbehaved(peer, Misbehavior::InvalidSignature).await;
+            }
+            None => {
+                tracing::debug!(peer_id = ?peer.map(|p| &p.id), msg_id = %msg_id, "Message missing signatures");
+                return self.misbehaved(peer, Misbehavior::InvalidSignature).await;
             }
-            sigs
-        } else {
-            // Legacy format: try to read single sig/pubkey for backward compatibility
-            // But we'll still require sigs array going forward
-            eprintln!("DEBUG: Message missing sigs array from peer {:?}", peer.map(|p| &p.id));
-            return Ok(()); // Reject message without sigs array
         };
-        
-        // msg_bytes already computed above for hash verification, reuse it for signature verification
-        
-        // Verify ALL signatures in the aggregate
-        let mut verified_pubkeys = Vec::new();
-        for sig_entry in sigs_array {
-            let sig_hex = match sig_entry.get("sig").and_then(|v| v.as_str()) {
-                Some(hex) => hex,
-                None => {
-                    eprintln!("DEBUG: Invalid signature entry: missing sig");
-                    return Ok(()); // Reject message with invalid signature entry
-                }
-            };
-            let pubkey_hex = match sig_entry.get("pubkey").and_then(|v| v.as_str()) {
-                Some(hex) => hex,
+        let signature_bytes = signature.to_bytes();
+        let signer_bytes: Vec<[u8; 48]> = verified_pubkeys.iter().map(|pk| pk.to_bytes()).collect();
+        let mut parts: Vec<&[u8]> = vec![&computed_hash, &signature_bytes];
+        parts.extend(signer_bytes.iter().map(|pk| pk.as_slice()));
+        let key = VerifyCache::key(&parts);
+        let cached = self.verified.get(&key);
+        if cached.is_some() {
+            MeshMetrics::add(&self.metrics.signature_cache_hits, 1);
+        }
+        let valid = cached.unwrap_or_else(|| {
+            let valid = aggregate_verify(&signature, verified_pubkeys.iter().map(|pk| (pk, msg_bytes.as_slice())));
+            self.verified.insert(key, valid);
+            valid
+        });
+        if !valid {
+            tracing::debug!(peer_id = ?peer.map(|p| &p.id), msg_id = %msg_id, signers = verified_pubkeys.len(), "Signature verification failed");
+            return self.misbehaved(peer, Misbehavior::InvalidSignature).await;
+        }
+
+        // The origin is the signer named in `from`, which the signatures
+        // cover; the order of the signers' keys can be changed by anyone
+        let origin = match msg.get("from") {
+            None => None,
+            Some(from) => match from_key(from).filter(|key| verified_pubkeys.contains(key)) {
+                Some(key) => Some(key),
                 None => {
-                    eprintln!("DEBUG: Invalid signature entry: missing pubkey");
-                    return Ok(()); // Reject message with invalid signature entry
-                }
-            };
-            
-            // Decode signature and public key
-            let sig_bytes = match hex::decode(sig_hex) {
-                Ok(bytes) => bytes,
-                Err(e) => {
-                    eprintln!("DEBUG: Invalid signature hex: {}", e);
-                    return Ok(()); // Reject message with invalid signature hex
-                }
-            };
-            let pubkey_bytes = match hex::decode(pubkey_hex) {
-                Ok(bytes) => bytes,
-                Err(e) => {
-                    eprintln!("DEBUG: Invalid public key hex: {}", e);
-                    return Ok(()); // Reject message with invalid public key hex
-                }
-            };
-            
-            // Convert to fixed-size arrays
-            if sig_bytes.len() != 96 {
-                eprintln!("DEBUG: Invalid signature length: expected 96 bytes, got {}", sig_bytes.len());
-                return Ok(()); // Reject message with invalid signature
-            }
-            if pubkey_bytes.len() != 48 {
-                eprintln!("DEBUG: Invalid public key length: expected 48 bytes, got {}", pubkey_bytes.len());
-                return Ok(()); // Reject message with invalid public key
-            }
-            
-            let mut sig_array = [0u8; 96];
-            sig_array.copy_from_slice(&sig_bytes);
-            let mut pubkey_array = [0u8; 48];
-            pubkey_array.copy_from_slice(&pubkey_bytes);
-            
-            let signature = match Signature::from_bytes(&sig_array) {
-                Ok(sig) => sig,
-                Err(e) => {
-                    eprintln!("DEBUG: Invalid signature format: {}", e);
-                    return Ok(()); // Reject message with invalid signature
-                }
-            };
-            let sender_pubkey = match PublicKey::from_bytes(&pubkey_array) {
-                Ok(pk) => pk,
-                Err(e) => {
-                    eprintln!("DEBUG: Invalid public key format: {}", e);
-                    return Ok(()); // Reject message with invalid public key
+                    tracing::debug!(peer_id = ?peer.map(|p| &p.id), msg_id = %msg_id, "Message origin is not one of its signers");
+                    return self.misbehaved(peer, Misbehavior::InvalidSignature).await;
                 }
-            };
-            
-            // Verify this signature
-            if !verify(&signature, &sender_pubkey, &msg_bytes) {
-                eprintln!("DEBUG: Signature verification failed for pubkey {} from peer {:?}", pubkey_hex, peer.map(|p| &p.id));
-                return Ok(()); // Reject message if any signature is invalid
-            }
-            
-            verified_pubkeys.push(sender_pubkey);
+            },
+        };
+
+        // The origin's key must be allowed to write; control messages are the
+        // neighbour's own
+        if !stays_on_link(msg) && !self.origin_permitted(origin.as_ref(), &verified_pubkeys, &msg_id, peer) {
+            return Ok(());
         }
         
+        // Deduplication check, before signing and forwarding: the same message
+        // arriving from several peers must only be passed on once
+        if !stays_on_link(msg) && self.seen(&msg_id, peer).await {
+            return Ok(()); // duplicate, ignore
+        }
+
+        // Frozen nodes must be what their soul says, before being passed on
+        if !self.content_addressed_ok(msg, &msg_id, peer).await? {
+            return Ok(());
+        }
+
         // Store peer's public keys for future reference
         if let Some(p) = peer {
             let mut peer_keys = self.peer_public_keys.write().await;
@@ -350,76 +1576,241 @@ impl Mesh {
             }
         }
         
-        // Check if my signature is already in the aggregate
-        let my_pubkey_hex = hex::encode(self.public_key.to_bytes());
-        let has_my_sig = sigs_array.iter().any(|sig_obj| {
-            sig_obj.get("pubkey")
-                .and_then(|v| v.as_str())
-                .map(|pk| pk == my_pubkey_hex)
-                .unwrap_or(false)
+        // If my signature is not in the aggregate and the ttl allows, add it and
+        // re-broadcast (but exclude the sender)
+        if !verified_pubkeys.contains(&self.public_key) && !stays_on_link(msg) && self.user_space_ok(msg).await {
+            if let Some(ttl) = self.next_ttl(msg, &msg_id) {
+                // The hashed copy is the message minus what changes on the way
+                let mut updated_msg = msg_for_hash;
+                updated_msg["#"] = msg_id.clone().into();
+                updated_msg["ttl"] = ttl.into();
+                if let Some(sigs) = sigs {
+                    updated_msg["sigs"] = sigs;
+                }
+                if let Some(work) = work {
+                    updated_msg["work"] = work;
+                }
+                self.sign_into(&mut updated_msg, &msg_bytes)?;
+                let updated_raw = serde_json::to_string(&updated_msg)?;
+                self.forward(&updated_raw.into(), msg, peer, false).await;
+            }
+        }
+
+        self.process(msg, &msg_id, peer, &verified_pubkeys).await
+    }
+
+    /// Handle a message without signatures from a peer we don't require them from
+    ///
+    /// Stock Gun.js peers send these; their `#` is a random ID rather than a
+    /// hash, so only dedup applies. They are forwarded unchanged.
+    async fn hear_unsigned(&self, msg: &Value, msg_id: &str, peer: Option<&Peer>) -> GunResult<()> {
+        if !stays_on_link(msg) && !self.origin_permitted(None, &[], msg_id, peer) {
+            return Ok(());
+        }
+        if !stays_on_link(msg) && self.seen(msg_id, peer).await {
+            return Ok(()); // duplicate, ignore
+        }
+        if !self.content_addressed_ok(msg, msg_id, peer).await? {
+            return Ok(());
+        }
+        let forwardable = !stays_on_link(msg) && self.user_space_ok(msg).await;
+        if let (true, Some(ttl)) = (forwardable, self.next_ttl(msg, msg_id)) {
+            let mut forwarded = msg.clone();
+            forwarded["ttl"] = ttl.into();
+            self.forward(&serde_json::to_string(&forwarded)?.into(), msg, peer, true).await;
+        }
+        self.process(msg, msg_id, peer, &[]).await
+    }
+
+    /// Whether every content-addressed (`#hash`) node a put carries hashes to
+    /// its soul
+    ///
+    /// A mismatch drops the whole message: the sender is told in an ack
+    /// with `err`, a [`CONTENT_HASH_MISMATCH_EVENT`] is emitted, and it
+    /// counts as [`Misbehavior::InvalidHash`].
+    async fn content_addressed_ok(&self, msg: &Value, msg_id: &str, peer: Option<&Peer>) -> GunResult<bool> {
+        let Some(put) = msg.get("put").map(put_nodes) else {
+            return Ok(true);
+        };
+        let tampered = put.as_object().into_iter().flatten().find_map(|(soul, node)| {
+            let soul = node.get("_").and_then(|m| m.get("#")).and_then(|v| v.as_str()).unwrap_or(soul);
+            if !soul.starts_with('#') {
+                return None;
+            }
+            let mut data = node.as_object().cloned().unwrap_or_default();
+            data.remove("_");
+            let data = Value::Object(data);
+            (!crate::valid::content_matches(soul, &data)).then(|| (soul.to_string(), crate::valid::content_hash(&data)))
         });
-        
-        // If my signature is not present, add it and re-broadcast (but exclude the sender)
-        if !has_my_sig {
-            // Sign the message
-            let signature = sign(&self.secret_key, &msg_bytes);
-            let signature_hex = hex::encode(signature.to_bytes());
-            
-            // Add my signature to the array
-            let mut updated_msg = msg.clone();
-            let mut updated_sigs = sigs_array.to_vec();
-            let sig_entry = serde_json::json!({
-                "sig": signature_hex,
-                "pubkey": my_pubkey_hex
-            });
-            updated_sigs.push(sig_entry);
-            updated_msg["sigs"] = serde_json::Value::Array(updated_sigs);
-            
-            // Re-broadcast to all peers except the one that sent it to us
-            let sender_id = peer.map(|p| p.id.clone());
-            let peer_ids: Vec<String> = {
-                let peers = self.peers.read().await;
-                peers.keys()
-                    .filter(|id| Some((**id).clone()) != sender_id)
-                    .cloned()
-                    .collect()
-            };
-            
-            let updated_raw = serde_json::to_string(&updated_msg)?;
-            for peer_id in peer_ids {
-                if let Err(e) = self.send_to_peer_by_id(&updated_raw, &peer_id).await {
-                    eprintln!("Error re-broadcasting signed message to peer {}: {}", peer_id, e);
+        let Some((soul, hash)) = tampered else {
+            return Ok(true);
+        };
+
+        tracing::warn!(peer_id = ?peer.map(|p| &p.id), msg_id = %msg_id, soul = %soul, hash = %hash, "Content-addressed node doesn't match its soul");
+        self.core.events.emit(&crate::events::Event {
+            event_type: CONTENT_HASH_MISMATCH_EVENT.to_string(),
+            data: serde_json::json!({ "peer": peer.map(|p| p.id.clone()), "soul": soul, "hash": hash, "@": msg_id }),
+        });
+        if msg.get("@").is_none() && peer.is_some() {
+            let err = format!("Content hash mismatch: {}", soul);
+            self.reply(&serde_json::json!({ "@": msg_id, "err": err }), peer).await;
+        }
+        self.misbehaved(peer, Misbehavior::InvalidHash).await?;
+        Ok(false)
+    }
+
+    /// Whether every user space (`~`) write a put carries passes
+    /// [`sea::verify_user_put`](crate::sea::verify_user_put), and at least
+    /// one of them is newer than what we hold, with `secure_user_space` on
+    ///
+    /// A put that fails isn't forwarded: its valid keys are still written
+    /// here, but a signed message can't be relayed with the others taken out.
+    /// Nor is an old signed value replayed at its own state, which HAM drops
+    /// here and would drop everywhere else.
+    async fn user_space_ok(&self, msg: &Value) -> bool {
+        if !self.secure_user_space {
+            return true;
+        }
+        let Some(put) = msg.get("put").map(put_nodes) else {
+            return true;
+        };
+        let mut writes = 0;
+        let mut stale = 0;
+        for (soul, node) in put.as_object().into_iter().flatten() {
+            let soul = node.get("_").and_then(|m| m.get("#")).and_then(|v| v.as_str()).unwrap_or(soul);
+            if !soul.starts_with('~') {
+                continue;
+            }
+            let states = node.get("_").and_then(|m| m.get(">"));
+            let held = self.core.graph.get(soul);
+            for (key, value) in node.as_object().into_iter().flatten().filter(|(key, _)| *key != "_") {
+                let state = states.and_then(|s| s.get(key));
+                if crate::sea::verify_user_put(soul, key, value, state).await.is_err() {
+                    return false;
+                }
+                writes += 1;
+                let held_state = held.as_ref().and_then(|node| node.meta.get(">")?.get(key)?.as_f64());
+                if let (Some(held), Some(held_state), Some(state)) = (&held, held_state, state.and_then(|s| s.as_f64())) {
+                    let current = held.data.get(key).unwrap_or(&Value::Null);
+                    if !crate::state::State::ham(state, value, held_state, current) {
+                        stale += 1;
+                    }
                 }
             }
         }
+        writes == 0 || stale < writes
+    }
 
-        // Check custom message predicate (application-level filtering)
-        // This runs after signature verification but before message processing
-        if let Some(ref predicate) = self.message_predicate {
-            if !predicate(msg) {
-                eprintln!("DEBUG: Message rejected by custom predicate from peer {:?}", peer.map(|p| &p.id));
-                return Ok(()); // Reject message based on predicate
+    /// The `ttl` to forward a message with, or `None` if it may not go further
+    ///
+    /// A missing `ttl` counts as `MeshOptions::ttl`, and so does a larger one.
+    fn next_ttl(&self, msg: &Value, msg_id: &str) -> Option<u64> {
+        let ttl = msg.get("ttl").and_then(|v| v.as_u64()).unwrap_or(self.opt.ttl).min(self.opt.ttl);
+        if ttl == 0 {
+            MeshMetrics::add(&self.metrics.ttl_expired, 1);
+            tracing::debug!(msg_id = %msg_id, "Message ttl ran out, not forwarding");
+            return None;
+        }
+        Some(ttl - 1)
+    }
+
+    /// Whether a message ID was seen recently; tracks it if not
+    ///
+    /// The peer it came from is kept with it, for replies to find their way back.
+    async fn seen(&self, msg_id: &str, peer: Option<&Peer>) -> bool {
+        let mut dup = self.dup.write().await;
+        if dup.check(msg_id) {
+            MeshMetrics::add(&self.metrics.duplicates, 1);
+            return true;
+        }
+        dup.track_with_peer(msg_id, peer.map(|p| p.id.as_str()));
+        false
+    }
+
+    /// Pass a message on to every peer except the one it came from
+    ///
+    /// The sender is skipped on every connection it has: one with the same
+    /// pid, e.g. a reconnect overlapping the old connection, would only echo
+    /// the message back. Unsigned messages skip the peers that must sign, which
+    /// would reject them, and a message for a `room` only goes to the peers in it.
+    ///
+    /// A reply (`@`) to a request we saw only goes back to the peer the
+    /// request came from, and no further if the request was ours. Replies to
+    /// requests we never saw still go to everyone.
+    async fn forward(&self, raw: &Frame, msg: &Value, from: Option<&Peer>, unsigned: bool) {
+        let is_sender = |p: &Peer| {
+            from.is_some_and(|from| p.id == from.id || (from.pid.is_some() && p.pid == from.pid))
+        };
+        let via = match msg.get("@").and_then(|v| v.as_str()) {
+            Some(reply_to) => {
+                let dup = self.dup.read().await;
+                dup.contains(reply_to).then(|| dup.get_via(reply_to))
             }
+            None => None,
+        };
+        if via.as_ref().is_some_and(|via| via.is_none()) {
+            tracing::trace!(reply_to = ?msg.get("@"), "Reply to one of our requests, not forwarding");
+            return;
         }
+        let room = msg.get("room").and_then(|v| v.as_str());
+        let priority = is_priority(msg);
+        let peer_ids: Vec<String> = {
+            let peers = self.peers.read().await;
+            peers
+                .values()
+                .filter(|p| !is_sender(p))
+                .filter(|p| via.as_ref().is_none_or(|via| via.as_deref() == Some(p.id.as_str())))
+                .filter(|p| !unsigned || !self.requires_signatures(Some(p)))
+                .filter(|p| room.is_none_or(|room| p.tags.contains(room)))
+                .map(|p| p.id.clone())
+                .collect()
+        };
 
-        // Deduplication check
-        {
-            let mut dup = self.dup.write().await;
-            if dup.check(&msg_id) {
-                return Ok(()); // duplicate, ignore
+        for peer_id in peer_ids {
+            MeshMetrics::add(&self.metrics.messages_forwarded, 1);
+            if let Err(e) = self.send_on_lane(raw, &peer_id, priority).await {
+                tracing::warn!(peer_id = %peer_id, error = %e, "Error re-broadcasting message");
+            }
+        }
+    }
+
+    /// Act on a verified, first-seen message: DAM control, put, get and replies
+    ///
+    /// `signers` are the keys whose signatures on the message were verified.
+    async fn process(&self, msg: &Value, msg_id: &str, peer: Option<&Peer>, signers: &[PublicKey]) -> GunResult<()> {
+        // Check custom message predicate (application-level filtering)
+        // This runs after signature verification but before message processing
+        if let Some(ref predicate) = self.message_predicate {
+            let ctx = PredicateContext {
+                peer_id: peer.map(|p| p.id.clone()),
+                peer_url: peer.map(|p| p.url.clone()),
+                public_keys: signers.to_vec(),
+            };
+            match predicate.check(msg, &ctx).await {
+                PredicateDecision::Accept => {}
+                PredicateDecision::Reject => {
+                    tracing::debug!(peer_id = ?peer.map(|p| &p.id), msg_id = %msg_id, "Message rejected by custom predicate");
+                    return self.misbehaved(peer, Misbehavior::Rejected).await;
+                }
+                PredicateDecision::RejectWithError(err) => {
+                    tracing::debug!(peer_id = ?peer.map(|p| &p.id), msg_id = %msg_id, error = %err, "Message rejected by custom predicate");
+                    if let Some(p) = peer {
+                        let _ = self
+                            .say(&serde_json::json!({"dam": "!", "err": err, "@": msg_id}), Some(p))
+                            .await;
+                    }
+                    return self.misbehaved(peer, Misbehavior::Rejected).await;
+                }
             }
-            dup.track(&msg_id);
         }
 
         // Handle special DAM messages
         if let Some(dam_type) = msg.get("dam").and_then(|v| v.as_str()) {
             match dam_type {
                 "!" => {
-                    // Error message
+                    // Error message, about one of ours if it carries "@"
                     if let Some(p) = peer {
-                        if let Some(err) = msg.get("err").and_then(|v| v.as_str()) {
-                            eprintln!("DAM Error from peer {}: {}", p.id, err);
-                        }
+                        self.handle_peer_error(msg, p);
                     }
                 }
                 "?" => {
@@ -428,10 +1819,31 @@ impl Mesh {
                         self.handle_peer_id_exchange(msg, p).await?;
                     }
                 }
+                "bye" => {
+                    // The peer is going away; forget it now rather than on timeout
+                    if let Some(p) = peer {
+                        self.bye(&p.id).await?;
+                    }
+                }
+                "pex" => {
+                    // Peer exchange: addresses the peer connected to successfully
+                    if let Some(p) = peer {
+                        self.handle_pex(msg, p).await;
+                    }
+                }
+                "join" | "leave" => {
+                    // Rooms the peer wants messages for, or no longer does
+                    if let Some(p) = peer {
+                        self.handle_rooms(msg, dam_type == "join", p).await;
+                    }
+                }
                 "rtc" => {
-                    // WebRTC signaling message - these are handled at the Gun level
-                    // to avoid circular dependencies between Mesh and WebRTCManager
-                    tracing::debug!("Received RTC signaling message via DAM protocol");
+                    // WebRTC signaling, for the WebRTC manager like `ok.rtc` below
+                    tracing::debug!(peer_id = ?peer.map(|p| &p.id), msg_id = %msg_id, "Received RTC signaling message via DAM protocol");
+                    self.core.events.emit(&crate::events::Event {
+                        event_type: RTC_SIGNAL_EVENT.to_string(),
+                        data: msg.clone(),
+                    });
                 }
                 _ => {
                     // Other DAM message types
@@ -442,10 +1854,13 @@ impl Mesh {
 
         // Process Gun protocol messages (put, get)
         if let Some(put_data) = msg.get("put") {
-            eprintln!("DEBUG: Received put message: {}", serde_json::to_string(msg).unwrap_or_default());
+            tracing::trace!(peer_id = ?peer.map(|p| &p.id), msg_id = %msg_id, msg = %msg, "Received put message");
             // Handle put message - update graph and emit node_update event
             // Gun.js format: { put: { soul: { _: { "#": soul, ">": states }, ...data } } }
             // The soul is a KEY in the put object, not a field
+            let put_data = put_nodes(put_data);
+            let mut put_error: Option<String> = None;
+            let mut unauthorized = Vec::new();
             if let Some(put_obj) = put_data.as_object() {
                 // Iterate over each soul in the put object
                 for (soul, node_data) in put_obj {
@@ -463,23 +1878,60 @@ impl Mesh {
                             .unwrap_or_else(|| Node::with_soul(soul_from_meta.to_string()));
                         
                         // Merge all fields from node_obj into node (except "_" which is metadata)
+                        let mut changed = Vec::new();
+                        let mut stale = 0;
+                        let rejected = unauthorized.len();
                         for (key, value) in node_obj {
                             if key != "_" {
+                                // User space keys must be signed by their owner
+                                if self.secure_user_space && soul_from_meta.starts_with('~') {
+                                    let state = states.and_then(|s| s.get(key));
+                                    if let Err(e) = crate::sea::verify_user_put(soul_from_meta, key, value, state).await {
+                                        tracing::debug!(peer_id = ?peer.map(|p| &p.id), soul = %soul_from_meta, key = %key, error = %e, "Unauthorized user space write");
+                                        unauthorized.push(format!("{}.{}", soul_from_meta, key));
+                                        continue;
+                                    }
+                                }
+
                                 // Get state for this key from states map if available
                                 let state = states.and_then(|s| s.get(key))
                                     .and_then(|v| v.as_f64())
                                     .unwrap_or_else(|| self.core.state.next());
-                                
+
+                                // Older than what we have: keep ours, e.g. a lagging peer's answer
+                                if let Some(current_state) = node.meta.get(">").and_then(|s| s.get(key)).and_then(|v| v.as_f64()) {
+                                    let current = node.data.get(key).unwrap_or(&Value::Null);
+                                    if !crate::state::State::ham(state, value, current_state, current) {
+                                        stale += 1;
+                                        continue;
+                                    }
+                                }
+
                                 node.data.insert(key.clone(), value.clone());
                                 crate::state::State::ify(&mut node, Some(&key), Some(state), Some(value.clone()), Some(soul_from_meta));
+                                changed.push((key.clone(), value.clone(), state));
                             }
                         }
                         
+                        // Nothing of this node may be written, or nothing is newer
+                        if changed.is_empty() && (unauthorized.len() > rejected || stale > 0) {
+                            continue;
+                        }
+
                         // Store updated node
                         if let Err(e) = self.core.graph.put(soul_from_meta, node.clone()) {
-                            eprintln!("Error updating graph for soul {}: {}", soul_from_meta, e);
+                            tracing::warn!(soul = %soul_from_meta, error = %e, "Error updating graph");
+                            put_error = Some(e.to_string());
                         } else {
-                            eprintln!("DEBUG: Updated graph for soul {} (from peer), emitting node_update event. Node data keys: {:?}", soul_from_meta, node.data.keys().collect::<Vec<_>>());
+                            tracing::trace!(soul = %soul_from_meta, keys = ?node.data.keys().collect::<Vec<_>>(), "Updated graph from peer");
+                            // Persist only the keys this message changed
+                            let write = crate::persist::StorageWrite::Delta {
+                                soul: soul_from_meta.to_string(),
+                                changed,
+                            };
+                            if let Err(e) = self.core.persist(write).await {
+                                tracing::warn!(soul = %soul_from_meta, error = %e, "Error persisting put");
+                            }
                             // Emit node_update event so once() and on() callbacks get called
                             let event_type = format!("node_update:{}", soul_from_meta);
                             self.core.events.emit(&crate::events::Event {
@@ -497,239 +1949,1205 @@ impl Mesh {
                     }
                 }
             }
+
+            if !unauthorized.is_empty() && put_error.is_none() {
+                put_error = Some(format!("Unauthorized user space writes: {}", unauthorized.join(", ")));
+            }
+            if put_error.is_none() {
+                MeshMetrics::add(&self.metrics.puts_applied, 1);
+            }
+            // Souls that just arrived aren't missing anymore
+            if let Some(souls) = put_data.as_object() {
+                self.missing.lock().retain(|(soul, _), _| !souls.contains_key(soul));
+            }
+
+            // Ack puts (not replies) so the sender can tell they landed
+            if msg.get("@").is_none() {
+                let ack = match put_error {
+                    Some(err) => serde_json::json!({ "@": msg_id, "err": err }),
+                    None => serde_json::json!({ "@": msg_id, "ok": { "": 1 } }),
+                };
+                if peer.is_some() {
+                    self.reply(&ack, peer).await;
+                }
+            }
         } else if let Some(get_data) = msg.get("get") {
-            eprintln!("DEBUG: Received get message: {}", serde_json::to_string(msg).unwrap_or_default());
+            tracing::trace!(peer_id = ?peer.map(|p| &p.id), msg_id = %msg_id, msg = %msg, "Received get message");
             // Handle get message - respond with requested data
-            if let Some(get_obj) = get_data.as_object() {
-                if let Some(soul_val) = get_obj.get("#") {
-                    if let Some(soul) = soul_val.as_str() {
-                        // Check if we have the requested node
-                        if let Some(node) = self.core.graph.get(soul) {
-                            // Check if get request has a key (for nested properties)
-                            if let Some(key_val) = get_obj.get(".") {
-                                if let Some(key) = key_val.as_str() {
-                                    // Requesting a specific key - check if it's a soul reference
-                                    if let Some(value) = node.data.get(key) {
-                                        if let Some(obj) = value.as_object() {
-                                            if let Some(soul_ref) = obj.get("#") {
-                                                if let Some(ref_soul) = soul_ref.as_str() {
-                                                    // It's a soul reference - get the referenced node
-                                                    if let Some(ref_node) = self.core.graph.get(ref_soul) {
-                                                        let mut put_obj = serde_json::json!({
-                                                            "#": ref_soul
-                                                        });
-                                                        for (k, v) in &ref_node.data {
-                                                            put_obj[k] = v.clone();
-                                                        }
-                                                        let response = serde_json::json!({
-                                                            "put": put_obj
-                                                        });
-                                                        eprintln!("DEBUG: Sending get response for nested soul {} (key: {}) to peer", ref_soul, key);
-                                                        if let Some(p) = peer {
-                                                            if let Err(e) = self.say(&response, Some(p)).await {
-                                                                eprintln!("Error sending get response to peer {}: {}", p.id, e);
-                                                            }
-                                                        }
-                                                    }
-                                                }
-                                            } else {
-                                                // Not a soul reference - return the value directly
-                                                let put_obj = serde_json::json!({
-                                                    "#": soul,
-                                                    key: value.clone()
-                                                });
-                                                let response = serde_json::json!({
-                                                    "put": put_obj
-                                                });
-                                                eprintln!("DEBUG: Sending get response for key {} in soul {} to peer", key, soul);
-                                                if let Some(p) = peer {
-                                                    if let Err(e) = self.say(&response, Some(p)).await {
-                                                        eprintln!("Error sending get response to peer {}: {}", p.id, e);
-                                                    }
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            } else {
-                                // No key specified - return entire node
-                                let mut put_obj = serde_json::json!({
-                                    "#": soul
-                                });
-                                for (key, value) in &node.data {
-                                    put_obj[key] = value.clone();
-                                }
-                                let response = serde_json::json!({
-                                    "put": put_obj
-                                });
-                                eprintln!("DEBUG: Sending get response for soul {} to peer. Response: {}", soul, serde_json::to_string(&response).unwrap_or_default());
-                                // Broadcast the response instead of sending to specific peer
-                                // This ensures the relay server forwards it properly
-                                if let Err(e) = self.say(&response, None).await {
-                                    eprintln!("Error broadcasting get response: {}", e);
-                                } else {
-                                    eprintln!("DEBUG: Get response broadcast successfully");
-                                }
-                            }
-                        } else {
-                            eprintln!("DEBUG: Requested soul {} not found in graph", soul);
-                        }
-                    }
-                }
+            self.answer_get(get_data, msg_id, peer).await?;
+        }
+
+        // WebRTC offers, answers and candidates, for the WebRTC manager
+        if msg.get("ok").and_then(|ok| ok.get("rtc")).is_some() {
+            self.core.events.emit(&crate::events::Event {
+                event_type: RTC_SIGNAL_EVENT.to_string(),
+                data: msg.clone(),
+            });
+        }
+
+        // Hand replies to whoever is waiting on the request they answer
+        if let Some(reply_to) = msg.get("@").and_then(|v| v.as_str()) {
+            let waiting = self.pending.lock().remove(reply_to);
+            if let Some(pending) = waiting {
+                let _ = pending.tx.send(msg.clone());
             }
         }
 
         Ok(())
     }
 
-    /// Handle peer ID exchange (DAM '?' message)
-    async fn handle_peer_id_exchange(&self, msg: &Value, peer: &Peer) -> GunResult<()> {
-        if let Some(pid) = msg.get("pid").and_then(|v| v.as_str()) {
-            // Update peer PID with minimal lock time
-            {
-                let mut peers = self.peers.write().await;
-                if let Some(p) = peers.get_mut(&peer.id) {
-                    p.pid = Some(pid.to_string());
-                }
-            } // Lock released before calling say()
-
-            // Reply with our PID (lock released to avoid deadlock)
-            self.say(
-                &serde_json::json!({
-                    "dam": "?",
-                    "pid": self.pid,
-                    "@": msg.get("#")
-                }),
-                Some(peer),
-            )
-            .await?;
+    /// Answer a get with the requested node in Gun.js put format
+    ///
+    /// The response is `{"put": {soul: {"_": {"#": soul, ">": states}, ...data}},
+    /// "@": request_id}` with the node's stored states, so the receiver merges it
+    /// by HAM instead of stamping it as new.
+    ///
+    /// Like a Gun.js relay, the get may narrow the keys sent back:
+    /// - `".": key` - that key only; if it links to another node, that node is included
+    /// - `".": {"*": prefix}` - keys starting with `prefix`
+    /// - `".": {">": min, "<": max}` - keys between `min` and `max` (inclusive, either optional)
+    /// - `"%": bytes` - stop adding keys (in key order) once the response would exceed
+    ///   `bytes`; at least one key is always sent
+    ///
+    /// Several nodes can be asked for at once, with `"#": [soul, ...]` or an array
+    /// of get objects; they are answered together in one `put`, leaving out the
+    /// missing ones. Nothing is sent when no node is found or no key matches.
+    async fn answer_get(&self, get: &Value, msg_id: &str, peer: Option<&Peer>) -> GunResult<()> {
+        let mut put = serde_json::Map::new();
+        for get in expand_get(get) {
+            self.collect_get(&get, &mut put).await?;
         }
+        if put.is_empty() {
+            return Ok(());
+        }
+
+        let response = serde_json::json!({
+            "put": put,
+            "@": msg_id
+        });
+        tracing::trace!(peer_id = ?peer.map(|p| &p.id), msg_id = %msg_id, souls = put.len(), response = %response, "Sending get response");
+        MeshMetrics::add(&self.metrics.gets_answered, 1);
+        self.reply(&response, peer).await;
         Ok(())
     }
 
-    /// Send message to peer(s) (matches mesh.say)
-    pub async fn say(&self, msg: &Value, peer: Option<&Peer>) -> GunResult<()> {
-        let mut msg = msg.clone();
-        
-        // Create message bytes for hashing and signing (without sigs field)
-        let mut msg_for_hash = msg.clone();
-        msg_for_hash.as_object_mut().unwrap().remove("sigs");
-        let msg_bytes = serde_json::to_vec(&msg_for_hash)?;
-        
-        // Generate message ID if not present - use SHA256 hash of message (without sigs)
-        if msg.get("#").is_none() {
-            let mut hasher = Sha256::new();
-            hasher.update(&msg_bytes);
-            let hash = hasher.finalize();
-            let hash_hex = hex::encode(hash);
-            msg["#"] = serde_json::Value::String(hash_hex);
-        }
-        
-        // Check if message already has signatures
-        let mut sigs_array = if let Some(sigs) = msg.get("sigs").and_then(|v| v.as_array()) {
-            sigs.clone()
-        } else {
-            Vec::new()
+    /// Add the node (or keys) one get asks for to a response's `put`
+    async fn collect_get(
+        &self,
+        get: &serde_json::Map<String, Value>,
+        put: &mut serde_json::Map<String, Value>,
+    ) -> GunResult<()> {
+        let Some(soul) = get.get("#").and_then(|v| v.as_str()) else {
+            return Ok(());
         };
-        
-        // Check if my signature is already in the array
-        let my_pubkey_hex = hex::encode(self.public_key.to_bytes());
-        let has_my_sig = sigs_array.iter().any(|sig_obj| {
-            sig_obj.get("pubkey")
-                .and_then(|v| v.as_str())
-                .map(|pk| pk == my_pubkey_hex)
-                .unwrap_or(false)
-        });
-        
-        // If my signature is not present, add it
-        if !has_my_sig {
-            let signature = sign(&self.secret_key, &msg_bytes);
-            let signature_hex = hex::encode(signature.to_bytes());
-            
-            let sig_entry = serde_json::json!({
-                "sig": signature_hex,
-                "pubkey": my_pubkey_hex
-            });
-            sigs_array.push(sig_entry);
+        let Some(node) = self.core.get_node(soul).await? else {
+            tracing::trace!(soul = %soul, "Requested soul not found in graph");
+            return Ok(());
+        };
+
+        // Only the matching keys, in key order, until the response reaches `%` bytes
+        let lex = get.get(".");
+        let limit = get.get("%").and_then(|v| v.as_u64()).map(|n| n as usize);
+        let mut keys: Vec<&String> = node
+            .data
+            .keys()
+            .filter(|key| lex.is_none_or(|lex| lex_match(lex, key)))
+            .collect();
+        if keys.is_empty() && lex.is_some() {
+            return Ok(());
         }
-        
-        // Add signatures array to message
-        msg["sigs"] = serde_json::Value::Array(sigs_array);
+        keys.sort();
 
-        let raw = serde_json::to_string(&msg)?;
+        let states = node.meta.get(">").and_then(|v| v.as_object());
+        let mut part = crate::state::Node::with_soul(soul.to_string());
+        let mut size = 0;
+        for key in keys {
+            let value = &node.data[key];
+            let entry = key.len() + value.to_string().len();
+            if limit.is_some_and(|limit| size > 0 && size + entry > limit) {
+                break;
+            }
+            size += entry;
+            let state = states.and_then(|s| s.get(key)).and_then(|v| v.as_f64());
+            crate::state::State::ify(&mut part, Some(key), state, Some(value.clone()), None);
+        }
 
-        if let Some(p) = peer {
-            self.send_to_peer_by_id(&raw, &p.id).await?;
-        } else {
-            // Broadcast to all peers - clone IDs first to avoid holding lock during async calls
-            let peer_ids: Vec<String> = {
-                let peers = self.peers.read().await;
-                let ids: Vec<String> = peers.keys().cloned().collect();
-                eprintln!("DEBUG: Broadcasting message to {} peers: {:?}", ids.len(), ids);
-                ids
-            };
+        put.insert(soul.to_string(), part.to_wire());
 
-            // Now send to each peer without holding the lock
-            for peer_id in peer_ids {
-                eprintln!("DEBUG: Attempting to send broadcast message to peer {}", peer_id);
-                if let Err(e) = self.send_to_peer_by_id(&raw, &peer_id).await {
-                    eprintln!("Error sending to peer {}: {}", peer_id, e);
-                    // Continue sending to other peers even if one fails
-                } else {
-                    eprintln!("DEBUG: Successfully sent broadcast message to peer {}", peer_id);
-                }
+        // A single key that links to another node brings that node along
+        let link = lex
+            .and_then(|lex| lex.as_str())
+            .and_then(|key| node.data.get(key))
+            .and_then(|value| value.get("#"))
+            .and_then(|v| v.as_str());
+        if let Some(link) = link {
+            if let Some(linked) = self.core.get_node(link).await? {
+                put.insert(link.to_string(), linked.to_wire());
             }
         }
 
         Ok(())
     }
 
-    /// Send raw message to a specific peer by ID
-    /// Routes through WebSocket connection if available, otherwise queues
-    pub(crate) async fn send_to_peer_by_id(&self, raw: &str, peer_id: &str) -> GunResult<()> {
-        // Try to get the sender without holding the lock for long
-        let tx_opt = {
-            let peers = self.peers.read().await;
-            if let Some(peer) = peers.get(peer_id) {
-                eprintln!("DEBUG: Found peer {} with sender available", peer_id);
-                peer.tx.clone() // Clone the Sender to release the lock immediately
-            } else {
-                eprintln!("DEBUG: Peer {} not found in peers list ({} total peers)", peer_id, peers.len());
-                None // Peer not found
-            }
+    /// Answer a request: to the peer that asked, or to everyone if it's unknown
+    ///
+    /// Goes to the connection the asking peer's pid is on now, which differs
+    /// from `peer` if it reconnected in the meantime.
+    async fn reply(&self, response: &Value, peer: Option<&Peer>) {
+        let current = match peer.and_then(|p| p.pid.as_deref()) {
+            Some(pid) => match self.peer_id_for_pid(pid) {
+                Some(peer_id) => self.get_peer(&peer_id).await,
+                None => None,
+            },
+            None => None,
         };
-
-        if let Some(tx) = tx_opt {
-            // Send immediately through WebSocket (no lock held)
-            let msg_preview = raw.chars().take(150).collect::<String>();
-            eprintln!("DEBUG: Sending message to WebSocket for peer {}: {}", peer_id, msg_preview);
-            tx.send(raw.to_string()).map_err(|e| {
-                eprintln!("DEBUG: WebSocket send error for peer {}: {}", peer_id, e);
-                crate::error::GunError::Network(format!(
-                    "Failed to send to peer {}: {}",
-                    peer_id, e
-                ))
-            })?;
-            eprintln!("DEBUG: Message sent successfully to WebSocket for peer {}", peer_id);
-            return Ok(());
+        if let Err(e) = self.say(response, current.as_ref().or(peer)).await {
+            tracing::warn!(peer_id = ?peer.map(|p| &p.id), error = %e, "Error sending reply");
         }
+    }
 
-        // No sender available - queue the message
-        // Use a short write lock to add to queue
-        {
-            let mut peers = self.peers.write().await;
-            if let Some(peer) = peers.get_mut(peer_id) {
-                peer.queue.push(raw.to_string());
-                // Don't warn - this is expected during initial connection
-            } else {
-                // Peer doesn't exist - this is fine, they'll get it when they connect
-                return Ok(());
+    /// Handle an error from a peer (DAM '!' message)
+    ///
+    /// Emits [`PEER_ERROR_EVENT`], and if the error names a request still
+    /// waiting on a reply, hands it over so the request fails now rather than
+    /// after `lack` ms.
+    fn handle_peer_error(&self, msg: &Value, peer: &Peer) {
+        let err = msg.get("err").and_then(|v| v.as_str()).unwrap_or("Unknown error");
+        let reply_to = msg.get("@").and_then(|v| v.as_str());
+        tracing::warn!(peer_id = %peer.id, error = %err, reply_to = ?reply_to, "DAM error from peer");
+
+        let mut data = serde_json::json!({ "peer": peer.id, "err": err });
+        if let Some(reply_to) = reply_to {
+            data["@"] = Value::String(reply_to.to_string());
+            let waiting = self.pending.lock().remove(reply_to);
+            if let Some(pending) = waiting {
+                let _ = pending.tx.send(msg.clone());
+            }
+        }
+        self.core.events.emit(&crate::events::Event {
+            event_type: PEER_ERROR_EVENT.to_string(),
+            data,
+        });
+    }
+
+    /// Handle peer ID exchange (DAM '?' message)
+    async fn handle_peer_id_exchange(&self, msg: &Value, peer: &Peer) -> GunResult<()> {
+        if let Some(pid) = msg.get("pid").and_then(|v| v.as_str()) {
+            // MessagePack if we both read it, else JSON
+            let offered = msg.get("enc").and_then(|v| v.as_array());
+            let encoding = match offered {
+                Some(enc) if self.opt.msgpack && enc.iter().any(|e| e == wire::MSGPACK) => WireEncoding::MessagePack,
+                _ => WireEncoding::Json,
+            };
+            // Update peer PID with minimal lock time
+            let known = {
+                let mut peers = self.peers.write().await;
+                match peers.get_mut(&peer.id) {
+                    Some(p) => {
+                        p.pid = Some(pid.to_string());
+                        if p.encoding != encoding && p.tx.as_ref().is_some_and(|tx| tx.set_encoding(encoding)) {
+                            tracing::debug!(peer_id = %p.id, encoding = ?encoding, "Switched frame encoding");
+                            p.encoding = encoding;
+                        }
+                        true
+                    }
+                    None => false,
+                }
+            }; // Lock released before calling say()
+            // A reconnecting peer says `?` again from its new connection
+            self.pids.lock().insert(pid.to_string(), peer.id.clone());
+            self.core.events.emit(&crate::events::Event {
+                event_type: PEER_HI_EVENT.to_string(),
+                data: serde_json::json!({ "peer": peer.id, "pid": pid }),
+            });
+            // Online unless it already was, e.g. announced reconnected, or
+            // was removed while this `?` was on its way
+            if known && self.online.lock().insert(peer.id.clone()) {
+                self.emit_lifecycle(&peer.id, &peer.url, Some(pid), serde_json::json!({ "state": "connected" }));
+            }
+
+            // A reply to our own '?' - answering it would ping-pong forever
+            if msg.get("@").is_some() {
+                return Ok(());
+            }
+
+            // Reply with our PID (lock released to avoid deadlock)
+            let mut reply = self.hi_message();
+            reply["@"] = msg.get("#").cloned().unwrap_or_default();
+            self.say(&reply, Some(peer)).await?;
+        }
+        Ok(())
+    }
+
+    /// Our DAM `?` message: our pid, and the encodings we read if not just JSON
+    fn hi_message(&self) -> Value {
+        let mut hi = serde_json::json!({ "dam": "?", "pid": self.pid });
+        if self.opt.msgpack {
+            hi["enc"] = serde_json::json!([wire::MSGPACK, wire::JSON]);
+        }
+        hi
+    }
+
+    /// Send a message to the peer with DAM pid `pid`
+    ///
+    /// Unlike the local peer ID, a pid stays the same when the peer reconnects,
+    /// so this reaches whichever connection last said `?` with it.
+    ///
+    /// # Errors
+    /// Returns `GunError::Network` if no connected peer has that pid.
+    pub async fn send_to_pid(&self, pid: &str, msg: &Value) -> GunResult<()> {
+        let peer = match self.peer_id_for_pid(pid) {
+            Some(peer_id) => self.get_peer(&peer_id).await,
+            None => None,
+        };
+        let peer = peer.ok_or_else(|| {
+            crate::error::GunError::Network(format!("No connected peer with pid {}", pid))
+        })?;
+        self.say(msg, Some(&peer)).await
+    }
+
+    /// Local peer ID of the connection the peer with DAM pid `pid` is on
+    pub fn peer_id_for_pid(&self, pid: &str) -> Option<String> {
+        self.pids.lock().get(pid).cloned()
+    }
+
+    /// Reach the peer with DAM pid `pid` over `transport` first
+    ///
+    /// Frames for its WebSocket connection go over `transport`, and over the
+    /// connection only when `transport` fails; the choice is counted in
+    /// [`MeshStats::transport_sent`] and [`MeshStats::transport_fallbacks`].
+    /// A pid we have no connection to, e.g. one behind a relay, is added as
+    /// a peer of its own, so broadcasts reach it over `transport` too.
+    /// Replaces any transport the pid had.
+    pub async fn add_transport(&self, pid: &str, transport: PeerTransport) {
+        let peer_id = match self.peer_id_for_pid(pid) {
+            Some(_) => None,
+            None => {
+                let mut peer = Peer::new(format!("webrtc:{}", pid));
+                peer.pid = Some(pid.to_string());
+                peer.last_seen = Some(std::time::Instant::now());
+                let peer_id = peer.id.clone();
+                self.peers.write().await.insert(peer_id.clone(), peer);
+                *self.near.write().await += 1;
+                Some(peer_id)
+            }
+        };
+        let old = self.transports.lock().insert(pid.to_string(), AltTransport { send: transport, peer_id });
+        if let Some(peer_id) = old.and_then(|old| old.peer_id) {
+            let _ = self.bye(&peer_id).await;
+        }
+    }
+
+    /// Stop using the alternate transport of the peer with DAM pid `pid`
+    ///
+    /// The peer added for it, if any, is removed.
+    pub async fn remove_transport(&self, pid: &str) {
+        let old = self.transports.lock().remove(pid);
+        if let Some(peer_id) = old.and_then(|old| old.peer_id) {
+            let _ = self.bye(&peer_id).await;
+        }
+    }
+
+    /// The peer with DAM pid `pid`: its WebSocket connection, or the peer
+    /// added for its alternate transport
+    ///
+    /// Frames arriving over an alternate transport are heard as from this
+    /// peer, so they aren't forwarded back to it.
+    pub async fn peer_for_pid(&self, pid: &str) -> Option<Peer> {
+        let peer_id = self
+            .peer_id_for_pid(pid)
+            .or_else(|| self.transports.lock().get(pid).and_then(|t| t.peer_id.clone()))?;
+        self.get_peer(&peer_id).await
+    }
+
+    /// Send message to peer(s) (matches mesh.say)
+    #[tracing::instrument(level = "trace", skip_all, fields(peer_id = peer.map(|p| p.id.as_str())))]
+    pub async fn say(&self, msg: &Value, peer: Option<&Peer>) -> GunResult<()> {
+        self.count_said(msg);
+        let sealed = self.seal_worked(msg).await?;
+        self.send(&sealed, peer).await
+    }
+
+    /// Send a message only to the peers tagged with `tag`
+    ///
+    /// The message carries the tag as its `room`, so peers passing it on
+    /// also only forward it to peers in that room. Peers are tagged when
+    /// they send a `dam: "join"` (see [`join`](Self::join)), with
+    /// [`tag_peer`](Self::tag_peer), or through [`Peer::tags`] before `hi`.
+    ///
+    /// # Returns
+    /// The number of peers the message was sent to.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # async fn example(mesh: &gun::dam::Mesh) -> Result<(), Box<dyn std::error::Error>> {
+    /// let presence = serde_json::json!({ "put": { "presence": { "alice": "online" } } });
+    /// mesh.say_to_tag("presence", &presence).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn say_to_tag(&self, tag: &str, msg: &Value) -> GunResult<usize> {
+        let mut msg = msg.clone();
+        if !stays_on_link(&msg) {
+            msg["room"] = tag.into();
+        }
+        self.count_said(&msg);
+        let sealed = self.seal_worked(&msg).await?;
+        let peer_ids = self.peers_tagged(tag).await;
+        for peer_id in &peer_ids {
+            if let Err(e) = self.send_on_lane(&sealed.frame, peer_id, sealed.priority).await {
+                tracing::warn!(peer_id = %peer_id, tag = %tag, error = %e, "Error sending to tagged peer");
+            }
+        }
+        Ok(peer_ids.len())
+    }
+
+    /// IDs of the peers tagged with `tag`
+    pub async fn peers_tagged(&self, tag: &str) -> Vec<String> {
+        let peers = self.peers.read().await;
+        peers.values().filter(|p| p.tags.contains(tag)).map(|p| p.id.clone()).collect()
+    }
+
+    /// Tag a peer, as if it had joined the room `tag`
+    ///
+    /// # Returns
+    /// `false` if there is no such peer.
+    pub async fn tag_peer(&self, peer_id: &str, tag: &str) -> bool {
+        match self.peers.write().await.get_mut(peer_id) {
+            Some(peer) => {
+                peer.tags.insert(tag.to_string());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove a tag from a peer
+    ///
+    /// # Returns
+    /// `false` if there is no such peer.
+    pub async fn untag_peer(&self, peer_id: &str, tag: &str) -> bool {
+        match self.peers.write().await.get_mut(peer_id) {
+            Some(peer) => {
+                peer.tags.remove(tag);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Join a room: ask every peer, now and on later connects, for its messages
+    ///
+    /// Sends a `dam: "join"` naming the room; peers then tag us with it, so
+    /// messages sent with [`say_to_tag`](Self::say_to_tag) reach us.
+    pub async fn join(&self, room: &str) -> GunResult<()> {
+        if self.rooms.lock().insert(room.to_string()) {
+            self.say(&serde_json::json!({ "dam": "join", "rooms": [room] }), None).await?;
+        }
+        Ok(())
+    }
+
+    /// Leave a room joined with [`join`](Self::join)
+    pub async fn leave(&self, room: &str) -> GunResult<()> {
+        if self.rooms.lock().remove(room) {
+            self.say(&serde_json::json!({ "dam": "leave", "rooms": [room] }), None).await?;
+        }
+        Ok(())
+    }
+
+    /// Rooms we joined, in order
+    pub fn rooms(&self) -> Vec<String> {
+        self.rooms.lock().iter().cloned().collect()
+    }
+
+    /// Tell a new or reconnected peer which rooms we're in
+    async fn announce_rooms(&self, peer: &Peer) {
+        let rooms = self.rooms();
+        if rooms.is_empty() {
+            return;
+        }
+        if let Err(e) = self.say(&serde_json::json!({ "dam": "join", "rooms": rooms }), Some(peer)).await {
+            tracing::warn!(peer_id = %peer.id, error = %e, "Failed to announce rooms");
+        }
+    }
+
+    /// Tag or untag a peer with the rooms in its `dam: "join"` or `"leave"`
+    ///
+    /// A peer can be in at most `MAX_PEER_TAGS` rooms.
+    async fn handle_rooms(&self, msg: &Value, join: bool, peer: &Peer) {
+        let Some(rooms) = msg.get("rooms").and_then(|v| v.as_array()) else {
+            return;
+        };
+        let mut peers = self.peers.write().await;
+        let Some(peer) = peers.get_mut(&peer.id) else {
+            return;
+        };
+        for room in rooms.iter().filter_map(|v| v.as_str()) {
+            if !join {
+                peer.tags.remove(room);
+            } else if peer.tags.len() < MAX_PEER_TAGS {
+                peer.tags.insert(room.to_string());
+            }
+        }
+        tracing::debug!(peer_id = %peer.id, tags = peer.tags.len(), join, "Peer rooms updated");
+    }
+
+    /// Broadcast a request and wait for its reply
+    ///
+    /// The reply is the first message whose `@` names this request's `#`, such as
+    /// a get response or a put ack. Requests nobody answers are dropped after
+    /// `lack` milliseconds.
+    ///
+    /// # Returns
+    /// The request's message ID and a receiver for the reply.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # async fn example(mesh: &gun::dam::Mesh) -> Result<(), Box<dyn std::error::Error>> {
+    /// let (_id, reply) = mesh.ask(&serde_json::json!({ "get": { "#": "user_123" } })).await?;
+    /// let response = reply.await?;
+    /// println!("{:?}", response.get("put"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn ask(&self, msg: &Value) -> GunResult<(String, oneshot::Receiver<Value>)> {
+        self.count_said(msg);
+        let sealed = self.seal_worked(msg).await?;
+        let id = sealed.id.clone();
+        let (tx, rx) = oneshot::channel();
+        {
+            let lack = std::time::Duration::from_millis(self.opt.lack);
+            let mut pending = self.pending.lock();
+            pending.retain(|_, request| !request.tx.is_closed() && request.sent.elapsed() < lack);
+            let get = msg.get("get").is_some().then(|| sealed.frame.clone());
+            pending.insert(id.clone(), PendingRequest { tx, sent: std::time::Instant::now(), get });
+        }
+        self.send(&sealed, None).await?;
+        Ok((id, rx))
+    }
+
+    /// Send a put and wait for a peer to acknowledge it
+    ///
+    /// Fails as soon as a peer answers with an error, either a put ack
+    /// carrying `err` or a `dam: "!"` message about this put (e.g. it was too
+    /// big, or a relay's message predicate turned it down).
+    ///
+    /// # Arguments
+    /// * `put` - The put object, in Gun.js format: `{soul: {"_": {"#": soul, ">": states}, ...data}}`
+    ///
+    /// # Returns
+    /// The ack, or `GunError::Network` with the peer's error, or if no peer
+    /// answered within `lack` milliseconds.
+    ///
+    /// # Errors
+    /// Also returns an error if the put can't be serialized or sent.
+    pub async fn put_with_ack(&self, put: &Value) -> GunResult<Value> {
+        let (id, reply) = self.ask(&serde_json::json!({ "put": put })).await?;
+        let lack = std::time::Duration::from_millis(self.opt.lack);
+        let ack = match tokio::time::timeout(lack, reply).await {
+            Ok(Ok(ack)) => ack,
+            Ok(Err(_)) | Err(_) => {
+                self.pending.lock().remove(&id);
+                return Err(crate::error::GunError::Network(format!(
+                    "No ack for put {} within {} ms",
+                    id, self.opt.lack
+                )));
+            }
+        };
+        match ack.get("err") {
+            Some(err) => Err(crate::error::GunError::Network(format!(
+                "Peer rejected put: {}",
+                err.as_str().map(str::to_string).unwrap_or_else(|| err.to_string())
+            ))),
+            None => Ok(ack),
+        }
+    }
+
+    /// Ask peers for data, sending gets made close together as one message
+    ///
+    /// The first get opens a `get_gap`-millisecond window; gets made during it
+    /// are sent with it as an array (identical ones once) and every caller
+    /// receives the combined response, which holds whichever of the requested
+    /// nodes were found.
+    ///
+    /// A get for a soul (and key) that is already on its way to peers isn't
+    /// sent again: the caller waits for the same response. If no response
+    /// arrives within `lack` ms, or it doesn't hold the soul, the receiver is
+    /// closed without a value and the soul counts as missing for `miss_ttl`
+    /// ms, during which gets for it are closed at once without asking peers.
+    ///
+    /// # Arguments
+    /// * `get` - A get object, e.g. `{"#": soul}` or `{"#": soul, ".": key}`
+    ///
+    /// # Returns
+    /// A receiver for the response.
+    pub async fn ask_get(&self, get: Value) -> GunResult<oneshot::Receiver<Value>> {
+        let (tx, rx) = oneshot::channel();
+        let tx = match get_key(&get) {
+            Some(key) => {
+                if self.known_missing(&key) {
+                    MeshMetrics::add(&self.metrics.gets_known_missing, 1);
+                    return Ok(rx);
+                }
+                let mut inflight = self.inflight.lock();
+                if let Some(waiters) = inflight.get_mut(&key) {
+                    waiters.push(tx);
+                    MeshMetrics::add(&self.metrics.gets_coalesced, 1);
+                    return Ok(rx);
+                }
+                inflight.insert(key, vec![tx]);
+                None
+            }
+            None => Some(tx),
+        };
+        let first = {
+            let mut batch = self.get_batch.lock();
+            batch.push(QueuedGet { get, tx });
+            batch.len() == 1
+        };
+        if !first {
+            return Ok(rx);
+        }
+
+        if self.opt.get_gap > 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(self.opt.get_gap)).await;
+        }
+        let batch = std::mem::take(&mut *self.get_batch.lock());
+        let mut unique: Vec<Value> = Vec::new();
+        let mut waiters = Vec::with_capacity(batch.len());
+        let mut keys = Vec::new();
+        for queued in batch {
+            keys.extend(get_key(&queued.get));
+            if !unique.contains(&queued.get) {
+                unique.push(queued.get);
+            }
+            waiters.extend(queued.tx);
+        }
+        let get = if unique.len() == 1 {
+            unique.remove(0)
+        } else {
+            Value::Array(unique)
+        };
+
+        let reply = match self.ask(&serde_json::json!({ "get": get })).await {
+            Ok((_, reply)) => reply,
+            Err(e) => {
+                let mut inflight = self.inflight.lock();
+                for key in &keys {
+                    inflight.remove(key);
+                }
+                return Err(e);
+            }
+        };
+        MeshMetrics::add(&self.metrics.gets_sent, 1);
+
+        let inflight = self.inflight.clone();
+        let missing = self.missing.clone();
+        let lack = std::time::Duration::from_millis(self.opt.lack);
+        let miss_ttl = std::time::Duration::from_millis(self.opt.miss_ttl);
+        self.core.spawn_until_cancelled(async move {
+            let response = tokio::time::timeout(lack, reply).await.ok().and_then(Result::ok);
+            for key in keys {
+                waiters.extend(inflight.lock().remove(&key).unwrap_or_default());
+                let found = response.as_ref().and_then(|r| r.get("put")).and_then(|put| put.get(&key.0)).is_some();
+                if !found && !miss_ttl.is_zero() {
+                    missing.lock().insert(key, std::time::Instant::now() + miss_ttl);
+                }
+            }
+            if let Some(response) = response {
+                for waiter in waiters {
+                    let _ = waiter.send(response.clone());
+                }
+            }
+        });
+        Ok(rx)
+    }
+
+    /// Whether a get went unanswered less than `miss_ttl` ago
+    fn known_missing(&self, key: &GetKey) -> bool {
+        let now = std::time::Instant::now();
+        let mut missing = self.missing.lock();
+        missing.retain(|_, until| *until > now);
+        missing.contains_key(key)
+    }
+
+    /// Count a message we originate in the stats
+    fn count_said(&self, msg: &Value) {
+        MeshMetrics::add(&self.metrics.messages_said, 1);
+        if msg.get("put").is_some() {
+            MeshMetrics::add(&self.metrics.puts_sent, 1);
+        }
+    }
+
+    /// Add the message ID (if missing) and our signature, and serialize it
+    ///
+    /// In unsigned mode (signatures not required from anyone) the `sigs` field
+    /// is left out, as stock Gun.js peers expect.
+    fn seal(&self, msg: &Value) -> GunResult<Sealed> {
+        let (msg, priority) = self.sealed_value(msg)?;
+        Ok(Sealed {
+            id: msg["#"].as_str().unwrap_or_default().to_string(),
+            frame: serde_json::to_string(&msg)?.into(),
+            priority,
+        })
+    }
+
+    /// [`seal`](Self::seal), with the proof of work `pow_bits` asks for
+    ///
+    /// The nonce is searched for on the blocking pool, off the async threads.
+    async fn seal_worked(&self, msg: &Value) -> GunResult<Sealed> {
+        let Some(bits) = self.pow_bits.filter(|_| !stays_on_link(msg)) else {
+            let sealed = self.seal(msg)?;
+            self.track_said(msg, &sealed.id).await;
+            return Ok(sealed);
+        };
+        let (mut msg, priority) = self.sealed_value(msg)?;
+        let id = msg["#"].as_str().unwrap_or_default().to_string();
+        let nonce = {
+            let id = id.clone();
+            tokio::task::spawn_blocking(move || pow_nonce(&id, bits))
+                .await
+                .map_err(|e| crate::error::GunError::Network(format!("Proof of work failed: {}", e)))?
+                .ok_or_else(|| crate::error::GunError::Network(format!("No proof of work of {} bits found", bits)))?
+        };
+        msg["work"] = nonce.into();
+        self.track_said(&msg, &id).await;
+        Ok(Sealed {
+            id,
+            frame: serde_json::to_string(&msg)?.into(),
+            priority,
+        })
+    }
+
+    /// Remember one of our messages as seen, from no peer
+    ///
+    /// Replies to it then end here rather than being forwarded, see `forward`.
+    async fn track_said(&self, msg: &Value, msg_id: &str) {
+        if !stays_on_link(msg) {
+            self.dup.write().await.track(msg_id);
+        }
+    }
+
+    /// The message [`seal`](Self::seal) serializes, and whether it goes on
+    /// the priority lane
+    fn sealed_value(&self, msg: &Value) -> GunResult<(Value, bool)> {
+        let mut msg = msg.clone();
+        let unsigned = !self.require_signatures && self.signed_peers.is_empty();
+        let priority = is_priority(&msg);
+        let fields = msg
+            .as_object_mut()
+            .ok_or_else(|| crate::error::GunError::InvalidData("Message is not an object".to_string()))?;
+
+        // Messages we sign first name us as their origin, see `with_peer_allowlist`
+        if !unsigned && !fields.contains_key("sigs") && !fields.contains_key("from") {
+            fields.insert("from".to_string(), hex::encode(self.public_key.to_bytes()).into());
+        }
+
+        // Create message bytes for hashing and signing (without sigs, ttl and work)
+        let sigs = fields.remove("sigs");
+        let ttl = fields.remove("ttl");
+        let work = fields.remove("work");
+        let msg_bytes = serde_json::to_vec(fields)?;
+        if let Some(sigs) = sigs {
+            fields.insert("sigs".to_string(), sigs);
+        }
+        if let Some(work) = work {
+            fields.insert("work".to_string(), work);
+        }
+        match ttl {
+            Some(ttl) => {
+                fields.insert("ttl".to_string(), ttl);
+            }
+            None if !stays_on_link(&msg) => msg["ttl"] = self.opt.ttl.into(),
+            None => {}
+        }
+
+        // Generate message ID if not present - use SHA256 hash of message (without sigs)
+        if msg.get("#").is_none() {
+            let mut hasher = Sha256::new();
+            hasher.update(&msg_bytes);
+            let hash = hasher.finalize();
+            let hash_hex = hex::encode(hash);
+            msg["#"] = serde_json::Value::String(hash_hex);
+        }
+
+        if !unsigned {
+            self.sign_into(&mut msg, &msg_bytes)?;
+        }
+        Ok((msg, priority))
+    }
+
+    /// Add our signature to a message's `sigs`, unless it's already there
+    ///
+    /// Signatures are aggregated into one, next to the list of signers' keys.
+    /// With `legacy_sigs` set, a message without signatures or with the old
+    /// per-signer array gets an entry appended to the array instead; an
+    /// aggregate can't be split back up, so it stays an aggregate.
+    fn sign_into(&self, msg: &mut Value, msg_bytes: &[u8]) -> GunResult<()> {
+        let my_pubkey_hex = hex::encode(self.public_key.to_bytes());
+        let legacy = match msg.get("sigs") {
+            None => self.opt.legacy_sigs,
+            Some(Value::Array(_)) => self.opt.legacy_sigs,
+            Some(_) => false,
+        };
+
+        if legacy {
+            let entries = msg
+                .as_object_mut()
+                .ok_or_else(|| crate::error::GunError::InvalidData("Message is not an object".to_string()))?
+                .entry("sigs")
+                .or_insert_with(|| Value::Array(Vec::new()));
+            let Value::Array(entries) = entries else {
+                return Ok(());
+            };
+            let signed = entries
+                .iter()
+                .any(|entry| entry.get("pubkey").and_then(|v| v.as_str()) == Some(my_pubkey_hex.as_str()));
+            if !signed {
+                let signature = sign(&self.secret_key, msg_bytes);
+                entries.push(serde_json::json!({
+                    "sig": hex::encode(signature.to_bytes()),
+                    "pubkey": my_pubkey_hex
+                }));
+            }
+            return Ok(());
+        }
+
+        let (mut aggregate, mut pubkeys) = match msg.get("sigs") {
+            Some(sigs) => read_sigs(sigs).map_err(crate::error::GunError::InvalidData)?,
+            None => (Signature::default(), Vec::new()),
+        };
+        if pubkeys.contains(&self.public_key) {
+            return Ok(());
+        }
+        aggregate += &sign(&self.secret_key, msg_bytes);
+        pubkeys.push(self.public_key);
+        msg["sigs"] = serde_json::json!({
+            "sig": hex::encode(aggregate.to_bytes()),
+            "pubkeys": pubkeys.iter().map(|pk| hex::encode(pk.to_bytes())).collect::<Vec<_>>()
+        });
+        Ok(())
+    }
+
+    /// Send a sealed message to one peer, or broadcast it
+    async fn send(&self, sealed: &Sealed, peer: Option<&Peer>) -> GunResult<()> {
+        let (raw, priority) = (&sealed.frame, sealed.priority);
+
+        if let Some(p) = peer {
+            self.send_on_lane(raw, &p.id, priority).await?;
+        } else {
+            // Broadcast to all peers - clone IDs first to avoid holding lock during async calls
+            let peer_ids: Vec<String> = {
+                let peers = self.peers.read().await;
+                let ids: Vec<String> = peers.keys().cloned().collect();
+                tracing::trace!(peers = ?ids, "Broadcasting message");
+                ids
+            };
+
+            // Now send to each peer without holding the lock
+            for peer_id in peer_ids {
+                tracing::trace!(peer_id = %peer_id, "Sending broadcast message");
+                if let Err(e) = self.send_on_lane(raw, &peer_id, priority).await {
+                    tracing::warn!(peer_id = %peer_id, error = %e, "Error sending to peer");
+                    // Continue sending to other peers even if one fails
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Send raw message to a specific peer by ID, on the bulk lane
+    /// Routes through WebSocket connection if available, otherwise queues
+    pub(crate) async fn send_to_peer_by_id(&self, raw: &str, peer_id: &str) -> GunResult<()> {
+        self.send_on_lane(&Frame::from(raw), peer_id, false).await
+    }
+
+    /// Send raw message to a specific peer by ID, on the priority lane if `priority`
+    ///
+    /// A peer whose pid has an alternate transport gets the frame over that
+    /// first, whole, and over its WebSocket connection only if that fails.
+    async fn send_on_lane(&self, raw: &Frame, peer_id: &str, priority: bool) -> GunResult<()> {
+        // Try to get the sender without holding the lock for long
+        let (tx_opt, pid) = {
+            let peers = self.peers.read().await;
+            if let Some(peer) = peers.get(peer_id) {
+                (peer.tx.clone(), peer.pid.clone()) // Clone the Sender to release the lock immediately
+            } else {
+                tracing::trace!(peer_id = %peer_id, peers = peers.len(), "Peer not found in peers list");
+                (None, None) // Peer not found
+            }
+        };
+
+        let transport = pid.and_then(|pid| {
+            let transports = self.transports.lock();
+            let transport = transports.get(&pid)?;
+            Some((transport.send.clone(), transport.peer_id.as_deref() == Some(peer_id)))
+        });
+        if let Some((transport, own_peer)) = transport {
+            match transport(raw.clone()).await {
+                Ok(()) => {
+                    MeshMetrics::add(&self.metrics.bytes_out, raw.len());
+                    MeshMetrics::add(&self.metrics.transport_sent, 1);
+                    return Ok(());
+                }
+                // A peer added for the transport has nothing to fall back on
+                Err(e) if own_peer => return Err(e),
+                Err(e) => {
+                    tracing::debug!(peer_id = %peer_id, error = %e, "Alternate transport failed, using WebSocket");
+                    MeshMetrics::add(&self.metrics.transport_fallbacks, 1);
+                }
+            }
+        }
+
+        if let Some(tx) = tx_opt {
+            if let Some(chunks) = self.chunk(raw) {
+                for chunk in chunks {
+                    self.send_frame(&chunk.into(), peer_id, tx.clone(), priority).await?;
+                }
+                return Ok(());
+            }
+            return self.send_frame(raw, peer_id, tx, priority).await;
+        }
+
+        // No sender available - queue the message
+        // Use a short write lock to add to queue
+        {
+            let mut peers = self.peers.write().await;
+            if let Some(peer) = peers.get_mut(peer_id) {
+                peer.queue.push(raw.to_string());
+                // Don't warn - this is expected during initial connection
+                if self.opt.queue_max > 0 && peer.queue.len() > self.opt.queue_max {
+                    if self.opt.queue_policy == QueuePolicy::CollapsePuts {
+                        peer.queue = self.coalesce_queue(std::mem::take(&mut peer.queue));
+                    }
+                    let excess = peer.queue.len().saturating_sub(self.opt.queue_max);
+                    peer.queue.drain(..excess);
+                    MeshMetrics::add(&self.metrics.queue_dropped, excess);
+                }
+            } else {
+                // Peer doesn't exist - this is fine, they'll get it when they connect
+                return Ok(());
             }
         } // Lock released here
 
         Ok(())
     }
 
+    /// Hand a frame to a connected peer, batched if `gap` is set
+    async fn send_frame(&self, raw: &Frame, peer_id: &str, tx: PeerSender, priority: bool) -> GunResult<()> {
+        if self.opt.gap > 0 {
+            return self.send_batched(raw, peer_id, tx, priority).await;
+        }
+
+        // Send immediately through WebSocket (no lock held)
+        tracing::trace!(peer_id = %peer_id, priority, raw = %raw.chars().take(150).collect::<String>(), "Sending message");
+        MeshMetrics::add(&self.metrics.bytes_out, raw.len());
+        let sent = if priority {
+            tx.send_priority(raw.clone())
+        } else {
+            tx.send(raw.clone())
+        };
+        sent.map_err(|e| {
+            tracing::debug!(peer_id = %peer_id, error = %e, "WebSocket send error");
+            crate::error::GunError::Network(format!(
+                "Failed to send to peer {}: {}",
+                peer_id, e
+            ))
+        })?;
+        Ok(())
+    }
+
+    /// Split a message larger than `chunk_size` into chunk messages
+    ///
+    /// Returns `None` for messages that fit. Pieces end on character
+    /// boundaries, so each is at most `chunk_size` bytes of the message text.
+    fn chunk(&self, raw: &str) -> Option<Vec<String>> {
+        if self.opt.chunk_size == 0 || raw.len() <= self.opt.chunk_size {
+            return None;
+        }
+        let mut pieces = Vec::with_capacity(raw.len() / self.opt.chunk_size + 1);
+        let mut rest = raw;
+        while !rest.is_empty() {
+            let mut end = self.opt.chunk_size.min(rest.len());
+            while !rest.is_char_boundary(end) {
+                end -= 1;
+            }
+            // A chunk_size below one character's width still has to make progress
+            if end == 0 {
+                end = rest.chars().next().map_or(rest.len(), char::len_utf8);
+            }
+            let (piece, tail) = rest.split_at(end);
+            pieces.push(piece);
+            rest = tail;
+        }
+
+        MeshMetrics::add(&self.metrics.chunked_sent, 1);
+        let id = self.core.random_id(12);
+        let of = pieces.len();
+        Some(
+            pieces
+                .into_iter()
+                .enumerate()
+                .map(|(seq, data)| {
+                    serde_json::json!({ "dam": "chunk", "id": id, "seq": seq, "of": of, "data": data })
+                        .to_string()
+                })
+                .collect(),
+        )
+    }
+
+    /// Take in one chunk of a chunked message
+    ///
+    /// Returns the whole message once its last missing chunk has arrived. The
+    /// first chunk of a message starts a `chunk_timeout` timer that drops it
+    /// if it's still incomplete by then.
+    async fn hear_chunk(&self, msg: &Value, peer: Option<&Peer>) -> GunResult<Option<Value>> {
+        let (Some(id), Some(seq), Some(of), Some(data)) = (
+            msg.get("id").and_then(|v| v.as_str()),
+            msg.get("seq").and_then(|v| v.as_u64()),
+            msg.get("of").and_then(|v| v.as_u64()),
+            msg.get("data").and_then(|v| v.as_str()),
+        ) else {
+            tracing::debug!(peer_id = ?peer.map(|p| &p.id), "Malformed chunk");
+            return Ok(None);
+        };
+        if seq >= of {
+            tracing::debug!(peer_id = ?peer.map(|p| &p.id), chunk_id = %id, seq, of, "Chunk out of range");
+            return Ok(None);
+        }
+
+        let from = peer.map(|p| p.id.as_str()).unwrap_or_default();
+        match self.add_chunk(from, id, seq, of, data) {
+            Ok(Some(raw)) => {
+                MeshMetrics::add(&self.metrics.chunked_received, 1);
+                match serde_json::from_str(&raw) {
+                    Ok(msg) => Ok(Some(msg)),
+                    Err(e) => {
+                        self.invalid(peer, &format!("Invalid JSON in chunked message: {}", e)).await?;
+                        Ok(None)
+                    }
+                }
+            }
+            Ok(None) => Ok(None),
+            Err(kind) => {
+                tracing::debug!(peer_id = ?peer.map(|p| &p.id), chunk_id = %id, "Chunked messages too big");
+                self.misbehaved(peer, kind).await?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Store a chunk; returns the message text once every chunk is in
+    ///
+    /// Fails with [`Misbehavior::Oversized`], dropping everything the peer
+    /// has sent in chunks so far, if its chunks would exceed `max_message_size`.
+    fn add_chunk(&self, from: &str, id: &str, seq: u64, of: u64, data: &str) -> Result<Option<String>, Misbehavior> {
+        let mut chunks = self.chunks.lock();
+        let buffered: usize = chunks
+            .iter()
+            .filter(|((peer_id, _), _)| peer_id == from)
+            .map(|(_, assembly)| assembly.bytes)
+            .sum();
+        if buffered + data.len() > self.opt.max_message_size {
+            chunks.retain(|(peer_id, _), _| peer_id != from);
+            return Err(Misbehavior::Oversized);
+        }
+
+        let key = (from.to_string(), id.to_string());
+        let assembly = chunks.entry(key.clone()).or_insert_with(|| {
+            let started = std::time::Instant::now();
+            let timeout = std::time::Duration::from_millis(self.opt.chunk_timeout);
+            let (chunks, metrics, key) = (self.chunks.clone(), self.metrics.clone(), key.clone());
+            self.core.spawn_until_cancelled(async move {
+                tokio::time::sleep(timeout).await;
+                let mut chunks = chunks.lock();
+                if chunks.get(&key).is_some_and(|assembly| assembly.started == started) {
+                    let assembly = chunks.remove(&key);
+                    MeshMetrics::add(&metrics.chunked_expired, 1);
+                    tracing::debug!(
+                        peer_id = %key.0,
+                        chunk_id = %key.1,
+                        received = assembly.map_or(0, |a| a.parts.len()),
+                        "Chunked message timed out"
+                    );
+                }
+            });
+            Assembly { of, parts: BTreeMap::new(), bytes: 0, started }
+        });
+        if assembly.of != of {
+            tracing::debug!(peer_id = %from, chunk_id = %id, "Chunk count changed mid-message");
+            return Ok(None);
+        }
+        if let std::collections::btree_map::Entry::Vacant(part) = assembly.parts.entry(seq) {
+            assembly.bytes += data.len();
+            part.insert(data.to_string());
+        }
+        if assembly.parts.len() as u64 != of {
+            return Ok(None);
+        }
+        Ok(chunks.remove(&key).map(|assembly| assembly.parts.into_values().collect()))
+    }
+
+    /// Add a message to the peer's batch (matches the batching in mesh.say)
+    ///
+    /// Without an open batch the message is sent at once and a new batch window
+    /// starts; the window's timer flushes whatever was collected after `gap` ms.
+    /// A message that would push the batch past `pack_size` flushes it first.
+    /// Priority frames go out at once on their lane and never join a batch.
+    async fn send_batched(
+        &self,
+        raw: &Frame,
+        peer_id: &str,
+        tx: PeerSender,
+        priority: bool,
+    ) -> GunResult<()> {
+        let full_batch = {
+            let mut peers = self.peers.write().await;
+            let peer = match peers.get_mut(peer_id) {
+                Some(peer) => peer,
+                None => return Ok(()),
+            };
+            match peer.batch.as_mut() {
+                // Priority frames never wait in a batch, only open the window
+                Some(_) if priority => None,
+                Some(batch) if peer.tail + raw.len() <= self.opt.pack_size => {
+                    if peer.tail > 0 {
+                        batch.push(',');
+                    }
+                    batch.push_str(raw);
+                    peer.tail += raw.len();
+                    return Ok(());
+                }
+                _ => {
+                    let full_batch = Self::take_batch(peer);
+                    peer.batch = Some("[".to_string());
+                    peer.tail = 0;
+                    Some(full_batch)
+                }
+            }
+        };
+        let Some(full_batch) = full_batch else {
+            MeshMetrics::add(&self.metrics.bytes_out, raw.len());
+            return tx.send_priority(raw.clone()).map_err(|e| {
+                crate::error::GunError::Network(format!("Failed to send to peer {}: {}", peer_id, e))
+            });
+        };
+
+        let peers = self.peers.clone();
+        let metrics = self.metrics.clone();
+        let flush_peer_id = peer_id.to_string();
+        let gap = tokio::time::Duration::from_millis(self.opt.gap);
+        self.core.spawn(async move {
+            tokio::time::sleep(gap).await;
+            let batch = {
+                let mut peers = peers.write().await;
+                peers.get_mut(&flush_peer_id).and_then(|peer| {
+                    Self::take_batch(peer).map(|batch| (batch, peer.tx.clone()))
+                })
+            };
+            if let Some((batch, Some(tx))) = batch {
+                MeshMetrics::add(&metrics.bytes_out, batch.len());
+                if let Err(e) = tx.send(batch) {
+                    tracing::warn!(peer_id = %flush_peer_id, error = %e, "Error flushing message batch");
+                }
+            }
+        });
+
+        for frame in full_batch.into_iter() {
+            MeshMetrics::add(&self.metrics.bytes_out, frame.len());
+            tx.send(frame).map_err(|e| {
+                crate::error::GunError::Network(format!("Failed to send to peer {}: {}", peer_id, e))
+            })?;
+        }
+        MeshMetrics::add(&self.metrics.bytes_out, raw.len());
+        let sent = if priority {
+            tx.send_priority(raw.clone())
+        } else {
+            tx.send(raw.clone())
+        };
+        sent.map_err(|e| crate::error::GunError::Network(format!("Failed to send to peer {}: {}", peer_id, e)))
+    }
+
+    /// Drop duplicate messages from a queue and merge its puts into one
+    ///
+    /// Every key of the merged put keeps the value with the highest state
+    /// (the later one on a tie); it takes the place of the first put. Replies
+    /// and puts someone is waiting on an ack for are left alone.
+    fn coalesce_queue(&self, queue: Vec<String>) -> Vec<String> {
+        let mut kept: Vec<Option<String>> = Vec::with_capacity(queue.len());
+        let mut ids = HashSet::new();
+        let mut merged = serde_json::Map::new();
+        let mut puts = Vec::new();
+        let mut slot = None;
+        for raw in queue {
+            let Ok(msg) = serde_json::from_str::<Value>(&raw) else {
+                kept.push(Some(raw));
+                continue;
+            };
+            let id = msg.get("#").and_then(|v| v.as_str()).unwrap_or_default();
+            if !id.is_empty() && !ids.insert(id.to_string()) {
+                MeshMetrics::add(&self.metrics.queue_dropped, 1);
+                continue;
+            }
+            let put = msg.get("put").and_then(|v| v.as_object());
+            let mergeable = put.is_some_and(|put| !put.contains_key("#"))
+                && msg.get("@").is_none()
+                && !self.pending.lock().contains_key(id);
+            match put {
+                Some(put) if mergeable => {
+                    for (soul, node) in put {
+                        let target = merged
+                            .entry(soul.clone())
+                            .or_insert_with(|| serde_json::json!({ "_": { "#": soul, ">": {} } }));
+                        merge_wire_node(target, node);
+                    }
+                    slot.get_or_insert(kept.len());
+                    if puts.is_empty() {
+                        kept.push(None);
+                    }
+                    puts.push(raw);
+                }
+                _ => kept.push(Some(raw)),
+            }
+        }
+
+        if let Some(slot) = slot {
+            let merged = if puts.len() == 1 {
+                puts.pop()
+            } else if self.pow_bits.is_some() {
+                None // A merged put would need work of its own
+            } else {
+                self.seal(&serde_json::json!({ "put": merged }))
+                    .map(|sealed| sealed.frame.to_string())
+                    .ok()
+            };
+            match merged {
+                Some(merged) => {
+                    MeshMetrics::add(&self.metrics.queue_coalesced, puts.len().saturating_sub(1));
+                    kept[slot] = Some(merged);
+                }
+                // Couldn't seal the merged put, or it needs work; send the originals instead
+                None => {
+                    kept.splice(slot..=slot, puts.into_iter().map(Some));
+                }
+            }
+        }
+        kept.into_iter().flatten().collect()
+    }
+
+    /// Close the peer's batch, returning it as a JSON array frame if it holds anything
+    fn take_batch(peer: &mut Peer) -> Option<String> {
+        let batch = peer.batch.take()?;
+        let tail = std::mem::take(&mut peer.tail);
+        if tail == 0 {
+            return None;
+        }
+        Some(batch + "]")
+    }
+
     /// Send raw message to a specific peer (by Peer reference)
     /// Routes through WebSocket connection if available, otherwise queues
     #[allow(dead_code)] // Used internally for peer communication
@@ -738,36 +3156,74 @@ impl Mesh {
     }
 
     /// Update peer with WebSocket sender (called when connection is established)
+    ///
+    /// Takes a plain channel sender or the [`PeerSender`] of an [`Outbox`].
     pub async fn set_peer_sender(
         &self,
         peer_id: &str,
-        tx: mpsc::UnboundedSender<String>,
+        tx: impl Into<PeerSender>,
     ) -> GunResult<()> {
-        let mut peers = self.peers.write().await;
-        if let Some(peer) = peers.get_mut(peer_id) {
-            let tx_clone = tx.clone();
-            peer.set_sender(tx);
-            // Flush any queued messages
-            let queue = peer.queue.clone();
-            peer.queue.clear();
-            drop(peers); // Release lock as soon as possible
+        let tx = tx.into();
+        let queue = self.attach_sender(peer_id, tx.clone()).await?;
+        self.flush_queue(&tx, queue);
+        Ok(())
+    }
 
-            // Send queued messages (outside of lock to avoid deadlocks)
-            for msg in queue {
-                if let Err(e) = tx_clone.send(msg) {
-                    eprintln!("Error sending queued message: {}", e);
-                    break;
-                }
+    /// Give a peer its connection's sender; returns what was queued for it meanwhile
+    async fn attach_sender(&self, peer_id: &str, tx: PeerSender) -> GunResult<Vec<String>> {
+        let mut peers = self.peers.write().await;
+        match peers.get_mut(peer_id) {
+            Some(peer) => {
+                peer.set_sender(tx);
+                peer.last_seen = Some(std::time::Instant::now());
+                Ok(std::mem::take(&mut peer.queue))
             }
-        } else {
             // Peer not found - this shouldn't happen if hi() was called first
-            drop(peers);
-            return Err(crate::error::GunError::Network(format!(
+            None => Err(crate::error::GunError::Network(format!(
                 "Peer {} not found in mesh, call hi() first",
                 peer_id
-            )));
+            ))),
+        }
+    }
+
+    /// Send the messages queued for a peer while it had no connection, in order
+    fn flush_queue(&self, tx: &PeerSender, queue: Vec<String>) {
+        let queue = self.coalesce_queue(queue);
+        let frames = queue
+            .into_iter()
+            .flat_map(|msg| self.chunk(&msg).unwrap_or_else(|| vec![msg]));
+        for msg in frames {
+            MeshMetrics::add(&self.metrics.bytes_out, msg.len());
+            if let Err(e) = tx.send(msg) {
+                tracing::warn!(error = %e, "Error sending queued message");
+                break;
+            }
+        }
+    }
+
+    /// Ask a reconnected peer again for the gets still waiting on a reply
+    ///
+    /// A get sent just before the connection dropped may never have arrived;
+    /// sending it again, with the same ID, lets the caller's reply come from
+    /// the new connection instead of timing out. Peers that did see it drop
+    /// the copy as a duplicate.
+    async fn retry_pending_gets(&self, peer: &Peer) {
+        let lack = std::time::Duration::from_millis(self.opt.lack);
+        let gets: Vec<Frame> = self
+            .pending
+            .lock()
+            .values()
+            .filter(|request| !request.tx.is_closed() && request.sent.elapsed() < lack)
+            .filter_map(|request| request.get.clone())
+            .collect();
+        if !gets.is_empty() {
+            tracing::debug!(peer_id = %peer.id, gets = gets.len(), "Asking reconnected peer again for pending gets");
+        }
+        for get in gets {
+            if let Err(e) = self.send_on_lane(&get, &peer.id, true).await {
+                tracing::warn!(peer_id = %peer.id, error = %e, "Failed to resend pending get");
+            }
         }
-        Ok(())
     }
 
     /// Add a peer (matches mesh.hi)
@@ -775,6 +3231,8 @@ impl Mesh {
         let mut peers = self.peers.write().await;
         let was_new = !peers.contains_key(&peer.id);
         let peer_id = peer.id.clone();
+        let mut peer = peer;
+        peer.last_seen = Some(std::time::Instant::now());
         peers.insert(peer_id.clone(), peer.clone());
         drop(peers);
 
@@ -787,31 +3245,605 @@ impl Mesh {
             // We do this after releasing the lock to avoid deadlocks
             // The "hi" message contains our peer ID (pid) for DAM protocol
             // Use say() to ensure the message is signed
-            let hi_message = serde_json::json!({
-                "dam": "?",
-                "pid": self.pid,
-            });
+            let hi_message = self.hi_message();
             
             // Send "hi" message using say() which will sign it
             if let Err(e) = self.say(&hi_message, Some(&peer)).await {
-                tracing::warn!("Failed to send hi message to peer {}: {}", peer_id, e);
+                tracing::warn!(peer_id = %peer_id, error = %e, "Failed to send hi message");
+            }
+            self.resubscribe(&peer).await;
+            self.announce_rooms(&peer).await;
+
+            if self.opt.pex.enabled {
+                self.share_addresses(&peer).await;
             }
         }
         Ok(())
     }
 
+    /// Ask a peer for every soul with live `on()` listeners, in one get
+    ///
+    /// Sent after `hi` and on reconnect: the peer answers with the current
+    /// state of the souls it has, so subscribers catch up on what changed
+    /// while we were apart without re-issuing their gets.
+    async fn resubscribe(&self, peer: &Peer) {
+        let souls = self.core.subscriptions();
+        if souls.is_empty() {
+            return;
+        }
+        tracing::debug!(peer_id = %peer.id, souls = souls.len(), "Asking peer for subscribed souls");
+        if let Err(e) = self.say(&serde_json::json!({ "get": { "#": souls } }), Some(peer)).await {
+            tracing::warn!(peer_id = %peer.id, error = %e, "Failed to send subscriptions");
+        }
+    }
+
     /// Remove a peer (matches mesh.bye)
+    ///
+    /// Anything still batched for the peer is sent first.
     pub async fn bye(&self, peer_id: &str) -> GunResult<()> {
+        self.drop_peer(peer_id, "disconnected").await;
+        Ok(())
+    }
+
+    /// Remove a peer, announcing it offline with `state` if it was online
+    async fn drop_peer(&self, peer_id: &str, state: &str) {
+        self.scores.lock().remove(peer_id);
+        self.pids.lock().retain(|_, id| id != peer_id);
         let mut peers = self.peers.write().await;
-        if peers.remove(peer_id).is_some() {
-            let mut near = self.near.write().await;
-            if *near > 0 {
-                *near -= 1;
+        let Some(mut peer) = peers.remove(peer_id) else {
+            return;
+        };
+        if let (Some(batch), Some(tx)) = (Self::take_batch(&mut peer), &peer.tx) {
+            MeshMetrics::add(&self.metrics.bytes_out, batch.len());
+            let _ = tx.send(batch);
+        }
+        drop(peers);
+        let mut near = self.near.write().await;
+        if *near > 0 {
+            *near -= 1;
+        }
+        drop(near);
+        if self.online.lock().remove(peer_id) {
+            self.emit_lifecycle(peer_id, &peer.url, peer.pid.as_deref(), serde_json::json!({ "state": state }));
+        }
+    }
+
+    /// Emit a [`PEER_LIFECYCLE_EVENT`], `data` giving the state
+    fn emit_lifecycle(&self, peer_id: &str, url: &str, pid: Option<&str>, mut data: Value) {
+        data["peer"] = Value::from(peer_id);
+        data["url"] = Value::from(url);
+        data["pid"] = pid.map(Value::from).unwrap_or_default();
+        self.core.events.emit(&crate::events::Event {
+            event_type: PEER_LIFECYCLE_EVENT.to_string(),
+            data,
+        });
+    }
+
+    /// Note that a peer is still there, e.g. on a transport-level keepalive
+    pub async fn touch(&self, peer_id: &str) {
+        if let Some(peer) = self.peers.write().await.get_mut(peer_id) {
+            peer.last_seen = Some(std::time::Instant::now());
+        }
+    }
+
+    /// Note the round trip of a keepalive ping the peer answered
+    pub async fn record_rtt(&self, peer_id: &str, rtt: std::time::Duration) {
+        if let Some(peer) = self.peers.write().await.get_mut(peer_id) {
+            peer.last_seen = Some(std::time::Instant::now());
+            peer.rtt = Some(rtt);
+        }
+        tracing::trace!(peer_id = %peer_id, rtt = ?rtt, "Pong");
+    }
+
+    /// Drop connected peers we haven't heard from for `lack` ms
+    ///
+    /// Each is removed like with [`bye`](Self::bye), which closes its
+    /// connection, but announced offline as timed out, and a
+    /// [`PEER_TIMEOUT_EVENT`] is emitted. Disconnected peers are left to
+    /// their reconnect attempts. Does nothing when `lack` is `0`.
+    ///
+    /// # Returns
+    /// The IDs of the peers removed.
+    pub async fn prune_idle(&self) -> GunResult<Vec<String>> {
+        if self.opt.lack == 0 {
+            return Ok(Vec::new());
+        }
+        let lack = std::time::Duration::from_millis(self.opt.lack);
+        let idle: Vec<(String, String, u128)> = {
+            let peers = self.peers.read().await;
+            peers
+                .values()
+                .filter(|p| p.tx.is_some())
+                .filter_map(|p| {
+                    let elapsed = p.last_seen?.elapsed();
+                    (elapsed > lack).then(|| (p.id.clone(), p.url.clone(), elapsed.as_millis()))
+                })
+                .collect()
+        };
+
+        let mut pruned = Vec::with_capacity(idle.len());
+        for (peer_id, url, idle_ms) in idle {
+            tracing::info!(peer_id = %peer_id, url = %url, idle_ms = idle_ms as u64, "Dropping idle peer");
+            self.drop_peer(&peer_id, "timeout").await;
+            self.core.events.emit(&crate::events::Event {
+                event_type: PEER_TIMEOUT_EVENT.to_string(),
+                data: serde_json::json!({
+                    "peer": peer_id,
+                    "url": url,
+                    "idle_ms": idle_ms as u64,
+                }),
+            });
+            pruned.push(peer_id);
+        }
+        Ok(pruned)
+    }
+
+    /// Run [`prune_idle`](Self::prune_idle) every `lack / 2` ms until shutdown
+    ///
+    /// The task only holds a weak reference, so it ends when the mesh is
+    /// dropped, or the core is cancelled. Returns `None` when `lack` is `0`.
+    pub fn start_pruning(mesh: &Arc<Mesh>) -> Option<tokio::task::JoinHandle<()>> {
+        if mesh.opt.lack == 0 {
+            return None;
+        }
+        let period = std::time::Duration::from_millis((mesh.opt.lack / 2).max(1));
+        let (core, mesh) = (mesh.core.clone(), Arc::downgrade(mesh));
+        Some(core.spawn_until_cancelled(async move {
+            let mut ticker = tokio::time::interval(period);
+            ticker.tick().await; // first tick completes immediately
+            loop {
+                ticker.tick().await;
+                let mesh = match mesh.upgrade() {
+                    Some(mesh) if !mesh.is_shut_down() => mesh,
+                    _ => break,
+                };
+                if let Err(e) = mesh.prune_idle().await {
+                    tracing::warn!(error = %e, "Error pruning idle peers");
+                }
             }
+        }))
+    }
+
+    /// Mark a peer's connection as lost without forgetting the peer
+    ///
+    /// Messages for the peer are queued until [`reconnect`](Self::reconnect)
+    /// gives it a new connection. A peer that already left (e.g. after saying
+    /// `bye`) is added back, disconnected.
+    pub async fn disconnect(&self, peer: &Peer) {
+        let mut peers = self.peers.write().await;
+        match peers.get_mut(&peer.id) {
+            Some(existing) => {
+                existing.tx = None;
+                existing.batch = None;
+                existing.tail = 0;
+                let pid = existing.pid.clone();
+                drop(peers);
+                if self.online.lock().remove(&peer.id) {
+                    self.emit_lifecycle(&peer.id, &peer.url, pid.as_deref(), serde_json::json!({ "state": "disconnected" }));
+                }
+            }
+            None => {
+                let mut peer = peer.clone();
+                peer.tx = None;
+                peer.batch = None;
+                peer.tail = 0;
+                peers.insert(peer.id.clone(), peer);
+                drop(peers);
+                *self.near.write().await += 1;
+            }
+        }
+    }
+
+    /// Record a reconnect attempt: `remaining` attempts left, tried now
+    pub async fn record_retry(&self, peer_id: &str, remaining: i32) {
+        let mut peers = self.peers.write().await;
+        if let Some(peer) = peers.get_mut(peer_id) {
+            peer.retry = remaining;
+            peer.tried = Some(chrono::Utc::now().timestamp_millis() as u64);
+        }
+    }
+
+    /// Attach a new connection to a peer that was [`disconnect`](Self::disconnect)ed
+    ///
+    /// Repeats the DAM `?` exchange, then sends the messages queued for the
+    /// peer in order, asks again for subscribed souls and for gets still
+    /// waiting on a reply, and emits [`PEER_RECONNECTED_EVENT`] and a
+    /// `"reconnected"` [`PEER_LIFECYCLE_EVENT`].
+    ///
+    /// # Arguments
+    /// * `peer_id` - The peer whose connection came back
+    /// * `tx` - Sender for the new connection
+    /// * `attempts` - Attempts it took, reported in the event
+    ///
+    /// # Errors
+    /// Returns `GunError::Network` if the peer is no longer in the mesh.
+    pub async fn reconnect(
+        &self,
+        peer_id: &str,
+        tx: impl Into<PeerSender>,
+        attempts: u32,
+    ) -> GunResult<()> {
+        let tx = tx.into();
+        let queue = self.attach_sender(peer_id, tx.clone()).await?;
+        let peer = match self.get_peer(peer_id).await {
+            Some(peer) => peer,
+            None => return Ok(()),
+        };
+
+        let hi_message = self.hi_message();
+        if let Err(e) = self.say(&hi_message, Some(&peer)).await {
+            tracing::warn!(peer_id = %peer_id, error = %e, "Failed to send hi message");
+        }
+        self.flush_queue(&tx, queue);
+        self.resubscribe(&peer).await;
+        self.retry_pending_gets(&peer).await;
+        self.announce_rooms(&peer).await;
+
+        self.core.events.emit(&crate::events::Event {
+            event_type: PEER_RECONNECTED_EVENT.to_string(),
+            data: serde_json::json!({
+                "peer": peer_id,
+                "url": peer.url,
+                "attempts": attempts,
+            }),
+        });
+        self.online.lock().insert(peer_id.to_string());
+        self.emit_lifecycle(
+            peer_id,
+            &peer.url,
+            peer.pid.as_deref(),
+            serde_json::json!({ "state": "reconnected", "attempts": attempts }),
+        );
+        Ok(())
+    }
+
+    /// Say goodbye to every peer and drop them (matches Gun.js `bye` on shutdown)
+    ///
+    /// Broadcasts a `dam: "bye"` message so peers can forget us immediately, then
+    /// removes all peers. Dropping their senders ends the connections, which send
+    /// a close frame on the way out, and stops any reconnect attempts.
+    pub async fn shutdown(&self) {
+        self.closing.send_replace(true);
+        if let Err(e) = self.say(&serde_json::json!({ "dam": "bye" }), None).await {
+            tracing::warn!(error = %e, "Error sending bye to peers");
+        }
+
+        let peer_ids: Vec<String> = self.peers.read().await.keys().cloned().collect();
+        for peer_id in peer_ids {
+            let _ = self.bye(&peer_id).await;
+        }
+    }
+
+    /// Whether [`shutdown`](Self::shutdown) has been called
+    pub fn is_shut_down(&self) -> bool {
+        *self.closing.borrow()
+    }
+
+    /// Resolves once [`shutdown`](Self::shutdown) has been called
+    pub async fn closed(&self) {
+        let mut closing = self.closing.subscribe();
+        let _ = closing.wait_for(|closed| *closed).await;
+    }
+
+    /// Count a bad message against the peer that sent it
+    ///
+    /// Once the peer's score reaches `max_score` it is told why with a
+    /// `dam: "!"` error, removed with [`bye`](Self::bye) (which closes its
+    /// connection), its host is banned for `ban_time` ms, and
+    /// [`PEER_BANNED_EVENT`] is emitted. The message itself is dropped either way.
+    async fn misbehaved(&self, peer: Option<&Peer>, kind: Misbehavior) -> GunResult<()> {
+        match kind {
+            Misbehavior::InvalidHash => MeshMetrics::add(&self.metrics.hash_failures, 1),
+            Misbehavior::InvalidSignature => MeshMetrics::add(&self.metrics.signature_failures, 1),
+            Misbehavior::Rejected | Misbehavior::Oversized => {}
+        }
+        let Some(p) = peer else {
+            return Ok(());
+        };
+        let score = {
+            let mut scores = self.scores.lock();
+            let score = scores.entry(p.id.clone()).or_default();
+            score.record(kind);
+            score.clone()
+        };
+        if self.opt.max_score == 0 || score.score() < self.opt.max_score {
+            return Ok(());
+        }
+
+        self.scores.lock().remove(&p.id);
+        let url = match self.get_peer(&p.id).await {
+            Some(known) => known.url,
+            None => p.url.clone(),
+        };
+        tracing::warn!(peer_id = %p.id, url = %url, score = score.score(), "Disconnecting misbehaving peer");
+        let _ = self
+            .say(
+                &serde_json::json!({
+                    "dam": "!",
+                    "err": "Too many invalid messages"
+                }),
+                Some(p),
+            )
+            .await;
+        self.bye(&p.id).await?;
+
+        if self.opt.ban_time > 0 {
+            let until = std::time::Instant::now() + std::time::Duration::from_millis(self.opt.ban_time);
+            self.banned.lock().insert(peer_host(&url), until);
         }
+
+        self.core.events.emit(&crate::events::Event {
+            event_type: PEER_BANNED_EVENT.to_string(),
+            data: serde_json::json!({
+                "peer": p.id,
+                "url": url,
+                "score": score.score(),
+                "invalid_hash": score.invalid_hash,
+                "invalid_sig": score.invalid_sig,
+                "rejected": score.rejected,
+                "oversized": score.oversized,
+                "ban_ms": self.opt.ban_time,
+            }),
+        });
         Ok(())
     }
 
+    /// Traffic counters since the mesh was created
+    ///
+    /// The counters are read without locking; `queued`, `lanes`, `rtt` and
+    /// `encodings` need the peer table and are left empty if it's busy at that
+    /// moment, as `dedup` is if the deduplication table is.
+    pub fn stats(&self) -> MeshStats {
+        let (queued, lanes, rtt, encodings) = match self.peers.try_read() {
+            Ok(peers) => (
+                peers
+                    .values()
+                    .filter(|peer| !peer.queue.is_empty())
+                    .map(|peer| (peer.id.clone(), peer.queue.len()))
+                    .collect(),
+                peers
+                    .values()
+                    .filter_map(|peer| {
+                        let (priority, bulk) = peer.tx.as_ref()?.depths()?;
+                        Some((peer.id.clone(), LaneStats { priority, bulk }))
+                    })
+                    .collect(),
+                peers
+                    .values()
+                    .filter_map(|peer| Some((peer.id.clone(), peer.rtt?)))
+                    .collect(),
+                peers
+                    .values()
+                    .filter(|peer| peer.tx.is_some())
+                    .map(|peer| (peer.id.clone(), peer.encoding))
+                    .collect(),
+            ),
+            Err(_) => (HashMap::new(), HashMap::new(), HashMap::new(), HashMap::new()),
+        };
+        let dedup = self.dup.try_read().map(|dup| dup.stats()).unwrap_or_default();
+        self.metrics.snapshot(queued, lanes, rtt, encodings, self.online.lock().len(), dedup)
+    }
+
+    /// Bad-message counters of connected peers that sent any, by peer ID
+    pub fn peer_scores(&self) -> HashMap<String, PeerScore> {
+        self.scores.lock().clone()
+    }
+
+    /// Whether the host of `url` is banned for misbehaving
+    ///
+    /// Servers refuse connections from banned hosts, and clients stop
+    /// reconnecting to them.
+    pub fn is_banned(&self, url: &str) -> bool {
+        let host = peer_host(url);
+        let mut banned = self.banned.lock();
+        match banned.get(&host) {
+            Some(until) if *until > std::time::Instant::now() => true,
+            Some(_) => {
+                banned.remove(&host);
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Record the outcome of dialing a peer URL, for peer exchange
+    ///
+    /// Successfully dialed URLs are what [`hi`](Self::hi) shares with new
+    /// peers; failures count towards `PexOptions::max_failures`. Does nothing
+    /// while peer exchange is off.
+    pub fn record_dial(&self, url: &str, ok: bool) {
+        if !self.opt.pex.enabled {
+            return;
+        }
+        let now = std::time::Instant::now();
+        let mut book = self.addresses.lock();
+        self.expire_addresses(&mut book, now);
+        let entry = book.entry(ws_url(url)).or_default();
+        if ok {
+            entry.last_ok = Some(now);
+            entry.failures = 0;
+        } else {
+            entry.failures += 1;
+        }
+    }
+
+    /// Note how connecting to a peer URL went, see [`peer_status`](Self::peer_status)
+    pub fn set_peer_status(&self, url: &str, status: PeerStatus) {
+        self.dials.lock().insert(ws_url(url), status);
+    }
+
+    /// How connecting to each dialed peer URL went, by URL
+    ///
+    /// Covers URLs dialed with `WebSocketClient::connect`: the configured
+    /// peers and addresses from peer exchange, not incoming connections.
+    pub fn peer_status(&self) -> HashMap<String, PeerStatus> {
+        self.dials.lock().clone()
+    }
+
+    /// Whether failover keeps this URL in reserve, see [`PeerStatus::Standby`]
+    pub fn is_standby(&self, url: &str) -> bool {
+        self.dials.lock().get(&ws_url(url)) == Some(&PeerStatus::Standby)
+    }
+
+    /// Say `bye` to a peer and drop it for good
+    ///
+    /// Unlike a lost connection, a peer removed this way isn't reconnected:
+    /// its URL leaves [`peer_status`](Self::peer_status) and
+    /// `WebSocketClient::connect` refuses it until it is
+    /// [`readmit`](Self::readmit)ted. Returns whether the peer was in the mesh.
+    pub async fn remove_peer(&self, peer_id: &str) -> GunResult<bool> {
+        let Some(peer) = self.get_peer(peer_id).await else {
+            return Ok(false);
+        };
+        self.forget_url(&peer.url);
+        if peer.tx.is_some() {
+            if let Err(e) = self.say(&serde_json::json!({ "dam": "bye" }), Some(&peer)).await {
+                tracing::debug!(peer_id = %peer_id, error = %e, "Error sending bye to peer");
+            }
+        }
+        self.bye(peer_id).await?;
+        Ok(true)
+    }
+
+    /// Stop dialing a peer URL, see [`remove_peer`](Self::remove_peer)
+    ///
+    /// A connection attempt or reconnect under way gives up at its next try.
+    pub fn forget_url(&self, url: &str) {
+        let url = ws_url(url);
+        self.dials.lock().remove(&url);
+        self.removed.lock().insert(url);
+    }
+
+    /// Whether the URL was dropped with [`remove_peer`](Self::remove_peer)
+    /// or [`forget_url`](Self::forget_url)
+    pub fn is_removed(&self, url: &str) -> bool {
+        self.removed.lock().contains(&ws_url(url))
+    }
+
+    /// Let a removed URL be dialed again
+    pub fn readmit(&self, url: &str) {
+        self.removed.lock().remove(&ws_url(url));
+    }
+
+    /// Stop using a peer URL and mark it [`PeerStatus::Standby`]
+    ///
+    /// Its peers are removed, so their connections close instead of
+    /// reconnecting.
+    pub async fn stand_down(&self, url: &str) {
+        let url = ws_url(url);
+        self.set_peer_status(&url, PeerStatus::Standby);
+        let ids: Vec<String> = {
+            let peers = self.peers.read().await;
+            peers.values().filter(|p| ws_url(&p.url) == url).map(|p| p.id.clone()).collect()
+        };
+        for id in ids {
+            let _ = self.bye(&id).await;
+        }
+    }
+
+    /// URLs dialed successfully within `PexOptions::ttl`, most recent first
+    pub fn known_addresses(&self) -> Vec<String> {
+        let now = std::time::Instant::now();
+        let mut book = self.addresses.lock();
+        self.expire_addresses(&mut book, now);
+        let mut good: Vec<(&String, std::time::Instant)> = book
+            .iter()
+            .filter(|(_, address)| address.failures < self.opt.pex.max_failures)
+            .filter_map(|(url, address)| address.last_ok.map(|ok| (url, ok)))
+            .collect();
+        good.sort_by_key(|&(_, ok)| std::cmp::Reverse(ok));
+        good.into_iter().map(|(url, _)| url.clone()).collect()
+    }
+
+    /// Drop addresses not seen within `PexOptions::ttl`
+    fn expire_addresses(&self, book: &mut HashMap<String, Address>, now: std::time::Instant) {
+        let ttl = std::time::Duration::from_millis(self.opt.pex.ttl);
+        book.retain(|_, address| {
+            address
+                .last_seen()
+                .is_some_and(|seen| now.duration_since(seen) < ttl)
+        });
+    }
+
+    /// Send a new peer our known-good addresses in a `dam: "pex"` message
+    async fn share_addresses(&self, peer: &Peer) {
+        let own_url = ws_url(&peer.url);
+        let urls: Vec<String> = self
+            .known_addresses()
+            .into_iter()
+            .filter(|url| *url != own_url)
+            .filter(|url| pex_dialable(url, self.opt.pex.allow_private))
+            .take(self.opt.pex.max_shared)
+            .collect();
+        if urls.is_empty() {
+            return;
+        }
+        let pex = serde_json::json!({ "dam": "pex", "peers": urls });
+        if let Err(e) = self.say(&pex, Some(peer)).await {
+            tracing::warn!(peer_id = %peer.id, error = %e, "Failed to send pex message");
+        }
+    }
+
+    /// Remember the addresses in a peer's `dam: "pex"` message and pick some to dial
+    ///
+    /// Addresses that aren't dialable, are banned, failed too often or were
+    /// already dialed within `ttl` are skipped, as are peers we're connected to.
+    /// Each one picked is announced with [`PEX_DIAL_EVENT`].
+    async fn handle_pex(&self, msg: &Value, peer: &Peer) {
+        let pex = &self.opt.pex;
+        if !pex.enabled {
+            return;
+        }
+        let Some(urls) = msg.get("peers").and_then(|v| v.as_array()) else {
+            return;
+        };
+        let connected: HashSet<String> = {
+            let peers = self.peers.read().await;
+            peers.values().filter(|p| p.tx.is_some()).map(|p| ws_url(&p.url)).collect()
+        };
+        let mut room = pex.max_peers.saturating_sub(connected.len()).min(pex.max_dials);
+
+        let now = std::time::Instant::now();
+        let ttl = std::time::Duration::from_millis(pex.ttl);
+        let mut dial = Vec::new();
+        {
+            let mut book = self.addresses.lock();
+            self.expire_addresses(&mut book, now);
+            for url in urls.iter().filter_map(|v| v.as_str()).take(pex.max_shared) {
+                let url = ws_url(url);
+                if !pex_dialable(&url, pex.allow_private) || self.is_banned(&url) {
+                    continue;
+                }
+                if !book.contains_key(&url) && book.len() >= MAX_PEX_ADDRESSES {
+                    continue;
+                }
+                let address = book.entry(url.clone()).or_default();
+                address.learned = Some(now);
+                let dialed_recently = address
+                    .last_dial
+                    .is_some_and(|at| now.duration_since(at) < ttl);
+                if room == 0
+                    || connected.contains(&url)
+                    || dialed_recently
+                    || address.failures >= pex.max_failures
+                {
+                    continue;
+                }
+                address.last_dial = Some(now);
+                room -= 1;
+                dial.push(url);
+            }
+        }
+
+        for url in dial {
+            tracing::debug!(peer_id = %peer.id, url = %url, "Peer exchange suggests dialing");
+            self.core.events.emit(&crate::events::Event {
+                event_type: PEX_DIAL_EVENT.to_string(),
+                data: serde_json::json!({ "url": url, "from": peer.id }),
+            });
+        }
+    }
+
     /// Get the number of connected peers (peers with active WebSocket connections)
     /// Acquires read lock with timeout to avoid indefinite blocking
     pub async fn connected_peer_count(&self) -> usize {
@@ -834,17 +3866,31 @@ impl Mesh {
     }
 
     /// Wait for at least one peer to be connected, with timeout
+    ///
+    /// Checks again on every [`PEER_LIFECYCLE_EVENT`] rather than polling.
     pub async fn wait_for_connection(&self, timeout_ms: u64) -> bool {
-        use tokio::time::{sleep, Duration, Instant};
-        let deadline = Instant::now() + Duration::from_millis(timeout_ms);
-
-        while Instant::now() < deadline {
-            if self.has_connected_peers().await {
-                return true;
+        let changed = Arc::new(tokio::sync::Notify::new());
+        let listener = {
+            let changed = changed.clone();
+            self.core.events.on(
+                PEER_LIFECYCLE_EVENT,
+                Box::new(move |_: &crate::events::Event| changed.notify_one()),
+            )
+        };
+        let connected = tokio::time::timeout(std::time::Duration::from_millis(timeout_ms), async {
+            while !self.has_connected_peers().await {
+                changed.notified().await;
             }
-            sleep(Duration::from_millis(100)).await;
-        }
-        false
+        })
+        .await
+        .is_ok();
+        self.core.events.off(PEER_LIFECYCLE_EVENT, listener);
+        connected
+    }
+
+    /// Number of peers in the mesh, connected or waiting to reconnect
+    pub async fn peer_count(&self) -> usize {
+        self.peers.read().await.len()
     }
 
     /// Get a peer by ID
@@ -852,4 +3898,15 @@ impl Mesh {
         let peers = self.peers.read().await;
         peers.get(peer_id).cloned()
     }
+
+    /// Every peer in the mesh, connected or waiting to reconnect
+    pub async fn peers(&self) -> Vec<Peer> {
+        self.peers.read().await.values().cloned().collect()
+    }
+
+    /// Whether messages for the peer with DAM pid `pid` go over an alternate
+    /// transport, see [`add_transport`](Self::add_transport)
+    pub fn has_transport(&self, pid: &str) -> bool {
+        self.transports.lock().contains_key(pid)
+    }
 }
@@ -14,15 +14,58 @@
 //! - Configurable maximum size and age
 //! - Tracks peer information for routing
 
+use serde::Serialize;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
 
+/// Bounds of the deduplication table
+///
+/// A relay sees every message of the mesh, so `max_entries` should cover at least
+/// `ttl` worth of traffic: ids evicted early for room can let loops through.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DupOptions {
+    /// Maximum number of message ids kept (default: 999)
+    /// When full, expired ids are dropped first, then the oldest tenth of the table.
+    /// With 0 only the latest id is kept, so loops get through
+    pub max_entries: usize,
+    /// How long an id is remembered (default: 9 seconds)
+    pub ttl: Duration,
+}
+
+impl Default for DupOptions {
+    fn default() -> Self {
+        Self {
+            max_entries: 999,
+            ttl: Duration::from_millis(9000),
+        }
+    }
+}
+
+/// Snapshot of the deduplication table, returned by [`Dup::stats`]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct DupStats {
+    /// Ids currently tracked
+    pub entries: usize,
+    /// Configured maximum number of ids
+    pub max_entries: usize,
+    /// Ids tracked since creation (re-tracking an id counts again)
+    pub tracked: u64,
+    /// `check()` calls that found a duplicate
+    pub duplicates: u64,
+    /// Ids dropped because their `ttl` passed
+    pub expired: u64,
+    /// Ids dropped before their `ttl` to keep the table under `max_entries`
+    pub evicted: u64,
+}
+
 /// Message deduplication tracker
 ///
 /// Tracks message IDs to prevent duplicate processing. Messages are stored with
-/// a timestamp and automatically expire after `max_age`. The tracker has a
-/// maximum size and will clean up expired entries when full.
+/// a timestamp and expire after `ttl`. Expired entries are swept on insert, at
+/// most once per `ttl` (or whenever the table is full), so the table never holds
+/// more than `max_entries` ids.
 ///
 /// # Default Configuration
 ///
@@ -33,9 +76,18 @@ use std::time::{Duration, Instant};
 ///
 /// `Dup` is thread-safe when wrapped in `Arc<RwLock<Dup>>` (as used in `GunCore`).
 pub struct Dup {
-    messages: Arc<RwLock<HashMap<String, MessageEntry>>>,
+    messages: Arc<RwLock<Table>>,
     max_age: Duration,
     max_size: usize,
+    tracked: AtomicU64,
+    duplicates: AtomicU64,
+    expired: AtomicU64,
+    evicted: AtomicU64,
+}
+
+struct Table {
+    entries: HashMap<String, MessageEntry>,
+    last_sweep: Instant,
 }
 
 #[derive(Clone, Debug)]
@@ -45,6 +97,16 @@ struct MessageEntry {
     it: Option<serde_json::Value>, // optional message data
 }
 
+impl MessageEntry {
+    fn new() -> Self {
+        Self {
+            was: Instant::now(),
+            via: None,
+            it: None,
+        }
+    }
+}
+
 impl Dup {
     /// Create a new deduplication tracker with custom settings
     ///
@@ -52,10 +114,25 @@ impl Dup {
     /// * `max_size` - Maximum number of message IDs to track
     /// * `max_age_ms` - Maximum age in milliseconds before entries expire
     pub fn new(max_size: usize, max_age_ms: u64) -> Self {
+        Self::with_options(DupOptions {
+            max_entries: max_size,
+            ttl: Duration::from_millis(max_age_ms),
+        })
+    }
+
+    /// Create a deduplication tracker with the given bounds
+    pub fn with_options(options: DupOptions) -> Self {
         Self {
-            messages: Arc::new(RwLock::new(HashMap::new())),
-            max_age: Duration::from_millis(max_age_ms),
-            max_size,
+            messages: Arc::new(RwLock::new(Table {
+                entries: HashMap::new(),
+                last_sweep: Instant::now(),
+            })),
+            max_age: options.ttl,
+            max_size: options.max_entries,
+            tracked: AtomicU64::new(0),
+            duplicates: AtomicU64::new(0),
+            expired: AtomicU64::new(0),
+            evicted: AtomicU64::new(0),
         }
     }
 
@@ -65,7 +142,24 @@ impl Dup {
     /// - Max size: 999 messages
     /// - Max age: 9000ms (9 seconds)
     pub fn new_default() -> Self {
-        Self::new(999, 9000)
+        Self::with_options(DupOptions::default())
+    }
+
+    /// Current table size and counters
+    ///
+    /// # Panics
+    /// This function will panic if the lock is poisoned, which should never happen
+    /// in practice since we don't panic while holding the lock.
+    pub fn stats(&self) -> DupStats {
+        let messages = self.messages.read().expect("Dup lock poisoned");
+        DupStats {
+            entries: messages.entries.len(),
+            max_entries: self.max_size,
+            tracked: self.tracked.load(Ordering::Relaxed),
+            duplicates: self.duplicates.load(Ordering::Relaxed),
+            expired: self.expired.load(Ordering::Relaxed),
+            evicted: self.evicted.load(Ordering::Relaxed),
+        }
     }
 
     /// Check if a message ID was already seen
@@ -85,19 +179,32 @@ impl Dup {
     /// in practice since we don't panic while holding the lock.
     pub fn check(&self, id: &str) -> bool {
         let messages = self.messages.read().expect("Dup lock poisoned");
-        if let Some(entry) = messages.get(id) {
+        if let Some(entry) = messages.entries.get(id) {
             // Check if still valid (not expired)
             if entry.was.elapsed() < self.max_age {
+                self.duplicates.fetch_add(1, Ordering::Relaxed);
                 return true; // duplicate
             }
         }
         false // new message
     }
 
+    /// Whether a message ID is tracked and not expired
+    ///
+    /// Like [`check`](Self::check), without counting a duplicate.
+    ///
+    /// # Panics
+    /// This function will panic if the lock is poisoned, which should never happen
+    /// in practice since we don't panic while holding the lock.
+    pub fn contains(&self, id: &str) -> bool {
+        let messages = self.messages.read().expect("Dup lock poisoned");
+        messages.entries.get(id).is_some_and(|entry| entry.was.elapsed() < self.max_age)
+    }
+
     /// Track a message ID (mark it as seen)
     ///
     /// Records the message ID with the current timestamp. If the tracker is full,
-    /// expired entries are cleaned up first.
+    /// expired entries are cleaned up first, then the oldest ones.
     ///
     /// # Arguments
     /// * `id` - The message ID to track
@@ -110,20 +217,7 @@ impl Dup {
     /// in practice since we don't panic while holding the lock.
     pub fn track(&mut self, id: &str) -> bool {
         let mut messages = self.messages.write().expect("Dup lock poisoned");
-
-        // Check size limit
-        if messages.len() >= self.max_size {
-            self.drop_expired(&mut messages);
-        }
-
-        let entry = messages
-            .entry(id.to_string())
-            .or_insert_with(|| MessageEntry {
-                was: Instant::now(),
-                via: None,
-                it: None,
-            });
-        entry.was = Instant::now();
+        self.entry(&mut messages, id).was = Instant::now();
         true
     }
 
@@ -141,23 +235,66 @@ impl Dup {
     /// in practice since we don't panic while holding the lock.
     pub fn track_with_peer(&mut self, id: &str, peer_id: Option<&str>) {
         let mut messages = self.messages.write().expect("Dup lock poisoned");
-        let entry = messages
-            .entry(id.to_string())
-            .or_insert_with(|| MessageEntry {
-                was: Instant::now(),
-                via: None,
-                it: None,
-            });
+        let entry = self.entry(&mut messages, id);
         entry.was = Instant::now();
         if let Some(pid) = peer_id {
             entry.via = Some(pid.to_string());
         }
     }
 
+    /// Entry for `id`, making room for it first if it's new
+    fn entry<'a>(&self, messages: &'a mut Table, id: &str) -> &'a mut MessageEntry {
+        self.tracked.fetch_add(1, Ordering::Relaxed);
+        if !messages.entries.contains_key(id) {
+            self.make_room(messages);
+        }
+        messages
+            .entries
+            .entry(id.to_string())
+            .or_insert_with(MessageEntry::new)
+    }
+
+    /// Sweep expired entries once per `max_age`, and evict the oldest when still full
+    ///
+    /// Eviction frees a tenth of the table at once so a full table doesn't pay for
+    /// a scan on every insert.
+    fn make_room(&self, messages: &mut Table) {
+        let full = messages.entries.len() >= self.max_size;
+        if full || messages.last_sweep.elapsed() >= self.max_age {
+            self.drop_expired(messages);
+        }
+        if messages.entries.len() < self.max_size {
+            return;
+        }
+
+        let keep = self.max_size.saturating_sub((self.max_size / 10).max(1));
+        let excess = messages.entries.len() - keep;
+        // Only an empty table with `max_size` 0
+        if excess == 0 {
+            return;
+        }
+        let mut by_age: Vec<(Instant, String)> = messages
+            .entries
+            .iter()
+            .map(|(id, entry)| (entry.was, id.clone()))
+            .collect();
+        by_age.select_nth_unstable_by_key(excess - 1, |(was, _)| *was);
+        for (_, id) in by_age.iter().take(excess) {
+            messages.entries.remove(id);
+        }
+        self.evicted.fetch_add(excess as u64, Ordering::Relaxed);
+    }
+
     /// Drop expired entries
-    fn drop_expired(&self, messages: &mut HashMap<String, MessageEntry>) {
+    fn drop_expired(&self, messages: &mut Table) {
         let now = Instant::now();
-        messages.retain(|_, entry| now.duration_since(entry.was) < self.max_age);
+        let before = messages.entries.len();
+        messages
+            .entries
+            .retain(|_, entry| now.duration_since(entry.was) < self.max_age);
+        messages.last_sweep = now;
+        self.expired
+            .fetch_add((before - messages.entries.len()) as u64, Ordering::Relaxed);
     }
 
     /// Drop all expired entries
@@ -183,7 +320,7 @@ impl Dup {
     /// in practice since we don't panic while holding the lock.
     pub fn get_via(&self, id: &str) -> Option<String> {
         let messages = self.messages.read().expect("Dup lock poisoned");
-        messages.get(id).and_then(|e| e.via.clone())
+        messages.entries.get(id).and_then(|e| e.via.clone())
     }
 
     /// Store message data along with its ID
@@ -200,17 +337,10 @@ impl Dup {
     /// in practice since we don't panic while holding the lock.
     pub fn store(&mut self, id: &str, data: serde_json::Value) {
         let mut messages = self.messages.write().expect("Dup lock poisoned");
-        if let Some(entry) = messages.get_mut(id) {
+        if let Some(entry) = messages.entries.get_mut(id) {
             entry.it = Some(data);
         } else {
-            messages.insert(
-                id.to_string(),
-                MessageEntry {
-                    was: Instant::now(),
-                    via: None,
-                    it: Some(data),
-                },
-            );
+            self.entry(&mut messages, id).it = Some(data);
         }
     }
 
@@ -227,7 +357,7 @@ impl Dup {
     /// in practice since we don't panic while holding the lock.
     pub fn get(&self, id: &str) -> Option<serde_json::Value> {
         let messages = self.messages.read().expect("Dup lock poisoned");
-        messages.get(id).and_then(|e| e.it.clone())
+        messages.entries.get(id).and_then(|e| e.it.clone())
     }
 
     /// Remove a specific message ID from tracking
@@ -243,7 +373,7 @@ impl Dup {
     /// in practice since we don't panic while holding the lock.
     pub fn remove(&self, id: &str) {
         let mut messages = self.messages.write().expect("Dup lock poisoned");
-        messages.remove(id);
+        messages.entries.remove(id);
     }
 }
 
@@ -14,14 +14,19 @@ use thiserror::Error;
 /// 
 /// - `InvalidData(String)`: Data format is invalid or doesn't match expected structure
 /// - `Storage(#[from] sled::Error)`: Storage operation failed (disk full, corruption, etc.)
+/// - `StorageBackend(String)`: A storage backend other than sled failed (Redis unreachable, etc.)
 /// - `Serialization(#[from] serde_json::Error)`: JSON serialization/deserialization failed
 /// - `Network(String)`: Network operation failed (connection lost, timeout, etc.)
+/// - `Tls(String)`: TLS setup or handshake failed (untrusted certificate, etc.)
 /// - `InvalidSoul(String)`: Soul (node ID) format is invalid
 /// - `NodeNotFound`: Requested node doesn't exist in the graph
 /// - `Io(#[from] std::io::Error)`: I/O operation failed (file read/write, etc.)
+/// - `InvalidPeerUrl(String)`: Peer URL can't be dialed (unparseable, or not ws/wss/http/https)
 /// - `UrlParseError(#[from] url::ParseError)`: URL parsing failed (invalid peer URL)
 /// - `WebRTC(String)`: WebRTC operation failed (connection, signaling, etc.)
 /// - `Crypto(String)`: Cryptographic operation failed (encryption, signing, etc.)
+/// - `Sea(#[from] SeaError)`: SEA operation failed, with the `SeaError` variant kept for matching
+/// - `Config(#[from] ConfigError)`: Options that can't work together
 /// 
 /// # Error Handling
 /// 
@@ -61,6 +66,11 @@ pub enum GunError {
     #[error("Storage error: {0}")]
     Storage(#[from] sled::Error),
 
+    /// A storage backend other than sled failed, e.g. a Redis server that
+    /// can't be reached
+    #[error("Storage backend error: {0}")]
+    StorageBackend(String),
+
     /// JSON serialization/deserialization failed
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
@@ -69,6 +79,11 @@ pub enum GunError {
     #[error("Network error: {0}")]
     Network(String),
 
+    /// TLS setup or handshake failed (untrusted or mismatched certificate,
+    /// unreadable root certificate file, etc.)
+    #[error("TLS error: {0}")]
+    Tls(String),
+
     /// Invalid soul (node ID) format
     #[error("Invalid soul: {0}")]
     InvalidSoul(String),
@@ -81,6 +96,11 @@ pub enum GunError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
+    /// Peer URL that can't be dialed: unparseable, or a scheme other than
+    /// `ws`, `wss`, `http` or `https`
+    #[error("Invalid peer URL {0}")]
+    InvalidPeerUrl(String),
+
     /// URL parsing failed (invalid peer URL format)
     #[error("URL parse error: {0}")]
     UrlParseError(#[from] url::ParseError),
@@ -92,6 +112,63 @@ pub enum GunError {
     /// Cryptographic operation failed (encryption, signing, etc.)
     #[error("Crypto error: {0}")]
     Crypto(String),
+
+    /// SEA operation failed; match on the [`SeaError`](crate::sea::SeaError)
+    /// to tell a wrong password from a missing user and so on
+    #[error("SEA error: {0}")]
+    Sea(#[from] crate::sea::SeaError),
+
+    /// Options that can't work together, see [`ConfigError`]
+    #[error("Configuration error: {0}")]
+    Config(#[from] ConfigError),
+}
+
+/// Invalid [`GunOptions`](crate::GunOptions), as
+/// [`GunOptionsBuilder::build`](crate::gun::GunOptionsBuilder::build) finds them
+///
+/// Each variant is a combination the plain struct accepts but ignores or
+/// misreads, so it fails when the options are built instead of silently
+/// doing something else.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// A peer URL that can't be dialed, see
+    /// [`normalize_peer_url`](crate::websocket::normalize_peer_url)
+    #[error("Invalid peer URL {0}")]
+    InvalidPeerUrl(String),
+
+    /// A port or listen address without relay mode: nothing would listen
+    /// unless there happen to be peers
+    #[error("Listening needs relay mode: use relay(port), or super_peer(true)")]
+    ListenWithoutRelay,
+
+    /// `radisk` without a `storage_path` for sled to open
+    #[error("radisk needs a storage path: use storage_sled(path)")]
+    RadiskWithoutStoragePath,
+
+    /// A custom storage backend as well as a `storage_path`, which it would
+    /// silently win over
+    #[error("Both a storage backend and a storage path are set")]
+    ConflictingStorage,
+
+    /// `signed_peers` with `require_signatures` on, where every peer already
+    /// has to sign
+    #[error("signed_peers only applies with require_signatures(false)")]
+    SignedPeersRequireSignatures,
+
+    /// A namespace that is empty or contains `/`, whose keys could overlap
+    /// another namespace's
+    #[error("Invalid namespace {0:?}: must be non-empty and without '/'")]
+    InvalidNamespace(String),
+
+    /// A deduplication table of 0 entries, which would let every loop through
+    #[error("dup.max_entries must be at least 1")]
+    ZeroDupEntries,
+
+    /// A proof of work difficulty above
+    /// [`MAX_POW_BITS`](crate::dam::MAX_POW_BITS), that would take minutes
+    /// per message to send
+    #[error("pow_bits {0} is above the maximum of {max}", max = crate::dam::MAX_POW_BITS)]
+    PowBitsTooHigh(u8),
 }
 
 /// Result type alias for Gun operations
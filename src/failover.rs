@@ -0,0 +1,206 @@
+//! Primary/backup use of relay URLs
+//!
+//! By default every URL in `GunOptions::peers` is dialed and used. With
+//! [`PeerMode::Failover`] the list is an order of preference instead: only the
+//! first `max_active` healthy URLs are connected, the rest are kept in reserve
+//! ([`PeerStatus::Standby`]) and probed every `health_check_interval`. When an
+//! active URL loses its connection and a probe can't reach it either, it is
+//! stood down and the first healthy backup takes its place.
+
+use crate::dam::Mesh;
+use crate::error::GunResult;
+use crate::websocket::{PeerStatus, WebSocketClient};
+use futures_util::future::join_all;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// How the URLs in `GunOptions::peers` are used
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PeerMode {
+    /// Dial every URL and use all of them
+    #[default]
+    All,
+    /// Use the first `max_active` healthy URLs, in list order, and keep the
+    /// others as backups
+    Failover { max_active: usize },
+}
+
+/// How to pick among the configured peer URLs
+///
+/// # Example
+///
+/// ```rust
+/// use gun::{GunOptions, PeerMode, PeerPolicy};
+/// use std::time::Duration;
+///
+/// let options = GunOptions {
+///     peers: vec![
+///         "wss://primary.example.com/gun".to_string(),
+///         "wss://backup.example.com/gun".to_string(),
+///     ],
+///     peer_policy: PeerPolicy {
+///         mode: PeerMode::Failover { max_active: 1 },
+///         health_check_interval: Duration::from_secs(2),
+///     },
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PeerPolicy {
+    pub mode: PeerMode,
+    /// How often active URLs are checked and backups probed in failover
+    /// mode. Default: 5 seconds
+    pub health_check_interval: Duration,
+}
+
+impl Default for PeerPolicy {
+    fn default() -> Self {
+        Self {
+            mode: PeerMode::All,
+            health_check_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+/// What failover mode knows about a peer URL, see `Gun::peer_health`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PeerHealth {
+    /// Whether the URL is one of those in use
+    pub active: bool,
+    /// Probes in a row that couldn't reach the URL; `0` means healthy
+    pub consecutive_failures: u32,
+    /// How long the latest successful probe took to connect
+    pub rtt: Option<Duration>,
+}
+
+/// Keeps the preferred healthy URLs connected, see [`PeerMode::Failover`]
+pub(crate) struct Failover {
+    urls: Vec<String>, // In order of preference
+    clients: HashMap<String, Arc<WebSocketClient>>, // What dials each URL
+    max_active: usize,
+    mesh: Arc<Mesh>,
+    health: Mutex<HashMap<String, PeerHealth>>,
+}
+
+impl Failover {
+    /// Choose among `peers`, each URL with the client that dials it, in
+    /// order of preference
+    pub(crate) fn new(peers: Vec<(String, Arc<WebSocketClient>)>, max_active: usize, mesh: Arc<Mesh>) -> Self {
+        let health = peers.iter().map(|(url, _)| (url.clone(), PeerHealth::default())).collect();
+        Self {
+            urls: peers.iter().map(|(url, _)| url.clone()).collect(),
+            clients: peers.into_iter().collect(),
+            max_active: max_active.max(1),
+            mesh,
+            health: Mutex::new(health),
+        }
+    }
+
+    /// Health of every configured URL, by URL
+    pub(crate) fn health(&self) -> HashMap<String, PeerHealth> {
+        self.health.lock().clone()
+    }
+
+    /// Check the active URLs, probe the backups and fill free slots
+    ///
+    /// An active URL whose connection is down is probed, and stood down if
+    /// that fails too. Free slots go to healthy backups in list order.
+    ///
+    /// # Returns
+    /// The dials of the URLs promoted, by URL.
+    pub(crate) async fn check(&self) -> Vec<(String, JoinHandle<GunResult<()>>)> {
+        let status = self.mesh.peer_status();
+        let to_probe: Vec<String> = {
+            let health = self.health.lock();
+            self.urls
+                .iter()
+                .filter(|url| !health[*url].active || status.get(*url) != Some(&PeerStatus::Connected))
+                .cloned()
+                .collect()
+        };
+        let probes = join_all(to_probe.iter().map(|url| self.clients[url].probe(url))).await;
+
+        let mut stood_down = Vec::new();
+        let promoted: Vec<String> = {
+            let mut health = self.health.lock();
+            for (url, probe) in to_probe.iter().zip(probes) {
+                let entry = health.get_mut(url).expect("configured URL");
+                match probe {
+                    Ok(rtt) => {
+                        entry.consecutive_failures = 0;
+                        entry.rtt = Some(rtt);
+                    }
+                    Err(e) => {
+                        entry.consecutive_failures += 1;
+                        if entry.active {
+                            tracing::warn!(url = %url, error = %e, "Active peer is down, failing over");
+                            entry.active = false;
+                            stood_down.push(url.clone());
+                        } else {
+                            tracing::debug!(url = %url, error = %e, "Backup peer unreachable");
+                        }
+                    }
+                }
+            }
+
+            let mut free = self.max_active - health.values().filter(|h| h.active).count();
+            let mut promoted = Vec::new();
+            for url in &self.urls {
+                if free == 0 {
+                    break;
+                }
+                let entry = health.get_mut(url).expect("configured URL");
+                if !entry.active && entry.consecutive_failures == 0 {
+                    entry.active = true;
+                    promoted.push(url.clone());
+                    free -= 1;
+                }
+            }
+            promoted
+        };
+
+        for url in &stood_down {
+            self.mesh.stand_down(url).await;
+        }
+        // Also covers a reconnect attempt of a stood down URL that ended
+        // after it was stood down
+        for (url, health) in self.health() {
+            if !health.active {
+                self.mesh.set_peer_status(&url, PeerStatus::Standby);
+            }
+        }
+        promoted
+            .into_iter()
+            .map(|url| {
+                tracing::info!(url = %url, "Using peer");
+                let client = self.clients[&url].clone();
+                let dial_url = url.clone();
+                (url, self.mesh.core().spawn(async move { client.connect(&dial_url).await }))
+            })
+            .collect()
+    }
+
+    /// Run [`check`](Self::check) every `interval` until shutdown
+    ///
+    /// The task only holds a weak reference, so it ends when the `Failover`
+    /// is dropped, or the core is cancelled.
+    pub(crate) fn start(failover: &Arc<Failover>, interval: Duration) -> JoinHandle<()> {
+        let core = failover.mesh.core().clone();
+        let failover: Weak<Failover> = Arc::downgrade(failover);
+        core.spawn_until_cancelled(async move {
+            let mut ticker = tokio::time::interval(interval.max(Duration::from_millis(1)));
+            ticker.tick().await; // first tick completes immediately
+            loop {
+                ticker.tick().await;
+                let failover = match failover.upgrade() {
+                    Some(failover) if !failover.mesh.is_shut_down() => failover,
+                    _ => break,
+                };
+                failover.check().await;
+            }
+        })
+    }
+}
@@ -10,10 +10,22 @@
 use crate::error::GunResult;
 use crate::state::Node;
 use parking_lot::RwLock;
+use serde::Serialize;
 use serde_json::Value;
 use std::collections::HashMap;
 use std::sync::Arc;
 
+/// Size of a [`Graph`], returned by [`Graph::stats`]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct GraphStats {
+    /// Nodes held
+    pub nodes: usize,
+    /// Keys over all nodes, tombstones included
+    pub keys: usize,
+    /// JSON bytes of the nodes, metadata included
+    pub bytes: u64,
+}
+
 /// Graph storage for all nodes in the database
 ///
 /// The graph is an in-memory hash map that stores nodes by their soul (unique identifier).
@@ -58,15 +70,48 @@ impl Graph {
         }
     }
 
+    /// Number of nodes held
+    pub fn len(&self) -> usize {
+        self.nodes.read().len()
+    }
+
+    /// Whether no nodes are held
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Size of the graph: nodes, keys and JSON bytes
+    ///
+    /// Serializes every node, so it costs as much as a full dump; meant for
+    /// dashboards and bug reports, not hot paths.
+    pub fn stats(&self) -> GraphStats {
+        let nodes = self.nodes.read();
+        GraphStats {
+            nodes: nodes.len(),
+            keys: nodes.values().map(|node| node.data.len()).sum(),
+            bytes: nodes
+                .values()
+                .map(|node| serde_json::to_vec(node).map(|b| b.len()).unwrap_or(0) as u64)
+                .sum(),
+        }
+    }
+
     /// Get a node by its soul (unique identifier)
     ///
     /// # Arguments
     /// * `soul` - The unique identifier of the node
     ///
+    /// Keys that have passed their `soul<?seconds` expiry are left out.
+    ///
     /// # Returns
     /// The node if found, or `None` if it doesn't exist.
     pub fn get(&self, soul: &str) -> Option<Node> {
-        self.nodes.read().get(soul).cloned()
+        let node = self.nodes.read().get(soul).cloned()?;
+        if node.has_expiry() {
+            Some(node.without_expired(now_millis()))
+        } else {
+            Some(node)
+        }
     }
 
     /// Store a node in the graph by its soul
@@ -158,6 +203,39 @@ impl Graph {
         }
     }
 
+    /// Tombstone the expired keys of a node
+    ///
+    /// Every key whose expiry time is at or before `now` is set to `null` with a
+    /// fresh state from `state_fn`, so the removal wins over the stale value when it
+    /// syncs to peers, and its expiry is cleared.
+    ///
+    /// # Arguments
+    /// * `soul` - The unique identifier of the node
+    /// * `now` - Current time in milliseconds since the Unix epoch
+    /// * `state_fn` - Function to generate the tombstone state
+    ///
+    /// # Returns
+    /// The updated node and the tombstoned keys, or `None` if nothing had expired.
+    pub fn expire(
+        &self,
+        soul: &str,
+        now: f64,
+        state_fn: impl Fn() -> f64,
+    ) -> Option<(Node, Vec<String>)> {
+        let mut nodes = self.nodes.write();
+        let node = nodes.get_mut(soul)?;
+        let expired = node.expired_keys(now);
+        if expired.is_empty() {
+            return None;
+        }
+
+        for key in &expired {
+            node.clear_expiry(key);
+            crate::state::State::ify(node, Some(key), Some(state_fn()), Some(Value::Null), None);
+        }
+        Some((node.clone(), expired))
+    }
+
     /// Merge two nodes resolving conflicts based on state
     fn merge_nodes(
         existing: &Node,
@@ -227,6 +305,11 @@ impl Graph {
     }
 }
 
+/// Current time in milliseconds since the Unix epoch
+fn now_millis() -> f64 {
+    chrono::Utc::now().timestamp_millis() as f64
+}
+
 impl Default for Graph {
     fn default() -> Self {
         Self::new()
@@ -1,15 +1,49 @@
 use crate::chain::Chain;
-use crate::core::GunCore;
-use crate::dam::Mesh;
-use crate::error::GunResult;
-use crate::storage::{LocalStorage, SledStorage, Storage};
-use crate::types::MessagePredicate;
-use crate::webrtc::{WebRTCManager, WebRTCOptions};
-use crate::websocket::{WebSocketClient, WebSocketServer};
+use crate::core::{GunCore, PreloadMode, PreloadProgress};
+use crate::dam::{Mesh, MeshOptions, MeshStats, PeerId, PeerScore, CONTENT_HASH_MISMATCH_EVENT, PEER_BANNED_EVENT, PEER_ERROR_EVENT, PEER_LIFECYCLE_EVENT, PEER_TIMEOUT_EVENT, PeerLifecycleEvent, PEX_DIAL_EVENT, MAX_POW_BITS};
+use crate::dup::DupOptions;
+use crate::error::{ConfigError, GunError, GunResult};
+use crate::failover::{Failover, PeerHealth, PeerMode, PeerPolicy};
+use crate::graph::{Graph, GraphStats};
+use crate::persist::STORAGE_ERROR_EVENT;
+use crate::sea::{User, UserChain};
+use crate::state::Node;
+use crate::storage::{
+    is_valid_namespace, migrate, node_changes, parse_gunjs_graph, write_gunjs_graph, InstrumentedStorage,
+    LocalStorage, MigrationOptions, MigrationReport, NamespacedStorage, SledStorage, Storage, StorageMetrics,
+    StorageStats, DEFAULT_SLOW_STORAGE_THRESHOLD,
+};
+use crate::types::{MessagePredicate, PublicKeyBytes, ServerAuth, Transport};
+#[cfg(feature = "lan-discovery")]
+use crate::lan::LanDiscovery;
+#[cfg(feature = "webrtc")]
+use crate::webrtc::{PeerEvent, WebRTCManager, WebRTCOptions, WEBRTC_PEER_EVENT};
+use crate::websocket::{
+    normalize_peer_url, ListenConfig, PeerConfig, PeerStatus, ProxyConfig, ServerStats, TlsOptions, WebSocketClient,
+    WebSocketServer,
+};
 use chia_bls::{PublicKey, SecretKey};
-use std::sync::Arc;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 use tokio::task::JoinHandle;
 
+/// Node cap for the default relay preload (see [`GunOptions::preload`])
+const RELAY_PRELOAD_MAX_NODES: usize = 100_000;
+
+/// How long [`Gun::shutdown`] lets relay connections drain before dropping them
+const SERVER_SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+/// How long [`Gun::shutdown`] waits for everything to stop
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Extra time `with_options` gives peer dials past `MeshOptions::connect_timeout`,
+/// for the settling pause after a connection opens
+const STARTUP_DIAL_SLACK: Duration = Duration::from_secs(1);
+
 /// Main Gun instance - entry point for the library
 /// 
 /// This is the primary interface for interacting with the Gun database.
@@ -68,10 +102,22 @@ use tokio::task::JoinHandle;
 /// Based on Gun.js IGun interface
 pub struct Gun {
     core: Arc<GunCore>,
-    mesh: Option<Arc<Mesh>>,
-    ws_server: Option<JoinHandle<()>>, // Server handle for graceful shutdown
-    #[allow(dead_code)] // Used internally for WebRTC signaling
+    mesh: OnceLock<Arc<Mesh>>, // Set at startup, or by the first add_peer() of a local-only instance
+    options: GunOptions, // Settings for networking started by add_peer()
+    ws_server: Option<WebSocketServer>, // Relay server, for introspection and graceful shutdown
+    ws_server_task: Option<JoinHandle<()>>, // Task running the server's accept loop
+    expiry_sweep: Option<JoinHandle<()>>, // Periodic sweep of `<?` expired keys
+    peer_pruning: Option<JoinHandle<()>>, // Periodic removal of idle peers
+    failover: Option<Arc<Failover>>, // Primary/backup selection of `peers` (PeerMode::Failover)
+    failover_task: Option<JoinHandle<()>>, // Periodic health checks of `failover`
+    storage_metrics: Arc<StorageMetrics>, // Counters of the instrumented storage backend
+    slow_storage_threshold: Duration,
+    namespace: Option<String>, // Storage key prefix (see GunOptions::namespace)
+    user: Arc<User>, // Signed-in SEA user (see user())
+    #[cfg(feature = "webrtc")]
     webrtc_manager: Option<Arc<WebRTCManager>>, // WebRTC manager for direct P2P connections
+    #[cfg(feature = "lan-discovery")]
+    lan_discovery: Option<LanDiscovery>, // mDNS advertising and browsing (see GunOptions::lan_discovery)
     secret_key: SecretKey, // BLS secret key for signing outgoing messages
     public_key: PublicKey, // BLS public key for verifying incoming messages
 }
@@ -102,11 +148,29 @@ impl Gun {
     /// 
     /// Based on Gun.js Gun() constructor
     pub fn new(secret_key: SecretKey, public_key: PublicKey) -> Self {
+        let storage_metrics = Arc::new(StorageMetrics::default());
+        let storage = InstrumentedStorage::with_metrics(
+            Arc::new(GunCore::default_storage()) as Arc<dyn Storage>,
+            storage_metrics.clone(),
+        );
         Self {
-            core: Arc::new(GunCore::new()),
-            mesh: None,
+            core: Arc::new(GunCore::with_storage(Arc::new(storage))),
+            mesh: OnceLock::new(),
+            options: GunOptions::default(),
             ws_server: None,
+            ws_server_task: None,
+            expiry_sweep: None,
+            peer_pruning: None,
+            failover: None,
+            failover_task: None,
+            storage_metrics,
+            slow_storage_threshold: DEFAULT_SLOW_STORAGE_THRESHOLD,
+            namespace: None,
+            user: Arc::new(User::new()),
+            #[cfg(feature = "webrtc")]
             webrtc_manager: None,
+            #[cfg(feature = "lan-discovery")]
+            lan_discovery: None,
             secret_key,
             public_key,
         }
@@ -144,6 +208,7 @@ impl Gun {
     /// ```rust,no_run
     /// use gun::{Gun, GunOptions, MessagePredicate};
     /// use chia_bls::{SecretKey, PublicKey};
+    /// use serde_json::Value;
     /// use std::sync::Arc;
     /// 
     /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
@@ -151,7 +216,7 @@ impl Gun {
     /// let public_key = secret_key.public_key();
     /// 
     /// // Optional: Create a message predicate to filter incoming messages
-    /// let predicate: MessagePredicate = Arc::new(|msg| {
+    /// let predicate: MessagePredicate = Arc::new(|msg: &Value| {
     ///     // Example: Only accept messages that contain a "put" field
     ///     msg.get("put").is_some()
     /// });
@@ -170,64 +235,194 @@ impl Gun {
     /// # }
     /// ```
     pub async fn with_options(secret_key: SecretKey, public_key: PublicKey, options: GunOptions) -> GunResult<Self> {
-        let core = if options.localStorage || options.storage_path.is_some() {
-            let storage: Arc<dyn Storage> = if let Some(ref storage_path) = options.storage_path {
-                if options.radisk {
-                    // Use SledStorage for radisk mode (more efficient for large datasets)
-                    Arc::new(SledStorage::new(storage_path)?)
-                } else {
-                    // Use LocalStorage (simpler, file-based, localStorage-like)
-                    Arc::new(LocalStorage::new(storage_path)?)
-                }
+        // A mistyped peer URL fails here rather than deep in a dial; spellings
+        // of the same endpoint are dialed once, with its `peer_configs` entry if any
+        let mut peers: Vec<(String, Option<&PeerConfig>)> = Vec::with_capacity(options.peers.len());
+        let configured = options.peers.iter().map(|url| (url, None));
+        for (url, config) in configured.chain(options.peer_configs.iter().map(|config| (&config.url, Some(config)))) {
+            let url = normalize_peer_url(url, options.append_gun_path)?;
+            match peers.iter_mut().find(|(known, _)| *known == url) {
+                Some(known) => known.1 = config.or(known.1),
+                None => peers.push((url, config)),
+            }
+        }
+
+        // Unchecked by literal options; turned away before anything starts
+        if let Some(bits) = options.pow_bits.filter(|&bits| bits > MAX_POW_BITS) {
+            return Err(ConfigError::PowBitsTooHigh(bits).into());
+        }
+
+        let storage: Arc<dyn Storage> = if let Some(ref storage) = options.storage {
+            // Caller-supplied backend (e.g. RedisStorage shared by a relay cluster)
+            storage.clone()
+        } else if let Some(ref storage_path) = options.storage_path {
+            if options.radisk {
+                // Use SledStorage for radisk mode (more efficient for large datasets)
+                Arc::new(SledStorage::new(storage_path)?)
             } else {
-                // Default localStorage location
-                let default_path = "./gun_data";
-                Arc::new(LocalStorage::new(default_path)?)
-            };
-            Arc::new(GunCore::with_storage(storage))
+                // Use LocalStorage (simpler, file-based, localStorage-like)
+                Arc::new(LocalStorage::new(storage_path)?)
+            }
+        } else if options.localStorage {
+            // Default localStorage location
+            let default_path = "./gun_data";
+            Arc::new(LocalStorage::new(default_path)?)
         } else {
-            Arc::new(GunCore::new())
+            // Nothing to persist to: a capped in-memory cache keeps the same code paths
+            Arc::new(GunCore::default_storage())
         };
+        let storage = Self::namespaced(storage, options.namespace.as_deref())?;
 
-        // Create mesh if we have peers or are a super peer
-        let mesh = if !options.peers.is_empty() || options.super_peer {
-            Some(Arc::new(Mesh::new(core.clone(), secret_key.clone(), public_key.clone(), options.message_predicate.clone())))
+        // Every backend is instrumented so stats() can tell disk stalls from network ones
+        let storage_metrics = Arc::new(StorageMetrics::default());
+        let core = Arc::new(GunCore::with_storage(Arc::new(
+            InstrumentedStorage::with_metrics(storage, storage_metrics.clone())
+                .with_slow_threshold(options.slow_storage_threshold),
+        )));
+
+        // Warm the graph before serving peers so their gets are answered from memory
+        let preload = options.preload.clone().unwrap_or(if options.super_peer {
+            PreloadMode::All {
+                max_nodes: RELAY_PRELOAD_MAX_NODES,
+            }
         } else {
-            None
+            PreloadMode::None
+        });
+        match core.preload(&preload, options.preload_progress.as_ref()).await {
+            Ok(_) => {}
+            // The relay default is only a warm-up, e.g. for a backend that can't list
+            Err(e) if options.preload.is_none() => {
+                tracing::warn!(error = %e, "Couldn't preload stored nodes, reading them on demand");
+            }
+            Err(e) => return Err(e),
+        }
+
+        // Keep fsync out of put(); writes are applied in order by a background task
+        if options.background_persistence {
+            core.start_background_persistence();
+        }
+
+        #[cfg(feature = "lan-discovery")]
+        let lan_discovery = options.lan_discovery;
+        #[cfg(not(feature = "lan-discovery"))]
+        let lan_discovery = false;
+
+        // Create mesh if we have peers or are a super peer
+        let mesh = match !peers.is_empty() || options.super_peer || lan_discovery {
+            true => Some(Self::start_mesh(&core, Self::build_mesh(&core, &secret_key, &public_key, &options)?, &options)),
+            false => None,
         };
 
         let mut ws_server = None;
+        let mut ws_server_task = None;
 
-        // Start WebSocket server if in super peer mode
-        if let (Some(ref mesh_ref), Some(port)) = (&mesh, options.port) {
-            let server = WebSocketServer::new(core.clone(), mesh_ref.clone(), port);
-            let server_clone = server;
-            let handle = tokio::spawn(async move {
+        // Start WebSocket server if in super peer mode, or on any free port
+        // for LAN peers to dial
+        let mut listeners = options.listen.clone();
+        if let Some(port) = options.port {
+            listeners.insert(0, ListenConfig::new(SocketAddr::from(([0, 0, 0, 0], port))));
+        }
+        if lan_discovery && listeners.is_empty() {
+            listeners.push(ListenConfig::new(SocketAddr::from(([0, 0, 0, 0], 0))));
+        }
+        if let (Some(ref mesh_ref), false) = (&mesh, listeners.is_empty()) {
+            let mut server = WebSocketServer::new(core.clone(), mesh_ref.clone(), 0).with_listeners(listeners);
+            if let Some(ref auth) = options.server_auth {
+                server = server.with_auth(auth.clone());
+            }
+            server = server.with_subprotocols(options.server_subprotocols.clone());
+            #[cfg(feature = "metrics")]
+            {
+                server = server.with_storage_metrics(storage_metrics.clone());
+            }
+            let server_clone = server.clone();
+            let handle = core.spawn(async move {
                 if let Err(e) = server_clone.start().await {
-                    eprintln!("WebSocket server error: {}", e);
+                    tracing::error!(error = %e, "WebSocket server error");
                 }
             });
-            ws_server = Some(handle);
+            // Bound (or failed) before we return, so port 0 can be looked up
+            server.listening().await;
+            ws_server = Some(server);
+            ws_server_task = Some(handle);
         }
 
         // Connect to peer URLs
+        let mut failover = None;
+        let mut failover_task = None;
         if let Some(ref mesh_ref) = mesh {
-            let client = WebSocketClient::new(core.clone(), mesh_ref.clone());
-            // Connect to all peers (always through public IPs for NAT traversal)
-            for peer_url in &options.peers {
-                match client.connect(peer_url).await {
-                    Ok(_) => {
-                        println!("Successfully connected to peer: {}", peer_url);
+            let client = Arc::new(Self::peer_client(&core, mesh_ref, &options));
+            let peers: Vec<(String, Arc<WebSocketClient>)> = peers
+                .into_iter()
+                .map(|(url, config)| match config {
+                    Some(config) => (url, Arc::new(client.for_peer(config))),
+                    None => (url, client.clone()),
+                })
+                .collect();
+            // Dial all peers at once (always through public IPs for NAT traversal),
+            // or in failover mode just the preferred healthy ones
+            let dials: Vec<(String, JoinHandle<GunResult<()>>)> = match options.peer_policy.mode {
+                PeerMode::All => peers
+                    .into_iter()
+                    .map(|(peer_url, client)| {
+                        let url = peer_url.clone();
+                        (peer_url, core.spawn(async move { client.connect(&url).await }))
+                    })
+                    .collect(),
+                PeerMode::Failover { max_active } => {
+                    let selector = Arc::new(Failover::new(peers, max_active, mesh_ref.clone()));
+                    let dials = selector.check().await;
+                    failover_task = Some(Failover::start(&selector, options.peer_policy.health_check_interval));
+                    failover = Some(selector);
+                    dials
+                }
+            };
+
+            // Wait for each peer's first attempt, not its retries: peers still
+            // failing keep being retried in the background (see peer_status())
+            let connect_timeout = options.mesh.connect_timeout;
+            let deadline = (connect_timeout > 0).then(|| {
+                tokio::time::Instant::now() + Duration::from_millis(connect_timeout) + STARTUP_DIAL_SLACK
+            });
+            for (peer_url, dial) in dials {
+                let outcome = match deadline {
+                    Some(deadline) => tokio::time::timeout_at(deadline, dial).await.ok(),
+                    None => Some(dial.await),
+                };
+                match outcome {
+                    Some(Ok(Ok(()))) => {
+                        tracing::info!(url = %peer_url, "Connected to peer");
+                    }
+                    Some(Ok(Err(e))) => {
+                        tracing::warn!(url = %peer_url, error = %e, "Failed to connect to peer");
                     }
-                    Err(e) => {
-                        eprintln!("Failed to connect to peer {}: {}", peer_url, e);
-                        // Continue trying other peers even if one fails
+                    Some(Err(e)) => {
+                        tracing::warn!(url = %peer_url, error = %e, "Connecting to peer panicked");
+                    }
+                    None => {
+                        tracing::warn!(url = %peer_url, "Peer not connected yet, retrying in the background");
                     }
                 }
             }
         }
 
+        // Advertise our listener on the local network and dial who else is there
+        #[cfg(feature = "lan-discovery")]
+        let lan_discovery = match (&mesh, &ws_server) {
+            (Some(mesh_ref), Some(server)) if lan_discovery => {
+                match server.local_addrs().first() {
+                    Some(addr) => Some(LanDiscovery::start(&core, mesh_ref, options.clone(), addr.port())?),
+                    None => {
+                        tracing::warn!("No listener to advertise, LAN discovery is off");
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
         // Initialize WebRTC manager if enabled and set it in the mesh
+        #[cfg(feature = "webrtc")]
         let webrtc_manager = if options.webrtc.enabled {
             if let Some(ref mesh_ref) = mesh {
                 let manager = Arc::new(WebRTCManager::new(
@@ -235,6 +430,7 @@ impl Gun {
                     mesh_ref.clone(),
                     options.webrtc.clone(),
                 ));
+                WebRTCManager::start_signaling(&manager);
                 Some(manager)
             } else {
                 None
@@ -243,105 +439,224 @@ impl Gun {
             None
         };
 
-        // Set up event listeners for network sync
-        if let Some(ref mesh_ref) = mesh {
-            let mesh_clone = mesh_ref.clone();
-            
-            // Listen for network_sync events (emitted by emit_update) and send to peers
-            let mesh_for_sync = mesh_clone.clone();
-            let core_for_sync = core.clone();
-            core.events.on("network_sync", Box::new(move |event: &crate::events::Event| {
-                if let Some(soul) = event.data.get("soul").and_then(|v| v.as_str()) {
-                    if let Some(data) = event.data.get("data") {
-                        // Gun.js expects: { put: { soul: { _: { "#": soul, ">": states }, ...data } } }
-                        // The soul must be a KEY in the put object, not a field
-                        let core_clone = core_for_sync.clone();
-                        let mesh_send = mesh_for_sync.clone();
-                        let soul_str = soul.to_string();
-                        let data_clone = data.clone();
-                        tokio::spawn(async move {
-                            // Get the node from graph to include state information
-                            if let Some(node) = core_clone.graph.get(&soul_str) {
-                                // Build node object with metadata
-                                let mut node_obj = serde_json::Map::new();
-                                
-                                // Add metadata object "_" from node.meta
-                                // Gun.js expects: { _: { "#": soul, ">": { key1: state1, key2: state2 } } }
-                                if let Some(meta_obj) = node.meta.get("_").and_then(|v| v.as_object()) {
-                                    // Use existing meta if available
-                                    let mut meta = serde_json::Map::new();
-                                    for (k, v) in meta_obj {
-                                        meta.insert(k.clone(), v.clone());
-                                    }
-                                    // Ensure "#" is set
-                                    meta.insert("#".to_string(), serde_json::Value::String(soul_str.clone()));
-                                    node_obj.insert("_".to_string(), serde_json::Value::Object(meta));
-                                } else {
-                                    // Build meta from node.meta
-                                    let mut meta = serde_json::Map::new();
-                                    meta.insert("#".to_string(), serde_json::Value::String(soul_str.clone()));
-                                    // Add state map if available
-                                    if let Some(states) = node.meta.get(">") {
-                                        meta.insert(">".to_string(), states.clone());
-                                    }
-                                    node_obj.insert("_".to_string(), serde_json::Value::Object(meta));
-                                }
-                                
-                                // Add data fields
-                                if let Some(data_obj) = data_clone.as_object() {
-                                    for (key, value) in data_obj {
-                                        node_obj.insert(key.clone(), value.clone());
-                                    }
-                                }
-                                
-                                // Build put message: { put: { soul: node_obj } }
-                                // Gun.js expects the soul to be a KEY, not a field
-                                let mut put_obj = serde_json::Map::new();
-                                put_obj.insert(soul_str.clone(), serde_json::Value::Object(node_obj));
-                                
-                                let msg = serde_json::json!({
-                                    "put": serde_json::Value::Object(put_obj)
-                                });
-                                eprintln!("DEBUG: Sending put message to peers (Gun.js format): {}", serde_json::to_string(&msg).unwrap_or_default());
-                                
-                                if let Err(e) = mesh_send.say(&msg, None).await {
-                                    eprintln!("Error sending network_sync to peers: {}", e);
-                                }
-                            }
-                        });
+        // Periodically tombstone expired keys. The task only holds a weak reference
+        // so it never keeps the core (and its storage) alive after Gun is dropped.
+        let expiry_sweep = options.expiry_sweep_interval.map(|interval| {
+            let core_weak = Arc::downgrade(&core);
+            core.spawn_until_cancelled(async move {
+                let mut ticker = tokio::time::interval(interval);
+                ticker.tick().await; // first tick completes immediately
+                loop {
+                    ticker.tick().await;
+                    let core = match core_weak.upgrade() {
+                        Some(core) => core,
+                        None => break,
+                    };
+                    if let Err(e) = core.sweep_expired().await {
+                        tracing::warn!(error = %e, "Error sweeping expired data");
                     }
                 }
-            }));
+            })
+        });
 
-            // Listen for get_request events and send them to peers
-            let mesh_for_get = mesh_clone.clone();
-            core.events.on("get_request", Box::new(move |event: &crate::events::Event| {
-                // Forward get request to peers
-                if let Some(get_data) = event.data.get("get") {
-                    let msg = serde_json::json!({
-                        "get": get_data
-                    });
-                    let mesh_send = mesh_for_get.clone();
-                    let msg_send = msg.clone();
-                    tokio::spawn(async move {
-                        if let Err(e) = mesh_send.say(&msg_send, None).await {
-                            eprintln!("Error sending get_request to peers: {}", e);
-                        }
-                    });
-                }
-            }));
-        }
+        // Drop peers that have gone silent without saying bye
+        let peer_pruning = mesh.as_ref().and_then(Mesh::start_pruning);
 
         Ok(Self {
             core,
-            mesh,
+            mesh: mesh.map(OnceLock::from).unwrap_or_default(),
             ws_server,
+            ws_server_task,
+            expiry_sweep,
+            peer_pruning,
+            failover,
+            failover_task,
+            storage_metrics,
+            slow_storage_threshold: options.slow_storage_threshold,
+            namespace: options.namespace.clone(),
+            user: Arc::new(User::new()),
+            #[cfg(feature = "webrtc")]
             webrtc_manager,
+            #[cfg(feature = "lan-discovery")]
+            lan_discovery,
             secret_key,
             public_key,
+            options,
         })
     }
 
+    /// The mesh for `options`, not yet listening to anything
+    ///
+    /// # Errors
+    /// `GunError::Config` if `pow_bits` is above [`MAX_POW_BITS`].
+    fn build_mesh(core: &Arc<GunCore>, secret_key: &SecretKey, public_key: &PublicKey, options: &GunOptions) -> GunResult<Mesh> {
+        Ok(Mesh::new(core.clone(), secret_key.clone(), public_key.clone(), options.message_predicate.clone())
+            .with_options(options.mesh.clone())
+            .with_dup_options(options.dup.clone())
+            .with_require_signatures(options.require_signatures)
+            .with_signed_peers(options.signed_peers.clone())
+            .with_peer_allowlist(options.peer_allowlist.clone())
+            .with_peer_denylist(options.peer_denylist.clone())
+            .with_secure_user_space(options.secure_user_space)
+            .with_pow_bits(options.pow_bits)?
+            .with_verify_cache(options.verify_cache_entries))
+    }
+
+    /// Start `mesh`, with the listeners that send puts and gets to peers and
+    /// dial addresses learned through peer exchange
+    fn start_mesh(core: &Arc<GunCore>, mesh: Mesh, options: &GunOptions) -> Arc<Mesh> {
+        let mesh = Arc::new(mesh);
+
+        // Dial addresses picked by peer exchange. Registered before connecting so
+        // the pex messages our first peers send on hi aren't missed.
+        if options.mesh.pex.enabled {
+            let mesh_for_pex = mesh.clone();
+            let core_for_pex = Arc::downgrade(core);
+            let tls = options.tls.clone();
+            let proxy = options.proxy.clone();
+            core.events.on(PEX_DIAL_EVENT, Box::new(move |event: &crate::events::Event| {
+                let (Some(url), Some(core)) = (
+                    event.data.get("url").and_then(|v| v.as_str()).map(str::to_string),
+                    core_for_pex.upgrade(),
+                ) else {
+                    return;
+                };
+                if mesh_for_pex.is_shut_down() {
+                    return;
+                }
+                let client = WebSocketClient::new(core.clone(), mesh_for_pex.clone())
+                    .with_tls(tls.clone())
+                    .with_proxy(proxy.clone());
+                core.spawn(async move {
+                    if let Err(e) = client.connect(&url).await {
+                        tracing::debug!(url = %url, error = %e, "Failed to connect to exchanged peer");
+                    }
+                });
+            }));
+        }
+
+        // Listen for network_sync events (emitted by emit_update) and send to peers
+        let mesh_for_sync = mesh.clone();
+        let core_for_sync = core.clone();
+        core.events.on("network_sync", Box::new(move |event: &crate::events::Event| {
+            if mesh_for_sync.is_shut_down() {
+                return;
+            }
+            if let Some(soul) = event.data.get("soul").and_then(|v| v.as_str()) {
+                if let Some(data) = event.data.get("data") {
+                    // Gun.js expects: { put: { soul: { _: { "#": soul, ">": states }, ...data } } }
+                    // The soul must be a KEY in the put object, not a field
+                    let core_clone = core_for_sync.clone();
+                    let mesh_send = mesh_for_sync.clone();
+                    let soul_str = soul.to_string();
+                    let data_clone = data.clone();
+                    core_for_sync.spawn(async move {
+                        // Get the node from graph to include state information
+                        if let Some(node) = core_clone.graph.get(&soul_str) {
+                            // Build node object with metadata
+                            let mut node_obj = serde_json::Map::new();
+                            
+                            // Add metadata object "_" from node.meta
+                            // Gun.js expects: { _: { "#": soul, ">": { key1: state1, key2: state2 } } }
+                            if let Some(meta_obj) = node.meta.get("_").and_then(|v| v.as_object()) {
+                                // Use existing meta if available
+                                let mut meta = serde_json::Map::new();
+                                for (k, v) in meta_obj {
+                                    meta.insert(k.clone(), v.clone());
+                                }
+                                // Ensure "#" is set
+                                meta.insert("#".to_string(), serde_json::Value::String(soul_str.clone()));
+                                node_obj.insert("_".to_string(), serde_json::Value::Object(meta));
+                            } else {
+                                // Build meta from node.meta
+                                let mut meta = serde_json::Map::new();
+                                meta.insert("#".to_string(), serde_json::Value::String(soul_str.clone()));
+                                // Add state map if available
+                                if let Some(states) = node.meta.get(">") {
+                                    meta.insert(">".to_string(), states.clone());
+                                }
+                                node_obj.insert("_".to_string(), serde_json::Value::Object(meta));
+                            }
+                            
+                            // Add data fields
+                            if let Some(data_obj) = data_clone.as_object() {
+                                for (key, value) in data_obj {
+                                    node_obj.insert(key.clone(), value.clone());
+                                }
+                            }
+                            
+                            // Build put message: { put: { soul: node_obj } }
+                            // Gun.js expects the soul to be a KEY, not a field
+                            let mut put_obj = serde_json::Map::new();
+                            put_obj.insert(soul_str.clone(), serde_json::Value::Object(node_obj));
+                            
+                            let msg = serde_json::json!({
+                                "put": serde_json::Value::Object(put_obj)
+                            });
+                            tracing::trace!(soul = %soul_str, msg = %msg, "Sending put message to peers");
+                            
+                            if let Err(e) = mesh_send.say(&msg, None).await {
+                                tracing::warn!(soul = %soul_str, error = %e, "Error sending network_sync to peers");
+                            }
+                        }
+                    });
+                }
+            }
+        }));
+
+        // Listen for get_request events and send them to peers. Requests are
+        // batched with others made at the same time and share a get already in
+        // flight; those with a "req" id get a `get_reply:{req}` event once their
+        // response has arrived or no peer had the data.
+        let mesh_for_get = mesh.clone();
+        let core_for_get = Arc::downgrade(core);
+        core.events.on("get_request", Box::new(move |event: &crate::events::Event| {
+            let Some(core) = core_for_get.upgrade().filter(|_| !mesh_for_get.is_shut_down()) else {
+                return;
+            };
+            // Forward get request to peers
+            if let Some(get_data) = event.data.get("get") {
+                let get = get_data.clone();
+                let request_id = event.data.get("req").and_then(|v| v.as_str()).map(str::to_string);
+                let mesh_send = mesh_for_get.clone();
+                let core_weak = core_for_get.clone();
+                core.spawn(async move {
+                    let reply = match mesh_send.ask_get(get).await {
+                        Ok(reply) => reply,
+                        Err(e) => {
+                            tracing::warn!(error = %e, "Error sending get_request to peers");
+                            return;
+                        }
+                    };
+                    let Some(request_id) = request_id else {
+                        return;
+                    };
+                    // A closed receiver means no peer has it: wake the caller anyway
+                    let response = reply.await.unwrap_or(serde_json::Value::Null);
+                    if let Some(core) = core_weak.upgrade() {
+                        core.events.emit(&crate::events::Event {
+                            event_type: format!("get_reply:{}", request_id),
+                            data: response,
+                        });
+                    }
+                });
+            }
+        }));
+
+        mesh
+    }
+
+    /// Client dialing `options.peers` with their TLS, proxy, headers, query
+    /// and subprotocols
+    fn peer_client(core: &Arc<GunCore>, mesh: &Arc<Mesh>, options: &GunOptions) -> WebSocketClient {
+        WebSocketClient::new(core.clone(), mesh.clone())
+            .with_tls(options.tls.clone())
+            .with_proxy(options.proxy.clone())
+            .with_headers(options.peer_headers.clone())
+            .with_query(options.peer_query.clone())
+            .with_subprotocols(options.peer_subprotocols.clone())
+    }
+
     /// Get a node by key (creates a chain)
     /// Based on Gun.js gun.get(key)
     pub fn get(&self, key: &str) -> Arc<Chain> {
@@ -350,11 +665,79 @@ impl Gun {
         Arc::new(Chain::with_soul(self.core.clone(), key.to_string(), None))
     }
 
+    /// Read several nodes at once
+    ///
+    /// Nodes not held locally are asked for from peers in a single message
+    /// (see [`Mesh::ask_get`]), instead of one round trip per soul.
+    ///
+    /// # Arguments
+    /// * `souls` - Souls of the nodes to read
+    ///
+    /// # Returns
+    /// The data of every node found, by soul; souls nobody has are left out.
+    ///
+    /// # Errors
+    /// Returns `GunError` if reading storage or sending the request fails.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # async fn example(gun: gun::Gun) -> Result<(), Box<dyn std::error::Error>> {
+    /// let users = gun.get_many(&["user_1", "user_2", "user_3"]).await?;
+    /// for (soul, data) in &users {
+    ///     println!("{}: {}", soul, data["name"]);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn get_many(&self, souls: &[&str]) -> GunResult<HashMap<String, serde_json::Value>> {
+        let mut found = HashMap::new();
+        let mut missing = Vec::new();
+        for soul in souls {
+            match self.core.get_node(soul).await? {
+                Some(node) => {
+                    found.insert(soul.to_string(), serde_json::Value::Object(node.data));
+                }
+                None => missing.push(*soul),
+            }
+        }
+
+        if let (false, Some(mesh)) = (missing.is_empty(), self.mesh.get()) {
+            // Same wait as once(): relays can be slow
+            let reply = mesh.ask_get(serde_json::json!({ "#": missing })).await?;
+            let _ = tokio::time::timeout(Duration::from_secs(20), reply).await;
+            for soul in missing {
+                if let Some(node) = self.core.graph.get(soul) {
+                    found.insert(soul.to_string(), serde_json::Value::Object(node.data));
+                }
+            }
+        }
+        Ok(found)
+    }
+
     /// Get the root chain
     pub fn root(&self) -> Arc<Chain> {
         Arc::new(Chain::new(self.core.clone()))
     }
 
+    /// The user chain: create accounts, sign in, and read and write the
+    /// signed-in user's `~pub` node
+    /// Based on Gun.js gun.user()
+    ///
+    /// Every call shares the same signed-in user.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # async fn example(gun: gun::Gun) -> Result<(), Box<dyn std::error::Error>> {
+    /// gun.user().auth("alice", "secure_password").await?;
+    /// assert!(gun.user().is().is_some());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn user(&self) -> UserChain {
+        UserChain::new(self.root(), self.user.clone())
+    }
+
     /// Get state timestamp (for testing/debugging)
     pub fn state(&self) -> f64 {
         self.core.state.next()
@@ -371,12 +754,184 @@ impl Gun {
     /// Kept for potential future internal module access
     #[allow(dead_code)]
     pub(crate) fn mesh(&self) -> Option<&Arc<Mesh>> {
-        self.mesh.as_ref()
+        self.mesh.get()
+    }
+
+    /// The relay's WebSocket server, when running with a `port`
+    /// Lists and closes incoming connections; see [`WebSocketServer`]
+    pub fn ws_server(&self) -> Option<&WebSocketServer> {
+        self.ws_server.as_ref()
+    }
+
+    /// The WebRTC manager, when `webrtc.enabled` and networking is on
+    ///
+    /// Peers find each other through the relays they share and open data
+    /// channels on their own; see [`WebRTCManager::is_open`] and
+    /// [`WebRTCManager::send_message`].
+    #[cfg(feature = "webrtc")]
+    pub fn webrtc(&self) -> Option<&Arc<WebRTCManager>> {
+        self.webrtc_manager.as_ref()
+    }
+
+    /// Which path messages for the peer with DAM pid `pid` take now
+    ///
+    /// [`Transport::WebRTC`] while a data channel to it is open,
+    /// [`Transport::WebSocket`] when it is one of our WebSocket peers, and
+    /// [`Transport::Relay`] otherwise.
+    #[cfg(feature = "webrtc")]
+    pub async fn transport_for(&self, pid: &str) -> Transport {
+        if let Some(manager) = &self.webrtc_manager {
+            if manager.is_open(pid).await {
+                return Transport::WebRTC;
+            }
+        }
+        match self.mesh.get() {
+            Some(mesh) if mesh.peer_id_for_pid(pid).is_some() => Transport::WebSocket,
+            _ => Transport::Relay,
+        }
+    }
+
+    /// How connecting to each peer URL went, by URL
+    ///
+    /// `Connected`, `Connecting` (first attempt or a retry under way) or
+    /// `Failed` with why the latest attempt failed: DNS failure, refused,
+    /// TLS error, timeout or a failed WebSocket handshake; backups held in
+    /// reserve by failover mode are `Standby`. Empty without networking.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use gun::websocket::{ConnectFailure, PeerStatus};
+    /// # async fn example(gun: gun::Gun) {
+    /// for (url, status) in gun.peer_status() {
+    ///     if let PeerStatus::Failed(ConnectFailure::DnsFailure(err)) = status {
+    ///         eprintln!("{} doesn't resolve: {}", url, err);
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn peer_status(&self) -> HashMap<String, PeerStatus> {
+        self.mesh.get().map(|mesh| mesh.peer_status()).unwrap_or_default()
+    }
+
+    /// Connect to another peer while running
+    ///
+    /// Dials `url` with the TLS, proxy, headers, query and subprotocols the
+    /// instance was created with (and its `peer_configs` entry, if any), runs
+    /// the DAM `?` exchange and adds the peer to the mesh, which asks it for
+    /// every subscribed soul. Retries as the peers given at startup are,
+    /// and keeps reconnecting once connected. A local-only instance starts
+    /// networking with its first peer, signing with its own keys; WebRTC and
+    /// failover only cover peers given at startup.
+    ///
+    /// # Returns
+    /// The peer's ID in the mesh, as [`peers`](Self::peers) lists it.
+    ///
+    /// # Errors
+    /// `GunError::InvalidPeerUrl` if `url` can't be dialed, and the errors of
+    /// [`WebSocketClient::connect`] if the peer can't be reached.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # async fn example(gun: gun::Gun) -> gun::GunResult<()> {
+    /// let peer_id = gun.add_peer("wss://relay.example.com/gun").await?;
+    /// // ...
+    /// gun.remove_peer(&peer_id).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn add_peer(&self, url: &str) -> GunResult<PeerId> {
+        let mesh = match self.mesh.get() {
+            Some(mesh) => mesh,
+            None => {
+                let mesh = Self::build_mesh(&self.core, &self.secret_key, &self.public_key, &self.options)?;
+                self.mesh.get_or_init(|| {
+                    let mesh = Self::start_mesh(&self.core, mesh, &self.options);
+                    // Cancelled with the core on shutdown
+                    let _ = Mesh::start_pruning(&mesh);
+                    mesh
+                })
+            }
+        };
+        Self::dial_peer(&self.core, mesh, &self.options, url).await
+    }
+
+    /// [`add_peer`](Self::add_peer) on a running mesh, for tasks that start
+    /// with the instance, such as LAN discovery
+    pub(crate) async fn dial_peer(core: &Arc<GunCore>, mesh: &Arc<Mesh>, options: &GunOptions, url: &str) -> GunResult<PeerId> {
+        let url = normalize_peer_url(url, options.append_gun_path)?;
+        mesh.readmit(&url);
+        let client = Self::peer_client(core, mesh, options);
+        let config = options
+            .peer_configs
+            .iter()
+            .find(|config| normalize_peer_url(&config.url, options.append_gun_path).is_ok_and(|known| known == url));
+        match config {
+            Some(config) => client.for_peer(config).connect_peer(&url).await,
+            None => client.connect_peer(&url).await,
+        }
+    }
+
+    /// Disconnect from a peer, by its ID or URL
+    ///
+    /// Says `bye` to the peer and closes the connection; a peer we dialed
+    /// isn't reconnected, and a dial of the URL still under way gives up.
+    /// Returns whether a peer or dial was found.
+    pub async fn remove_peer(&self, id_or_url: &str) -> GunResult<bool> {
+        let Some(mesh) = self.mesh.get() else {
+            return Ok(false);
+        };
+        let url = normalize_peer_url(id_or_url, self.options.append_gun_path).ok();
+        let mut found = false;
+        for peer in mesh.peers().await {
+            let same_url = url.as_ref().is_some_and(|url| normalize_peer_url(&peer.url, false).is_ok_and(|known| known == *url));
+            if peer.id == id_or_url || same_url {
+                found |= mesh.remove_peer(&peer.id).await?;
+            }
+        }
+        if let Some(url) = url {
+            found |= self.peer_status().contains_key(&url);
+            mesh.forget_url(&url);
+        }
+        Ok(found)
+    }
+
+    /// The peers in the mesh, connected or waiting to reconnect
+    ///
+    /// Empty without networking.
+    pub async fn peers(&self) -> Vec<PeerInfo> {
+        let Some(mesh) = self.mesh.get() else {
+            return Vec::new();
+        };
+        mesh.peers()
+            .await
+            .into_iter()
+            .map(|peer| {
+                let over_webrtc = peer.url.starts_with("webrtc:")
+                    || peer.pid.as_deref().is_some_and(|pid| mesh.has_transport(pid));
+                PeerInfo {
+                    connected: peer.tx.is_some(),
+                    transport: if over_webrtc { Transport::WebRTC } else { Transport::WebSocket },
+                    id: peer.id,
+                    url: peer.url,
+                    pid: peer.pid,
+                    last_seen: peer.last_seen,
+                }
+            })
+            .collect()
+    }
+
+    /// Health of each peer URL in failover mode, by URL
+    ///
+    /// Which URLs are in use, how many probes in a row failed to reach each,
+    /// and how long the latest successful probe took. Empty unless
+    /// `peer_policy.mode` is [`PeerMode::Failover`].
+    pub fn peer_health(&self) -> HashMap<String, PeerHealth> {
+        self.failover.as_ref().map(|failover| failover.health()).unwrap_or_default()
     }
 
     /// Get the number of connected peers
     pub async fn connected_peer_count(&self) -> usize {
-        if let Some(ref mesh) = self.mesh {
+        if let Some(mesh) = self.mesh.get() {
             mesh.connected_peer_count().await
         } else {
             0
@@ -385,7 +940,7 @@ impl Gun {
 
     /// Check if any peers are connected
     pub async fn is_connected(&self) -> bool {
-        if let Some(ref mesh) = self.mesh {
+        if let Some(mesh) = self.mesh.get() {
             mesh.has_connected_peers().await
         } else {
             false
@@ -395,30 +950,496 @@ impl Gun {
     /// Wait for at least one peer connection to be established
     /// Returns true if connection was established, false if timeout was reached
     pub async fn wait_for_connection(&self, timeout_ms: u64) -> bool {
-        if let Some(ref mesh) = self.mesh {
+        if let Some(mesh) = self.mesh.get() {
             mesh.wait_for_connection(timeout_ms).await
         } else {
             false
         }
     }
 
+    /// Let an origin key write again (see `GunOptions::peer_allowlist`)
+    ///
+    /// Lifts a denial and adds the key to the allowlist, if one is set.
+    /// Does nothing without a mesh.
+    pub fn allow_key(&self, key: PublicKeyBytes) {
+        if let Some(mesh) = self.mesh.get() {
+            mesh.allow_key(key);
+        }
+    }
+
+    /// Stop accepting messages created by an origin key, without a restart
+    pub fn deny_key(&self, key: PublicKeyBytes) {
+        if let Some(mesh) = self.mesh.get() {
+            mesh.deny_key(key);
+        }
+    }
+
+    /// Wrap a backend in the configured namespace, if any
+    fn namespaced(storage: Arc<dyn Storage>, namespace: Option<&str>) -> GunResult<Arc<dyn Storage>> {
+        Ok(match namespace {
+            Some(namespace) => Arc::new(NamespacedStorage::new(storage, namespace)?),
+            None => storage,
+        })
+    }
+
+    /// Move all persisted data to a new storage backend and switch to it
+    ///
+    /// Copies every node from the current backend into `new_backend` with
+    /// [`storage::migrate`](crate::storage::migrate), then swaps the backend
+    /// atomically so all later reads and writes go to `new_backend`. The swap only
+    /// happens if the copy verified cleanly; a dry run never swaps.
+    ///
+    /// Writes made while the copy is running still land in the old backend, so
+    /// run this before accepting traffic or while writers are paused.
+    ///
+    /// # Arguments
+    /// * `new_backend` - Backend to migrate into
+    /// * `opts` - Migration options (batch size, dry run, verification, progress)
+    ///
+    /// # Returns
+    /// The [`MigrationReport`](crate::storage::MigrationReport) for the copy.
+    ///
+    /// # Errors
+    /// - `GunError::InvalidData`: If the copy did not verify (the old backend stays active)
+    /// - `GunError::Storage` / `GunError::Io`: If either backend fails
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use gun::{Gun, GunOptions};
+    /// use gun::storage::{MigrationOptions, SledStorage};
+    /// use chia_bls::SecretKey;
+    /// use std::sync::Arc;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let secret_key = SecretKey::from_seed(&[0u8; 32]);
+    /// let public_key = secret_key.public_key();
+    /// let options = GunOptions {
+    ///     storage_path: Some("./gun_data".to_string()),
+    ///     radisk: false, // LocalStorage
+    ///     ..Default::default()
+    /// };
+    /// let gun = Gun::with_options(secret_key, public_key, options).await?;
+    ///
+    /// let sled = Arc::new(SledStorage::new("./gun_sled")?);
+    /// let report = gun.migrate_storage(sled, MigrationOptions::default()).await?;
+    /// println!("Migrated {} nodes", report.copied);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn migrate_storage(
+        &self,
+        new_backend: Arc<dyn Storage>,
+        opts: MigrationOptions,
+    ) -> GunResult<MigrationReport> {
+        // Queued writes belong to the old backend and must be copied with it
+        self.flush().await?;
+        let current = self.core.storage();
+
+        let dry_run = opts.dry_run;
+        let report = migrate(current.as_ref(), new_backend.as_ref(), opts).await?;
+        if dry_run {
+            return Ok(report);
+        }
+        if !report.is_complete() {
+            return Err(GunError::InvalidData(format!(
+                "Storage migration did not verify: {} mismatched nodes",
+                report.mismatched.len()
+            )));
+        }
+
+        let new_backend = Self::namespaced(new_backend, self.namespace.as_deref())?;
+        self.core.set_storage(Arc::new(
+            InstrumentedStorage::with_metrics(new_backend, self.storage_metrics.clone())
+                .with_slow_threshold(self.slow_storage_threshold),
+        ));
+        Ok(report)
+    }
+
+    /// Snapshot of this instance's runtime statistics
+    ///
+    /// Serializes to JSON for dashboards and bug reports, or can be put into
+    /// the graph itself, e.g. at a debug soul peers read. Sizing the graph
+    /// serializes every node, so don't call it on a hot path.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # async fn example(gun: gun::Gun) -> Result<(), Box<dyn std::error::Error>> {
+    /// let stats = gun.stats();
+    /// println!("{} puts, slowest {:?}", stats.storage.puts, stats.storage.put_latency.max);
+    ///
+    /// let snapshot = serde_json::to_value(&stats)?;
+    /// gun.get("debug").get("stats").put(snapshot).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn stats(&self) -> GunStats {
+        GunStats {
+            graph: self.core.graph.stats(),
+            subscriptions: self.core.subscription_count(),
+            uptime: self.core.uptime(),
+            storage: self.storage_metrics.snapshot(),
+            peer_scores: self
+                .mesh
+                .get()
+                .map(|mesh| mesh.peer_scores())
+                .unwrap_or_default(),
+            mesh: self.mesh.get().map(|mesh| mesh.stats()).unwrap_or_default(),
+            server: self.ws_server.as_ref().map(|server| server.stats()).unwrap_or_default(),
+            tasks: self.core.task_count(),
+        }
+    }
+
+    /// Wait until every write queued for storage so far has been persisted
+    ///
+    /// With [`GunOptions::background_persistence`] on, `put()` returns before its
+    /// storage write happens; this drains the queue and then flushes the backend
+    /// (see [`Storage::flush`]), so afterwards the data is on disk. Writes that
+    /// fail for good are reported through the
+    /// [`STORAGE_ERROR_EVENT`](crate::persist::STORAGE_ERROR_EVENT) event rather
+    /// than here.
+    ///
+    /// # Errors
+    /// Returns `GunError` if the backend's flush fails.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use gun::Gun;
+    /// # async fn example(gun: &Gun) -> Result<(), Box<dyn std::error::Error>> {
+    /// gun.get("user").put(serde_json::json!({"name": "Alice"})).await?;
+    /// gun.flush().await?; // Now persisted
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn flush(&self) -> GunResult<()> {
+        self.core.flush().await;
+        self.core.storage().flush().await
+    }
+
+    /// Subscribe to background storage write failures
+    ///
+    /// Called with the [`STORAGE_ERROR_EVENT`](crate::persist::STORAGE_ERROR_EVENT)
+    /// data (`{"soul", "op", "error", "attempts"}`) whenever a queued write still
+    /// fails after its retries.
+    ///
+    /// # Returns
+    /// The listener id.
+    pub fn on_storage_error<F>(&self, callback: F) -> u64
+    where
+        F: Fn(&serde_json::Value) + Send + Sync + 'static,
+    {
+        self.core.events.on(
+            STORAGE_ERROR_EVENT,
+            Box::new(move |event: &crate::events::Event| callback(&event.data)),
+        )
+    }
+
+    /// Subscribe to peers being disconnected for misbehaving
+    ///
+    /// Called with the [`PEER_BANNED_EVENT`] data (`{"peer", "url", "score",
+    /// "invalid_hash", "invalid_sig", "rejected", "oversized", "ban_ms"}`) when a
+    /// peer's score reaches `mesh.max_score`.
+    ///
+    /// # Returns
+    /// The listener id.
+    pub fn on_peer_banned<F>(&self, callback: F) -> u64
+    where
+        F: Fn(&serde_json::Value) + Send + Sync + 'static,
+    {
+        self.core.events.on(
+            PEER_BANNED_EVENT,
+            Box::new(move |event: &crate::events::Event| callback(&event.data)),
+        )
+    }
+
+    /// Subscribe to peers sending tampered content-addressed nodes
+    ///
+    /// Called with the [`CONTENT_HASH_MISMATCH_EVENT`] data (`{"peer", "soul",
+    /// "hash", "@"}`) whenever a put for a `#hash` soul carries data that
+    /// doesn't hash to it. The put is dropped.
+    ///
+    /// # Returns
+    /// The listener id.
+    pub fn on_content_hash_mismatch<F>(&self, callback: F) -> u64
+    where
+        F: Fn(&serde_json::Value) + Send + Sync + 'static,
+    {
+        self.core.events.on(
+            CONTENT_HASH_MISMATCH_EVENT,
+            Box::new(move |event: &crate::events::Event| callback(&event.data)),
+        )
+    }
+
+    /// Subscribe to errors sent by peers
+    ///
+    /// Called with the [`PEER_ERROR_EVENT`] data (`{"peer", "err"}`, plus `"@"`
+    /// with the ID of the message it is about when known) whenever a peer
+    /// answers with a `dam: "!"` error, such as a relay rejecting a put.
+    ///
+    /// # Returns
+    /// The listener id.
+    pub fn on_peer_error<F>(&self, callback: F) -> u64
+    where
+        F: Fn(&serde_json::Value) + Send + Sync + 'static,
+    {
+        self.core.events.on(
+            PEER_ERROR_EVENT,
+            Box::new(move |event: &crate::events::Event| callback(&event.data)),
+        )
+    }
+
+    /// Subscribe to peers dropped for going silent
+    ///
+    /// Called with the [`PEER_TIMEOUT_EVENT`] data (`{"peer", "url", "idle_ms"}`)
+    /// whenever a connected peer we heard nothing from for `mesh.lack` ms is removed.
+    ///
+    /// # Returns
+    /// The listener id.
+    pub fn on_peer_timeout<F>(&self, callback: F) -> u64
+    where
+        F: Fn(&serde_json::Value) + Send + Sync + 'static,
+    {
+        self.core.events.on(
+            PEER_TIMEOUT_EVENT,
+            Box::new(move |event: &crate::events::Event| callback(&event.data)),
+        )
+    }
+
+    /// Subscribe to peers coming online and going offline
+    ///
+    /// Called with a [`PeerLifecycleEvent`] for every [`PEER_LIFECYCLE_EVENT`]:
+    /// when a peer answers the `?` handshake, when it says `bye`, is removed or
+    /// its connection drops, when it is dropped for going silent, and when a
+    /// dropped connection comes back. Each peer goes offline once for each
+    /// time it came online, so the events can drive an online indicator, or
+    /// a resync when connectivity returns.
+    ///
+    /// # Returns
+    /// The listener id.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use gun::{Gun, PeerTransition};
+    ///
+    /// # fn example(gun: &Gun) {
+    /// gun.on_peer(|event| match event.transition {
+    ///     PeerTransition::Connected | PeerTransition::Reconnected { .. } => println!("{} online", event.url),
+    ///     PeerTransition::Disconnected | PeerTransition::TimedOut => println!("{} offline", event.url),
+    /// });
+    /// # }
+    /// ```
+    pub fn on_peer<F>(&self, callback: F) -> u64
+    where
+        F: Fn(PeerLifecycleEvent) + Send + Sync + 'static,
+    {
+        self.core.events.on(
+            PEER_LIFECYCLE_EVENT,
+            Box::new(move |event: &crate::events::Event| {
+                if let Some(peer_event) = PeerLifecycleEvent::from_event_data(&event.data) {
+                    callback(peer_event);
+                }
+            }),
+        )
+    }
+
+    /// Subscribe to direct connections to peers opening, ending or failing
+    ///
+    /// Called with a [`PeerEvent`] for every [`WEBRTC_PEER_EVENT`]: when a
+    /// WebRTC data channel opens, when it ends and the peer is only reached
+    /// through relays again, and when a connection fails before opening.
+    ///
+    /// # Returns
+    /// The listener id.
+    #[cfg(feature = "webrtc")]
+    pub fn on_peer_event<F>(&self, callback: F) -> u64
+    where
+        F: Fn(PeerEvent) + Send + Sync + 'static,
+    {
+        self.core.events.on(
+            WEBRTC_PEER_EVENT,
+            Box::new(move |event: &crate::events::Event| {
+                if let Some(peer_event) = PeerEvent::from_event_data(&event.data) {
+                    callback(peer_event);
+                }
+            }),
+        )
+    }
+
+    /// Import a Gun.js localStorage dump
+    ///
+    /// Merges every node from the dump into the graph and the storage backend by state, so newer local data wins, and notifies subscribers and peers.
+    /// See [`storage::parse_gunjs_graph`](crate::storage::parse_gunjs_graph) for
+    /// the accepted formats.
+    ///
+    /// # Arguments
+    /// * `path` - File holding the dump
+    ///
+    /// # Returns
+    /// The number of nodes imported, or `GunError` if the file can't be read or parsed.
+    pub async fn import_gunjs_localstorage(&self, path: impl AsRef<Path>) -> GunResult<usize> {
+        let nodes = parse_gunjs_graph(&std::fs::read_to_string(path)?)?;
+        self.flush().await?; // Don't race queued writes to the same souls
+        let storage = self.core.storage();
+        for node in &nodes {
+            let soul = match node.get_soul() {
+                Some(soul) => soul,
+                None => continue,
+            };
+            let merged = self.core.graph.merge(&soul, node, || self.core.state.next())?;
+            storage.put_delta(&soul, &node_changes(node)).await?;
+            self.core.emit_update(&soul, &merged.data);
+        }
+        Ok(nodes.len())
+    }
+
+    /// Export the graph and storage as a Gun.js localStorage dump
+    ///
+    /// Writes the value Gun.js keeps under the `gun/` localStorage key. Nodes from
+    /// storage and the in-memory graph are merged by state.
+    ///
+    /// # Returns
+    /// The number of nodes exported, or `GunError` if storage or the write fails.
+    pub async fn export_gunjs_localstorage(&self, path: impl AsRef<Path>) -> GunResult<usize> {
+        let merged = Graph::new();
+        let storage = self.core.storage();
+        for soul in storage.list().await? {
+            if let Some(node) = storage.get(&soul).await? {
+                merged.merge(&soul, &node, || self.core.state.next())?;
+            }
+        }
+        for (soul, node) in self.core.graph.all_nodes() {
+            merged.merge(&soul, &node, || self.core.state.next())?;
+        }
+
+        let nodes: Vec<Node> = merged.all_nodes().into_values().collect();
+        write_gunjs_graph(path, &nodes)?;
+        Ok(nodes.len())
+    }
+
     /// Gracefully shutdown the Gun instance
-    /// Closes the WebSocket server and cleans up resources
+    ///
+    /// In order: stops the periodic sweeps and health checks; sends peers a
+    /// DAM `bye`, closes WebSocket and WebRTC connections and stops reconnect
+    /// attempts; closes the WebSocket server, which stops accepting, gives open
+    /// connections up to 5 seconds to send what is queued and a close frame,
+    /// and frees the port; writes out everything queued for storage; cancels
+    /// the background tasks left and waits for every task of the instance to
+    /// end; and flushes the storage backend. Returns once all of that is done,
+    /// so [`stats`](Self::stats)`().tasks` is then `0` and the last put is on
+    /// disk.
+    ///
+    /// # Errors
+    /// `GunError::Io` with `TimedOut` if it takes longer than 10 seconds;
+    /// whatever is left is cancelled. Otherwise the storage backend's flush
+    /// error, if any.
     pub async fn shutdown(&mut self) -> GunResult<()> {
-        // Abort the WebSocket server task if running
-        if let Some(handle) = self.ws_server.take() {
+        let stopped = tokio::time::timeout(SHUTDOWN_TIMEOUT, self.stop()).await;
+        self.core.cancel();
+        stopped.unwrap_or_else(|_| {
+            tracing::warn!(tasks = self.core.task_count(), "Shutdown timed out");
+            Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!("Shutdown didn't finish within {:?}", SHUTDOWN_TIMEOUT),
+            )
+            .into())
+        })
+    }
+
+    /// The steps of [`shutdown`](Self::shutdown), without its timeout
+    async fn stop(&mut self) -> GunResult<()> {
+        for handle in [self.expiry_sweep.take(), self.peer_pruning.take(), self.failover_task.take()].into_iter().flatten() {
             handle.abort();
-            // Wait a bit for the task to finish cleanup
-            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
         }
 
-        // Mesh connections will be cleaned up automatically when dropped
-        // For explicit cleanup, we could add a mesh.shutdown() method if needed
+        // Stop advertising before the listener goes away
+        #[cfg(feature = "lan-discovery")]
+        if let Some(lan_discovery) = self.lan_discovery.take() {
+            lan_discovery.stop();
+        }
 
-        Ok(())
+        // Tell peers we're leaving and close our connections to them
+        #[cfg(feature = "webrtc")]
+        if let Some(ref manager) = self.webrtc_manager {
+            manager.close_all().await;
+        }
+        if let Some(mesh) = self.mesh.get() {
+            mesh.shutdown().await;
+        }
+        if let Some(server) = self.ws_server.take() {
+            server.shutdown(SERVER_SHUTDOWN_GRACE).await;
+        }
+        if let Some(handle) = self.ws_server_task.take() {
+            let _ = handle.await;
+        }
+
+        // Write out everything queued, including what peers sent before
+        // leaving, then let the writer end
+        self.core.flush().await;
+        self.core.stop_background_persistence();
+
+        // Connections end once they have sent their close frames; loops and
+        // timers are dropped
+        self.core.cancel();
+        self.core.wait_for_tasks().await;
+
+        self.core.storage().flush().await
     }
 }
 
+impl Drop for Gun {
+    /// Cancel the background tasks of an instance that wasn't shut down
+    ///
+    /// Connections aren't closed nor storage flushed; call
+    /// [`shutdown`](Gun::shutdown) for that.
+    fn drop(&mut self) {
+        self.core.cancel();
+    }
+}
+
+/// Runtime statistics returned by [`Gun::stats`]
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct GunStats {
+    /// Nodes, keys and bytes held in memory
+    pub graph: GraphStats,
+    /// Live `on()` listeners
+    pub subscriptions: usize,
+    /// Time since the instance was created
+    pub uptime: Duration,
+    /// Storage counters and latencies
+    pub storage: StorageStats,
+    /// Bad messages received from connected peers, by peer ID
+    pub peer_scores: HashMap<String, PeerScore>,
+    /// Peers, messages and bytes through the mesh, and the deduplication
+    /// table; all zero without networking
+    pub mesh: MeshStats,
+    /// Connections the relay server closed for misbehaving; all zero
+    /// without one
+    pub server: ServerStats,
+    /// Background tasks of the instance still running: connections, timers,
+    /// sweeps; `0` after [`Gun::shutdown`]
+    pub tasks: usize,
+}
+
+/// A peer of the mesh, as [`Gun::peers`] lists it
+#[derive(Clone, Debug)]
+pub struct PeerInfo {
+    /// ID in the mesh, which [`Gun::remove_peer`] takes
+    pub id: PeerId,
+    /// URL we dialed, or where an incoming connection came from
+    pub url: String,
+    /// DAM pid, once the `?` exchange is done
+    pub pid: Option<String>,
+    /// Whether a connection is open; `false` while reconnecting
+    pub connected: bool,
+    /// When we last heard from the peer
+    pub last_seen: Option<Instant>,
+    /// How messages reach it: [`Transport::WebRTC`] over a data channel,
+    /// [`Transport::WebSocket`] otherwise
+    pub transport: Transport,
+}
+
 // Note: Default implementation removed because Gun now requires BLS key pair
 // Users must explicitly provide secret_key and public_key
 
@@ -431,8 +1452,16 @@ pub struct GunOptions {
     /// Can be WebSocket URLs like: "ws://example.com/gun" or "wss://example.com/gun"
     /// These are OPTIONAL - Gun works fully P2P without any relays
     /// Relays are just helpful peers for NAT traversal and connectivity
+    /// `http://` and `https://` URLs are dialed as `ws://` and `wss://`;
+    /// other schemes make `Gun::with_options` fail with `InvalidPeerUrl`.
+    /// URLs naming the same endpoint are dialed once
     pub peers: Vec<String>,
 
+    /// Append Gun.js's `/gun` path to `peers` URLs without one, e.g.
+    /// `ws://relay.example.com:8765` dials `ws://relay.example.com:8765/gun`.
+    /// Default: `false`
+    pub append_gun_path: bool,
+
     /// Storage path for persistent storage (sled)
     pub storage_path: Option<String>,
 
@@ -443,22 +1472,115 @@ pub struct GunOptions {
     #[allow(non_snake_case)] // Matches Gun.js API naming convention
     pub localStorage: bool,
 
+    /// Custom storage backend
+    /// When set, this is used instead of the backend selected by `storage_path`,
+    /// `radisk` and `localStorage` (e.g. a `RedisStorage` shared between relays).
+    /// With none of those set, a capped in-memory `MemoryStorage` is used
+    pub storage: Option<Arc<dyn Storage>>,
+
+    /// Keep this instance's data under a namespace in the storage backend
+    /// Every soul is stored as `"{namespace}/{soul}"`, so instances opening the
+    /// same path (or sharing a backend) with different namespaces don't see each
+    /// other's data. The prefix never reaches the graph or the wire. Must be
+    /// non-empty and without `/`. Default: `None`
+    pub namespace: Option<String>,
+
     /// Super peer mode (relay server mode)
     /// When true, this peer acts as a relay server for others
     pub super_peer: bool,
 
-    /// Port to listen on (for relay server mode)
+    /// Port to listen on (for relay server mode), on every interface and
+    /// for any URL path
     pub port: Option<u16>,
 
-    /// WebRTC configuration for direct peer-to-peer connections
+    /// Further addresses to listen on in relay server mode, each with its
+    /// own interface, URL path and optional TLS certificate, e.g. `wss://`
+    /// on one port and plain `ws://` for localhost on another. The bound
+    /// addresses (with the port picked for port `0`) are in
+    /// `ws_server().local_addrs()`. Default: empty
+    pub listen: Vec<ListenConfig>,
+
+    /// Decides which WebSocket connections the relay server accepts
+    /// Called with each upgrade request's path, query parameters and headers;
+    /// it accepts (optionally tagging the peer) or rejects with an HTTP status
+    /// and reason. Rejected connections never reach the mesh. Default: `None`
+    /// (accept everyone)
+    pub server_auth: Option<ServerAuth>,
+
+    /// WebRTC configuration for direct peer-to-peer connections (`webrtc`
+    /// feature)
+    #[cfg(feature = "webrtc")]
     pub webrtc: WebRTCOptions,
 
-    /// Optional predicate function to filter incoming messages
+    /// Advertise this instance over mDNS/DNS-SD (`_gun._tcp`) and dial the
+    /// gun.rs instances found on the local network, at most
+    /// [`MAX_LAN_PEERS`](crate::lan::MAX_LAN_PEERS) of them. Without `port`
+    /// or `listen`, a WebSocket listener is started on a free port for them
+    /// to dial (`lan-discovery` feature). Default: `false`
+    #[cfg(feature = "lan-discovery")]
+    pub lan_discovery: bool,
+
+    /// TLS settings for `wss://` peers: extra root certificates (e.g. a
+    /// private CA), a server name to verify instead of the URL's host, and a
+    /// dangerous switch to skip verification. Default: system roots only
+    pub tls: TlsOptions,
+
+    /// HTTP (`CONNECT`) or SOCKS5 proxy to dial peers through, including
+    /// addresses learned through peer exchange. Hosts in its `no_proxy` list
+    /// (localhost by default) are dialed directly. Default: `None`
+    pub proxy: Option<ProxyConfig>,
+
+    /// How `peers` are used: all at once (default), or with
+    /// `PeerMode::Failover { max_active }` only the first `max_active`
+    /// healthy ones, the others kept as backups, probed every
+    /// `health_check_interval` and promoted when an active one dies
+    pub peer_policy: PeerPolicy,
+
+    /// Peer URLs with their own headers, query parameters or subprotocols,
+    /// dialed along with `peers` (an entry here wins over the same URL in
+    /// `peers`). Default: empty
+    pub peer_configs: Vec<PeerConfig>,
+
+    /// Subprotocols offered in `Sec-WebSocket-Protocol` when connecting to
+    /// `peers`, most preferred first; a relay picking one we didn't offer
+    /// fails the handshake. Default: empty (none offered)
+    pub peer_subprotocols: Vec<String>,
+
+    /// Subprotocols the relay server speaks, most preferred first: the first
+    /// one a client offers is echoed back. Default: empty
+    pub server_subprotocols: Vec<String>,
+
+    /// HTTP headers sent when connecting to `peers`, e.g. `("Authorization",
+    /// "Bearer ...")` for a relay with `server_auth`. Not sent to addresses
+    /// learned through peer exchange. Default: empty
+    pub peer_headers: Vec<(String, String)>,
+
+    /// Query parameters added to the `peers` URLs when connecting, e.g.
+    /// `("token", "...")`. Not added for addresses learned through peer
+    /// exchange. Default: empty
+    pub peer_query: Vec<(String, String)>,
+
+    /// DAM mesh settings
+    /// Set `mesh.gap` (ms) to batch messages to each peer into fewer frames,
+    /// up to `mesh.pack_size` bytes per frame. Default: no batching (`gap: 0`).
+    /// Peers sending bad messages are disconnected at `mesh.max_score` and
+    /// refused for `mesh.ban_time` ms (default: no ban).
+    /// `mesh.pex.enabled` turns on peer exchange: peers share the addresses
+    /// they connected to and we dial some of them (default: off)
+    pub mesh: MeshOptions,
+
+    /// Bounds of the table of seen message ids used to drop duplicates and loops
+    /// Busy relays should raise `dup.max_entries` to cover `dup.ttl` worth of
+    /// traffic. Default: 999 ids for 9 seconds
+    pub dup: DupOptions,
+
+    /// Optional filter for incoming messages
     /// 
-    /// If provided, the predicate receives the entire message object (as `serde_json::Value`)
-    /// and returns:
-    /// - `true` to accept the message (continue processing)
-    /// - `false` to reject the message (drop silently)
+    /// Either a plain `Fn(&Value) -> bool` closure (`true` accepts, `false`
+    /// drops the message silently) or a [`MessageFilter`](crate::types::MessageFilter)
+    /// that can await and sees who sent the message: the peer's ID and URL
+    /// and the verified signers' public keys. A filter may also reject with
+    /// an error, which is sent back to the peer as a `dam: "!"` message.
     /// 
     /// The predicate is called after signature verification but before message processing.
     /// This allows the application layer to implement custom message filtering logic,
@@ -470,28 +1592,143 @@ pub struct GunOptions {
     /// 
     /// # Example
     /// ```rust,no_run
-    /// use gun::MessagePredicate;
+    /// use gun::{MessageFilter, MessagePredicate, PredicateContext, PredicateDecision};
+    /// use serde_json::Value;
     /// use std::sync::Arc;
     /// 
     /// // Only accept "put" messages
-    /// let predicate: MessagePredicate = Arc::new(|msg| {
+    /// let predicate: MessagePredicate = Arc::new(|msg: &Value| {
     ///     msg.get("put").is_some()
     /// });
+    /// 
+    /// // Only accept messages signed by someone
+    /// struct SignedOnly;
+    /// 
+    /// #[async_trait::async_trait]
+    /// impl MessageFilter for SignedOnly {
+    ///     async fn check(&self, _msg: &Value, ctx: &PredicateContext) -> PredicateDecision {
+    ///         if ctx.public_keys.is_empty() {
+    ///             PredicateDecision::RejectWithError("Signature required".to_string())
+    ///         } else {
+    ///             PredicateDecision::Accept
+    ///         }
+    ///     }
+    /// }
+    /// let predicate: MessagePredicate = Arc::new(SignedOnly);
     /// ```
     pub message_predicate: Option<MessagePredicate>,
+
+    /// Reject messages without BLS signatures (default: `true`)
+    /// Stock Gun.js relays and browsers don't sign messages, so set this to
+    /// `false` to talk to them. Unsigned messages are then only deduplicated,
+    /// not authenticated: any peer can forge data, so rely on SEA-signed data or
+    /// trusted peers. Our own messages go out unsigned unless `signed_peers` is set
+    pub require_signatures: bool,
+
+    /// Peer URLs (matched on host) that must still sign their messages when
+    /// `require_signatures` is `false`, for meshes mixing gun.rs and Gun.js peers
+    pub signed_peers: Vec<String>,
+
+    /// Origin keys allowed to write through this instance
+    /// A message is accepted only if its origin (the signer named in its
+    /// `from`, the peer that created it) is listed; messages without one are
+    /// dropped. Relays outside the list
+    /// still pass others' messages along. `None` allows every key. Change it at
+    /// runtime with `Mesh::allow_key` and `Mesh::deny_key`. Default: `None`
+    pub peer_allowlist: Option<HashSet<PublicKeyBytes>>,
+
+    /// Keys whose messages are dropped, even if allowlisted, whether they
+    /// signed them as origin or as a relay. Default: empty
+    pub peer_denylist: HashSet<PublicKeyBytes>,
+
+    /// Only merge incoming writes to user space (`~pub` souls) that pub signed
+    /// with SEA, or that a certificate from pub allows, as Gun.js relays do.
+    /// Unauthorized keys are dropped and the sender is acked with an error.
+    /// Default: `false`
+    pub secure_user_space: bool,
+
+    /// Leading zero bits of proof of work every message must carry, for spam
+    /// resistance on open relays; peers with it set also do the work on what
+    /// they send. Clients of such a relay need the same setting. Messages
+    /// without enough work are dropped with an error naming the difficulty.
+    /// At most [`MAX_POW_BITS`](crate::dam::MAX_POW_BITS).
+    /// See `Mesh::with_pow_bits`. Default: `None`
+    pub pow_bits: Option<u8>,
+
+    /// Results of signature checks on incoming messages to keep, so copies
+    /// of a message arriving from several peers are verified once. 0 turns
+    /// the cache off. See `Mesh::with_verify_cache`. Default: 4096
+    pub verify_cache_entries: usize,
+
+    /// How often to tombstone keys written through `soul<?seconds` chains once they expire
+    /// Expired keys are hidden from reads immediately; the sweep removes them from
+    /// storage and tells peers. `None` disables the sweep. Default: 60 seconds
+    pub expiry_sweep_interval: Option<Duration>,
+
+    /// Which stored nodes to load into the graph during `with_options()`
+    /// `None` picks the default: relays (`super_peer`) load everything up to
+    /// 100,000 nodes so they can answer peers' gets from memory; other instances
+    /// don't preload and read through to storage on demand.
+    /// A default preload that fails is logged and skipped; one asked for fails
+    /// `with_options()`.
+    pub preload: Option<PreloadMode>,
+
+    /// Called after each preload batch with `(processed, total)` souls
+    pub preload_progress: Option<PreloadProgress>,
+
+    /// Storage operations slower than this are logged as a `tracing` warning
+    /// and counted in `stats().storage.slow_ops`. Default: 500ms
+    pub slow_storage_threshold: Duration,
+
+    /// Persist writes from a background task instead of inside `put()`
+    /// `put()` returns once the graph is updated and events are emitted; storage
+    /// writes are applied in order, retried on failure, and reported as a
+    /// `storage_error` event if they still fail. Use `Gun::flush()` to wait for
+    /// them. When `false`, `put()` awaits the write and returns its error. Default: `true`
+    pub background_persistence: bool,
 }
 
 impl Default for GunOptions {
     fn default() -> Self {
         Self {
             peers: vec![],
+            append_gun_path: false,
             storage_path: None,
             radisk: true,
             localStorage: true,
+            storage: None,
+            namespace: None,
             super_peer: false,
             port: None,
+            listen: vec![],
+            server_auth: None,
+            #[cfg(feature = "webrtc")]
             webrtc: WebRTCOptions::default(),
+            #[cfg(feature = "lan-discovery")]
+            lan_discovery: false,
+            tls: TlsOptions::default(),
+            proxy: None,
+            peer_policy: PeerPolicy::default(),
+            peer_configs: vec![],
+            peer_subprotocols: vec![],
+            server_subprotocols: vec![],
+            peer_headers: vec![],
+            peer_query: vec![],
+            mesh: MeshOptions::default(),
+            dup: DupOptions::default(),
             message_predicate: None,
+            require_signatures: true,
+            signed_peers: vec![],
+            peer_allowlist: None,
+            peer_denylist: HashSet::new(),
+            secure_user_space: false,
+            pow_bits: None,
+            verify_cache_entries: crate::verify_cache::DEFAULT_VERIFY_CACHE_ENTRIES,
+            expiry_sweep_interval: Some(Duration::from_secs(60)),
+            preload: None,
+            preload_progress: None,
+            slow_storage_threshold: DEFAULT_SLOW_STORAGE_THRESHOLD,
+            background_persistence: true,
         }
     }
 }
@@ -521,4 +1758,261 @@ impl GunOptions {
             ..Default::default()
         }
     }
+
+    /// Build options step by step, checking that they work together
+    ///
+    /// See [`GunOptionsBuilder`].
+    pub fn builder() -> GunOptionsBuilder {
+        GunOptionsBuilder::default()
+    }
+}
+
+/// Builder for [`GunOptions`], returned by [`GunOptions::builder`]
+///
+/// Unlike `GunOptions::default()`, nothing is persisted until a storage
+/// method picks a backend: a built instance without one keeps its data in
+/// memory. [`build`](Self::build) normalizes the peer URLs and rejects
+/// combinations the struct would ignore or misread, such as a port without
+/// relay mode or `radisk` without a path. Fields without a method here are
+/// set on the built options, e.g.
+/// `GunOptions { verify_cache_entries: 0, ..builder.build()? }`.
+///
+/// # Example
+/// ```rust,no_run
+/// use gun::{Gun, GunOptions};
+/// use chia_bls::SecretKey;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let secret_key = SecretKey::from_seed(&[1u8; 32]);
+/// let public_key = secret_key.public_key();
+///
+/// // GunOptions { peers: vec!["ws://relay.example.com/gun".to_string()],
+/// //              storage_path: Some("./gun_data".to_string()), ..Default::default() }
+/// let options = GunOptions::builder()
+///     .peer("ws://relay.example.com/gun")
+///     .storage_sled("./gun_data")
+///     .build()?;
+/// let gun = Gun::with_options(secret_key, public_key, options).await?;
+///
+/// // A mistake fails here, not once the instance is running
+/// assert!(GunOptions::builder().port(8765).build().is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct GunOptionsBuilder {
+    options: GunOptions,
+}
+
+impl Default for GunOptionsBuilder {
+    fn default() -> Self {
+        Self {
+            options: GunOptions {
+                radisk: false,
+                localStorage: false,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl GunOptionsBuilder {
+    /// Add a peer URL to dial, see [`GunOptions::peers`]
+    pub fn peer(mut self, url: impl Into<String>) -> Self {
+        self.options.peers.push(url.into());
+        self
+    }
+
+    /// Add peer URLs to dial
+    pub fn peers<I, S>(mut self, urls: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.options.peers.extend(urls.into_iter().map(Into::into));
+        self
+    }
+
+    /// Append Gun.js's `/gun` path to peer URLs without one, see
+    /// [`GunOptions::append_gun_path`]
+    pub fn append_gun_path(mut self, append: bool) -> Self {
+        self.options.append_gun_path = append;
+        self
+    }
+
+    /// Persist to a sled database at `path`
+    pub fn storage_sled(mut self, path: impl Into<String>) -> Self {
+        self.options.storage_path = Some(path.into());
+        self.options.radisk = true;
+        self
+    }
+
+    /// Persist to localStorage-like files at `path`
+    pub fn storage_local(mut self, path: impl Into<String>) -> Self {
+        self.options.storage_path = Some(path.into());
+        self.options.radisk = false;
+        self
+    }
+
+    /// Persist to a custom backend, e.g. a `RedisStorage` shared by relays
+    pub fn storage(mut self, storage: Arc<dyn Storage>) -> Self {
+        self.options.storage = Some(storage);
+        self
+    }
+
+    /// Set the storage path on its own, leaving the backend to `radisk`
+    pub fn storage_path(mut self, path: impl Into<String>) -> Self {
+        self.options.storage_path = Some(path.into());
+        self
+    }
+
+    /// Use sled for the storage path, see [`GunOptions::radisk`]
+    pub fn radisk(mut self, enabled: bool) -> Self {
+        self.options.radisk = enabled;
+        self
+    }
+
+    /// Keep this instance's data under `namespace` in the backend, see
+    /// [`GunOptions::namespace`]
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.options.namespace = Some(namespace.into());
+        self
+    }
+
+    /// Relay for other peers, listening on `port` on every interface
+    pub fn relay(mut self, port: u16) -> Self {
+        self.options.super_peer = true;
+        self.options.port = Some(port);
+        self
+    }
+
+    /// Relay for other peers, see [`GunOptions::super_peer`]
+    pub fn super_peer(mut self, enabled: bool) -> Self {
+        self.options.super_peer = enabled;
+        self
+    }
+
+    /// Listen on `port`; takes relay mode
+    pub fn port(mut self, port: u16) -> Self {
+        self.options.port = Some(port);
+        self
+    }
+
+    /// Listen on a further address; takes relay mode, see [`GunOptions::listen`]
+    pub fn listen(mut self, listen: ListenConfig) -> Self {
+        self.options.listen.push(listen);
+        self
+    }
+
+    /// WebRTC settings (`webrtc` feature)
+    #[cfg(feature = "webrtc")]
+    pub fn webrtc(mut self, webrtc: WebRTCOptions) -> Self {
+        self.options.webrtc = webrtc;
+        self
+    }
+
+    /// Find and dial peers on the local network, see
+    /// [`GunOptions::lan_discovery`] (`lan-discovery` feature)
+    #[cfg(feature = "lan-discovery")]
+    pub fn lan_discovery(mut self, enabled: bool) -> Self {
+        self.options.lan_discovery = enabled;
+        self
+    }
+
+    /// Bounds of the deduplication table, see [`GunOptions::dup`]
+    pub fn dup(mut self, dup: DupOptions) -> Self {
+        self.options.dup = dup;
+        self
+    }
+
+    /// Proof of work on every message, see [`GunOptions::pow_bits`]
+    pub fn pow_bits(mut self, bits: u8) -> Self {
+        self.options.pow_bits = Some(bits);
+        self
+    }
+
+    /// TLS settings for `wss://` peers
+    pub fn tls(mut self, tls: TlsOptions) -> Self {
+        self.options.tls = tls;
+        self
+    }
+
+    /// Reject unsigned messages, see [`GunOptions::require_signatures`]
+    pub fn require_signatures(mut self, required: bool) -> Self {
+        self.options.require_signatures = required;
+        self
+    }
+
+    /// A peer that must still sign with `require_signatures(false)`, see
+    /// [`GunOptions::signed_peers`]
+    pub fn signed_peer(mut self, url: impl Into<String>) -> Self {
+        self.options.signed_peers.push(url.into());
+        self
+    }
+
+    /// Only merge user space writes their owner signed, see
+    /// [`GunOptions::secure_user_space`]
+    pub fn secure_user_space(mut self, enabled: bool) -> Self {
+        self.options.secure_user_space = enabled;
+        self
+    }
+
+    /// Filter incoming messages, see [`GunOptions::message_predicate`]
+    pub fn message_predicate(mut self, predicate: MessagePredicate) -> Self {
+        self.options.message_predicate = Some(predicate);
+        self
+    }
+
+    /// Check the options and normalize the peer URLs
+    ///
+    /// Peer URLs are normalized as [`normalize_peer_url`] does, with
+    /// `append_gun_path`, and spellings of one endpoint kept once.
+    ///
+    /// # Errors
+    /// - `ConfigError::InvalidPeerUrl`: A peer URL can't be dialed
+    /// - `ConfigError::ListenWithoutRelay`: A port or listen address without relay mode
+    /// - `ConfigError::RadiskWithoutStoragePath`: `radisk` without a storage path
+    /// - `ConfigError::ConflictingStorage`: A storage backend as well as a storage path
+    /// - `ConfigError::SignedPeersRequireSignatures`: Signed peers while every peer must sign
+    /// - `ConfigError::InvalidNamespace`: An empty namespace, or one containing `/`
+    /// - `ConfigError::ZeroDupEntries`: A deduplication table of 0 entries
+    /// - `ConfigError::PowBitsTooHigh`: Proof of work above `MAX_POW_BITS`
+    pub fn build(self) -> Result<GunOptions, ConfigError> {
+        let mut options = self.options;
+
+        let mut peers: Vec<String> = Vec::with_capacity(options.peers.len());
+        for url in &options.peers {
+            let url = normalize_peer_url(url, options.append_gun_path).map_err(|e| match e {
+                GunError::InvalidPeerUrl(url) => ConfigError::InvalidPeerUrl(url),
+                e => ConfigError::InvalidPeerUrl(e.to_string()),
+            })?;
+            if !peers.contains(&url) {
+                peers.push(url);
+            }
+        }
+        options.peers = peers;
+
+        if (options.port.is_some() || !options.listen.is_empty()) && !options.super_peer {
+            return Err(ConfigError::ListenWithoutRelay);
+        }
+        if options.radisk && options.storage_path.is_none() {
+            return Err(ConfigError::RadiskWithoutStoragePath);
+        }
+        if options.storage.is_some() && options.storage_path.is_some() {
+            return Err(ConfigError::ConflictingStorage);
+        }
+        if options.require_signatures && !options.signed_peers.is_empty() {
+            return Err(ConfigError::SignedPeersRequireSignatures);
+        }
+        if let Some(namespace) = options.namespace.as_ref().filter(|namespace| !is_valid_namespace(namespace)) {
+            return Err(ConfigError::InvalidNamespace(namespace.clone()));
+        }
+        if options.dup.max_entries == 0 {
+            return Err(ConfigError::ZeroDupEntries);
+        }
+        if let Some(bits) = options.pow_bits.filter(|&bits| bits > MAX_POW_BITS) {
+            return Err(ConfigError::PowBitsTooHigh(bits));
+        }
+        Ok(options)
+    }
 }
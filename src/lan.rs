@@ -0,0 +1,170 @@
+//! Peer discovery on the local network
+//!
+//! With `GunOptions::lan_discovery` on, an instance advertises its WebSocket
+//! listener over mDNS/DNS-SD as a [`SERVICE_TYPE`] service and browses for
+//! the others, dialing each one it finds. Two instances on the same Wi-Fi
+//! then sync without a relay between them.
+//!
+//! Services are named after the DAM pid of their mesh, so an instance knows
+//! its own and the peers it is already connected to. Of two instances that
+//! find each other, only the one with the smaller pid dials, so they share a
+//! single connection.
+
+use crate::core::GunCore;
+use crate::dam::Mesh;
+use crate::error::{GunError, GunResult};
+use crate::gun::{Gun, GunOptions};
+use crate::websocket::PeerStatus;
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// DNS-SD service type gun.rs instances advertise and browse for
+pub const SERVICE_TYPE: &str = "_gun._tcp.local.";
+
+/// Most peers one instance dials after finding them on the network
+pub const MAX_LAN_PEERS: usize = 16;
+
+/// A service advertised for, and browsing on behalf of, one instance
+///
+/// Stopped with [`stop`](Self::stop) or when dropped.
+pub struct LanDiscovery {
+    daemon: ServiceDaemon,
+}
+
+impl LanDiscovery {
+    /// Advertise the listener on `port` and dial the instances found
+    ///
+    /// Dials go through [`Gun::add_peer`] with `options` and are tracked by
+    /// `core`, so they end with the instance. An instance counts against
+    /// [`MAX_LAN_PEERS`] until its service is withdrawn, its dial fails, or
+    /// its connection ends; found again after that, it is dialed again.
+    ///
+    /// # Errors
+    /// `GunError::Network` if the mDNS daemon can't start, or the service
+    /// can't be registered or browsed for.
+    pub fn start(core: &Arc<GunCore>, mesh: &Arc<Mesh>, options: GunOptions, port: u16) -> GunResult<Self> {
+        let daemon = ServiceDaemon::new().map_err(mdns_error)?;
+        // Our addresses are filled in, and kept up to date, by the daemon
+        let service = ServiceInfo::new(SERVICE_TYPE, &mesh.pid, &format!("{}.local.", mesh.pid), "", port, None)
+            .map_err(mdns_error)?
+            .enable_addr_auto();
+        daemon.register(service).map_err(mdns_error)?;
+        let found = daemon.browse(SERVICE_TYPE).map_err(mdns_error)?;
+
+        // The daemon's channel blocks; a thread hands its events over until
+        // the daemon shuts down
+        let (tx, mut events) = mpsc::unbounded_channel();
+        std::thread::spawn(move || {
+            while let Ok(event) = found.recv() {
+                if tx.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let tasks = core.clone();
+        let mesh = mesh.clone();
+        core.spawn_until_cancelled(async move {
+            // Pids dialed, and whether the dial connected
+            let dialed: Arc<Mutex<HashMap<String, bool>>> = Arc::default();
+            while let Some(event) = events.recv().await {
+                let info = match event {
+                    ServiceEvent::ServiceResolved(info) => info,
+                    ServiceEvent::ServiceRemoved(_, fullname) => {
+                        if let Some(pid) = service_pid(&fullname) {
+                            dialed.lock().remove(&pid);
+                        }
+                        continue;
+                    }
+                    _ => continue,
+                };
+                let Some(pid) = service_pid(info.get_fullname()) else {
+                    continue;
+                };
+                // Connections that ended free their slot
+                let peers = mesh.peers().await;
+                dialed.lock().retain(|pid, connected| {
+                    !*connected || peers.iter().any(|peer| peer.pid.as_deref() == Some(pid.as_str()))
+                });
+                // Ourselves, an instance that dials us, or one dialed already
+                if pid.as_str() <= mesh.pid.as_str() || dialed.lock().contains_key(&pid) {
+                    continue;
+                }
+                let Some(url) = lan_url(&info) else {
+                    continue;
+                };
+                if dialed.lock().len() >= MAX_LAN_PEERS {
+                    tracing::debug!(%pid, %url, "LAN peer not dialed, at the limit");
+                    continue;
+                }
+                let reconnecting = matches!(mesh.peer_status().get(&url), Some(PeerStatus::Connecting | PeerStatus::Connected));
+                let known = reconnecting
+                    || peers.iter().any(|peer| peer.pid.as_deref() == Some(pid.as_str()) || peer.url == url);
+                if known {
+                    continue;
+                }
+
+                tracing::info!(%pid, %url, "Found LAN peer");
+                dialed.lock().insert(pid.clone(), false);
+                let (core, mesh, options, dialed) = (tasks.clone(), mesh.clone(), options.clone(), dialed.clone());
+                tasks.spawn(async move {
+                    match Gun::dial_peer(&core, &mesh, &options, &url).await {
+                        Ok(_) => {
+                            if let Some(connected) = dialed.lock().get_mut(&pid) {
+                                *connected = true;
+                            }
+                        }
+                        Err(e) => {
+                            dialed.lock().remove(&pid);
+                            tracing::warn!(%url, error = %e, "Failed to connect to LAN peer");
+                        }
+                    }
+                });
+            }
+        });
+
+        Ok(Self { daemon })
+    }
+
+    /// Withdraw the service and stop browsing
+    pub fn stop(&self) {
+        // Errors only if the daemon is already shut down
+        let _ = self.daemon.shutdown();
+    }
+}
+
+impl Drop for LanDiscovery {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// The pid a service is named after, from its full name
+fn service_pid(fullname: &str) -> Option<String> {
+    fullname.strip_suffix(SERVICE_TYPE)?.strip_suffix('.').map(str::to_string)
+}
+
+/// URL to dial a resolved service at: an IPv4 address if it has one, else a
+/// routable IPv6 one
+fn lan_url(info: &ServiceInfo) -> Option<String> {
+    let port = info.get_port();
+    if let Some(ip) = info.get_addresses_v4().into_iter().min() {
+        return Some(format!("ws://{}:{}/gun", ip, port));
+    }
+    info.get_addresses()
+        .iter()
+        .filter_map(|ip| match ip {
+            IpAddr::V6(ip) if !ip.is_loopback() && ip.segments()[0] & 0xffc0 != 0xfe80 => Some(ip),
+            _ => None,
+        })
+        .min()
+        .map(|ip| format!("ws://[{}]:{}/gun", ip, port))
+}
+
+fn mdns_error(e: mdns_sd::Error) -> GunError {
+    GunError::Network(format!("mDNS: {}", e))
+}
@@ -14,7 +14,10 @@
 //! - **Cryptographic Security**: BLS signatures for message authentication
 //! - **Pluggable Storage**: Memory, file-based (localStorage-like), or Sled database
 //! - **WebRTC Support**: Direct peer-to-peer connections with NAT traversal
+//!   (`webrtc` feature, on by default)
 //! - **WebSocket Support**: Relay server connections for NAT traversal
+//! - **LAN Discovery**: Peers on the same network found over mDNS
+//!   (`lan-discovery` feature)
 //!
 //! ## Quick Start
 //!
@@ -132,32 +135,96 @@
 //! - [Gun.js Documentation](https://gun.eco/docs)
 //! - [Gun.js GitHub](https://github.com/amark/gun)
 
+#[cfg(feature = "native")]
 pub mod chain;
+#[cfg(feature = "native")]
 pub mod core;
+#[cfg(feature = "native")]
 pub mod dam;
+#[cfg(feature = "native")]
 pub mod dup;
+#[cfg(feature = "native")]
 pub mod error;
+#[cfg(feature = "native")]
 pub mod events;
+#[cfg(feature = "native")]
+pub mod failover;
+#[cfg(feature = "native")]
 pub mod graph;
+#[cfg(feature = "native")]
 pub mod gun;
+#[cfg(feature = "lan-discovery")]
+pub mod lan;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "native")]
+pub mod persist;
 pub mod sea;
+#[cfg(feature = "native")]
 pub mod state;
+#[cfg(feature = "native")]
 pub mod storage;
+#[cfg(feature = "native")]
 pub mod types;
+#[cfg(feature = "native")]
 pub mod valid;
+pub mod verify_cache;
+#[cfg(feature = "webrtc")]
 pub mod webrtc;
+#[cfg(feature = "native")]
 pub mod websocket;
+#[cfg(feature = "native")]
+pub mod wire;
 
-pub use chain::Chain;
-pub use error::GunError;
-pub use gun::{Gun, GunOptions};
+#[cfg(feature = "native")]
+pub use chain::{Chain, ReadMeta};
+#[cfg(feature = "native")]
+pub use core::{PreloadMode, PreloadProgress};
+#[cfg(feature = "native")]
+pub use dam::{PeerId, PeerLifecycleEvent, PeerTransition};
+#[cfg(feature = "native")]
+pub use error::{ConfigError, GunError};
+#[cfg(feature = "native")]
+pub use failover::{PeerHealth, PeerMode, PeerPolicy};
+#[cfg(feature = "native")]
+pub use gun::{Gun, GunOptions, GunOptionsBuilder, GunStats, PeerInfo};
 pub use sea::*;
-pub use types::MessagePredicate;
+#[cfg(feature = "native")]
+pub use types::{
+    AuthDecision, AuthRequest, MessageFilter, MessagePredicate, PredicateContext, PredicateDecision, PublicKeyBytes,
+    ServerAuth, Transport,
+};
+#[cfg(feature = "native")]
 pub use valid::valid;
+#[cfg(feature = "native")]
 pub use valid::{is_valid_data, valid_soul};
-pub use webrtc::{WebRTCManager, WebRTCOptions, WebRTCPeer};
+#[cfg(feature = "webrtc")]
+pub use webrtc::{PeerEvent, WebRTCManager, WebRTCOptions, WebRTCPeer};
+#[cfg(feature = "native")]
+pub use websocket::{ListenConfig, PeerConfig, ProxyConfig, TlsOptions, TlsServerConfig};
+#[cfg(feature = "native")]
+pub use wire::WireEncoding;
 
-#[cfg(test)]
+/// Print log output to stderr, filtered by `RUST_LOG`
+///
+/// Gun logs through `tracing` and prints nothing unless a subscriber is
+/// installed. This installs a human-readable one; without `RUST_LOG` only
+/// warnings and errors are shown. `RUST_LOG=gun=trace` shows every message
+/// heard and sent, with `peer_id`, `msg_id` and `soul` fields.
+///
+/// Requires the `log-subscriber` feature. Does nothing if a global subscriber
+/// is already set.
+#[cfg(feature = "log-subscriber")]
+pub fn init_logging() {
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("gun=warn"));
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .try_init();
+}
+
+#[cfg(all(test, feature = "native"))]
 mod tests {
     use super::*;
     use chia_bls::{SecretKey, PublicKey};
@@ -0,0 +1,250 @@
+//! Prometheus metrics and health checks for relay servers
+//!
+//! With the `metrics` feature, every [`WebSocketServer`](crate::websocket::WebSocketServer)
+//! listener also answers two plain HTTP `GET`s next to its WebSocket upgrades:
+//!
+//! - `/metrics`: mesh, graph, storage and per-connection counters in the
+//!   Prometheus text format (version 0.0.4)
+//! - `/healthz`: `200 OK` while the accept loop is running, `503` once it
+//!   is shutting down
+//!
+//! Any other request goes on to the WebSocket handshake as before.
+
+use crate::dam::MeshStats;
+use crate::storage::{LatencyStats, StorageStats, LATENCY_BUCKETS_MS};
+use crate::websocket::{ConnectionInfo, ServerStats};
+use std::fmt::Write;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, ReadBuf};
+
+/// Largest request head read before giving up on finding its end
+const MAX_HEAD: usize = 16 * 1024;
+
+/// Render the metrics page
+///
+/// `storage` is `None` when the server wasn't given the storage counters.
+pub fn render(
+    mesh: &MeshStats,
+    graph_nodes: usize,
+    storage: Option<&StorageStats>,
+    server: &ServerStats,
+    connections: &[ConnectionInfo],
+) -> String {
+    let mut out = String::new();
+
+    for (name, help, value) in [
+        ("messages_heard", "Messages received, counting each message of a batch", mesh.messages_heard),
+        ("messages_said", "Messages sent, including replies and acks", mesh.messages_said),
+        ("messages_forwarded", "Copies of received messages passed on to other peers", mesh.messages_forwarded),
+        ("bytes_in", "Bytes of the frames received", mesh.bytes_in),
+        ("bytes_out", "Bytes of the frames handed to peer connections", mesh.bytes_out),
+        ("duplicates", "Messages dropped as already seen", mesh.duplicates),
+        ("invalid_messages", "Frames and messages that couldn't be read", mesh.invalid_messages),
+        ("messages_failed", "Messages whose handling failed with an error", mesh.messages_failed),
+        ("hash_failures", "Messages dropped because # doesn't match their hash", mesh.hash_failures),
+        ("signature_failures", "Messages dropped for missing or invalid signatures", mesh.signature_failures),
+        ("puts_sent", "Put messages sent, including get responses", mesh.puts_sent),
+        ("puts_applied", "Put messages received and merged into the graph", mesh.puts_applied),
+        ("gets_sent", "Gets sent, counting a batch once", mesh.gets_sent),
+        ("gets_answered", "Gets answered with data", mesh.gets_answered),
+        ("gets_coalesced", "Gets that joined an identical get already on its way", mesh.gets_coalesced),
+        ("gets_known_missing", "Gets answered not found from the cache of missing souls", mesh.gets_known_missing),
+        ("queue_dropped", "Messages dropped from full queues of disconnected peers", mesh.queue_dropped),
+        ("queue_coalesced", "Queued puts merged into others", mesh.queue_coalesced),
+        ("chunked_sent", "Messages sent in chunks", mesh.chunked_sent),
+        ("chunked_received", "Chunked messages received whole", mesh.chunked_received),
+        ("chunked_expired", "Chunked messages that didn't arrive whole in time", mesh.chunked_expired),
+        ("origin_rejected", "Messages whose origin key isn't allowed to write", mesh.origin_rejected),
+        ("ttl_expired", "Messages not forwarded because their ttl ran out", mesh.ttl_expired),
+        ("transport_sent", "Frames sent over a peer's alternate transport", mesh.transport_sent),
+        ("transport_fallbacks", "Frames sent over WebSocket after the alternate transport failed", mesh.transport_fallbacks),
+    ] {
+        counter(&mut out, &format!("gun_mesh_{}_total", name), help, &[("", value)]);
+    }
+    let queued: Vec<(String, u64)> = mesh
+        .queued
+        .iter()
+        .map(|(peer, queued)| (format!("peer=\"{}\"", escape(peer)), *queued as u64))
+        .collect();
+    gauge(&mut out, "gun_mesh_queued_messages", "Messages waiting for a disconnected peer", &queued);
+    let rtt: Vec<(String, f64)> = mesh
+        .rtt
+        .iter()
+        .map(|(peer, rtt)| (format!("peer=\"{}\"", escape(peer)), rtt.as_secs_f64()))
+        .collect();
+    gauge(&mut out, "gun_mesh_peer_rtt_seconds", "Round trip of the last answered keepalive ping", &rtt);
+
+    gauge(&mut out, "gun_graph_nodes", "Nodes held in memory", &[("", graph_nodes as u64)]);
+
+    if let Some(storage) = storage {
+        for (name, help, value) in [
+            ("gets", "Storage get calls", storage.gets),
+            ("puts", "Storage put and put_delta calls", storage.puts),
+            ("deletes", "Storage delete calls", storage.deletes),
+            ("has", "Storage has calls", storage.has),
+            ("lists", "Storage list calls", storage.lists),
+            ("errors", "Storage calls that returned an error", storage.errors),
+            ("slow_ops", "Storage calls slower than the slow-operation threshold", storage.slow_ops),
+            ("bytes_read", "JSON bytes of the nodes read from storage", storage.bytes_read),
+            ("bytes_written", "JSON bytes of the nodes and deltas written to storage", storage.bytes_written),
+        ] {
+            counter(&mut out, &format!("gun_storage_{}_total", name), help, &[("", value)]);
+        }
+        for (name, help, latency) in [
+            ("get", "Latency of storage get calls", &storage.get_latency),
+            ("put", "Latency of storage put calls", &storage.put_latency),
+            ("delete", "Latency of storage delete calls", &storage.delete_latency),
+        ] {
+            histogram(&mut out, &format!("gun_storage_{}_latency_seconds", name), help, latency);
+        }
+    }
+
+    gauge(&mut out, "gun_connections", "Open WebSocket connections", &[("", connections.len() as u64)]);
+    counter(
+        &mut out,
+        "gun_connections_closed_total",
+        "Connections closed for misbehaving, by reason",
+        &[("reason=\"frame_too_large\"", server.frames_rejected), ("reason=\"rate_limited\"", server.rate_limited)],
+    );
+    let labels = |connection: &ConnectionInfo| {
+        format!("peer=\"{}\",remote=\"{}\"", escape(&connection.peer_id), connection.remote_addr)
+    };
+    let bytes_in: Vec<(String, u64)> = connections.iter().map(|c| (labels(c), c.bytes_in)).collect();
+    counter(&mut out, "gun_connection_bytes_in_total", "Frame bytes received on a connection", &bytes_in);
+    let bytes_out: Vec<(String, u64)> = connections.iter().map(|c| (labels(c), c.bytes_out)).collect();
+    counter(&mut out, "gun_connection_bytes_out_total", "Frame bytes sent on a connection", &bytes_out);
+    let ages: Vec<(String, f64)> = connections
+        .iter()
+        .map(|c| (labels(c), c.connected_at.elapsed().as_secs_f64()))
+        .collect();
+    gauge(&mut out, "gun_connection_age_seconds", "Time since the connection was accepted", &ages);
+    out
+}
+
+fn counter<L: AsRef<str>, V: std::fmt::Display>(out: &mut String, name: &str, help: &str, samples: &[(L, V)]) {
+    family(out, name, "counter", help, samples);
+}
+
+fn gauge<L: AsRef<str>, V: std::fmt::Display>(out: &mut String, name: &str, help: &str, samples: &[(L, V)]) {
+    family(out, name, "gauge", help, samples);
+}
+
+/// One metric family: its `HELP` and `TYPE` lines, then a line per sample
+fn family<L: AsRef<str>, V: std::fmt::Display>(out: &mut String, name: &str, kind: &str, help: &str, samples: &[(L, V)]) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    for (labels, value) in samples {
+        match labels.as_ref() {
+            "" => {
+                let _ = writeln!(out, "{} {}", name, value);
+            }
+            labels => {
+                let _ = writeln!(out, "{}{{{}}} {}", name, labels, value);
+            }
+        }
+    }
+}
+
+/// A latency histogram, with cumulative buckets as Prometheus wants them
+fn histogram(out: &mut String, name: &str, help: &str, latency: &LatencyStats) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} histogram", name);
+    let mut cumulative = 0;
+    for (bound, count) in LATENCY_BUCKETS_MS.iter().zip(&latency.buckets) {
+        cumulative += count;
+        let _ = writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, *bound as f64 / 1000.0, cumulative);
+    }
+    let _ = writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, latency.count);
+    let _ = writeln!(out, "{}_sum {}", name, latency.total.as_secs_f64());
+    let _ = writeln!(out, "{}_count {}", name, latency.count);
+}
+
+/// Escape a label value
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Read a request head, up to and including its blank line
+///
+/// Whatever else arrived with it stays at the end of the buffer. Stops early
+/// at end of stream or after [`MAX_HEAD`] bytes.
+pub(crate) async fn read_head<S: AsyncRead + Unpin>(stream: &mut S) -> std::io::Result<Vec<u8>> {
+    let mut head = Vec::with_capacity(1024);
+    let mut buf = [0u8; 1024];
+    while head.len() < MAX_HEAD && !head.windows(4).any(|window| window == b"\r\n\r\n") {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        head.extend_from_slice(&buf[..n]);
+    }
+    Ok(head)
+}
+
+/// The path of a plain HTTP `GET` (not a WebSocket upgrade) in a request head
+pub(crate) fn plain_get(head: &[u8]) -> Option<&str> {
+    let head = std::str::from_utf8(head).ok()?;
+    let (request_line, headers) = head.split_once("\r\n")?;
+    let mut parts = request_line.split(' ');
+    if parts.next()? != "GET" {
+        return None;
+    }
+    let path = parts.next()?;
+    let upgrade = headers.split("\r\n").any(|line| {
+        line.split_once(':')
+            .is_some_and(|(name, value)| name.trim().eq_ignore_ascii_case("upgrade") && value.trim().eq_ignore_ascii_case("websocket"))
+    });
+    (!upgrade).then(|| path.split('?').next().unwrap_or(path))
+}
+
+/// A plain HTTP response that closes the connection
+pub(crate) fn response(status: &str, content_type: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )
+}
+
+/// A stream that first gives back bytes already read from it
+pub(crate) struct Replay<S> {
+    head: Vec<u8>,
+    pos: usize,
+    inner: S,
+}
+
+impl<S> Replay<S> {
+    pub(crate) fn new(head: Vec<u8>, inner: S) -> Self {
+        Self { head, pos: 0, inner }
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for Replay<S> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+        if self.pos < self.head.len() {
+            let n = buf.remaining().min(self.head.len() - self.pos);
+            let start = self.pos;
+            buf.put_slice(&self.head[start..start + n]);
+            self.pos += n;
+            return Poll::Ready(Ok(()));
+        }
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for Replay<S> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
@@ -0,0 +1,148 @@
+//! Background persistence
+//!
+//! With background persistence on (the [`Gun`](crate::Gun) default), a write updates
+//! the graph, emits its events and returns; the storage write is queued and applied
+//! in order by a dedicated task, so `put()` never waits for the disk. Failed writes
+//! are retried with backoff, and writes that still fail are reported as a
+//! [`STORAGE_ERROR_EVENT`] instead of being lost.
+//!
+//! [`GunCore::flush`](crate::core::GunCore::flush) waits until everything queued so
+//! far has been written.
+
+use crate::error::GunResult;
+use crate::events::{Event, EventEmitter};
+use crate::state::Node;
+use crate::storage::Storage;
+use parking_lot::RwLock;
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+
+/// Writes that can be queued before `put()` waits for the background task
+pub const PERSIST_QUEUE_CAPACITY: usize = 10_000;
+
+/// Attempts made for each queued write before it is reported as failed
+pub const PERSIST_MAX_ATTEMPTS: u32 = 4;
+
+/// Delay before the first retry; doubled for each further attempt
+const PERSIST_RETRY_DELAY: Duration = Duration::from_millis(25);
+
+/// Event emitted when a background write fails for good
+///
+/// The event data is `{"soul": ..., "op": "put" | "put_delta" | "delete", "error": ..., "attempts": n}`.
+pub const STORAGE_ERROR_EVENT: &str = "storage_error";
+
+/// The storage slot shared by [`GunCore`](crate::core::GunCore) and its background writer
+pub(crate) type SharedStorage = Arc<RwLock<Arc<dyn Storage>>>;
+
+/// A change to persist
+#[derive(Clone, Debug)]
+pub enum StorageWrite {
+    /// Replace the whole node
+    Put { soul: String, node: Node },
+    /// Merge changed keys (see [`Storage::put_delta`])
+    Delta {
+        soul: String,
+        changed: Vec<(String, Value, f64)>,
+    },
+    /// Remove the node
+    Delete { soul: String },
+}
+
+impl StorageWrite {
+    /// Soul the write is for
+    pub fn soul(&self) -> &str {
+        match self {
+            StorageWrite::Put { soul, .. }
+            | StorageWrite::Delta { soul, .. }
+            | StorageWrite::Delete { soul } => soul,
+        }
+    }
+
+    /// Name of the storage operation, as used in [`STORAGE_ERROR_EVENT`]
+    pub fn op(&self) -> &'static str {
+        match self {
+            StorageWrite::Put { .. } => "put",
+            StorageWrite::Delta { .. } => "put_delta",
+            StorageWrite::Delete { .. } => "delete",
+        }
+    }
+
+    /// Apply the write to a backend
+    pub async fn apply(&self, storage: &dyn Storage) -> GunResult<()> {
+        match self {
+            StorageWrite::Put { soul, node } => storage.put(soul, node).await,
+            StorageWrite::Delta { soul, changed } => storage.put_delta(soul, changed).await,
+            StorageWrite::Delete { soul } => storage.delete(soul).await,
+        }
+    }
+}
+
+/// Messages handled by the background writer
+pub(crate) enum PersistMessage {
+    Write(StorageWrite),
+    /// Answered once every earlier message has been handled
+    Flush(oneshot::Sender<()>),
+}
+
+/// Start the background writer
+///
+/// The task runs until every sender is dropped, writing whatever is still queued
+/// before it exits.
+pub(crate) fn spawn(
+    tasks: &tokio_util::task::TaskTracker,
+    storage: SharedStorage,
+    events: Arc<EventEmitter>,
+) -> (mpsc::Sender<PersistMessage>, tokio::task::JoinHandle<()>) {
+    let (tx, mut rx) = mpsc::channel(PERSIST_QUEUE_CAPACITY);
+    let handle = tasks.spawn(async move {
+        while let Some(message) = rx.recv().await {
+            match message {
+                PersistMessage::Write(write) => {
+                    // Resolved per write so a migrated backend takes effect immediately
+                    let backend = storage.read().clone();
+                    write_with_retry(&write, backend.as_ref(), &events).await;
+                }
+                PersistMessage::Flush(done) => {
+                    let _ = done.send(());
+                }
+            }
+        }
+    });
+    (tx, handle)
+}
+
+/// Apply a write, retrying failures with backoff and reporting the last error
+async fn write_with_retry(write: &StorageWrite, storage: &dyn Storage, events: &EventEmitter) {
+    let mut delay = PERSIST_RETRY_DELAY;
+    for attempt in 1..=PERSIST_MAX_ATTEMPTS {
+        let error = match write.apply(storage).await {
+            Ok(()) => return,
+            Err(e) => e,
+        };
+
+        if attempt == PERSIST_MAX_ATTEMPTS {
+            tracing::error!(
+                op = write.op(),
+                soul = %write.soul(),
+                attempts = attempt,
+                error = %error,
+                "Error persisting write"
+            );
+            events.emit(&Event {
+                event_type: STORAGE_ERROR_EVENT.to_string(),
+                data: serde_json::json!({
+                    "soul": write.soul(),
+                    "op": write.op(),
+                    "error": error.to_string(),
+                    "attempts": attempt,
+                }),
+            });
+            return;
+        }
+
+        tokio::time::sleep(delay).await;
+        delay *= 2;
+    }
+}
@@ -2,6 +2,7 @@
 //! Based on Gun.js sea/certify.js
 //! Allows an authority to grant permissions to certificants for specific paths/patterns
 
+use super::settings::parse;
 use super::{sign, KeyPair, SeaError};
 use serde_json::{json, Value};
 use std::collections::HashMap;
@@ -22,8 +23,17 @@ pub struct Certificate {
     pub read_block: Option<String>,
     /// Write block/blacklist (optional)
     pub write_block: Option<String>,
+    /// Who signed it: the authority, or for a delegated certificate a key
+    /// its parent certifies
+    pub issuer: String,
+    /// For a delegated certificate, the issuer's own certificate, and so on
+    /// up to the one the authority signed
+    pub parent: Option<Box<Certificate>>,
 }
 
+/// Most certificates in a chain, counting the one the authority signed
+pub const MAX_CERTIFICATE_CHAIN: usize = 4;
+
 /// Certificants can be wildcard or a list of public keys
 #[derive(Clone, Debug)]
 pub enum Certificants {
@@ -35,24 +45,150 @@ pub enum Certificants {
 
 /// Policy for access control
 /// Can be a string, RAD/LEX object, or array of policies
+///
+/// Policies are matched as in Gun.js against the written path: the soul's
+/// path under the user (`inbox` for `~pub/inbox`) and the key, joined as
+/// `inbox/key`.
 #[derive(Clone, Debug)]
 pub enum Policy {
-    /// Simple string policy (e.g., "inbox")
+    /// Simple string policy (e.g., "inbox"): exactly this path
     String(String),
-    /// RAD/LEX object with pattern matching
+    /// RAD/LEX object with pattern matching, on the whole path
     Radix(RadixPolicy),
+    /// LEX object matching the soul's path (`#`) and the key (`.`) apart
+    Lex(LexPolicy),
     /// Array of policies
     Array(Vec<Policy>),
 }
 
 /// RAD/LEX pattern matching policy
 /// Supports patterns like '*', '>', '<', '?', etc.
+///
+/// `=` is an exact match, `*` a prefix, `>` and `<` lower and upper bounds,
+/// and `"+": "*"` requires the path or key to contain the writer's pub.
 #[derive(Clone, Debug)]
 pub struct RadixPolicy {
     /// Pattern map (key -> pattern)
     pub patterns: HashMap<String, String>,
 }
 
+impl RadixPolicy {
+    /// `{"*": prefix}`: paths starting with `prefix`
+    pub fn prefix(prefix: &str) -> Self {
+        Self { patterns: HashMap::from([("*".to_string(), prefix.to_string())]) }
+    }
+
+    /// `{"=": path}`: exactly `path`
+    pub fn exact(path: &str) -> Self {
+        Self { patterns: HashMap::from([("=".to_string(), path.to_string())]) }
+    }
+
+    /// `{">": from, "<": to}`: paths between `from` and `to`, inclusive
+    pub fn range(from: &str, to: &str) -> Self {
+        Self {
+            patterns: HashMap::from([(">".to_string(), from.to_string()), ("<".to_string(), to.to_string())]),
+        }
+    }
+
+    /// Adds `"+": "*"`: the path or key must contain the writer's pub
+    pub fn with_certificant(mut self) -> Self {
+        self.patterns.insert("+".to_string(), "*".to_string());
+        self
+    }
+}
+
+/// LEX policy on the soul's path and the key
+///
+/// A write is allowed if both given matches hold, e.g. `{"#": {"*":
+/// "inbox"}, ".": {"*": "msg"}}` for keys starting with `msg` in souls
+/// whose path starts with `inbox`.
+#[derive(Clone, Debug, Default)]
+pub struct LexPolicy {
+    /// `#`: match on the soul's path
+    pub soul: Option<RadixPolicy>,
+    /// `.`: match on the key
+    pub key: Option<RadixPolicy>,
+    /// Other patterns, on the whole path, and `"+": "*"`
+    pub patterns: HashMap<String, String>,
+}
+
+impl LexPolicy {
+    /// Matches the soul's path with `soul`
+    pub fn with_soul(mut self, soul: RadixPolicy) -> Self {
+        self.soul = Some(soul);
+        self
+    }
+
+    /// Matches the key with `key`
+    pub fn with_key(mut self, key: RadixPolicy) -> Self {
+        self.key = Some(key);
+        self
+    }
+
+    /// Adds `"+": "*"`: the path or key must contain the writer's pub
+    pub fn with_certificant(mut self) -> Self {
+        self.patterns.insert("+".to_string(), "*".to_string());
+        self
+    }
+}
+
+impl Policy {
+    /// The policy as Gun.js writes it in a certificate
+    pub fn to_value(&self) -> Value {
+        match self {
+            Policy::String(s) => json!(s),
+            Policy::Radix(r) => json!(r.patterns),
+            Policy::Lex(lex) => {
+                let mut value = json!(lex.patterns);
+                if let Some(ref soul) = lex.soul {
+                    value["#"] = json!(soul.patterns);
+                }
+                if let Some(ref key) = lex.key {
+                    value["."] = json!(key.patterns);
+                }
+                value
+            }
+            Policy::Array(policies) => Value::Array(policies.iter().map(Policy::to_value).collect()),
+        }
+    }
+
+    /// Read a policy as Gun.js writes it in a certificate
+    ///
+    /// Returns `None` for anything but a string, object or array.
+    pub fn from_value(value: &Value) -> Option<Policy> {
+        // A match in `#` or `.` is a pattern object, or a string for `=`
+        fn radix(value: &Value) -> Option<RadixPolicy> {
+            match value {
+                Value::String(s) => Some(RadixPolicy::exact(s)),
+                Value::Object(_) => Some(RadixPolicy { patterns: patterns(value) }),
+                _ => None,
+            }
+        }
+        fn patterns(value: &Value) -> HashMap<String, String> {
+            value
+                .as_object()
+                .into_iter()
+                .flatten()
+                .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+                .collect()
+        }
+
+        match value {
+            Value::String(s) => Some(Policy::String(s.clone())),
+            Value::Array(policies) => Some(Policy::Array(policies.iter().filter_map(Policy::from_value).collect())),
+            Value::Object(object) if object.contains_key("#") || object.contains_key(".") => {
+                Some(Policy::Lex(LexPolicy {
+                    soul: object.get("#").and_then(radix),
+                    key: object.get(".").and_then(radix),
+                    patterns: patterns(value),
+                }))
+            }
+            Value::Object(_) => Some(Policy::Radix(RadixPolicy { patterns: patterns(value) })),
+            _ => None,
+        }
+    }
+}
+
 /// Options for certificate creation
 #[derive(Clone, Debug, Default)]
 pub struct CertifyOptions {
@@ -62,6 +198,9 @@ pub struct CertifyOptions {
     pub block: Option<BlockPolicy>,
     /// If true, return raw certificate without "SEA" prefix
     pub raw: bool,
+    /// To delegate: the signing key's own certificate, which the new one's
+    /// policy must lie within (see [`verify_certificate`])
+    pub parent: Option<String>,
 }
 
 /// Block/blacklist policy
@@ -104,55 +243,32 @@ pub async fn certify(
     authority: &KeyPair,
     opt: CertifyOptions,
 ) -> Result<String, SeaError> {
-    // Normalize certificants
+    // Normalize certificants: "*" if anyone, a pub if one, else the pubs
     let certs = match certificants {
-        Certificants::Wildcard => "*".to_string(),
-        Certificants::List(list) => {
-            if list.len() == 1 {
-                list[0].clone()
-            } else {
-                serde_json::to_string(&list)
-                    .map_err(|e| SeaError::Crypto(format!("Serialization error: {}", e)))?
-            }
-        }
-    };
-
-    // Extract read/write policies
-    // In Gun.js, if policy is an object with 'read' key, use it; otherwise assume write
-    let (read_policy, write_policy): (Option<String>, Option<String>) = match policy {
-        Policy::String(s) => (None, Some(s)),
-        Policy::Radix(r) => {
-            // For RAD/LEX, serialize patterns as JSON
-            (
-                None,
-                Some(serde_json::to_string(&r.patterns).unwrap_or_default()),
-            )
-        }
-        Policy::Array(policies) => {
-            // Arrays are complex, convert to JSON array
-            let policy_strs: Vec<String> = policies.iter().map(|p| format!("{:?}", p)).collect();
-            (
-                None,
-                Some(serde_json::to_string(&policy_strs).unwrap_or_default()),
-            )
+        Certificants::List(list) if list.is_empty() => {
+            return Err(SeaError::Crypto("No certificant found".to_string()));
         }
+        Certificants::List(list) if list.iter().any(|c| c == "*") => json!("*"),
+        Certificants::List(list) if list.len() == 1 => json!(list[0]),
+        Certificants::List(list) => json!(list),
+        Certificants::Wildcard => json!("*"),
     };
 
     // Build certificate data (matching Gun.js format)
+    // The policy is the write policy, "w"
     let mut cert_data = json!({
         "c": certs, // certificants
+        "w": policy.to_value(),
     });
 
     if let Some(expiry) = opt.expiry {
         cert_data["e"] = json!(expiry);
     }
 
-    if let Some(ref read) = read_policy {
-        cert_data["r"] = json!(read);
-    }
-
-    if let Some(ref write) = write_policy {
-        cert_data["w"] = json!(write);
+    // A delegated certificate carries its issuer and the issuer's certificate
+    if let Some(ref parent) = opt.parent {
+        cert_data["i"] = json!(authority.pub_key);
+        cert_data["p"] = json!(parent);
     }
 
     if let Some(ref block) = opt.block {
@@ -164,19 +280,17 @@ pub async fn certify(
         }
     }
 
-    // Sign the certificate (sign expects Value, not string)
+    // Sign the certificate: "SEA{...}", the format Gun.js reads
     let signature = sign(&cert_data, authority).await?;
+    let signed = signature
+        .as_str()
+        .ok_or_else(|| SeaError::Crypto("Signature is not a string".to_string()))?;
 
-    // Format: "SEA{...}" unless raw (matching Gun.js format)
+    // Without the "SEA" prefix if raw (matching Gun.js format)
     if opt.raw {
-        // Return raw signature object
-        serde_json::to_string(&signature)
-            .map_err(|e| SeaError::Crypto(format!("Serialization error: {}", e)))
+        Ok(signed.trim_start_matches("SEA").to_string())
     } else {
-        // Wrap in "SEA" prefix
-        let sig_str = serde_json::to_string(&signature)
-            .map_err(|e| SeaError::Crypto(format!("Serialization error: {}", e)))?;
-        Ok(format!("SEA{}", sig_str))
+        Ok(signed.to_string())
     }
 }
 
@@ -184,6 +298,14 @@ pub async fn certify(
 /// 
 /// Verifies a certificate's signature using the authority's public key and extracts
 /// the certificate data. This performs full cryptographic verification, not just JSON parsing.
+///
+/// # Delegation
+///
+/// A certificate made with [`CertifyOptions::parent`] is signed by a certificant of
+/// its parent instead of the authority, and carries the parent. The chain is verified
+/// up to a certificate signed by `authority_pub`, at most [`MAX_CERTIFICATE_CHAIN`]
+/// long; each link's issuer must be one of its parent's certificants, and its write
+/// policy must lie within the parent's. Gun.js doesn't know delegated certificates.
 /// 
 /// # Arguments
 /// * `cert` - Certificate string (with optional "SEA" prefix)
@@ -193,7 +315,7 @@ pub async fn certify(
 /// `Certificate` struct with verified data if signature is valid
 /// 
 /// # Errors
-/// - `SeaError::Crypto`: If certificate format is invalid
+/// - `SeaError::CertInvalid`: If the certificate is malformed, or a link of its chain doesn't delegate
 /// - `SeaError::VerificationFailed`: If signature verification fails (wrong authority or tampered data)
 /// 
 /// # Security
@@ -223,26 +345,73 @@ pub async fn certify(
 /// # }
 /// ```
 pub async fn verify_certificate(cert: &str, authority_pub: &str) -> Result<Certificate, SeaError> {
+    verify_certificate_sync(cert, authority_pub)
+}
+
+/// [`verify_certificate`], for callers that can't await
+pub(crate) fn verify_certificate_sync(cert: &str, authority_pub: &str) -> Result<Certificate, SeaError> {
+    // The chain, from `cert` up to the one signed by the authority; who
+    // signed each is only known once its parent is verified
+    let mut chain = vec![cert.to_string()];
+    loop {
+        let unverified = parse(&Value::String(chain[chain.len() - 1].clone()));
+        let data = unverified
+            .get("m")
+            .map(parse)
+            .ok_or_else(|| invalid("Certificate is not signed data"))?;
+        let Some(parent) = data.get("p") else {
+            break;
+        };
+        if chain.len() == MAX_CERTIFICATE_CHAIN {
+            return Err(invalid(format!(
+                "Certificate chain is longer than {} certificates",
+                MAX_CERTIFICATE_CHAIN
+            )));
+        }
+        let parent = parent
+            .as_str()
+            .ok_or_else(|| invalid("Parent certificate is not a string"))?;
+        chain.push(parent.to_string());
+    }
+
+    // Verified from the authority down
+    let mut verified: Option<Certificate> = None;
+    for cert in chain.iter().rev() {
+        let issuer = match verified {
+            None => authority_pub.to_string(),
+            Some(_) => {
+                let data = parse(&Value::String(cert.clone())).get("m").map(parse).unwrap_or_default();
+                data.get("i")
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string)
+                    .ok_or_else(|| invalid("Delegated certificate is missing its issuer"))?
+            }
+        };
+        let cert = certificate(cert, issuer, verified.map(Box::new))?;
+        if let Some(ref parent) = cert.parent {
+            delegates(parent, &cert)?;
+        }
+        verified = Some(cert);
+    }
+    verified.ok_or_else(|| invalid("Empty certificate chain"))
+}
+
+/// `SeaError::CertInvalid` for `reason`
+fn invalid(reason: impl Into<String>) -> SeaError {
+    SeaError::CertInvalid { reason: reason.into() }
+}
+
+/// Verify one certificate of a chain, signed by `issuer`
+fn certificate(cert: &str, issuer: String, parent: Option<Box<Certificate>>) -> Result<Certificate, SeaError> {
     // Remove "SEA" prefix if present
-    let cert_data = if let Some(stripped) = cert.strip_prefix("SEA{") {
-        // Remove "SEA{" prefix and find matching "}"
-        // The certificate is a JSON object, so we need to parse it properly
-        let mut json_str = String::from("{");
-        json_str.push_str(stripped);
-        json_str
-    } else if let Some(stripped) = cert.strip_prefix("SEA") {
-        stripped.to_string()
-    } else {
-        cert.to_string()
-    };
+    let cert_data = cert.strip_prefix("SEA").unwrap_or(cert);
 
     // Parse certificate as signed data (format: {m: message, s: signature})
-    let signed_data: Value = serde_json::from_str(&cert_data)
-        .map_err(|e| SeaError::Crypto(format!("Parse error: {}", e)))?;
+    serde_json::from_str::<Value>(cert_data)
+        .map_err(|e| invalid(format!("Parse error: {}", e)))?;
 
-    // Verify signature using SEA.verify()
-    use super::verify;
-    let parsed = verify(&signed_data, authority_pub).await?;
+    // Verify signature using SEA.verify(), over `m` as it was signed
+    let parsed = super::verify::verify_sync(&Value::String(cert_data.to_string()), &issuer)?;
 
     // Extract certificate fields
     let certificants = if let Some(c) = parsed.get("c") {
@@ -259,19 +428,15 @@ pub async fn verify_certificate(cert: &str, authority_pub: &str) -> Result<Certi
                     .collect(),
             )
         } else {
-            return Err(SeaError::Crypto("Invalid certificants format".to_string()));
+            return Err(invalid("Invalid certificants format"));
         }
     } else {
-        return Err(SeaError::Crypto("Missing certificants".to_string()));
+        return Err(invalid("Missing certificants"));
     };
 
     let expiry = parsed.get("e").and_then(|v| v.as_f64());
-    let read_policy = parsed
-        .get("r")
-        .and_then(|v| v.as_str().map(|s| Policy::String(s.to_string())));
-    let write_policy = parsed
-        .get("w")
-        .and_then(|v| v.as_str().map(|s| Policy::String(s.to_string())));
+    let read_policy = parsed.get("r").and_then(Policy::from_value);
+    let write_policy = parsed.get("w").and_then(Policy::from_value);
     let read_block = parsed
         .get("rb")
         .and_then(|v| v.as_str().map(|s| s.to_string()));
@@ -286,35 +451,249 @@ pub async fn verify_certificate(cert: &str, authority_pub: &str) -> Result<Certi
         write_policy,
         read_block,
         write_block,
+        issuer,
+        parent,
     })
 }
 
+/// Check that `parent` lets its certificant `child.issuer` grant `child`
+///
+/// The issuer must be one of the parent's certificants, and the child's
+/// write policy must lie within the parent's. Only containment that can be
+/// shown from the patterns counts: exact paths the parent matches, prefixes
+/// of the parent's prefixes, and ranges inside a parent's prefix or equal
+/// to its range.
+fn delegates(parent: &Certificate, child: &Certificate) -> Result<(), SeaError> {
+    if !certifies(parent, &child.issuer) {
+        return Err(invalid(format!("{} isn't certified to delegate", child.issuer)));
+    }
+    let within = match (&child.write_policy, &parent.write_policy) {
+        (None, _) => true,
+        (Some(_), None) => false,
+        (Some(child), Some(parent)) => {
+            let parent = parent.to_value();
+            lexes(&child.to_value()).all(|c| lexes(&parent).any(|p| lex_within(c, p)))
+        }
+    };
+    if !within {
+        return Err(invalid("Certificate policy exceeds its parent's"));
+    }
+    Ok(())
+}
+
+/// Whether every write the LEX `child` matches is matched by `parent`
+fn lex_within(child: &Value, parent: &Value) -> bool {
+    let forced = |lex: &Value| lex.get("+").and_then(|v| v.as_str()).is_some_and(|plus| plus.contains('*'));
+    if forced(parent) && !forced(child) {
+        return false;
+    }
+    let parts = |lex: &Value| (lex.get("#").cloned(), lex.get(".").cloned());
+    match (parts(child), parts(parent)) {
+        // Both on the whole path
+        ((None, None), (None, None)) => pattern_within(child, parent),
+        // The parent restricts the soul's path or the key: so must the child
+        ((soul, key), (parent_soul, parent_key)) if parent_soul.is_some() || parent_key.is_some() => {
+            let part_within = |part: Option<Value>, parent_part: Option<Value>| match (part, parent_part) {
+                (_, None) => true,
+                (Some(part), Some(parent_part)) => pattern_within(&part, &parent_part),
+                (None, Some(_)) => false,
+            };
+            (soul.is_some() || key.is_some()) && part_within(soul, parent_soul) && part_within(key, parent_key)
+        }
+        _ => false,
+    }
+}
+
+/// Whether every text the pattern `child` matches is matched by `parent`,
+/// as [`text_match`] matches them
+fn pattern_within(child: &Value, parent: &Value) -> bool {
+    match (Pattern::of(child), Pattern::of(parent)) {
+        (Pattern::Exact(text), _) => text_match(&text, Some(parent)),
+        (Pattern::Prefix(prefix), Pattern::Prefix(within)) => prefix.starts_with(&within),
+        (Pattern::Prefix(prefix), Pattern::Range(from, to)) => from.or(to).is_some_and(|first| prefix.starts_with(&first)),
+        (Pattern::Range(Some(from), Some(to)), Pattern::Prefix(within)) => from.starts_with(&within) && to.starts_with(&within),
+        (child @ Pattern::Range(..), parent @ Pattern::Range(..)) => child == parent,
+        _ => false,
+    }
+}
+
+/// What a pattern of [`text_match`] matches
+#[derive(PartialEq)]
+enum Pattern {
+    /// `=`, or a string: this text
+    Exact(String),
+    /// `*`: texts starting with it
+    Prefix(String),
+    /// `>` and `<`: texts between them, and those starting with the first
+    Range(Option<String>, Option<String>),
+    /// Anything else, e.g. empty patterns
+    Other,
+}
+
+impl Pattern {
+    fn of(pattern: &Value) -> Pattern {
+        if let Value::String(text) = pattern {
+            return Pattern::Exact(text.clone());
+        }
+        let get = |k: &str| pattern.get(k).and_then(|v| v.as_str()).map(str::to_string);
+        match (get("="), get("*"), get(">"), get("<")) {
+            (Some(exact), ..) if !exact.is_empty() => Pattern::Exact(exact),
+            (None, Some(prefix), ..) if !prefix.is_empty() => Pattern::Prefix(prefix),
+            (None, None, from, to)
+                if (from.is_some() || to.is_some()) && !from.iter().chain(&to).any(String::is_empty) =>
+            {
+                Pattern::Range(from, to)
+            }
+            _ => Pattern::Other,
+        }
+    }
+}
+
+/// Whether `pub_key` is one of `cert`'s certificants
+fn certifies(cert: &Certificate, pub_key: &str) -> bool {
+    match cert.certificants {
+        Certificants::Wildcard => true,
+        Certificants::List(ref list) => list.iter().any(|c| c == "*" || c == pub_key),
+    }
+}
+
 /// Check if a path matches a policy
 /// Implements RAD/LEX pattern matching
+///
+/// `path` is the soul's path and the key joined by `/`, like `inbox/msg`;
+/// `"+": "*"` is not checked, there being no writer.
 pub fn matches_policy(path: &str, policy: &Policy) -> bool {
-    match policy {
-        Policy::String(s) => {
-            // Simple string match
-            path == s || path.starts_with(&format!("{}/", s))
-        }
-        Policy::Radix(r) => {
-            // RAD/LEX pattern matching
-            // For now, simple implementation - full RAD/LEX would need more complex matching
-            for pattern in r.patterns.keys() {
-                if pattern == "*" {
-                    return true; // Wildcard matches everything
-                }
-                if path.starts_with(pattern) {
-                    return true;
-                }
-            }
-            false
+    let (soul, key) = path.rsplit_once('/').unwrap_or(("", path));
+    lexes(&policy.to_value()).any(|lex| lex_matches(lex, soul, key))
+}
+
+/// Check if `writer_pub` may write `key` of `soul` under a certificate
+///
+/// Evaluates the certificate as Gun.js does before accepting a write to
+/// someone's user space: it must not have expired, `writer_pub` must be
+/// one of its certificants, and its write policy must match the path. The
+/// path is `soul` after the user's `~pub/` and `key`. A policy with
+/// `"+": "*"` also requires the path or key to contain `writer_pub`.
+///
+/// The certificate must already be verified as signed by the owner of
+/// `soul`, see [`verify_certificate`]. Its write block (`wb`) is a path in
+/// the owner's graph and is left to the caller to look up.
+///
+/// A delegated certificate is checked up its chain: each parent must let
+/// the issuer of the certificate below it make the same write, so no link
+/// may have expired and none grants more than its parent.
+///
+/// # Example
+/// ```rust,no_run
+/// use gun::sea::{certify, pair, verify_certificate, verify_write, Certificants, CertifyOptions, Policy, RadixPolicy};
+///
+/// # async fn example() -> Result<(), gun::sea::SeaError> {
+/// let alice = pair().await?;
+/// let bob = pair().await?;
+///
+/// // Anyone may write into alice's inbox, under a key naming them
+/// let policy = Policy::Radix(RadixPolicy::prefix("inbox").with_certificant());
+/// let cert = certify(Certificants::Wildcard, policy, &alice, CertifyOptions::default()).await?;
+/// let cert = verify_certificate(&cert, &alice.pub_key).await?;
+///
+/// let inbox = format!("~{}/inbox", alice.pub_key);
+/// assert!(verify_write(&cert, &bob.pub_key, &inbox, &format!("from-{}", bob.pub_key)));
+/// assert!(!verify_write(&cert, &bob.pub_key, &inbox, "from-someone-else"));
+/// # Ok(())
+/// # }
+/// ```
+pub fn verify_write(cert: &Certificate, writer_pub: &str, soul: &str, key: &str) -> bool {
+    // Each certificate of a delegated chain must let the one below it make
+    // the write itself
+    let mut writer = writer_pub;
+    let mut link = Some(cert);
+    while let Some(cert) = link {
+        if !permits(cert, writer, soul, key) {
+            return false;
         }
-        Policy::Array(policies) => {
-            // Array: any policy matches
-            policies.iter().any(|p| matches_policy(path, p))
+        writer = &cert.issuer;
+        link = cert.parent.as_deref();
+    }
+    true
+}
+
+/// [`verify_write`] for one certificate of a chain
+fn permits(cert: &Certificate, writer_pub: &str, soul: &str, key: &str) -> bool {
+    // Expired certificates grant nothing
+    if let Some(expiry) = cert.expiry {
+        if chrono::Utc::now().timestamp_millis() as f64 > expiry {
+            return false;
         }
     }
+
+    // The writer must be a certificant
+    if !certifies(cert, writer_pub) {
+        return false;
+    }
+    let Some(ref policy) = cert.write_policy else {
+        return false;
+    };
+
+    // The soul's path under the user
+    let path = soul.split_once('/').map(|(_, path)| path).unwrap_or("");
+    lexes(&policy.to_value()).any(|lex| {
+        // Gun.js skips this for souls without a path; we don't
+        let forced = lex.get("+").and_then(|v| v.as_str()).is_some_and(|plus| plus.contains('*'));
+        lex_matches(lex, path, key) && (!forced || path.contains(writer_pub) || key.contains(writer_pub))
+    })
+}
+
+/// The LEX objects or strings of a policy, as Gun.js lists them
+fn lexes(policy: &Value) -> impl Iterator<Item = &Value> {
+    match policy {
+        Value::Array(lexes) => lexes.iter().collect::<Vec<_>>().into_iter(),
+        policy => vec![policy].into_iter(),
+    }
+}
+
+/// Whether a LEX, or string, matches a write of `key` in the soul `path`
+///
+/// Gun.js also matches `#` against the joined path, so that `.` never
+/// restricts a LEX with `#`; here `#` and `.` only match their own part.
+fn lex_matches(lex: &Value, path: &str, key: &str) -> bool {
+    let (soul, dot) = (lex.get("#"), lex.get("."));
+    let full = if path.is_empty() { key.to_string() } else { format!("{}/{}", path, key) };
+    (text_match(path, soul) && text_match(key, dot))
+        || (dot.is_none() && text_match(path, soul))
+        || (soul.is_none() && text_match(key, dot))
+        || (soul.is_none() && dot.is_none() && text_match(&full, Some(lex)))
+}
+
+/// Gun.js `Gun.text.match`: `t` against a string (exact) or `=`, `*`, `>`
+/// and `<` patterns, quirks included
+fn text_match(t: &str, o: Option<&Value>) -> bool {
+    let o = match o {
+        Some(Value::String(s)) => json!({ "=": s }),
+        Some(o @ Value::Object(_)) => o.clone(),
+        _ => json!({}),
+    };
+    let pattern = |k: &str| o.get(k).and_then(|v| v.as_str());
+    // JavaScript's `o[a] || o[b] || …`, empty strings being false
+    let first = |keys: &[&str]| keys.iter().find_map(|k| pattern(k).filter(|p| !p.is_empty()));
+
+    if first(&["=", "*", ">", "<"]) == Some(t) {
+        return true;
+    }
+    if pattern("=").is_some() {
+        return false;
+    }
+    if first(&["*", ">", "<"]).is_some_and(|prefix| t.starts_with(prefix)) {
+        return true;
+    }
+    if pattern("*").is_some() {
+        return false;
+    }
+    match (pattern(">"), pattern("<")) {
+        (Some(from), Some(to)) => from <= t && t <= to,
+        (Some(from), None) => from <= t,
+        (None, Some(to)) => t <= to,
+        (None, None) => false,
+    }
 }
 
 /// Check if a certificate grants permission for a path
@@ -331,6 +710,13 @@ pub fn check_permission(
         }
     }
 
+    // A delegated certificate grants only what its whole chain does
+    if let Some(ref parent) = cert.parent {
+        if !check_permission(parent, path, operation) {
+            return false;
+        }
+    }
+
     // Check blocks
     if operation == "read" {
         if let Some(ref block) = cert.read_block {
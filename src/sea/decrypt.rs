@@ -2,22 +2,54 @@
 //! Based on Gun.js sea/decrypt.js
 //! AES-GCM decryption with ECDH key derivation
 
+use super::encrypt::{aes_key, derive_aes_key};
+use super::provider::crypto;
+use super::settings::{decode_base64, parse};
 use super::{KeyPair, SeaError};
-use aes_gcm::{
-    aead::{Aead, KeyInit},
-    Aes256Gcm, Nonce,
-};
-use base64::{engine::general_purpose, Engine as _};
 use serde_json::Value;
 
 /// Decrypt data using AES-GCM
 /// Uses ECDH to derive decryption key
+///
+/// Takes what [`encrypt`](super::encrypt) or Gun.js `SEA.encrypt` returns:
+/// the `SEA{"ct":…,"iv":…,"s":…}` string, or its object. The `{ct, iv, s}`
+/// objects with a 12-byte IV that earlier versions of this crate wrote are
+/// still decrypted. The plaintext is parsed as Gun.js does: JSON if it is,
+/// else the string.
 pub async fn decrypt(
     encrypted_data: &Value,
     pair: &KeyPair,
     their_epub: Option<&str>,
 ) -> Result<Value, SeaError> {
+    // The key it was encrypted with (same as encryption)
+    let key = if let Some(their_pub) = their_epub {
+        let our_epriv = pair
+            .epriv_key
+            .as_ref()
+            .ok_or_else(|| SeaError::Decryption("Missing epriv key".to_string()))?;
+        let our_epub = pair
+            .epub_key
+            .as_ref()
+            .ok_or_else(|| SeaError::Decryption("Missing epub key".to_string()))?;
+
+        crate::sea::secret::derive_secret(their_pub, our_epriv, our_epub).await?
+    } else {
+        pair.epriv_key
+            .clone()
+            .ok_or_else(|| SeaError::Decryption("Missing epriv key".to_string()))?
+    };
+    decrypt_with(encrypted_data, &key).await
+}
+
+/// Decrypt data with `key`
+/// Based on Gun.js SEA.decrypt(data, key)
+///
+/// `key` is what the data was encrypted with: a secret from
+/// [`secret`](super::secret), an epriv, or a passphrase. Reads the same
+/// layouts as [`decrypt`].
+pub async fn decrypt_with(encrypted_data: &Value, key: &str) -> Result<Value, SeaError> {
     // Parse encrypted data
+    let encrypted_data = parse(encrypted_data);
     let ct_b64 = encrypted_data
         .get("ct")
         .and_then(|v| v.as_str())
@@ -32,54 +64,26 @@ pub async fn decrypt(
         .ok_or_else(|| SeaError::Decryption("Missing salt".to_string()))?;
 
     // Decode from base64
-    let ciphertext = general_purpose::STANDARD_NO_PAD
-        .decode(ct_b64)
-        .map_err(|_| SeaError::Decryption("Invalid ciphertext encoding".to_string()))?;
-    let iv_bytes = general_purpose::STANDARD_NO_PAD
-        .decode(iv_b64)
-        .map_err(|_| SeaError::Decryption("Invalid IV encoding".to_string()))?;
-    let salt_bytes = general_purpose::STANDARD_NO_PAD
-        .decode(s_b64)
-        .map_err(|_| SeaError::Decryption("Invalid salt encoding".to_string()))?;
+    let ciphertext =
+        decode_base64(ct_b64).ok_or_else(|| SeaError::Decryption("Invalid ciphertext encoding".to_string()))?;
+    let iv_bytes = decode_base64(iv_b64).ok_or_else(|| SeaError::Decryption("Invalid IV encoding".to_string()))?;
+    let salt_bytes = decode_base64(s_b64).ok_or_else(|| SeaError::Decryption("Invalid salt encoding".to_string()))?;
 
-    // Derive AES key (same as encryption)
-    let aes_key = if let Some(their_pub) = their_epub {
-        let our_epriv = pair
-            .epriv_key
-            .as_ref()
-            .ok_or_else(|| SeaError::Decryption("Missing epriv key".to_string()))?;
-        let our_epub = pair
-            .epub_key
-            .as_ref()
-            .ok_or_else(|| SeaError::Decryption("Missing epub key".to_string()))?;
-
-        let shared_secret =
-            crate::sea::secret::derive_secret(their_pub, our_epriv, our_epub).await?;
-        crate::sea::encrypt::derive_aes_key(&shared_secret, &salt_bytes).await?
-    } else {
-        let epriv = pair
-            .epriv_key
-            .as_ref()
-            .ok_or_else(|| SeaError::Decryption("Missing epriv key".to_string()))?;
-        crate::sea::encrypt::derive_aes_key(epriv, &salt_bytes).await?
+    // Decrypt, Gun.js layout or our legacy one by the IV length
+    let aes_key = match iv_bytes.len() {
+        15 => aes_key(key, &salt_bytes),
+        12 => derive_aes_key(key, &salt_bytes).await?,
+        n => return Err(SeaError::Decryption(format!("Invalid IV length: {}", n))),
     };
+    let plaintext = crypto().aes_gcm_decrypt(&aes_key, &iv_bytes, &ciphertext)?;
 
-    // Create AES-GCM cipher
-    let cipher = Aes256Gcm::new_from_slice(&aes_key)
-        .map_err(|e| SeaError::Decryption(format!("Failed to create cipher: {}", e)))?;
-
-    // Create nonce from IV
-    #[allow(deprecated)] // generic_array::from_slice is deprecated but aes-gcm still uses it
-    let nonce = Nonce::from_slice(&iv_bytes);
-
-    // Decrypt
-    let plaintext = cipher
-        .decrypt(nonce, ciphertext.as_ref())
-        .map_err(|e| SeaError::Decryption(format!("Decryption failed: {}", e)))?;
-
-    // Decode from UTF-8 and parse JSON
+    // Decode from UTF-8
     let msg = String::from_utf8(plaintext)
         .map_err(|e| SeaError::Decryption(format!("Invalid UTF-8: {}", e)))?;
 
-    serde_json::from_str(&msg).map_err(|e| SeaError::Decryption(format!("Invalid JSON: {}", e)))
+    // Legacy plaintext is always JSON
+    if iv_bytes.len() == 12 {
+        return serde_json::from_str(&msg).map_err(|e| SeaError::Decryption(format!("Invalid JSON: {}", e)));
+    }
+    Ok(parse(&Value::String(msg)))
 }
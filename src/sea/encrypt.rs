@@ -2,38 +2,28 @@
 //! Based on Gun.js sea/encrypt.js
 //! AES-GCM encryption with ECDH key derivation
 
+use super::provider::{crypto, random_bytes};
+use super::settings::{stringify, SEA_PREFIX};
 use super::{KeyPair, SeaError};
-use aes_gcm::{
-    aead::{Aead, KeyInit},
-    Aes256Gcm, Nonce,
-};
 use base64::{engine::general_purpose, Engine as _};
-use rand::RngCore;
 use serde_json::Value;
 
 /// Encrypt data using AES-GCM
 /// Uses ECDH to derive encryption key from the recipient's public key
 ///
-/// Format: {ct: ciphertext, iv: nonce, s: salt} (all base64)
+/// Returns the string Gun.js peers read: `SEA{"ct":…,"iv":…,"s":…}`, the
+/// ciphertext, a random 15-byte IV and 9-byte salt in base64. The AES key
+/// is the SHA-256 of the ECDH secret with `their_epub` (or of our epriv
+/// without it) followed by the salt read as UTF-8, as in Gun.js
+/// sea/aeskey.js. Strings are encrypted as they are, anything else as JSON.
 pub async fn encrypt(
     data: &Value,
     pair: &KeyPair,
     their_epub: Option<&str>,
 ) -> Result<Value, SeaError> {
-    // Serialize data to string
-    let msg = serde_json::to_string(data)
-        .map_err(|e| SeaError::Encryption(format!("Serialization error: {}", e)))?;
-
-    // Generate random salt and IV (nonce)
-    // AES-GCM requires a 12-byte (96-bit) nonce
-    let mut salt_bytes = [0u8; 9];
-    let mut iv_bytes = [0u8; 12]; // Fixed: AES-GCM requires 12 bytes, not 15
-    rand::thread_rng().fill_bytes(&mut salt_bytes);
-    rand::thread_rng().fill_bytes(&mut iv_bytes);
-
     // Derive AES key
     // If their_epub is provided, use ECDH; otherwise use epriv directly (for self-encryption)
-    let aes_key = if let Some(their_pub) = their_epub {
+    let key = if let Some(their_pub) = their_epub {
         // Use ECDH to derive shared secret
         let our_epriv = pair
             .epriv_key
@@ -44,60 +34,65 @@ pub async fn encrypt(
             .as_ref()
             .ok_or_else(|| SeaError::Encryption("Missing epub key".to_string()))?;
 
-        // Derive shared secret
-        let shared_secret =
-            crate::sea::secret::derive_secret(their_pub, our_epriv, our_epub).await?;
-
-        // Combine shared secret with salt to create AES key
-        derive_aes_key(&shared_secret, &salt_bytes).await?
+        crate::sea::secret::derive_secret(their_pub, our_epriv, our_epub).await?
     } else {
-        // Use epriv directly for self-encryption
-        // This is the correct approach: derive AES key from the encryption private key
-        let epriv = pair
-            .epriv_key
-            .as_ref()
-            .ok_or_else(|| SeaError::Encryption("Missing epriv key".to_string()))?;
-        derive_aes_key(epriv, &salt_bytes).await?
+        // Use epriv directly for self-encryption, like Gun.js SEA.encrypt(data, pair)
+        pair.epriv_key
+            .clone()
+            .ok_or_else(|| SeaError::Encryption("Missing epriv key".to_string()))?
     };
+    encrypt_with(data, &key).await
+}
 
-    // Create AES-GCM cipher
-    let cipher = Aes256Gcm::new_from_slice(&aes_key)
-        .map_err(|e| SeaError::Encryption(format!("Failed to create cipher: {}", e)))?;
+/// Encrypt data with `key`
+/// Based on Gun.js SEA.encrypt(data, key)
+///
+/// `key` is a secret from [`secret`](super::secret), an epriv, or a
+/// passphrase; the output is the same `SEA{"ct":…,"iv":…,"s":…}` string as
+/// [`encrypt`]'s, and [`decrypt_with`](super::decrypt_with) reads it.
+pub async fn encrypt_with(data: &Value, key: &str) -> Result<Value, SeaError> {
+    // Strings as they are, anything else as JSON.stringify writes it
+    let msg = match data {
+        Value::String(text) => text.clone(),
+        data => stringify(data),
+    };
 
-    // Create nonce from IV
-    #[allow(deprecated)] // generic_array::from_slice is deprecated but aes-gcm still uses it
-    let nonce = Nonce::from_slice(&iv_bytes);
+    // Generate random salt and IV (nonce), Gun.js sizes
+    let salt_bytes = random_bytes(9)?;
+    let iv_bytes = random_bytes(15)?;
 
     // Encrypt
-    let ciphertext = cipher
-        .encrypt(nonce, msg.as_bytes())
-        .map_err(|e| SeaError::Encryption(format!("Encryption failed: {}", e)))?;
+    let ciphertext = crypto().aes_gcm_encrypt(&aes_key(key, &salt_bytes), &iv_bytes, msg.as_bytes())?;
 
-    // Encode everything as base64
-    let ct_b64 = general_purpose::STANDARD_NO_PAD.encode(ciphertext);
-    let iv_b64 = general_purpose::STANDARD_NO_PAD.encode(iv_bytes);
-    let s_b64 = general_purpose::STANDARD_NO_PAD.encode(salt_bytes);
+    // Encode everything as base64, as Node's Buffer does
+    let encrypted = serde_json::json!({
+        "ct": general_purpose::STANDARD.encode(ciphertext),
+        "iv": general_purpose::STANDARD.encode(iv_bytes),
+        "s": general_purpose::STANDARD.encode(salt_bytes)
+    });
 
     // Return in Gun.js format
-    Ok(serde_json::json!({
-        "ct": ct_b64,
-        "iv": iv_b64,
-        "s": s_b64
-    }))
+    Ok(Value::String(format!("{}{}", SEA_PREFIX, stringify(&encrypted))))
+}
+
+/// AES key for `key` (a secret, epriv or passphrase) and `salt`, as Gun.js
+/// sea/aeskey.js derives it: SHA-256 of the key followed by the salt as
+/// UTF-8, invalid bytes replaced
+pub(crate) fn aes_key(key: &str, salt: &[u8]) -> Vec<u8> {
+    let combo = format!("{}{}", key, String::from_utf8_lossy(salt));
+    crypto().sha256(combo.as_bytes()).to_vec()
 }
 
-/// Derive AES key from secret and salt
+/// Derive AES key from secret and salt for the legacy layout
+///
+/// Earlier versions of this crate wrote `{ct, iv, s}` objects with a
+/// 12-byte IV and this PBKDF2 key; [`decrypt`](super::decrypt) still reads them.
 pub(crate) async fn derive_aes_key(secret: &str, salt: &[u8]) -> Result<Vec<u8>, SeaError> {
     // Use PBKDF2 to derive key (matching Gun.js behavior)
-    use pbkdf2::pbkdf2_hmac;
-    use sha2::Sha256;
-
-    let secret_bytes = general_purpose::STANDARD_NO_PAD
-        .decode(secret)
-        .unwrap_or_else(|_| secret.as_bytes().to_vec());
+    let secret_bytes = super::settings::decode_base64(secret).unwrap_or_else(|| secret.as_bytes().to_vec());
 
     let mut key = vec![0u8; 32]; // AES-256 key size
-    pbkdf2_hmac::<Sha256>(&secret_bytes, salt, 100000, &mut key);
+    crypto().pbkdf2_sha256(&secret_bytes, salt, 100000, &mut key);
 
     Ok(key)
 }
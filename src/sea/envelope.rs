@@ -0,0 +1,102 @@
+//! Multi-recipient encryption
+//! Built on Gun.js sea/encrypt.js and sea/secret.js
+//! Data encrypted once, its key wrapped for each recipient
+
+use super::provider::random_bytes;
+use super::settings::parse;
+use super::{decrypt_with, encrypt_with, secret, KeyPair, SeaError};
+use base64::{engine::general_purpose, Engine as _};
+use serde_json::{json, Map, Value};
+
+/// Version of the envelope layout [`encrypt_for_many`] writes
+const ENVELOPE_VERSION: u64 = 1;
+
+/// Encrypt `data` once for every epub in `recipients`
+///
+/// Returns an envelope, version 1 of this layout:
+///
+/// ```json
+/// {
+///   "v": 1,
+///   "from": "<sender epub>",
+///   "ct": "SEA{\"ct\":…,\"iv\":…,\"s\":…}",
+///   "keys": { "<recipient epub>": "SEA{\"ct\":…,\"iv\":…,\"s\":…}" }
+/// }
+/// ```
+///
+/// `ct` is `data` encrypted by [`encrypt_with`] with a random content key:
+/// 32 bytes, base64url. Each entry of `keys` is `{"k": content key}`
+/// encrypted by [`encrypt_with`] with the [`secret`] the sender shares
+/// with that recipient. Both are AES-GCM, so tampering fails decryption.
+/// [`decrypt_from_envelope`] reads it.
+///
+/// # Errors
+/// `SeaError::InvalidKey` if a recipient's epub isn't a valid key,
+/// `SeaError::Encryption` if `sender` has no ECDH keys.
+///
+/// # Example
+/// ```rust,no_run
+/// # async fn example(alice: gun::sea::KeyPair, bob_epub: &str, carol_epub: &str) -> Result<(), gun::sea::SeaError> {
+/// let doc = serde_json::json!({ "title": "Plans" });
+/// let envelope = gun::sea::encrypt_for_many(&doc, &[bob_epub, carol_epub], &alice).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn encrypt_for_many(data: &Value, recipients: &[&str], sender: &KeyPair) -> Result<Value, SeaError> {
+    let from = sender
+        .epub_key
+        .clone()
+        .ok_or_else(|| SeaError::Encryption("Missing epub key".to_string()))?;
+
+    let content_key = general_purpose::URL_SAFE_NO_PAD.encode(random_bytes(32)?);
+
+    let mut keys = Map::new();
+    for epub in recipients {
+        let shared = secret(epub, sender).await?;
+        let wrapped = encrypt_with(&json!({ "k": content_key }), &shared).await?;
+        keys.insert(epub.to_string(), wrapped);
+    }
+
+    Ok(json!({
+        "v": ENVELOPE_VERSION,
+        "from": from,
+        "ct": encrypt_with(data, &content_key).await?,
+        "keys": keys,
+    }))
+}
+
+/// Decrypt an envelope from [`encrypt_for_many`] as one of its recipients
+///
+/// Takes the envelope object, or the JSON text of one.
+///
+/// # Errors
+/// `SeaError::Decryption` if `my_pair` isn't a recipient, the envelope
+/// isn't version 1, or it was tampered with.
+pub async fn decrypt_from_envelope(envelope: &Value, my_pair: &KeyPair) -> Result<Value, SeaError> {
+    let envelope = parse(envelope);
+    let field = |name: &str| {
+        envelope
+            .get(name)
+            .ok_or_else(|| SeaError::Decryption(format!("Envelope is missing {}", name)))
+    };
+    if field("v")?.as_u64() != Some(ENVELOPE_VERSION) {
+        return Err(SeaError::Decryption("Unknown envelope version".to_string()));
+    }
+    let from = field("from")?
+        .as_str()
+        .ok_or_else(|| SeaError::Decryption("Envelope sender is not an epub".to_string()))?;
+    let wrapped = my_pair
+        .epub_key
+        .as_deref()
+        .and_then(|epub| field("keys").ok()?.get(epub))
+        .ok_or_else(|| SeaError::Decryption("Not a recipient of this envelope".to_string()))?;
+
+    let shared = secret(from, my_pair).await?;
+    let content_key = decrypt_with(wrapped, &shared)
+        .await?
+        .get("k")
+        .and_then(|k| k.as_str())
+        .map(str::to_string)
+        .ok_or_else(|| SeaError::Decryption("Envelope key is malformed".to_string()))?;
+    decrypt_with(field("ct")?, &content_key).await
+}
@@ -5,9 +5,15 @@
 mod certify;
 mod decrypt;
 mod encrypt;
+mod envelope;
 mod pair;
+mod provider;
 mod secret;
+mod settings;
 mod sign;
+mod souls;
+mod space;
+#[cfg(feature = "native")]
 mod user;
 mod verify;
 mod work;
@@ -15,9 +21,14 @@ mod work;
 pub use certify::*;
 pub use decrypt::*;
 pub use encrypt::*;
+pub use envelope::*;
 pub use pair::*;
+pub use provider::*;
 pub use secret::*;
 pub use sign::*;
+pub use souls::*;
+pub use space::*;
+#[cfg(feature = "native")]
 pub use user::*;
 pub use verify::*;
 pub use work::*;
@@ -70,6 +81,7 @@ pub async fn pair() -> Result<KeyPair, SeaError> {
 /// # Fields
 /// - `pair`: The user's key pair (public and private keys)
 /// - `alias`: Optional user alias/username
+#[derive(Clone)]
 pub struct UserAuth {
     pub pair: KeyPair,
     pub alias: Option<String>,
@@ -85,6 +97,10 @@ pub struct UserAuth {
 /// - `VerificationFailed`: Signature verification failed (data may be tampered or wrong key)
 /// - `Encryption(String)`: Error during encryption operation
 /// - `Decryption(String)`: Error during decryption operation
+/// - `UserNotFound`: No account goes by the alias, or it was deleted
+/// - `WrongPassword`: The password doesn't open the account
+/// - `Expired`: The session's 12 hours are over
+/// - `CertInvalid { reason }`: A certificate is malformed, or doesn't grant what it's used for
 /// 
 /// # Example
 /// ```rust,no_run
@@ -119,4 +135,12 @@ pub enum SeaError {
     Encryption(String),
     #[error("Decryption error: {0}")]
     Decryption(String),
+    #[error("User not found")]
+    UserNotFound,
+    #[error("Wrong password")]
+    WrongPassword,
+    #[error("Session expired")]
+    Expired,
+    #[error("Invalid certificate: {reason}")]
+    CertInvalid { reason: String },
 }
@@ -2,79 +2,117 @@
 //! Based on Gun.js sea/pair.js
 //! Generates ECDSA (P-256) keys for signing and ECDH (P-256) keys for encryption
 
+use super::provider::crypto;
 use super::KeyPair;
 use super::SeaError;
 use base64::{engine::general_purpose, Engine as _};
-use p256::ecdsa::{SigningKey, VerifyingKey};
-use p256::elliptic_curve::sec1::ToEncodedPoint;
-use p256::{PublicKey as EcdhPublicKey, SecretKey};
-use rand::rngs::OsRng;
 
 /// Generate a new key pair
 /// Creates both signing keys (ECDSA) and encryption keys (ECDH)
-/// Matches Gun.js format: pub = "x.y", priv = base64 encoded scalar
+/// Matches Gun.js format: pub = "x.y", priv = the scalar, all base64url as in a JWK
 pub async fn generate_pair() -> Result<KeyPair, SeaError> {
-    // Generate ECDSA key pair for signing
-    let signing_key = SigningKey::random(&mut OsRng);
-    let verifying_key = VerifyingKey::from(&signing_key);
-
-    // Export signing keys
-    let priv_bytes = signing_key.to_bytes();
-    #[allow(deprecated)] // generic_array::as_slice is deprecated but aes-gcm still uses it
-    let priv_key = general_purpose::STANDARD_NO_PAD.encode(priv_bytes.as_slice());
-
-    // Get uncompressed public key point
-    let pub_point = verifying_key.to_encoded_point(false);
-    let pub_bytes = pub_point.as_bytes();
+    let signing_key = random_scalar()?;
+    let ecdh_secret = random_scalar()?;
+    export_pair(&signing_key, &ecdh_secret)
+}
 
-    // Extract x, y from uncompressed format (0x04 || x || y)
-    if pub_bytes.len() != 65 || pub_bytes[0] != 0x04 {
-        return Err(SeaError::Crypto("Invalid public key format".to_string()));
+/// 32 random bytes that are a P-256 private key, drawn again in the
+/// vanishingly rare case they are zero or not below the order
+fn random_scalar() -> Result<[u8; 32], SeaError> {
+    let provider = crypto();
+    loop {
+        let mut bytes = [0u8; 32];
+        provider.fill_random(&mut bytes)?;
+        if provider.p256_public_key(&bytes).is_ok() {
+            return Ok(bytes);
+        }
     }
+}
 
-    let x = &pub_bytes[1..33];
-    let y = &pub_bytes[33..65];
-
-    // Convert to base64 (matching Gun.js format: x.y)
-    let pub_key = format!(
-        "{}.{}",
-        general_purpose::STANDARD_NO_PAD.encode(x),
-        general_purpose::STANDARD_NO_PAD.encode(y)
-    );
-
-    // Generate ECDH key pair for encryption
-    // Use SecretKey for persistent storage (EphemeralSecret doesn't expose raw bytes)
-    let ecdh_secret = SecretKey::random(&mut OsRng);
-    let ecdh_public = EcdhPublicKey::from_secret_scalar(&ecdh_secret.to_nonzero_scalar());
+/// Domain of the seed derivation, version 1; see [`pair_from_seed`]
+const SEED_DOMAIN: &[u8] = b"gun-rs/sea/pair-from-seed/v1";
 
-    // Export ECDH keys in same format
-    let pub_point = ecdh_public.to_encoded_point(false);
-    let epub_bytes = pub_point.as_bytes();
+/// Derive a key pair from `seed`, the same one every time
+///
+/// For recovering an identity from a phrase or other secret instead of a
+/// stored session. Version 1 of the derivation:
+///
+/// 1. HKDF-SHA256 extracts from `seed` with the salt
+///    `gun-rs/sea/pair-from-seed/v1`.
+/// 2. The signing scalar (`priv`) is expanded with the info `sign`, the
+///    ECDH scalar (`epriv`) with `encrypt`, 32 bytes each, big-endian.
+/// 3. Should a scalar be zero or not below the P-256 order, it is expanded
+///    again with `/1`, `/2`, … appended to the info.
+///
+/// Any change to these steps gets a new version and domain, so a seed keeps
+/// giving the pair it gave.
+///
+/// # Errors
+/// `SeaError::InvalidKey` if `seed` is shorter than 16 bytes.
+pub async fn pair_from_seed(seed: &[u8]) -> Result<KeyPair, SeaError> {
+    if seed.len() < 16 {
+        return Err(SeaError::InvalidKey);
+    }
+    let hkdf = hkdf::Hkdf::<sha2::Sha256>::new(Some(SEED_DOMAIN), seed);
+    let scalar = |purpose: &str| {
+        (0u32..)
+            .find_map(|attempt| {
+                let info = match attempt {
+                    0 => purpose.to_string(),
+                    n => format!("{}/{}", purpose, n),
+                };
+                let mut bytes = [0u8; 32];
+                hkdf.expand(info.as_bytes(), &mut bytes).ok()?;
+                crypto().p256_public_key(&bytes).ok().map(|_| bytes)
+            })
+            .ok_or_else(|| SeaError::Crypto("Seed derivation failed".to_string()))
+    };
+    let signing_key = scalar("sign")?;
+    let ecdh_secret = scalar("encrypt")?;
+    export_pair(&signing_key, &ecdh_secret)
+}
 
-    if epub_bytes.len() != 65 || epub_bytes[0] != 0x04 {
-        return Err(SeaError::Crypto(
-            "Invalid ECDH public key format".to_string(),
-        ));
+/// A 12-word BIP39 mnemonic of fresh random entropy, to write down and
+/// later turn back into a pair with [`pair_from_mnemonic`]
+#[cfg(feature = "mnemonic")]
+pub fn generate_mnemonic() -> String {
+    let mut entropy = [0u8; 16];
+    if crypto().fill_random(&mut entropy).is_err() {
+        return String::new();
     }
+    bip39::Mnemonic::from_entropy(&entropy)
+        .map(|mnemonic| mnemonic.to_string())
+        .unwrap_or_default()
+}
 
-    let ex = &epub_bytes[1..33];
-    let ey = &epub_bytes[33..65];
+/// Derive a key pair from a BIP39 mnemonic
+///
+/// The mnemonic and `passphrase` give the standard 64-byte BIP39 seed,
+/// which [`pair_from_seed`] turns into the pair.
+///
+/// # Errors
+/// `SeaError::InvalidKey` if `phrase` isn't a valid English mnemonic.
+#[cfg(feature = "mnemonic")]
+pub async fn pair_from_mnemonic(phrase: &str, passphrase: &str) -> Result<KeyPair, SeaError> {
+    let mnemonic = bip39::Mnemonic::parse_normalized(phrase).map_err(|_| SeaError::InvalidKey)?;
+    pair_from_seed(&mnemonic.to_seed_normalized(passphrase)).await
+}
 
-    let epub_key = Some(format!(
-        "{}.{}",
-        general_purpose::STANDARD_NO_PAD.encode(ex),
-        general_purpose::STANDARD_NO_PAD.encode(ey)
-    ));
+/// `signing_key` and `ecdh_secret` as a Gun.js pair
+fn export_pair(signing_key: &[u8; 32], ecdh_secret: &[u8; 32]) -> Result<KeyPair, SeaError> {
+    let provider = crypto();
+    let encode = |bytes: &[u8]| general_purpose::URL_SAFE_NO_PAD.encode(bytes);
 
-    // Export ECDH private key (32 bytes for P-256)
-    let epriv_bytes = ecdh_secret.to_bytes();
-    #[allow(deprecated)] // generic_array::as_slice is deprecated but aes-gcm still uses it
-    let epriv_key = Some(general_purpose::STANDARD_NO_PAD.encode(epriv_bytes.as_slice()));
+    // Public keys as Gun.js writes them, x.y, from the uncompressed point (0x04 || x || y)
+    let public_key = |secret: &[u8; 32]| -> Result<String, SeaError> {
+        let point = provider.p256_public_key(secret)?;
+        Ok(format!("{}.{}", encode(&point[1..33]), encode(&point[33..65])))
+    };
 
     Ok(KeyPair {
-        pub_key,
-        priv_key,
-        epub_key,
-        epriv_key,
+        pub_key: public_key(signing_key)?,
+        priv_key: encode(signing_key),
+        epub_key: Some(public_key(ecdh_secret)?),
+        epriv_key: Some(encode(ecdh_secret)),
     })
 }
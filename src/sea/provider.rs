@@ -0,0 +1,219 @@
+//! Crypto provider
+//! The primitives SEA is built on, behind one trait
+//!
+//! Every SEA operation gets its randomness, hashes, key derivation, P-256
+//! and AES-GCM from the installed [`CryptoProvider`]. The default,
+//! [`RustCryptoProvider`], is pure Rust and builds for wasm32 as well; an
+//! application can install another, such as one calling WebCrypto or a
+//! hardware module, with [`set_crypto_provider`].
+
+use super::SeaError;
+use aes_gcm::{
+    aead::{consts::U15, Aead, KeyInit},
+    aes::Aes256,
+    Aes256Gcm, AesGcm, Nonce,
+};
+use p256::ecdsa::{
+    signature::{Signer, Verifier},
+    Signature, SigningKey, VerifyingKey,
+};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use p256::{PublicKey, SecretKey};
+use parking_lot::RwLock;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, OnceLock};
+
+/// AES-256-GCM with the 15-byte IV of Gun.js
+type Aes256Gcm15 = AesGcm<Aes256, U15>;
+
+/// The primitives SEA is built on
+///
+/// Keys are raw bytes: private keys are 32-byte big-endian scalars, public
+/// keys uncompressed SEC1 points (`0x04 || x || y`), signatures `r || s`.
+/// ECDSA hashes with SHA-256 as WebCrypto does, so `p256_sign` signs the
+/// SHA-256 of `message`.
+pub trait CryptoProvider: Send + Sync {
+    /// Fill `buf` with cryptographically secure random bytes
+    fn fill_random(&self, buf: &mut [u8]) -> Result<(), SeaError>;
+
+    /// SHA-256 of `data`
+    fn sha256(&self, data: &[u8]) -> [u8; 32];
+
+    /// PBKDF2-HMAC-SHA256 of `password` with `salt`, filling `out`
+    fn pbkdf2_sha256(&self, password: &[u8], salt: &[u8], iterations: u32, out: &mut [u8]);
+
+    /// The public key of the P-256 private key `secret`
+    ///
+    /// `SeaError::InvalidKey` if `secret` isn't a scalar in 1..n, which is
+    /// also how key generation tells usable random bytes.
+    fn p256_public_key(&self, secret: &[u8]) -> Result<[u8; 65], SeaError>;
+
+    /// ECDSA P-256 signature of `message` with `secret`
+    fn p256_sign(&self, secret: &[u8], message: &[u8]) -> Result<[u8; 64], SeaError>;
+
+    /// Whether `signature` is `public_key`'s over `message`
+    ///
+    /// `SeaError::Crypto` if `public_key` isn't a P-256 point; a malformed
+    /// signature just doesn't verify.
+    fn p256_verify(
+        &self,
+        public_key: &[u8],
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<bool, SeaError>;
+
+    /// ECDH between `secret` and `public_key`: the x-coordinate of the
+    /// shared point
+    fn p256_ecdh(&self, secret: &[u8], public_key: &[u8]) -> Result<[u8; 32], SeaError>;
+
+    /// AES-256-GCM encryption of `plaintext`, the tag appended
+    ///
+    /// `iv` is 12 bytes, or 15 as Gun.js uses.
+    fn aes_gcm_encrypt(&self, key: &[u8], iv: &[u8], plaintext: &[u8])
+        -> Result<Vec<u8>, SeaError>;
+
+    /// AES-256-GCM decryption of what [`aes_gcm_encrypt`](Self::aes_gcm_encrypt) returned
+    fn aes_gcm_decrypt(
+        &self,
+        key: &[u8],
+        iv: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, SeaError>;
+}
+
+/// The default provider: the RustCrypto crates
+///
+/// Pure Rust on every target. Randomness comes from the OS through
+/// `getrandom`; on wasm32 in a browser or JS runtime that takes the `wasm`
+/// feature, which turns on its `crypto.getRandomValues` backend.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RustCryptoProvider;
+
+impl CryptoProvider for RustCryptoProvider {
+    fn fill_random(&self, buf: &mut [u8]) -> Result<(), SeaError> {
+        rand::rngs::OsRng
+            .try_fill_bytes(buf)
+            .map_err(|e| SeaError::Crypto(format!("No randomness: {}", e)))
+    }
+
+    fn sha256(&self, data: &[u8]) -> [u8; 32] {
+        Sha256::digest(data).into()
+    }
+
+    fn pbkdf2_sha256(&self, password: &[u8], salt: &[u8], iterations: u32, out: &mut [u8]) {
+        pbkdf2::pbkdf2_hmac::<Sha256>(password, salt, iterations, out);
+    }
+
+    fn p256_public_key(&self, secret: &[u8]) -> Result<[u8; 65], SeaError> {
+        let secret = SecretKey::from_slice(secret).map_err(|_| SeaError::InvalidKey)?;
+        let point = secret.public_key().to_encoded_point(false);
+        point
+            .as_bytes()
+            .try_into()
+            .map_err(|_| SeaError::Crypto("Invalid public key format".to_string()))
+    }
+
+    fn p256_sign(&self, secret: &[u8], message: &[u8]) -> Result<[u8; 64], SeaError> {
+        let signing_key = SigningKey::from_slice(secret)
+            .map_err(|e| SeaError::Crypto(format!("Invalid private key: {}", e)))?;
+        let signature: Signature = signing_key.sign(message);
+        let mut bytes = [0u8; 64];
+        bytes.copy_from_slice(&signature.to_bytes());
+        Ok(bytes)
+    }
+
+    fn p256_verify(
+        &self,
+        public_key: &[u8],
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<bool, SeaError> {
+        let verifying_key = VerifyingKey::from_sec1_bytes(public_key)
+            .map_err(|e| SeaError::Crypto(format!("Invalid public key: {}", e)))?;
+        Ok(Signature::from_slice(signature)
+            .is_ok_and(|signature| verifying_key.verify(message, &signature).is_ok()))
+    }
+
+    fn p256_ecdh(&self, secret: &[u8], public_key: &[u8]) -> Result<[u8; 32], SeaError> {
+        let secret = SecretKey::from_slice(secret).map_err(|_| SeaError::InvalidKey)?;
+        let public_key =
+            PublicKey::from_sec1_bytes(public_key).map_err(|_| SeaError::InvalidKey)?;
+        let shared = p256::ecdh::diffie_hellman(secret.to_nonzero_scalar(), public_key.as_affine());
+        let mut bytes = [0u8; 32];
+        bytes.copy_from_slice(shared.raw_secret_bytes());
+        Ok(bytes)
+    }
+
+    fn aes_gcm_encrypt(
+        &self,
+        key: &[u8],
+        iv: &[u8],
+        plaintext: &[u8],
+    ) -> Result<Vec<u8>, SeaError> {
+        let cipher = |e: aes_gcm::aes::cipher::InvalidLength| {
+            SeaError::Encryption(format!("Failed to create cipher: {}", e))
+        };
+        #[allow(deprecated)] // generic_array::from_slice is deprecated but aes-gcm still uses it
+        let result = match iv.len() {
+            15 => Aes256Gcm15::new_from_slice(key)
+                .map_err(cipher)?
+                .encrypt(Nonce::<U15>::from_slice(iv), plaintext),
+            12 => Aes256Gcm::new_from_slice(key)
+                .map_err(cipher)?
+                .encrypt(Nonce::from_slice(iv), plaintext),
+            n => return Err(SeaError::Encryption(format!("Invalid IV length: {}", n))),
+        };
+        result.map_err(|e| SeaError::Encryption(format!("Encryption failed: {}", e)))
+    }
+
+    fn aes_gcm_decrypt(
+        &self,
+        key: &[u8],
+        iv: &[u8],
+        ciphertext: &[u8],
+    ) -> Result<Vec<u8>, SeaError> {
+        let cipher = |e: aes_gcm::aes::cipher::InvalidLength| {
+            SeaError::Decryption(format!("Failed to create cipher: {}", e))
+        };
+        #[allow(deprecated)] // generic_array::from_slice is deprecated but aes-gcm still uses it
+        let result = match iv.len() {
+            15 => Aes256Gcm15::new_from_slice(key)
+                .map_err(cipher)?
+                .decrypt(Nonce::<U15>::from_slice(iv), ciphertext),
+            12 => Aes256Gcm::new_from_slice(key)
+                .map_err(cipher)?
+                .decrypt(Nonce::from_slice(iv), ciphertext),
+            n => return Err(SeaError::Decryption(format!("Invalid IV length: {}", n))),
+        };
+        result.map_err(|e| SeaError::Decryption(format!("Decryption failed: {}", e)))
+    }
+}
+
+/// The provider every SEA operation uses
+static PROVIDER: OnceLock<RwLock<Arc<dyn CryptoProvider>>> = OnceLock::new();
+
+fn installed() -> &'static RwLock<Arc<dyn CryptoProvider>> {
+    PROVIDER.get_or_init(|| RwLock::new(Arc::new(RustCryptoProvider)))
+}
+
+/// Use `provider` for every SEA operation from now on
+///
+/// Process-wide, like [`verify_cache`](super::verify_cache). Install it
+/// before the first SEA call, so no data is half made with another.
+pub fn set_crypto_provider(provider: Arc<dyn CryptoProvider>) {
+    *installed().write() = provider;
+}
+
+/// The provider SEA operations use, [`RustCryptoProvider`] unless
+/// [`set_crypto_provider`] installed another
+pub fn crypto() -> Arc<dyn CryptoProvider> {
+    installed().read().clone()
+}
+
+/// `len` random bytes from the installed provider
+pub(crate) fn random_bytes(len: usize) -> Result<Vec<u8>, SeaError> {
+    let mut bytes = vec![0u8; len];
+    crypto().fill_random(&mut bytes)?;
+    Ok(bytes)
+}
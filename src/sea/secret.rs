@@ -2,13 +2,14 @@
 //! Based on Gun.js sea/secret.js
 //! Derives a shared secret from ECDH key exchange
 
-use super::SeaError;
+use super::provider::crypto;
+use super::settings::decode_base64;
+use super::{KeyPair, SeaError};
 use base64::{engine::general_purpose, Engine as _};
-use p256::{PublicKey, SecretKey};
 
 /// Derive shared secret from ECDH key exchange
 /// Takes a public key (epub) and a key pair with epriv/epub
-/// Returns the derived secret key (base64 encoded)
+/// Returns the derived secret key (base64url encoded)
 pub(crate) async fn derive_secret(
     their_epub: &str,
     our_epriv: &str,
@@ -19,63 +20,74 @@ pub(crate) async fn derive_secret(
 
     // Parse our keys
     let _our_pub = parse_epub(our_epub)?;
-    let our_priv_bytes = general_purpose::STANDARD_NO_PAD
-        .decode(our_epriv)
-        .map_err(|_| SeaError::InvalidKey)?;
+    let our_priv_bytes = decode_base64(our_epriv).ok_or(SeaError::InvalidKey)?;
 
-    // Create our secret key from private key bytes (32 bytes for P-256)
+    // Our private key is 32 bytes for P-256
     if our_priv_bytes.len() != 32 {
         return Err(SeaError::InvalidKey);
     }
-    let mut priv_array = [0u8; 32];
-    priv_array.copy_from_slice(&our_priv_bytes);
-    let our_secret = SecretKey::from_bytes(&priv_array.into()).map_err(|_| SeaError::InvalidKey)?;
 
     // Derive shared secret using ECDH
-    // Use the ecdh module's diffie_hellman function
-    let shared_secret =
-        p256::ecdh::diffie_hellman(our_secret.to_nonzero_scalar(), their_pub.as_affine());
+    // The x-coordinate of the shared point is the secret, as in Gun.js
+    let shared_point = crypto().p256_ecdh(&our_priv_bytes, &their_pub)?;
 
-    // Extract the x-coordinate of the shared point as the secret
-    // This is what Gun.js does - uses the x coordinate
-    let shared_point = shared_secret.raw_secret_bytes();
-
-    // Return as base64 (matching Gun.js format)
-    #[allow(deprecated)] // generic_array::as_slice is deprecated but aes-gcm still uses it
-    #[allow(deprecated)] // generic_array::as_slice is deprecated but aes-gcm still uses it
-    Ok(general_purpose::STANDARD_NO_PAD.encode(shared_point.as_slice()))
+    // Return as base64url, the `k` of the JWK Gun.js exports it as
+    Ok(general_purpose::URL_SAFE_NO_PAD.encode(shared_point))
 }
 
-/// Public API for secret derivation
-/// Derives shared secret from ECDH key exchange
+/// Derive the secret shared with the holder of `their_epub`
 /// Based on Gun.js SEA.secret()
-pub async fn secret(
-    their_epub: &str,
-    our_epriv: &str,
-    our_epub: &str,
-) -> Result<String, super::SeaError> {
+///
+/// P-256 ECDH between `their_epub` and `pair`'s epriv; the x-coordinate of
+/// the shared point is returned as base64url, as Gun.js exports it. Both
+/// sides derive the same secret, which [`encrypt_with`](super::encrypt_with)
+/// and [`decrypt_with`](super::decrypt_with) take as their key, as Gun.js
+/// `SEA.encrypt(data, secret)` does.
+///
+/// # Errors
+/// `SeaError::InvalidKey` if `their_epub` isn't a P-256 key, or `pair` has
+/// no encryption keys.
+///
+/// # Example
+/// ```rust,no_run
+/// use gun::sea::{decrypt_with, encrypt_with, pair, secret};
+/// use serde_json::json;
+///
+/// # async fn example() -> Result<(), gun::sea::SeaError> {
+/// let alice = pair().await?;
+/// let bob = pair().await?;
+/// let to_bob = secret(bob.epub_key.as_deref().unwrap(), &alice).await?;
+/// let message = encrypt_with(&json!("hi bob"), &to_bob).await?;
+///
+/// let from_alice = secret(alice.epub_key.as_deref().unwrap(), &bob).await?;
+/// assert_eq!(decrypt_with(&message, &from_alice).await?, json!("hi bob"));
+/// # Ok(())
+/// # }
+/// ```
+pub async fn secret(their_epub: &str, pair: &KeyPair) -> Result<String, SeaError> {
+    let our_epriv = pair.epriv_key.as_deref().ok_or(SeaError::InvalidKey)?;
+    let our_epub = pair.epub_key.as_deref().ok_or(SeaError::InvalidKey)?;
     derive_secret(their_epub, our_epriv, our_epub).await
 }
 
-/// Parse an epub key (format: x.y base64) into a PublicKey
-fn parse_epub(epub: &str) -> Result<PublicKey, SeaError> {
+/// Parse an epub key (format: x.y base64) into its uncompressed SEC1 bytes
+fn parse_epub(epub: &str) -> Result<Vec<u8>, SeaError> {
     let parts: Vec<&str> = epub.split('.').collect();
     if parts.len() != 2 {
         return Err(SeaError::InvalidKey);
     }
 
-    let x = general_purpose::STANDARD_NO_PAD
-        .decode(parts[0])
-        .map_err(|_| SeaError::InvalidKey)?;
-    let y = general_purpose::STANDARD_NO_PAD
-        .decode(parts[1])
-        .map_err(|_| SeaError::InvalidKey)?;
+    let x = decode_base64(parts[0]).ok_or(SeaError::InvalidKey)?;
+    let y = decode_base64(parts[1]).ok_or(SeaError::InvalidKey)?;
 
     // Reconstruct uncompressed public key (0x04 || x || y)
     let mut pub_bytes = vec![0x04u8];
     pub_bytes.extend_from_slice(&x);
     pub_bytes.extend_from_slice(&y);
 
-    // Import as public key
-    PublicKey::from_sec1_bytes(&pub_bytes).map_err(|_| SeaError::InvalidKey)
+    // Whether it is on the curve is for ECDH to check
+    if pub_bytes.len() != 65 {
+        return Err(SeaError::InvalidKey);
+    }
+    Ok(pub_bytes)
 }
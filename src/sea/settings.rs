@@ -0,0 +1,112 @@
+//! Shared helpers
+//! Based on Gun.js sea/settings.js and sea/shim.js
+//! How SEA reads and writes JSON and keys, byte for byte like Gun.js
+
+use base64::{engine::general_purpose, Engine as _};
+use serde_json::{Number, Value};
+
+/// Prefix of signed data in Gun.js: `SEA{"m":…,"s":…}`
+pub(crate) const SEA_PREFIX: &str = "SEA";
+
+/// Read `data` as Gun.js `S.parse` does
+///
+/// A string holding JSON, after any `SEA` prefix, is the data it holds;
+/// anything else is returned as it is.
+pub(crate) fn parse(data: &Value) -> Value {
+    let Value::String(text) = data else {
+        return data.clone();
+    };
+    let text = text.strip_prefix(SEA_PREFIX).filter(|t| t.starts_with('{')).unwrap_or(text);
+    serde_json::from_str(text).unwrap_or_else(|_| data.clone())
+}
+
+/// Write `value` as JavaScript's `JSON.stringify` does
+///
+/// Signatures are made over these bytes, so numbers are written as
+/// `Number.prototype.toString` writes them (`30`, not `30.0`; `1e+21`).
+/// Object keys keep the order of the `Value`.
+pub(crate) fn stringify(value: &Value) -> String {
+    let mut out = String::new();
+    write_json(&mut out, value);
+    out
+}
+
+fn write_json(out: &mut String, value: &Value) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => out.push_str(&js_number(n)),
+        Value::String(s) => out.push_str(&serde_json::to_string(s).unwrap_or_default()),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_json(out, item);
+            }
+            out.push(']');
+        }
+        Value::Object(fields) => {
+            out.push('{');
+            for (i, (key, value)) in fields.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&serde_json::to_string(key).unwrap_or_default());
+                out.push(':');
+                write_json(out, value);
+            }
+            out.push('}');
+        }
+    }
+}
+
+/// A number as JavaScript writes it
+fn js_number(n: &Number) -> String {
+    // Integers are written alike
+    if !n.is_f64() {
+        return n.to_string();
+    }
+    let f = n.as_f64().unwrap_or_default();
+    if f == 0.0 {
+        return "0".to_string();
+    }
+    // Shortest digits that read back as `f`, and where the point goes
+    let sci = format!("{:e}", f.abs());
+    let (mantissa, exponent) = sci.split_once('e').unwrap_or((&sci, "0"));
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let (k, n) = (digits.len() as i32, exponent.parse::<i32>().unwrap_or(0) + 1);
+    let abs = if k <= n && n <= 21 {
+        format!("{}{}", digits, "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat(-n as usize), digits)
+    } else {
+        let fraction = if k > 1 { format!(".{}", &digits[1..]) } else { String::new() };
+        format!("{}{}e{}{}", &digits[..1], fraction, if n > 0 { "+" } else { "-" }, (n - 1).abs())
+    };
+    if f < 0.0 {
+        format!("-{}", abs)
+    } else {
+        abs
+    }
+}
+
+/// Decode base64 as Node's `Buffer.from(s, 'base64')` does: standard or
+/// URL-safe alphabet, with or without padding
+///
+/// Gun.js keys are base64url (JWK), signatures standard base64.
+pub(crate) fn decode_base64(s: &str) -> Option<Vec<u8>> {
+    let s: String = s
+        .trim_end_matches('=')
+        .chars()
+        .map(|c| match c {
+            '-' => '+',
+            '_' => '/',
+            c => c,
+        })
+        .collect();
+    general_purpose::STANDARD_NO_PAD.decode(s).ok()
+}
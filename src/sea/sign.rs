@@ -2,44 +2,55 @@
 //! Based on Gun.js sea/sign.js
 //! ECDSA P-256 signing
 
-use super::{KeyPair, SeaError};
+use super::provider::crypto;
+use super::settings::{decode_base64, parse, stringify, SEA_PREFIX};
+use super::{verify, KeyPair, SeaError};
 use base64::{engine::general_purpose, Engine as _};
-use p256::ecdsa::Signature;
-use p256::ecdsa::{signature::Signer, SigningKey};
 use serde_json::Value;
 
 /// Sign data with a key pair
-/// Returns signed data in format: {m: message, s: signature}
 ///
-/// The message is JSON-serialized and signed using ECDSA P-256
+/// Returns the string Gun.js peers read: `SEA{"m":data,"s":signature}`.
+/// `s` is the base64 ECDSA P-256 signature (`r || s`), with SHA-256, of
+/// the SHA-256 of `data` as `JSON.stringify` writes it, or of `data`
+/// itself if it is a string; WebCrypto hashes the hash once more, as in
+/// Gun.js. A string holding JSON is signed as the data it holds, and data
+/// the pair already signed is returned as it is.
 pub async fn sign(data: &Value, key_pair: &KeyPair) -> Result<Value, SeaError> {
-    // Serialize data
-    let message = serde_json::to_string(data)
-        .map_err(|e| SeaError::Crypto(format!("Serialization error: {}", e)))?;
+    let json = parse(data);
+    if json.get("m").is_some() && json.get("s").is_some() && verify(data, &key_pair.pub_key).await.is_ok() {
+        return Ok(match data {
+            Value::String(signed) if signed.starts_with(SEA_PREFIX) => data.clone(),
+            _ => Value::String(format!("{}{}", SEA_PREFIX, stringify(&json))),
+        });
+    }
 
     // Create signing key from private key
-    let priv_bytes = general_purpose::STANDARD_NO_PAD
-        .decode(&key_pair.priv_key)
-        .map_err(|_| SeaError::InvalidKey)?;
+    let priv_bytes = decode_base64(&key_pair.priv_key).ok_or(SeaError::InvalidKey)?;
 
-    // Convert Vec<u8> to fixed-size array for SigningKey
+    // A P-256 private key is 32 bytes
     if priv_bytes.len() != 32 {
         return Err(SeaError::InvalidKey);
     }
-    let mut priv_array = [0u8; 32];
-    priv_array.copy_from_slice(&priv_bytes);
-    let signing_key = SigningKey::from_bytes(&priv_array.into())
-        .map_err(|e| SeaError::Crypto(format!("Invalid private key: {}", e)))?;
 
-    // Sign the message (ECDSA with SHA-256)
-    let signature: Signature = signing_key.sign(message.as_bytes());
-    let sig_bytes = signature.to_bytes();
-    #[allow(deprecated)] // generic_array::as_slice is deprecated but aes-gcm still uses it
-    let sig_b64 = general_purpose::STANDARD_NO_PAD.encode(sig_bytes.as_slice());
+    // Sign the hash (ECDSA with SHA-256, like WebCrypto)
+    let provider = crypto();
+    let hash = provider.sha256(hashed_text(&json).as_bytes());
+    let sig_bytes = provider.p256_sign(&priv_bytes, &hash)?;
+    let sig_b64 = general_purpose::STANDARD.encode(sig_bytes);
 
-    // Return in Gun.js format: {m: message, s: signature}
-    Ok(serde_json::json!({
-        "m": message,
+    let signed = serde_json::json!({
+        "m": json,
         "s": sig_b64
-    }))
+    });
+    Ok(Value::String(format!("{}{}", SEA_PREFIX, stringify(&signed))))
+}
+
+/// What Gun.js hashes to sign or verify `m`: a string as it is, anything
+/// else as JSON
+pub(crate) fn hashed_text(m: &Value) -> String {
+    match m {
+        Value::String(text) => text.clone(),
+        m => stringify(m),
+    }
 }
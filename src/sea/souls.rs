@@ -0,0 +1,75 @@
+//! User space souls
+//! Based on Gun.js sea/user.js
+//! The souls accounts are kept under: `~pub`, `~pub/path` and `~@alias`
+
+use super::SeaError;
+use base64::{engine::general_purpose, Engine as _};
+
+/// A user space soul, as [`parse_user_soul`] reads it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParsedUserSoul {
+    /// `~pub`, the account, or `~pub/path`, a node under it
+    User {
+        /// The owner's pub, `x.y` in base64url
+        pub_key: String,
+        /// What follows the `/`, if anything
+        path: Option<String>,
+    },
+    /// `~@alias`, linking to the accounts that go by the alias
+    Alias(String),
+}
+
+/// The soul of the account of `pub_key`, `~pub`
+///
+/// # Errors
+/// `SeaError::InvalidKey` if `pub_key` isn't a P-256 pub as [`pair`](super::pair)
+/// writes it: two 32-byte coordinates in unpadded base64url, joined by `.`.
+pub fn user_soul(pub_key: &str) -> Result<String, SeaError> {
+    if !is_pub(pub_key) {
+        return Err(SeaError::InvalidKey);
+    }
+    Ok(format!("~{}", pub_key))
+}
+
+/// The soul linking `alias` to its accounts, `~@alias`
+///
+/// # Errors
+/// `SeaError::Crypto` if `alias` is empty.
+pub fn alias_soul(alias: &str) -> Result<String, SeaError> {
+    if alias.is_empty() {
+        return Err(SeaError::Crypto("Alias can't be empty".to_string()));
+    }
+    Ok(format!("~@{}", alias))
+}
+
+/// Read a user space soul
+///
+/// `None` if `soul` doesn't start with `~`, or does but isn't `~@alias`
+/// with an alias, nor `~pub` or `~pub/path` with a pub [`user_soul`] takes
+/// and a path.
+pub fn parse_user_soul(soul: &str) -> Option<ParsedUserSoul> {
+    let space = soul.strip_prefix('~')?;
+    if let Some(alias) = space.strip_prefix('@') {
+        return (!alias.is_empty()).then(|| ParsedUserSoul::Alias(alias.to_string()));
+    }
+    let (pub_key, path) = match space.split_once('/') {
+        Some((_, "")) => return None,
+        Some((pub_key, path)) => (pub_key, Some(path.to_string())),
+        None => (space, None),
+    };
+    is_pub(pub_key).then(|| ParsedUserSoul::User {
+        pub_key: pub_key.to_string(),
+        path,
+    })
+}
+
+/// Whether `pub_key` is `x.y`, each 32 bytes in unpadded base64url
+fn is_pub(pub_key: &str) -> bool {
+    let Some((x, y)) = pub_key.split_once('.') else {
+        return false;
+    };
+    [x, y].iter().all(|coordinate| {
+        coordinate.len() == 43
+            && general_purpose::URL_SAFE_NO_PAD.decode(coordinate).is_ok_and(|bytes| bytes.len() == 32)
+    })
+}
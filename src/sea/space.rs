@@ -0,0 +1,171 @@
+//! User space write rules
+//! Based on Gun.js sea/index.js (check.pub, check.alias)
+//! Data under `~pub` souls must be signed by pub, or by a writer pub certified
+
+use super::certify::verify_certificate_sync;
+use super::settings::{parse, stringify, SEA_PREFIX};
+use super::verify::verify_sync;
+use super::{parse_user_soul, sign, verify_write, KeyPair, ParsedUserSoul, SeaError};
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use serde_json::{json, Value};
+
+/// A user space value as Gun.js writes it: `{":": data, "~": signature}`,
+/// plus the certificate (`+`) and pub (`*`) of a writer other than the owner
+#[derive(Deserialize)]
+struct Packed<'a> {
+    #[serde(rename = ":")]
+    data: Value,
+    #[serde(rename = "~")]
+    sig: String,
+    #[serde(rename = "+", borrow, default)]
+    cert: Option<&'a RawValue>,
+    #[serde(rename = "*", default)]
+    writer: Option<String>,
+}
+
+/// The owner's pub of a user space soul, `~pub` or `~pub/path`
+fn owner(soul: &str) -> Option<String> {
+    match parse_user_soul(soul)? {
+        ParsedUserSoul::User { pub_key, .. } => Some(pub_key),
+        ParsedUserSoul::Alias(_) => None,
+    }
+}
+
+/// Check a write of `key` = `value` at `state` to a user space soul
+///
+/// Souls not starting with `~` aren't user space and pass. `~@alias` souls
+/// may only link to accounts under their own soul (`"~pub": {"#": "~pub"}`),
+/// or drop that link with a `null` the account signed.
+/// In `~pub` souls, `value` must be the string Gun.js writes (see
+/// [`sign_put`]), signed by pub over the soul, key, value and state, so it
+/// can't be copied to another key or replayed at a newer state. Replayed at
+/// its own, older state it still verifies: merging it by HAM (see
+/// [`State::ham`](crate::state::State::ham)) is what keeps it from rolling
+/// back a newer value. A bare
+/// `SEA{…}` string from [`sign`] binds none of these and is refused. A
+/// value signed by another writer (`*`) needs a certificate (`+`) from pub
+/// that lets them write there, see [`verify_write`]. The one unsigned value
+/// allowed is a link from `key` to the node nested under it, `{"#":
+/// "~pub/key"}`, whose own writes are checked the same way.
+pub async fn verify_user_put(soul: &str, key: &str, value: &Value, state: Option<&Value>) -> Result<(), SeaError> {
+    verify_user_put_sync(soul, key, value, state)
+}
+
+/// [`verify_user_put`], for callers that can't await
+pub(crate) fn verify_user_put_sync(soul: &str, key: &str, value: &Value, state: Option<&Value>) -> Result<(), SeaError> {
+    if !soul.starts_with('~') {
+        return Ok(());
+    }
+    if let Some(alias) = soul.strip_prefix("~@") {
+        let links_itself = value.get("#").and_then(|v| v.as_str()) == Some(key);
+        if links_itself && owner(key).is_some() {
+            return Ok(());
+        }
+        // The account itself unlinking from the alias
+        if let (Some(pub_key), Some(packed)) = (owner(key), unpack(value)) {
+            if packed.data.is_null() && packed.writer.is_none() {
+                return verify_packed(soul, key, &packed, state, &pub_key);
+            }
+        }
+        return Err(SeaError::Crypto(format!("Alias {} may only link to accounts under their own soul", alias)));
+    }
+    let pub_key = owner(soul).ok_or(SeaError::InvalidKey)?;
+
+    // The node nested under key, which is checked on its own
+    if value.get("#").and_then(|v| v.as_str()) == Some(&format!("{}/{}", soul, key)) {
+        return Ok(());
+    }
+
+    let packed = unpack(value).ok_or_else(|| SeaError::Crypto(format!("Data under {} must be signed", soul)))?;
+    let writer = packed.writer.as_deref().unwrap_or(&pub_key);
+    verify_packed(soul, key, &packed, state, writer)?;
+
+    // Someone else writing needs the owner's certificate
+    if writer != pub_key {
+        let cert = packed
+            .cert
+            .ok_or_else(|| SeaError::CertInvalid { reason: format!("{} needs a certificate to write under {}", writer, soul) })?;
+        let cert = match serde_json::from_str::<Value>(cert.get()) {
+            Ok(Value::String(cert)) => cert,
+            _ => format!("{}{}", SEA_PREFIX, cert.get()),
+        };
+        let cert = verify_certificate_sync(&cert, &pub_key)?;
+        if !verify_write(&cert, writer, soul, key) {
+            return Err(SeaError::CertInvalid { reason: format!("Certificate doesn't let {} write {} under {}", writer, key, soul) });
+        }
+    }
+    Ok(())
+}
+
+/// `value` as Gun.js packs a signed value, if it is one
+fn unpack(value: &Value) -> Option<Packed<'_>> {
+    serde_json::from_str(value.as_str()?).ok()
+}
+
+/// Check that `writer` signed `packed` for `key` of `soul` at `state`, as
+/// [`sign_put`] signs it
+fn verify_packed(soul: &str, key: &str, packed: &Packed<'_>, state: Option<&Value>, writer: &str) -> Result<(), SeaError> {
+    let state = state.ok_or_else(|| SeaError::Crypto(format!("Signed data under {} needs its state", soul)))?;
+    let signed = json!({ "m": { "#": soul, ".": key, ":": packed.data, ">": state }, "s": packed.sig });
+    verify_sync(&signed, writer).map(|_| ())
+}
+
+/// Read a value of the user space `soul` as its writer signed it
+///
+/// Returns the data inside what [`sign_put`] or [`sign`] wrote, and whether
+/// [`verify_user_put`] accepts it, which it only does for [`sign_put`]'s.
+/// Values outside user space, or not signed, are returned as they are,
+/// unverified.
+pub async fn open_user_value(soul: &str, key: &str, value: &Value, state: Option<&Value>) -> (Value, bool) {
+    open_user_value_sync(soul, key, value, state)
+}
+
+/// [`open_user_value`], for callers that can't await, such as `on`
+/// listeners
+pub(crate) fn open_user_value_sync(soul: &str, key: &str, value: &Value, state: Option<&Value>) -> (Value, bool) {
+    let data = match value {
+        Value::String(text) if soul.starts_with('~') && text.starts_with(SEA_PREFIX) => {
+            let signed = parse(value);
+            signed.get("s").and(signed.get("m")).map(parse)
+        }
+        Value::String(text) if soul.starts_with('~') => serde_json::from_str::<Packed>(text).ok().map(|packed| packed.data),
+        _ => None,
+    };
+    match data {
+        Some(data) => (data, verify_user_put_sync(soul, key, value, state).is_ok()),
+        None => (value.clone(), false),
+    }
+}
+
+/// Sign `value` for `key` of the user space `soul` at `state`
+///
+/// Returns the string Gun.js writes, `{":": value, "~": signature}`,
+/// signed over `{"#": soul, ".": key, ":": value, ">": state}`. Writing
+/// under someone else's soul takes a `cert` from them: the value then also
+/// carries it (`+`) and `pair`'s pub (`*`).
+pub async fn sign_put(
+    soul: &str,
+    key: &str,
+    value: &Value,
+    state: f64,
+    pair: &KeyPair,
+    cert: Option<&str>,
+) -> Result<Value, SeaError> {
+    let signed = sign(&json!({ "#": soul, ".": key, ":": value, ">": state }), pair).await?;
+    let sig = signed
+        .as_str()
+        .and_then(|signed| serde_json::from_str::<Value>(&signed[SEA_PREFIX.len()..]).ok())
+        .and_then(|signed| signed.get("s").cloned())
+        .ok_or_else(|| SeaError::Crypto("Signature is not in SEA format".to_string()))?;
+
+    let mut packed = stringify(&json!({ ":": value, "~": sig }));
+    if let Some(cert) = cert {
+        // The certificate as its authority wrote it, so it still verifies
+        let cert = cert.strip_prefix(SEA_PREFIX).unwrap_or(cert);
+        serde_json::from_str::<Value>(cert).map_err(|e| SeaError::CertInvalid { reason: e.to_string() })?;
+        packed.pop();
+        packed.push_str(&format!(",\"+\":{},\"*\":{}}}", cert, stringify(&json!(pair.pub_key))));
+    }
+    Ok(Value::String(packed))
+}
@@ -3,14 +3,14 @@
 //! User creation, authentication, and password management
 
 use super::pair;
-use super::{SeaError, UserAuth, KeyPair};
+use super::{alias_soul, parse_user_soul, user_soul, KeyPair, ParsedUserSoul, SeaError, UserAuth};
 use crate::chain::Chain;
-use aes_gcm::{Aes256Gcm, KeyInit, aead::Aead};
+use super::provider::{crypto, random_bytes};
 use base64::{engine::general_purpose, Engine as _};
-use pbkdf2::pbkdf2_hmac;
+use parking_lot::RwLock;
 use serde_json::json;
-use sha2::Sha256;
 use std::sync::Arc;
+use subtle::ConstantTimeEq;
 
 /// Create a new user with key pair and store in graph
 /// 
@@ -28,24 +28,22 @@ use std::sync::Arc;
 /// # Storage Format
 /// 
 /// User data is stored in the graph at:
-/// - `~{pub_key}`: Main user node with encrypted keys and password hash
-/// - `~{pub_key}@{alias}`: Alias lookup node pointing to main user node (if alias provided)
+/// - `~{pub_key}`: Main user node with encrypted keys and password hash, each
+///   field signed by the new pair (see [`sign_put`](super::sign_put))
+/// - `~@{alias}`: Alias node linking to the main user node, `{"~{pub_key}": {"#": "~{pub_key}"}}`
+///   (if alias provided), as Gun.js writes it
 /// 
 /// # Errors
 /// - `SeaError::Crypto`: If key generation, encryption, or storage fails
+/// - `SeaError::Encryption`: If the private keys can't be encrypted with the password
 /// 
 /// # Example
 /// ```rust,no_run
-/// use gun::{Gun, Chain};
 /// use gun::sea::create_user;
-/// use std::sync::Arc;
-/// 
-/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
-/// let gun = Gun::new();
-/// let chain = gun.root();
 /// 
+/// # async fn example(gun: gun::Gun) -> Result<(), Box<dyn std::error::Error>> {
 /// // Create user with alias
-/// let user = create_user(Arc::new(chain), Some("alice".to_string()), "secure_password")
+/// let user = create_user(gun.root(), Some("alice".to_string()), "secure_password")
 ///     .await?;
 /// 
 /// println!("User created with pub key: {}", user.pair.pub_key);
@@ -58,7 +56,37 @@ pub async fn create_user(
     password: &str,
 ) -> Result<UserAuth, SeaError> {
     let pair = pair::generate_pair().await?;
+    let user_soul = user_soul(&pair.pub_key)?;
+    let user_data = account(&pair, alias.as_deref(), password).await?;
+    let auth = UserAuth { pair, alias };
+    
+    // Store in graph, signed by the new pair
+    signed_by(&chain, user_soul.clone(), auth.clone()).put(user_data).await
+        .map_err(|e| SeaError::Crypto(format!("Failed to store user data: {}", e)))?;
     
+    // If alias provided, also link ~@alias to it for lookup
+    if let Some(ref alias_str) = auth.alias {
+        let alias_soul = alias_soul(alias_str)?;
+        chain.get(&alias_soul).put(json!({
+            user_soul.clone(): { "#": user_soul }
+        })).await
+            .map_err(|e| SeaError::Crypto(format!("Failed to store alias: {}", e)))?;
+    }
+    
+    Ok(auth)
+}
+
+/// Chain to `soul` whose writes are signed by `auth`'s pair, as its user
+/// space writes must be
+fn signed_by(chain: &Chain, soul: String, auth: UserAuth) -> Arc<Chain> {
+    let signer = Arc::new(User { auth: RwLock::new(Some(auth)) });
+    Arc::new(Chain::with_soul(chain.core.clone(), soul, None).with_user(signer))
+}
+
+/// The account node for `pair`, as stored at `~pub`: its private keys
+/// encrypted with a key derived from `password` and a fresh salt, and the
+/// password hash
+async fn account(pair: &KeyPair, alias: Option<&str>, password: &str) -> Result<serde_json::Value, SeaError> {
     // Generate salt for password hashing
     let salt = generate_salt();
     let password_hash = hash_password(password, &salt);
@@ -88,17 +116,11 @@ pub async fn create_user(
         .map_err(|e| SeaError::Crypto(format!("Serialization error: {}", e)))?;
     
     // Use AES-GCM directly with password-derived key
-    use aes_gcm::{Aes256Gcm, KeyInit, aead::Aead};
-    use rand::RngCore;
+    let provider = crypto();
     let password_key_bytes = general_purpose::STANDARD_NO_PAD.decode(&password_key)
         .map_err(|_| SeaError::Crypto("Failed to decode password key".to_string()))?;
-    let cipher = Aes256Gcm::new_from_slice(&password_key_bytes)
-        .map_err(|e| SeaError::Crypto(format!("Failed to create cipher: {}", e)))?;
-    let mut nonce_bytes = [0u8; 12];
-    rand::thread_rng().fill_bytes(&mut nonce_bytes);
-    let nonce = aes_gcm::Nonce::from_slice(&nonce_bytes);
-    let ciphertext = cipher.encrypt(nonce, priv_data_str.as_bytes())
-        .map_err(|e| SeaError::Crypto(format!("Encryption failed: {}", e)))?;
+    let nonce_bytes = random_bytes(12)?;
+    let ciphertext = provider.aes_gcm_encrypt(&password_key_bytes, &nonce_bytes, priv_data_str.as_bytes())?;
     
     let priv_key_encrypted = json!({
         "ct": general_purpose::STANDARD_NO_PAD.encode(ciphertext),
@@ -111,11 +133,9 @@ pub async fn create_user(
         let epriv_data = json!({"epriv": epriv});
         let epriv_data_str = serde_json::to_string(&epriv_data)
             .map_err(|e| SeaError::Crypto(format!("Serialization error: {}", e)))?;
-        let mut epriv_nonce_bytes = [0u8; 12];
-        rand::thread_rng().fill_bytes(&mut epriv_nonce_bytes);
-        let epriv_nonce = aes_gcm::Nonce::from_slice(&epriv_nonce_bytes);
-        let epriv_ciphertext = cipher.encrypt(epriv_nonce, epriv_data_str.as_bytes())
-            .map_err(|e| SeaError::Crypto(format!("Encryption failed: {}", e)))?;
+        let epriv_nonce_bytes = random_bytes(12)?;
+        let epriv_ciphertext =
+            provider.aes_gcm_encrypt(&password_key_bytes, &epriv_nonce_bytes, epriv_data_str.as_bytes())?;
         json!({
             "ct": general_purpose::STANDARD_NO_PAD.encode(epriv_ciphertext),
             "iv": general_purpose::STANDARD_NO_PAD.encode(epriv_nonce_bytes),
@@ -125,38 +145,23 @@ pub async fn create_user(
         json!({})
     };
     
-    // Store user data in graph at ~@alias (where ~ is pub key)
-    let user_soul = format!("~{}", pair.pub_key);
-    let user_data = json!({
-        "alias": alias.clone().unwrap_or_default(),
+    Ok(json!({
+        "alias": alias.unwrap_or_default(),
         "pub": pair.pub_key.clone(),
         "epub": pair.epub_key.clone(),
         "hash": password_hash,
         "salt": general_purpose::STANDARD_NO_PAD.encode(&salt),
         "priv": priv_key_encrypted,
         "epriv": epriv_key_encrypted,
-    });
-    
-    // Store in graph
-    chain.get(&user_soul).put(user_data).await
-        .map_err(|e| SeaError::Crypto(format!("Failed to store user data: {}", e)))?;
-    
-    // If alias provided, also store at ~@alias for lookup
-    if let Some(ref alias_str) = alias {
-        let alias_soul = format!("~{}@{}", pair.pub_key, alias_str);
-        chain.get(&alias_soul).put(json!({
-            "#": user_soul
-        })).await
-            .map_err(|e| SeaError::Crypto(format!("Failed to store alias: {}", e)))?;
-    }
-    
-    Ok(UserAuth { pair, alias })
+    }))
 }
 
 /// Authenticate user with password
 /// 
-/// Looks up user in graph by alias, verifies password hash, and decrypts private keys.
-/// Returns authenticated `UserAuth` with full key pair if authentication succeeds.
+/// Looks up the accounts linked from `~@alias`, locally and then from peers,
+/// verifies password hash, and decrypts private keys. Only accounts holding
+/// their own pub (`pub` of `~pub`) are tried. Returns authenticated `UserAuth`
+/// with full key pair if authentication succeeds.
 /// 
 /// # Arguments
 /// * `chain` - Chain instance to access the graph (typically `gun.root()`)
@@ -167,7 +172,9 @@ pub async fn create_user(
 /// `UserAuth` with decrypted key pair if authentication succeeds
 /// 
 /// # Errors
-/// - `SeaError::Crypto`: If user not found, password incorrect, or decryption fails
+/// - `SeaError::UserNotFound`: If no account goes by `alias`, or it was deleted
+/// - `SeaError::WrongPassword`: If `password` doesn't open the account
+/// - `SeaError::Crypto` / `SeaError::Decryption`: If the account is malformed or can't be decrypted
 /// 
 /// # Security
 /// 
@@ -177,21 +184,26 @@ pub async fn create_user(
 ///
 /// # Example
 /// ```rust,no_run
-/// use gun::{Gun, Chain};
-/// use gun::sea::{create_user, authenticate};
-/// use std::sync::Arc;
+/// use gun::sea::{create_user, authenticate, SeaError};
 /// 
-/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
-/// let gun = Gun::new();
+/// # async fn example(gun: gun::Gun) -> Result<(), Box<dyn std::error::Error>> {
 /// let chain = gun.root();
 /// 
 /// // Create user
-/// create_user(Arc::new(chain.clone()), Some("alice".to_string()), "password123")
+/// create_user(chain.clone(), Some("alice".to_string()), "password123")
 ///     .await?;
 /// 
 /// // Authenticate
-/// let user = authenticate(Arc::new(chain), "alice", "password123").await?;
+/// let user = authenticate(chain.clone(), "alice", "password123").await?;
 /// println!("Authenticated user: {:?}", user.alias);
+///
+/// // Tell a typo from a missing account
+/// match authenticate(chain, "alice", "password124").await {
+///     Err(SeaError::WrongPassword) => println!("Wrong password, try again"),
+///     Err(SeaError::UserNotFound) => println!("No such user"),
+///     Err(e) => return Err(e.into()),
+///     Ok(_) => unreachable!(),
+/// }
 /// # Ok(())
 /// # }
 /// ```
@@ -200,66 +212,75 @@ pub async fn authenticate(
     alias: &str,
     password: &str,
 ) -> Result<UserAuth, SeaError> {
-    // Look up user by alias
-    // In Gun.js, aliases are stored at ~@alias which points to ~pub
-    // We need to search for the alias in the graph
-    // The alias is stored at a node that has the alias as a key pointing to the user soul
-    
-    // Try to find user by searching for alias in graph
-    // In Gun.js, this is done via gun.get('~@' + alias) which returns the user soul
-    // For now, we'll search the graph nodes for one with matching alias
-    // This is not efficient but works for the implementation
-    
-    let graph = &chain.core.graph;
-    let all_nodes = graph.all_nodes();
-    
-    // Search for user node with matching alias
-    let mut user_soul: Option<String> = None;
-    for (soul, node) in all_nodes.iter() {
-        if let Some(alias_value) = node.data.get("alias") {
-            if let Some(alias_str) = alias_value.as_str() {
-                if alias_str == alias {
-                    user_soul = Some(soul.clone());
-                    break;
-                }
-            }
-        }
-    }
-    
-    // If not found, try alias lookup path ~@alias
-    if user_soul.is_none() {
-        let alias_path = format!("~{}@{}", "", alias);
-        let alias_chain = chain.get(&alias_path);
-        let mut alias_data: Option<serde_json::Value> = None;
-        alias_chain.once(|data, _key| {
-            alias_data = Some(data);
-        }).await.map_err(|e| SeaError::Crypto(format!("Failed to look up alias: {}", e)))?;
-        
-        if let Some(data) = alias_data {
-            // Check if it's a soul reference
-            if let Some(soul_ref) = data.get("#").and_then(|v| v.as_str()) {
-                user_soul = Some(soul_ref.to_string());
-            } else if data.is_string() {
-                // Might be the soul directly
-                user_soul = data.as_str().map(|s| s.to_string());
-            }
+    // Accounts the alias links to, as create_user writes them
+    let mut links = serde_json::Value::Null;
+    chain.get(&alias_soul(alias)?).once(|data, _key| {
+        links = data;
+    }).await.map_err(|e| SeaError::Crypto(format!("Failed to look up alias: {}", e)))?;
+    let souls: Vec<String> = links
+        .as_object()
+        .map(|links| {
+            links
+                .iter()
+                .filter(|(soul, link)| link.get("#").and_then(|v| v.as_str()) == Some(soul.as_str()))
+                .map(|(soul, _)| soul.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // Anyone can link an account to an alias, so try each with the password;
+    // if one of them is the alias's, a wrong password says more than the others
+    let mut error = SeaError::UserNotFound;
+    for soul in souls {
+        match open_account(&chain, &soul, alias, password).await {
+            Ok(auth) => return Ok(auth),
+            Err(e) if !matches!(error, SeaError::WrongPassword) => error = e,
+            Err(_) => {}
         }
     }
-    
-    if user_soul.is_none() {
-        return Err(SeaError::Crypto(format!("User with alias '{}' not found", alias)));
-    }
-    
-    let user_soul = user_soul.unwrap();
-    
-    // Get user data from graph
-    let user_chain = chain.get(&user_soul);
+    Err(error)
+}
+
+/// Sign in to the account at `soul` with `password`
+///
+/// The account must hold its own pub, `~pub`, so a node copied from another
+/// account or made up under some other soul is refused.
+async fn open_account(chain: &Chain, soul: &str, alias: &str, password: &str) -> Result<UserAuth, SeaError> {
+    let user_chain = chain.get(soul);
     let mut user_data: Option<serde_json::Value> = None;
     user_chain.once(|data, _key| {
         user_data = Some(data);
     }).await.map_err(|e| SeaError::Crypto(format!("Failed to get user data: {}", e)))?;
     
-    let user_data = user_data.ok_or_else(|| SeaError::Crypto("User data not found".to_string()))?;
+    let user_data = user_data
+        .filter(|data| data.is_object())
+        .ok_or(SeaError::UserNotFound)?;
+
+    // Get public key, which must be the one the soul is named after
+    let pub_key = user_data.get("pub")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| SeaError::Crypto("Missing public key in user data".to_string()))?
+        .to_string();
+    if user_soul(&pub_key).ok().as_deref() != Some(soul) {
+        return Err(SeaError::UserNotFound); // Not an account, whatever links to it
+    }
+
+    // Deleted by its owner, see delete_user
+    if user_data.get("hash").is_some_and(|hash| hash.is_null()) {
+        return Err(SeaError::UserNotFound);
+    }
+
+    // Retired by its owner for new keys, see rotate_keys
+    let retired = match chain.core.graph.get(soul) {
+        Some(node) => match node.data.get("rotated") {
+            Some(to) => super::open_user_value(soul, "rotated", to, node.meta.get(">").and_then(|s| s.get("rotated"))).await.1,
+            None => false,
+        },
+        None => false,
+    };
+    if retired {
+        return Err(SeaError::Crypto(format!("Account {} was rotated to new keys", soul)));
+    }
     
     // Extract stored password hash and salt
     let stored_hash = user_data.get("hash")
@@ -273,15 +294,9 @@ pub async fn authenticate(
     
     // Verify password
     if !verify_password(password, &salt, stored_hash) {
-        return Err(SeaError::Crypto("Invalid password".to_string()));
+        return Err(SeaError::WrongPassword);
     }
     
-    // Get public key
-    let pub_key = user_data.get("pub")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| SeaError::Crypto("Missing public key in user data".to_string()))?
-        .to_string();
-    
     // Decrypt private keys using password-derived key
     let priv_encrypted = user_data.get("priv")
         .ok_or_else(|| SeaError::Crypto("Missing encrypted private key".to_string()))?;
@@ -308,18 +323,15 @@ pub async fn authenticate(
         .and_then(|v| v.as_str())
         .ok_or_else(|| SeaError::Decryption("Missing IV".to_string()))?;
     
+    let provider = crypto();
     let password_key_bytes = general_purpose::STANDARD_NO_PAD.decode(&password_key)
         .map_err(|_| SeaError::Crypto("Failed to decode password key".to_string()))?;
-    let cipher = Aes256Gcm::new_from_slice(&password_key_bytes)
-        .map_err(|e| SeaError::Crypto(format!("Failed to create cipher: {}", e)))?;
     let iv_bytes = general_purpose::STANDARD_NO_PAD.decode(iv_b64)
         .map_err(|_| SeaError::Decryption("Invalid IV encoding".to_string()))?;
     let ciphertext = general_purpose::STANDARD_NO_PAD.decode(ct_b64)
         .map_err(|_| SeaError::Decryption("Invalid ciphertext encoding".to_string()))?;
     
-    let nonce = aes_gcm::Nonce::from_slice(&iv_bytes);
-    let plaintext = cipher.decrypt(nonce, ciphertext.as_ref())
-        .map_err(|e| SeaError::Decryption(format!("Decryption failed: {}", e)))?;
+    let plaintext = provider.aes_gcm_decrypt(&password_key_bytes, &iv_bytes, &ciphertext)?;
     
     let priv_data_str = String::from_utf8(plaintext)
         .map_err(|e| SeaError::Decryption(format!("Invalid UTF-8: {}", e)))?;
@@ -350,9 +362,7 @@ pub async fn authenticate(
             let epriv_ciphertext = general_purpose::STANDARD_NO_PAD.decode(epriv_ct_b64)
                 .map_err(|_| SeaError::Decryption("Invalid ciphertext encoding".to_string()))?;
             
-            let epriv_nonce = aes_gcm::Nonce::from_slice(&epriv_iv_bytes);
-            let epriv_plaintext = cipher.decrypt(epriv_nonce, epriv_ciphertext.as_ref())
-                .map_err(|e| SeaError::Decryption(format!("Decryption failed: {}", e)))?;
+            let epriv_plaintext = provider.aes_gcm_decrypt(&password_key_bytes, &epriv_iv_bytes, &epriv_ciphertext)?;
             
             let epriv_data_str = String::from_utf8(epriv_plaintext)
                 .map_err(|e| SeaError::Decryption(format!("Invalid UTF-8: {}", e)))?;
@@ -383,73 +393,320 @@ pub async fn authenticate(
     })
 }
 
+/// Change the password of `alias`'s account
+///
+/// Signs in with `old_password`, then writes the account again with its
+/// private keys encrypted under `new_password` and a fresh salt. The key pair
+/// stays the same, so everything the user wrote or was sent still works. The
+/// new fields carry newer states than the old ones, so peers that merge both
+/// keep them.
+///
+/// # Returns
+/// `UserAuth` for the account, as [`authenticate`] with `new_password` would
+///
+/// # Errors
+/// See [`authenticate`]; `SeaError::Crypto` if the account can't be stored.
+pub async fn change_password(
+    chain: Arc<Chain>,
+    alias: &str,
+    old_password: &str,
+    new_password: &str,
+) -> Result<UserAuth, SeaError> {
+    let auth = authenticate(chain.clone(), alias, old_password).await?;
+    let user_data = account(&auth.pair, Some(alias), new_password).await?;
+    signed_by(&chain, user_soul(&auth.pair.pub_key)?, auth.clone()).put(user_data).await
+        .map_err(|e| SeaError::Crypto(format!("Failed to store user data: {}", e)))?;
+    Ok(auth)
+}
+
+/// Move `alias` to a fresh key pair
+///
+/// Signs in with `password` and creates an account for a new pair under the
+/// same alias and password. The old account gets a `rotated` field, signed
+/// by the old pair, holding the new `~pub`, and `~@alias` links to the new
+/// account instead of the old one. Signing in as `alias` from then on gives
+/// the new pair. Data under the old `~pub` stays there, and still verifies
+/// against the old pub.
+///
+/// # Returns
+/// `UserAuth` with the new key pair
+///
+/// # Errors
+/// See [`authenticate`]; `SeaError::Crypto` if key generation or storage
+/// fails.
+pub async fn rotate_keys(chain: Arc<Chain>, alias: &str, password: &str) -> Result<UserAuth, SeaError> {
+    let old = authenticate(chain.clone(), alias, password).await?;
+    let pair = pair::generate_pair().await?;
+    let old_soul = user_soul(&old.pair.pub_key)?;
+    let new_soul = user_soul(&pair.pub_key)?;
+    let stored = |e: crate::error::GunError| SeaError::Crypto(format!("Failed to store user data: {}", e));
+
+    let new = UserAuth {
+        pair,
+        alias: Some(alias.to_string()),
+    };
+    let new_account = account(&new.pair, Some(alias), password).await?;
+    signed_by(&chain, new_soul.clone(), new.clone()).put(new_account).await.map_err(stored)?;
+
+    // The old pair vouches for the new one, and unlinks itself from the alias
+    signed_by(&chain, old_soul.clone(), old.clone()).get("rotated").put(json!(new_soul)).await.map_err(stored)?;
+    signed_by(&chain, alias_soul(alias)?, old).put(json!({
+        old_soul: null,
+        new_soul.clone(): { "#": new_soul },
+    })).await.map_err(stored)?;
+
+    Ok(new)
+}
+
+/// Delete `alias`'s account
+///
+/// Signs in with `password`, then tombstones the account's encrypted keys,
+/// password hash and salt, and the `~@alias` link to it. The tombstones are
+/// signed by the account and written like any put, so peers delete them too. Afterwards
+/// [`authenticate`] can't find the user, [`recall`] refuses sessions of the
+/// account, and the alias is free for [`create_user`] again.
+///
+/// # Errors
+/// See [`authenticate`]; `SeaError::Crypto` if the tombstones can't be
+/// stored.
+pub async fn delete_user(chain: Arc<Chain>, alias: &str, password: &str) -> Result<(), SeaError> {
+    let auth = authenticate(chain.clone(), alias, password).await?;
+    let user_soul = user_soul(&auth.pair.pub_key)?;
+    let stored = |e: crate::error::GunError| SeaError::Crypto(format!("Failed to delete user: {}", e));
+
+    signed_by(&chain, user_soul.clone(), auth.clone()).put(json!({
+        "hash": null,
+        "salt": null,
+        "priv": null,
+        "epriv": null,
+    })).await.map_err(stored)?;
+    signed_by(&chain, alias_soul(alias)?, auth).put(json!({ user_soul: null })).await.map_err(stored)?;
+    Ok(())
+}
+
+/// Whether the account of `pub_key` was deleted, see [`delete_user`]
+async fn deleted(chain: &Chain, pub_key: &str) -> Result<bool, SeaError> {
+    // No account is kept under a malformed pub
+    let Ok(soul) = user_soul(pub_key) else {
+        return Ok(false);
+    };
+    let mut account = serde_json::Value::Null;
+    chain.get(&soul).once(|data, _key| {
+        account = data;
+    }).await.map_err(|e| SeaError::Crypto(format!("Failed to get user data: {}", e)))?;
+    Ok(account.get("hash").is_some_and(|hash| hash.is_null()))
+}
+
 /// Hash a password using PBKDF2
 /// Returns base64-encoded hash
 pub fn hash_password(password: &str, salt: &[u8]) -> String {
-    let mut hash = vec![0u8; 32];
-    pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, 100000, &mut hash);
-    general_purpose::STANDARD_NO_PAD.encode(&hash)
+    general_purpose::STANDARD_NO_PAD.encode(password_hash(password, salt))
 }
 
 /// Verify a password against a hash
+///
+/// The hashes are compared as bytes in constant time, so how long a wrong
+/// guess takes says nothing about how close it came. A `hash` that isn't
+/// base64 never matches.
 pub fn verify_password(password: &str, salt: &[u8], hash: &str) -> bool {
-    let computed_hash = hash_password(password, salt);
-    computed_hash == hash
+    let Ok(expected) = general_purpose::STANDARD_NO_PAD.decode(hash) else {
+        return false;
+    };
+    password_hash(password, salt).ct_eq(&expected).into()
+}
+
+/// PBKDF2-SHA256 of `password`, 100000 iterations
+fn password_hash(password: &str, salt: &[u8]) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    crypto().pbkdf2_sha256(password.as_bytes(), salt, 100000, &mut hash);
+    hash
 }
 
 /// Generate a random salt for password hashing
 pub fn generate_salt() -> Vec<u8> {
-    use rand::RngCore;
     let mut salt = vec![0u8; 16];
-    rand::thread_rng().fill_bytes(&mut salt);
+    crypto().fill_random(&mut salt).unwrap_or_else(|e| panic!("Failed to generate salt: {}", e));
     salt
 }
 
+/// How long a session written by [`remember`] stays valid, 12 hours as in
+/// Gun.js settings
+const SESSION_MS: f64 = 12.0 * 60.0 * 60.0 * 1000.0;
+
+/// Store `user`'s session at `path`, encrypted with `key`
+///
+/// `key` is a passphrase or a device key, such as the one [`device_key`]
+/// keeps in the OS keyring. The pair's private keys are encrypted with
+/// AES-GCM under a key derived from it with PBKDF2 and a fresh salt; the
+/// public keys, alias and expiry, 12 hours from now, are stored as they
+/// are. [`recall`] reads the session back with the same `key`. A plaintext
+/// session file written by earlier versions is replaced.
+///
+/// # Errors
+/// `SeaError::Crypto` if the file can't be written, `SeaError::Encryption`
+/// if encrypting fails.
+///
+/// # Example
+/// ```rust,no_run
+/// # async fn example(user: gun::UserAuth) -> Result<(), Box<dyn std::error::Error>> {
+/// gun::sea::remember(&user, "./session.json", "device passphrase").await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn remember(user: &UserAuth, path: &str, key: &str) -> Result<(), SeaError> {
+    let session = session(user, key).await?;
+    tokio::fs::write(path, session.to_string()).await
+        .map_err(|e| SeaError::Crypto(format!("Failed to write storage file: {}", e)))
+}
+
+/// Store `user`'s session in the storage of `chain`'s Gun instance
+///
+/// As [`remember`], without a separate file: the session is the node
+/// `~{pub}/session/{device_id}` of the configured backend (Sled,
+/// LocalStorage, …), so [`recall`] with a chain finds it after a restart.
+/// The node is written to storage only, like Gun.js sessionStorage: it
+/// isn't put on the graph or broadcast, and holds the private keys only
+/// encrypted, as the file does. `device_id` tells apart the
+/// sessions of devices sharing a storage; remembering again on the same
+/// device replaces its session.
+///
+/// # Errors
+/// `SeaError::Crypto` if storage can't be written, `SeaError::Encryption`
+/// if encrypting fails.
+///
+/// # Example
+/// ```rust,no_run
+/// # async fn example(gun: gun::Gun, user: gun::UserAuth) -> Result<(), Box<dyn std::error::Error>> {
+/// gun::sea::remember_on_device(gun.root(), &user, "laptop", "device passphrase").await?;
+/// let recalled = gun::sea::recall(Some(gun.root()), None, Some("device passphrase")).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn remember_on_device(chain: Arc<Chain>, user: &UserAuth, device_id: &str, key: &str) -> Result<(), SeaError> {
+    let soul = format!("{}/session/{}", user_soul(&user.pair.pub_key)?, device_id);
+    let state = chain.core.state.next();
+    let mut node = crate::state::Node::with_soul(soul.clone());
+    if let serde_json::Value::Object(fields) = session(user, key).await? {
+        for (field, value) in fields {
+            crate::state::State::ify(&mut node, Some(&field), Some(state), Some(value), Some(&soul));
+        }
+    }
+    let failed = |e: crate::error::GunError| SeaError::Crypto(format!("Failed to store session: {}", e));
+    chain.core.persist(crate::persist::StorageWrite::Put { soul, node }).await.map_err(failed)?;
+    chain.core.flush().await;
+    Ok(())
+}
+
+/// `user`'s session as [`remember`] stores it, private keys encrypted with `key`
+async fn session(user: &UserAuth, key: &str) -> Result<serde_json::Value, SeaError> {
+    let salt = generate_salt();
+    let keys = super::encrypt_with(
+        &json!({ "priv": user.pair.priv_key, "epriv": user.pair.epriv_key }),
+        &session_key(key, &salt).await?,
+    )
+    .await?;
+    Ok(json!({
+        "pub": user.pair.pub_key,
+        "epub": user.pair.epub_key,
+        "alias": user.alias,
+        "expires": chrono::Utc::now().timestamp_millis() as f64 + SESSION_MS,
+        "salt": general_purpose::STANDARD_NO_PAD.encode(&salt),
+        "keys": keys,
+    }))
+}
+
+/// The key [`remember`] and [`recall`] use for sessions, derived from a
+/// passphrase or device key
+async fn session_key(key: &str, salt: &[u8]) -> Result<String, SeaError> {
+    super::work::work(
+        key.as_bytes(),
+        Some(salt.to_vec()),
+        super::work::WorkOptions {
+            name: Some("PBKDF2".to_string()),
+            iterations: Some(100_000),
+            salt: Some(salt.to_vec()),
+            hash: Some("SHA-256".to_string()),
+            length: Some(256),
+            encode: Some("base64".to_string()),
+        },
+    ).await
+}
+
+/// This device's session key, kept in the OS keyring
+///
+/// Made up at random the first time, then read back, so sessions stored
+/// with [`remember`] can be recalled without asking for a passphrase.
+///
+/// # Errors
+/// `SeaError::Crypto` if the keyring can't be read or written.
+#[cfg(feature = "keyring")]
+pub fn device_key() -> Result<String, SeaError> {
+    let keyring = |e: keyring::Error| SeaError::Crypto(format!("Keyring error: {}", e));
+    let entry = keyring::Entry::new("gun-rs", "session").map_err(keyring)?;
+    match entry.get_password() {
+        Ok(key) => Ok(key),
+        Err(keyring::Error::NoEntry) => {
+            let key = general_purpose::STANDARD_NO_PAD.encode(random_bytes(32)?);
+            entry.set_password(&key).map_err(keyring)?;
+            Ok(key)
+        }
+        Err(e) => Err(keyring(e)),
+    }
+}
+
 /// Recall user session from storage
 /// 
 /// Recalls a previously authenticated user session from storage.
 /// Based on Gun.js sea/recall.js behavior.
 ///
 /// # Arguments
-/// * `chain` - Optional Chain instance to access the graph (for graph-based recall)
+/// * `chain` - Optional Chain instance, whose storage holds sessions from [`remember_on_device`]
 /// * `storage_path` - Optional path to storage file for file-based session recall
+/// * `key` - The passphrase or device key the session was stored with, see [`remember`]
 ///
 /// # Returns
-/// `Ok(Some(UserAuth))` if session is valid and not expired, `Ok(None)` if expired or not found
+/// `Ok(Some(UserAuth))` if session is valid and not expired, `Ok(None)` if not found
 ///
 /// # Session Expiry
 ///
 /// Session validity is 12 hours (matching Gun.js settings).
-/// Sessions older than 12 hours will return `None`.
+/// A session file older than that is `SeaError::Expired`; expired sessions in
+/// the instance's storage are passed over.
 /// 
 /// # Storage Priority
 /// 
 /// This function tries to recall from:
-/// 1. Graph storage (if `chain` is provided)
+/// 1. The instance's storage (if `chain` and `key` are provided): the newest unexpired
+///    session [`remember_on_device`] stored that `key` decrypts
 /// 2. File-based storage (if `storage_path` is provided)
 /// 
 /// Returns `None` if no valid session is found in either location.
+/// With a `chain`, a session of an account deleted with [`delete_user`] is
+/// an error.
 /// 
+/// # Errors
+/// - `SeaError::Expired`: If the session file is older than 12 hours
+/// - `SeaError::UserNotFound`: If the session's account was deleted
+/// - `SeaError::Crypto`: If the file can't be read, or an encrypted session is recalled without a `key`
+/// - `SeaError::Decryption`: If `key` isn't the one the session was stored with
+///
 /// # Security Note
 /// 
-/// File-based recall currently stores keys in plaintext (not recommended for production).
-/// In a secure implementation, keys should be encrypted with a master key or user password.
+/// Sessions written by [`remember`] hold the private keys encrypted. Plaintext session
+/// files written by earlier versions still load, with a warning; `remember` the session
+/// again to encrypt them.
 /// 
 /// # Example
 /// ```rust,no_run
-/// use gun::{Gun, Chain};
-/// use gun::sea::recall;
-/// use std::sync::Arc;
-/// 
-/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
-/// let gun = Gun::new();
-/// let chain = gun.root();
+/// use gun::sea::{recall, SeaError};
 /// 
+/// # async fn example(gun: gun::Gun) -> Result<(), Box<dyn std::error::Error>> {
 /// // Try to recall session
-/// if let Some(user) = recall(Some(Arc::new(chain)), Some("./session.json")).await? {
-///     println!("Session recalled for user: {:?}", user.alias);
-/// } else {
-///     println!("No valid session found");
+/// match recall(Some(gun.root()), Some("./session.json"), Some("device passphrase")).await {
+///     Ok(Some(user)) => println!("Session recalled for user: {:?}", user.alias),
+///     Ok(None) => println!("No valid session found"),
+///     Err(SeaError::Expired) => println!("Session expired, sign in again"),
+///     Err(e) => return Err(e.into()),
 /// }
 /// # Ok(())
 /// # }
@@ -457,20 +714,23 @@ pub fn generate_salt() -> Vec<u8> {
 pub async fn recall(
     chain: Option<Arc<Chain>>,
     storage_path: Option<&str>,
+    key: Option<&str>,
 ) -> Result<Option<UserAuth>, SeaError> {
-    // Try graph-based recall first (if chain provided)
-    if let Some(_chain_ref) = chain {
-        // In Gun.js, recall checks for stored session in graph
-        // Look for session data at a known location (e.g., ~session or similar)
-        // For now, we'll check if there's a current user in the graph
-        // This is simplified - in practice, you'd have a specific session storage mechanism
-        
-        // Try to get session from graph
-        // In Gun.js, this might be stored at a specific path
-        // For now, we'll return None and rely on file-based storage
-        // TODO: Implement full graph-based session recall
+    // Sessions kept in the instance's storage by remember_on_device(), most
+    // recent first; the first one `key` opens is ours
+    if let (Some(chain), Some(key)) = (&chain, key) {
+        for session in stored_sessions(chain).await? {
+            let user = match open_session(&session, Some(key), "graph").await {
+                Ok(Some(user)) => user,
+                Ok(None) | Err(_) => continue, // Expired, or another device's
+            };
+            if deleted(chain, &user.pair.pub_key).await? {
+                return Err(SeaError::UserNotFound); // Deleted since it was remembered
+            }
+            return Ok(Some(user));
+        }
     }
-    
+
     // Try file-based recall
     if let Some(path) = storage_path {
         // Read from storage file
@@ -479,52 +739,207 @@ pub async fn recall(
         
         let pair_data: serde_json::Value = serde_json::from_str(&contents)
             .map_err(|e| SeaError::Crypto(format!("Failed to parse storage data: {}", e)))?;
-        
-        // Check for expiry (12 hours default, matching Gun.js)
-                if let Some(exp) = pair_data.get("exp").and_then(|v| v.as_f64()) {
-                    let now = chrono::Utc::now().timestamp_millis() as f64;
-            let expiry_ms = 12.0 * 60.0 * 60.0 * 1000.0; // 12 hours in milliseconds
-            if now > (exp + expiry_ms) {
-                        return Ok(None); // Expired
-                    }
-                }
-
-        // Reconstruct UserAuth from stored data
-        let pub_key = pair_data.get("pub")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| SeaError::Crypto("Missing pub key in stored data".to_string()))?
-            .to_string();
-        
-        let priv_key = pair_data.get("priv")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| SeaError::Crypto("Missing priv key in stored data".to_string()))?
-            .to_string();
-        
-        let epub_key = pair_data.get("epub")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
-        
-        let epriv_key = pair_data.get("epriv")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
-        
-        let alias = pair_data.get("alias")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
-        
-        // Note: In production, you'd want to decrypt the private keys if they're encrypted
-        // For now, we assume they're stored in plaintext (not recommended for production)
-        // In a secure implementation, you'd decrypt using a master key or user password
-        
-        let pair = KeyPair {
-            pub_key,
-            priv_key,
-            epub_key,
-            epriv_key,
+        let Some(user) = open_session(&pair_data, key, path).await? else {
+            return Err(SeaError::Expired);
         };
-        
-        return Ok(Some(UserAuth { pair, alias }));
+
+        // The account may have been deleted since the session was stored
+        if let Some(chain) = &chain {
+            if deleted(chain, &user.pair.pub_key).await? {
+                return Err(SeaError::UserNotFound);
+            }
+        }
+        return Ok(Some(user));
     }
 
     Ok(None)
 }
+
+/// Sessions [`remember_on_device`] stored, newest first
+async fn stored_sessions(chain: &Chain) -> Result<Vec<serde_json::Value>, SeaError> {
+    let storage = chain.core.storage();
+    let failed = |e: crate::error::GunError| SeaError::Crypto(format!("Failed to read stored sessions: {}", e));
+    let mut sessions = Vec::new();
+    for soul in storage.list().await.map_err(failed)? {
+        let in_session = match parse_user_soul(&soul) {
+            Some(ParsedUserSoul::User { path: Some(path), .. }) => path.starts_with("session/"),
+            _ => false,
+        };
+        if !in_session {
+            continue;
+        }
+        if let Some(node) = storage.get(&soul).await.map_err(failed)? {
+            sessions.push(serde_json::Value::Object(node.data));
+        }
+    }
+    let expires = |session: &serde_json::Value| session.get("expires").and_then(|v| v.as_f64()).unwrap_or_default();
+    sessions.sort_by(|a, b| expires(b).total_cmp(&expires(a)));
+    Ok(sessions)
+}
+
+/// The user of a stored session, or `None` once it expired
+///
+/// `source` names where the session came from, for the plaintext warning.
+async fn open_session(pair_data: &serde_json::Value, key: Option<&str>, source: &str) -> Result<Option<UserAuth>, SeaError> {
+    let now = chrono::Utc::now().timestamp_millis() as f64;
+    let keys = match pair_data.get("keys") {
+        // Written by remember(), with the private keys encrypted
+        Some(keys) => {
+            let expires = pair_data.get("expires").and_then(|v| v.as_f64()).unwrap_or_default();
+            if now > expires {
+                return Ok(None); // Expired
+            }
+            let key = key.ok_or_else(|| SeaError::Crypto("Session is encrypted: a key is needed to recall it".to_string()))?;
+            let salt = pair_data.get("salt")
+                .and_then(|v| v.as_str())
+                .and_then(|salt| general_purpose::STANDARD_NO_PAD.decode(salt).ok())
+                .ok_or_else(|| SeaError::Decryption("Missing salt in stored data".to_string()))?;
+            super::decrypt_with(keys, &session_key(key, &salt).await?).await
+                .map_err(|_| SeaError::Decryption("Wrong key for stored session".to_string()))?
+        }
+        // Plaintext, as earlier versions stored it, with when it was stored as `exp`
+        None => {
+            tracing::warn!(source, "Session file holds private keys in plaintext; remember() it again to encrypt it");
+            if let Some(exp) = pair_data.get("exp").and_then(|v| v.as_f64()) {
+                if now > exp + SESSION_MS {
+                    return Ok(None); // Expired
+                }
+            }
+            pair_data.clone()
+        }
+    };
+
+    // Reconstruct UserAuth from stored data
+    let pub_key = pair_data.get("pub")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| SeaError::Crypto("Missing pub key in stored data".to_string()))?
+        .to_string();
+    
+    let priv_key = keys.get("priv")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| SeaError::Crypto("Missing priv key in stored data".to_string()))?
+        .to_string();
+    
+    let epub_key = pair_data.get("epub")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    
+    let epriv_key = keys.get("epriv")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    
+    let alias = pair_data.get("alias")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    let pair = KeyPair {
+        pub_key,
+        priv_key,
+        epub_key,
+        epriv_key,
+    };
+    Ok(Some(UserAuth { pair, alias }))
+}
+
+/// The signed-in user of a Gun instance
+///
+/// Shared by every [`UserChain`] the instance hands out, so signing in
+/// through one is seen by all of them, as with Gun.js `gun.user()`.
+#[derive(Default)]
+pub struct User {
+    auth: RwLock<Option<UserAuth>>, // Key pair and alias, while signed in
+}
+
+impl User {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The signed-in user's key pair and alias
+    pub fn auth(&self) -> Option<UserAuth> {
+        self.auth.read().clone()
+    }
+}
+
+/// User chain, as returned by `gun.user()`
+/// Based on Gun.js sea/user.js
+///
+/// Creates accounts, signs in and out, and reads and writes the signed-in
+/// user's data under their `~pub` soul.
+///
+/// # Example
+/// ```rust,no_run
+/// # async fn example(gun: gun::Gun) -> Result<(), Box<dyn std::error::Error>> {
+/// let user = gun.user();
+/// user.create("alice", "secure_password").await?;
+/// user.auth("alice", "secure_password").await?;
+/// user.get("name")?.put(serde_json::json!("Alice")).await?;
+/// println!("Signed in as {:?}", user.is());
+/// user.leave();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct UserChain {
+    root: Arc<Chain>,
+    user: Arc<User>,
+}
+
+impl UserChain {
+    pub fn new(root: Arc<Chain>, user: Arc<User>) -> Self {
+        Self { root, user }
+    }
+
+    /// Create an account for `alias`, without signing in
+    /// Based on Gun.js user.create()
+    ///
+    /// # Returns
+    /// The new account's public key
+    ///
+    /// # Errors
+    /// See [`create_user`]
+    pub async fn create(&self, alias: &str, password: &str) -> Result<String, SeaError> {
+        let created = create_user(self.root.clone(), Some(alias.to_string()), password).await?;
+        Ok(created.pair.pub_key)
+    }
+
+    /// Sign in as `alias`
+    /// Based on Gun.js user.auth()
+    ///
+    /// # Errors
+    /// See [`authenticate`]; a failed attempt leaves the current user signed in.
+    pub async fn auth(&self, alias: &str, password: &str) -> Result<UserAuth, SeaError> {
+        let auth = authenticate(self.root.clone(), alias, password).await?;
+        *self.user.auth.write() = Some(auth.clone());
+        Ok(auth)
+    }
+
+    /// Sign out
+    /// Based on Gun.js user.leave()
+    pub fn leave(&self) {
+        self.user.auth.write().take();
+    }
+
+    /// The signed-in user's public key, if anyone is signed in
+    /// Based on Gun.js user.is
+    pub fn is(&self) -> Option<String> {
+        self.user.auth.read().as_ref().map(|auth| auth.pair.pub_key.clone())
+    }
+
+    /// Chain to `key` of the signed-in user's `~pub` node
+    /// Based on Gun.js user.get()
+    ///
+    /// Values written through it into the user's space are SEA-signed with
+    /// their key pair, as Gun.js writes them (see
+    /// [`sign_put`](super::sign_put)), and reads unwrap them again; use
+    /// `once_with_meta` or `on_with_meta` to see whether they verified.
+    ///
+    /// # Errors
+    /// `SeaError::Crypto` if nobody is signed in.
+    pub fn get(&self, key: &str) -> Result<Arc<Chain>, SeaError> {
+        let pub_key = self.is().ok_or_else(|| SeaError::Crypto("User is not authenticated".to_string()))?;
+        let soul = user_soul(&pub_key)?;
+        let space = Chain::with_soul(self.root.core.clone(), soul, None).with_user(self.user.clone());
+        Ok(Arc::new(space).get(key))
+    }
+}
@@ -2,26 +2,89 @@
 //! Based on Gun.js sea/verify.js
 //! ECDSA P-256 verification
 
+use super::provider::crypto;
+use super::settings::{decode_base64, parse, SEA_PREFIX};
+use super::sign::hashed_text;
 use super::SeaError;
-use base64::{engine::general_purpose, Engine as _};
-use p256::ecdsa::Signature;
-use p256::ecdsa::{signature::Verifier, VerifyingKey};
+use crate::verify_cache::VerifyCache;
+use serde::Deserialize;
+use serde_json::value::RawValue;
 use serde_json::Value;
+use std::sync::OnceLock;
+
+/// Results of the ECDSA checks [`verify`] did
+static VERIFIED: OnceLock<VerifyCache> = OnceLock::new();
+
+/// The cache of [`verify`] results, shared by the whole process
+///
+/// The same signed user-space values are verified on every read and write
+/// path; each signature, text and public key is only checked once. Results
+/// are kept for [`DEFAULT_VERIFY_CACHE_AGE`](crate::verify_cache::DEFAULT_VERIFY_CACHE_AGE)
+/// at most, in a table of [`DEFAULT_VERIFY_CACHE_ENTRIES`](crate::verify_cache::DEFAULT_VERIFY_CACHE_ENTRIES).
+pub fn verify_cache() -> &'static VerifyCache {
+    VERIFIED.get_or_init(VerifyCache::default)
+}
+
+/// The cached result for `key`
+///
+/// Not on wasm32, whose `std::time::Instant` the cache's ages can't be
+/// read from; every signature is checked there.
+#[cfg(not(target_arch = "wasm32"))]
+fn cached(key: &[u8; 32]) -> Option<bool> {
+    verify_cache().get(key)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn cached(_key: &[u8; 32]) -> Option<bool> {
+    None
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn remember(key: [u8; 32], verified: bool) {
+    verify_cache().insert(key, verified);
+}
+
+#[cfg(target_arch = "wasm32")]
+fn remember(_key: [u8; 32], _verified: bool) {}
+
+/// Signed data as written, so `m` is hashed in the bytes it was signed in
+#[derive(Deserialize)]
+struct Signed<'a> {
+    #[serde(borrow)]
+    m: &'a RawValue,
+    s: String,
+}
 
 /// Verify a signature
 /// Returns the verified message data if valid
 ///
-/// Takes signed data in format {m: message, s: signature} and a public key
+/// Takes what [`sign`](super::sign) or Gun.js `SEA.sign` returns: the
+/// `SEA{"m":…,"s":…}` string, or its `{m, s}` object. The signature may be
+/// base64 or base64url. Data signed by earlier versions of this crate, whose
+/// `m` is JSON text signed without hashing it first, is still accepted.
 pub async fn verify(signed_data: &Value, pub_key: &str) -> Result<Value, SeaError> {
-    let message = signed_data
-        .get("m")
-        .and_then(|v| v.as_str())
-        .ok_or(SeaError::VerificationFailed)?;
+    verify_sync(signed_data, pub_key)
+}
 
-    let signature = signed_data
-        .get("s")
-        .and_then(|v| v.as_str())
-        .ok_or(SeaError::VerificationFailed)?;
+/// [`verify`], for callers that can't await; checking a signature never
+/// waits on I/O
+pub(crate) fn verify_sync(signed_data: &Value, pub_key: &str) -> Result<Value, SeaError> {
+    let (m, raw_m, signature) = match signed_data {
+        Value::String(signed) => {
+            let text = signed.strip_prefix(SEA_PREFIX).filter(|t| t.starts_with('{')).unwrap_or(signed);
+            let signed: Signed = serde_json::from_str(text).map_err(|_| SeaError::VerificationFailed)?;
+            let m: Value = serde_json::from_str(signed.m.get()).map_err(|_| SeaError::VerificationFailed)?;
+            (m, Some(signed.m.get()), signed.s)
+        }
+        signed_data => {
+            let m = signed_data.get("m").cloned().ok_or(SeaError::VerificationFailed)?;
+            let signature = signed_data
+                .get("s")
+                .and_then(|v| v.as_str())
+                .ok_or(SeaError::VerificationFailed)?;
+            (m, None, signature.to_string())
+        }
+    };
 
     // Parse public key (format: x.y)
     let parts: Vec<&str> = pub_key.split('.').collect();
@@ -29,38 +92,51 @@ pub async fn verify(signed_data: &Value, pub_key: &str) -> Result<Value, SeaErro
         return Err(SeaError::InvalidKey);
     }
 
-    let x = general_purpose::STANDARD_NO_PAD
-        .decode(parts[0])
-        .map_err(|_| SeaError::InvalidKey)?;
-    let y = general_purpose::STANDARD_NO_PAD
-        .decode(parts[1])
-        .map_err(|_| SeaError::InvalidKey)?;
+    let x = decode_base64(parts[0]).ok_or(SeaError::InvalidKey)?;
+    let y = decode_base64(parts[1]).ok_or(SeaError::InvalidKey)?;
 
     // Reconstruct public key bytes (uncompressed format: 0x04 || x || y)
     let mut pub_bytes = vec![0x04u8];
     pub_bytes.extend_from_slice(&x);
     pub_bytes.extend_from_slice(&y);
 
-    let verifying_key = VerifyingKey::from_sec1_bytes(&pub_bytes)
-        .map_err(|e| SeaError::Crypto(format!("Invalid public key: {}", e)))?;
-
     // Decode signature (ECDSA signatures are 64 bytes: r || s)
-    let sig_bytes = general_purpose::STANDARD_NO_PAD
-        .decode(signature)
-        .map_err(|_| SeaError::VerificationFailed)?;
+    let sig_bytes = decode_base64(&signature).ok_or(SeaError::VerificationFailed)?;
     if sig_bytes.len() != 64 {
         return Err(SeaError::VerificationFailed);
     }
-    let mut sig_array = [0u8; 64];
-    sig_array.copy_from_slice(&sig_bytes);
-    let signature =
-        Signature::from_bytes(&sig_array.into()).map_err(|_| SeaError::VerificationFailed)?;
-
-    // Verify signature
-    verifying_key
-        .verify(message.as_bytes(), &signature)
-        .map_err(|_| SeaError::VerificationFailed)?;
-
-    // Parse and return the message
-    serde_json::from_str(message).map_err(|e| SeaError::Crypto(format!("Invalid JSON: {}", e)))
+
+    // `m` as it was written, when we have it, then as we write it
+    let mut texts = vec![hashed_text(&m)];
+    if let (Some(raw), false) = (raw_m, m.is_string()) {
+        texts.insert(0, raw.to_string());
+    }
+    let legacy = [m.is_string() as u8];
+    let mut parts: Vec<&[u8]> = vec![pub_key.as_bytes(), &sig_bytes, &legacy];
+    parts.extend(texts.iter().map(|text| text.as_bytes()));
+    let key = VerifyCache::key(&parts);
+    let verified = match cached(&key) {
+        Some(verified) => verified,
+        None => {
+            let provider = crypto();
+            let mut verified = false;
+            for text in &texts {
+                if provider.p256_verify(&pub_bytes, &provider.sha256(text.as_bytes()), &sig_bytes)? {
+                    verified = true;
+                    break;
+                }
+            }
+            if let (false, Value::String(legacy)) = (verified, &m) {
+                verified = provider.p256_verify(&pub_bytes, legacy.as_bytes(), &sig_bytes)?;
+            }
+            remember(key, verified);
+            verified
+        }
+    };
+    if !verified {
+        return Err(SeaError::VerificationFailed);
+    }
+
+    // Return the message, parsed as Gun.js does
+    Ok(parse(&m))
 }
@@ -2,13 +2,10 @@
 //! Based on Gun.js sea/work.js
 //! Provides PBKDF2 key derivation and SHA-256 hashing for proof-of-work and content addressing
 
+use super::provider::{crypto, random_bytes};
 use super::SeaError;
 use base64::{engine::general_purpose, Engine as _};
-use pbkdf2::pbkdf2_hmac;
-use rand::RngCore;
-use sha2::{Digest, Sha256};
 use std::sync::Arc;
-use tokio::task;
 
 /// Options for SEA.work()
 #[derive(Clone, Debug)]
@@ -81,10 +78,8 @@ pub async fn work(
 
     if name_lower.starts_with("sha") {
         // SHA-256 hashing mode
-        return task::spawn_blocking(move || {
-            let mut hasher = Sha256::new();
-            hasher.update(&data);
-            let hash = hasher.finalize();
+        return blocking(move || {
+            let hash = crypto().sha256(&data);
 
             let encoded = match opt.encode.as_deref().unwrap_or("base64") {
                 "base64" => general_purpose::STANDARD_NO_PAD.encode(hash),
@@ -94,9 +89,7 @@ pub async fn work(
 
             Ok(encoded)
         })
-        .await
-        .map_err(|e| SeaError::Crypto(format!("Task join error: {}", e)))?
-        .map_err(|e: SeaError| e);
+        .await;
     }
 
     // PBKDF2 key derivation mode (default)
@@ -106,9 +99,7 @@ pub async fn work(
         opt_salt.clone()
     } else {
         // Generate random 9-byte salt (matching Gun.js)
-        let mut salt_bytes = vec![0u8; 9];
-        rand::thread_rng().fill_bytes(&mut salt_bytes);
-        salt_bytes
+        random_bytes(9)?
     };
 
     let iterations = opt.iterations.unwrap_or(100_000);
@@ -116,13 +107,12 @@ pub async fn work(
     let length_bytes = length_bits / 8;
 
     // Perform PBKDF2 in blocking task (CPU-intensive)
-    let result = task::spawn_blocking(move || {
+    let result = blocking(move || {
         let mut output = vec![0u8; length_bytes];
-        pbkdf2_hmac::<Sha256>(&data, &salt, iterations, &mut output);
-        output
+        crypto().pbkdf2_sha256(&data, &salt, iterations, &mut output);
+        Ok(output)
     })
-    .await
-    .map_err(|e| SeaError::Crypto(format!("Task join error: {}", e)))?;
+    .await?;
 
     // Encode result
     let encoded = match opt.encode.as_deref().unwrap_or("base64") {
@@ -134,6 +124,24 @@ pub async fn work(
     Ok(encoded)
 }
 
+/// Run `f` off the async runtime's threads
+///
+/// Only native builds have a blocking pool; on wasm32 there is one thread,
+/// so `f` runs in place.
+#[cfg(feature = "native")]
+async fn blocking<T: Send + 'static>(
+    f: impl FnOnce() -> Result<T, SeaError> + Send + 'static,
+) -> Result<T, SeaError> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| SeaError::Crypto(format!("Task join error: {}", e)))?
+}
+
+#[cfg(not(feature = "native"))]
+async fn blocking<T>(f: impl FnOnce() -> Result<T, SeaError>) -> Result<T, SeaError> {
+    f()
+}
+
 /// Convenience function: work with string data
 pub async fn work_string(
     data: &str,
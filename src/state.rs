@@ -29,6 +29,7 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 const DRIFT: f64 = 0.0; // Time drift compensation (currently unused)
 const D: f64 = 999.0;   // Divisor for sub-millisecond precision
+const EXPIRY: &str = "<?"; // Node meta key mapping data keys to expiry times (ms)
 
 /// State timestamp generator for conflict resolution
 ///
@@ -129,6 +130,39 @@ impl State {
         None
     }
 
+    /// Whether an incoming value for a key replaces the current one (HAM)
+    ///
+    /// The higher state wins. On equal states the value whose JSON text sorts
+    /// higher wins, as in Gun.js, so every peer settles on the same value; an
+    /// equal value changes nothing.
+    ///
+    /// # Arguments
+    /// * `incoming_state` - State the incoming value was written at
+    /// * `incoming` - The incoming value
+    /// * `current_state` - State of the value already stored
+    /// * `current` - The value already stored
+    ///
+    /// # Returns
+    /// `true` if the incoming value should be stored.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use gun::state::State;
+    /// use serde_json::json;
+    ///
+    /// assert!(State::ham(2.0, &json!("old"), 1.0, &json!("new")));
+    /// assert!(!State::ham(1.0, &json!("new"), 2.0, &json!("old")));
+    /// assert!(State::ham(1.0, &json!("b"), 1.0, &json!("a")));
+    /// ```
+    pub fn ham(incoming_state: f64, incoming: &Value, current_state: f64, current: &Value) -> bool {
+        if incoming_state != current_state {
+            return incoming_state > current_state;
+        }
+        let lexical = |value: &Value| serde_json::to_string(value).unwrap_or_default();
+        lexical(incoming) > lexical(current)
+    }
+
     /// Update a node with state information for a key
     ///
     /// This method sets the state timestamp and optionally the soul and data for a node.
@@ -214,6 +248,8 @@ impl State {
 /// identifier generated by [`GunCore::uuid`](crate::core::GunCore::uuid).
 ///
 /// State information is stored in `meta[">"]` as a map from key to timestamp.
+/// Keys written through a `soul<?seconds` chain also get an expiry time in
+/// `meta["<?"]` (milliseconds since the Unix epoch).
 ///
 /// # Example
 ///
@@ -284,6 +320,111 @@ impl Node {
             .and_then(|v| v.as_str())
             .map(|s| s.to_string())
     }
+
+    /// Get the expiry time (ms since the Unix epoch) of a key, if it has one
+    pub fn expires_at(&self, key: &str) -> Option<f64> {
+        self.meta
+            .get(EXPIRY)
+            .and_then(|v| v.get(key))
+            .and_then(|v| v.as_f64())
+    }
+
+    /// Set the expiry time (ms since the Unix epoch) of a key
+    pub fn set_expiry(&mut self, key: &str, at: f64) {
+        let expiry = self
+            .meta
+            .entry(EXPIRY.to_string())
+            .or_insert_with(|| Value::Object(serde_json::Map::new()));
+        if let (Value::Object(map), Some(number)) = (expiry, serde_json::Number::from_f64(at)) {
+            map.insert(key.to_string(), Value::Number(number));
+        }
+    }
+
+    /// Remove the expiry time of a key
+    pub fn clear_expiry(&mut self, key: &str) {
+        let now_empty = match self.meta.get_mut(EXPIRY) {
+            Some(Value::Object(map)) => {
+                map.remove(key);
+                map.is_empty()
+            }
+            _ => false,
+        };
+        if now_empty {
+            self.meta.remove(EXPIRY);
+        }
+    }
+
+    /// Whether any key of this node has an expiry time
+    pub fn has_expiry(&self) -> bool {
+        self.meta.contains_key(EXPIRY)
+    }
+
+    /// Keys whose expiry time is at or before `now` (ms since the Unix epoch)
+    pub fn expired_keys(&self, now: f64) -> Vec<String> {
+        match self.meta.get(EXPIRY) {
+            Some(Value::Object(map)) => map
+                .iter()
+                .filter(|(_, at)| matches!(at.as_f64(), Some(at) if at <= now))
+                .map(|(key, _)| key.clone())
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// This node in Gun.js wire format: `{"_": {"#": soul, ">": states}, ...data}`
+    ///
+    /// This is how nodes travel in `put` messages (keyed by soul) and how Gun.js
+    /// stores them. Local-only metadata such as expiry times is left out.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use gun::state::Node;
+    /// use serde_json::json;
+    ///
+    /// let mut node = Node::with_soul("user_123".to_string());
+    /// node.data.insert("name".to_string(), json!("Alice"));
+    /// let wire = node.to_wire();
+    /// assert_eq!(wire["_"]["#"], json!("user_123"));
+    /// ```
+    pub fn to_wire(&self) -> Value {
+        let mut meta = serde_json::Map::new();
+        if let Some(soul) = self.meta.get("#") {
+            meta.insert("#".to_string(), soul.clone());
+        }
+        // Whole-millisecond states are written as integers, like JavaScript does
+        let states = match self.meta.get(">") {
+            Some(Value::Object(states)) => states
+                .iter()
+                .map(|(key, state)| {
+                    let state = match state.as_f64() {
+                        Some(ms) if ms.fract() == 0.0 && ms.abs() < 9_007_199_254_740_992.0 => {
+                            Value::from(ms as i64)
+                        }
+                        _ => state.clone(),
+                    };
+                    (key.clone(), state)
+                })
+                .collect(),
+            _ => serde_json::Map::new(),
+        };
+        meta.insert(">".to_string(), Value::Object(states));
+
+        let mut obj = serde_json::Map::new();
+        obj.insert("_".to_string(), Value::Object(meta));
+        for (key, value) in &self.data {
+            obj.insert(key.clone(), value.clone());
+        }
+        Value::Object(obj)
+    }
+
+    /// This node as readers should see it at `now`, with expired keys removed
+    pub fn without_expired(mut self, now: f64) -> Node {
+        for key in self.expired_keys(now) {
+            self.data.remove(&key);
+        }
+        self
+    }
 }
 
 impl Default for Node {
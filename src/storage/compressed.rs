@@ -0,0 +1,228 @@
+//! Per-node compression
+//!
+//! [`CompressedStorage`] wraps any [`Storage`] backend and compresses nodes whose
+//! JSON is larger than a threshold before they reach the backend. Nodes holding
+//! long text (documents, base64 images) typically shrink 5-10x on disk or in Redis.
+//!
+//! A compressed node is stored as an envelope node whose only content is the
+//! [`COMPRESSED_META_KEY`] meta entry: base64 of one header byte naming the codec,
+//! followed by the compressed JSON of the original node. Anything without that
+//! entry is returned as-is, so a store written without compression keeps loading
+//! after the wrapper is added, and small nodes are never touched.
+//!
+//! Codecs are behind features: `compression-deflate` (on by default) and
+//! `compression-zstd`.
+
+use super::Storage;
+use crate::error::{GunError, GunResult};
+use crate::state::Node;
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde_json::Value;
+use std::io::{Read, Write};
+
+/// Nodes whose JSON is smaller than this many bytes are stored uncompressed
+pub const DEFAULT_COMPRESSION_THRESHOLD: usize = 512;
+
+/// Meta key holding the compressed payload of an envelope node
+pub const COMPRESSED_META_KEY: &str = "~z";
+
+/// Header byte of a deflate payload
+const HEADER_DEFLATE: u8 = 1;
+
+/// Header byte of a zstd payload
+const HEADER_ZSTD: u8 = 2;
+
+/// Compression codec used for new writes
+///
+/// Reads always accept every codec compiled in, whatever is configured here.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    /// DEFLATE via `flate2` (feature `compression-deflate`)
+    #[cfg(feature = "compression-deflate")]
+    Deflate,
+    /// Zstandard (feature `compression-zstd`); better ratio, needs a C toolchain
+    #[cfg(feature = "compression-zstd")]
+    Zstd,
+}
+
+impl Compression {
+    fn header(self) -> u8 {
+        match self {
+            #[cfg(feature = "compression-deflate")]
+            Compression::Deflate => HEADER_DEFLATE,
+            #[cfg(feature = "compression-zstd")]
+            Compression::Zstd => HEADER_ZSTD,
+        }
+    }
+
+    fn compress(self, bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            #[cfg(feature = "compression-deflate")]
+            Compression::Deflate => {
+                let mut encoder = flate2::write::DeflateEncoder::new(
+                    Vec::with_capacity(bytes.len() / 2),
+                    flate2::Compression::default(),
+                );
+                encoder.write_all(bytes)?;
+                encoder.finish()
+            }
+            #[cfg(feature = "compression-zstd")]
+            Compression::Zstd => zstd::stream::encode_all(bytes, 0),
+        }
+    }
+}
+
+/// Decompress a payload according to its header byte
+fn decompress(header: u8, payload: &[u8]) -> GunResult<Vec<u8>> {
+    match header {
+        #[cfg(feature = "compression-deflate")]
+        HEADER_DEFLATE => {
+            let mut bytes = Vec::new();
+            flate2::read::DeflateDecoder::new(payload).read_to_end(&mut bytes)?;
+            Ok(bytes)
+        }
+        #[cfg(feature = "compression-zstd")]
+        HEADER_ZSTD => Ok(zstd::stream::decode_all(payload)?),
+        #[cfg(not(feature = "compression-deflate"))]
+        HEADER_DEFLATE => Err(GunError::InvalidData(
+            "Node is deflate-compressed; enable the compression-deflate feature".to_string(),
+        )),
+        #[cfg(not(feature = "compression-zstd"))]
+        HEADER_ZSTD => Err(GunError::InvalidData(
+            "Node is zstd-compressed; enable the compression-zstd feature".to_string(),
+        )),
+        other => Err(GunError::InvalidData(format!(
+            "Unknown compression header byte {}",
+            other
+        ))),
+    }
+}
+
+/// Storage wrapper that compresses large nodes
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use gun::storage::{CompressedStorage, Compression, SledStorage};
+/// use gun::{Gun, GunOptions};
+/// use std::sync::Arc;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let storage = CompressedStorage::new(SledStorage::new("./gun_data")?, Compression::Deflate)
+///     .with_threshold(1024);
+///
+/// let options = GunOptions {
+///     storage: Some(Arc::new(storage)),
+///     ..Default::default()
+/// };
+/// # Ok(())
+/// # }
+/// ```
+pub struct CompressedStorage<S> {
+    inner: S,
+    compression: Compression,
+    threshold: usize,
+}
+
+impl<S: Storage> CompressedStorage<S> {
+    /// Wrap a backend, compressing new writes with `compression`
+    pub fn new(inner: S, compression: Compression) -> Self {
+        Self {
+            inner,
+            compression,
+            threshold: DEFAULT_COMPRESSION_THRESHOLD,
+        }
+    }
+
+    /// Only compress nodes whose JSON is at least `bytes` long (default: 512)
+    pub fn with_threshold(mut self, bytes: usize) -> Self {
+        self.threshold = bytes;
+        self
+    }
+
+    /// The wrapped backend
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Codec used for new writes
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    /// Build the node actually written to the backend
+    ///
+    /// Returns `None` when the node should be stored as-is: it is below the
+    /// threshold, or compressing it doesn't make it smaller.
+    fn encode(&self, soul: &str, node: &Node) -> GunResult<Option<Node>> {
+        let json = serde_json::to_vec(node)?;
+        if json.len() < self.threshold {
+            return Ok(None);
+        }
+
+        let mut payload = vec![self.compression.header()];
+        payload.extend(self.compression.compress(&json)?);
+        let encoded = STANDARD.encode(&payload);
+        if encoded.len() >= json.len() {
+            return Ok(None);
+        }
+
+        let mut envelope = Node::with_soul(soul.to_string());
+        envelope
+            .meta
+            .insert(COMPRESSED_META_KEY.to_string(), Value::String(encoded));
+        Ok(Some(envelope))
+    }
+
+    /// Turn a stored node back into the original, passing uncompressed nodes through
+    fn decode(node: Node) -> GunResult<Node> {
+        let encoded = match node.meta.get(COMPRESSED_META_KEY) {
+            Some(Value::String(encoded)) => encoded,
+            _ => return Ok(node),
+        };
+
+        let payload = STANDARD.decode(encoded).map_err(|e| {
+            GunError::InvalidData(format!("Invalid compressed node payload: {}", e))
+        })?;
+        let (header, compressed) = payload
+            .split_first()
+            .ok_or_else(|| GunError::InvalidData("Empty compressed node payload".to_string()))?;
+        let json = decompress(*header, compressed)?;
+        Ok(serde_json::from_slice(&json)?)
+    }
+}
+
+#[async_trait]
+impl<S: Storage> Storage for CompressedStorage<S> {
+    async fn get(&self, soul: &str) -> GunResult<Option<Node>> {
+        self.inner.get(soul).await?.map(Self::decode).transpose()
+    }
+
+    async fn put(&self, soul: &str, node: &Node) -> GunResult<()> {
+        match self.encode(soul, node)? {
+            Some(envelope) => self.inner.put(soul, &envelope).await,
+            None => self.inner.put(soul, node).await,
+        }
+    }
+
+    async fn has(&self, soul: &str) -> GunResult<bool> {
+        self.inner.has(soul).await
+    }
+
+    async fn list(&self) -> GunResult<Vec<String>> {
+        self.inner.list().await
+    }
+
+    async fn delete(&self, soul: &str) -> GunResult<()> {
+        self.inner.delete(soul).await
+    }
+
+    // put_delta keeps the trait's read-modify-write default: a delta can't be
+    // merged into a compressed envelope by the backend
+
+    async fn flush(&self) -> GunResult<()> {
+        self.inner.flush().await
+    }
+}
@@ -0,0 +1,210 @@
+//! Import and export of Gun.js localStorage dumps
+//!
+//! Browser Gun.js apps persist their graph as a single JSON blob under the `gun/`
+//! localStorage key, in the wire graph format:
+//!
+//! ```json
+//! {
+//!   "todo": { "_": { "#": "todo", ">": { "kq1": 1700000000000 } }, "kq1": { "#": "kq1" } },
+//!   "kq1": { "_": { "#": "kq1", ">": { "title": 1700000000000 } }, "title": "Buy milk" }
+//! }
+//! ```
+//!
+//! Each node's `_` object is its metadata (`#` soul and `>` per-key states) and
+//! maps directly onto [`Node::meta`]; soul references (`{"#": soul}`) are kept
+//! as-is. Imports merge by state, so importing an older dump never overwrites
+//! newer local data.
+
+use super::Storage;
+use crate::error::{GunError, GunResult};
+use crate::state::Node;
+use serde_json::{Map, Value};
+use std::path::Path;
+
+/// localStorage key Gun.js keeps its graph under
+pub const GUNJS_LOCALSTORAGE_KEY: &str = "gun/";
+
+/// Parse a Gun.js localStorage dump into nodes
+///
+/// Accepts either the graph blob itself (the value stored under `gun/`) or a
+/// dump of the whole localStorage object, where `gun/` maps to that blob as a
+/// JSON string.
+///
+/// # Errors
+/// Returns `GunError::InvalidData` if the dump isn't a graph of node objects.
+pub fn parse_gunjs_graph(json: &str) -> GunResult<Vec<Node>> {
+    let mut graph: Value = serde_json::from_str(json)?;
+
+    // Whole localStorage dump: { "gun/": "<graph json>" }
+    if let Some(blob) = graph.get(GUNJS_LOCALSTORAGE_KEY) {
+        graph = match blob {
+            Value::String(inner) => serde_json::from_str(inner)?,
+            other => other.clone(),
+        };
+    }
+
+    let graph = graph.as_object().ok_or_else(|| {
+        GunError::InvalidData("Gun.js graph must be a JSON object".to_string())
+    })?;
+
+    let mut nodes = Vec::with_capacity(graph.len());
+    for (soul, value) in graph {
+        // Gun.js may leave `null` behind for removed nodes
+        if value.is_null() {
+            continue;
+        }
+        nodes.push(node_from_gunjs(soul, value)?);
+    }
+    Ok(nodes)
+}
+
+/// Build the Gun.js graph blob (`{soul: node}`) for a set of nodes
+///
+/// Only the `#` and `>` metadata Gun.js understands is written.
+pub fn gunjs_graph<'a>(nodes: impl IntoIterator<Item = &'a Node>) -> Value {
+    let mut graph = Map::new();
+    for node in nodes {
+        if let Some(soul) = node.get_soul() {
+            graph.insert(soul, node_to_gunjs(node));
+        }
+    }
+    Value::Object(graph)
+}
+
+/// Import a Gun.js localStorage dump into a storage backend
+///
+/// Every key is merged with its Gun.js state through
+/// [`Storage::put_delta`], so keys that are newer in `storage` are kept.
+///
+/// # Arguments
+/// * `path` - File holding the dump (see [`parse_gunjs_graph`] for accepted formats)
+/// * `storage` - Backend to import into
+///
+/// # Returns
+/// The number of nodes imported, or `GunError` if the file can't be read or parsed.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use gun::storage::{import_gunjs_localstorage, SledStorage};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let storage = SledStorage::new("./gun_data")?;
+/// let imported = import_gunjs_localstorage("gun-localstorage.json", &storage).await?;
+/// println!("Imported {} nodes", imported);
+/// # Ok(())
+/// # }
+/// ```
+pub async fn import_gunjs_localstorage(
+    path: impl AsRef<Path>,
+    storage: &dyn Storage,
+) -> GunResult<usize> {
+    let nodes = parse_gunjs_graph(&std::fs::read_to_string(path)?)?;
+    for node in &nodes {
+        if let Some(soul) = node.get_soul() {
+            storage.put_delta(&soul, &node_changes(node)).await?;
+        }
+    }
+    Ok(nodes.len())
+}
+
+/// Export every node in a storage backend as a Gun.js localStorage graph blob
+///
+/// The file holds the value Gun.js keeps under the `gun/` localStorage key, so
+/// a browser app can load it with `localStorage.setItem('gun/', contents)`.
+///
+/// # Returns
+/// The number of nodes exported, or `GunError` if storage or the write fails.
+pub async fn export_gunjs_localstorage(
+    path: impl AsRef<Path>,
+    storage: &dyn Storage,
+) -> GunResult<usize> {
+    let mut nodes = Vec::new();
+    for soul in storage.list().await? {
+        if let Some(node) = storage.get(&soul).await? {
+            nodes.push(node);
+        }
+    }
+    write_gunjs_graph(path, &nodes)?;
+    Ok(nodes.len())
+}
+
+/// Write nodes to `path` in the Gun.js graph blob format
+pub(crate) fn write_gunjs_graph(path: impl AsRef<Path>, nodes: &[Node]) -> GunResult<()> {
+    let json = serde_json::to_string(&gunjs_graph(nodes))?;
+    std::fs::write(path, json)?;
+    Ok(())
+}
+
+/// Every data key of a node with its state (0 if the node has no state for it)
+pub(crate) fn node_changes(node: &Node) -> Vec<(String, Value, f64)> {
+    let states = node.meta.get(">");
+    node.data
+        .iter()
+        .map(|(key, value)| {
+            let state = states
+                .and_then(|states| states.get(key))
+                .and_then(Value::as_f64)
+                .unwrap_or(0.0);
+            (key.clone(), value.clone(), state)
+        })
+        .collect()
+}
+
+fn node_from_gunjs(soul: &str, value: &Value) -> GunResult<Node> {
+    let obj = value.as_object().ok_or_else(|| {
+        GunError::InvalidData(format!("Gun.js node {} is not an object", soul))
+    })?;
+
+    let mut node = Node::with_soul(soul.to_string());
+    if let Some(meta) = obj.get("_") {
+        let meta = meta.as_object().ok_or_else(|| {
+            GunError::InvalidData(format!("Gun.js node {} has invalid `_` metadata", soul))
+        })?;
+        if let Some(states) = meta.get(">") {
+            if !states.is_object() {
+                return Err(GunError::InvalidData(format!(
+                    "Gun.js node {} has invalid `>` states",
+                    soul
+                )));
+            }
+            node.meta.insert(">".to_string(), states.clone());
+        }
+        if let Some(meta_soul) = meta.get("#").and_then(Value::as_str) {
+            if meta_soul != soul {
+                return Err(GunError::InvalidData(format!(
+                    "Gun.js node stored under {} claims soul {}",
+                    soul, meta_soul
+                )));
+            }
+        }
+    }
+
+    for (key, value) in obj {
+        if key != "_" {
+            node.data.insert(key.clone(), value.clone());
+        }
+    }
+    Ok(node)
+}
+
+fn node_to_gunjs(node: &Node) -> Value {
+    let mut meta = Map::new();
+    if let Some(soul) = node.meta.get("#") {
+        meta.insert("#".to_string(), soul.clone());
+    }
+    meta.insert(
+        ">".to_string(),
+        node.meta
+            .get(">")
+            .cloned()
+            .unwrap_or_else(|| Value::Object(Map::new())),
+    );
+
+    let mut obj = Map::new();
+    obj.insert("_".to_string(), Value::Object(meta));
+    for (key, value) in &node.data {
+        obj.insert(key.clone(), value.clone());
+    }
+    Value::Object(obj)
+}
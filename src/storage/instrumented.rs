@@ -0,0 +1,313 @@
+//! Storage metrics and slow-operation logging
+//!
+//! [`InstrumentedStorage`] wraps any [`Storage`] backend and counts operations,
+//! errors and bytes, records per-operation latency histograms, and logs a
+//! `tracing` warning whenever a single operation takes longer than a threshold.
+//! [`Gun`](crate::Gun) wraps its backend automatically; see
+//! [`Gun::stats`](crate::Gun::stats).
+
+use super::Storage;
+use crate::error::GunResult;
+use crate::state::Node;
+use async_trait::async_trait;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Default threshold above which a storage operation is logged as slow
+pub const DEFAULT_SLOW_STORAGE_THRESHOLD: Duration = Duration::from_millis(500);
+
+/// Upper bounds (inclusive, in milliseconds) of the latency histogram buckets.
+/// A final bucket counts everything slower than the last bound.
+pub const LATENCY_BUCKETS_MS: [u64; 8] = [1, 5, 10, 50, 100, 500, 1000, 5000];
+
+/// Lock-free latency histogram
+#[derive(Default)]
+struct Histogram {
+    buckets: [AtomicU64; LATENCY_BUCKETS_MS.len() + 1],
+    count: AtomicU64,
+    total_micros: AtomicU64,
+    max_micros: AtomicU64,
+}
+
+impl Histogram {
+    fn record(&self, elapsed: Duration) {
+        let millis = elapsed.as_millis() as u64;
+        let bucket = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|bound| millis <= *bound)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+        let micros = elapsed.as_micros() as u64;
+        self.total_micros.fetch_add(micros, Ordering::Relaxed);
+        self.max_micros.fetch_max(micros, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> LatencyStats {
+        LatencyStats {
+            buckets: self
+                .buckets
+                .iter()
+                .map(|bucket| bucket.load(Ordering::Relaxed))
+                .collect(),
+            count: self.count.load(Ordering::Relaxed),
+            total: Duration::from_micros(self.total_micros.load(Ordering::Relaxed)),
+            max: Duration::from_micros(self.max_micros.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Snapshot of a latency histogram
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct LatencyStats {
+    /// Operation counts per bucket of [`LATENCY_BUCKETS_MS`], plus one final
+    /// bucket for slower operations
+    pub buckets: Vec<u64>,
+    /// Number of operations recorded
+    pub count: u64,
+    /// Sum of all latencies
+    pub total: Duration,
+    /// Slowest operation seen
+    pub max: Duration,
+}
+
+impl LatencyStats {
+    /// Average latency, or zero if nothing was recorded
+    pub fn mean(&self) -> Duration {
+        if self.count == 0 {
+            return Duration::ZERO;
+        }
+        self.total / self.count as u32
+    }
+}
+
+/// Live counters shared by an [`InstrumentedStorage`] and whoever reports on it
+///
+/// Cloning the `Arc` returned by [`InstrumentedStorage::metrics`] keeps the
+/// counters readable after the storage is swapped or dropped.
+#[derive(Default)]
+pub struct StorageMetrics {
+    gets: AtomicU64,
+    puts: AtomicU64,
+    deletes: AtomicU64,
+    has: AtomicU64,
+    lists: AtomicU64,
+    errors: AtomicU64,
+    slow_ops: AtomicU64,
+    bytes_read: AtomicU64,
+    bytes_written: AtomicU64,
+    get_latency: Histogram,
+    put_latency: Histogram,
+    delete_latency: Histogram,
+}
+
+impl StorageMetrics {
+    /// Take a consistent-enough snapshot of every counter
+    pub fn snapshot(&self) -> StorageStats {
+        StorageStats {
+            gets: self.gets.load(Ordering::Relaxed),
+            puts: self.puts.load(Ordering::Relaxed),
+            deletes: self.deletes.load(Ordering::Relaxed),
+            has: self.has.load(Ordering::Relaxed),
+            lists: self.lists.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            slow_ops: self.slow_ops.load(Ordering::Relaxed),
+            bytes_read: self.bytes_read.load(Ordering::Relaxed),
+            bytes_written: self.bytes_written.load(Ordering::Relaxed),
+            get_latency: self.get_latency.snapshot(),
+            put_latency: self.put_latency.snapshot(),
+            delete_latency: self.delete_latency.snapshot(),
+        }
+    }
+}
+
+/// Storage counters at a point in time
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct StorageStats {
+    /// `get` calls
+    pub gets: u64,
+    /// `put` and `put_delta` calls
+    pub puts: u64,
+    /// `delete` calls
+    pub deletes: u64,
+    /// `has` calls
+    pub has: u64,
+    /// `list` calls
+    pub lists: u64,
+    /// Calls of any kind that returned an error
+    pub errors: u64,
+    /// Calls that took longer than the slow-operation threshold
+    pub slow_ops: u64,
+    /// JSON bytes of the nodes returned by `get`
+    pub bytes_read: u64,
+    /// JSON bytes of the nodes and deltas written
+    pub bytes_written: u64,
+    /// Latency of `get` calls
+    pub get_latency: LatencyStats,
+    /// Latency of `put` and `put_delta` calls
+    pub put_latency: LatencyStats,
+    /// Latency of `delete` calls
+    pub delete_latency: LatencyStats,
+}
+
+/// Storage wrapper that records metrics for any backend
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use gun::storage::{InstrumentedStorage, SledStorage, Storage};
+/// use gun::state::Node;
+/// use std::time::Duration;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let storage = InstrumentedStorage::new(SledStorage::new("./gun_data")?)
+///     .with_slow_threshold(Duration::from_millis(100));
+///
+/// storage.put("user_123", &Node::with_soul("user_123".to_string())).await?;
+/// let stats = storage.stats();
+/// println!("{} puts, mean {:?}", stats.puts, stats.put_latency.mean());
+/// # Ok(())
+/// # }
+/// ```
+pub struct InstrumentedStorage<S> {
+    inner: S,
+    metrics: Arc<StorageMetrics>,
+    slow_threshold: Duration,
+}
+
+impl<S: Storage> InstrumentedStorage<S> {
+    /// Wrap a backend with fresh counters
+    pub fn new(inner: S) -> Self {
+        Self::with_metrics(inner, Arc::new(StorageMetrics::default()))
+    }
+
+    /// Wrap a backend, adding to existing counters
+    ///
+    /// Used to keep one set of numbers across a backend swap.
+    pub fn with_metrics(inner: S, metrics: Arc<StorageMetrics>) -> Self {
+        Self {
+            inner,
+            metrics,
+            slow_threshold: DEFAULT_SLOW_STORAGE_THRESHOLD,
+        }
+    }
+
+    /// Log a warning for operations slower than `threshold` (default: 500ms)
+    pub fn with_slow_threshold(mut self, threshold: Duration) -> Self {
+        self.slow_threshold = threshold;
+        self
+    }
+
+    /// The wrapped backend
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Shared handle to the live counters
+    pub fn metrics(&self) -> Arc<StorageMetrics> {
+        self.metrics.clone()
+    }
+
+    /// Snapshot of the counters
+    pub fn stats(&self) -> StorageStats {
+        self.metrics.snapshot()
+    }
+
+    /// Count errors and slow calls, and record latency
+    fn finish<T>(
+        &self,
+        op: &str,
+        soul: Option<&str>,
+        started: Instant,
+        histogram: Option<&Histogram>,
+        result: &GunResult<T>,
+    ) {
+        let elapsed = started.elapsed();
+        if let Some(histogram) = histogram {
+            histogram.record(elapsed);
+        }
+        if result.is_err() {
+            self.metrics.errors.fetch_add(1, Ordering::Relaxed);
+        }
+        if elapsed > self.slow_threshold {
+            self.metrics.slow_ops.fetch_add(1, Ordering::Relaxed);
+            tracing::warn!(
+                op,
+                soul = soul.unwrap_or(""),
+                elapsed_ms = elapsed.as_millis() as u64,
+                threshold_ms = self.slow_threshold.as_millis() as u64,
+                "Slow storage operation"
+            );
+        }
+    }
+}
+
+#[async_trait]
+impl<S: Storage> Storage for InstrumentedStorage<S> {
+    async fn get(&self, soul: &str) -> GunResult<Option<Node>> {
+        self.metrics.gets.fetch_add(1, Ordering::Relaxed);
+        let started = Instant::now();
+        let result = self.inner.get(soul).await;
+        self.finish("get", Some(soul), started, Some(&self.metrics.get_latency), &result);
+        if let Ok(Some(node)) = &result {
+            let bytes = serde_json::to_vec(node).map(|b| b.len()).unwrap_or(0);
+            self.metrics.bytes_read.fetch_add(bytes as u64, Ordering::Relaxed);
+        }
+        result
+    }
+
+    async fn put(&self, soul: &str, node: &Node) -> GunResult<()> {
+        self.metrics.puts.fetch_add(1, Ordering::Relaxed);
+        let started = Instant::now();
+        let result = self.inner.put(soul, node).await;
+        self.finish("put", Some(soul), started, Some(&self.metrics.put_latency), &result);
+        if result.is_ok() {
+            let bytes = serde_json::to_vec(node).map(|b| b.len()).unwrap_or(0);
+            self.metrics.bytes_written.fetch_add(bytes as u64, Ordering::Relaxed);
+        }
+        result
+    }
+
+    async fn has(&self, soul: &str) -> GunResult<bool> {
+        self.metrics.has.fetch_add(1, Ordering::Relaxed);
+        let started = Instant::now();
+        let result = self.inner.has(soul).await;
+        self.finish("has", Some(soul), started, None, &result);
+        result
+    }
+
+    async fn list(&self) -> GunResult<Vec<String>> {
+        self.metrics.lists.fetch_add(1, Ordering::Relaxed);
+        let started = Instant::now();
+        let result = self.inner.list().await;
+        self.finish("list", None, started, None, &result);
+        result
+    }
+
+    async fn delete(&self, soul: &str) -> GunResult<()> {
+        self.metrics.deletes.fetch_add(1, Ordering::Relaxed);
+        let started = Instant::now();
+        let result = self.inner.delete(soul).await;
+        self.finish("delete", Some(soul), started, Some(&self.metrics.delete_latency), &result);
+        result
+    }
+
+    async fn put_delta(&self, soul: &str, changed: &[(String, Value, f64)]) -> GunResult<()> {
+        self.metrics.puts.fetch_add(1, Ordering::Relaxed);
+        let started = Instant::now();
+        let result = self.inner.put_delta(soul, changed).await;
+        self.finish("put_delta", Some(soul), started, Some(&self.metrics.put_latency), &result);
+        if result.is_ok() {
+            let bytes = serde_json::to_vec(changed).map(|b| b.len()).unwrap_or(0);
+            self.metrics.bytes_written.fetch_add(bytes as u64, Ordering::Relaxed);
+        }
+        result
+    }
+
+    async fn flush(&self) -> GunResult<()> {
+        self.inner.flush().await
+    }
+}
@@ -0,0 +1,188 @@
+//! Copy data between storage backends
+//!
+//! Used to move an existing dataset to a different backend (for example from
+//! [`LocalStorage`](super::LocalStorage) to [`SledStorage`](super::SledStorage))
+//! without losing data. Souls are enumerated with [`Storage::list`], copied in
+//! batches, and then read back from the target to verify the copy.
+
+use super::Storage;
+use crate::error::GunResult;
+use crate::state::Node;
+use futures::future::join_all;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+/// Progress callback, called after each batch with `(processed, total)` souls
+pub type MigrationProgress = Arc<dyn Fn(usize, usize) + Send + Sync>;
+
+/// Options for [`migrate`]
+#[derive(Clone)]
+pub struct MigrationOptions {
+    /// Number of nodes read concurrently per batch (default: 100)
+    pub batch_size: usize,
+    /// Read and checksum the source without writing anything to the target
+    pub dry_run: bool,
+    /// Read every node back from the target and compare it to the source (default: true)
+    pub verify: bool,
+    /// Called after each batch with `(processed, total)`
+    pub progress: Option<MigrationProgress>,
+}
+
+impl Default for MigrationOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: 100,
+            dry_run: false,
+            verify: true,
+            progress: None,
+        }
+    }
+}
+
+/// Outcome of a [`migrate`] run
+#[derive(Clone, Debug, Default)]
+pub struct MigrationReport {
+    /// Souls listed by the source backend
+    pub source_count: usize,
+    /// Nodes written to the target (0 for a dry run)
+    pub copied: usize,
+    /// Souls that were listed but had disappeared from the source before being read
+    pub missing: Vec<String>,
+    /// Nodes read back from the target that matched the source
+    pub verified: usize,
+    /// Souls whose node in the target differs from the source (or is absent)
+    pub mismatched: Vec<String>,
+    /// SHA-256 over every `(soul, node)` read from the source, in soul order
+    pub source_checksum: String,
+    /// SHA-256 over the same souls read back from the target (only when verifying)
+    pub target_checksum: Option<String>,
+    /// Whether this was a dry run
+    pub dry_run: bool,
+}
+
+impl MigrationReport {
+    /// `true` if every node was copied and (when verified) matched the source
+    pub fn is_complete(&self) -> bool {
+        let checksums_match = match &self.target_checksum {
+            Some(target) => *target == self.source_checksum,
+            None => true,
+        };
+        self.mismatched.is_empty() && checksums_match
+    }
+}
+
+/// Copy every node from one storage backend to another
+///
+/// Souls are listed from `from`, read in batches of `opts.batch_size` and
+/// written to `to`, and (unless `opts.verify` is false) read back from `to`
+/// and compared node by node. Nodes already present in `to` are overwritten.
+///
+/// # Arguments
+/// * `from` - Backend to copy from (only read)
+/// * `to` - Backend to copy into
+/// * `opts` - Batch size, dry run, verification and progress settings
+///
+/// # Returns
+/// A [`MigrationReport`] with counts and checksums, or `GunError` if either
+/// backend fails.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use gun::storage::{migrate, LocalStorage, MigrationOptions, SledStorage};
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let from = LocalStorage::new("./gun_data")?;
+/// let to = SledStorage::new("./gun_sled")?;
+///
+/// let report = migrate(&from, &to, MigrationOptions::default()).await?;
+/// assert!(report.is_complete());
+/// println!("Copied {} nodes", report.copied);
+/// # Ok(())
+/// # }
+/// ```
+pub async fn migrate(
+    from: &dyn Storage,
+    to: &dyn Storage,
+    opts: MigrationOptions,
+) -> GunResult<MigrationReport> {
+    let mut souls = from.list().await?;
+    souls.sort();
+
+    let total = souls.len();
+    let batch_size = opts.batch_size.max(1);
+    let mut report = MigrationReport {
+        source_count: total,
+        dry_run: opts.dry_run,
+        ..Default::default()
+    };
+    let mut source_hasher = Sha256::new();
+    let mut target_hasher = Sha256::new();
+
+    for (index, batch) in souls.chunks(batch_size).enumerate() {
+        let nodes = join_all(batch.iter().map(|soul| from.get(soul)))
+            .await
+            .into_iter()
+            .collect::<GunResult<Vec<Option<Node>>>>()?;
+
+        let mut present = Vec::with_capacity(batch.len());
+        for (soul, node) in batch.iter().zip(nodes) {
+            match node {
+                Some(node) => {
+                    hash_node(&mut source_hasher, soul, &node)?;
+                    present.push((soul, node));
+                }
+                None => report.missing.push(soul.clone()),
+            }
+        }
+
+        if !opts.dry_run {
+            // Writes are sequential: some backends (sled's flush_async) don't
+            // tolerate many concurrent flushes from one task
+            for (soul, node) in &present {
+                to.put(soul, node).await?;
+                report.copied += 1;
+            }
+
+            if opts.verify {
+                let copies = join_all(present.iter().map(|(soul, _)| to.get(soul)))
+                    .await
+                    .into_iter()
+                    .collect::<GunResult<Vec<Option<Node>>>>()?;
+
+                for ((soul, node), copy) in present.iter().zip(copies) {
+                    match copy {
+                        Some(copy) => {
+                            hash_node(&mut target_hasher, soul, &copy)?;
+                            if serde_json::to_value(&copy)? == serde_json::to_value(node)? {
+                                report.verified += 1;
+                            } else {
+                                report.mismatched.push((*soul).clone());
+                            }
+                        }
+                        None => report.mismatched.push((*soul).clone()),
+                    }
+                }
+            }
+        }
+
+        if let Some(progress) = &opts.progress {
+            progress((index * batch_size + batch.len()).min(total), total);
+        }
+    }
+
+    report.source_checksum = hex::encode(source_hasher.finalize());
+    if !opts.dry_run && opts.verify {
+        report.target_checksum = Some(hex::encode(target_hasher.finalize()));
+    }
+
+    Ok(report)
+}
+
+fn hash_node(hasher: &mut Sha256, soul: &str, node: &Node) -> GunResult<()> {
+    hasher.update(soul.as_bytes());
+    hasher.update([0u8]);
+    hasher.update(serde_json::to_vec(node)?);
+    hasher.update([0u8]);
+    Ok(())
+}
@@ -0,0 +1,213 @@
+//! Fault-injecting storage backend for tests
+//!
+//! [`MockStorage`] keeps nodes in memory like [`MemoryStorage`](super::MemoryStorage)
+//! but can be told to fail operations, slow them down, return unparseable data or
+//! silently drop writes, and it records every call so tests can assert on the
+//! exact sequence of storage operations.
+
+use super::{MemoryStorage, Storage};
+use crate::error::{GunError, GunResult};
+use crate::state::Node;
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// A [`Storage`] operation, used to target injected failures and in call records
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StorageOp {
+    Get,
+    Put,
+    PutDelta,
+    Has,
+    List,
+    Delete,
+}
+
+/// One call made to a [`MockStorage`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct StorageCall {
+    pub op: StorageOp,
+    /// Soul the call was for (`None` for `list`)
+    pub soul: Option<String>,
+    /// Whether the call returned `Ok`
+    pub ok: bool,
+}
+
+#[derive(Default)]
+struct MockState {
+    fail_next: HashMap<StorageOp, usize>,
+    latency: Option<Duration>,
+    corrupt_reads: bool,
+    drop_writes: bool,
+    calls: Vec<StorageCall>,
+}
+
+/// In-memory storage with programmable faults
+///
+/// Injected failures are returned as `GunError::Storage` wrapping an I/O error,
+/// the same shape as a real disk failure.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use gun::storage::mock::{MockStorage, StorageOp};
+/// use gun::storage::Storage;
+/// use gun::state::Node;
+///
+/// # async fn example() {
+/// let storage = MockStorage::new();
+/// storage.fail_next(StorageOp::Put, 2);
+///
+/// let node = Node::with_soul("a".to_string());
+/// assert!(storage.put("a", &node).await.is_err());
+/// assert!(storage.put("a", &node).await.is_err());
+/// assert!(storage.put("a", &node).await.is_ok());
+/// assert_eq!(storage.calls().len(), 3);
+/// # }
+/// ```
+pub struct MockStorage {
+    inner: MemoryStorage,
+    state: Mutex<MockState>,
+}
+
+impl MockStorage {
+    /// Create an empty mock that behaves like `MemoryStorage` until told otherwise
+    pub fn new() -> Self {
+        Self {
+            inner: MemoryStorage::new(),
+            state: Mutex::new(MockState::default()),
+        }
+    }
+
+    /// Make the next `count` calls of `op` fail (adds to any pending failures)
+    pub fn fail_next(&self, op: StorageOp, count: usize) {
+        *self.state.lock().fail_next.entry(op).or_insert(0) += count;
+    }
+
+    /// Delay every operation by `latency` (`None` removes the delay)
+    pub fn set_latency(&self, latency: Option<Duration>) {
+        self.state.lock().latency = latency;
+    }
+
+    /// Make `get` fail as if the stored JSON were corrupted
+    pub fn set_corrupt_reads(&self, corrupt: bool) {
+        self.state.lock().corrupt_reads = corrupt;
+    }
+
+    /// Make writes (`put`, `put_delta`, `delete`) report success without storing anything
+    pub fn set_drop_writes(&self, drop: bool) {
+        self.state.lock().drop_writes = drop;
+    }
+
+    /// Every call made so far, in order
+    pub fn calls(&self) -> Vec<StorageCall> {
+        self.state.lock().calls.clone()
+    }
+
+    /// The operations called so far, in order
+    pub fn ops(&self) -> Vec<StorageOp> {
+        self.state.lock().calls.iter().map(|call| call.op).collect()
+    }
+
+    /// Forget recorded calls
+    pub fn clear_calls(&self) {
+        self.state.lock().calls.clear();
+    }
+
+    /// Apply latency and decide whether this call fails
+    async fn begin(&self, op: StorageOp) -> GunResult<()> {
+        let latency = self.state.lock().latency;
+        if let Some(latency) = latency {
+            tokio::time::sleep(latency).await;
+        }
+
+        let mut state = self.state.lock();
+        match state.fail_next.get_mut(&op) {
+            Some(remaining) if *remaining > 0 => {
+                *remaining -= 1;
+                Err(GunError::Storage(sled::Error::Io(std::io::Error::other(
+                    format!("Injected {:?} failure", op),
+                ))))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn record<T>(&self, op: StorageOp, soul: Option<&str>, result: GunResult<T>) -> GunResult<T> {
+        self.state.lock().calls.push(StorageCall {
+            op,
+            soul: soul.map(str::to_string),
+            ok: result.is_ok(),
+        });
+        result
+    }
+
+    fn drops_writes(&self) -> bool {
+        self.state.lock().drop_writes
+    }
+}
+
+impl Default for MockStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Storage for MockStorage {
+    async fn get(&self, soul: &str) -> GunResult<Option<Node>> {
+        let result = match self.begin(StorageOp::Get).await {
+            Ok(()) if self.state.lock().corrupt_reads => {
+                serde_json::from_str::<Node>("{\"data\":{\"trunc").map(Some).map_err(GunError::from)
+            }
+            Ok(()) => self.inner.get(soul).await,
+            Err(e) => Err(e),
+        };
+        self.record(StorageOp::Get, Some(soul), result)
+    }
+
+    async fn put(&self, soul: &str, node: &Node) -> GunResult<()> {
+        let result = match self.begin(StorageOp::Put).await {
+            Ok(()) if self.drops_writes() => Ok(()),
+            Ok(()) => self.inner.put(soul, node).await,
+            Err(e) => Err(e),
+        };
+        self.record(StorageOp::Put, Some(soul), result)
+    }
+
+    async fn has(&self, soul: &str) -> GunResult<bool> {
+        let result = match self.begin(StorageOp::Has).await {
+            Ok(()) => self.inner.has(soul).await,
+            Err(e) => Err(e),
+        };
+        self.record(StorageOp::Has, Some(soul), result)
+    }
+
+    async fn list(&self) -> GunResult<Vec<String>> {
+        let result = match self.begin(StorageOp::List).await {
+            Ok(()) => self.inner.list().await,
+            Err(e) => Err(e),
+        };
+        self.record(StorageOp::List, None, result)
+    }
+
+    async fn delete(&self, soul: &str) -> GunResult<()> {
+        let result = match self.begin(StorageOp::Delete).await {
+            Ok(()) if self.drops_writes() => Ok(()),
+            Ok(()) => self.inner.delete(soul).await,
+            Err(e) => Err(e),
+        };
+        self.record(StorageOp::Delete, Some(soul), result)
+    }
+
+    async fn put_delta(&self, soul: &str, changed: &[(String, Value, f64)]) -> GunResult<()> {
+        let result = match self.begin(StorageOp::PutDelta).await {
+            Ok(()) if self.drops_writes() => Ok(()),
+            Ok(()) => self.inner.put_delta(soul, changed).await,
+            Err(e) => Err(e),
+        };
+        self.record(StorageOp::PutDelta, Some(soul), result)
+    }
+}
@@ -0,0 +1,1087 @@
+//! Pluggable storage backends for persistent data
+//!
+//! This module provides storage abstractions for Gun, allowing data to be persisted
+//! to disk or other backends. Multiple storage implementations are provided:
+//!
+//! - **MemoryStorage**: In-memory only (no persistence)
+//! - **LocalStorage**: File-based storage (similar to browser localStorage)
+//! - **SledStorage**: High-performance embedded database
+//! - **RedisStorage**: Shared Redis backend for relay clusters (requires the
+//!   `redis-storage` feature)
+//! - **MockStorage**: In-memory backend with injectable faults, for tests
+//!
+//! [`CompressedStorage`] compresses large nodes before they reach a backend
+//! (requires the `compression-deflate` or `compression-zstd` feature).
+//!
+//! [`NamespacedStorage`] keeps the keys of several Gun instances apart in one
+//! shared backend.
+//!
+//! Data can be moved between backends with [`migrate`], and imported from or
+//! exported to Gun.js localStorage dumps with [`import_gunjs_localstorage`] and
+//! [`export_gunjs_localstorage`].
+//!
+//! Based on Gun.js storage adapters (localStorage, RAD, S3, etc.). All storage
+//! backends implement the [`Storage`](Storage) trait for a uniform interface.
+
+use crate::error::{GunError, GunResult};
+use crate::state::{Node, State};
+use async_trait::async_trait;
+use parking_lot::{Mutex, RwLock};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+
+#[cfg(any(feature = "compression-deflate", feature = "compression-zstd"))]
+mod compressed;
+mod gunjs;
+mod instrumented;
+mod migrate;
+pub mod mock;
+mod namespace;
+#[cfg(any(feature = "compression-deflate", feature = "compression-zstd"))]
+pub use self::compressed::*;
+pub use self::gunjs::*;
+pub use self::instrumented::*;
+pub use self::migrate::*;
+pub use self::mock::MockStorage;
+pub use self::namespace::*;
+
+#[cfg(feature = "redis-storage")]
+mod redis;
+#[cfg(feature = "redis-storage")]
+pub use self::redis::RedisStorage;
+
+/// Storage backend trait for persistent data storage
+///
+/// All storage backends in Gun implement this trait. It provides a simple interface
+/// for storing and retrieving nodes by their soul (unique identifier).
+///
+/// Based on Gun.js storage adapters. The trait is async to support I/O operations
+/// and is `Send + Sync` to work across threads.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use gun::storage::{Storage, LocalStorage};
+/// use gun::state::Node;
+/// use std::sync::Arc;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let storage: Arc<dyn Storage> = Arc::new(LocalStorage::new("./gun_data")?);
+///
+/// let node = Node::with_soul("user_123".to_string());
+/// storage.put("user_123", &node).await?;
+///
+/// if let Some(loaded_node) = storage.get("user_123").await? {
+///     println!("Loaded node: {:?}", loaded_node);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Retrieve a node by its soul (unique identifier)
+    ///
+    /// # Arguments
+    /// * `soul` - The unique identifier of the node to retrieve
+    ///
+    /// # Returns
+    /// `Ok(Some(node))` if found, `Ok(None)` if not found, or `GunError` on failure.
+    async fn get(&self, soul: &str) -> GunResult<Option<Node>>;
+
+    /// Store a node by its soul (unique identifier)
+    ///
+    /// # Arguments
+    /// * `soul` - The unique identifier for the node
+    /// * `node` - The node to store
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or `GunError` on failure.
+    async fn put(&self, soul: &str, node: &Node) -> GunResult<()>;
+
+    /// Check if a node exists in storage
+    ///
+    /// # Arguments
+    /// * `soul` - The unique identifier to check
+    ///
+    /// # Returns
+    /// `Ok(true)` if the node exists, `Ok(false)` if not, or `GunError` on failure.
+    async fn has(&self, soul: &str) -> GunResult<bool>;
+
+    /// List the souls of all nodes held in storage
+    ///
+    /// Needed to preload, export or migrate from the backend. The default,
+    /// for backends written before this method existed, fails with
+    /// `GunError::StorageBackend`.
+    ///
+    /// # Returns
+    /// `Ok(souls)` in no particular order, or `GunError` on failure.
+    async fn list(&self) -> GunResult<Vec<String>> {
+        Err(GunError::StorageBackend("This storage backend can't list its nodes".to_string()))
+    }
+
+    /// Remove a node from storage
+    ///
+    /// Deleting a soul that does not exist is not an error. The default, for
+    /// backends written before this method existed, fails with
+    /// `GunError::StorageBackend`.
+    ///
+    /// # Arguments
+    /// * `soul` - The unique identifier of the node to remove
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or `GunError` on failure.
+    async fn delete(&self, soul: &str) -> GunResult<()> {
+        Err(GunError::StorageBackend(format!("This storage backend can't delete nodes ({})", soul)))
+    }
+
+    /// Persist changed keys of a node without rewriting the whole node
+    ///
+    /// Each entry is `(key, value, state)`. A key whose stored state is newer
+    /// than the delta's is left alone. Creates the node if it doesn't exist.
+    ///
+    /// The default implementation reads the node, applies the changes and writes
+    /// it back. Backends that can store individual keys (like [`SledStorage`])
+    /// override it so a single-key update to a large node only writes that key.
+    ///
+    /// # Arguments
+    /// * `soul` - The unique identifier of the node
+    /// * `changed` - The keys that changed, with their new values and states
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or `GunError` on failure.
+    async fn put_delta(&self, soul: &str, changed: &[(String, Value, f64)]) -> GunResult<()> {
+        let mut node = self
+            .get(soul)
+            .await?
+            .unwrap_or_else(|| Node::with_soul(soul.to_string()));
+        apply_delta(&mut node, soul, changed);
+        self.put(soul, &node).await
+    }
+
+    /// Make every completed write durable
+    ///
+    /// Backends that buffer writes (like [`SledStorage`]) write them out; the
+    /// default does nothing. Called by [`Gun::flush`](crate::Gun::flush) and
+    /// [`Gun::shutdown`](crate::Gun::shutdown).
+    ///
+    /// # Returns
+    /// `Ok(())` once buffered writes are on disk, or `GunError` on failure.
+    async fn flush(&self) -> GunResult<()> {
+        Ok(())
+    }
+}
+
+/// Shared backends are backends too, so wrappers like [`InstrumentedStorage`] can
+/// hold an `Arc<dyn Storage>`
+#[async_trait]
+impl<T: Storage + ?Sized> Storage for Arc<T> {
+    async fn get(&self, soul: &str) -> GunResult<Option<Node>> {
+        (**self).get(soul).await
+    }
+
+    async fn put(&self, soul: &str, node: &Node) -> GunResult<()> {
+        (**self).put(soul, node).await
+    }
+
+    async fn has(&self, soul: &str) -> GunResult<bool> {
+        (**self).has(soul).await
+    }
+
+    async fn list(&self) -> GunResult<Vec<String>> {
+        (**self).list().await
+    }
+
+    async fn delete(&self, soul: &str) -> GunResult<()> {
+        (**self).delete(soul).await
+    }
+
+    async fn put_delta(&self, soul: &str, changed: &[(String, Value, f64)]) -> GunResult<()> {
+        (**self).put_delta(soul, changed).await
+    }
+
+    async fn flush(&self) -> GunResult<()> {
+        (**self).flush().await
+    }
+}
+
+/// Apply `(key, value, state)` changes to a node, skipping keys whose state is newer
+fn apply_delta(node: &mut Node, soul: &str, changed: &[(String, Value, f64)]) {
+    for (key, value, state) in changed {
+        let current = node
+            .meta
+            .get(">")
+            .and_then(|states| states.get(key))
+            .and_then(Value::as_f64);
+        if matches!(current, Some(current) if current > *state) {
+            continue;
+        }
+        node.data.insert(key.clone(), value.clone());
+        State::ify(node, Some(key), Some(*state), None, Some(soul));
+    }
+}
+
+/// In-memory storage backend (no persistence)
+///
+/// Stores data in a `HashMap` in memory. Data is lost when the instance is dropped.
+/// This is useful for:
+/// - Testing
+/// - Temporary data
+/// - Ephemeral caches when embedding Gun in another process
+///
+/// It is also the backend [`Gun`](crate::Gun) falls back to when no persistent
+/// storage is configured, so read-through, preload and metrics work the same
+/// with or without durability.
+///
+/// # Capacity
+///
+/// By default the store is unbounded. [`with_max_entries`](Self::with_max_entries)
+/// and [`with_max_bytes`](Self::with_max_bytes) cap it; once a write takes it over
+/// a cap, the least recently used nodes (by `get`, `put` or `put_delta`) are
+/// evicted until it fits again. Sizes are the JSON length of each node plus its
+/// soul. A node larger than the byte cap on its own is not kept.
+///
+/// # Thread Safety
+///
+/// `MemoryStorage` is thread-safe and can be shared across threads using `Arc<MemoryStorage>`.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use gun::storage::{Storage, MemoryStorage};
+/// use gun::state::Node;
+/// use std::sync::Arc;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let storage = Arc::new(MemoryStorage::new().with_max_bytes(16 * 1024 * 1024));
+/// let node = Node::with_soul("user_123".to_string());
+/// storage.put("user_123", &node).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct MemoryStorage {
+    state: Mutex<MemoryState>,
+    max_entries: Option<usize>,
+    max_bytes: Option<usize>,
+}
+
+/// Entry cap of the in-memory backend Gun uses when no storage is configured
+pub const DEFAULT_MEMORY_STORAGE_MAX_ENTRIES: usize = 100_000;
+
+/// Byte cap of the in-memory backend Gun uses when no storage is configured
+pub const DEFAULT_MEMORY_STORAGE_MAX_BYTES: usize = 64 * 1024 * 1024;
+
+struct MemoryEntry {
+    node: Node,
+    size: usize,
+    last_used: u64,
+}
+
+/// Nodes plus the bookkeeping for LRU eviction
+#[derive(Default)]
+struct MemoryState {
+    entries: HashMap<String, MemoryEntry>,
+    lru: BTreeMap<u64, String>, // last_used tick -> soul, oldest first
+    tick: u64,
+    bytes: usize,
+    evictions: u64,
+}
+
+impl MemoryState {
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    /// Mark a soul as just used
+    fn touch(&mut self, soul: &str) {
+        let tick = self.next_tick();
+        if let Some(entry) = self.entries.get_mut(soul) {
+            self.lru.remove(&entry.last_used);
+            entry.last_used = tick;
+            self.lru.insert(tick, soul.to_string());
+        }
+    }
+
+    fn insert(&mut self, soul: &str, node: Node) {
+        self.remove(soul);
+        let size = soul.len() + serde_json::to_vec(&node).map(|b| b.len()).unwrap_or(0);
+        let tick = self.next_tick();
+        self.bytes += size;
+        self.lru.insert(tick, soul.to_string());
+        self.entries.insert(
+            soul.to_string(),
+            MemoryEntry {
+                node,
+                size,
+                last_used: tick,
+            },
+        );
+    }
+
+    fn remove(&mut self, soul: &str) -> Option<Node> {
+        let entry = self.entries.remove(soul)?;
+        self.lru.remove(&entry.last_used);
+        self.bytes -= entry.size;
+        Some(entry.node)
+    }
+
+    /// Evict least recently used nodes until both caps hold
+    fn enforce(&mut self, max_entries: Option<usize>, max_bytes: Option<usize>) {
+        while matches!(max_entries, Some(max) if self.entries.len() > max)
+            || matches!(max_bytes, Some(max) if self.bytes > max)
+        {
+            let oldest = match self.lru.pop_first() {
+                Some((_, soul)) => soul,
+                None => break,
+            };
+            if let Some(entry) = self.entries.remove(&oldest) {
+                self.bytes -= entry.size;
+                self.evictions += 1;
+            }
+        }
+    }
+}
+
+impl MemoryStorage {
+    /// Create an empty, unbounded store
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(MemoryState::default()),
+            max_entries: None,
+            max_bytes: None,
+        }
+    }
+
+    /// Keep at most `max_entries` nodes, evicting the least recently used
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    /// Keep at most `max_bytes` of node data, evicting the least recently used
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Number of nodes held
+    pub fn len(&self) -> usize {
+        self.state.lock().entries.len()
+    }
+
+    /// Whether no nodes are held
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Total size of the nodes held, in bytes (see [Capacity](#capacity))
+    pub fn size_bytes(&self) -> usize {
+        self.state.lock().bytes
+    }
+
+    /// Number of nodes evicted to stay within the caps
+    pub fn evictions(&self) -> u64 {
+        self.state.lock().evictions
+    }
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    async fn get(&self, soul: &str) -> GunResult<Option<Node>> {
+        let mut state = self.state.lock();
+        state.touch(soul);
+        Ok(state.entries.get(soul).map(|entry| entry.node.clone()))
+    }
+
+    async fn put(&self, soul: &str, node: &Node) -> GunResult<()> {
+        let mut state = self.state.lock();
+        state.insert(soul, node.clone());
+        state.enforce(self.max_entries, self.max_bytes);
+        Ok(())
+    }
+
+    async fn has(&self, soul: &str) -> GunResult<bool> {
+        Ok(self.state.lock().entries.contains_key(soul))
+    }
+
+    async fn list(&self) -> GunResult<Vec<String>> {
+        Ok(self.state.lock().entries.keys().cloned().collect())
+    }
+
+    async fn delete(&self, soul: &str) -> GunResult<()> {
+        self.state.lock().remove(soul);
+        Ok(())
+    }
+
+    async fn put_delta(&self, soul: &str, changed: &[(String, Value, f64)]) -> GunResult<()> {
+        let mut state = self.state.lock();
+        let mut node = state
+            .remove(soul)
+            .unwrap_or_else(|| Node::with_soul(soul.to_string()));
+        apply_delta(&mut node, soul, changed);
+        state.insert(soul, node);
+        state.enforce(self.max_entries, self.max_bytes);
+        Ok(())
+    }
+}
+
+impl Default for MemoryStorage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Sled-based persistent storage backend
+///
+/// Uses the [sled](https://docs.rs/sled) embedded database for high-performance,
+/// persistent storage. This is recommended for:
+/// - Large datasets
+/// - High write throughput
+/// - Production applications
+///
+/// Sled provides:
+/// - ACID transactions
+/// - High performance
+/// - Automatic crash recovery
+/// - Efficient storage format
+///
+/// # Delta Writes
+///
+/// [`put_delta`](Storage::put_delta) stores each changed key as its own entry in
+/// a `deltas` tree (keyed by soul and key) instead of rewriting the node, so a
+/// single-key update to a node with thousands of keys only writes that key.
+/// Reads overlay the deltas on the stored node. Once a node has more than
+/// 256 pending deltas they are folded back into it.
+///
+/// # Thread Safety
+///
+/// `SledStorage` is thread-safe and can be shared across threads using `Arc<SledStorage>`.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use gun::storage::{Storage, SledStorage};
+/// use gun::state::Node;
+/// use std::sync::Arc;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let storage = Arc::new(SledStorage::new("./gun_data")?);
+/// let node = Node::with_soul("user_123".to_string());
+/// storage.put("user_123", &node).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct SledStorage {
+    db: Arc<sled::Db>,
+    deltas: sled::Tree, // Per-key updates not yet folded into their node
+    flush_each_write: bool, // False when a flush_every_ms timer flushes instead
+}
+
+/// Pending deltas per node before [`SledStorage`] folds them into the node
+const DELTA_COMPACT_THRESHOLD: usize = 256;
+
+/// Sled's background flush interval when every write is flushed anyway
+const DEFAULT_SLED_FLUSH_EVERY_MS: u64 = 500;
+
+/// Sled databases currently open in this process, by canonical path
+///
+/// Sled locks its directory, so a second `sled::open` of the same path fails;
+/// instead every `SledStorage` for a path shares one database handle.
+static OPEN_SLED_DBS: Mutex<BTreeMap<PathBuf, Weak<sled::Db>>> =
+    Mutex::new(BTreeMap::new());
+
+impl SledStorage {
+    /// Create a new SledStorage instance
+    ///
+    /// # Arguments
+    /// * `path` - Directory path where the sled database will be stored
+    ///
+    /// # Returns
+    /// `Ok(SledStorage)` if initialization succeeds, or `GunError` on failure.
+    ///
+    /// Opening a path that is already open in this process shares the same
+    /// database, so several instances (e.g. with different
+    /// [`namespace`](crate::GunOptions::namespace)s) can use one directory.
+    ///
+    /// # Errors
+    /// Returns `GunError::Storage` if the sled database cannot be opened or created.
+    pub fn new(path: &str) -> GunResult<Self> {
+        Self::with_flush_every_ms(path, None)
+    }
+
+    /// Create a SledStorage instance with a timed flush policy
+    ///
+    /// By default every write is flushed to disk before it returns. With
+    /// `flush_every_ms` set, writes return once sled has them in memory and a
+    /// background thread flushes them every `flush_every_ms` milliseconds, which
+    /// is much faster for bursts of writes; writes since the last flush are lost
+    /// if the process dies before [`flush`](Self::flush) (called by
+    /// [`Gun::shutdown`](crate::Gun::shutdown)).
+    ///
+    /// If the path is already open in this process, the database keeps the
+    /// background interval it was opened with.
+    ///
+    /// # Arguments
+    /// * `path` - Directory path where the sled database will be stored
+    /// * `flush_every_ms` - Milliseconds between background flushes, or `None`
+    ///   to flush on every write
+    ///
+    /// # Errors
+    /// Returns `GunError::Storage` if the sled database cannot be opened or created.
+    pub fn with_flush_every_ms(path: &str, flush_every_ms: Option<u64>) -> GunResult<Self> {
+        let db = Self::open_shared(path, flush_every_ms)?;
+        let deltas = db.open_tree("deltas")?;
+        Ok(Self {
+            db,
+            deltas,
+            flush_each_write: flush_every_ms.is_none(),
+        })
+    }
+
+    /// Write every buffered change to disk
+    ///
+    /// # Returns
+    /// `Ok(())` once the data is durable, or `GunError::Storage` if the write fails.
+    pub async fn flush(&self) -> GunResult<()> {
+        self.db.flush_async().await?;
+        Ok(())
+    }
+
+    /// Flush after a write unless the background flush takes care of it
+    async fn flush_write(&self) -> GunResult<()> {
+        if self.flush_each_write {
+            self.flush().await?;
+        }
+        Ok(())
+    }
+
+    /// Open the database at `path`, reusing a handle already open in this process
+    fn open_shared(path: &str, flush_every_ms: Option<u64>) -> GunResult<Arc<sled::Db>> {
+        fs::create_dir_all(path)?;
+        let canonical = fs::canonicalize(path)?;
+
+        let mut open = OPEN_SLED_DBS.lock();
+        if let Some(db) = open.get(&canonical).and_then(Weak::upgrade) {
+            return Ok(db);
+        }
+        open.retain(|_, db| db.strong_count() > 0);
+        let db = sled::Config::new()
+            .path(&canonical)
+            .flush_every_ms(flush_every_ms.or(Some(DEFAULT_SLED_FLUSH_EVERY_MS)))
+            .open()?;
+        let db = Arc::new(db);
+        open.insert(canonical, Arc::downgrade(&db));
+        Ok(db)
+    }
+
+    /// Prefix of every delta entry for a soul (`soul` followed by a NUL byte)
+    fn delta_prefix(soul: &str) -> Vec<u8> {
+        let mut prefix = Vec::with_capacity(soul.len() + 1);
+        prefix.extend_from_slice(soul.as_bytes());
+        prefix.push(0);
+        prefix
+    }
+
+    /// Read the stored node without any pending deltas
+    fn get_base(&self, soul: &str) -> GunResult<Option<Node>> {
+        match self.db.get(soul)? {
+            Some(ivec) => {
+                let json_str = String::from_utf8(ivec.to_vec())
+                    .map_err(|e| GunError::InvalidData(format!("Invalid UTF-8: {}", e)))?;
+                let node: Node = serde_json::from_str(&json_str)?;
+                Ok(Some(node))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Read the node with its pending deltas applied, and the delta keys applied
+    fn get_merged(&self, soul: &str) -> GunResult<(Option<Node>, Vec<sled::IVec>)> {
+        let prefix = Self::delta_prefix(soul);
+        let mut changed = Vec::new();
+        let mut delta_keys = Vec::new();
+        for entry in self.deltas.scan_prefix(&prefix) {
+            let (delta_key, value) = entry?;
+            let key = String::from_utf8(delta_key[prefix.len()..].to_vec())
+                .map_err(|e| GunError::InvalidData(format!("Invalid UTF-8: {}", e)))?;
+            let (value, state): (Value, f64) = serde_json::from_slice(&value)?;
+            changed.push((key, value, state));
+            delta_keys.push(delta_key);
+        }
+
+        let node = match (self.get_base(soul)?, changed.is_empty()) {
+            (base, true) => base,
+            (base, false) => {
+                let mut node = base.unwrap_or_else(|| Node::with_soul(soul.to_string()));
+                apply_delta(&mut node, soul, &changed);
+                Some(node)
+            }
+        };
+        Ok((node, delta_keys))
+    }
+
+    /// Remove every pending delta of a soul
+    fn clear_deltas(&self, soul: &str) -> GunResult<()> {
+        let mut batch = sled::Batch::default();
+        for delta_key in self.deltas.scan_prefix(Self::delta_prefix(soul)).keys() {
+            batch.remove(delta_key?);
+        }
+        self.deltas.apply_batch(batch)?;
+        Ok(())
+    }
+
+    /// Fold pending deltas into the stored node
+    ///
+    /// The node is written before the deltas are removed; a crash in between
+    /// leaves deltas that no longer change anything when applied.
+    fn compact(&self, soul: &str) -> GunResult<()> {
+        let (node, delta_keys) = self.get_merged(soul)?;
+        if let Some(node) = node {
+            self.db.insert(soul, serde_json::to_vec(&node)?)?;
+        }
+        let mut batch = sled::Batch::default();
+        for delta_key in delta_keys {
+            batch.remove(delta_key);
+        }
+        self.deltas.apply_batch(batch)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Storage for SledStorage {
+    async fn get(&self, soul: &str) -> GunResult<Option<Node>> {
+        Ok(self.get_merged(soul)?.0)
+    }
+
+    async fn put(&self, soul: &str, node: &Node) -> GunResult<()> {
+        let json_str = serde_json::to_string(node)?;
+        self.db.insert(soul, json_str.as_bytes())?;
+        // The whole node replaces any pending per-key updates
+        self.clear_deltas(soul)?;
+        self.flush_write().await
+    }
+
+    async fn has(&self, soul: &str) -> GunResult<bool> {
+        Ok(self.db.contains_key(soul)?
+            || self.deltas.scan_prefix(Self::delta_prefix(soul)).next().is_some())
+    }
+
+    async fn list(&self) -> GunResult<Vec<String>> {
+        let mut souls = Vec::new();
+        for key in self.db.iter().keys() {
+            let key = key?;
+            let soul = String::from_utf8(key.to_vec())
+                .map_err(|e| GunError::InvalidData(format!("Invalid UTF-8: {}", e)))?;
+            souls.push(soul);
+        }
+
+        // Nodes created by put_delta may only exist as deltas so far
+        let mut delta_souls = HashSet::new();
+        for delta_key in self.deltas.iter().keys() {
+            let delta_key = delta_key?;
+            let end = delta_key.iter().position(|b| *b == 0).unwrap_or(delta_key.len());
+            let soul = String::from_utf8(delta_key[..end].to_vec())
+                .map_err(|e| GunError::InvalidData(format!("Invalid UTF-8: {}", e)))?;
+            if !self.db.contains_key(&soul)? {
+                delta_souls.insert(soul);
+            }
+        }
+        souls.extend(delta_souls);
+        Ok(souls)
+    }
+
+    async fn delete(&self, soul: &str) -> GunResult<()> {
+        self.db.remove(soul)?;
+        self.clear_deltas(soul)?;
+        self.flush_write().await
+    }
+
+    async fn put_delta(&self, soul: &str, changed: &[(String, Value, f64)]) -> GunResult<()> {
+        let prefix = Self::delta_prefix(soul);
+        let mut batch = sled::Batch::default();
+        for (key, value, state) in changed {
+            let mut delta_key = prefix.clone();
+            delta_key.extend_from_slice(key.as_bytes());
+            if let Some(pending) = self.deltas.get(&delta_key)? {
+                let (_, pending_state): (Value, f64) = serde_json::from_slice(&pending)?;
+                if pending_state > *state {
+                    continue;
+                }
+            }
+            batch.insert(delta_key, serde_json::to_vec(&(value, state))?);
+        }
+        self.deltas.apply_batch(batch)?;
+
+        let pending = self
+            .deltas
+            .scan_prefix(&prefix)
+            .keys()
+            .take(DELTA_COMPACT_THRESHOLD + 1)
+            .count();
+        if pending > DELTA_COMPACT_THRESHOLD {
+            self.compact(soul)?;
+        }
+
+        self.flush_write().await
+    }
+
+    async fn flush(&self) -> GunResult<()> {
+        SledStorage::flush(self).await
+    }
+}
+
+/// LocalStorage-equivalent storage for Rust
+/// Provides a simple, persistent key-value store similar to browser localStorage
+/// Stores data in JSON files on disk in a single directory
+///
+/// This is similar to browser localStorage in that it:
+/// - Persists data to disk
+/// - Provides simple get/put/has operations
+/// - Stores data in a user-accessible location
+/// - Is simpler than a full database (like Sled)
+///
+/// # Crash Safety
+///
+/// Each write goes to a uniquely named `#….tmp` file that is fsynced and then
+/// renamed over the node's file (the directory is fsynced too), so a crash leaves
+/// either the old or the new node on disk, never a truncated one. On open,
+/// leftover temp files are removed and files that fail to parse are renamed to
+/// `.corrupt` and listed in [`load_report`](Self::load_report) instead of
+/// failing the whole backend.
+pub struct LocalStorage {
+    data_dir: PathBuf,
+    cache: RwLock<HashMap<String, Node>>, // In-memory cache for performance
+    dirty: RwLock<HashSet<String>>,       // Track which keys need to be written to disk
+    load_report: LoadReport,
+    temp_counter: AtomicU64, // Unique temp file names for concurrent writes
+}
+
+/// What [`LocalStorage::new`] found on disk when loading its directory
+#[derive(Clone, Debug, Default)]
+pub struct LoadReport {
+    /// Nodes loaded successfully
+    pub loaded: usize,
+    /// Files that could not be parsed and were quarantined
+    pub corrupted: Vec<CorruptFile>,
+    /// Leftover temp files from interrupted writes that were removed
+    pub removed_temp_files: usize,
+}
+
+/// A node file that failed to load
+#[derive(Clone, Debug)]
+pub struct CorruptFile {
+    /// Soul decoded from the file name
+    pub soul: String,
+    /// Where the file was moved (`<name>.corrupt`), or where it still is if
+    /// it couldn't be moved
+    pub quarantined_to: PathBuf,
+    /// Why it failed to load
+    pub error: String,
+}
+
+/// Prefix of in-progress writes: URL encoding escapes `#`, so no node file
+/// name starts with it
+const TEMP_PREFIX: &str = "#";
+/// Suffix of in-progress writes
+const TEMP_SUFFIX: &str = ".tmp";
+/// Suffix of quarantined files that failed to load
+const CORRUPT_SUFFIX: &str = ".corrupt";
+
+impl LocalStorage {
+    /// Create a new LocalStorage instance
+    ///
+    /// # Arguments
+    /// * `data_dir` - Directory path where data will be stored (e.g., "./gun_data")
+    ///
+    /// Creates the directory if it doesn't exist. Unreadable node files don't
+    /// cause an error; see [`load_report`](Self::load_report).
+    pub fn new(data_dir: &str) -> GunResult<Self> {
+        let path = PathBuf::from(data_dir);
+
+        // Create directory if it doesn't exist
+        fs::create_dir_all(&path).map_err(|e| {
+            GunError::Io(std::io::Error::other(format!(
+                "Failed to create storage directory: {}",
+                e
+            )))
+        })?;
+
+        // Load existing data into cache
+        let (cache, load_report) = Self::load_all(&path)?;
+        for corrupt in &load_report.corrupted {
+            tracing::warn!(
+                soul = %corrupt.soul,
+                quarantined_to = %corrupt.quarantined_to.display(),
+                error = %corrupt.error,
+                "LocalStorage: quarantined unreadable node"
+            );
+        }
+
+        Ok(Self {
+            data_dir: path,
+            cache: RwLock::new(cache),
+            dirty: RwLock::new(HashSet::new()),
+            load_report,
+            temp_counter: AtomicU64::new(0),
+        })
+    }
+
+    /// Report of what was loaded from disk when this instance was opened
+    pub fn load_report(&self) -> &LoadReport {
+        &self.load_report
+    }
+
+    /// File name for a soul
+    ///
+    /// URL encoding makes the soul filename-safe; `.` is escaped as well so that
+    /// node files never contain a dot and can't be confused with `.corrupt`
+    /// files.
+    fn file_name(soul: &str) -> String {
+        urlencoding::encode(soul).replace('.', "%2E")
+    }
+
+    /// Whether `file_name` is a temp file as [`save_file`](Self::save_file)
+    /// names them: `#<file name>.<counter>.tmp`
+    fn is_temp_file(file_name: &str) -> bool {
+        file_name.starts_with(TEMP_PREFIX) && file_name.ends_with(TEMP_SUFFIX)
+    }
+
+    /// Whether `file_name` has the shape of a quarantined node file:
+    /// `<file name>.corrupt`
+    fn is_quarantined_file(file_name: &str) -> bool {
+        file_name.strip_suffix(CORRUPT_SUFFIX).is_some_and(|name| !name.contains('.'))
+    }
+
+    /// Load all data from disk into memory cache
+    fn load_all(path: &PathBuf) -> GunResult<(HashMap<String, Node>, LoadReport)> {
+        let mut data = HashMap::new();
+        let mut report = LoadReport::default();
+
+        // Read all files in the directory
+        if let Ok(entries) = fs::read_dir(path) {
+            for entry in entries.flatten() {
+                let file_path = entry.path();
+                if !file_path.is_file() {
+                    continue;
+                }
+                let file_name = match file_path.file_name().and_then(|n| n.to_str()) {
+                    Some(name) => name.to_string(),
+                    None => continue,
+                };
+
+                if Self::is_temp_file(&file_name) {
+                    // Interrupted write - the node file itself is still intact
+                    if fs::remove_file(&file_path).is_ok() {
+                        report.removed_temp_files += 1;
+                    }
+                    continue;
+                }
+                let node = Self::load_file(&file_path);
+                // Versions that didn't escape `.` may have written a node
+                // named like a quarantined file; only unreadable ones are
+                if Self::is_quarantined_file(&file_name) && node.is_err() {
+                    continue;
+                }
+
+                // Try to decode the filename (may be URL-encoded)
+                let soul = urlencoding::decode(&file_name)
+                    .map(|s| s.into_owned())
+                    .unwrap_or_else(|_| file_name.clone());
+
+                match node {
+                    Ok(node) => {
+                        // Files written before `.` was escaped get their current name
+                        let expected = path.join(Self::file_name(&soul));
+                        if expected != file_path {
+                            let _ = fs::rename(&file_path, &expected);
+                        }
+                        data.insert(soul, node);
+                        report.loaded += 1;
+                    }
+                    Err(e) => {
+                        let quarantined_to =
+                            path.join(format!("{}{}", Self::file_name(&soul), CORRUPT_SUFFIX));
+                        // Left in place, the file fails again on the next open
+                        let (quarantined_to, error) = match fs::rename(&file_path, &quarantined_to) {
+                            Ok(()) => (quarantined_to, e.to_string()),
+                            Err(rename_err) => {
+                                (file_path, format!("{} (couldn't quarantine it: {})", e, rename_err))
+                            }
+                        };
+                        report.corrupted.push(CorruptFile {
+                            soul,
+                            quarantined_to,
+                            error,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok((data, report))
+    }
+
+    /// Load a single file from disk
+    fn load_file(path: &PathBuf) -> GunResult<Node> {
+        let mut file = fs::File::open(path)?;
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+        let node: Node = serde_json::from_str(&contents)?;
+        Ok(node)
+    }
+
+    /// Path of the file backing a soul
+    fn file_path(&self, soul: &str) -> PathBuf {
+        self.data_dir.join(Self::file_name(soul))
+    }
+
+    /// Save a node to disk
+    fn save_file(&self, soul: &str, node: &Node) -> GunResult<()> {
+        let file_path = self.file_path(soul);
+
+        let json_str = serde_json::to_string_pretty(node).map_err(GunError::Serialization)?;
+
+        // Write atomically: write to a unique temp file, fsync, then rename
+        let temp_path = self.data_dir.join(format!(
+            "{}{}.{}{}",
+            TEMP_PREFIX,
+            Self::file_name(soul),
+            self.temp_counter.fetch_add(1, Ordering::Relaxed),
+            TEMP_SUFFIX
+        ));
+        let result = (|| {
+            let mut file = fs::File::create(&temp_path)?;
+            file.write_all(json_str.as_bytes())?;
+            file.sync_all()?;
+            drop(file);
+
+            // Atomic rename
+            fs::rename(&temp_path, &file_path)
+        })();
+        if let Err(e) = result {
+            let _ = fs::remove_file(&temp_path);
+            return Err(GunError::Io(e));
+        }
+
+        self.sync_dir();
+        Ok(())
+    }
+
+    /// Fsync the data directory so renames and removals survive a crash
+    fn sync_dir(&self) {
+        // Directories can't be opened as files on every platform; best effort
+        if let Ok(dir) = fs::File::open(&self.data_dir) {
+            let _ = dir.sync_all();
+        }
+    }
+
+    /// Flush dirty entries to disk
+    pub async fn flush(&self) -> GunResult<()> {
+        let dirty_keys: Vec<String> = {
+            let dirty = self.dirty.read();
+            dirty.iter().cloned().collect()
+        };
+
+        let cache = self.cache.read();
+        for soul in dirty_keys {
+            if let Some(node) = cache.get(&soul) {
+                if let Err(e) = self.save_file(&soul, node) {
+                    tracing::warn!(soul = %soul, error = %e, "Error saving node to disk");
+                }
+            }
+        }
+
+        // Clear dirty set
+        let mut dirty = self.dirty.write();
+        dirty.clear();
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Storage for LocalStorage {
+    async fn get(&self, soul: &str) -> GunResult<Option<Node>> {
+        // Check cache first
+        let cache = self.cache.read();
+        Ok(cache.get(soul).cloned())
+    }
+
+    async fn put(&self, soul: &str, node: &Node) -> GunResult<()> {
+        // Update cache
+        {
+            let mut cache = self.cache.write();
+            cache.insert(soul.to_string(), node.clone());
+        }
+
+        // Mark as dirty for disk write
+        {
+            let mut dirty = self.dirty.write();
+            dirty.insert(soul.to_string());
+        }
+
+        // Write to disk immediately (localStorage behavior)
+        // Could be optimized to batch writes, but for now we match localStorage's synchronous behavior
+        self.save_file(soul, node)?;
+
+        // Remove from dirty set since we just wrote it
+        let mut dirty = self.dirty.write();
+        dirty.remove(soul);
+
+        Ok(())
+    }
+
+    async fn has(&self, soul: &str) -> GunResult<bool> {
+        let cache = self.cache.read();
+        Ok(cache.contains_key(soul))
+    }
+
+    async fn list(&self) -> GunResult<Vec<String>> {
+        let cache = self.cache.read();
+        Ok(cache.keys().cloned().collect())
+    }
+
+    async fn delete(&self, soul: &str) -> GunResult<()> {
+        self.cache.write().remove(soul);
+        self.dirty.write().remove(soul);
+
+        match fs::remove_file(self.file_path(soul)) {
+            Ok(()) => {
+                self.sync_dir();
+                Ok(())
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(GunError::Io(e)),
+        }
+    }
+
+    async fn flush(&self) -> GunResult<()> {
+        LocalStorage::flush(self).await
+    }
+}
+
+// Implement Drop to flush on cleanup
+impl Drop for LocalStorage {
+    fn drop(&mut self) {
+        // Flush any remaining dirty entries
+        let dirty_keys: Vec<String> = {
+            let dirty = self.dirty.read();
+            dirty.iter().cloned().collect()
+        };
+
+        if !dirty_keys.is_empty() {
+            let cache = self.cache.read();
+            for soul in dirty_keys {
+                if let Some(node) = cache.get(&soul) {
+                    let _ = self.save_file(&soul, node);
+                }
+            }
+        }
+    }
+}
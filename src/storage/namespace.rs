@@ -0,0 +1,124 @@
+//! Namespaced storage keys
+//!
+//! [`NamespacedStorage`] lets several Gun instances share one backend (one Sled
+//! directory, one Redis database) without colliding: every soul is stored as
+//! `"{namespace}/{soul}"` and the prefix is stripped again on the way out, so the
+//! graph and the wire protocol never see it.
+//!
+//! Namespaces can't be empty or contain `/`: `app/` would otherwise be a prefix
+//! of the keys of namespace `app/x`, and `app` would list them as its own.
+
+use super::Storage;
+use crate::error::{GunError, GunResult};
+use crate::state::Node;
+use async_trait::async_trait;
+use serde_json::Value;
+
+/// Storage wrapper that keeps all keys under a namespace prefix
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use gun::storage::{NamespacedStorage, SledStorage, Storage};
+/// use std::sync::Arc;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let sled = Arc::new(SledStorage::new("./gun_data")?);
+/// let app = NamespacedStorage::new(sled.clone(), "app")?;
+/// let test = NamespacedStorage::new(sled, "test")?;
+///
+/// // Same soul, different keys on disk ("app/user_123" and "test/user_123")
+/// assert_eq!(app.key("user_123"), "app/user_123");
+/// assert!(test.list().await?.is_empty());
+/// # Ok(())
+/// # }
+/// ```
+pub struct NamespacedStorage<S> {
+    inner: S,
+    prefix: String,
+}
+
+impl<S: Storage> NamespacedStorage<S> {
+    /// Wrap a backend so all keys live under `namespace`
+    ///
+    /// # Errors
+    /// `GunError::InvalidData` if `namespace` is empty or contains `/`.
+    pub fn new(inner: S, namespace: impl Into<String>) -> GunResult<Self> {
+        let namespace = namespace.into();
+        if !is_valid_namespace(&namespace) {
+            return Err(GunError::InvalidData(format!(
+                "Invalid namespace {:?}: must be non-empty and without '/'",
+                namespace
+            )));
+        }
+        Ok(Self {
+            inner,
+            prefix: format!("{}/", namespace),
+        })
+    }
+
+    /// The namespace, without the trailing `/`
+    pub fn namespace(&self) -> &str {
+        &self.prefix[..self.prefix.len() - 1]
+    }
+
+    /// The wrapped backend
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Key a soul is stored under in the wrapped backend
+    pub fn key(&self, soul: &str) -> String {
+        format!("{}{}", self.prefix, soul)
+    }
+}
+
+#[async_trait]
+impl<S: Storage> Storage for NamespacedStorage<S> {
+    async fn get(&self, soul: &str) -> GunResult<Option<Node>> {
+        let key = self.key(soul);
+        let mut node = self.inner.get(&key).await?;
+        // Nodes created by a delta write carry the prefixed key as their soul
+        if let Some(node) = &mut node {
+            if node.get_soul().as_deref() == Some(key.as_str()) {
+                node.meta.insert("#".to_string(), Value::String(soul.to_string()));
+            }
+        }
+        Ok(node)
+    }
+
+    async fn put(&self, soul: &str, node: &Node) -> GunResult<()> {
+        self.inner.put(&self.key(soul), node).await
+    }
+
+    async fn has(&self, soul: &str) -> GunResult<bool> {
+        self.inner.has(&self.key(soul)).await
+    }
+
+    async fn list(&self) -> GunResult<Vec<String>> {
+        Ok(self
+            .inner
+            .list()
+            .await?
+            .into_iter()
+            .filter_map(|key| key.strip_prefix(&self.prefix).map(str::to_string))
+            .collect())
+    }
+
+    async fn delete(&self, soul: &str) -> GunResult<()> {
+        self.inner.delete(&self.key(soul)).await
+    }
+
+    async fn put_delta(&self, soul: &str, changed: &[(String, Value, f64)]) -> GunResult<()> {
+        self.inner.put_delta(&self.key(soul), changed).await
+    }
+
+    async fn flush(&self) -> GunResult<()> {
+        self.inner.flush().await
+    }
+}
+
+/// Whether `namespace` can be used by [`NamespacedStorage`]: non-empty, without `/`
+pub fn is_valid_namespace(namespace: &str) -> bool {
+    !namespace.is_empty() && !namespace.contains('/')
+}
@@ -0,0 +1,198 @@
+//! Redis storage backend
+//!
+//! Lets several relay processes share one graph by persisting nodes in Redis.
+//! Each node is stored as a JSON string under `gun:node:{soul}`, using the same
+//! serialization as [`SledStorage`](super::SledStorage) and
+//! [`LocalStorage`](super::LocalStorage).
+//!
+//! Only compiled when the `redis-storage` feature is enabled.
+
+use super::Storage;
+use crate::error::{GunError, GunResult};
+use crate::state::Node;
+use ::redis::aio::ConnectionManager;
+use ::redis::{FromRedisValue, RedisError};
+use async_trait::async_trait;
+use std::time::Duration;
+
+/// Key prefix for node entries
+const KEY_PREFIX: &str = "gun:node:";
+
+/// Number of keys requested per `SCAN` round trip
+const SCAN_COUNT: usize = 500;
+
+/// Redis-backed storage shared between relay processes
+///
+/// Transient failures (dropped connections, timeouts, refused connections) are
+/// retried a bounded number of times with exponential backoff. Anything that
+/// still fails is returned as `GunError::StorageBackend`.
+///
+/// # Thread Safety
+///
+/// `RedisStorage` is thread-safe and can be shared across threads using `Arc<RedisStorage>`.
+/// The underlying connection is multiplexed and reconnects automatically.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use gun::storage::{Storage, RedisStorage};
+/// use gun::state::Node;
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let storage = RedisStorage::new("redis://127.0.0.1/")
+///     .await?
+///     .with_ttl(Duration::from_secs(3600));
+/// let storage = Arc::new(storage);
+///
+/// let node = Node::with_soul("user_123".to_string());
+/// storage.put("user_123", &node).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct RedisStorage {
+    conn: ConnectionManager,
+    ttl: Option<Duration>,
+    max_retries: u32,
+    retry_backoff: Duration,
+}
+
+impl RedisStorage {
+    /// Connect to Redis and create a new RedisStorage instance
+    ///
+    /// # Arguments
+    /// * `url` - Redis connection URL (e.g., "redis://127.0.0.1:6379/0")
+    ///
+    /// # Returns
+    /// `Ok(RedisStorage)` once connected, or `GunError` on failure.
+    ///
+    /// # Errors
+    /// Returns `GunError::StorageBackend` if the URL is invalid or the server cannot be reached.
+    pub async fn new(url: &str) -> GunResult<Self> {
+        let client = ::redis::Client::open(url).map_err(storage_error)?;
+        let conn = ConnectionManager::new(client)
+            .await
+            .map_err(storage_error)?;
+
+        Ok(Self {
+            conn,
+            ttl: None,
+            max_retries: 3,
+            retry_backoff: Duration::from_millis(50),
+        })
+    }
+
+    /// Expire node keys after `ttl`
+    ///
+    /// The TTL is refreshed every time a node is written. By default keys never expire.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Configure retries for transient failures
+    ///
+    /// # Arguments
+    /// * `max_retries` - Attempts made after the first failure (default: 3)
+    /// * `backoff` - Delay before the first retry, doubled on each attempt (default: 50ms)
+    pub fn with_retries(mut self, max_retries: u32, backoff: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.retry_backoff = backoff;
+        self
+    }
+
+    fn key(soul: &str) -> String {
+        format!("{}{}", KEY_PREFIX, soul)
+    }
+
+    /// Run a command, retrying transient failures with exponential backoff
+    async fn query<T: FromRedisValue>(&self, cmd: &::redis::Cmd) -> GunResult<T> {
+        let mut attempt = 0;
+        let mut backoff = self.retry_backoff;
+        loop {
+            let mut conn = self.conn.clone();
+            match cmd.query_async::<_, T>(&mut conn).await {
+                Ok(value) => return Ok(value),
+                Err(e) if attempt < self.max_retries && is_transient(&e) => {
+                    tracing::warn!(
+                        error = %e,
+                        attempt = attempt + 1,
+                        max_attempts = self.max_retries + 1,
+                        "Redis command failed, retrying"
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                    attempt += 1;
+                }
+                Err(e) => return Err(storage_error(e)),
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Storage for RedisStorage {
+    async fn get(&self, soul: &str) -> GunResult<Option<Node>> {
+        let value: Option<String> = self.query(::redis::cmd("GET").arg(Self::key(soul))).await?;
+        match value {
+            Some(json_str) => Ok(Some(serde_json::from_str(&json_str)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn put(&self, soul: &str, node: &Node) -> GunResult<()> {
+        let json_str = serde_json::to_string(node)?;
+        let mut cmd = ::redis::cmd("SET");
+        cmd.arg(Self::key(soul)).arg(json_str);
+        if let Some(ttl) = self.ttl {
+            cmd.arg("PX").arg(ttl.as_millis().max(1) as u64);
+        }
+        self.query::<()>(&cmd).await
+    }
+
+    async fn has(&self, soul: &str) -> GunResult<bool> {
+        self.query(::redis::cmd("EXISTS").arg(Self::key(soul))).await
+    }
+
+    async fn list(&self) -> GunResult<Vec<String>> {
+        let pattern = format!("{}*", KEY_PREFIX);
+        let mut souls = Vec::new();
+        let mut cursor: u64 = 0;
+        loop {
+            let mut cmd = ::redis::cmd("SCAN");
+            cmd.arg(cursor)
+                .arg("MATCH")
+                .arg(&pattern)
+                .arg("COUNT")
+                .arg(SCAN_COUNT);
+            let (next, keys): (u64, Vec<String>) = self.query(&cmd).await?;
+            souls.extend(
+                keys.into_iter()
+                    .filter_map(|key| key.strip_prefix(KEY_PREFIX).map(str::to_string)),
+            );
+            if next == 0 {
+                break;
+            }
+            cursor = next;
+        }
+        // SCAN may return a key more than once across iterations
+        souls.sort();
+        souls.dedup();
+        Ok(souls)
+    }
+
+    async fn delete(&self, soul: &str) -> GunResult<()> {
+        self.query::<()>(::redis::cmd("DEL").arg(Self::key(soul)))
+            .await
+    }
+}
+
+/// Whether a Redis error is worth retrying
+fn is_transient(e: &RedisError) -> bool {
+    e.is_io_error() || e.is_connection_dropped() || e.is_connection_refusal() || e.is_timeout()
+}
+
+fn storage_error(e: RedisError) -> GunError {
+    GunError::StorageBackend(format!("Redis: {}", e))
+}
@@ -1,29 +1,144 @@
+use async_trait::async_trait;
+use chia_bls::PublicKey;
 use serde_json::Value;
+use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::sync::Arc;
 
-/// Message predicate function type
-/// 
-/// Receives the entire message object (as `serde_json::Value`) and returns:
-/// - `true` to accept the message (continue processing)
-/// - `false` to reject the message (drop silently)
-/// 
-/// The predicate is called after signature verification but before message processing.
-/// This allows the application layer to implement custom message filtering logic,
-/// such as:
+/// What a [`MessageFilter`] decided about a message
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PredicateDecision {
+    /// Continue processing the message
+    Accept,
+    /// Drop the message silently
+    Reject,
+    /// Drop the message and tell the sender why with a `dam: "!"` error
+    RejectWithError(String),
+}
+
+impl From<bool> for PredicateDecision {
+    fn from(accept: bool) -> Self {
+        if accept {
+            PredicateDecision::Accept
+        } else {
+            PredicateDecision::Reject
+        }
+    }
+}
+
+/// Who a message came from, as seen by the mesh
+#[derive(Clone, Debug, Default)]
+pub struct PredicateContext {
+    /// Mesh ID of the sending peer (`None` for local messages)
+    pub peer_id: Option<String>,
+    /// URL the sending peer connected from or to
+    pub peer_url: Option<String>,
+    /// Keys whose signatures on the message were verified, first signer first
+    ///
+    /// Empty for unsigned messages.
+    pub public_keys: Vec<PublicKey>,
+}
+
+/// Application-level message filter
+///
+/// Called with the entire message object (as `serde_json::Value`) after
+/// signature verification but before message processing. This allows the
+/// application layer to implement custom message filtering logic, such as:
 /// - Filtering messages based on content
 /// - Implementing rate limiting
-/// - Enforcing access control policies
+/// - Enforcing access control policies based on the signers' keys
 /// - Blocking specific message types
-/// 
+///
+/// Rejected messages count against the sending peer's score either way.
+///
+/// Plain `Fn(&Value) -> bool` closures implement this trait, returning
+/// [`PredicateDecision::Accept`] for `true` and [`PredicateDecision::Reject`]
+/// for `false`.
+#[async_trait]
+pub trait MessageFilter: Send + Sync {
+    /// Decide whether to process `msg`
+    async fn check(&self, msg: &Value, ctx: &PredicateContext) -> PredicateDecision;
+}
+
+#[async_trait]
+impl<F> MessageFilter for F
+where
+    F: Fn(&Value) -> bool + Send + Sync,
+{
+    async fn check(&self, msg: &Value, _ctx: &PredicateContext) -> PredicateDecision {
+        self(msg).into()
+    }
+}
+
+/// Message predicate type
+///
 /// # Example
 /// ```rust,no_run
 /// use gun::MessagePredicate;
+/// use serde_json::Value;
 /// use std::sync::Arc;
 /// 
 /// // Only accept "put" messages
-/// let predicate: MessagePredicate = Arc::new(|msg| {
+/// let predicate: MessagePredicate = Arc::new(|msg: &Value| {
 ///     msg.get("put").is_some()
 /// });
 /// ```
-pub type MessagePredicate = Arc<dyn Fn(&Value) -> bool + Send + Sync>;
+pub type MessagePredicate = Arc<dyn MessageFilter>;
+
+/// A BLS public key in its 48-byte compressed form (`PublicKey::to_bytes()`)
+///
+/// Used for the mesh's key allowlist and denylist.
+pub type PublicKeyBytes = [u8; 48];
+
+
+/// A WebSocket upgrade request to the relay server, as seen by a [`ServerAuth`] hook
+#[derive(Clone, Debug)]
+pub struct AuthRequest {
+    /// Path of the request, e.g. `/gun`
+    pub path: String,
+    /// Decoded query parameters; the last value wins for repeated names
+    pub query: HashMap<String, String>,
+    /// Headers by lowercase name; the last value wins for repeated headers and
+    /// values that aren't valid UTF-8 are left out
+    pub headers: HashMap<String, String>,
+    /// Address the connection came from
+    pub remote_addr: SocketAddr,
+}
+
+/// What a [`ServerAuth`] hook decided about a connection
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AuthDecision {
+    /// Complete the upgrade and give the peer these tags (see `Peer::tags`); may be empty
+    Accept(Vec<String>),
+    /// Answer with this HTTP status and reason instead; no peer is added
+    Reject(u16, String),
+}
+
+/// Hook deciding which connections the relay server accepts
+///
+/// # Example
+/// ```rust,no_run
+/// use gun::{AuthDecision, AuthRequest, ServerAuth};
+/// use std::sync::Arc;
+///
+/// // Require ?token=... or an Authorization: Bearer header
+/// let auth: ServerAuth = Arc::new(|request: &AuthRequest| {
+///     let bearer = request.headers.get("authorization").and_then(|h| h.strip_prefix("Bearer "));
+///     match request.query.get("token").map(String::as_str).or(bearer) {
+///         Some("s3cret") => AuthDecision::Accept(vec!["members".to_string()]),
+///         _ => AuthDecision::Reject(401, "Token required".to_string()),
+///     }
+/// });
+/// ```
+pub type ServerAuth = Arc<dyn Fn(&AuthRequest) -> AuthDecision + Send + Sync>;
 
+/// The path messages for a peer take, see `Gun::transport_for` and `Gun::peers`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transport {
+    /// An open WebRTC data channel
+    WebRTC,
+    /// A WebSocket connection of our own
+    WebSocket,
+    /// Relays that pass messages on
+    Relay,
+}
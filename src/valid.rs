@@ -101,6 +101,9 @@ pub fn valid(value: &Value) -> Result<bool, Option<String>> {
 /// - A soul reference object: `{"#": "soul_id"}`
 /// - A string that could be a soul ID
 ///
+/// Souls starting with `~` are user space and must have its shape: `~pub`,
+/// `~pub/path` or `~@alias`, see [`parse_user_soul`](crate::sea::parse_user_soul).
+///
 /// # Arguments
 /// * `value` - The JSON value to check
 ///
@@ -121,9 +124,12 @@ pub fn valid(value: &Value) -> Result<bool, Option<String>> {
 ///
 /// // Not a soul
 /// assert_eq!(valid_soul(&json!("hello")), Some("hello".to_string())); // Strings are treated as potential souls
+///
+/// // User space, but not a pub
+/// assert_eq!(valid_soul(&json!("~hello")), None);
 /// ```
 pub fn valid_soul(value: &Value) -> Option<String> {
-    match valid(value) {
+    let soul = match valid(value) {
         Err(Some(soul)) => Some(soul), // It's a soul reference object
         Ok(true) => {
             // Check if it's a string that could be a soul
@@ -138,7 +144,12 @@ pub fn valid_soul(value: &Value) -> Option<String> {
             }
         }
         _ => None,
+    }?;
+    // User space souls only in the shapes SEA writes
+    if soul.starts_with('~') && crate::sea::parse_user_soul(&soul).is_none() {
+        return None;
     }
+    Some(soul)
 }
 
 /// Check if data is valid for storage in Gun
@@ -171,3 +182,35 @@ pub fn is_valid_data(value: &Value) -> bool {
         _ => false,
     }
 }
+
+/// Hash a content-addressed soul (`#hash`) names: SHA-256 of `data` as
+/// JSON, in unpadded base64
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use gun::valid::{content_hash, content_matches};
+/// use serde_json::json;
+///
+/// let data = json!({"name": "test", "value": 42});
+/// let soul = format!("#{}", content_hash(&data));
+/// assert!(content_matches(&soul, &data));
+/// ```
+pub fn content_hash(data: &Value) -> String {
+    use base64::Engine as _;
+    use sha2::{Digest, Sha256};
+    let hash = Sha256::digest(data.to_string().as_bytes());
+    base64::engine::general_purpose::STANDARD_NO_PAD.encode(hash)
+}
+
+/// Check that `data` is what `soul` holds, if the soul is content-addressed
+///
+/// A soul starting with `#` is frozen: what follows must be
+/// [`content_hash`] of its data, in base64 or hex. Other souls always match.
+pub fn content_matches(soul: &str, data: &Value) -> bool {
+    use sha2::{Digest, Sha256};
+    let Some(hash) = soul.strip_prefix('#') else {
+        return true;
+    };
+    hash == content_hash(data) || hash == hex::encode(Sha256::digest(data.to_string().as_bytes()))
+}
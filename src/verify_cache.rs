@@ -0,0 +1,186 @@
+//! Cache of signature verification results
+//!
+//! Relays check the same signatures again and again: a message's BLS
+//! signatures are verified before deduplication drops its copies, and signed
+//! user-space values are verified on every read and write path. A signature
+//! verifies or not forever, so results are kept in a bounded LRU table and
+//! only dropped for room or age.
+//!
+//! Keys are SHA-256 hashes of everything the result depends on: the signed
+//! bytes, the signature and the public keys. A cached result never speaks for
+//! a different signature.
+
+use parking_lot::Mutex;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+/// Entries kept by the caches the mesh and SEA use by default
+pub const DEFAULT_VERIFY_CACHE_ENTRIES: usize = 4096;
+
+/// How long results are kept by the caches the mesh and SEA use by default
+pub const DEFAULT_VERIFY_CACHE_AGE: Duration = Duration::from_secs(600);
+
+/// Snapshot of a cache, returned by [`VerifyCache::stats`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VerifyCacheStats {
+    /// Results currently cached
+    pub entries: usize,
+    /// Lookups answered from the cache
+    pub hits: u64,
+    /// Lookups that had to verify
+    pub misses: u64,
+    /// Results dropped for room or age
+    pub evicted: u64,
+}
+
+struct Entry {
+    valid: bool,
+    added: Instant,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Table {
+    entries: HashMap<[u8; 32], Entry>,
+    lru: BTreeMap<u64, [u8; 32]>, // last_used tick -> key, oldest first
+    tick: u64,
+    hits: u64,
+    misses: u64,
+    evicted: u64,
+}
+
+impl Table {
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn remove(&mut self, key: &[u8; 32]) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.lru.remove(&entry.last_used);
+        }
+    }
+}
+
+/// Bounded LRU table of verification results
+///
+/// A cache with `max_entries` of 0 keeps nothing, so every lookup misses.
+pub struct VerifyCache {
+    table: Mutex<Table>,
+    max_entries: usize,
+    max_age: Duration,
+}
+
+impl VerifyCache {
+    /// Keep at most `max_entries` results, each for at most `max_age`
+    pub fn new(max_entries: usize, max_age: Duration) -> Self {
+        Self {
+            table: Mutex::new(Table::default()),
+            max_entries,
+            max_age,
+        }
+    }
+
+    /// Key for a result depending on `parts`
+    ///
+    /// Each part is hashed with its length, so no two lists of parts share
+    /// a key.
+    pub fn key(parts: &[&[u8]]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        for part in parts {
+            hasher.update((part.len() as u64).to_be_bytes());
+            hasher.update(part);
+        }
+        hasher.finalize().into()
+    }
+
+    /// The cached result for `key`, if any
+    pub fn get(&self, key: &[u8; 32]) -> Option<bool> {
+        let mut table = self.table.lock();
+        let fresh = table.entries.get(key).map(|entry| entry.added.elapsed() < self.max_age);
+        match fresh {
+            Some(true) => {
+                let tick = table.next_tick();
+                table.hits += 1;
+                let entry = table.entries.get_mut(key)?;
+                let last_used = std::mem::replace(&mut entry.last_used, tick);
+                let valid = entry.valid;
+                table.lru.remove(&last_used);
+                table.lru.insert(tick, *key);
+                Some(valid)
+            }
+            Some(false) => {
+                table.remove(key);
+                table.evicted += 1;
+                table.misses += 1;
+                None
+            }
+            None => {
+                table.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Remember that the signature behind `key` is `valid` or not
+    pub fn insert(&self, key: [u8; 32], valid: bool) {
+        if self.max_entries == 0 {
+            return;
+        }
+        let mut table = self.table.lock();
+        table.remove(&key);
+        let tick = table.next_tick();
+        table.lru.insert(tick, key);
+        table.entries.insert(
+            key,
+            Entry {
+                valid,
+                added: Instant::now(),
+                last_used: tick,
+            },
+        );
+        while table.entries.len() > self.max_entries {
+            let Some((_, oldest)) = table.lru.pop_first() else {
+                break;
+            };
+            table.entries.remove(&oldest);
+            table.evicted += 1;
+        }
+    }
+
+    /// The cached result for `key`, or `verify()`'s, which is then cached
+    pub fn get_or_verify(&self, key: [u8; 32], verify: impl FnOnce() -> bool) -> bool {
+        if let Some(valid) = self.get(&key) {
+            return valid;
+        }
+        let valid = verify();
+        self.insert(key, valid);
+        valid
+    }
+
+    /// Drop every result, keeping the counters
+    pub fn clear(&self) {
+        let mut table = self.table.lock();
+        table.evicted += table.entries.len() as u64;
+        table.entries.clear();
+        table.lru.clear();
+    }
+
+    /// Size and hit counters
+    pub fn stats(&self) -> VerifyCacheStats {
+        let table = self.table.lock();
+        VerifyCacheStats {
+            entries: table.entries.len(),
+            hits: table.hits,
+            misses: table.misses,
+            evicted: table.evicted,
+        }
+    }
+}
+
+impl Default for VerifyCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_VERIFY_CACHE_ENTRIES, DEFAULT_VERIFY_CACHE_AGE)
+    }
+}
@@ -10,9 +10,21 @@
 //! ## Features
 //!
 //! - Direct peer-to-peer connections
-//! - NAT traversal using STUN/TURN servers
-//! - Automatic ICE candidate exchange
-//! - Data channel management
+//! - NAT traversal using STUN/TURN servers, with TURN credentials given in
+//!   code or the environment and an optional relay-only transport policy
+//! - Trickle ICE: candidates are exchanged over the DAM signaling channel as
+//!   they are gathered
+//! - Discovery through relays: managers announce their pid to each peer they
+//!   connect to, and again every `WebRTCOptions::announce_interval`, and
+//!   signaling for a pid that isn't a direct peer is broadcast for relays to
+//!   pass on
+//! - Rooms: announcements and offers carry `WebRTCOptions::room`, and only
+//!   peers in the same room connect directly
+//! - Encrypted signaling: with `WebRTCOptions::signaling_pair`, offers,
+//!   answers and candidates are encrypted for peers that announced an epub,
+//!   so relays don't see the addresses in them
+//! - Data channel management, with messages larger than
+//!   `WebRTCOptions::chunk_size` sent in chunks (see [`Reassembly`])
 //! - Connection lifecycle management
 //!
 //! ## Components
@@ -22,25 +34,32 @@
 //! - **WebRTCManager**: Manages all WebRTC connections and signaling
 
 use crate::core::GunCore;
-use crate::dam::Mesh;
+use crate::dam::{Frame, Mesh, PeerTransport, PEER_HI_EVENT, RTC_SIGNAL_EVENT};
+pub use crate::types::Transport;
 use crate::error::{GunError, GunResult};
+use crate::sea::KeyPair;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use tokio::sync::{mpsc, watch, Mutex, RwLock};
 use webrtc::api::interceptor_registry::register_default_interceptors;
 use webrtc::api::media_engine::MediaEngine;
 use webrtc::api::APIBuilder;
 use webrtc::data_channel::data_channel_init::RTCDataChannelInit;
 use webrtc::data_channel::data_channel_message::DataChannelMessage;
-use webrtc::ice_transport::ice_candidate::RTCIceCandidateInit;
+use webrtc::data_channel::RTCDataChannel;
+use webrtc::ice_transport::ice_candidate::{RTCIceCandidate, RTCIceCandidateInit};
 use webrtc::ice_transport::ice_credential_type::RTCIceCredentialType;
 use webrtc::ice_transport::ice_server::RTCIceServer;
 use webrtc::interceptor::registry::Registry;
 use webrtc::peer_connection::configuration::RTCConfiguration;
 use webrtc::peer_connection::peer_connection_state::RTCPeerConnectionState;
+use webrtc::peer_connection::policy::ice_transport_policy::RTCIceTransportPolicy;
 use webrtc::peer_connection::sdp::session_description::RTCSessionDescription;
+use webrtc::peer_connection::signaling_state::RTCSignalingState;
 use webrtc::peer_connection::RTCPeerConnection;
 
 /// Configuration options for WebRTC connections
@@ -75,13 +94,67 @@ pub struct WebRTCOptions {
     /// Maximum number of WebRTC connections (default 55, matching Gun.js)
     pub max_connections: usize,
 
-    /// Room name for peer discovery (optional)
+    /// Room for peer discovery (default none). Announcements and offers
+    /// carry it, and only peers in the same room, or both in none, connect
+    /// directly; the others still reach each other through the relays
     pub room: Option<String>,
 
+    /// How often our pid is announced again to every peer (default 30
+    /// seconds), so peers that missed it, or gave up on us, find us later.
+    /// Zero only announces on connect
+    pub announce_interval: Duration,
+
     /// Enable WebRTC (default true)
     pub enabled: bool,
+
+    /// Which ICE candidates connections use (default `All`). `Relay` only
+    /// uses TURN relays, e.g. to check that a TURN server works
+    pub ice_transport_policy: RTCIceTransportPolicy,
+
+    /// How long a connection may stay `Disconnected` before it is dropped
+    /// (default 5 seconds); `Failed` and `Closed` ones are dropped at once
+    pub disconnect_grace: Duration,
+
+    /// Messages longer than this many bytes go over the data channel in
+    /// chunks (default 16 KiB, which every SCTP implementation takes; 0 =
+    /// never). Separate from the mesh's own `chunk_size`
+    pub chunk_size: usize,
+
+    /// How long a message sent in chunks has to arrive whole (default 30
+    /// seconds). The channel may drop chunks, see `data_channel`
+    pub chunk_timeout: Duration,
+
+    /// How long a new connection has to open its data channel before it is
+    /// closed and removed, e.g. when the other side never answers our offer
+    /// (default 15 seconds)
+    pub offer_timeout: Duration,
+
+    /// Offers made to a pid in a row, the first included, before giving up
+    /// on it for `relay_only_cooldown` (default 3). Retries wait 1, 2, 4…
+    /// seconds after a timeout
+    pub offer_attempts: u32,
+
+    /// How long a pid that never answered stays relay-only: no offers are
+    /// made to it, though its own offers are answered (default 5 minutes)
+    pub relay_only_cooldown: Duration,
+
+    /// SEA key pair whose epub is announced to peers (default none). With
+    /// one, offers, answers and candidates for peers that announced an epub
+    /// too are encrypted with the ECDH secret of the two, so relays on the
+    /// path don't see the addresses in them; other peers get plaintext
+    pub signaling_pair: Option<KeyPair>,
 }
 
+/// Environment variable with the URL of a TURN server, see
+/// [`WebRTCOptions::with_turn_from_env`]
+pub const TURN_URL_ENV: &str = "GUN_TURN_URL";
+
+/// Environment variable with the TURN username
+pub const TURN_USERNAME_ENV: &str = "GUN_TURN_USERNAME";
+
+/// Environment variable with the TURN credential
+pub const TURN_CREDENTIAL_ENV: &str = "GUN_TURN_CREDENTIAL";
+
 impl Default for WebRTCOptions {
     fn default() -> Self {
         // Default STUN servers (matching Gun.js)
@@ -111,11 +184,128 @@ impl Default for WebRTCOptions {
             data_channel,
             max_connections: 55, // Matching Gun.js default
             room: None,
+            announce_interval: Duration::from_secs(30),
             enabled: true,
+            ice_transport_policy: RTCIceTransportPolicy::All,
+            disconnect_grace: Duration::from_secs(5),
+            chunk_size: 16 * 1024,
+            chunk_timeout: Duration::from_secs(30),
+            offer_timeout: Duration::from_secs(15),
+            offer_attempts: 3,
+            relay_only_cooldown: Duration::from_secs(300),
+            signaling_pair: None,
         }
     }
 }
 
+impl WebRTCOptions {
+    /// Add a TURN server, for peers behind NATs that STUN can't get through
+    /// (symmetric NATs, UDP-blocking firewalls)
+    ///
+    /// `url` is a `turn:` URL, or `turns:` for TURN over TLS.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use gun::webrtc::WebRTCOptions;
+    ///
+    /// let options = WebRTCOptions::default()
+    ///     .with_turn("turns:turn.example.com:5349?transport=tcp", "user", "secret");
+    /// assert_eq!(options.ice_servers.len(), 3);
+    /// ```
+    pub fn with_turn(mut self, url: &str, username: &str, credential: &str) -> Self {
+        self.ice_servers.push(RTCIceServer {
+            urls: vec![url.to_string()],
+            username: username.to_string(),
+            credential: credential.to_string(),
+            credential_type: RTCIceCredentialType::Password,
+        });
+        self
+    }
+
+    /// Add the TURN server given by the [`TURN_URL_ENV`],
+    /// [`TURN_USERNAME_ENV`] and [`TURN_CREDENTIAL_ENV`] environment
+    /// variables, if `GUN_TURN_URL` is set
+    ///
+    /// Keeps credentials out of code and examples.
+    pub fn with_turn_from_env(self) -> Self {
+        match std::env::var(TURN_URL_ENV) {
+            Ok(url) if !url.is_empty() => {
+                let username = std::env::var(TURN_USERNAME_ENV).unwrap_or_default();
+                let credential = std::env::var(TURN_CREDENTIAL_ENV).unwrap_or_default();
+                self.with_turn(&url, &username, &credential)
+            }
+            _ => self,
+        }
+    }
+
+    /// Only connect directly to peers in `room`, see [`room`](Self::room)
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use gun::webrtc::WebRTCOptions;
+    ///
+    /// let options = WebRTCOptions::default().with_room("doc-42");
+    /// assert_eq!(options.room.as_deref(), Some("doc-42"));
+    /// ```
+    pub fn with_room(mut self, room: &str) -> Self {
+        self.room = Some(room.to_string());
+        self
+    }
+
+    /// Encrypt signaling with `pair`, see [`signaling_pair`](Self::signaling_pair)
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// use gun::webrtc::WebRTCOptions;
+    ///
+    /// # async fn example() -> Result<(), gun::sea::SeaError> {
+    /// let options = WebRTCOptions::default().with_signaling_pair(gun::sea::pair().await?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_signaling_pair(mut self, pair: KeyPair) -> Self {
+        self.signaling_pair = Some(pair);
+        self
+    }
+
+    /// Set which ICE candidates connections use, see
+    /// [`ice_transport_policy`](Self::ice_transport_policy)
+    pub fn with_ice_transport_policy(mut self, policy: RTCIceTransportPolicy) -> Self {
+        self.ice_transport_policy = policy;
+        self
+    }
+
+    /// Check the ICE servers before a connection is made with them
+    ///
+    /// URLs must be `stun:`, `stuns:`, `turn:` or `turns:`, TURN servers need
+    /// a username and credential, and a relay-only policy needs a TURN server.
+    fn check_ice_servers(&self) -> GunResult<()> {
+        let mut turn = false;
+        for server in &self.ice_servers {
+            for url in &server.urls {
+                let scheme = url.split(':').next().unwrap_or_default();
+                match scheme {
+                    "stun" | "stuns" => {}
+                    "turn" | "turns" => {
+                        if server.username.is_empty() || server.credential.is_empty() {
+                            return Err(GunError::WebRTC(format!("TURN server {} needs a username and credential", url)));
+                        }
+                        turn = true;
+                    }
+                    _ => return Err(GunError::WebRTC(format!("Unsupported ICE server URL: {}", url))),
+                }
+            }
+        }
+        if self.ice_transport_policy == RTCIceTransportPolicy::Relay && !turn {
+            return Err(GunError::WebRTC("Relay-only ICE transport policy needs a TURN server".to_string()));
+        }
+        Ok(())
+    }
+}
+
 /// Represents a WebRTC peer connection
 ///
 /// Wraps an `RTCPeerConnection` and its associated data channel for bidirectional
@@ -127,10 +317,16 @@ impl Default for WebRTCOptions {
 /// `WebRTCPeer` is thread-safe and can be shared across threads using `Arc<WebRTCPeer>`.
 pub struct WebRTCPeer {
     pub peer_id: String,
+    pub role: WebRTCRole,
     pc: Arc<RTCPeerConnection>,
-    data_channel: Arc<webrtc::data_channel::RTCDataChannel>,
+    data_channel: Arc<watch::Sender<Option<Arc<RTCDataChannel>>>>, // Set while the channel is open
+    state: Arc<watch::Sender<RTCPeerConnectionState>>, // Latest connection state
+    chunk_size: usize,
+    next_chunk_id: AtomicU32,
     #[allow(dead_code)] // Used for internal message routing
     message_sender: tokio::sync::mpsc::UnboundedSender<String>,
+    // Remote candidates that arrived before the remote description
+    pending_candidates: Mutex<Vec<RTCIceCandidateInit>>,
 }
 
 impl std::fmt::Debug for WebRTCPeer {
@@ -141,12 +337,39 @@ impl std::fmt::Debug for WebRTCPeer {
     }
 }
 
+/// Which side of the offer/answer exchange a [`WebRTCPeer`] is on
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WebRTCRole {
+    /// Creates the "dc" data channel and sends the offer
+    Offerer,
+    /// Answers an offer and takes the data channel the offerer created
+    Answerer,
+}
+
 impl WebRTCPeer {
-    /// Create a new WebRTC peer connection
+    /// Create a new WebRTC peer connection that will send the offer
     pub async fn new(
         peer_id: String,
         config: &WebRTCOptions,
     ) -> GunResult<(Self, tokio::sync::mpsc::UnboundedReceiver<String>)> {
+        Self::with_role(peer_id, config, WebRTCRole::Offerer).await
+    }
+
+    /// Create a new WebRTC peer connection for one side of the exchange
+    ///
+    /// An offerer creates the data channel; an answerer waits for the
+    /// offerer's through `on_data_channel`. Either way the channel is only
+    /// used once open, and messages arriving on it come out of the returned
+    /// receiver. Fails with `GunError::WebRTC` on an unsupported ICE server
+    /// URL, a TURN server without credentials, or a relay-only policy
+    /// without a TURN server.
+    pub async fn with_role(
+        peer_id: String,
+        config: &WebRTCOptions,
+        role: WebRTCRole,
+    ) -> GunResult<(Self, tokio::sync::mpsc::UnboundedReceiver<String>)> {
+        config.check_ice_servers()?;
+
         // Create API with media engine
         let mut m = MediaEngine::default();
         m.register_default_codecs()
@@ -163,6 +386,7 @@ impl WebRTCPeer {
         // Create peer connection configuration
         let rtc_config = RTCConfiguration {
             ice_servers: config.ice_servers.clone(),
+            ice_transport_policy: config.ice_transport_policy,
             ..Default::default()
         };
 
@@ -171,74 +395,102 @@ impl WebRTCPeer {
             GunError::Network(format!("Failed to create RTCPeerConnection: {}", e))
         })?);
 
-        // Create data channel
-        let data_channel = pc
-            .create_data_channel("dc", Some(config.data_channel.clone()))
-            .await
-            .map_err(|e| GunError::Network(format!("Failed to create data channel: {}", e)))?;
-
         // Create message channel for receiving data
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
-
-        // Set up data channel message handler
-        let tx_clone = tx.clone();
-        data_channel.on_message(Box::new(move |msg: DataChannelMessage| {
-            if msg.is_string {
-                if let Ok(text) = String::from_utf8(msg.data.to_vec()) {
-                    let _ = tx_clone.send(text);
-                }
-            } else {
-                // Binary data - convert to string if possible
-                if let Ok(text) = String::from_utf8(msg.data.to_vec()) {
-                    let _ = tx_clone.send(text);
-                }
+        let open = Arc::new(watch::channel(None).0);
+
+        match role {
+            WebRTCRole::Offerer => {
+                let data_channel = pc
+                    .create_data_channel("dc", Some(config.data_channel.clone()))
+                    .await
+                    .map_err(|e| GunError::Network(format!("Failed to create data channel: {}", e)))?;
+                wire_data_channel(&data_channel, tx.clone(), open.clone(), config.chunk_timeout);
             }
-            Box::pin(async {})
-        }));
+            WebRTCRole::Answerer => {
+                let (tx, open, peer_id, chunk_timeout) = (tx.clone(), open.clone(), peer_id.clone(), config.chunk_timeout);
+                pc.on_data_channel(Box::new(move |data_channel: Arc<RTCDataChannel>| {
+                    tracing::debug!(peer_id = %peer_id, label = %data_channel.label(), "Accepted data channel");
+                    wire_data_channel(&data_channel, tx.clone(), open.clone(), chunk_timeout);
+                    Box::pin(async {})
+                }));
+            }
+        }
 
         // Set up connection state change handler
         let peer_id_clone = peer_id.clone();
+        let state = Arc::new(watch::channel(RTCPeerConnectionState::New).0);
+        let state_clone = state.clone();
         pc.on_peer_connection_state_change(Box::new(move |s: RTCPeerConnectionState| {
             tracing::info!("WebRTC peer {} connection state: {:?}", peer_id_clone, s);
+            state_clone.send_replace(s);
             Box::pin(async {})
         }));
 
-        // Set up ICE candidate handler
-        let peer_id_for_candidates = peer_id.clone();
-        pc.on_ice_candidate(Box::new(
-            move |candidate: Option<webrtc::ice_transport::ice_candidate::RTCIceCandidate>| {
-                let peer_id_clone = peer_id_for_candidates.clone();
-                Box::pin(async move {
-                    if let Some(candidate) = candidate {
-                        tracing::debug!(
-                            "ICE candidate for peer {}: {:?}",
-                            peer_id_clone,
-                            candidate
-                        );
-                        // ICE candidates will be sent via DAM protocol signaling
-                    }
-                })
-            },
-        ));
-
         Ok((
             Self {
                 peer_id,
+                role,
                 pc,
-                data_channel,
+                data_channel: open,
+                state,
+                chunk_size: config.chunk_size,
+                next_chunk_id: AtomicU32::new(0),
                 message_sender: tx,
+                pending_candidates: Mutex::new(Vec::new()),
             },
             rx,
         ))
     }
 
+    /// Hand each local ICE candidate to `candidates` as it is gathered
+    ///
+    /// Gathering starts when the local description is set, so call this
+    /// before [`create_offer`](Self::create_offer) or
+    /// [`create_answer`](Self::create_answer).
+    pub fn on_local_candidate(&self, candidates: mpsc::UnboundedSender<RTCIceCandidateInit>) {
+        let peer_id = self.peer_id.clone();
+        self.pc.on_ice_candidate(Box::new(move |candidate: Option<RTCIceCandidate>| {
+            // `None` marks the end of gathering
+            if let Some(candidate) = candidate {
+                match candidate.to_json() {
+                    Ok(init) => {
+                        tracing::trace!(peer_id = %peer_id, candidate = %init.candidate, "Gathered ICE candidate");
+                        let _ = candidates.send(init);
+                    }
+                    Err(e) => tracing::warn!(peer_id = %peer_id, error = %e, "Can't serialize ICE candidate"),
+                }
+            }
+            Box::pin(async {})
+        }));
+    }
+
+    /// The data channel, once it is open
+    pub fn data_channel(&self) -> Option<Arc<RTCDataChannel>> {
+        self.data_channel.borrow().clone()
+    }
+
+    /// Whether the data channel is open
+    pub fn is_open(&self) -> bool {
+        self.data_channel.borrow().is_some()
+    }
+
     /// Send a message through the data channel
+    ///
+    /// Fails until the channel is open.
     pub async fn send(&self, message: &str) -> GunResult<()> {
-        let data: bytes::Bytes = message.as_bytes().to_vec().into();
-        self.data_channel
-            .send(&data)
-            .await
-            .map_err(|e| GunError::Network(format!("Failed to send WebRTC message: {}", e)))?;
+        let data_channel = self
+            .data_channel()
+            .ok_or_else(|| GunError::Network(format!("Data channel to {} isn't open", self.peer_id)))?;
+        let id = self.next_chunk_id.fetch_add(1, Ordering::Relaxed);
+        let frames = chunk_frames(message, self.chunk_size, id)
+            .unwrap_or_else(|| vec![message.as_bytes().to_vec().into()]);
+        for data in &frames {
+            data_channel
+                .send(data)
+                .await
+                .map_err(|e| GunError::Network(format!("Failed to send WebRTC message: {}", e)))?;
+        }
         Ok(())
     }
 
@@ -275,16 +527,33 @@ impl WebRTCPeer {
     }
 
     /// Set remote description (from offer or answer)
+    ///
+    /// Candidates that arrived before it are added now.
     pub async fn set_remote_description(&self, desc: RTCSessionDescription) -> GunResult<()> {
+        let mut pending = self.pending_candidates.lock().await;
         self.pc
             .set_remote_description(desc)
             .await
             .map_err(|e| GunError::Network(format!("Failed to set remote description: {}", e)))?;
+        for candidate in pending.drain(..) {
+            self.pc
+                .add_ice_candidate(candidate)
+                .await
+                .map_err(|e| GunError::Network(format!("Failed to add ICE candidate: {}", e)))?;
+        }
         Ok(())
     }
 
     /// Add ICE candidate
+    ///
+    /// Before the remote description is set the candidate is kept, and added
+    /// along with it.
     pub async fn add_ice_candidate(&self, candidate: RTCIceCandidateInit) -> GunResult<()> {
+        let mut pending = self.pending_candidates.lock().await;
+        if self.pc.remote_description().await.is_none() {
+            pending.push(candidate);
+            return Ok(());
+        }
         self.pc
             .add_ice_candidate(candidate)
             .await
@@ -294,10 +563,12 @@ impl WebRTCPeer {
 
     /// Close the peer connection
     pub async fn close(&self) -> GunResult<()> {
-        self.data_channel
-            .close()
-            .await
-            .map_err(|e| GunError::Network(format!("Failed to close data channel: {}", e)))?;
+        if let Some(data_channel) = self.data_channel.send_replace(None) {
+            data_channel
+                .close()
+                .await
+                .map_err(|e| GunError::Network(format!("Failed to close data channel: {}", e)))?;
+        }
         self.pc
             .close()
             .await
@@ -309,6 +580,192 @@ impl WebRTCPeer {
     pub async fn connection_state(&self) -> RTCPeerConnectionState {
         self.pc.connection_state()
     }
+
+    /// Where the offer/answer exchange stands: `HaveLocalOffer` while an
+    /// offer of ours waits for its answer
+    pub fn signaling_state(&self) -> RTCSignalingState {
+        self.pc.signaling_state()
+    }
+}
+
+/// Pass messages arriving on `data_channel` to `messages`, and publish it
+/// in `open` while it is open
+///
+/// Chunked messages are passed on once whole.
+fn wire_data_channel(
+    data_channel: &Arc<RTCDataChannel>,
+    messages: mpsc::UnboundedSender<String>,
+    open: Arc<watch::Sender<Option<Arc<RTCDataChannel>>>>,
+    chunk_timeout: Duration,
+) {
+    let mut reassembly = Reassembly::new(chunk_timeout);
+    data_channel.on_message(Box::new(move |msg: DataChannelMessage| {
+        if let Some(text) = reassembly.add(&msg.data) {
+            let _ = messages.send(text);
+        }
+        Box::pin(async {})
+    }));
+    // Weak, or the channel's own handler would keep it alive
+    let weak: Weak<RTCDataChannel> = Arc::downgrade(data_channel);
+    let opened = open.clone();
+    data_channel.on_open(Box::new(move || {
+        if let Some(data_channel) = weak.upgrade() {
+            opened.send_replace(Some(data_channel));
+        }
+        Box::pin(async {})
+    }));
+    data_channel.on_close(Box::new(move || {
+        open.send_replace(None);
+        Box::pin(async {})
+    }));
+}
+
+/// First byte of a chunk frame; never the first byte of UTF-8 text
+const CHUNK_TAG: u8 = 0xFF;
+
+/// Bytes of a chunk frame before its data: tag, then `id`, `seq`, `of` and
+/// the data length as big-endian `u32`s
+pub const CHUNK_HEADER_LEN: usize = 17;
+
+/// Most bytes of unfinished chunked messages kept per data channel, the
+/// mesh's default `max_message_size`
+const MAX_REASSEMBLY_BYTES: usize = 90_000_000;
+
+/// Split a message longer than `chunk_size` bytes into chunk frames for a
+/// data channel
+///
+/// Returns `None` for messages that fit (or a `chunk_size` of 0); those go
+/// as they are. Each frame is [`CHUNK_HEADER_LEN`] bytes of header, then
+/// up to `chunk_size` bytes of the message. `id` tells the messages of one
+/// channel apart.
+///
+/// # Example
+///
+/// ```rust
+/// use gun::webrtc::{chunk_frames, Reassembly};
+/// use std::time::Duration;
+///
+/// let message = "x".repeat(100);
+/// let frames = chunk_frames(&message, 40, 7).unwrap();
+/// assert_eq!(frames.len(), 3);
+///
+/// let mut reassembly = Reassembly::new(Duration::from_secs(30));
+/// assert_eq!(reassembly.add(&frames[2]), None);
+/// assert_eq!(reassembly.add(&frames[0]), None);
+/// assert_eq!(reassembly.add(&frames[1]), Some(message));
+/// ```
+pub fn chunk_frames(message: &str, chunk_size: usize, id: u32) -> Option<Vec<bytes::Bytes>> {
+    if chunk_size == 0 || message.len() <= chunk_size {
+        return None;
+    }
+    let pieces: Vec<&[u8]> = message.as_bytes().chunks(chunk_size).collect();
+    let of = pieces.len() as u32;
+    Some(
+        pieces
+            .into_iter()
+            .enumerate()
+            .map(|(seq, data)| {
+                let mut frame = Vec::with_capacity(CHUNK_HEADER_LEN + data.len());
+                frame.push(CHUNK_TAG);
+                for field in [id, seq as u32, of, data.len() as u32] {
+                    frame.extend_from_slice(&field.to_be_bytes());
+                }
+                frame.extend_from_slice(data);
+                frame.into()
+            })
+            .collect(),
+    )
+}
+
+/// Puts the messages of one data channel back together from their chunks
+///
+/// Chunks may come in any order, since the channel is unordered, and some
+/// may never come: a message still missing chunks after the timeout is
+/// dropped. Frames that aren't chunks are whole messages.
+pub struct Reassembly {
+    timeout: Duration,
+    partial: HashMap<u32, Partial>, // by message id
+    bytes: usize,
+}
+
+/// A message of which some chunks have arrived
+struct Partial {
+    of: u32,
+    parts: BTreeMap<u32, Vec<u8>>,
+    bytes: usize,
+    started: std::time::Instant,
+}
+
+impl Reassembly {
+    /// Give messages `timeout` to arrive whole
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout, partial: HashMap::new(), bytes: 0 }
+    }
+
+    /// Take in one frame from the channel
+    ///
+    /// Returns the message it completes, if any. Malformed chunks and
+    /// messages that aren't UTF-8 are dropped.
+    pub fn add(&mut self, frame: &[u8]) -> Option<String> {
+        if frame.first() != Some(&CHUNK_TAG) {
+            return String::from_utf8(frame.to_vec()).ok();
+        }
+        let field = |i: usize| frame.get(1 + 4 * i..5 + 4 * i).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+        let (Some(id), Some(seq), Some(of), Some(len)) = (field(0), field(1), field(2), field(3)) else {
+            tracing::debug!(len = frame.len(), "Truncated WebRTC chunk header");
+            return None;
+        };
+        let data = &frame[CHUNK_HEADER_LEN..];
+        if data.len() != len as usize || seq >= of {
+            tracing::debug!(chunk_id = id, seq, of, "Malformed WebRTC chunk");
+            return None;
+        }
+
+        self.expire();
+        if self.bytes + data.len() > MAX_REASSEMBLY_BYTES {
+            tracing::debug!(chunk_id = id, "Chunked WebRTC messages too big, dropping them");
+            self.partial.clear();
+            self.bytes = 0;
+            return None;
+        }
+        let partial = self.partial.entry(id).or_insert_with(|| Partial {
+            of,
+            parts: BTreeMap::new(),
+            bytes: 0,
+            started: std::time::Instant::now(),
+        });
+        if partial.of != of {
+            tracing::debug!(chunk_id = id, "Chunk count changed mid-message");
+            return None;
+        }
+        if let std::collections::btree_map::Entry::Vacant(part) = partial.parts.entry(seq) {
+            partial.bytes += data.len();
+            self.bytes += data.len();
+            part.insert(data.to_vec());
+        }
+        if partial.parts.len() as u32 != of {
+            return None;
+        }
+
+        let partial = self.partial.remove(&id)?;
+        self.bytes -= partial.bytes;
+        String::from_utf8(partial.parts.into_values().flatten().collect()).ok()
+    }
+
+    /// Drop the messages that ran out of time
+    fn expire(&mut self) {
+        let timeout = self.timeout;
+        let mut expired = 0;
+        self.partial.retain(|id, partial| {
+            let keep = partial.started.elapsed() < timeout;
+            if !keep {
+                tracing::debug!(chunk_id = *id, received = partial.parts.len(), of = partial.of, "Chunked WebRTC message timed out");
+                expired += partial.bytes;
+            }
+            keep
+        });
+        self.bytes -= expired;
+    }
 }
 
 /// WebRTC manager - handles all WebRTC peer connections
@@ -321,37 +778,127 @@ impl WebRTCPeer {
 /// # Example
 ///
 /// ```rust,no_run
-/// use gun::webrtc::{WebRTCManager, WebRTCOptions};
-/// use gun::core::GunCore;
-/// use gun::dam::Mesh;
-/// use std::sync::Arc;
+/// use chia_bls::SecretKey;
+/// use gun::{Gun, GunOptions};
 ///
-/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
-/// let core = Arc::new(GunCore::new());
-/// let mesh = Arc::new(Mesh::new(core.clone(), /* ... */));
-/// let options = WebRTCOptions::default();
-/// let manager = Arc::new(WebRTCManager::new(core, mesh, options));
+/// # async fn example(peer_pid: &str, frame: &str) -> Result<(), Box<dyn std::error::Error>> {
+/// let key = SecretKey::from_seed(&[0u8; 32]);
+/// let gun = Gun::with_options(key.clone(), key.public_key(), GunOptions::with_relay("wss://relay.example.com/gun")).await?;
+/// let manager = gun.webrtc().expect("WebRTC is on by default");
+/// if manager.is_open(peer_pid).await {
+///     manager.send_message(peer_pid, frame).await?;
+/// }
 /// # Ok(())
 /// # }
 /// ```
 pub struct WebRTCManager {
-    #[allow(dead_code)] // Used internally by other modules
     core: Arc<GunCore>,
     mesh: Arc<Mesh>,
     options: WebRTCOptions,
     peers: Arc<RwLock<HashMap<String, Arc<WebRTCPeer>>>>, // Store in Arc to allow cloning
+    // Candidates from peers whose offer hasn't arrived yet, by pid
+    early_candidates: parking_lot::Mutex<HashMap<String, Vec<RTCIceCandidateInit>>>,
+    // One offer or answer at a time, so colliding offers are seen whole
+    negotiation: Mutex<()>,
+    // Offers that timed out, by pid (see WebRTCOptions::offer_attempts)
+    offers: Arc<parking_lot::Mutex<HashMap<String, OfferAttempts>>>,
+    // The signaling task's queue, once start_signaling runs; retries go there
+    signals: Arc<parking_lot::Mutex<Option<mpsc::UnboundedSender<Signal>>>>,
+    sealer: Sealer,
     pub(crate) pid: String, // Public for testing purposes
 }
 
+/// Candidates kept per peer while waiting for its offer
+const MAX_EARLY_CANDIDATES: usize = 32;
+
+/// Wait before the first retry of an offer that timed out; doubled for
+/// each retry after it
+const OFFER_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Longest wait before retrying an offer
+const MAX_OFFER_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Offers to one pid that timed out, see [`WebRTCManager::is_relay_only`]
+#[derive(Default)]
+struct OfferAttempts {
+    timed_out: u32,
+    relay_only_until: Option<std::time::Instant>,
+}
+
+/// Event emitted when a WebRTC connection is dropped as lost
+///
+/// The event data is `{"pid": pid, "reason": ...}`, the reason being
+/// `"failed"`, `"closed"`, `"disconnected"` (for longer than
+/// `WebRTCOptions::disconnect_grace`) or `"channel_closed"`.
+pub const WEBRTC_PEER_LOST_EVENT: &str = "webrtc_peer_lost";
+
+/// Event emitted when the data channel to a peer opens
+///
+/// Emitted as `webrtc_connected:{pid}`, with data `{"pid": pid}`.
+pub const WEBRTC_CONNECTED_EVENT: &str = "webrtc_connected";
+
+/// Event emitted when an open WebRTC connection ends
+///
+/// Emitted as `webrtc_disconnected:{pid}`, with data `{"pid": pid, "reason": ...}`;
+/// the reasons are those of [`WEBRTC_PEER_LOST_EVENT`]. Messages for the
+/// peer go through the relays again.
+pub const WEBRTC_DISCONNECTED_EVENT: &str = "webrtc_disconnected";
+
+/// Event emitted for every peer whose direct connection opens, ends or
+/// can't be made, see [`PeerEvent`]
+///
+/// The event data is `{"pid": pid, "state": ...}`, the state being
+/// `"connected"`, `"relay_only"` (the connection ended) or `"failed"` (it
+/// was lost before its data channel opened, or didn't open it within
+/// `WebRTCOptions::offer_timeout`: reason `"timeout"`), the last two with a
+/// `"reason"`.
+pub const WEBRTC_PEER_EVENT: &str = "webrtc_peer";
+
+/// What happened to the direct connection to a peer, see `Gun::on_peer_event`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PeerEvent {
+    /// The data channel opened; messages for the peer now go straight to it
+    WebRTCConnected { pid: String },
+    /// The connection was lost, or timed out, before its data channel opened
+    WebRTCFailed { pid: String, reason: String },
+    /// The direct connection ended; messages go through the relays until the
+    /// two connect again
+    RelayOnly { pid: String },
+}
+
+impl PeerEvent {
+    /// Read the data of a [`WEBRTC_PEER_EVENT`]
+    pub fn from_event_data(data: &Value) -> Option<Self> {
+        let pid = data.get("pid")?.as_str()?.to_string();
+        let reason = || data.get("reason").and_then(|v| v.as_str()).unwrap_or_default().to_string();
+        match data.get("state")?.as_str()? {
+            "connected" => Some(PeerEvent::WebRTCConnected { pid }),
+            "failed" => Some(PeerEvent::WebRTCFailed { pid, reason: reason() }),
+            "relay_only" => Some(PeerEvent::RelayOnly { pid }),
+            _ => None,
+        }
+    }
+}
+
 impl WebRTCManager {
     /// Create a new WebRTC manager
     pub fn new(core: Arc<GunCore>, mesh: Arc<Mesh>, options: WebRTCOptions) -> Self {
-        let pid = core.random_id(9);
+        // Signaling addresses peers by DAM pid, so ours must be the mesh's
+        let pid = mesh.pid.clone();
+        let sealer = Sealer {
+            pair: options.signaling_pair.clone().filter(|pair| pair.epub_key.is_some()),
+            keys: Arc::new(parking_lot::Mutex::new(HashMap::new())),
+        };
         Self {
             core,
             mesh,
             options,
             peers: Arc::new(RwLock::new(HashMap::new())),
+            early_candidates: parking_lot::Mutex::new(HashMap::new()),
+            negotiation: Mutex::new(()),
+            offers: Arc::new(parking_lot::Mutex::new(HashMap::new())),
+            signals: Arc::new(parking_lot::Mutex::new(None)),
+            sealer,
             pid,
         }
     }
@@ -361,10 +908,329 @@ impl WebRTCManager {
         &self.pid
     }
 
+    /// Handle the signaling messages the mesh receives, and announce this
+    /// manager to peers
+    ///
+    /// Listens for [`RTC_SIGNAL_EVENT`] and passes the messages to
+    /// [`handle_rtc_message`](Self::handle_rtc_message) one at a time, in the
+    /// order they arrived. Our pid is announced (`{"ok": {"rtc": {"id": pid}}}`,
+    /// with `"room"` if we have one) to the peers connected now, every
+    /// `announce_interval` after that and, on [`PEER_HI_EVENT`], to each peer
+    /// that connects later; relays pass it on to their other peers. Stops once
+    /// the manager is dropped or the core is cancelled.
+    pub fn start_signaling(manager: &Arc<Self>) {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Signal>();
+        *manager.signals.lock() = Some(tx.clone());
+        let core = manager.core.clone();
+        let (messages, announce) = (tx.clone(), tx.clone());
+        let listener = core.events.on(
+            RTC_SIGNAL_EVENT,
+            Box::new(move |event: &crate::events::Event| {
+                let _ = messages.send(Signal::Message(event.data.clone()));
+            }),
+        );
+        let hi_listener = core.events.on(
+            PEER_HI_EVENT,
+            Box::new(move |event: &crate::events::Event| {
+                if let Some(pid) = event.data.get("pid").and_then(|v| v.as_str()) {
+                    let _ = tx.send(Signal::Hi(pid.to_string()));
+                }
+            }),
+        );
+        let interval = manager.options.announce_interval;
+        if !interval.is_zero() {
+            // Ends with the signaling task, which owns the receiver
+            core.spawn_until_cancelled(async move {
+                loop {
+                    tokio::time::sleep(interval).await;
+                    if announce.send(Signal::Announce).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        let (mesh, announcement) = (manager.mesh.clone(), manager.announcement());
+        let manager: Weak<Self> = Arc::downgrade(manager);
+        core.clone().spawn(async move {
+            if let Err(e) = mesh.say(&announcement, None).await {
+                tracing::debug!(error = %e, "Failed to announce WebRTC peer");
+            }
+            loop {
+                let signal = tokio::select! {
+                    signal = rx.recv() => signal,
+                    _ = core.cancelled() => None,
+                };
+                let Some(signal) = signal else {
+                    break;
+                };
+                let Some(manager) = manager.upgrade() else {
+                    break;
+                };
+                let result = match signal {
+                    Signal::Message(msg) => manager.handle_rtc_message(&msg).await,
+                    // Without `to`: relays pass it on to everyone
+                    Signal::Hi(pid) => manager.mesh.send_to_pid(&pid, &manager.announcement()).await,
+                    Signal::Retry(pid) => manager.initiate_connection(&pid).await,
+                    Signal::Announce => manager.mesh.say(&manager.announcement(), None).await,
+                };
+                if let Err(e) = result {
+                    tracing::debug!(error = %e, "Error handling RTC signaling message");
+                }
+            }
+            core.events.off(RTC_SIGNAL_EVENT, listener);
+            core.events.off(PEER_HI_EVENT, hi_listener);
+        });
+    }
+
+    /// The message that tells peers about us: our pid, our room and the
+    /// epub to encrypt signaling for us with
+    ///
+    /// Each one has a fresh `nonce`: message IDs are content hashes, so a
+    /// peer would drop a repeat of an announcement it has already seen.
+    fn announcement(&self) -> Value {
+        let mut msg = serde_json::json!({ "ok": { "rtc": { "id": self.pid, "nonce": self.core.random_id(9) } } });
+        if let Some(room) = &self.options.room {
+            msg["ok"]["rtc"]["room"] = room.as_str().into();
+        }
+        if let Some(epub) = self.sealer.pair.as_ref().and_then(|pair| pair.epub_key.as_deref()) {
+            msg["ok"]["rtc"]["epub"] = epub.into();
+        }
+        msg
+    }
+
+    /// Whether signaling tagged with `room` comes from our room
+    ///
+    /// Untagged signaling is from a peer in no room, like Gun.js peers
+    /// without `opt.rtc.room`.
+    fn in_room(&self, rtc: &Value) -> bool {
+        rtc.get("room").and_then(|v| v.as_str()) == self.options.room.as_deref()
+    }
+
+    /// Whether the data channel to the peer with DAM pid `pid` is open
+    pub async fn is_open(&self, pid: &str) -> bool {
+        self.peers.read().await.get(pid).is_some_and(|peer| peer.is_open())
+    }
+
+    /// Connection state of the WebRTC connection to the peer with DAM pid
+    /// `pid`, if there is one
+    pub async fn peer_state(&self, pid: &str) -> Option<RTCPeerConnectionState> {
+        let peer = self.peers.read().await.get(pid).cloned();
+        match peer {
+            Some(peer) => Some(peer.connection_state().await),
+            None => None,
+        }
+    }
+
+    /// Whether we gave up offering the peer with DAM pid `pid` connections
+    /// for now, see `WebRTCOptions::offer_attempts`
+    pub fn is_relay_only(&self, pid: &str) -> bool {
+        self.offers
+            .lock()
+            .get(pid)
+            .and_then(|offer| offer.relay_only_until)
+            .is_some_and(|until| std::time::Instant::now() < until)
+    }
+
+    /// Connection state of every WebRTC connection, by DAM pid
+    pub async fn peer_states(&self) -> HashMap<String, RTCPeerConnectionState> {
+        let peers: Vec<(String, Arc<WebRTCPeer>)> =
+            self.peers.read().await.iter().map(|(pid, peer)| (pid.clone(), peer.clone())).collect();
+        let mut states = HashMap::with_capacity(peers.len());
+        for (pid, peer) in peers {
+            states.insert(pid, peer.connection_state().await);
+        }
+        states
+    }
+
+    /// Drop `peer` once its connection is lost, and tell the application
+    /// how it went
+    ///
+    /// A `Failed` or `Closed` connection, or a data channel that closes after
+    /// opening, is lost at once; a `Disconnected` one if it doesn't recover
+    /// within `disconnect_grace`. The peer is closed and removed, which frees
+    /// its slot, [`WEBRTC_PEER_LOST_EVENT`] is emitted and the pid is told
+    /// about us again, so the two can connect anew.
+    ///
+    /// [`WEBRTC_CONNECTED_EVENT`] fires when the data channel opens and
+    /// [`WEBRTC_DISCONNECTED_EVENT`] when an open connection ends, however it
+    /// was closed; [`WEBRTC_PEER_EVENT`] follows both, and a connection lost
+    /// before it ever opened.
+    fn watch(&self, peer: &Arc<WebRTCPeer>) {
+        let mut states = peer.state.subscribe();
+        let mut open = peer.data_channel.subscribe();
+        let (weak_peer, pid) = (Arc::downgrade(peer), peer.peer_id.clone());
+        let (peers, core, mesh) = (self.peers.clone(), self.core.clone(), self.mesh.clone());
+        let (announcement, grace) = (self.announcement(), self.options.disconnect_grace);
+        self.core.spawn_until_cancelled(async move {
+            let emit = |event_type: String, data: Value| core.events.emit(&crate::events::Event { event_type, data });
+            let mut was_open = false;
+            let reason = loop {
+                if !was_open && open.borrow_and_update().is_some() {
+                    was_open = true;
+                    tracing::info!(peer_id = %pid, "WebRTC data channel open");
+                    mesh.add_transport(&pid, data_channel_transport(weak_peer.clone())).await;
+                    emit(format!("{}:{}", WEBRTC_CONNECTED_EVENT, pid), serde_json::json!({ "pid": pid }));
+                    emit(WEBRTC_PEER_EVENT.to_string(), serde_json::json!({ "pid": pid, "state": "connected" }));
+                }
+                tokio::select! {
+                    changed = states.changed() => {
+                        // The peer is gone, closed by whoever dropped it
+                        if changed.is_err() {
+                            break "closed";
+                        }
+                        let state = *states.borrow_and_update();
+                        match state {
+                            RTCPeerConnectionState::Failed => break "failed",
+                            RTCPeerConnectionState::Closed => break "closed",
+                            RTCPeerConnectionState::Disconnected => {
+                                let recovered = states.wait_for(|s| *s != RTCPeerConnectionState::Disconnected);
+                                if tokio::time::timeout(grace, recovered).await.is_err() {
+                                    break "disconnected";
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                    changed = open.changed() => {
+                        if changed.is_err() {
+                            break "closed";
+                        }
+                        if was_open && open.borrow().is_none() {
+                            break "channel_closed";
+                        }
+                    }
+                }
+            };
+            if was_open {
+                mesh.remove_transport(&pid).await;
+                emit(
+                    format!("{}:{}", WEBRTC_DISCONNECTED_EVENT, pid),
+                    serde_json::json!({ "pid": pid, "reason": reason }),
+                );
+                emit(WEBRTC_PEER_EVENT.to_string(), serde_json::json!({ "pid": pid, "state": "relay_only", "reason": reason }));
+            }
+
+            // Unless it was already removed, or replaced by a new connection
+            let Some(peer) = weak_peer.upgrade() else {
+                return;
+            };
+            let removed = {
+                let mut peers = peers.write().await;
+                match peers.get(&pid) {
+                    Some(current) if Arc::ptr_eq(current, &peer) => peers.remove(&pid),
+                    _ => None,
+                }
+            };
+            if removed.is_none() {
+                return;
+            }
+            tracing::info!(peer_id = %pid, reason = %reason, "WebRTC connection lost");
+            if let Err(e) = peer.close().await {
+                tracing::debug!(peer_id = %pid, error = %e, "Error closing lost WebRTC connection");
+            }
+            emit(WEBRTC_PEER_LOST_EVENT.to_string(), serde_json::json!({ "pid": pid, "reason": reason }));
+            if !was_open {
+                emit(WEBRTC_PEER_EVENT.to_string(), serde_json::json!({ "pid": pid, "state": "failed", "reason": reason }));
+            }
+            if let Err(e) = signal(&mesh, &pid, &announcement).await {
+                tracing::debug!(peer_id = %pid, error = %e, "Failed to announce after losing WebRTC connection");
+            }
+        });
+    }
+
+    /// Close `peer` if its data channel isn't open within `offer_timeout`
+    ///
+    /// Otherwise a peer that never answers would hold a half-open connection,
+    /// with its sockets and a slot under `max_connections`, for good. An
+    /// offer of ours is made again after a backoff, through the signaling
+    /// task, until `offer_attempts` offers timed out in a row; the pid is then
+    /// relay-only for `relay_only_cooldown`. Each timeout emits a `"failed"`
+    /// [`WEBRTC_PEER_EVENT`] with the reason `"timeout"`.
+    fn expire(&self, peer: &Arc<WebRTCPeer>) {
+        let mut open = peer.data_channel.subscribe();
+        let (weak_peer, pid, role) = (Arc::downgrade(peer), peer.peer_id.clone(), peer.role);
+        let (peers, offers, signals, core) =
+            (self.peers.clone(), self.offers.clone(), self.signals.clone(), self.core.clone());
+        let (timeout, attempts, cooldown) =
+            (self.options.offer_timeout, self.options.offer_attempts, self.options.relay_only_cooldown);
+        self.core.spawn_until_cancelled(async move {
+            let opened = matches!(tokio::time::timeout(timeout, open.wait_for(|dc| dc.is_some())).await, Ok(Ok(_)));
+            if opened {
+                if role == WebRTCRole::Offerer {
+                    offers.lock().remove(&pid);
+                }
+                return;
+            }
+
+            // Unless it was closed, lost or replaced meanwhile
+            let Some(peer) = weak_peer.upgrade() else {
+                return;
+            };
+            let removed = {
+                let mut peers = peers.write().await;
+                match peers.get(&pid) {
+                    Some(current) if Arc::ptr_eq(current, &peer) => peers.remove(&pid),
+                    _ => None,
+                }
+            };
+            if removed.is_none() {
+                return;
+            }
+            tracing::info!(peer_id = %pid, role = ?role, timeout = ?timeout, "WebRTC negotiation timed out");
+            if let Err(e) = peer.close().await {
+                tracing::debug!(peer_id = %pid, error = %e, "Error closing timed out WebRTC connection");
+            }
+            core.events.emit(&crate::events::Event {
+                event_type: WEBRTC_PEER_EVENT.to_string(),
+                data: serde_json::json!({ "pid": pid, "state": "failed", "reason": "timeout" }),
+            });
+            if role != WebRTCRole::Offerer {
+                return;
+            }
+
+            let retry = {
+                let mut offers = offers.lock();
+                let offer = offers.entry(pid.clone()).or_default();
+                offer.timed_out += 1;
+                if offer.timed_out >= attempts {
+                    *offer = OfferAttempts {
+                        timed_out: 0,
+                        relay_only_until: Some(std::time::Instant::now() + cooldown),
+                    };
+                    None
+                } else {
+                    let backoff = OFFER_BACKOFF.checked_mul(1 << (offer.timed_out - 1).min(16));
+                    Some(backoff.unwrap_or(MAX_OFFER_BACKOFF).min(MAX_OFFER_BACKOFF))
+                }
+            };
+            match retry {
+                Some(backoff) => {
+                    tokio::time::sleep(backoff).await;
+                    let signals = signals.lock().clone();
+                    if let Some(signals) = signals {
+                        let _ = signals.send(Signal::Retry(pid));
+                    }
+                }
+                None => {
+                    tracing::info!(peer_id = %pid, cooldown = ?cooldown, "No answer to WebRTC offers, using relays only");
+                }
+            }
+        });
+    }
+
     /// Handle incoming RTC signaling message from DAM protocol
     /// This is called when we receive an RTC message through the mesh
+    ///
+    /// The signaling is the message's `ok.rtc`, or `rtc` in a `dam: "rtc"`
+    /// message. Signaling whose `to` is another pid was broadcast for a peer
+    /// behind a relay and is ignored, as are announcements and offers from
+    /// another room. Encrypted signaling (`sea`) is decrypted first; it, and
+    /// any signaling with an `epub`, is dropped unless the message was signed
+    /// by the key that first gave us the pid's epub. Offers that cross ours
+    /// are settled as in perfect negotiation, the peer with the higher pid
+    /// giving way.
     pub async fn handle_rtc_message(&self, msg: &Value) -> GunResult<()> {
-        let rtc = match msg.get("ok").and_then(|v| v.get("rtc")) {
+        let rtc = match rtc_signal(msg) {
             Some(rtc) => rtc,
             None => return Ok(()),
         };
@@ -373,8 +1239,27 @@ impl WebRTCManager {
             .and_then(|v| v.as_str())
             .ok_or_else(|| GunError::InvalidData("Missing RTC peer ID".to_string()))?;
 
-        // Don't process our own messages
-        if peer_id == self.pid {
+        // Don't process our own messages, or those for someone else
+        if peer_id == self.pid || rtc.get("to").and_then(|v| v.as_str()).is_some_and(|to| to != self.pid) {
+            return Ok(());
+        }
+
+        if !self.sealer.learn(peer_id, rtc, origin(msg)) {
+            tracing::warn!(peer_id = %peer_id, "Ignoring WebRTC signaling signed by another key than the pid's");
+            return Ok(());
+        }
+        let opened;
+        let rtc = match rtc.get("sea") {
+            Some(_) => {
+                opened = self.sealer.open(peer_id, rtc).await?;
+                &opened
+            }
+            None => rtc,
+        };
+
+        let discovery = ["candidate", "answer", "offer"].iter().all(|key| rtc.get(key).is_none());
+        if (discovery || rtc.get("offer").is_some()) && !self.in_room(rtc) {
+            tracing::debug!(peer_id = %peer_id, room = ?rtc.get("room"), "Ignoring WebRTC signaling from another room");
             return Ok(());
         }
 
@@ -388,31 +1273,80 @@ impl WebRTCManager {
         } else if rtc.get("offer").is_some() {
             // SDP offer
             self.handle_offer(peer_id, rtc).await?;
-        } else if rtc.get("id").is_some() {
-            // Peer discovery - initiate connection
-            self.initiate_connection(peer_id).await?;
+        } else if !self.peers.read().await.contains_key(peer_id) {
+            // Peer discovery. Both sides hear of each other, and two offers
+            // would cross, so only the lower pid offers; the other one makes
+            // sure the peer has heard of it (the lower one never answers, so
+            // this can't ping-pong)
+            if self.pid.as_str() < peer_id {
+                self.initiate_connection(peer_id).await?;
+            } else {
+                signal(&self.mesh, peer_id, &self.announcement()).await?;
+            }
         }
 
         Ok(())
     }
 
     /// Handle ICE candidate
+    ///
+    /// Candidates from a peer we have no connection with yet are kept until
+    /// its offer arrives; a `null` candidate (end of gathering) is ignored.
     async fn handle_ice_candidate(&self, peer_id: &str, rtc: &Value) -> GunResult<()> {
-        let peers = self.peers.read().await;
-        if peers.get(peer_id).is_some() {
-            if let Some(_candidate_json) = rtc.get("candidate") {
-            // Parse ICE candidate from JSON
-            // Note: Full RTCIceCandidate parsing is handled by the webrtc-rs library
-            // We log the candidate here for debugging, but the actual ICE candidate
-            // processing is done by the underlying WebRTC implementation
-            tracing::debug!("Received ICE candidate for peer {}", peer_id);
+        let candidate = match rtc.get("candidate") {
+            Some(Value::Null) | None => return Ok(()),
+            Some(candidate) => serde_json::from_value::<RTCMessageCandidate>(candidate.clone())
+                .map_err(|e| GunError::InvalidData(format!("Invalid ICE candidate: {}", e)))?,
+        };
+        tracing::debug!(peer_id = %peer_id, candidate = %candidate.candidate, "Received ICE candidate");
+
+        let peer = self.peers.read().await.get(peer_id).cloned();
+        match peer {
+            Some(peer) => peer.add_ice_candidate(candidate.into()).await,
+            None => {
+                let mut early = self.early_candidates.lock();
+                if early.len() >= self.options.max_connections && !early.contains_key(peer_id) {
+                    return Ok(());
+                }
+                let pending = early.entry(peer_id.to_string()).or_default();
+                if pending.len() < MAX_EARLY_CANDIDATES {
+                    pending.push(candidate.into());
+                }
+                Ok(())
             }
         }
-        Ok(())
+    }
+
+    /// Send each local candidate of `peer` to it as it is gathered
+    fn trickle(&self, peer: &WebRTCPeer) {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        peer.on_local_candidate(tx);
+        let (mesh, pid, to) = (self.mesh.clone(), self.pid.clone(), peer.peer_id.clone());
+        let sealer = self.sealer.clone();
+        // Ends when the peer connection, which holds the sender, is dropped
+        self.core.spawn_until_cancelled(async move {
+            while let Some(candidate) = rx.recv().await {
+                let rtc = serde_json::json!({
+                    "id": pid,
+                    "candidate": RTCMessageCandidate::from(candidate),
+                });
+                let sent = match sealer.seal(&to, rtc).await {
+                    Ok(rtc) => signal(&mesh, &to, &serde_json::json!({ "ok": { "rtc": rtc } })).await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = sent {
+                    tracing::debug!(peer_id = %to, error = %e, "Failed to send ICE candidate");
+                }
+            }
+        });
     }
 
     /// Handle SDP answer
+    ///
+    /// Ignored unless an offer of ours to the peer is waiting for it, e.g.
+    /// when we rolled our offer back after it collided with the peer's.
     async fn handle_answer(&self, peer_id: &str, rtc: &Value) -> GunResult<()> {
+        let _negotiating = self.negotiation.lock().await;
         // Clone peer Arc to avoid holding lock during async operations
         let peer_arc = {
         let peers = self.peers.read().await;
@@ -420,6 +1354,10 @@ impl WebRTCManager {
         }; // Lock released here
         
         if let Some(peer) = peer_arc {
+            if peer.signaling_state() != RTCSignalingState::HaveLocalOffer {
+                tracing::debug!(peer_id = %peer_id, state = ?peer.signaling_state(), "Ignoring answer without an offer of ours");
+                return Ok(());
+            }
             let answer_json = rtc.get("answer")
                 .ok_or_else(|| GunError::InvalidData("Missing answer in RTC message".to_string()))?;
             // Parse SDP from JSON
@@ -442,76 +1380,105 @@ impl WebRTCManager {
     }
 
     /// Handle SDP offer
+    ///
+    /// An offer that crosses one of ours (glare) is settled as in perfect
+    /// negotiation. The peer with the higher pid is polite: it rolls its
+    /// offer back, dropping the connection that made it, and answers with a
+    /// new one. The other one ignores the peer's offer and waits for the
+    /// answer to its own.
     async fn handle_offer(&self, peer_id: &str, rtc: &Value) -> GunResult<()> {
-        // Check if peer exists and create if needed
-        let should_create = {
-            let peers = self.peers.read().await;
-            !peers.contains_key(peer_id)
-        };
-
-        if should_create {
-            let peer_id_for_task = peer_id.to_string();
-            let options_clone = self.options.clone();
-            let (peer, mut rx) = WebRTCPeer::new(peer_id_for_task.clone(), &options_clone).await?;
-
-            // Set up message receiver to forward to mesh
-            // We use a separate task that doesn't hold references to avoid Send issues
-            let mesh_clone = self.mesh.clone();
-            tokio::spawn(async move {
-                while let Some(msg) = rx.recv().await {
-                    // Forward message to mesh
-                    // The mesh will handle DAM protocol processing
-                    // Note: peer is None because it's coming from WebRTC, not WebSocket
-                    if let Err(e) = mesh_clone.hear(&msg, None).await {
-                        tracing::error!("Error forwarding WebRTC message to mesh: {}", e);
-                    }
+        let _negotiating = self.negotiation.lock().await;
+        let offer_json = rtc.get("offer")
+            .ok_or_else(|| GunError::InvalidData("Missing offer in RTC message".to_string()))?;
+        let sdp_str = offer_json
+            .get("sdp")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| GunError::InvalidData("Missing SDP in offer".to_string()))?
+            .replace("\\r\\n", "\r\n");
+        let desc = RTCSessionDescription::offer(sdp_str)
+            .map_err(|e| GunError::WebRTC(format!("Failed to parse offer SDP: {}", e)))?;
+
+        let mut existing = self.peers.read().await.get(peer_id).cloned();
+        if let Some(ours) = existing.take_if(|peer| peer.signaling_state() == RTCSignalingState::HaveLocalOffer) {
+            if self.pid.as_str() < peer_id {
+                tracing::debug!(peer_id = %peer_id, "Offer collision, keeping ours");
+                return Ok(());
+            }
+            tracing::debug!(peer_id = %peer_id, "Offer collision, rolling ours back");
+            {
+                let mut peers = self.peers.write().await;
+                if peers.get(peer_id).is_some_and(|peer| Arc::ptr_eq(peer, &ours)) {
+                    peers.remove(peer_id);
                 }
-            });
-
-            // Insert peer after spawning task (wrap in Arc)
-            let mut peers = self.peers.write().await;
-            peers.insert(peer_id_for_task, Arc::new(peer));
+            }
+            // The peer's candidates so far are for the offer we now answer
+            let candidates = std::mem::take(&mut *ours.pending_candidates.lock().await);
+            self.early_candidates.lock().entry(peer_id.to_string()).or_default().extend(candidates);
+            if let Err(e) = ours.close().await {
+                tracing::debug!(peer_id = %peer_id, error = %e, "Error closing rolled back WebRTC connection");
+            }
         }
 
-        let peer_exists = {
-            let peers = self.peers.read().await;
-            peers.get(peer_id).is_some()
-        };
-
-        if peer_exists {
-            let offer_json = rtc.get("offer")
-                .ok_or_else(|| GunError::InvalidData("Missing offer in RTC message".to_string()))?;
-            let sdp_str = offer_json
-                .get("sdp")
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| GunError::InvalidData("Missing SDP in offer".to_string()))?
-                .replace("\\r\\n", "\r\n");
+        let peer = match existing {
+            Some(peer) => peer,
+            None => {
+                let (peer, mut rx) =
+                    WebRTCPeer::with_role(peer_id.to_string(), &self.options, WebRTCRole::Answerer).await?;
+                self.trickle(&peer);
+
+                // Set up message receiver to forward to mesh
+                // We use a separate task that doesn't hold references to avoid Send issues
+                let (mesh_clone, pid) = (self.mesh.clone(), peer_id.to_string());
+                self.core.spawn_until_cancelled(async move {
+                    while let Some(msg) = rx.recv().await {
+                        // Forward message to mesh
+                        // The mesh will handle DAM protocol processing, as
+                        // from the peer with this pid, so it isn't echoed back
+                        let from = mesh_clone.peer_for_pid(&pid).await;
+                        if let Err(e) = mesh_clone.hear(&msg, from.as_ref()).await {
+                            tracing::error!("Error forwarding WebRTC message to mesh: {}", e);
+                        }
+                    }
+                });
 
-            let desc = RTCSessionDescription::offer(sdp_str)
-                .map_err(|e| GunError::WebRTC(format!("Failed to parse offer SDP: {}", e)))?;
+                // Candidates sent ahead of the offer wait in the peer for it
+                let early = self.early_candidates.lock().remove(peer_id).unwrap_or_default();
+                for candidate in early {
+                    peer.add_ice_candidate(candidate).await?;
+                }
 
-            // Clone peer Arc to avoid holding lock during async operations
-            let peer_id_clone = peer_id.to_string();
-            let peer_arc = {
-            let peers = self.peers.read().await;
-                peers.get(peer_id).cloned() // Clone the Arc, not the peer
-            }; // Lock released here
-            
-            if let Some(peer) = peer_arc {
-                // Perform async operations without holding the lock
-                peer.set_remote_description(desc).await?;
-                let answer = peer.create_answer().await?;
-                // Send answer without holding any locks
-                self.send_rtc_message(&peer_id_clone, "answer", &answer)
-                    .await?;
+                // Insert peer after spawning task (wrap in Arc)
+                let peer = Arc::new(peer);
+                self.peers.write().await.insert(peer_id.to_string(), peer.clone());
+                self.watch(&peer);
+                self.expire(&peer);
+                peer
             }
-        }
+        };
 
-        Ok(())
+        // Perform async operations without holding the lock
+        peer.set_remote_description(desc).await?;
+        let answer = peer.create_answer().await?;
+        self.send_rtc_message(peer_id, "answer", &answer).await
+    }
+
+    /// Offer a connection to the peer with DAM pid `pid` now, rather than
+    /// waiting to hear of it
+    ///
+    /// Does nothing if there is a connection to it already. Both peers may
+    /// call this at once: their offers collide and one is rolled back, see
+    /// [`handle_rtc_message`](Self::handle_rtc_message).
+    pub async fn connect(&self, pid: &str) -> GunResult<()> {
+        self.initiate_connection(pid).await
     }
 
     /// Initiate WebRTC connection to a peer
     async fn initiate_connection(&self, peer_id: &str) -> GunResult<()> {
+        if self.is_relay_only(peer_id) {
+            tracing::debug!(peer_id = %peer_id, "Not offering a connection to a relay-only peer");
+            return Ok(());
+        }
+        let _negotiating = self.negotiation.lock().await;
         // Check if connection already exists
         let should_create = {
             let peers = self.peers.read().await;
@@ -531,13 +1498,15 @@ impl WebRTCManager {
 
         // Create new peer connection
         let (peer, mut rx) = WebRTCPeer::new(peer_id.to_string(), &self.options).await?;
+        self.trickle(&peer);
 
         // Set up message receiver (clone before acquiring write lock)
-        let mesh_clone = self.mesh.clone();
-        tokio::spawn(async move {
+        let (mesh_clone, pid) = (self.mesh.clone(), peer_id.to_string());
+        self.core.spawn_until_cancelled(async move {
             while let Some(msg) = rx.recv().await {
                 // Forward message to mesh
-                if let Err(e) = mesh_clone.hear(&msg, None).await {
+                let from = mesh_clone.peer_for_pid(&pid).await;
+                if let Err(e) = mesh_clone.hear(&msg, from.as_ref()).await {
                     tracing::error!("Error forwarding WebRTC message to mesh: {}", e);
                 }
             }
@@ -547,10 +1516,10 @@ impl WebRTCManager {
         let offer = peer.create_offer().await?;
 
         // Insert peer and send offer (wrap in Arc)
-        {
-            let mut peers = self.peers.write().await;
-            peers.insert(peer_id.to_string(), Arc::new(peer));
-        } // Lock released here
+        let peer = Arc::new(peer);
+        self.peers.write().await.insert(peer_id.to_string(), peer.clone());
+        self.watch(&peer);
+        self.expire(&peer);
 
         self.send_rtc_message(peer_id, "offer", &offer).await?;
 
@@ -564,14 +1533,8 @@ impl WebRTCManager {
         msg_type: &str,
         sdp: &RTCSessionDescription,
     ) -> GunResult<()> {
-        // Create RTC message in Gun.js format
-        let mut rtc_msg = serde_json::json!({
-            "ok": {
-                "rtc": {
-                    "id": self.pid,
-                }
-            }
-        });
+        // Create RTC message in Gun.js format, in our room
+        let mut rtc_msg = self.announcement();
 
         // Add SDP
         match msg_type {
@@ -595,37 +1558,162 @@ impl WebRTCManager {
             }
         }
 
-        // Send through mesh
-        let msg_str = serde_json::to_string(&rtc_msg).map_err(GunError::Serialization)?;
+        rtc_msg["ok"]["rtc"] = self.sealer.seal(peer_id, rtc_msg["ok"]["rtc"].take()).await?;
+        signal(&self.mesh, peer_id, &rtc_msg).await
+    }
 
-        // Find the peer in mesh and send
-        // This will go through WebSocket if WebRTC isn't established yet
-        // Once WebRTC is established, messages will go through the data channel
-        if self.mesh.get_peer(peer_id).await.is_some() {
-            self.mesh.send_to_peer_by_id(&msg_str, peer_id).await?;
+    /// Close every WebRTC connection (data channel first, then the peer connection)
+    pub async fn close_all(&self) {
+        let peers: Vec<(String, Arc<WebRTCPeer>)> = self.peers.write().await.drain().collect();
+        for (peer_id, peer) in peers {
+            if let Err(e) = peer.close().await {
+                tracing::warn!(peer_id = %peer_id, error = %e, "Error closing WebRTC connection");
+            }
         }
-
-        Ok(())
     }
 
     /// Send a DAM message through WebRTC if available, otherwise fall back to WebSocket
     pub async fn send_message(&self, peer_id: &str, message: &str) -> GunResult<()> {
-        let peers = self.peers.read().await;
-        if let Some(peer) = peers.get(peer_id) {
-            // Check if WebRTC connection is open
-            if matches!(
-                peer.connection_state().await,
-                RTCPeerConnectionState::Connected
-            ) {
-                return peer.send(message).await;
-            }
+        let peer = self.peers.read().await.get(peer_id).cloned();
+        if let Some(peer) = peer.filter(|peer| peer.is_open()) {
+            return peer.send(message).await;
         }
 
         // Fall back to WebSocket via mesh
-        self.mesh.send_to_peer_by_id(message, peer_id).await
+        let mesh_peer_id = self
+            .mesh
+            .peer_id_for_pid(peer_id)
+            .unwrap_or_else(|| peer_id.to_string());
+        self.mesh.send_to_peer_by_id(message, &mesh_peer_id).await
+    }
+}
+
+/// Send a signaling message through the mesh to the peer with DAM pid `pid`
+///
+/// The message is addressed to `pid` with `ok.rtc.to`. It goes straight to
+/// the connection with that pid if there is one, else to every peer, for
+/// relays to pass on; peers it isn't for ignore it.
+async fn signal(mesh: &Mesh, pid: &str, msg: &Value) -> GunResult<()> {
+    let mut msg = msg.clone();
+    msg["ok"]["rtc"]["to"] = pid.into();
+    if mesh.peer_id_for_pid(pid).is_some() {
+        mesh.send_to_pid(pid, &msg).await
+    } else {
+        mesh.say(&msg, None).await
+    }
+}
+
+/// Hex key of the origin of `msg`, the signer its `from` names, which the
+/// mesh checked; unsigned messages have none
+fn origin(msg: &Value) -> Option<&str> {
+    msg.get("sigs")?;
+    msg.get("from")?.as_str()
+}
+
+/// The signaling keys a peer announced
+struct PeerKey {
+    epub: String,
+    signer: Option<String>, // Hex key of the origin of the message that gave it
+}
+
+/// Encrypts and decrypts the signaling with peers that announced an epub,
+/// see `WebRTCOptions::signaling_pair`
+#[derive(Clone)]
+struct Sealer {
+    pair: Option<KeyPair>, // Only one with an epub
+    keys: Arc<parking_lot::Mutex<HashMap<String, PeerKey>>>, // By pid
+}
+
+impl Sealer {
+    /// Remember the epub in signaling from `pid`, signed by `signer`
+    ///
+    /// Once a signer gave the pid's epub only it may change it: signaling
+    /// with an epub signed by anyone else is refused (`false`).
+    fn learn(&self, pid: &str, rtc: &Value, signer: Option<&str>) -> bool {
+        let Some(epub) = rtc.get("epub").and_then(|v| v.as_str()) else {
+            return true;
+        };
+        let mut keys = self.keys.lock();
+        if keys.get(pid).is_some_and(|known| known.signer.is_some() && known.signer.as_deref() != signer) {
+            return false;
+        }
+        keys.insert(pid.to_string(), PeerKey { epub: epub.to_string(), signer: signer.map(str::to_string) });
+        true
+    }
+
+    /// `rtc` encrypted for the peer with pid `to`, or as it is if either of
+    /// us has no epub
+    ///
+    /// Only `id` and our `epub`, which `to` decrypts with, stay readable.
+    async fn seal(&self, to: &str, rtc: Value) -> GunResult<Value> {
+        let their_epub = self.keys.lock().get(to).map(|key| key.epub.clone());
+        let (Some(pair), Some(their_epub)) = (&self.pair, their_epub) else {
+            return Ok(rtc);
+        };
+        let sealed = crate::sea::encrypt(&rtc, pair, Some(&their_epub))
+            .await
+            .map_err(|e| GunError::Crypto(format!("Can't encrypt signaling for {}: {}", to, e)))?;
+        Ok(serde_json::json!({ "id": rtc["id"], "epub": pair.epub_key, "sea": sealed }))
+    }
+
+    /// The signaling `pid` sealed in `rtc`, see [`seal`](Self::seal)
+    ///
+    /// Fails unless it decrypts, with the epub next to it, and the pid
+    /// inside is `pid`.
+    async fn open(&self, pid: &str, rtc: &Value) -> GunResult<Value> {
+        let pair = self
+            .pair
+            .as_ref()
+            .ok_or_else(|| GunError::WebRTC(format!("Encrypted signaling from {} without a signaling pair", pid)))?;
+        let epub = rtc
+            .get("epub")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| GunError::InvalidData(format!("Encrypted signaling from {} without an epub", pid)))?;
+        let opened = crate::sea::decrypt(&rtc["sea"], pair, Some(epub))
+            .await
+            .map_err(|e| GunError::Crypto(format!("Can't decrypt signaling from {}: {}", pid, e)))?;
+        if opened.get("id").and_then(|v| v.as_str()) != Some(pid) {
+            return Err(GunError::InvalidData(format!("Encrypted signaling from {} names another pid", pid)));
+        }
+        Ok(opened)
     }
 }
 
+/// The WebRTC signaling in a message: its `ok.rtc`, or `rtc` in a
+/// `dam: "rtc"` message
+fn rtc_signal(msg: &Value) -> Option<&Value> {
+    match msg.get("ok").and_then(|ok| ok.get("rtc")) {
+        Some(rtc) => Some(rtc),
+        None if msg.get("dam").and_then(|v| v.as_str()) == Some("rtc") => msg.get("rtc"),
+        None => None,
+    }
+}
+
+/// Mesh transport sending frames over `peer`'s data channel
+fn data_channel_transport(peer: Weak<WebRTCPeer>) -> PeerTransport {
+    Arc::new(move |frame: Frame| {
+        let peer = peer.upgrade();
+        Box::pin(async move {
+            match peer {
+                Some(peer) => peer.send(&frame).await,
+                None => Err(GunError::Network("WebRTC connection is gone".to_string())),
+            }
+        })
+    })
+}
+
+/// What [`WebRTCManager::start_signaling`] hears of
+enum Signal {
+    /// A message carrying signaling
+    Message(Value),
+    /// A peer connected and told us its pid
+    Hi(String),
+    /// Time to offer the pid a connection again, after one timed out
+    Retry(String),
+    /// Time to announce ourselves again, see `WebRTCOptions::announce_interval`
+    Announce,
+}
+
 /// RTC message types for signaling
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RTCMessage {
@@ -640,6 +1728,9 @@ pub struct RTCMessageOk {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RTCMessageRTC {
     pub id: String,
+    /// The pid the message is for, when sent through relays
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
     pub offer: Option<RTCMessageSDP>,
     pub answer: Option<RTCMessageSDP>,
     pub candidate: Option<RTCMessageCandidate>,
@@ -652,9 +1743,35 @@ pub struct RTCMessageSDP {
     pub sdp: String,
 }
 
+/// An ICE candidate as browsers serialize it: `{"candidate", "sdpMid",
+/// "sdpMLineIndex", "usernameFragment"}`
 #[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
 pub struct RTCMessageCandidate {
     pub candidate: String,
     pub sdp_mid: Option<String>,
     pub sdp_m_line_index: Option<u16>,
+    pub username_fragment: Option<String>,
+}
+
+impl From<RTCIceCandidateInit> for RTCMessageCandidate {
+    fn from(init: RTCIceCandidateInit) -> Self {
+        Self {
+            candidate: init.candidate,
+            sdp_mid: init.sdp_mid,
+            sdp_m_line_index: init.sdp_mline_index,
+            username_fragment: init.username_fragment,
+        }
+    }
+}
+
+impl From<RTCMessageCandidate> for RTCIceCandidateInit {
+    fn from(candidate: RTCMessageCandidate) -> Self {
+        Self {
+            candidate: candidate.candidate,
+            sdp_mid: candidate.sdp_mid,
+            sdp_mline_index: candidate.sdp_m_line_index,
+            username_fragment: candidate.username_fragment,
+        }
+    }
 }
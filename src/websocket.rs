@@ -12,16 +12,511 @@
 //! Both client and server handle the DAM protocol message exchange over WebSocket.
 
 use crate::core::GunCore;
-use crate::dam::{Mesh, Peer};
-use crate::error::GunResult;
+use crate::dam::{Mesh, Outbox, Peer, PeerId};
+use crate::wire::{self, WireEncoding};
+use crate::error::{GunError, GunResult};
+#[cfg(feature = "metrics")]
+use crate::metrics;
+#[cfg(feature = "metrics")]
+use crate::storage::StorageMetrics;
+use crate::types::{AuthDecision, AuthRequest, ServerAuth};
 use futures_util::{SinkExt, StreamExt};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::mpsc;
-use tokio_tungstenite::accept_async;
-use tokio_tungstenite::{connect_async, tungstenite::Message};
-use url::Url;
+use tokio::sync::watch;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::header::{HeaderMap, HeaderName, HeaderValue, SEC_WEBSOCKET_PROTOCOL};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::protocol::{CloseFrame, WebSocketConfig};
+use tokio_tungstenite::accept_hdr_async_with_config;
+use tokio_tungstenite::{client_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+use url::{Host, Url};
+
+/// TLS settings for `wss://` peers
+///
+/// By default servers are verified against the system's root certificates
+/// and the host in the peer URL.
+#[derive(Clone, Debug, Default)]
+pub struct TlsOptions {
+    /// PEM files of root certificates to trust besides the system's, e.g. a
+    /// private CA. A file may hold several certificates
+    pub extra_roots: Vec<PathBuf>,
+
+    /// Accept any server certificate: expired, self-signed or for another host
+    /// DANGEROUS: anyone on the path can impersonate the relay. For local
+    /// development only. Default: `false`
+    pub accept_invalid_certs: bool,
+
+    /// Server name to send (SNI) and check the certificate against instead of
+    /// the URL's host, e.g. when dialing a relay by IP address. Default: `None`
+    pub sni_override: Option<String>,
+}
+
+impl TlsOptions {
+    /// Build the connector used for `wss://` handshakes
+    fn connector(&self) -> GunResult<TlsConnector> {
+        let mut builder = native_tls::TlsConnector::builder();
+        for path in &self.extra_roots {
+            let pem = std::fs::read(path).map_err(|e| {
+                GunError::Tls(format!("Can't read root certificates {}: {}", path.display(), e))
+            })?;
+            let certs = native_tls::Certificate::stack_from_pem(&pem).map_err(|e| {
+                GunError::Tls(format!("Invalid root certificates in {}: {}", path.display(), e))
+            })?;
+            if certs.is_empty() {
+                return Err(GunError::Tls(format!("No certificates in {}", path.display())));
+            }
+            for cert in certs {
+                builder.add_root_certificate(cert);
+            }
+        }
+        if self.accept_invalid_certs {
+            tracing::warn!("TLS certificate verification is disabled: wss peers are not authenticated");
+            builder.danger_accept_invalid_certs(true);
+        }
+        let connector = builder
+            .build()
+            .map_err(|e| GunError::Tls(format!("Can't set up TLS: {}", e)))?;
+        Ok(TlsConnector {
+            connector: connector.into(),
+            sni: self.sni_override.clone(),
+        })
+    }
+}
+
+/// Connector for `wss://` peers built from [`TlsOptions`]
+#[derive(Clone)]
+struct TlsConnector {
+    connector: tokio_native_tls::TlsConnector,
+    sni: Option<String>,
+}
+
+/// Proxy for outbound peer connections
+///
+/// `url` is `http://host:port` for an HTTP proxy, which is asked to tunnel
+/// with `CONNECT`, or `socks5://host:port` for a SOCKS5 proxy, which resolves
+/// peer host names itself. `wss://` peers still do their TLS handshake with
+/// the peer, through the tunnel.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use gun::websocket::ProxyConfig;
+///
+/// let proxy = ProxyConfig::new("socks5://proxy.corp.example:1080").with_auth("alice", "secret");
+/// ```
+#[derive(Clone, Debug)]
+pub struct ProxyConfig {
+    /// `http://host:port` or `socks5://host:port`
+    pub url: String,
+
+    /// Username and password: Basic authentication for HTTP proxies,
+    /// username/password authentication for SOCKS5. Default: `None`
+    pub auth: Option<(String, String)>,
+
+    /// Hosts to connect to directly, like `NO_PROXY`: a host name also
+    /// matches its subdomains (a leading `.` is ignored), an IP address
+    /// matches itself and `*` matches everything. Default: `localhost`,
+    /// `127.0.0.1` and `::1`
+    pub no_proxy: Vec<String>,
+}
+
+impl ProxyConfig {
+    /// Proxy at `url`, without credentials, bypassed for localhost
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            auth: None,
+            no_proxy: vec!["localhost".to_string(), "127.0.0.1".to_string(), "::1".to_string()],
+        }
+    }
+
+    /// Authenticate to the proxy with this username and password
+    pub fn with_auth(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.auth = Some((username.into(), password.into()));
+        self
+    }
+
+    /// Check the URL and credentials once for every attempt
+    fn proxy(&self) -> GunResult<Proxy> {
+        let invalid = |why: String| GunError::InvalidData(format!("Invalid proxy URL {}: {}", self.url, why));
+        let url = Url::parse(&self.url).map_err(|e| invalid(e.to_string()))?;
+        let (kind, default_port) = match url.scheme() {
+            "http" => (ProxyKind::Http, 80),
+            "socks5" | "socks5h" => (ProxyKind::Socks5, 1080),
+            scheme => return Err(invalid(format!("unsupported scheme {}", scheme))),
+        };
+        let host = match url.host() {
+            Some(Host::Ipv6(ip)) => ip.to_string(),
+            Some(host) => host.to_string(),
+            None => return Err(invalid("no host".to_string())),
+        };
+        if let (ProxyKind::Socks5, Some((username, password))) = (&kind, &self.auth) {
+            if username.len() > 255 || password.len() > 255 {
+                return Err(GunError::InvalidData(
+                    "SOCKS5 usernames and passwords are at most 255 bytes".to_string(),
+                ));
+            }
+        }
+        Ok(Proxy {
+            kind,
+            host,
+            port: url.port().unwrap_or(default_port),
+            auth: self.auth.clone(),
+            no_proxy: self
+                .no_proxy
+                .iter()
+                .map(|entry| entry.trim().trim_start_matches('.').trim_matches(['[', ']']).to_ascii_lowercase())
+                .filter(|entry| !entry.is_empty())
+                .collect(),
+        })
+    }
+}
+
+/// A checked [`ProxyConfig`]
+#[derive(Clone)]
+struct Proxy {
+    kind: ProxyKind,
+    host: String,
+    port: u16,
+    auth: Option<(String, String)>,
+    no_proxy: Vec<String>,
+}
+
+#[derive(Clone, Copy)]
+enum ProxyKind {
+    Http,
+    Socks5,
+}
+
+impl Proxy {
+    /// Whether `host` is dialed directly
+    fn bypasses(&self, host: &str) -> bool {
+        let host = host.to_ascii_lowercase();
+        self.no_proxy.iter().any(|entry| {
+            entry == "*"
+                || *entry == host
+                || host.strip_suffix(entry.as_str()).is_some_and(|rest| rest.ends_with('.'))
+        })
+    }
+
+    /// Connect to the proxy and have it open a tunnel to `host:port`
+    async fn connect(&self, host: &str, port: u16) -> Result<TcpStream, ConnectFailure> {
+        let mut stream = connect_tcp(&self.host, self.port).await?;
+        let target = match host.parse::<IpAddr>() {
+            Ok(IpAddr::V6(ip)) => format!("[{}]:{}", ip, port),
+            _ => format!("{}:{}", host, port),
+        };
+        let io = |e: std::io::Error| {
+            ConnectFailure::HandshakeError(format!("Proxy {}:{} failed: {}", self.host, self.port, e))
+        };
+        match self.kind {
+            ProxyKind::Http => self.http_connect(&mut stream, &target).await.map_err(io)??,
+            ProxyKind::Socks5 => self.socks5_connect(&mut stream, host, port, &target).await.map_err(io)??,
+        }
+        tracing::debug!(proxy = %format!("{}:{}", self.host, self.port), target = %target, "Tunnel through proxy open");
+        Ok(stream)
+    }
+
+    /// Ask an HTTP proxy to tunnel with `CONNECT`
+    async fn http_connect(
+        &self,
+        stream: &mut TcpStream,
+        target: &str,
+    ) -> std::io::Result<Result<(), ConnectFailure>> {
+        let mut request = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", target);
+        if let Some((username, password)) = &self.auth {
+            use base64::Engine as _;
+            let credentials = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", username, password));
+            request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", credentials));
+        }
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes()).await?;
+
+        // Read only the response head: whatever follows belongs to the tunnel
+        let mut head = Vec::new();
+        while !head.ends_with(b"\r\n\r\n") {
+            if head.len() >= 8192 {
+                return Ok(Err(ConnectFailure::HandshakeError(
+                    "Proxy response head is too long".to_string(),
+                )));
+            }
+            head.push(stream.read_u8().await?);
+        }
+        let head = String::from_utf8_lossy(&head);
+        let status_line = head.lines().next().unwrap_or_default();
+        let status = status_line.split_whitespace().nth(1).and_then(|code| code.parse::<u16>().ok());
+        Ok(match status {
+            Some(200..=299) => Ok(()),
+            // The proxy couldn't reach the peer
+            Some(502..=504) => Err(ConnectFailure::Refused(format!(
+                "Proxy couldn't connect to {}: {}",
+                target, status_line
+            ))),
+            _ => Err(ConnectFailure::HandshakeError(format!(
+                "Proxy refused to connect to {}: {}",
+                target, status_line
+            ))),
+        })
+    }
+
+    /// SOCKS5 greeting, optional username/password authentication (RFC 1929)
+    /// and `CONNECT` (RFC 1928)
+    async fn socks5_connect(
+        &self,
+        stream: &mut TcpStream,
+        host: &str,
+        port: u16,
+        target: &str,
+    ) -> std::io::Result<Result<(), ConnectFailure>> {
+        let refused = |why: &str| Ok(Err(ConnectFailure::HandshakeError(format!("SOCKS5 proxy {}", why))));
+        let methods: &[u8] = if self.auth.is_some() { &[0x00, 0x02] } else { &[0x00] };
+        let mut greeting = vec![0x05, methods.len() as u8];
+        greeting.extend_from_slice(methods);
+        stream.write_all(&greeting).await?;
+        let mut choice = [0u8; 2];
+        stream.read_exact(&mut choice).await?;
+        match (choice, &self.auth) {
+            ([0x05, 0x00], _) => {}
+            ([0x05, 0x02], Some((username, password))) => {
+                let mut login = vec![0x01, username.len() as u8];
+                login.extend_from_slice(username.as_bytes());
+                login.push(password.len() as u8);
+                login.extend_from_slice(password.as_bytes());
+                stream.write_all(&login).await?;
+                let mut status = [0u8; 2];
+                stream.read_exact(&mut status).await?;
+                if status[1] != 0x00 {
+                    return refused("rejected the username and password");
+                }
+            }
+            ([0x05, _], _) => return refused("accepts none of our authentication methods"),
+            _ => return refused("sent an invalid greeting"),
+        }
+
+        let mut request = vec![0x05, 0x01, 0x00];
+        match host.parse::<IpAddr>() {
+            Ok(IpAddr::V4(ip)) => {
+                request.push(0x01);
+                request.extend_from_slice(&ip.octets());
+            }
+            Ok(IpAddr::V6(ip)) => {
+                request.push(0x04);
+                request.extend_from_slice(&ip.octets());
+            }
+            // Host names are resolved by the proxy
+            Err(_) if host.len() <= 255 => {
+                request.extend_from_slice(&[0x03, host.len() as u8]);
+                request.extend_from_slice(host.as_bytes());
+            }
+            Err(_) => return refused("can't take host names over 255 bytes"),
+        }
+        request.extend_from_slice(&port.to_be_bytes());
+        stream.write_all(&request).await?;
+
+        let mut reply = [0u8; 4];
+        stream.read_exact(&mut reply).await?;
+        // Skip the address the proxy bound
+        let bound = match reply[3] {
+            0x01 => 4,
+            0x04 => 16,
+            0x03 => stream.read_u8().await? as usize,
+            _ => return refused("sent an invalid reply"),
+        };
+        let mut skip = vec![0u8; bound + 2];
+        stream.read_exact(&mut skip).await?;
+        let why = format!("SOCKS5 proxy couldn't connect to {}", target);
+        Ok(match reply[1] {
+            0x00 => Ok(()),
+            0x03 => Err(ConnectFailure::Refused(format!("{}: network unreachable", why))),
+            0x04 => Err(ConnectFailure::Refused(format!("{}: host unreachable", why))),
+            0x05 => Err(ConnectFailure::Refused(format!("{}: connection refused", why))),
+            0x06 => Err(ConnectFailure::Timeout(format!("{}: TTL expired", why))),
+            code => Err(ConnectFailure::HandshakeError(format!("{}: error {}", why, code))),
+        })
+    }
+}
+
+/// The canonical form of a peer URL
+///
+/// `http`/`https` become `ws`/`wss`, as Gun.js peers are often written as
+/// HTTP URLs. Scheme and host are lowercased and default ports and fragments
+/// dropped, so two spellings of one endpoint compare equal. With
+/// `append_gun_path`, a URL without a path gets Gun.js's `/gun`. Any other
+/// scheme is a [`GunError::InvalidPeerUrl`].
+///
+/// # Example
+///
+/// ```rust
+/// use gun::websocket::normalize_peer_url;
+///
+/// assert_eq!(normalize_peer_url("HTTP://Relay.example.com:80", true).unwrap(), "ws://relay.example.com/gun");
+/// assert!(normalize_peer_url("tcp://relay.example.com", true).is_err());
+/// ```
+pub fn normalize_peer_url(url: &str, append_gun_path: bool) -> GunResult<String> {
+    let invalid = |why: String| GunError::InvalidPeerUrl(format!("{}: {}", url, why));
+    let mut parsed = Url::parse(url.trim()).map_err(|e| invalid(e.to_string()))?;
+    let scheme = match parsed.scheme() {
+        "ws" | "http" => "ws",
+        "wss" | "https" => "wss",
+        other => {
+            return Err(invalid(format!(
+                "unsupported scheme {}, expected ws, wss, http or https",
+                other
+            )))
+        }
+    };
+    parsed
+        .set_scheme(scheme)
+        .map_err(|_| invalid(format!("can't use scheme {}", scheme)))?;
+    if parsed.host().is_none() {
+        return Err(invalid("no host".to_string()));
+    }
+    if append_gun_path && parsed.path() == "/" {
+        parsed.set_path("/gun");
+    }
+    parsed.set_fragment(None);
+    Ok(parsed.into())
+}
+
+/// Resolve `host` and open a TCP connection to it
+async fn connect_tcp(host: &str, port: u16) -> Result<TcpStream, ConnectFailure> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|e| ConnectFailure::DnsFailure(format!("Can't resolve {}: {}", host, e)))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(ConnectFailure::DnsFailure(format!("No addresses for {}", host)));
+    }
+    TcpStream::connect(&addrs[..])
+        .await
+        .map_err(|e| ConnectFailure::Refused(format!("Connection to {}:{} failed: {}", host, port, e)))
+}
+
+/// How a client opens connections: TLS settings, proxy, headers and query
+/// parameters added to every upgrade request, and how long an attempt may take
+#[derive(Clone)]
+struct Dialer {
+    tls: TlsConnector,
+    proxy: Option<Proxy>,
+    headers: HeaderMap,
+    query: Vec<(String, String)>,
+    subprotocols: Vec<String>, // Offered in `Sec-WebSocket-Protocol`
+    timeout: Option<Duration>,
+}
+
+/// A peer URL with its own upgrade request settings
+///
+/// Headers replace `GunOptions::peer_headers` of the same name, query
+/// parameters are added to `GunOptions::peer_query`, and non-empty
+/// subprotocols replace `GunOptions::peer_subprotocols`.
+///
+/// # Example
+///
+/// ```rust
+/// use gun::websocket::PeerConfig;
+///
+/// let peer = PeerConfig::new("wss://gateway.example.com/gun")
+///     .with_header("Authorization", "Bearer s3cret")
+///     .with_subprotocols(vec!["gun.v1".to_string()]);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PeerConfig {
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub query: Vec<(String, String)>,
+    pub subprotocols: Vec<String>,
+}
+
+impl PeerConfig {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self { url: url.into(), ..Default::default() }
+    }
+
+    /// Send this header with the upgrade request
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Offer these subprotocols, most preferred first
+    pub fn with_subprotocols(mut self, subprotocols: Vec<String>) -> Self {
+        self.subprotocols = subprotocols;
+        self
+    }
+}
+
+impl From<&str> for PeerConfig {
+    fn from(url: &str) -> Self {
+        Self::new(url)
+    }
+}
+
+impl From<String> for PeerConfig {
+    fn from(url: String) -> Self {
+        Self::new(url)
+    }
+}
+
+/// Why an attempt to connect to a peer failed
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ConnectFailure {
+    /// The host name didn't resolve
+    #[error("{0}")]
+    DnsFailure(String),
+    /// Nothing accepted the TCP connection: refused, unreachable or reset
+    #[error("{0}")]
+    Refused(String),
+    /// The TLS handshake of a `wss://` URL failed, e.g. an untrusted certificate
+    #[error("{0}")]
+    TlsError(String),
+    /// The attempt took longer than `MeshOptions::connect_timeout`
+    #[error("{0}")]
+    Timeout(String),
+    /// The server didn't complete the WebSocket upgrade (e.g. it answered
+    /// with an HTTP error), or the mesh didn't take the new peer
+    #[error("{0}")]
+    HandshakeError(String),
+}
+
+impl ConnectFailure {
+    /// Whether trying again soon may help: the peer may just be starting up
+    /// or briefly unreachable
+    fn is_transient(&self) -> bool {
+        matches!(self, ConnectFailure::Refused(_) | ConnectFailure::Timeout(_))
+    }
+}
+
+impl From<ConnectFailure> for GunError {
+    fn from(failure: ConnectFailure) -> Self {
+        match failure {
+            ConnectFailure::TlsError(msg) => GunError::Tls(msg),
+            other => GunError::Network(other.to_string()),
+        }
+    }
+}
+
+/// Where connecting to a peer URL stands, see [`Mesh::peer_status`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PeerStatus {
+    /// The first attempt is under way, or the connection dropped and is being retried
+    Connecting,
+    /// The latest attempt succeeded
+    Connected,
+    /// The latest attempt failed; transient failures are retried meanwhile
+    Failed(ConnectFailure),
+    /// Kept in reserve by `PeerMode::Failover`: probed, but not connected
+    Standby,
+}
 
 /// WebSocket client for connecting to peers
 ///
@@ -40,62 +535,204 @@ use url::Url;
 /// let core = Arc::new(GunCore::new());
 /// let mesh = Arc::new(Mesh::new(core.clone(), /* ... */));
 /// let client = WebSocketClient::new(core, mesh);
-/// client.connect("ws://relay.example.com/gun").await?;
+/// client.connect("wss://relay.example.com/gun").await?;
 /// # Ok(())
 /// # }
 /// ```
+#[derive(Clone)]
 pub struct WebSocketClient {
     core: Arc<GunCore>,
     mesh: Arc<Mesh>,
+    tls: TlsOptions,
+    proxy: Option<ProxyConfig>,
+    headers: Vec<(String, String)>,
+    query: Vec<(String, String)>,
+    subprotocols: Vec<String>,
 }
 
 impl WebSocketClient {
     pub fn new(core: Arc<GunCore>, mesh: Arc<Mesh>) -> Self {
-        Self { core, mesh }
+        Self {
+            core,
+            mesh,
+            tls: TlsOptions::default(),
+            proxy: None,
+            headers: Vec::new(),
+            query: Vec::new(),
+            subprotocols: Vec::new(),
+        }
+    }
+
+    /// Use these TLS settings for `wss://` peers
+    pub fn with_tls(mut self, tls: TlsOptions) -> Self {
+        self.tls = tls;
+        self
+    }
+
+    /// Connect through this proxy, `None` to connect directly
+    pub fn with_proxy(mut self, proxy: Option<ProxyConfig>) -> Self {
+        self.proxy = proxy;
+        self
+    }
+
+    /// Send these HTTP headers with every upgrade request, e.g. an
+    /// `Authorization` header for a relay that checks tokens
+    pub fn with_headers(mut self, headers: Vec<(String, String)>) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    /// Add these query parameters to every peer URL when connecting
+    pub fn with_query(mut self, query: Vec<(String, String)>) -> Self {
+        self.query = query;
+        self
+    }
+
+    /// Offer these subprotocols in `Sec-WebSocket-Protocol`, most preferred
+    /// first
+    ///
+    /// A server picking one we didn't offer fails the handshake.
+    pub fn with_subprotocols(mut self, subprotocols: Vec<String>) -> Self {
+        self.subprotocols = subprotocols;
+        self
+    }
+
+    /// This client with `peer`'s headers, query and subprotocols on top of
+    /// its own
+    pub fn for_peer(&self, peer: &PeerConfig) -> Self {
+        let mut client = self.clone();
+        client.headers.retain(|(name, _)| !peer.headers.iter().any(|(own, _)| own.eq_ignore_ascii_case(name)));
+        client.headers.extend(peer.headers.iter().cloned());
+        client.query.extend(peer.query.iter().cloned());
+        if !peer.subprotocols.is_empty() {
+            client.subprotocols = peer.subprotocols.clone();
+        }
+        client
+    }
+
+    /// Check the headers and proxy and set up TLS once for every attempt
+    fn dialer(&self) -> GunResult<Dialer> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let header = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| GunError::InvalidData(format!("Invalid header name {}: {}", name, e)))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| GunError::InvalidData(format!("Invalid value for header {}: {}", name, e)))?;
+            headers.append(header, value);
+        }
+        if !self.subprotocols.is_empty() {
+            let offered = self.subprotocols.join(", ");
+            let value = HeaderValue::from_str(&offered)
+                .map_err(|e| GunError::InvalidData(format!("Invalid subprotocols {}: {}", offered, e)))?;
+            headers.insert(SEC_WEBSOCKET_PROTOCOL, value);
+        }
+        let timeout = self.mesh.options().connect_timeout;
+        Ok(Dialer {
+            tls: self.tls.connector()?,
+            proxy: self.proxy.as_ref().map(ProxyConfig::proxy).transpose()?,
+            headers,
+            query: self.query.clone(),
+            subprotocols: self.subprotocols.clone(),
+            timeout: (timeout > 0).then(|| Duration::from_millis(timeout)),
+        })
     }
 
     /// Connect to a peer URL with automatic reconnection
     /// Returns when connection is established or fails
+    ///
+    /// The URL is normalized with [`normalize_peer_url`] first; unsupported
+    /// schemes fail at once with [`GunError::InvalidPeerUrl`].
+    ///
+    /// Each attempt may take up to `MeshOptions::connect_timeout`. Refused
+    /// and timed out attempts are retried with backoff and end as
+    /// [`GunError::Network`] once retries run out; DNS, TLS and handshake
+    /// failures won't fix themselves and are returned right away (TLS ones as
+    /// [`GunError::Tls`]). How the latest attempt went is in
+    /// [`Mesh::peer_status`].
     pub async fn connect(&self, url: &str) -> GunResult<()> {
-        let url_str = url.to_string();
+        self.connect_peer(url).await.map(|_| ())
+    }
+
+    /// [`connect`](Self::connect), returning the ID the peer got in the mesh
+    ///
+    /// A URL dropped with [`Mesh::remove_peer`] isn't dialed, and an attempt
+    /// under way gives up once it is dropped.
+    pub async fn connect_peer(&self, url: &str) -> GunResult<PeerId> {
+        // Convert http/https to ws/wss
+        let ws_url = normalize_peer_url(url, false)?;
         let core = self.core.clone();
         let mesh = self.mesh.clone();
+        let dialer = self.dialer()?;
 
         // Ensure we use the public URL (convert localhost/127.0.0.1 to public IP if needed)
-        let public_url = Self::ensure_public_url(&url_str)?;
+        let ws_url = Self::ensure_public_url(&ws_url)?;
+        mesh.set_peer_status(&ws_url, PeerStatus::Connecting);
 
         let mut retry_count = 0;
         let max_retries = 10;
         let base_wait = Duration::from_millis(500);
 
         loop {
-            match Self::connect_once(&public_url, core.clone(), mesh.clone()).await {
-                Ok(_) => {
+            if mesh.is_shut_down() {
+                return Err(GunError::Network(format!("Shut down before connecting to {}", ws_url)));
+            }
+            if mesh.is_removed(&ws_url) {
+                return Err(GunError::Network(format!("{} was removed", ws_url)));
+            }
+            match Self::connect_once(&ws_url, core.clone(), mesh.clone(), &dialer).await {
+                Ok(peer_id) => {
+                    mesh.record_dial(&ws_url, true);
+                    mesh.set_peer_status(&ws_url, PeerStatus::Connected);
                     // Connection successful, wait a bit for handshake and peer registration to complete
                     tokio::time::sleep(Duration::from_millis(500)).await;
-                    return Ok(());
+                    return Ok(peer_id);
+                }
+                Err(e) if !e.is_transient() => {
+                    mesh.record_dial(&ws_url, false);
+                    mesh.set_peer_status(&ws_url, PeerStatus::Failed(e.clone()));
+                    return Err(e.into());
                 }
                 Err(e) => {
+                    mesh.set_peer_status(&ws_url, PeerStatus::Failed(e.clone()));
                     retry_count += 1;
                     if retry_count >= max_retries {
-                        return Err(crate::error::GunError::Network(format!(
+                        mesh.record_dial(&ws_url, false);
+                        return Err(GunError::Network(format!(
                             "Max reconnection attempts reached for {}: {}",
-                            public_url, e
+                            ws_url, e
                         )));
                     }
 
                     // Exponential backoff: wait = base_wait * 2^retry_count (capped at 2 seconds)
                     let wait_time = base_wait * (1 << retry_count.min(4));
-                    eprintln!(
-                        "Connection failed for {}, retrying in {:?}... (attempt {})",
-                        public_url, wait_time, retry_count
+                    tracing::debug!(
+                        url = %ws_url,
+                        wait = ?wait_time,
+                        attempt = retry_count,
+                        error = %e,
+                        "Connection failed, retrying"
                     );
-                    tokio::time::sleep(wait_time).await;
+                    tokio::select! {
+                        _ = tokio::time::sleep(wait_time) => {}
+                        _ = mesh.closed() => {}
+                    }
                 }
             }
         }
     }
 
+    /// Open a connection to `url` and close it again, for health checks
+    ///
+    /// Returns how long opening the connection took.
+    pub(crate) async fn probe(&self, url: &str) -> Result<Duration, ConnectFailure> {
+        let dialer = self.dialer().map_err(|e| ConnectFailure::HandshakeError(e.to_string()))?;
+        let started = Instant::now();
+        let mut ws_stream = Self::open(url, &dialer).await?;
+        let rtt = started.elapsed();
+        let _ = ws_stream.close(None).await;
+        Ok(rtt)
+    }
+
     /// Ensure URL uses public IP (not localhost/127.0.0.1)
     /// For relay servers, we should always connect via their public IP
     fn ensure_public_url(url: &str) -> GunResult<String> {
@@ -107,88 +744,516 @@ impl WebSocketClient {
                 // This is a localhost URL - for testing, we might want to allow it
                 // but in production, we should fail or resolve to public IP
                 // For now, we'll allow it but warn
-                eprintln!("Warning: Connecting to localhost URL: {}. This should use a public IP for NAT traversal.", url);
+                tracing::warn!(url = %url, "Connecting to localhost URL. This should use a public IP for NAT traversal.");
             }
         }
 
         Ok(url.to_string())
     }
 
-    async fn connect_once(url: &str, _core: Arc<GunCore>, mesh: Arc<Mesh>) -> GunResult<()> {
-        // Convert http/https to ws/wss
-        let ws_url = url
-            .replace("http://", "ws://")
-            .replace("https://", "wss://");
-        let url = Url::parse(&ws_url).map_err(|e| {
-            crate::error::GunError::Network(format!("Invalid URL {}: {}", ws_url, e))
-        })?;
+    async fn connect_once(
+        ws_url: &str,
+        core: Arc<GunCore>,
+        mesh: Arc<Mesh>,
+        dialer: &Dialer,
+    ) -> Result<PeerId, ConnectFailure> {
+        let ws_stream = Self::open(ws_url, dialer).await?;
+        let refused = |e: GunError| ConnectFailure::HandshakeError(format!("Mesh didn't take {}: {}", ws_url, e));
+
+        let peer = Peer::new(ws_url.to_string());
+        let peer_id = peer.id.clone();
+
+        // Create channels for sending messages
+        let (tx, rx) = Outbox::new();
+
+        // Add peer to mesh FIRST (this registers the peer for message routing)
+        mesh.hi(peer.clone()).await.map_err(refused)?;
+
+        // Set sender in mesh AFTER adding peer (so peer exists in the map)
+        mesh.set_peer_sender(&peer_id, tx.clone()).await.map_err(refused)?;
+
+        core.spawn(Self::run(ws_stream, rx, peer, mesh, dialer.clone()));
+        Ok(peer_id)
+    }
+
+    /// Open a WebSocket connection, within the dialer's timeout
+    async fn open(ws_url: &str, dialer: &Dialer) -> Result<ClientStream, ConnectFailure> {
+        let Some(limit) = dialer.timeout else {
+            return Self::dial(ws_url, dialer).await;
+        };
+        tokio::time::timeout(limit, Self::dial(ws_url, dialer))
+            .await
+            .unwrap_or_else(|_| {
+                Err(ConnectFailure::Timeout(format!(
+                    "Connecting to {} timed out after {:?}",
+                    ws_url, limit
+                )))
+            })
+    }
+
+    /// Resolve the host and connect, directly or through the proxy, do the
+    /// TLS handshake for `wss://` URLs,
+    /// then the WebSocket upgrade
+    async fn dial(ws_url: &str, dialer: &Dialer) -> Result<ClientStream, ConnectFailure> {
+        let handshake = |msg: String| ConnectFailure::HandshakeError(msg);
+        let mut url = Url::parse(ws_url).map_err(|e| handshake(format!("Invalid URL {}: {}", ws_url, e)))?;
+        if !dialer.query.is_empty() {
+            url.query_pairs_mut().extend_pairs(&dialer.query);
+        }
+        let host = match url.host() {
+            Some(Host::Ipv6(ip)) => ip.to_string(),
+            Some(host) => host.to_string(),
+            None => return Err(handshake(format!("No host in URL {}", ws_url))),
+        };
+        let port = url
+            .port_or_known_default()
+            .ok_or_else(|| handshake(format!("No port in URL {}", ws_url)))?;
 
         // Connect to the WebSocket server (always uses public IP since we're connecting to a remote URL)
-        let (ws_stream, response) = connect_async(url.clone()).await.map_err(|e| {
-            crate::error::GunError::Network(format!(
-                "WebSocket connection failed to {}: {}",
-                ws_url, e
-            ))
-        })?;
+        let tcp = match dialer.proxy.as_ref().filter(|proxy| !proxy.bypasses(&host)) {
+            Some(proxy) => proxy.connect(&host, port).await?,
+            None => connect_tcp(&host, port).await?,
+        };
+        let stream = match url.scheme() {
+            "ws" => MaybeTlsStream::Plain(tcp),
+            "wss" => {
+                let domain = dialer.tls.sni.as_deref().unwrap_or(&host);
+                let tls_stream = dialer.tls.connector.connect(domain, tcp).await.map_err(|e| {
+                    ConnectFailure::TlsError(format!("TLS handshake with {} failed: {}", ws_url, e))
+                })?;
+                MaybeTlsStream::NativeTls(tls_stream)
+            }
+            scheme => return Err(handshake(format!("Unsupported scheme {} in {}", scheme, ws_url))),
+        };
+
+        let mut request = url
+            .as_str()
+            .into_client_request()
+            .map_err(|e| handshake(format!("Invalid URL {}: {}", ws_url, e)))?;
+        request.headers_mut().extend(dialer.headers.clone());
+        let (ws_stream, response) = client_async(request, stream)
+            .await
+            .map_err(|e| handshake(format!("WebSocket connection failed to {}: {}", ws_url, e)))?;
 
         // Verify connection was successful (status 101 Switching Protocols)
         if response.status() != 101 {
-            return Err(crate::error::GunError::Network(format!(
+            return Err(handshake(format!(
                 "WebSocket handshake failed with status: {}",
                 response.status()
             )));
         }
+        // tungstenite leaves this check to us (RFC 6455, 4.1)
+        if let Some(chosen) = response.headers().get(SEC_WEBSOCKET_PROTOCOL) {
+            let chosen = chosen.to_str().unwrap_or_default();
+            if !dialer.subprotocols.iter().any(|offered| offered == chosen) {
+                return Err(handshake(format!("{} picked subprotocol {:?}, which we didn't offer", ws_url, chosen)));
+            }
+        }
+        Ok(ws_stream)
+    }
 
-        let peer = Peer::new(ws_url.clone());
-        let peer_id = peer.id.clone();
+    /// Serve a peer connection, reconnecting whenever it drops
+    ///
+    /// Ends once the mesh shuts down, the peer's URL is stood down or the peer
+    /// is removed from the mesh while waiting to reconnect, or `retry`
+    /// reconnect attempts in a row have failed.
+    async fn run(
+        mut ws_stream: ClientStream,
+        mut rx: Outbox,
+        peer: Peer,
+        mesh: Arc<Mesh>,
+        dialer: Dialer,
+    ) {
+        let traffic = Traffic::default();
+        loop {
+            pump(ws_stream, &mut rx, &peer, &mesh, &traffic, 0).await;
 
-        // Create channel for sending messages
-        let (tx, mut rx) = mpsc::unbounded_channel();
+            // We are shutting down, or dropped the peer for misbehaving: don't come back
+            if mesh.is_shut_down() || mesh.is_banned(&peer.url) {
+                return;
+            }
+            // Failover moved on to another URL, or the peer was removed
+            if mesh.is_standby(&peer.url) || mesh.is_removed(&peer.url) {
+                let _ = mesh.bye(&peer.id).await;
+                return;
+            }
+            mesh.disconnect(&peer).await;
+            mesh.set_peer_status(&peer.url, PeerStatus::Connecting);
+            tracing::info!(peer_id = %peer.id, url = %peer.url, "Connection lost, reconnecting");
 
-        // Add peer to mesh FIRST (this registers the peer for message routing)
-        mesh.hi(peer.clone()).await?;
+            match Self::reconnect(&peer, &mesh, &dialer).await {
+                Some((stream, new_rx)) => {
+                    ws_stream = stream;
+                    rx = new_rx;
+                }
+                None => {
+                    let _ = mesh.bye(&peer.id).await;
+                    return;
+                }
+            }
+        }
+    }
 
-        // Set sender in mesh AFTER adding peer (so peer exists in the map)
-        mesh.set_peer_sender(&peer_id, tx.clone()).await?;
-
-        // Split WebSocket stream
-        let (mut write, mut read) = ws_stream.split();
-
-        // Spawn task to handle incoming messages
-        let mesh_clone = mesh.clone();
-        let peer_for_read = peer.clone();
-        tokio::spawn(async move {
-            while let Some(msg) = read.next().await {
-                match msg {
-                    Ok(Message::Text(text)) => {
-                        if let Err(e) = mesh_clone.hear(&text, Some(&peer_for_read)).await {
-                            eprintln!("Error handling message from {}: {}", peer_for_read.id, e);
+    /// Retry the peer's URL with exponential backoff plus jitter
+    async fn reconnect(
+        peer: &Peer,
+        mesh: &Mesh,
+        dialer: &Dialer,
+    ) -> Option<(ClientStream, Outbox)> {
+        let max_attempts = mesh.options().retry.max(0);
+        for attempt in 1..=max_attempts {
+            tokio::select! {
+                _ = tokio::time::sleep(reconnect_delay(mesh.options().retry_wait, attempt as u32)) => {}
+                _ = mesh.closed() => return None,
+            }
+            // Removed or stood down while we were waiting
+            mesh.get_peer(&peer.id).await?;
+            if mesh.is_standby(&peer.url) || mesh.is_removed(&peer.url) {
+                return None;
+            }
+            mesh.record_retry(&peer.id, max_attempts - attempt).await;
+
+            match Self::open(&peer.url, dialer).await {
+                Ok(stream) => {
+                    let (tx, rx) = Outbox::new();
+                    return match mesh.reconnect(&peer.id, tx, attempt as u32).await {
+                        Ok(()) => {
+                            mesh.set_peer_status(&peer.url, PeerStatus::Connected);
+                            Some((stream, rx))
                         }
+                        Err(_) => None,
+                    };
+                }
+                Err(e) => {
+                    mesh.set_peer_status(&peer.url, PeerStatus::Failed(e.clone()));
+                    tracing::debug!(
+                        url = %peer.url,
+                        attempt,
+                        max_attempts,
+                        error = %e,
+                        "Reconnect failed"
+                    );
+                }
+            }
+        }
+        tracing::warn!(url = %peer.url, attempts = max_attempts, "Giving up reconnecting");
+        None
+    }
+}
+
+/// Client side of a WebSocket connection
+type ClientStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Longest wait between reconnect attempts, before jitter
+const MAX_RECONNECT_WAIT: Duration = Duration::from_secs(30);
+
+/// Delay before reconnect attempt `attempt` (1-based): `base_ms` doubled per
+/// attempt, capped at [`MAX_RECONNECT_WAIT`], plus up to 25% random jitter so
+/// peers dropped together don't retry together
+fn reconnect_delay(base_ms: u64, attempt: u32) -> Duration {
+    let exp = Duration::from_millis(base_ms)
+        .saturating_mul(1 << (attempt - 1).min(16))
+        .min(MAX_RECONNECT_WAIT);
+    exp + exp.mul_f64(rand::random::<f64>() * 0.25)
+}
+
+/// Keepalive pings sent on a connection that haven't been answered yet
+#[derive(Default)]
+struct Keepalive {
+    seq: u64,
+    unanswered: Option<(u64, Instant)>, // the latest ping, until its pong arrives
+    missed: u32,                        // pings in a row that got no pong
+}
+
+impl Keepalive {
+    /// Payload of the next ping; counts the previous one as missed if it's
+    /// still unanswered
+    fn ping(&mut self) -> Vec<u8> {
+        if self.unanswered.is_some() {
+            self.missed += 1;
+        }
+        self.seq += 1;
+        self.unanswered = Some((self.seq, Instant::now()));
+        self.seq.to_be_bytes().to_vec()
+    }
+
+    /// Take in a pong; returns the round trip if it answers the latest ping
+    ///
+    /// Any pong shows the connection is alive and clears the missed count.
+    fn pong(&mut self, payload: &[u8]) -> Option<Duration> {
+        self.missed = 0;
+        let seq = u64::from_be_bytes(payload.try_into().ok()?);
+        match self.unanswered {
+            Some((latest, sent)) if latest == seq => {
+                self.unanswered = None;
+                Some(sent.elapsed())
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Frame bytes a connection has sent in the current one-second window
+struct RateWindow {
+    limit: u64, // 0 = no limit
+    started: Instant,
+    bytes: u64,
+}
+
+impl RateWindow {
+    fn new(limit: u64) -> Self {
+        Self { limit, started: Instant::now(), bytes: 0 }
+    }
+
+    /// Count `bytes` received; `false` once this second's are over the limit
+    fn allow(&mut self, bytes: usize) -> bool {
+        if self.limit == 0 {
+            return true;
+        }
+        if self.started.elapsed() >= Duration::from_secs(1) {
+            self.started = Instant::now();
+            self.bytes = 0;
+        }
+        self.bytes += bytes as u64;
+        self.bytes <= self.limit
+    }
+}
+
+/// Why [`pump`] closed a connection on the peer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Abuse {
+    /// A frame or message over the transport limits
+    TooLarge,
+    /// More frame bytes in a second than allowed
+    RateLimited,
+}
+
+/// Exchange messages over a connection until either direction stops
+///
+/// Incoming frames go to `mesh.hear()`; frames from `rx` go out, priority lane first.
+/// A ping goes out every `ping_interval` ms, and pings and pongs from the peer
+/// count as signs of life, so quiet peers aren't pruned as idle. Pongs answering
+/// our latest ping give the peer's round trip time. Once `max_missed_pongs`
+/// pings in a row go unanswered the connection is given up as dead. Once the
+/// handshake agrees on MessagePack, frames go out as binary MessagePack;
+/// binary frames coming in are read as MessagePack whatever was agreed. Text
+/// and binary frame bytes each way are added to `traffic`.
+///
+/// A peer sending more than `rate_limit` frame bytes in a second (`0` = no
+/// limit), or a frame over the stream's size limits, is sent a close frame
+/// saying so; the returned [`Abuse`] tells which.
+async fn pump<S>(
+    ws_stream: WebSocketStream<S>,
+    rx: &mut Outbox,
+    peer: &Peer,
+    mesh: &Mesh,
+    traffic: &Traffic,
+    rate_limit: u64,
+) -> Option<Abuse>
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+{
+    let (mut write, mut read) = ws_stream.split();
+    let keepalive = parking_lot::Mutex::new(Keepalive::default());
+
+    let incoming = async {
+        let mut rate = RateWindow::new(rate_limit);
+        while let Some(msg) = read.next().await {
+            if let Ok(frame @ (Message::Text(_) | Message::Binary(_))) = &msg {
+                if !rate.allow(frame.len()) {
+                    tracing::info!(peer_id = %peer.id, url = %peer.url, limit = rate_limit, "Peer over its byte rate, closing connection");
+                    return Some(Abuse::RateLimited);
+                }
+            }
+            match msg {
+                Ok(Message::Text(text)) => {
+                    traffic.bytes_in.fetch_add(text.len() as u64, Ordering::Relaxed);
+                    if let Err(e) = mesh.hear(&text, Some(peer)).await {
+                        tracing::debug!(peer_id = %peer.id, error = %e, "Error handling message");
                     }
-                    Ok(Message::Close(_)) => {
-                        break;
+                }
+                Ok(Message::Binary(bytes)) => {
+                    traffic.bytes_in.fetch_add(bytes.len() as u64, Ordering::Relaxed);
+                    let heard = match wire::from_msgpack(&bytes) {
+                        Ok(text) => mesh.hear(&text, Some(peer)).await,
+                        Err(e) => mesh.invalid(Some(peer), &e.to_string()).await,
+                    };
+                    if let Err(e) = heard {
+                        tracing::debug!(peer_id = %peer.id, error = %e, "Error handling message");
                     }
-                    Err(e) => {
-                        eprintln!("WebSocket error from {}: {}", peer_for_read.id, e);
-                        break;
+                }
+                Ok(Message::Ping(_)) => {
+                    mesh.touch(&peer.id).await;
+                }
+                Ok(Message::Pong(payload)) => {
+                    let rtt = keepalive.lock().pong(&payload);
+                    match rtt {
+                        Some(rtt) => mesh.record_rtt(&peer.id, rtt).await,
+                        None => mesh.touch(&peer.id).await,
                     }
-                    _ => {}
                 }
+                Ok(Message::Close(_)) => {
+                    break;
+                }
+                Err(tokio_tungstenite::tungstenite::Error::Capacity(e)) => {
+                    tracing::info!(peer_id = %peer.id, url = %peer.url, error = %e, "Peer sent an oversized frame, closing connection");
+                    return Some(Abuse::TooLarge);
+                }
+                Err(e) => {
+                    tracing::debug!(peer_id = %peer.id, error = %e, "WebSocket error");
+                    break;
+                }
+                _ => {}
             }
-            // Cleanup on disconnect
-            let _ = mesh_clone.bye(&peer_for_read.id).await;
-        });
+        }
+        None
+    };
 
-        // Spawn task to send outgoing messages
-        tokio::spawn(async move {
-            while let Some(message) = rx.recv().await {
-                if write.send(Message::Text(message)).await.is_err() {
-                    break;
+    let (ping_interval, max_missed) = (mesh.options().ping_interval, mesh.options().max_missed_pongs);
+    let outgoing = async {
+        let mut ticker = tokio::time::interval(Duration::from_millis(ping_interval.max(1)));
+        ticker.tick().await; // first tick completes immediately
+        loop {
+            let frame = tokio::select! {
+                message = rx.recv() => match message {
+                    Some(message) => {
+                        let frame = match rx.encoding() {
+                            WireEncoding::MessagePack => match wire::to_msgpack(&message) {
+                                Ok(bytes) => Message::Binary(bytes),
+                                Err(e) => {
+                                    tracing::warn!(peer_id = %peer.id, error = %e, "Can't encode frame as MessagePack, sending JSON");
+                                    Message::Text(message.to_string())
+                                }
+                            },
+                            WireEncoding::Json => Message::Text(message.to_string()),
+                        };
+                        traffic.bytes_out.fetch_add(frame.len() as u64, Ordering::Relaxed);
+                        frame
+                    }
+                    None => break,
+                },
+                _ = ticker.tick(), if ping_interval > 0 => {
+                    let mut keepalive = keepalive.lock();
+                    if max_missed > 0 && keepalive.missed + u32::from(keepalive.unanswered.is_some()) >= max_missed {
+                        tracing::info!(peer_id = %peer.id, url = %peer.url, missed = max_missed, "Peer stopped answering pings, closing connection");
+                        return;
+                    }
+                    Message::Ping(keepalive.ping())
                 }
+            };
+            if write.send(frame).await.is_err() {
+                return;
             }
-        });
+        }
+        // The mesh dropped the peer: close the connection properly
+        let _ = write.close().await;
+    };
 
-        Ok(())
+    let abuse = tokio::select! {
+        abuse = incoming => abuse,
+        _ = outgoing => None,
+    };
+    if let Some(abuse) = abuse {
+        let (code, reason) = match abuse {
+            Abuse::TooLarge => (CloseCode::Size, "Frame too large"),
+            Abuse::RateLimited => (CloseCode::Policy, "Rate limit exceeded"),
+        };
+        let close = Message::Close(Some(CloseFrame { code, reason: reason.into() }));
+        let _ = tokio::time::timeout(Duration::from_secs(1), write.send(close)).await;
+    }
+    abuse
+}
+
+/// Certificate and key a [`ListenConfig`] serves `wss://` with
+///
+/// Both are PEM files: `cert` holds the certificate chain, leaf first, and
+/// `key` its PKCS#8 private key.
+#[derive(Clone, Debug)]
+pub struct TlsServerConfig {
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+impl TlsServerConfig {
+    pub fn new(cert: impl Into<PathBuf>, key: impl Into<PathBuf>) -> Self {
+        Self { cert: cert.into(), key: key.into() }
+    }
+
+    /// Load the certificate and key for TLS handshakes
+    fn acceptor(&self) -> GunResult<tokio_native_tls::TlsAcceptor> {
+        let read = |path: &PathBuf, what: &str| {
+            std::fs::read(path).map_err(|e| GunError::Tls(format!("Can't read {} {}: {}", what, path.display(), e)))
+        };
+        let (cert, key) = (read(&self.cert, "certificate")?, read(&self.key, "private key")?);
+        let identity = native_tls::Identity::from_pkcs8(&cert, &key).map_err(|e| {
+            GunError::Tls(format!("Invalid certificate {} or key {}: {}", self.cert.display(), self.key.display(), e))
+        })?;
+        let acceptor = native_tls::TlsAcceptor::new(identity)
+            .map_err(|e| GunError::Tls(format!("Can't set up TLS: {}", e)))?;
+        Ok(acceptor.into())
+    }
+}
+
+/// An address a [`WebSocketServer`] listens on
+///
+/// Upgrade requests are accepted for `path` and paths below it (`/gun`
+/// takes `/gun` and `/gun/x`, not `/gunx`); `/`, the default, takes every
+/// path. Others are answered `404 Not Found`. With `tls` set the listener
+/// serves `wss://` instead of `ws://`.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use gun::websocket::{ListenConfig, TlsServerConfig};
+///
+/// let listeners = vec![
+///     ListenConfig::new(([127, 0, 0, 1], 8765).into()).with_path("/gun"),
+///     ListenConfig::new(([0, 0, 0, 0], 8443).into())
+///         .with_path("/gun")
+///         .with_tls(TlsServerConfig::new("/etc/gun/cert.pem", "/etc/gun/key.pem")),
+/// ];
+/// ```
+#[derive(Clone, Debug)]
+pub struct ListenConfig {
+    /// Interface and port to bind; port `0` picks a free one, see
+    /// [`WebSocketServer::local_addrs`]
+    pub addr: SocketAddr,
+    /// URL path peers connect to. Default: `/` (any path)
+    pub path: String,
+    /// Serve `wss://` with this certificate. Default: `None` (plain `ws://`)
+    pub tls: Option<TlsServerConfig>,
+}
+
+impl ListenConfig {
+    pub fn new(addr: SocketAddr) -> Self {
+        Self { addr, path: "/".to_string(), tls: None }
+    }
+
+    /// Only accept connections to `path` and paths below it
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Serve `wss://` with this certificate and key
+    pub fn with_tls(mut self, tls: TlsServerConfig) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+}
+
+/// A bound listener's settings, shared by its connections
+struct Listener {
+    path: String, // Without a trailing `/`, empty for any path
+    tls: Option<tokio_native_tls::TlsAcceptor>,
+}
+
+impl Listener {
+    fn serves(&self, path: &str) -> bool {
+        match path.strip_prefix(self.path.as_str()) {
+            Some(rest) => rest.is_empty() || rest.starts_with('/'),
+            None => false,
+        }
     }
 }
 
@@ -197,6 +1262,14 @@ impl WebSocketClient {
 /// Runs a WebSocket server that listens for peer connections. Used when Gun is
 /// running in "super peer" (relay) mode to help other peers with NAT traversal.
 ///
+/// By default it listens on every interface on one port and accepts any path;
+/// [`with_listeners`](Self::with_listeners) picks the interfaces, paths and
+/// TLS of one or more listeners instead, all feeding the same mesh.
+///
+/// The server is a cheap handle: clones share the same listeners and
+/// connections, so one clone can run [`start`](Self::start) while another
+/// lists connections or shuts the server down.
+///
 /// # Example
 ///
 /// ```rust,no_run
@@ -204,116 +1277,523 @@ impl WebSocketClient {
 /// use gun::core::GunCore;
 /// use gun::dam::Mesh;
 /// use std::sync::Arc;
+/// use std::time::Duration;
 ///
 /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
 /// let core = Arc::new(GunCore::new());
 /// let mesh = Arc::new(Mesh::new(core.clone(), /* ... */));
 /// let server = WebSocketServer::new(core, mesh, 8080);
-/// server.start().await?;
+/// let task = tokio::spawn({
+///     let server = server.clone();
+///     async move { server.start().await }
+/// });
+///
+/// println!("{} peers connected", server.connection_count());
+/// server.shutdown(Duration::from_secs(5)).await;
+/// task.await??;
 /// # Ok(())
 /// # }
 /// ```
+#[derive(Clone)]
 pub struct WebSocketServer {
+    #[allow(dead_code)] // Kept for connection handlers that need the graph
     core: Arc<GunCore>,
     mesh: Arc<Mesh>,
-    port: u16,
+    listeners: Vec<ListenConfig>,
+    local_addrs: Arc<watch::Sender<Vec<SocketAddr>>>, // Bound addresses while running
+    connections: Arc<parking_lot::Mutex<HashMap<String, Connection>>>, // Open connections by peer ID
+    state: Arc<watch::Sender<ServerState>>,
+    auth: Option<ServerAuth>, // Decides which upgrade requests are accepted
+    subprotocols: Vec<String>, // Supported, most preferred first
+    abuse: Arc<AbuseCounters>,
+    #[cfg(feature = "metrics")]
+    storage_metrics: Option<Arc<StorageMetrics>>, // Storage counters for `/metrics`
+}
+
+/// Connections a [`WebSocketServer`] closed for misbehaving, see
+/// [`WebSocketServer::stats`]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ServerStats {
+    /// Connections closed for a frame or message over `max_frame_size` or
+    /// `max_message_size` (close code 1009)
+    pub frames_rejected: u64,
+    /// Connections closed for sending more than `max_bytes_per_sec` (close
+    /// code 1008)
+    pub rate_limited: u64,
+}
+
+#[derive(Default)]
+struct AbuseCounters {
+    frames_rejected: AtomicU64,
+    rate_limited: AtomicU64,
+}
+
+/// A connection accepted by a [`WebSocketServer`]
+#[derive(Clone, Debug)]
+pub struct ConnectionInfo {
+    /// ID of the connection's peer in the mesh
+    pub peer_id: String,
+    /// Address the connection came from
+    pub remote_addr: SocketAddr,
+    /// When the WebSocket handshake completed
+    pub connected_at: Instant,
+    /// Bytes of text and binary frames received from the peer
+    pub bytes_in: u64,
+    /// Bytes of text and binary frames sent to the peer
+    pub bytes_out: u64,
+}
+
+/// Frame bytes through a connection
+#[derive(Default)]
+struct Traffic {
+    bytes_in: AtomicU64,
+    bytes_out: AtomicU64,
+}
+
+/// An open server connection
+struct Connection {
+    remote_addr: SocketAddr,
+    connected_at: Instant,
+    traffic: Arc<Traffic>,
+}
+
+/// Where a server's accept loop is
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ServerState {
+    Idle,
+    Running,
+    Stopping(Duration), // grace period for open connections
+    Stopped,
+}
+
+/// Marks the server stopped however `start` ends: returned, failed or aborted
+struct StoppedOnDrop(Arc<watch::Sender<ServerState>>);
+
+impl Drop for StoppedOnDrop {
+    fn drop(&mut self) {
+        self.0.send_replace(ServerState::Stopped);
+    }
 }
 
 impl WebSocketServer {
+    /// Server listening on `port` on every interface, for any path
     pub fn new(core: Arc<GunCore>, mesh: Arc<Mesh>, port: u16) -> Self {
-        Self { core, mesh, port }
+        Self {
+            core,
+            mesh,
+            listeners: vec![ListenConfig::new(SocketAddr::from(([0, 0, 0, 0], port)))],
+            local_addrs: Arc::new(watch::channel(Vec::new()).0),
+            connections: Arc::new(parking_lot::Mutex::new(HashMap::new())),
+            state: Arc::new(watch::channel(ServerState::Idle).0),
+            auth: None,
+            subprotocols: Vec::new(),
+            abuse: Arc::new(AbuseCounters::default()),
+            #[cfg(feature = "metrics")]
+            storage_metrics: None,
+        }
+    }
+
+    /// Check every upgrade request with `auth` before accepting the connection
+    ///
+    /// Rejected requests get the hook's HTTP status and reason and never reach
+    /// the mesh. Accepted peers get the tags the hook returned.
+    pub fn with_auth(mut self, auth: ServerAuth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Subprotocols the server speaks, most preferred first
+    ///
+    /// The most preferred one a client offers in `Sec-WebSocket-Protocol`
+    /// is echoed back in the response. Clients offering none of them, or
+    /// none at all, are accepted without a subprotocol.
+    pub fn with_subprotocols(mut self, subprotocols: Vec<String>) -> Self {
+        self.subprotocols = subprotocols;
+        self
+    }
+
+    /// Include these storage counters in `/metrics`
+    #[cfg(feature = "metrics")]
+    pub fn with_storage_metrics(mut self, metrics: Arc<StorageMetrics>) -> Self {
+        self.storage_metrics = Some(metrics);
+        self
+    }
+
+    /// Listen on these addresses instead of the port given to [`new`](Self::new)
+    pub fn with_listeners(mut self, listeners: Vec<ListenConfig>) -> Self {
+        self.listeners = listeners;
+        self
     }
 
     /// Start the WebSocket server
+    ///
+    /// Binds every listener, failing if any can't be bound or its TLS
+    /// certificate can't be loaded, then runs one accept loop per listener
+    /// until [`shutdown`](Self::shutdown) is called. Returns right away if
+    /// it already was. Connections are served by tasks owned by this call,
+    /// so dropping or aborting it closes them too.
     pub async fn start(&self) -> GunResult<()> {
-        let addr = format!("0.0.0.0:{}", self.port);
-        let listener = TcpListener::bind(&addr).await?;
-        println!("Gun.rs WebSocket server listening on ws://{}", addr);
+        let started = self.state.send_if_modified(|state| {
+            let idle = *state == ServerState::Idle;
+            if idle {
+                *state = ServerState::Running;
+            }
+            idle
+        });
+        if !started {
+            return Ok(());
+        }
+        let _stopped = StoppedOnDrop(self.state.clone());
+
+        let mut bound = Vec::with_capacity(self.listeners.len());
+        for config in &self.listeners {
+            let tls = config.tls.as_ref().map(TlsServerConfig::acceptor).transpose()?;
+            let listener = TcpListener::bind(config.addr).await?;
+            let addr = listener.local_addr()?;
+            let path = config.path.trim_end_matches('/');
+            let path = if path.is_empty() || path.starts_with('/') { path.to_string() } else { format!("/{}", path) };
+            tracing::info!(addr = %addr, path = %config.path, tls = tls.is_some(), "Gun.rs WebSocket server listening");
+            bound.push((listener, addr, Arc::new(Listener { path, tls })));
+        }
+        let addrs: Vec<SocketAddr> = bound.iter().map(|(_, addr, _)| *addr).collect();
 
-        while let Ok((stream, addr)) = listener.accept().await {
-            let core = self.core.clone();
-            let mesh = self.mesh.clone();
-            tokio::spawn(Self::handle_connection(stream, addr, core, mesh));
+        // One accept loop per listener, all handing connections to this task
+        let (accepted_tx, mut accepted_rx) = tokio::sync::mpsc::channel(64);
+        let mut accept_loops = tokio::task::JoinSet::new();
+        for (listener, addr, settings) in bound {
+            let accepted_tx = accepted_tx.clone();
+            accept_loops.spawn(async move {
+                loop {
+                    match listener.accept().await {
+                        Ok((stream, remote_addr)) => {
+                            if accepted_tx.send((stream, remote_addr, settings.clone())).await.is_err() {
+                                return;
+                            }
+                        }
+                        Err(e) => {
+                            tracing::warn!(addr = %addr, error = %e, "Listener failed, no longer accepting on it");
+                            return;
+                        }
+                    }
+                }
+            });
         }
+        drop(accepted_tx);
+        self.local_addrs.send_replace(addrs.clone());
+
+        let mut state = self.state.subscribe();
+        let mut connections = tokio::task::JoinSet::new();
+        let grace = loop {
+            tokio::select! {
+                accepted = accepted_rx.recv() => {
+                    let Some((stream, addr, listener)) = accepted else {
+                        break Duration::ZERO; // every listener failed
+                    };
+                    connections.spawn(self.clone().handle_connection(stream, addr, listener));
+                }
+                // Reap finished connections
+                Some(_) = connections.join_next(), if !connections.is_empty() => {}
+                stopping = state.wait_for(|state| matches!(state, ServerState::Stopping(_))) => {
+                    break match stopping.as_deref() {
+                        Ok(ServerState::Stopping(grace)) => *grace,
+                        _ => Duration::ZERO,
+                    };
+                }
+            }
+        };
 
+        // Free the ports, then close every connection: each writes out what is
+        // queued for it, then a close frame
+        accept_loops.shutdown().await;
+        self.local_addrs.send_replace(Vec::new());
+        tracing::info!(addrs = ?addrs, connections = self.connection_count(), "WebSocket server shutting down");
+        let peer_ids: Vec<String> = self.connections.lock().keys().cloned().collect();
+        for peer_id in peer_ids {
+            let _ = self.mesh.bye(&peer_id).await;
+        }
+        let drained = tokio::time::timeout(grace, async {
+            while connections.join_next().await.is_some() {}
+        })
+        .await;
+        if drained.is_err() {
+            tracing::warn!(remaining = connections.len(), grace = ?grace, "Connections still open after grace period, dropping them");
+        }
+        connections.shutdown().await;
+        self.connections.lock().clear();
         Ok(())
     }
 
-    async fn handle_connection(
-        stream: TcpStream,
-        addr: std::net::SocketAddr,
-        _core: Arc<GunCore>,
-        mesh: Arc<Mesh>,
-    ) {
-        let ws_stream = match accept_async(stream).await {
+    /// Stop accepting connections and close the open ones
+    ///
+    /// Open connections are sent what is queued for them and a close frame;
+    /// those still open after `grace` are dropped. Returns once `start` has
+    /// returned and the port is free. Safe to call more than once, or
+    /// without `start`.
+    pub async fn shutdown(&self, grace: Duration) {
+        let mut state = self.state.subscribe();
+        self.state.send_if_modified(|state| {
+            let next = match *state {
+                ServerState::Idle => ServerState::Stopped,
+                ServerState::Running => ServerState::Stopping(grace),
+                _ => return false,
+            };
+            *state = next;
+            true
+        });
+        let _ = state.wait_for(|state| *state == ServerState::Stopped).await;
+    }
+
+    /// Addresses the listeners are bound to, e.g. with the port picked for
+    /// port `0`
+    ///
+    /// Empty until [`start`](Self::start) has bound every listener, and again
+    /// once it stops.
+    pub fn local_addrs(&self) -> Vec<SocketAddr> {
+        self.local_addrs.borrow().clone()
+    }
+
+    /// Wait until [`start`](Self::start) has bound every listener
+    ///
+    /// Returns the bound addresses, or nothing if the server stopped or
+    /// failed to start first.
+    pub async fn listening(&self) -> Vec<SocketAddr> {
+        let mut addrs = self.local_addrs.subscribe();
+        let mut state = self.state.subscribe();
+        tokio::select! {
+            bound = addrs.wait_for(|addrs| !addrs.is_empty()) => bound.map(|addrs| addrs.clone()).unwrap_or_default(),
+            _ = state.wait_for(|state| *state == ServerState::Stopped) => self.local_addrs(),
+        }
+    }
+
+    /// Open connections, oldest first
+    pub fn connections(&self) -> Vec<ConnectionInfo> {
+        let mut connections: Vec<ConnectionInfo> = self
+            .connections
+            .lock()
+            .iter()
+            .map(|(peer_id, connection)| ConnectionInfo {
+                peer_id: peer_id.clone(),
+                remote_addr: connection.remote_addr,
+                connected_at: connection.connected_at,
+                bytes_in: connection.traffic.bytes_in.load(Ordering::Relaxed),
+                bytes_out: connection.traffic.bytes_out.load(Ordering::Relaxed),
+            })
+            .collect();
+        connections.sort_by_key(|connection| connection.connected_at);
+        connections
+    }
+
+    /// Counts of connections closed for misbehaving
+    pub fn stats(&self) -> ServerStats {
+        ServerStats {
+            frames_rejected: self.abuse.frames_rejected.load(Ordering::Relaxed),
+            rate_limited: self.abuse.rate_limited.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Number of open connections
+    pub fn connection_count(&self) -> usize {
+        self.connections.lock().len()
+    }
+
+    /// Close the connection of a peer
+    ///
+    /// The peer is removed from the mesh and sent what is queued for it, then
+    /// a close frame. Returns `false` if no connection to this server has that
+    /// peer ID.
+    pub async fn close_connection(&self, peer_id: &str) -> bool {
+        if !self.connections.lock().contains_key(peer_id) {
+            return false;
+        }
+        tracing::info!(peer_id = %peer_id, "Closing connection");
+        let _ = self.mesh.bye(peer_id).await;
+        true
+    }
+
+    /// Our most preferred subprotocol among those the request offers
+    fn pick_subprotocol(&self, request: &Request) -> Option<HeaderValue> {
+        let offered: Vec<&str> = request
+            .headers()
+            .get_all(SEC_WEBSOCKET_PROTOCOL)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .collect();
+        let chosen = self.subprotocols.iter().find(|ours| offered.contains(&ours.as_str()))?;
+        HeaderValue::from_str(chosen).ok()
+    }
+
+    fn is_stopping(&self) -> bool {
+        !matches!(*self.state.borrow(), ServerState::Running)
+    }
+
+    /// Answer plain HTTP `GET`s of `/metrics` and `/healthz`
+    ///
+    /// Anything else is handed back for the WebSocket handshake, along with
+    /// what was read of it; `None` once the request was answered.
+    #[cfg(feature = "metrics")]
+    async fn serve_http<S>(&self, mut stream: S, addr: SocketAddr) -> Option<metrics::Replay<S>>
+    where
+        S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+    {
+        let head = metrics::read_head(&mut stream).await.ok()?;
+        let response = match metrics::plain_get(&head) {
+            Some("/metrics") => {
+                let storage = self.storage_metrics.as_ref().map(|metrics| metrics.snapshot());
+                let page = metrics::render(
+                    &self.mesh.stats(),
+                    self.core.graph.len(),
+                    storage.as_ref(),
+                    &self.stats(),
+                    &self.connections(),
+                );
+                metrics::response("200 OK", "text/plain; version=0.0.4; charset=utf-8", &page)
+            }
+            Some("/healthz") if self.is_stopping() => metrics::response("503 Service Unavailable", "text/plain", "stopping\n"),
+            Some("/healthz") => metrics::response("200 OK", "text/plain", "ok\n"),
+            _ => return Some(metrics::Replay::new(head, stream)),
+        };
+        tracing::trace!(addr = %addr, "Answered HTTP request");
+        let _ = stream.write_all(response.as_bytes()).await;
+        let _ = stream.shutdown().await;
+        None
+    }
+
+    async fn handle_connection(self, stream: TcpStream, addr: SocketAddr, listener: Arc<Listener>) {
+        let mesh = self.mesh.clone();
+        let peer_url = format!("ws://{}", addr);
+        if mesh.is_banned(&peer_url) {
+            tracing::info!(addr = %addr, "Refusing connection from banned peer");
+            return;
+        }
+        let stream = match &listener.tls {
+            Some(acceptor) => match acceptor.accept(stream).await {
+                Ok(tls_stream) => MaybeTlsStream::NativeTls(tls_stream),
+                Err(e) => {
+                    tracing::debug!(addr = %addr, error = %e, "TLS handshake failed");
+                    return;
+                }
+            },
+            None => MaybeTlsStream::Plain(stream),
+        };
+        #[cfg(feature = "metrics")]
+        let Some(stream) = self.serve_http(stream, addr).await else {
+            return;
+        };
+
+        let mut tags = Vec::new();
+        #[allow(clippy::result_large_err)] // the signature tungstenite's handshake callback takes
+        let check = |request: &Request, mut response: Response| {
+            if !listener.serves(request.uri().path()) {
+                tracing::debug!(addr = %addr, path = %request.uri().path(), "Rejected connection to unknown path");
+                return Err(rejection(404, "Not Found".to_string()));
+            }
+            if let Some(chosen) = self.pick_subprotocol(request) {
+                response.headers_mut().insert(SEC_WEBSOCKET_PROTOCOL, chosen);
+            }
+            let Some(auth) = &self.auth else {
+                return Ok(response);
+            };
+            match auth(&auth_request(request, addr)) {
+                AuthDecision::Accept(granted) => {
+                    tags = granted;
+                    Ok(response)
+                }
+                AuthDecision::Reject(status, reason) => {
+                    tracing::info!(addr = %addr, status, reason = %reason, "Rejected connection");
+                    Err(rejection(status, reason))
+                }
+            }
+        };
+        // Oversized frames are refused from their header, before their
+        // payload is buffered
+        let options = mesh.options();
+        let config = WebSocketConfig {
+            max_message_size: Some(options.max_message_size).filter(|max| *max > 0),
+            max_frame_size: Some(options.max_frame_size).filter(|max| *max > 0),
+            ..Default::default()
+        };
+        let accepted = accept_hdr_async_with_config(stream, check, Some(config)).await;
+        let mut ws_stream = match accepted {
             Ok(ws) => ws,
             Err(e) => {
-                eprintln!("Error accepting WebSocket connection: {}", e);
+                tracing::debug!(error = %e, "Error accepting WebSocket connection");
                 return;
             }
         };
+        if self.is_stopping() {
+            let _ = ws_stream.close(None).await;
+            return;
+        }
 
-        let peer_url = format!("ws://{}", addr);
-        let mut peer = Peer::new(peer_url.clone());
+        let mut peer = Peer::new(peer_url);
+        peer.tags = tags.into_iter().collect();
         let peer_id = peer.id.clone();
 
-        // Create channel for sending messages
-        let (tx, mut rx) = mpsc::unbounded_channel();
-        peer.set_sender(tx.clone());
+        // Create channels for sending messages. Only the mesh keeps the sender, so
+        // removing the peer closes the connection.
+        let (tx, mut rx) = Outbox::new();
 
         if let Err(e) = mesh.hi(peer.clone()).await {
-            eprintln!("Error adding peer: {}", e);
+            tracing::warn!(error = %e, "Error adding peer");
             return;
         }
 
         // Set sender in mesh
-        if let Err(e) = mesh.set_peer_sender(&peer_id, tx.clone()).await {
-            eprintln!("Error setting peer sender: {}", e);
+        if let Err(e) = mesh.set_peer_sender(&peer_id, tx).await {
+            tracing::warn!(error = %e, "Error setting peer sender");
         }
 
-        let (mut write, mut read) = ws_stream.split();
-
-        // Spawn task to handle incoming messages
-        let mesh_clone = mesh.clone();
-        let peer_for_read = peer.clone();
-        let handle_task = tokio::spawn(async move {
-            while let Some(msg) = read.next().await {
-                match msg {
-                    Ok(Message::Text(text)) => {
-                        if let Err(e) = mesh_clone.hear(&text, Some(&peer_for_read)).await {
-                            eprintln!("Error handling message: {}", e);
-                        }
-                    }
-                    Ok(Message::Close(_)) => {
-                        break;
-                    }
-                    Err(e) => {
-                        eprintln!("WebSocket error: {}", e);
-                        break;
-                    }
-                    _ => {}
-                }
-            }
-        });
-
-        // Spawn task to send outgoing messages
-        let send_task = tokio::spawn(async move {
-            while let Some(message) = rx.recv().await {
-                if write.send(Message::Text(message)).await.is_err() {
-                    break;
-                }
-            }
-        });
-
-        // Wait for either task to complete (connection closed)
-        tokio::select! {
-            _ = handle_task => {},
-            _ = send_task => {},
+        let traffic = Arc::new(Traffic::default());
+        self.connections.lock().insert(
+            peer_id.clone(),
+            Connection {
+                remote_addr: addr,
+                connected_at: Instant::now(),
+                traffic: traffic.clone(),
+            },
+        );
+        // The server started shutting down before this connection was listed
+        if self.is_stopping() {
+            let _ = mesh.bye(&peer_id).await;
         }
 
+        // Serve until the connection closes
+        let abuse = pump(ws_stream, &mut rx, &peer, &mesh, &traffic, mesh.options().max_bytes_per_sec).await;
+        match abuse {
+            Some(Abuse::TooLarge) => self.abuse.frames_rejected.fetch_add(1, Ordering::Relaxed),
+            Some(Abuse::RateLimited) => self.abuse.rate_limited.fetch_add(1, Ordering::Relaxed),
+            None => 0,
+        };
+
         // Cleanup on disconnect
+        self.connections.lock().remove(&peer_id);
         if let Err(e) = mesh.bye(&peer_id).await {
-            eprintln!("Error removing peer: {}", e);
+            tracing::warn!(error = %e, "Error removing peer");
         }
     }
 }
+
+/// What an auth hook sees of an upgrade request
+fn auth_request(request: &Request, remote_addr: SocketAddr) -> AuthRequest {
+    AuthRequest {
+        path: request.uri().path().to_string(),
+        query: url::form_urlencoded::parse(request.uri().query().unwrap_or("").as_bytes())
+            .into_owned()
+            .collect(),
+        headers: request
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| Some((name.as_str().to_string(), value.to_str().ok()?.to_string())))
+            .collect(),
+        remote_addr,
+    }
+}
+
+/// HTTP answer to a rejected upgrade request; invalid statuses become 403
+fn rejection(status: u16, reason: String) -> ErrorResponse {
+    let mut response = ErrorResponse::new(Some(reason));
+    *response.status_mut() = StatusCode::from_u16(status).unwrap_or(StatusCode::FORBIDDEN);
+    response
+}
@@ -0,0 +1,128 @@
+//! Wire encodings for DAM messages
+//!
+//! Messages travel as JSON text by default. Peers that both set
+//! `MeshOptions::msgpack` advertise `enc: ["msgpack", "json"]` in the DAM `?`
+//! handshake and from then on send each other binary MessagePack frames, with
+//! signatures and public keys as raw bytes instead of hex.
+//!
+//! The mesh itself only deals in JSON: a WebSocket connection converts frames
+//! to MessagePack on their way out and back to JSON on their way in. A
+//! message's `#` hashes the JSON serialization of its fields, keys sorted,
+//! which is the same whichever encoding carried the message, so mixed meshes
+//! verify and dedup messages alike.
+
+use crate::error::{GunError, GunResult};
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+
+/// Name of MessagePack in the `enc` list of a DAM `?` message
+pub const MSGPACK: &str = "msgpack";
+
+/// Name of JSON in the `enc` list of a DAM `?` message
+pub const JSON: &str = "json";
+
+/// How the frames of a connection are encoded
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub enum WireEncoding {
+    /// JSON text frames, which every Gun peer reads
+    #[default]
+    Json,
+    /// Binary MessagePack frames, agreed on in the `?` handshake
+    MessagePack,
+}
+
+/// Convert a JSON frame (a message, or a batch of them) to MessagePack
+///
+/// Lowercase hex strings under `sigs` are sent as raw bytes.
+///
+/// # Example
+///
+/// ```rust
+/// use gun::wire::{from_msgpack, to_msgpack};
+///
+/// let frame = r##"{"#":"abc","put":{"n":1}}"##;
+/// assert_eq!(from_msgpack(&to_msgpack(frame).unwrap()).unwrap(), frame);
+/// ```
+pub fn to_msgpack(frame: &str) -> GunResult<Vec<u8>> {
+    let value: Value = serde_json::from_str(frame)?;
+    let mut bytes = Vec::with_capacity(frame.len() / 2);
+    rmpv::encode::write_value(&mut bytes, &pack(&value, false))
+        .map_err(|e| GunError::InvalidData(format!("Can't encode MessagePack: {}", e)))?;
+    Ok(bytes)
+}
+
+/// Convert a MessagePack frame back to the JSON text the mesh reads
+///
+/// Raw bytes become lowercase hex strings. Fails on anything JSON can't
+/// hold: non-string map keys, extension types, NaN or infinite floats.
+pub fn from_msgpack(frame: &[u8]) -> GunResult<String> {
+    let mut rest = frame;
+    let value = rmpv::decode::read_value(&mut rest)
+        .map_err(|e| GunError::InvalidData(format!("Invalid MessagePack: {}", e)))?;
+    if !rest.is_empty() {
+        return Err(GunError::InvalidData("Trailing bytes after MessagePack value".to_string()));
+    }
+    Ok(serde_json::to_string(&unpack(value)?)?)
+}
+
+fn pack(value: &Value, in_sigs: bool) -> rmpv::Value {
+    match value {
+        Value::Null => rmpv::Value::Nil,
+        Value::Bool(b) => rmpv::Value::Boolean(*b),
+        Value::Number(n) => match (n.as_u64(), n.as_i64()) {
+            (Some(n), _) => rmpv::Value::from(n),
+            (_, Some(n)) => rmpv::Value::from(n),
+            _ => rmpv::Value::F64(n.as_f64().unwrap_or_default()),
+        },
+        // Only lowercase hex, so the bytes turn back into the same string
+        Value::String(s) if in_sigs && !s.is_empty() && !s.bytes().any(|b| b.is_ascii_uppercase()) => {
+            match hex::decode(s) {
+                Ok(bytes) => rmpv::Value::Binary(bytes),
+                Err(_) => rmpv::Value::from(s.as_str()),
+            }
+        }
+        Value::String(s) => rmpv::Value::from(s.as_str()),
+        Value::Array(items) => rmpv::Value::Array(items.iter().map(|item| pack(item, in_sigs)).collect()),
+        Value::Object(fields) => rmpv::Value::Map(
+            fields
+                .iter()
+                .map(|(key, value)| (rmpv::Value::from(key.as_str()), pack(value, in_sigs || key == "sigs")))
+                .collect(),
+        ),
+    }
+}
+
+fn unpack(value: rmpv::Value) -> GunResult<Value> {
+    let unsupported = |what: &str| GunError::InvalidData(format!("MessagePack {} can't be read as JSON", what));
+    let float = |f: f64| {
+        Number::from_f64(f)
+            .map(Value::Number)
+            .ok_or_else(|| unsupported("NaN or infinite float"))
+    };
+    Ok(match value {
+        rmpv::Value::Nil => Value::Null,
+        rmpv::Value::Boolean(b) => Value::Bool(b),
+        rmpv::Value::Integer(n) => match (n.as_u64(), n.as_i64()) {
+            (Some(n), _) => n.into(),
+            (_, Some(n)) => n.into(),
+            _ => return Err(unsupported("integer")),
+        },
+        rmpv::Value::F32(f) => float(f64::from(f))?,
+        rmpv::Value::F64(f) => float(f)?,
+        rmpv::Value::String(s) => Value::String(s.into_str().ok_or_else(|| unsupported("string that isn't UTF-8"))?),
+        rmpv::Value::Binary(bytes) => Value::String(hex::encode(bytes)),
+        rmpv::Value::Array(items) => Value::Array(items.into_iter().map(unpack).collect::<GunResult<_>>()?),
+        rmpv::Value::Map(entries) => {
+            let mut fields = Map::with_capacity(entries.len());
+            for (key, value) in entries {
+                let rmpv::Value::String(key) = key else {
+                    return Err(unsupported("map key that isn't a string"));
+                };
+                let key = key.into_str().ok_or_else(|| unsupported("string that isn't UTF-8"))?;
+                fields.insert(key, unpack(value)?);
+            }
+            Value::Object(fields)
+        }
+        rmpv::Value::Ext(..) => return Err(unsupported("extension type")),
+    })
+}
@@ -13,13 +13,31 @@ fn test_gun_core_new() {
     assert!(!uuid.is_empty());
 }
 
-#[test]
-fn test_gun_core_with_storage() {
+#[tokio::test]
+async fn test_gun_core_with_storage() {
+    use gun::storage::Storage;
+
     let storage = Arc::new(MemoryStorage::new());
-    let core = GunCore::with_storage(storage);
+    let core = GunCore::with_storage(storage.clone());
+
+    // Should use the given storage
+    storage
+        .put("n", &gun::state::Node::with_soul("n".to_string()))
+        .await
+        .unwrap();
+    assert!(core.storage().has("n").await.unwrap());
+}
 
-    // Should have storage
-    assert!(core.storage.is_some());
+#[tokio::test]
+async fn test_gun_core_new_uses_memory_storage() {
+    let core = GunCore::new();
+    core.persist(gun::persist::StorageWrite::Put {
+        soul: "n".to_string(),
+        node: gun::state::Node::with_soul("n".to_string()),
+    })
+    .await
+    .unwrap();
+    assert!(core.storage().has("n").await.unwrap());
 }
 
 #[test]
@@ -1,7 +1,8 @@
 //! Comprehensive tests for message deduplication
 //! Tests duplicate detection, message tracking, and expiration
 
-use gun::dup::Dup;
+use gun::dup::{Dup, DupOptions};
+use std::time::Duration;
 
 #[test]
 fn test_dup_new_default() {
@@ -129,3 +130,62 @@ fn test_dup_default() {
     let dup = Dup::default();
     assert!(!dup.check("test"));
 }
+
+#[test]
+fn test_dup_sweeps_expired_on_insert() {
+    let mut dup = Dup::with_options(DupOptions {
+        max_entries: 1000,
+        ttl: Duration::from_millis(50),
+    });
+
+    for i in 0..100 {
+        dup.track(&format!("old{}", i));
+    }
+    std::thread::sleep(Duration::from_millis(80));
+
+    // The table isn't full, but a ttl has passed since the last sweep
+    dup.track("fresh");
+    let stats = dup.stats();
+    assert_eq!(stats.entries, 1);
+    assert_eq!(stats.expired, 100);
+    assert_eq!(stats.evicted, 0);
+}
+
+#[test]
+fn test_dup_stays_bounded_under_a_million_ids() {
+    let max_entries = 10_000;
+    let mut dup = Dup::with_options(DupOptions {
+        max_entries,
+        ttl: Duration::from_secs(60),
+    });
+
+    let total = 1_000_000;
+    for i in 0..total {
+        dup.track(&format!("msg{}", i));
+        if i % 100_000 == 0 {
+            assert!(dup.stats().entries <= max_entries);
+        }
+    }
+
+    let stats = dup.stats();
+    assert!(stats.entries <= max_entries, "{} entries", stats.entries);
+    assert_eq!(stats.tracked, total as u64);
+    assert_eq!(stats.evicted as usize, total - stats.entries);
+
+    // The most recent ids are still caught, the oldest were evicted
+    for i in (total - 1000)..total {
+        assert!(dup.check(&format!("msg{}", i)), "msg{} forgotten", i);
+    }
+    assert!(!dup.check("msg0"));
+    assert_eq!(dup.stats().duplicates, 1000);
+}
+
+#[test]
+fn test_dup_zero_entries_keeps_only_the_latest_id() {
+    let mut dup = Dup::new(0, 9000);
+    assert!(dup.track("msg1"));
+    assert!(dup.track("msg2"));
+    assert!(dup.check("msg2"));
+    assert!(!dup.check("msg1"));
+    assert_eq!(dup.stats().entries, 1);
+}
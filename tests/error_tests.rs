@@ -2,6 +2,7 @@
 //! Tests all error variants and error propagation
 
 use gun::error::GunError;
+use gun::storage::mock::{MockStorage, StorageCall, StorageOp};
 use gun::storage::{MemoryStorage, Storage};
 use gun::Gun;
 use serde_json::json;
@@ -27,6 +28,13 @@ fn test_gun_error_network() {
     assert!(err.to_string().contains("network"));
 }
 
+#[test]
+fn test_gun_error_invalid_peer_url() {
+    let err = gun::websocket::normalize_peer_url("ftp://relay.example.com", false).unwrap_err();
+    assert!(matches!(err, GunError::InvalidPeerUrl(_)));
+    assert!(err.to_string().contains("ftp://relay.example.com"));
+}
+
 #[test]
 fn test_gun_error_storage() {
     // Storage error uses sled::Error which we can't easily create in tests
@@ -49,6 +57,16 @@ fn test_gun_error_crypto() {
     assert!(err.to_string().contains("crypto"));
 }
 
+#[test]
+fn test_gun_error_from_sea_error_keeps_variant() {
+    let err: GunError = gun::sea::SeaError::WrongPassword.into();
+    assert!(matches!(err, GunError::Sea(gun::sea::SeaError::WrongPassword)));
+    assert!(err.to_string().contains("Wrong password"));
+
+    let err: GunError = gun::sea::SeaError::CertInvalid { reason: "Missing certificants".to_string() }.into();
+    assert!(matches!(err, GunError::Sea(gun::sea::SeaError::CertInvalid { ref reason }) if reason == "Missing certificants"));
+}
+
 #[tokio::test]
 async fn test_error_propagation_storage() {
     // Test that storage errors propagate correctly
@@ -64,7 +82,8 @@ async fn test_error_propagation_storage() {
 
 #[tokio::test]
 async fn test_error_handling_invalid_json() {
-    let gun = Gun::new();
+    let secret_key = chia_bls::SecretKey::from_seed(&[0u8; 32]);
+    let gun = Gun::new(secret_key.clone(), secret_key.public_key());
     let chain = gun.get("test");
 
     // Valid JSON should work
@@ -105,7 +124,8 @@ async fn test_error_handling_user_auth_wrong_password() {
     use gun::Gun;
     use std::sync::Arc;
     
-    let gun = Gun::new();
+    let secret_key = chia_bls::SecretKey::from_seed(&[0u8; 32]);
+    let gun = Gun::new(secret_key.clone(), secret_key.public_key());
     let chain = gun.root();
     
     // Try to authenticate non-existent user
@@ -153,7 +173,8 @@ async fn test_error_handling_invalid_certificate_format() {
 
 #[tokio::test]
 async fn test_error_handling_chain_operations() {
-    let gun = Gun::new();
+    let secret_key = chia_bls::SecretKey::from_seed(&[0u8; 32]);
+    let gun = Gun::new(secret_key.clone(), secret_key.public_key());
     let chain = gun.get("error_test");
     
     // Valid operations should work
@@ -171,7 +192,8 @@ async fn test_error_handling_chain_operations() {
 
 #[tokio::test]
 async fn test_error_handling_graceful_degradation() {
-    let gun = Gun::new();
+    let secret_key = chia_bls::SecretKey::from_seed(&[0u8; 32]);
+    let gun = Gun::new(secret_key.clone(), secret_key.public_key());
     let chain = gun.get("degradation_test");
     
     // Operations should continue even if some fail
@@ -187,7 +209,8 @@ async fn test_error_handling_graceful_degradation() {
 
 #[tokio::test]
 async fn test_error_handling_concurrent_errors() {
-    let gun = Gun::new();
+    let secret_key = chia_bls::SecretKey::from_seed(&[0u8; 32]);
+    let gun = Gun::new(secret_key.clone(), secret_key.public_key());
     
     // Multiple concurrent operations that might error
     let mut handles = vec![];
@@ -292,3 +315,214 @@ async fn test_error_handling_storage_operations() {
     assert!(result.is_ok(), "Missing data should return Ok(None)");
     assert!(result.unwrap().is_none(), "Missing data should be None");
 }
+
+// ========== Storage Fault Tests ==========
+
+async fn gun_with_mock_storage(storage: Arc<MockStorage>, background_persistence: bool) -> Gun {
+    let secret_key = chia_bls::SecretKey::from_seed(&[0u8; 32]);
+    let options = gun::GunOptions {
+        storage: Some(storage),
+        background_persistence,
+        ..Default::default()
+    };
+    Gun::with_options(secret_key.clone(), secret_key.public_key(), options)
+        .await
+        .unwrap()
+}
+
+async fn read_key(gun: &Gun, soul: &str, key: &str) -> serde_json::Value {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let tx = parking_lot::Mutex::new(Some(tx));
+    gun.get(soul).get(key).once(move |data, _| {
+        if let Some(tx) = tx.lock().take() {
+            let _ = tx.send(data);
+        }
+    }).await.unwrap();
+    rx.await.unwrap()
+}
+
+#[tokio::test]
+async fn test_chain_put_surfaces_storage_errors() {
+    let storage = Arc::new(MockStorage::new());
+    let gun = gun_with_mock_storage(storage.clone(), false).await;
+
+    gun.get("user").put(json!({"name": "Alice", "age": 30})).await.unwrap();
+    storage.fail_next(StorageOp::PutDelta, 1);
+
+    let result = gun.get("user").put(json!({"name": "Bob"})).await;
+    assert!(matches!(result, Err(GunError::Storage(_))), "storage error must reach the caller");
+
+    // The graph keeps the complete node with the new value; storage keeps the old one
+    assert_eq!(read_key(&gun, "user", "name").await, json!("Bob"));
+    assert_eq!(read_key(&gun, "user", "age").await, json!(30));
+    let stored = storage.get("user").await.unwrap().unwrap();
+    assert_eq!(stored.data.get("name"), Some(&json!("Alice")));
+    assert_eq!(stored.data.get("age"), Some(&json!(30)));
+
+    // The next write goes through and storage catches up
+    gun.get("user").put(json!({"name": "Carol"})).await.unwrap();
+    let stored = storage.get("user").await.unwrap().unwrap();
+    assert_eq!(stored.data.get("name"), Some(&json!("Carol")));
+    assert_eq!(stored.data.get("age"), Some(&json!(30)));
+
+    let write_results: Vec<bool> = storage
+        .calls()
+        .iter()
+        .filter(|call| call.op == StorageOp::PutDelta)
+        .map(|call| call.ok)
+        .collect();
+    assert_eq!(write_results, vec![true, false, true]);
+}
+
+#[tokio::test]
+async fn test_chain_put_storage_error_keeps_graph_readable() {
+    let storage = Arc::new(MockStorage::new());
+    let gun = gun_with_mock_storage(storage.clone(), false).await;
+
+    gun.get("doc").put(json!({"title": "Draft", "body": "..."})).await.unwrap();
+    storage.fail_next(StorageOp::PutDelta, 1);
+    assert!(gun.get("doc").get("title").put(json!("Final")).await.is_err());
+
+    assert_eq!(read_key(&gun, "doc", "body").await, json!("..."));
+    assert_eq!(read_key(&gun, "doc", "title").await, json!("Final"));
+}
+
+#[tokio::test]
+async fn test_read_through_surfaces_corrupted_storage() {
+    let storage = Arc::new(MockStorage::new());
+    storage
+        .put("stored", &gun::state::Node::with_soul("stored".to_string()))
+        .await
+        .unwrap();
+    storage.set_corrupt_reads(true);
+    let gun = gun_with_mock_storage(storage.clone(), true).await;
+
+    let result = gun.get("stored").once(|_, _| {}).await;
+    assert!(matches!(result, Err(GunError::Serialization(_))));
+}
+
+#[tokio::test]
+async fn test_background_persistence_keeps_storage_out_of_put() {
+    let latency = std::time::Duration::from_millis(100);
+    let mut durations = Vec::new();
+    for background in [false, true] {
+        let storage = Arc::new(MockStorage::new());
+        let gun = gun_with_mock_storage(storage.clone(), background).await;
+        gun.get("doc").put(json!({"rev": 0})).await.unwrap();
+        storage.set_latency(Some(latency));
+
+        let start = std::time::Instant::now();
+        gun.get("doc").put(json!({"rev": 1})).await.unwrap();
+        durations.push(start.elapsed());
+
+        // Either way the write is in storage once flushed
+        gun.flush().await.unwrap();
+        storage.set_latency(None);
+        let stored = storage.get("doc").await.unwrap().unwrap();
+        assert_eq!(stored.data.get("rev"), Some(&json!(1)));
+    }
+
+    assert!(durations[0] >= latency, "synchronous put waits for storage: {:?}", durations);
+    assert!(durations[1] < latency / 2, "background put doesn't: {:?}", durations);
+}
+
+#[tokio::test]
+async fn test_background_persistence_retries_transient_failures() {
+    let storage = Arc::new(MockStorage::new());
+    let gun = gun_with_mock_storage(storage.clone(), true).await;
+    let errors = Arc::new(parking_lot::Mutex::new(Vec::new()));
+    let errors_clone = errors.clone();
+    gun.on_storage_error(move |error| errors_clone.lock().push(error.clone()));
+
+    storage.fail_next(StorageOp::PutDelta, 2);
+    gun.get("user").put(json!({"name": "Alice"})).await.unwrap();
+    gun.flush().await.unwrap();
+
+    let stored = storage.get("user").await.unwrap().unwrap();
+    assert_eq!(stored.data.get("name"), Some(&json!("Alice")));
+    let write_results: Vec<bool> = storage
+        .calls()
+        .iter()
+        .filter(|call| call.op == StorageOp::PutDelta)
+        .map(|call| call.ok)
+        .collect();
+    assert_eq!(write_results, vec![false, false, true]);
+    assert!(errors.lock().is_empty());
+}
+
+#[tokio::test]
+async fn test_background_persistence_reports_failed_writes() {
+    let storage = Arc::new(MockStorage::new());
+    let gun = gun_with_mock_storage(storage.clone(), true).await;
+    let errors = Arc::new(parking_lot::Mutex::new(Vec::new()));
+    let errors_clone = errors.clone();
+    gun.on_storage_error(move |error| errors_clone.lock().push(error.clone()));
+
+    storage.fail_next(StorageOp::PutDelta, gun::persist::PERSIST_MAX_ATTEMPTS as usize);
+    gun.get("user").put(json!({"name": "Alice"})).await.unwrap();
+    gun.flush().await.unwrap();
+
+    let errors = errors.lock().clone();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0]["soul"], json!("user"));
+    assert_eq!(errors[0]["op"], json!("put_delta"));
+    assert_eq!(errors[0]["attempts"], json!(gun::persist::PERSIST_MAX_ATTEMPTS));
+    assert!(errors[0]["error"].as_str().unwrap().contains("Injected"));
+
+    // The graph still has the value
+    assert_eq!(read_key(&gun, "user", "name").await, json!("Alice"));
+}
+
+#[tokio::test]
+async fn test_mock_storage_faults() {
+    use gun::state::Node;
+
+    let storage = MockStorage::new();
+    let node = Node::with_soul("a".to_string());
+
+    storage.fail_next(StorageOp::Put, 2);
+    assert!(storage.put("a", &node).await.is_err());
+    assert!(storage.put("a", &node).await.is_err());
+    storage.put("a", &node).await.unwrap();
+    assert!(storage.has("a").await.unwrap());
+
+    // Dropped writes report success but store nothing
+    storage.set_drop_writes(true);
+    storage.put("b", &node).await.unwrap();
+    storage.delete("a").await.unwrap();
+    storage.set_drop_writes(false);
+    assert!(!storage.has("b").await.unwrap());
+    assert!(storage.has("a").await.unwrap());
+
+    storage.set_corrupt_reads(true);
+    assert!(storage.get("a").await.is_err());
+    storage.set_corrupt_reads(false);
+
+    storage.set_latency(Some(std::time::Duration::from_millis(50)));
+    let start = std::time::Instant::now();
+    storage.get("a").await.unwrap();
+    assert!(start.elapsed() >= std::time::Duration::from_millis(50));
+
+    assert_eq!(
+        storage.ops(),
+        vec![
+            StorageOp::Put,
+            StorageOp::Put,
+            StorageOp::Put,
+            StorageOp::Has,
+            StorageOp::Put,
+            StorageOp::Delete,
+            StorageOp::Has,
+            StorageOp::Has,
+            StorageOp::Get,
+            StorageOp::Get,
+        ]
+    );
+    assert_eq!(
+        storage.calls()[0],
+        StorageCall { op: StorageOp::Put, soul: Some("a".to_string()), ok: false }
+    );
+    storage.clear_calls();
+    assert!(storage.calls().is_empty());
+}
+
@@ -7,7 +7,8 @@ use tokio::time::sleep;
 
 #[tokio::test]
 async fn test_expiration_reject_old_data() {
-    let gun = Gun::new();
+    let secret_key = chia_bls::SecretKey::from_seed(&[0u8; 32]);
+    let gun = Gun::new(secret_key.clone(), secret_key.public_key());
 
     // Create soul with 1 second expiration
     let soul = "test_node<?1";
@@ -33,7 +34,8 @@ async fn test_expiration_reject_old_data() {
 
 #[tokio::test]
 async fn test_expiration_parse() {
-    let gun = Gun::new();
+    let secret_key = chia_bls::SecretKey::from_seed(&[0u8; 32]);
+    let gun = Gun::new(secret_key.clone(), secret_key.public_key());
 
     // Test parsing of expiration suffix
     let soul = "node<?3600"; // 1 hour expiration
@@ -43,3 +45,70 @@ async fn test_expiration_parse() {
 
     assert!(result.is_ok());
 }
+
+#[tokio::test]
+async fn test_expiration_sweep_removes_expired_data() {
+    use chia_bls::SecretKey;
+    use gun::storage::{MemoryStorage, Storage};
+    use gun::GunOptions;
+    use std::sync::Arc;
+
+    let storage: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let secret_key = SecretKey::from_seed(&[20u8; 32]);
+    let public_key = secret_key.public_key();
+    let options = GunOptions {
+        storage: Some(storage.clone()),
+        expiry_sweep_interval: Some(Duration::from_millis(200)),
+        ..Default::default()
+    };
+    let gun = Gun::with_options(secret_key, public_key, options).await.unwrap();
+
+    gun.get("session<?1").put(json!({"token": "abc"})).await.unwrap();
+    gun.flush().await.unwrap();
+    assert!(storage.has("session").await.unwrap());
+
+    // Readable before it expires
+    let mut token = serde_json::Value::Null;
+    gun.get("session<?1").once(|data, _key| {
+        token = data.get("token").cloned().unwrap_or_default();
+    }).await.unwrap();
+    assert_eq!(token, json!("abc"));
+
+    sleep(Duration::from_millis(1600)).await;
+
+    // Gone from reads
+    let mut token = json!("unset");
+    gun.get("session").once(|data, _key| {
+        token = data.get("token").cloned().unwrap_or_default();
+    }).await.unwrap();
+    assert!(token.is_null());
+
+    // Gone from the storage backend
+    gun.flush().await.unwrap();
+    assert!(!storage.has("session").await.unwrap());
+}
+
+#[tokio::test]
+async fn test_expiration_hidden_from_graph_before_sweep() {
+    use gun::core::GunCore;
+
+    let core = GunCore::new();
+    let mut node = gun::state::Node::with_soul("n".to_string());
+    node.data.insert("old".to_string(), json!(1));
+    node.data.insert("kept".to_string(), json!(2));
+    let now = chrono::Utc::now().timestamp_millis() as f64;
+    node.set_expiry("old", now - 1.0);
+    core.graph.put("n", node).unwrap();
+
+    // Expired key is hidden even though no sweep has run
+    let visible = core.graph.get("n").unwrap();
+    assert!(visible.data.get("old").is_none());
+    assert_eq!(visible.data.get("kept"), Some(&json!(2)));
+
+    // Tombstoning sets it to null with a fresh state and clears the expiry
+    let (tombstoned, keys) = core.graph.expire("n", now, || core.state.next()).unwrap();
+    assert_eq!(keys, vec!["old".to_string()]);
+    assert_eq!(tombstoned.data.get("old"), Some(&serde_json::Value::Null));
+    assert!(!tombstoned.has_expiry());
+    assert!(core.graph.expire("n", now, || core.state.next()).is_none());
+}
@@ -57,6 +57,87 @@ async fn test_gun_with_options_storage() {
     let _ = std::fs::remove_dir_all(&temp_dir);
 }
 
+#[test]
+fn test_gun_options_builder_normalizes_peers() {
+    let options = GunOptions::builder()
+        .peer("HTTP://Relay.example.com:80")
+        .peer("ws://relay.example.com/gun")
+        .peers(["https://backup.example.com/gun"])
+        .append_gun_path(true)
+        .storage_sled("./gun_data")
+        .require_signatures(false)
+        .signed_peer("ws://rust.example.com/gun")
+        .build()
+        .unwrap();
+    assert_eq!(options.peers, vec!["ws://relay.example.com/gun", "wss://backup.example.com/gun"]);
+    assert_eq!(options.storage_path.as_deref(), Some("./gun_data"));
+    assert!(options.radisk);
+    assert!(!options.require_signatures);
+
+    // Nothing persisted unless asked
+    let options = GunOptions::builder().relay(8765).build().unwrap();
+    assert!(options.super_peer && options.port == Some(8765));
+    assert!(options.storage_path.is_none() && !options.radisk && !options.localStorage);
+}
+
+#[tokio::test]
+async fn test_gun_with_options_rejects_pow_bits_above_the_maximum() {
+    let secret_key = SecretKey::from_seed(&[12u8; 32]);
+    let bits = gun::dam::MAX_POW_BITS + 1;
+    let dir = tempfile::tempdir().unwrap();
+    let options = GunOptions {
+        pow_bits: Some(bits),
+        super_peer: true,
+        storage_path: Some(dir.path().to_string_lossy().into_owned()),
+        ..Default::default()
+    };
+    let err = Gun::with_options(secret_key.clone(), secret_key.public_key(), options).await.err().unwrap();
+    assert!(matches!(err, gun::GunError::Config(gun::ConfigError::PowBitsTooHigh(b)) if b == bits), "{}", err);
+}
+
+#[test]
+fn test_gun_options_builder_rejects_invalid_combinations() {
+    use gun::storage::MemoryStorage;
+    use gun::ConfigError;
+    use std::sync::Arc;
+
+    let err = GunOptions::builder().peer("tcp://relay.example.com").build().err().unwrap();
+    assert!(matches!(err, ConfigError::InvalidPeerUrl(ref url) if url.contains("tcp://relay.example.com")), "{}", err);
+
+    assert_eq!(GunOptions::builder().port(8765).build().err().unwrap(), ConfigError::ListenWithoutRelay);
+    let listen = gun::ListenConfig::new(std::net::SocketAddr::from(([127, 0, 0, 1], 8765)));
+    assert_eq!(GunOptions::builder().listen(listen.clone()).build().err().unwrap(), ConfigError::ListenWithoutRelay);
+    assert!(GunOptions::builder().super_peer(true).listen(listen).build().is_ok());
+
+    assert_eq!(GunOptions::builder().radisk(true).build().err().unwrap(), ConfigError::RadiskWithoutStoragePath);
+    assert!(GunOptions::builder().radisk(true).storage_path("./gun_data").build().is_ok());
+
+    let err = GunOptions::builder()
+        .storage(Arc::new(MemoryStorage::new()))
+        .storage_local("./gun_data")
+        .build()
+        .err().unwrap();
+    assert_eq!(err, ConfigError::ConflictingStorage);
+
+    let err = GunOptions::builder().signed_peer("ws://rust.example.com/gun").build().err().unwrap();
+    assert_eq!(err, ConfigError::SignedPeersRequireSignatures);
+
+    let err = GunOptions::builder().namespace("app/x").build().err().unwrap();
+    assert_eq!(err, ConfigError::InvalidNamespace("app/x".to_string()));
+    assert_eq!(GunOptions::builder().namespace("").build().err().unwrap(), ConfigError::InvalidNamespace(String::new()));
+
+    let dup = gun::dup::DupOptions { max_entries: 0, ..Default::default() };
+    assert_eq!(GunOptions::builder().dup(dup).build().err().unwrap(), ConfigError::ZeroDupEntries);
+
+    let err = GunOptions::builder().pow_bits(gun::dam::MAX_POW_BITS + 1).build().err().unwrap();
+    assert_eq!(err, ConfigError::PowBitsTooHigh(gun::dam::MAX_POW_BITS + 1));
+    assert!(GunOptions::builder().pow_bits(gun::dam::MAX_POW_BITS).build().is_ok());
+
+    // Converts into GunError with `?`
+    let err: gun::GunError = ConfigError::ListenWithoutRelay.into();
+    assert!(matches!(err, gun::GunError::Config(ConfigError::ListenWithoutRelay)));
+}
+
 #[tokio::test]
 async fn test_gun_root() {
     let secret_key = SecretKey::from_seed(&[4u8; 32]);
@@ -79,3 +160,831 @@ async fn test_gun_get() {
     // Should be able to put data
     assert!(chain.put(serde_json::json!("value")).await.is_ok());
 }
+
+#[tokio::test]
+async fn test_gun_storage_read_through_after_restart() {
+    use std::env;
+    use std::time::{Duration, Instant};
+    let temp_dir = env::temp_dir().join("gun_test_read_through");
+    let _ = std::fs::remove_dir_all(&temp_dir);
+    let path = temp_dir.to_str().unwrap().to_string();
+
+    // First run: persist a node and a nested path
+    {
+        let secret_key = SecretKey::from_seed(&[6u8; 32]);
+        let public_key = secret_key.public_key();
+        let options = GunOptions {
+            storage_path: Some(path.clone()),
+            ..Default::default()
+        };
+        let gun = Gun::with_options(secret_key, public_key, options).await.unwrap();
+        gun.get("user").put(serde_json::json!({"name": "Alice"})).await.unwrap();
+        gun.get("app").get("settings").put(serde_json::json!({"theme": "dark"})).await.unwrap();
+        gun.flush().await.unwrap();
+    }
+
+    // Second run: same path, no peers, nothing loaded in memory yet
+    let secret_key = SecretKey::from_seed(&[6u8; 32]);
+    let public_key = secret_key.public_key();
+    let options = GunOptions {
+        storage_path: Some(path.clone()),
+        ..Default::default()
+    };
+    let gun = Gun::with_options(secret_key, public_key, options).await.unwrap();
+
+    let start = Instant::now();
+    let mut name = serde_json::Value::Null;
+    gun.get("user").once(|data, _key| {
+        name = data.get("name").cloned().unwrap_or_default();
+    }).await.unwrap();
+    assert_eq!(name, serde_json::json!("Alice"));
+
+    let mut theme = serde_json::Value::Null;
+    gun.get("app").get("settings").once(|data, _key| {
+        theme = data.get("theme").cloned().unwrap_or_default();
+    }).await.unwrap();
+    assert_eq!(theme, serde_json::json!("dark"));
+
+    // Served from disk, not after a network timeout
+    assert!(start.elapsed() < Duration::from_secs(2));
+
+    drop(gun);
+    let _ = std::fs::remove_dir_all(&temp_dir);
+}
+
+#[tokio::test]
+async fn test_gun_storage_read_through_on() {
+    use std::env;
+    use std::sync::{Arc, Mutex};
+    let temp_dir = env::temp_dir().join("gun_test_read_through_on");
+    let _ = std::fs::remove_dir_all(&temp_dir);
+    let path = temp_dir.to_str().unwrap().to_string();
+
+    {
+        let secret_key = SecretKey::from_seed(&[7u8; 32]);
+        let public_key = secret_key.public_key();
+        let options = GunOptions {
+            storage_path: Some(path.clone()),
+            radisk: false,
+            ..Default::default()
+        };
+        let gun = Gun::with_options(secret_key, public_key, options).await.unwrap();
+        gun.get("profile").put(serde_json::json!({"bio": "hello"})).await.unwrap();
+        gun.flush().await.unwrap();
+    }
+
+    let secret_key = SecretKey::from_seed(&[7u8; 32]);
+    let public_key = secret_key.public_key();
+    let options = GunOptions {
+        storage_path: Some(path.clone()),
+        radisk: false,
+        ..Default::default()
+    };
+    let gun = Gun::with_options(secret_key, public_key, options).await.unwrap();
+
+    // on() delivers the persisted value once it has been read from disk
+    let received = Arc::new(Mutex::new(None));
+    let received_clone = received.clone();
+    gun.get("profile").on(move |data, _key| {
+        *received_clone.lock().unwrap() = data.get("bio").cloned();
+    });
+    tokio::time::sleep(tokio::time::Duration::from_millis(200)).await;
+    assert_eq!(*received.lock().unwrap(), Some(serde_json::json!("hello")));
+
+    drop(gun);
+    let _ = std::fs::remove_dir_all(&temp_dir);
+}
+
+#[tokio::test]
+async fn test_gun_migrate_storage() {
+    use gun::storage::{MemoryStorage, MigrationOptions, Storage};
+    use std::sync::Arc;
+
+    let old_backend: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let secret_key = SecretKey::from_seed(&[8u8; 32]);
+    let public_key = secret_key.public_key();
+    let options = GunOptions {
+        storage: Some(old_backend.clone()),
+        ..Default::default()
+    };
+    let gun = Gun::with_options(secret_key, public_key, options).await.unwrap();
+    gun.get("before").put(serde_json::json!({"v": 1})).await.unwrap();
+
+    let new_backend: Arc<dyn Storage> = Arc::new(MemoryStorage::new());
+    let report = gun.migrate_storage(new_backend.clone(), MigrationOptions::default()).await.unwrap();
+    assert_eq!(report.copied, 1);
+    assert!(new_backend.has("before").await.unwrap());
+
+    // Writes after the swap go to the new backend only
+    gun.get("after").put(serde_json::json!({"v": 2})).await.unwrap();
+    gun.flush().await.unwrap();
+    assert!(new_backend.has("after").await.unwrap());
+    assert!(!old_backend.has("after").await.unwrap());
+}
+
+/// Storage wrapper that counts reads, to tell preloaded answers from disk hits
+struct CountingStorage {
+    inner: gun::storage::MemoryStorage,
+    gets: std::sync::atomic::AtomicUsize,
+}
+
+#[async_trait::async_trait]
+impl gun::storage::Storage for CountingStorage {
+    async fn get(&self, soul: &str) -> gun::error::GunResult<Option<gun::state::Node>> {
+        self.gets.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        self.inner.get(soul).await
+    }
+    async fn put(&self, soul: &str, node: &gun::state::Node) -> gun::error::GunResult<()> {
+        self.inner.put(soul, node).await
+    }
+    async fn has(&self, soul: &str) -> gun::error::GunResult<bool> {
+        self.inner.has(soul).await
+    }
+    async fn list(&self) -> gun::error::GunResult<Vec<String>> {
+        self.inner.list().await
+    }
+    async fn delete(&self, soul: &str) -> gun::error::GunResult<()> {
+        self.inner.delete(soul).await
+    }
+}
+
+async fn counting_storage_with(souls: &[&str]) -> std::sync::Arc<CountingStorage> {
+    use gun::storage::Storage;
+
+    let storage = CountingStorage {
+        inner: gun::storage::MemoryStorage::new(),
+        gets: std::sync::atomic::AtomicUsize::new(0),
+    };
+    for soul in souls {
+        let mut node = gun::state::Node::with_soul(soul.to_string());
+        node.data.insert("name".to_string(), serde_json::json!(soul.to_uppercase()));
+        gun::state::State::ify(&mut node, Some("name"), Some(1.0), None, Some(soul));
+        storage.put(soul, &node).await.unwrap();
+    }
+    std::sync::Arc::new(storage)
+}
+
+#[tokio::test]
+async fn test_gun_preload_modes() {
+    use gun::PreloadMode;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let storage = counting_storage_with(&["a", "b", "c"]).await;
+    let secret_key = SecretKey::from_seed(&[9u8; 32]);
+
+    // Not a relay: nothing is preloaded by default
+    let gun = Gun::with_options(secret_key.clone(), secret_key.public_key(), GunOptions {
+        storage: Some(storage.clone()),
+        ..Default::default()
+    }).await.unwrap();
+    assert_eq!(storage.gets.load(Ordering::SeqCst), 0);
+    drop(gun);
+
+    // Only the listed souls
+    let gun = Gun::with_options(secret_key.clone(), secret_key.public_key(), GunOptions {
+        storage: Some(storage.clone()),
+        preload: Some(PreloadMode::Souls(vec!["b".to_string(), "missing".to_string()])),
+        ..Default::default()
+    }).await.unwrap();
+    assert_eq!(storage.gets.load(Ordering::SeqCst), 2);
+    drop(gun);
+
+    // Everything, capped, with progress
+    storage.gets.store(0, Ordering::SeqCst);
+    let last_progress = Arc::new(AtomicUsize::new(0));
+    let last_progress_cb = last_progress.clone();
+    let gun = Gun::with_options(secret_key.clone(), secret_key.public_key(), GunOptions {
+        storage: Some(storage.clone()),
+        preload: Some(PreloadMode::All { max_nodes: 2 }),
+        preload_progress: Some(Arc::new(move |processed, total| {
+            assert_eq!(total, 2);
+            last_progress_cb.store(processed, Ordering::SeqCst);
+        })),
+        ..Default::default()
+    }).await.unwrap();
+    assert_eq!(storage.gets.load(Ordering::SeqCst), 2);
+    assert_eq!(last_progress.load(Ordering::SeqCst), 2);
+
+    // Preloaded souls are served from memory
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let tx = parking_lot::Mutex::new(Some(tx));
+    gun.get("a").get("name").once(move |data, _key| {
+        if let Some(tx) = tx.lock().take() {
+            let _ = tx.send(data);
+        }
+    }).await.unwrap();
+    assert_eq!(rx.await.unwrap(), serde_json::json!("A"));
+    assert_eq!(storage.gets.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_gun_relay_preload_answers_peer_get_from_memory() {
+    use std::sync::atomic::Ordering;
+
+    // A relay restarting on top of existing data
+    let storage = counting_storage_with(&["relay_user"]).await;
+    let relay_key = SecretKey::from_seed(&[10u8; 32]);
+    let port = 18816;
+    let mut relay = Gun::with_options(relay_key.clone(), relay_key.public_key(), GunOptions {
+        storage: Some(storage.clone()),
+        ..GunOptions::relay_server(port)
+    }).await.unwrap();
+    assert_eq!(storage.gets.load(Ordering::SeqCst), 1, "relays preload by default");
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    let peer_key = SecretKey::from_seed(&[11u8; 32]);
+    let mut peer = Gun::with_options(peer_key.clone(), peer_key.public_key(), GunOptions {
+        localStorage: false,
+        radisk: false,
+        ..GunOptions::with_relay(&format!("ws://127.0.0.1:{}/gun", port))
+    }).await.unwrap();
+    assert!(peer.wait_for_connection(5000).await);
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let tx = parking_lot::Mutex::new(Some(tx));
+    peer.get("relay_user").get("name").once(move |data, _key| {
+        if let Some(tx) = tx.lock().take() {
+            let _ = tx.send(data);
+        }
+    }).await.unwrap();
+    assert_eq!(rx.await.unwrap(), serde_json::json!("RELAY_USER"));
+
+    // The relay answered without reading its storage at request time
+    assert_eq!(storage.gets.load(Ordering::SeqCst), 1);
+
+    peer.shutdown().await.unwrap();
+    relay.shutdown().await.unwrap();
+}
+
+/// A backend written against the first `Storage` trait: get, put and has
+struct GetPutHasStorage(gun::storage::MemoryStorage);
+
+#[async_trait::async_trait]
+impl gun::storage::Storage for GetPutHasStorage {
+    async fn get(&self, soul: &str) -> gun::error::GunResult<Option<gun::state::Node>> {
+        self.0.get(soul).await
+    }
+    async fn put(&self, soul: &str, node: &gun::state::Node) -> gun::error::GunResult<()> {
+        self.0.put(soul, node).await
+    }
+    async fn has(&self, soul: &str) -> gun::error::GunResult<bool> {
+        self.0.has(soul).await
+    }
+}
+
+#[tokio::test]
+async fn test_gun_relay_starts_on_a_backend_that_cant_list() {
+    use gun::storage::Storage;
+
+    let storage = std::sync::Arc::new(GetPutHasStorage(gun::storage::MemoryStorage::new()));
+    assert!(storage.list().await.is_err());
+    assert!(storage.delete("a").await.is_err());
+    storage.put_delta("a", &[("name".to_string(), serde_json::json!("A"), 1.0)]).await.unwrap();
+
+    // The default preload is skipped rather than failing the relay
+    let relay_key = SecretKey::from_seed(&[13u8; 32]);
+    let mut relay = Gun::with_options(relay_key.clone(), relay_key.public_key(), GunOptions {
+        storage: Some(storage.clone()),
+        ..GunOptions::relay_server(18817)
+    }).await.unwrap();
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let tx = parking_lot::Mutex::new(Some(tx));
+    relay.get("a").get("name").once(move |data, _key| {
+        if let Some(tx) = tx.lock().take() {
+            let _ = tx.send(data);
+        }
+    }).await.unwrap();
+    assert_eq!(rx.await.unwrap(), serde_json::json!("A"));
+    relay.shutdown().await.unwrap();
+
+    // Asked for explicitly, it still fails
+    let result = Gun::with_options(relay_key.clone(), relay_key.public_key(), GunOptions {
+        storage: Some(storage),
+        preload: Some(gun::PreloadMode::All { max_nodes: 10 }),
+        ..GunOptions::relay_server(18818)
+    }).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_gun_concurrent_once_sends_one_get() {
+    let storage = counting_storage_with(&["shared_user"]).await;
+    let relay_key = SecretKey::from_seed(&[12u8; 32]);
+    let port = 18851;
+    let mut relay = Gun::with_options(relay_key.clone(), relay_key.public_key(), GunOptions {
+        storage: Some(storage),
+        ..GunOptions::relay_server(port)
+    }).await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    let peer_key = SecretKey::from_seed(&[13u8; 32]);
+    let mut peer = Gun::with_options(peer_key.clone(), peer_key.public_key(), GunOptions {
+        localStorage: false,
+        radisk: false,
+        ..GunOptions::with_relay(&format!("ws://127.0.0.1:{}/gun", port))
+    }).await.unwrap();
+    assert!(peer.wait_for_connection(5000).await);
+
+    // Ten components reading the same missing soul at once
+    let values = std::sync::Arc::new(parking_lot::Mutex::new(Vec::new()));
+    let reads = (0..10).map(|_| {
+        let values = values.clone();
+        let chain = peer.get("shared_user").get("name");
+        async move {
+            chain.once(move |data, _key| values.lock().push(data)).await.unwrap();
+        }
+    });
+    futures::future::join_all(reads).await;
+
+    assert_eq!(*values.lock(), vec![serde_json::json!("SHARED_USER"); 10]);
+    assert_eq!(peer.stats().mesh.gets_sent, 1);
+
+    peer.shutdown().await.unwrap();
+    relay.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_gun_on_resubscribes_after_relay_restart() {
+    let storage = counting_storage_with(&["alice", "bob", "carol"]).await;
+    let relay_key = SecretKey::from_seed(&[14u8; 32]);
+    let port = 18852;
+    let relay_options = || GunOptions {
+        storage: Some(storage.clone()),
+        ..GunOptions::relay_server(port)
+    };
+    let mut relay = Gun::with_options(relay_key.clone(), relay_key.public_key(), relay_options()).await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    let peer_key = SecretKey::from_seed(&[15u8; 32]);
+    let mut peer = Gun::with_options(peer_key.clone(), peer_key.public_key(), GunOptions {
+        localStorage: false,
+        radisk: false,
+        ..GunOptions::with_relay(&format!("ws://127.0.0.1:{}/gun", port))
+    }).await.unwrap();
+    assert!(peer.wait_for_connection(5000).await);
+
+    // Live listeners only - nothing ever calls once()
+    let values = std::sync::Arc::new(parking_lot::Mutex::new(std::collections::BTreeMap::new()));
+    for soul in ["alice", "bob", "carol"] {
+        let values = values.clone();
+        peer.get(soul).get("name").on(move |data, _key| {
+            values.lock().insert(soul, data);
+        });
+    }
+
+    relay.shutdown().await.unwrap();
+    drop(relay);
+    let mut relay = Gun::with_options(relay_key.clone(), relay_key.public_key(), relay_options()).await.unwrap();
+
+    let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(10);
+    while values.lock().len() < 3 && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    let values = values.lock().clone();
+    assert_eq!(values.get("alice"), Some(&serde_json::json!("ALICE")));
+    assert_eq!(values.get("bob"), Some(&serde_json::json!("BOB")));
+    assert_eq!(values.get("carol"), Some(&serde_json::json!("CAROL")));
+
+    peer.shutdown().await.unwrap();
+    relay.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_gun_add_peer_to_local_only_instance() {
+    let port = 18854;
+    let relay_key = SecretKey::from_seed(&[16u8; 32]);
+    let mut relay = Gun::with_options(relay_key.clone(), relay_key.public_key(), GunOptions::relay_server(port))
+        .await
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+    let url = format!("ws://127.0.0.1:{}/gun", port);
+
+    let alice_key = SecretKey::from_seed(&[17u8; 32]);
+    let mut alice = Gun::with_options(alice_key.clone(), alice_key.public_key(), GunOptions {
+        localStorage: false,
+        radisk: false,
+        ..GunOptions::with_relay(&url)
+    })
+    .await
+    .unwrap();
+    assert!(alice.wait_for_connection(5000).await);
+
+    // Local-only until it adds a peer; its subscription carries over
+    let bob_key = SecretKey::from_seed(&[18u8; 32]);
+    let mut bob = Gun::new(bob_key.clone(), bob_key.public_key());
+    assert!(bob.peers().await.is_empty());
+    let seen = std::sync::Arc::new(parking_lot::Mutex::new(None));
+    let seen_by_bob = seen.clone();
+    bob.get("greeting").get("text").on(move |data, _key| {
+        *seen_by_bob.lock() = Some(data);
+    });
+
+    let peer_id = bob.add_peer(&url).await.unwrap();
+    let peers = bob.peers().await;
+    assert_eq!(peers.len(), 1);
+    assert_eq!(peers[0].id, peer_id);
+    assert_eq!(peers[0].url, url);
+    assert!(peers[0].connected);
+    assert_eq!(peers[0].transport, gun::Transport::WebSocket);
+    assert!(peers[0].pid.is_some() && peers[0].last_seen.is_some());
+
+    // Sync runs both ways
+    alice.get("greeting").put(serde_json::json!({"text": "hi bob"})).await.unwrap();
+    bob.get("reply").put(serde_json::json!({"text": "hi alice"})).await.unwrap();
+    let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(5);
+    while seen.lock().is_none() && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    assert_eq!(*seen.lock(), Some(serde_json::json!("hi bob")));
+    let mut reply = serde_json::Value::Null;
+    alice.get("reply").get("text").once(|data, _key| reply = data).await.unwrap();
+    assert_eq!(reply, serde_json::json!("hi alice"));
+
+    // Removed by URL: bye, and no reconnecting
+    assert!(bob.remove_peer(&url).await.unwrap());
+    tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+    assert!(bob.peers().await.is_empty());
+    assert!(!bob.is_connected().await);
+    assert!(bob.peer_status().is_empty());
+    assert!(!bob.remove_peer(&peer_id).await.unwrap());
+
+    // And back by ID
+    let peer_id = bob.add_peer(&url).await.unwrap();
+    assert!(bob.remove_peer(&peer_id).await.unwrap());
+    assert!(bob.peers().await.is_empty());
+
+    assert!(bob.add_peer("ftp://relay.example.com").await.is_err());
+
+    bob.shutdown().await.unwrap();
+    alice.shutdown().await.unwrap();
+    relay.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_gun_on_peer_reports_connect_and_disconnect() {
+    use gun::PeerTransition;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+    use tokio::time::timeout;
+
+    let port = 18855;
+    let url = format!("ws://127.0.0.1:{}/gun", port);
+    let relay_key = SecretKey::from_seed(&[19u8; 32]);
+    let mut relay = Gun::with_options(relay_key.clone(), relay_key.public_key(), GunOptions::relay_server(port))
+        .await
+        .unwrap();
+    let (relay_tx, mut relay_events) = mpsc::unbounded_channel();
+    relay.on_peer(move |event| {
+        let _ = relay_tx.send(event);
+    });
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let peer_key = SecretKey::from_seed(&[20u8; 32]);
+    let mut peer = Gun::with_options(peer_key.clone(), peer_key.public_key(), GunOptions {
+        localStorage: false,
+        radisk: false,
+        mesh: gun::dam::MeshOptions {
+            retry_wait: 50,
+            ..Default::default()
+        },
+        ..Default::default()
+    })
+    .await
+    .unwrap();
+    let (peer_tx, mut peer_events) = mpsc::unbounded_channel();
+    peer.on_peer(move |event| {
+        let _ = peer_tx.send(event);
+    });
+
+    // Online once the handshake is done, on both sides
+    let peer_id = peer.add_peer(&url).await.unwrap();
+    let event = timeout(Duration::from_secs(5), peer_events.recv()).await.unwrap().unwrap();
+    assert_eq!(event.transition, PeerTransition::Connected);
+    assert_eq!(event.peer, peer_id);
+    assert_eq!(event.url, url);
+    assert!(event.pid.is_some());
+    let event = timeout(Duration::from_secs(5), relay_events.recv()).await.unwrap().unwrap();
+    assert_eq!(event.transition, PeerTransition::Connected);
+    assert!(event.pid.is_some());
+
+    // The relay goes away, then comes back
+    relay.shutdown().await.unwrap();
+    drop(relay);
+    let event = timeout(Duration::from_secs(5), peer_events.recv()).await.unwrap().unwrap();
+    assert_eq!(event.transition, PeerTransition::Disconnected);
+    assert_eq!(event.url, url);
+    let mut relay = Gun::with_options(relay_key.clone(), relay_key.public_key(), GunOptions::relay_server(port))
+        .await
+        .unwrap();
+    let event = timeout(Duration::from_secs(10), peer_events.recv()).await.unwrap().unwrap();
+    assert!(matches!(event.transition, PeerTransition::Reconnected { attempts } if attempts >= 1));
+    assert!(peer.wait_for_connection(5000).await);
+
+    // Removed: offline once, and nothing after
+    assert!(peer.remove_peer(&url).await.unwrap());
+    let event = timeout(Duration::from_secs(5), peer_events.recv()).await.unwrap().unwrap();
+    assert_eq!(event.transition, PeerTransition::Disconnected);
+    assert!(timeout(Duration::from_millis(500), peer_events.recv()).await.is_err());
+    assert!(!peer.wait_for_connection(200).await);
+
+    peer.shutdown().await.unwrap();
+    relay.shutdown().await.unwrap();
+}
+
+#[cfg(feature = "lan-discovery")]
+#[tokio::test]
+async fn test_gun_lan_discovery_syncs_without_configured_peers() {
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    let lan_options = || GunOptions {
+        localStorage: false,
+        radisk: false,
+        lan_discovery: true,
+        ..Default::default()
+    };
+    let alice_key = SecretKey::from_seed(&[25u8; 32]);
+    let mut alice = Gun::with_options(alice_key.clone(), alice_key.public_key(), lan_options()).await.unwrap();
+    let bob_key = SecretKey::from_seed(&[26u8; 32]);
+    let mut bob = Gun::with_options(bob_key.clone(), bob_key.public_key(), lan_options()).await.unwrap();
+    assert!(alice.wait_for_connection(10_000).await);
+    assert!(bob.wait_for_connection(10_000).await);
+    // One connection between them, whoever dialed
+    assert_eq!(alice.peers().await.len(), 1);
+    assert_eq!(bob.peers().await.len(), 1);
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    bob.get("lan_note").get("text").on(move |data, _key| {
+        let _ = tx.send(data);
+    });
+    alice.get("lan_note").put(serde_json::json!({"text": "found you"})).await.unwrap();
+    loop {
+        let data = timeout(Duration::from_secs(5), rx.recv()).await.unwrap().unwrap();
+        if data == serde_json::json!("found you") {
+            break;
+        }
+    }
+
+    alice.shutdown().await.unwrap();
+    bob.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_gun_import_export_gunjs_localstorage() {
+    let secret_key = SecretKey::from_seed(&[12u8; 32]);
+    let gun = Gun::new(secret_key.clone(), secret_key.public_key());
+    let imported = gun
+        .import_gunjs_localstorage("tests/fixtures/gunjs_localstorage_todo.json")
+        .await
+        .unwrap();
+    assert_eq!(imported, 6);
+
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    let tx = parking_lot::Mutex::new(Some(tx));
+    gun.get("lx8k3b7f02kPmWnX5vCe").get("title").once(move |data, _key| {
+        if let Some(tx) = tx.lock().take() {
+            let _ = tx.send(data);
+        }
+    }).await.unwrap();
+    assert_eq!(rx.await.unwrap(), serde_json::json!("Walk the dog"));
+
+    gun.get("lx8k3b7f02kPmWnX5vCe").get("done").put(serde_json::json!(true)).await.unwrap();
+    let export_path = std::env::temp_dir().join("gun_test_gun_gunjs_export.json");
+    assert_eq!(gun.export_gunjs_localstorage(&export_path).await.unwrap(), 6);
+    let blob: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&export_path).unwrap()).unwrap();
+    assert_eq!(blob["lx8k3b7f02kPmWnX5vCe"]["done"], serde_json::json!(true));
+    let _ = std::fs::remove_file(&export_path);
+}
+
+#[tokio::test]
+async fn test_gun_stats_storage() {
+    use gun::storage::mock::MockStorage;
+    use std::sync::Arc;
+
+    let secret_key = SecretKey::from_seed(&[13u8; 32]);
+    assert_eq!(Gun::new(secret_key.clone(), secret_key.public_key()).stats().storage.puts, 0);
+
+    let mock = Arc::new(MockStorage::new());
+    mock.set_latency(Some(std::time::Duration::from_millis(20)));
+    let gun = Gun::with_options(secret_key.clone(), secret_key.public_key(), GunOptions {
+        storage: Some(mock.clone()),
+        slow_storage_threshold: std::time::Duration::from_millis(5),
+        ..Default::default()
+    }).await.unwrap();
+
+    gun.get("stats").put(serde_json::json!({"a": 1})).await.unwrap();
+    gun.flush().await.unwrap();
+    let stats = gun.stats().storage;
+    assert!(stats.puts >= 1);
+    assert!(stats.slow_ops >= 1);
+    assert!(stats.bytes_written > 0);
+
+    // Counters carry over to a migrated backend
+    let puts_before = stats.puts;
+    gun.migrate_storage(Arc::new(gun::storage::MemoryStorage::new()), Default::default())
+        .await
+        .unwrap();
+    gun.get("stats").put(serde_json::json!({"b": 2})).await.unwrap();
+    gun.flush().await.unwrap();
+    assert!(gun.stats().storage.puts > puts_before);
+}
+
+#[tokio::test]
+async fn test_gun_stats_snapshot_serializes_with_live_values() {
+    use std::time::Duration;
+
+    let port = 18857;
+    let relay_key = SecretKey::from_seed(&[23u8; 32]);
+    let mut relay = Gun::with_options(relay_key.clone(), relay_key.public_key(), GunOptions::relay_server(port))
+        .await
+        .unwrap();
+    let peer_key = SecretKey::from_seed(&[24u8; 32]);
+    let mut peer = Gun::with_options(peer_key.clone(), peer_key.public_key(), GunOptions {
+        localStorage: false,
+        radisk: false,
+        ..GunOptions::with_relay(&format!("ws://127.0.0.1:{}/gun", port))
+    })
+    .await
+    .unwrap();
+    assert!(peer.wait_for_connection(5000).await);
+
+    peer.get("stats_profile").on(|_data, _key| {});
+    for n in 0..3 {
+        peer.get(&format!("stats_item_{}", n)).put(serde_json::json!({"n": n, "label": "item"})).await.unwrap();
+    }
+    peer.flush().await.unwrap();
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(5);
+    while relay.stats().mesh.puts_applied < 3 && tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    let stats = serde_json::to_value(peer.stats()).unwrap();
+    assert!(stats["graph"]["nodes"].as_u64().unwrap() >= 3);
+    assert!(stats["graph"]["keys"].as_u64().unwrap() >= 6);
+    assert!(stats["graph"]["bytes"].as_u64().unwrap() > 0);
+    assert_eq!(stats["subscriptions"], 1);
+    assert!(stats["uptime"].is_object());
+    assert_eq!(stats["mesh"]["peers"], 1);
+    assert!(stats["mesh"]["messages_said"].as_u64().unwrap() >= 3);
+    assert!(stats["mesh"]["bytes_out"].as_u64().unwrap() > 0);
+    assert!(stats["mesh"]["dedup"]["entries"].as_u64().unwrap() > 0);
+    assert!(stats["storage"]["puts"].as_u64().unwrap() >= 3);
+    assert!(stats["tasks"].as_u64().unwrap() > 0);
+
+    let stats = serde_json::to_value(relay.stats()).unwrap();
+    assert!(stats["graph"]["nodes"].as_u64().unwrap() >= 3);
+    assert!(stats["mesh"]["puts_applied"].as_u64().unwrap() >= 3);
+    assert!(stats["mesh"]["bytes_in"].as_u64().unwrap() > 0);
+    assert_eq!(stats["server"]["rate_limited"], 0);
+
+    peer.shutdown().await.unwrap();
+    relay.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_gun_namespaces_share_sled_path() {
+    use gun::storage::{SledStorage, Storage};
+    use std::env;
+    let temp_dir = env::temp_dir().join("gun_test_namespaces");
+    let _ = std::fs::remove_dir_all(&temp_dir);
+    let path = temp_dir.to_str().unwrap().to_string();
+
+    let open = |namespace: &str| {
+        let secret_key = SecretKey::from_seed(&[14u8; 32]);
+        Gun::with_options(secret_key.clone(), secret_key.public_key(), GunOptions {
+            storage_path: Some(path.clone()),
+            radisk: true,
+            namespace: Some(namespace.to_string()),
+            ..Default::default()
+        })
+    };
+    let read_mode = |gun: Gun| async move {
+        let mut mode = serde_json::Value::Null;
+        gun.get("config").once(|data, _key| {
+            mode = data.get("mode").cloned().unwrap_or_default();
+        }).await.unwrap();
+        mode
+    };
+
+    {
+        let app = open("app").await.unwrap();
+        let test = open("test").await.unwrap();
+        app.get("config").put(serde_json::json!({"mode": "production"})).await.unwrap();
+        test.get("config").put(serde_json::json!({"mode": "testing"})).await.unwrap();
+        app.flush().await.unwrap();
+        test.flush().await.unwrap();
+
+        // Both namespaces live side by side in the one database
+        let sled = SledStorage::new(&path).unwrap();
+        assert!(sled.has("app/config").await.unwrap());
+        assert!(sled.has("test/config").await.unwrap());
+        assert!(!sled.has("config").await.unwrap());
+    }
+
+    // Fresh instances read only their own namespace back from disk
+    assert_eq!(read_mode(open("app").await.unwrap()).await, serde_json::json!("production"));
+    assert_eq!(read_mode(open("test").await.unwrap()).await, serde_json::json!("testing"));
+    let other = gun::storage::NamespacedStorage::new(SledStorage::new(&path).unwrap(), "other").unwrap();
+    assert!(other.list().await.unwrap().is_empty());
+
+    let _ = std::fs::remove_dir_all(&temp_dir);
+}
+
+#[tokio::test]
+async fn test_gun_shutdown_leaves_no_tasks_and_keeps_the_last_put() {
+    use gun::storage::{LocalStorage, Storage};
+
+    let temp_dir = std::env::temp_dir().join("gun_test_shutdown_tasks");
+    let _ = std::fs::remove_dir_all(&temp_dir);
+    let path = temp_dir.to_str().unwrap().to_string();
+    let port = 18856;
+    let relay_key = SecretKey::from_seed(&[21u8; 32]);
+    let mut relay = Gun::with_options(relay_key.clone(), relay_key.public_key(), GunOptions {
+        expiry_sweep_interval: Some(std::time::Duration::from_secs(60)),
+        ..GunOptions::relay_server(port)
+    })
+    .await
+    .unwrap();
+    let peer_key = SecretKey::from_seed(&[22u8; 32]);
+    let mut peer = Gun::with_options(peer_key.clone(), peer_key.public_key(), GunOptions {
+        storage_path: Some(path.clone()),
+        radisk: false,
+        background_persistence: true,
+        ..GunOptions::with_relay(&format!("ws://127.0.0.1:{}/gun", port))
+    })
+    .await
+    .unwrap();
+    assert!(peer.wait_for_connection(5000).await);
+    assert!(peer.stats().tasks > 0);
+    assert!(relay.stats().tasks > 0);
+
+    // Queued behind the background writer when shutdown starts
+    for n in 0..200 {
+        peer.get("counter").put(serde_json::json!({"n": n})).await.unwrap();
+    }
+    peer.shutdown().await.unwrap();
+    assert_eq!(peer.stats().tasks, 0);
+    relay.shutdown().await.unwrap();
+    assert_eq!(relay.stats().tasks, 0);
+
+    let stored = LocalStorage::new(&path).unwrap().get("counter").await.unwrap().unwrap();
+    assert_eq!(stored.data.get("n"), Some(&serde_json::json!(199)));
+    let _ = std::fs::remove_dir_all(&temp_dir);
+}
+
+/// Child half of `test_gun_shutdown_flushes_sled`: write, optionally shut down,
+/// then exit without running destructors as if the process were killed
+async fn sled_crash_child(path: &str, shutdown: bool) {
+    use gun::storage::SledStorage;
+    use std::sync::Arc;
+
+    let secret_key = SecretKey::from_seed(&[15u8; 32]);
+    let mut gun = Gun::with_options(secret_key.clone(), secret_key.public_key(), GunOptions {
+        storage: Some(Arc::new(SledStorage::with_flush_every_ms(path, Some(60_000)).unwrap())),
+        ..Default::default()
+    }).await.unwrap();
+    gun.get("journal").put(serde_json::json!({"entry": "last words"})).await.unwrap();
+    if shutdown {
+        gun.shutdown().await.unwrap();
+    }
+    std::process::exit(0);
+}
+
+#[tokio::test]
+async fn test_gun_shutdown_flushes_sled() {
+    use gun::storage::{SledStorage, Storage};
+    use std::env;
+
+    if let Ok(path) = env::var("GUN_SLED_CRASH_PATH") {
+        let shutdown = env::var("GUN_SLED_CRASH_SHUTDOWN").is_ok();
+        return sled_crash_child(&path, shutdown).await;
+    }
+
+    let run_child = |path: &str, shutdown: bool| {
+        let mut child = std::process::Command::new(env::current_exe().unwrap());
+        child
+            .args(["test_gun_shutdown_flushes_sled", "--exact", "--nocapture"])
+            .env("GUN_SLED_CRASH_PATH", path);
+        if shutdown {
+            child.env("GUN_SLED_CRASH_SHUTDOWN", "1");
+        }
+        assert!(child.status().unwrap().success());
+    };
+
+    for shutdown in [false, true] {
+        let temp_dir = env::temp_dir().join(format!("gun_test_sled_crash_{}", shutdown));
+        let _ = std::fs::remove_dir_all(&temp_dir);
+        let path = temp_dir.to_str().unwrap().to_string();
+
+        run_child(&path, shutdown);
+
+        let storage = SledStorage::new(&path).unwrap();
+        let survived = storage.has("journal").await.unwrap();
+        assert_eq!(survived, shutdown, "write survives the crash only after shutdown()");
+        drop(storage);
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+}
+
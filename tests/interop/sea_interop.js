@@ -0,0 +1,233 @@
+// SEA interop with Gun.js
+//
+//   node tests/interop/sea_interop.js sign-fixtures > tests/fixtures/gunjs_sea_signed.json
+//   node tests/interop/sea_interop.js verify <file>
+//   node tests/interop/sea_interop.js encrypt-fixtures > tests/fixtures/gunjs_sea_encrypted.json
+//   node tests/interop/sea_interop.js decrypt <file>
+//   node tests/interop/sea_interop.js localstorage-fixture > tests/fixtures/gunjs_localstorage_todo.json
+//
+// `sign-fixtures` signs sample data with a fresh pair; `verify` checks a
+// file of `{pub, signed: [{data, sea}]}` written by Gun.rs.
+// `encrypt-fixtures` encrypts sample data with a fresh pair (`own`) and for
+// a second pair (`shared`, with the `secret` they share); `decrypt` checks a
+// file of that shape written by Gun.rs. Both checks exit 1 on a mismatch.
+// `localstorage-fixture` writes a browser's localStorage as Gun.js leaves it
+// after a todo app ran and `alice` signed up: the graph under `gun/`, with
+// the account's values signed the way sea/index.js check.pub signs them.
+// Uses Gun.js SEA when `gun` is installed (`npm install gun`), else the
+// port of sea/sign.js, verify.js, secret.js, encrypt.js and decrypt.js
+// below, on Node's WebCrypto.
+
+const { webcrypto } = require('crypto');
+const subtle = webcrypto.subtle;
+
+const port = {
+  // sea/settings.js S.parse
+  parse(t) {
+    try {
+      if ('string' != typeof t) { return t }
+      if ('SEA{' === t.slice(0, 4)) { t = t.slice(3) }
+      return JSON.parse(t);
+    } catch (e) { return t }
+  },
+  // sea/settings.js S.jwk
+  jwk(pub, d) {
+    pub = pub.split('.');
+    const jwk = { kty: 'EC', crv: 'P-256', x: pub[0], y: pub[1], ext: true };
+    jwk.key_ops = d ? ['sign'] : ['verify'];
+    if (d) { jwk.d = d }
+    return jwk;
+  },
+  // sea/sha256.js
+  async sha(d) {
+    const t = ('string' == typeof d) ? d : JSON.stringify(d);
+    return Buffer.from(await subtle.digest({ name: 'SHA-256' }, new TextEncoder().encode(t)));
+  },
+  // sea/pair.js
+  async pair() {
+    const key = await subtle.generateKey({ name: 'ECDSA', namedCurve: 'P-256' }, true, ['sign', 'verify']);
+    const { x, y, d } = await subtle.exportKey('jwk', key.privateKey);
+    const ekey = await subtle.generateKey({ name: 'ECDH', namedCurve: 'P-256' }, true, ['deriveKey']);
+    const e = await subtle.exportKey('jwk', ekey.privateKey);
+    return { pub: x + '.' + y, priv: d, epub: e.x + '.' + e.y, epriv: e.d };
+  },
+  // sea/aeskey.js
+  async aeskey(key, salt) {
+    const hash = await port.sha(key + salt.toString('utf8'));
+    const k = hash.toString('base64').replace(/\+/g, '-').replace(/\//g, '_').replace(/\=/g, '');
+    return subtle.importKey('jwk', { kty: 'oct', k, ext: false, alg: 'A256GCM' }, { name: 'AES-GCM' }, false, ['encrypt', 'decrypt']);
+  },
+  // sea/secret.js
+  async secret(key, pair) {
+    const ecdh = (pub, d) => {
+      const [x, y] = pub.split('.');
+      return Object.assign(d ? { d } : {}, { x, y, kty: 'EC', crv: 'P-256', ext: true });
+    };
+    const pub = await subtle.importKey('jwk', ecdh(key.epub || key), { name: 'ECDH', namedCurve: 'P-256' }, true, []);
+    const priv = await subtle.importKey('jwk', ecdh(pair.epub, pair.epriv), { name: 'ECDH', namedCurve: 'P-256' }, false, ['deriveBits']);
+    const bits = await subtle.deriveBits({ name: 'ECDH', namedCurve: 'P-256', public: pub }, priv, 256);
+    const derived = await subtle.importKey('raw', new Uint8Array(bits), { name: 'AES-GCM', length: 256 }, true, ['encrypt', 'decrypt']);
+    return (await subtle.exportKey('jwk', derived)).k;
+  },
+  // sea/encrypt.js
+  async encrypt(data, pair) {
+    const key = (pair || {}).epriv || pair;
+    const msg = ('string' == typeof data) ? data : JSON.stringify(data);
+    const rand = { s: Buffer.from(webcrypto.getRandomValues(new Uint8Array(9))), iv: Buffer.from(webcrypto.getRandomValues(new Uint8Array(15))) };
+    const ct = await subtle.encrypt({ name: 'AES-GCM', iv: new Uint8Array(rand.iv) }, await port.aeskey(key, rand.s), new TextEncoder().encode(msg));
+    return 'SEA' + JSON.stringify({ ct: Buffer.from(ct, 'binary').toString('base64'), iv: rand.iv.toString('base64'), s: rand.s.toString('base64') });
+  },
+  // sea/decrypt.js
+  async decrypt(data, pair) {
+    const key = (pair || {}).epriv || pair;
+    const json = port.parse(data);
+    const aes = await port.aeskey(key, Buffer.from(json.s, 'base64'));
+    const ct = await subtle.decrypt({ name: 'AES-GCM', iv: new Uint8Array(Buffer.from(json.iv, 'base64')), tagLength: 128 }, aes, new Uint8Array(Buffer.from(json.ct, 'base64')));
+    return port.parse(new TextDecoder('utf8').decode(ct));
+  },
+  // sea/sign.js
+  async sign(data, pair) {
+    const json = port.parse(data);
+    const key = await subtle.importKey('jwk', port.jwk(pair.pub, pair.priv), { name: 'ECDSA', namedCurve: 'P-256' }, false, ['sign']);
+    const sig = await subtle.sign({ name: 'ECDSA', hash: { name: 'SHA-256' } }, key, new Uint8Array(await port.sha(json)));
+    return 'SEA' + JSON.stringify({ m: json, s: Buffer.from(sig, 'binary').toString('base64') });
+  },
+  // sea/verify.js
+  async verify(data, pub) {
+    const json = port.parse(data);
+    const key = await subtle.importKey('jwk', port.jwk(pub), { name: 'ECDSA', namedCurve: 'P-256' }, false, ['verify']);
+    const sig = new Uint8Array(Buffer.from(json.s, 'base64'));
+    const check = await subtle.verify({ name: 'ECDSA', hash: { name: 'SHA-256' } }, key, sig, new Uint8Array(await port.sha(json.m)));
+    return check ? port.parse(json.m) : undefined;
+  },
+};
+
+function sea() {
+  try {
+    return require('gun/sea');
+  } catch (e) {
+    return port;
+  }
+}
+
+const SAMPLES = [
+  'hello world',
+  '',
+  42,
+  -7,
+  1.5,
+  0.1,
+  1e21,
+  true,
+  null,
+  [1, 'two', { three: 3 }],
+  { message: 'hello', n: 30 },
+  { nested: { list: [1, 2, 3], flag: false }, 'ünïcödé': 'é"\n' },
+];
+
+async function signFixtures() {
+  const SEA = sea();
+  const pair = await SEA.pair();
+  const signed = [];
+  for (const data of SAMPLES) {
+    signed.push({ data, sea: await SEA.sign(data, pair) });
+  }
+  process.stdout.write(JSON.stringify({ pub: pair.pub, signed }, null, 2) + '\n');
+}
+
+async function verify(file) {
+  const SEA = sea();
+  const { pub, signed } = JSON.parse(require('fs').readFileSync(file, 'utf8'));
+  let failed = 0;
+  for (const { data, sea } of signed) {
+    const verified = await SEA.verify(sea, pub);
+    if (JSON.stringify(verified) !== JSON.stringify(data)) {
+      console.error('not verified:', sea, 'expected', JSON.stringify(data), 'got', JSON.stringify(verified));
+      failed++;
+    }
+  }
+  if (failed) { process.exit(1) }
+  console.log('verified', signed.length);
+}
+
+async function encryptFixtures() {
+  const SEA = sea();
+  const alice = await SEA.pair();
+  const bob = await SEA.pair();
+  const own = [];
+  const shared = [];
+  for (const data of SAMPLES) {
+    own.push({ data, sea: await SEA.encrypt(data, alice) });
+    shared.push({ data, sea: await SEA.encrypt(data, await SEA.secret(bob.epub, alice)) });
+  }
+  const secret = await SEA.secret(bob.epub, alice);
+  process.stdout.write(JSON.stringify({ alice, bob, secret, own, shared }, null, 2) + '\n');
+}
+
+// `own` is decrypted by alice, `shared` by bob from alice
+async function decrypt(file) {
+  const SEA = sea();
+  const { alice, bob, own, shared } = JSON.parse(require('fs').readFileSync(file, 'utf8'));
+  const secret = await SEA.secret(alice.epub, bob);
+  let failed = 0;
+  for (const [entries, key] of [[own, alice], [shared, secret]]) {
+    for (const { data, sea } of entries) {
+      const decrypted = await SEA.decrypt(sea, key);
+      if (JSON.stringify(decrypted) !== JSON.stringify(data)) {
+        console.error('not decrypted:', sea, 'expected', JSON.stringify(data), 'got', JSON.stringify(decrypted));
+        failed++;
+      }
+    }
+  }
+  if (failed) { process.exit(1) }
+  console.log('decrypted', own.length + shared.length);
+}
+
+// sea/index.js check.pub: `{":": val, "~": sig}`, signed over the soul,
+// key, value and state
+async function signPut(SEA, soul, key, val, state, pair) {
+  const signed = port.parse(await SEA.sign({ '#': soul, '.': key, ':': val, '>': state }, pair));
+  return JSON.stringify({ ':': val, '~': signed.s });
+}
+
+async function localstorageFixture() {
+  const SEA = sea();
+  const alice = await SEA.pair();
+  const soul = '~' + alice.pub;
+  const node = (id, states, data) => Object.assign({ _: { '#': id, '>': states } }, data);
+  const graph = {
+    todo: node('todo', {
+      lx8k2q1a0FqDtY3Jb2Rz: 1718035287543.001,
+      lx8k3b7f02kPmWnX5vCe: 1718035301120.002,
+      lx8k4c9d0aHsLtQ7eUoM: 1718035322871,
+    }, {
+      lx8k2q1a0FqDtY3Jb2Rz: { '#': 'lx8k2q1a0FqDtY3Jb2Rz' },
+      lx8k3b7f02kPmWnX5vCe: { '#': 'lx8k3b7f02kPmWnX5vCe' },
+      lx8k4c9d0aHsLtQ7eUoM: null,
+    }),
+    lx8k2q1a0FqDtY3Jb2Rz: node('lx8k2q1a0FqDtY3Jb2Rz', { title: 1718035287542, done: 1718035410003.001 }, { title: 'Buy milk', done: true }),
+    lx8k3b7f02kPmWnX5vCe: node('lx8k3b7f02kPmWnX5vCe', { title: 1718035301119, done: 1718035301119, priority: 1718035301119 }, { title: 'Walk the dog', done: false, priority: 2 }),
+    lx8k4c9d0aHsLtQ7eUoM: node('lx8k4c9d0aHsLtQ7eUoM', { title: 1718035322870 }, { title: 'Removed item' }),
+    '~@alice': node('~@alice', { [soul]: 1718035200000.004 }, { [soul]: { '#': soul } }),
+  };
+  const account = { alias: 'alice', pub: alice.pub, epub: alice.epub };
+  const states = { alias: 1718035200000.002, pub: 1718035200000.003, epub: 1718035200000.001 };
+  const signed = {};
+  for (const key of Object.keys(account)) {
+    signed[key] = await signPut(SEA, soul, key, account[key], states[key], alice);
+  }
+  graph[soul] = node(soul, states, signed);
+  const local = { 'gun/': JSON.stringify(graph), debug: '', 'todo-app:theme': 'dark' };
+  process.stdout.write(JSON.stringify(local, null, 2) + '\n');
+}
+
+const MODES = {
+  'sign-fixtures': signFixtures,
+  verify,
+  'encrypt-fixtures': encryptFixtures,
+  decrypt,
+  'localstorage-fixture': localstorageFixture,
+};
+const [mode, file] = process.argv.slice(2);
+(MODES[mode] ? MODES[mode](file) : Promise.reject(new Error('usage: sea_interop.js sign-fixtures | verify <file> | encrypt-fixtures | decrypt <file> | localstorage-fixture')))
+  .catch((e) => { console.error(e); process.exit(1) });
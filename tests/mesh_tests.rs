@@ -0,0 +1,2227 @@
+//! Tests for DAM mesh message handling against in-process mock peers
+//! A mock peer is a `Peer` whose sender is an mpsc channel the test reads frames from
+
+use chia_bls::SecretKey;
+use gun::core::GunCore;
+use gun::dam::{
+    LaneStats, Mesh, MeshOptions, Outbox, Peer, PeerLifecycleEvent, PeerTransition, PeerTransport, PexOptions, QueuePolicy, CONTENT_HASH_MISMATCH_EVENT,
+    PEER_BANNED_EVENT, PEER_ERROR_EVENT, PEER_LIFECYCLE_EVENT, PEER_TIMEOUT_EVENT, PEX_DIAL_EVENT, RTC_SIGNAL_EVENT,
+};
+use gun::{Gun, GunError, GunOptions, MessageFilter, MessagePredicate, PredicateContext, PredicateDecision, PublicKeyBytes};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::time::{sleep, Duration};
+
+// Mesh with its own key pair and the given options
+fn create_mesh(seed: u8, opt: MeshOptions) -> Mesh {
+    let secret_key = SecretKey::from_seed(&[seed; 32]);
+    let public_key = secret_key.public_key();
+    Mesh::new(Arc::new(GunCore::new()), secret_key, public_key, None).with_options(opt)
+}
+
+// Mesh configured by `configure`, along with its core
+fn mesh_with_core(seed: u8, configure: impl FnOnce(Mesh) -> Mesh) -> (Arc<GunCore>, Mesh) {
+    let core = Arc::new(GunCore::new());
+    let key = SecretKey::from_seed(&[seed; 32]);
+    let mesh = configure(Mesh::new(core.clone(), key.clone(), key.public_key(), None));
+    (core, mesh)
+}
+
+// Register a mock peer and return the receiving end of its connection
+async fn add_mock_peer(mesh: &Mesh, url: &str) -> (Peer, mpsc::UnboundedReceiver<String>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let mut peer = Peer::new(url.to_string());
+    peer.set_sender(tx);
+    mesh.hi(peer.clone()).await.unwrap();
+    (peer, rx)
+}
+
+// Connect two meshes with in-memory channels, delivering each frame to the other side's hear()
+async fn link(a: &Arc<Mesh>, b: &Arc<Mesh>) {
+    let (peer_b, rx_to_b) = add_mock_peer(a, "ws://mock/b").await; // a's view of b
+    let (peer_a, rx_to_a) = add_mock_peer(b, "ws://mock/a").await; // b's view of a
+    for (to, from, mut rx) in [(b.clone(), peer_a, rx_to_b), (a.clone(), peer_b, rx_to_a)] {
+        tokio::spawn(async move {
+            while let Some(frame) = rx.recv().await {
+                let _ = to.hear(&frame, Some(&from)).await;
+            }
+        });
+    }
+}
+
+// Everything sent so far, one entry per frame
+fn drain_frames(rx: &mut mpsc::UnboundedReceiver<String>) -> Vec<String> {
+    let mut frames = Vec::new();
+    while let Ok(frame) = rx.try_recv() {
+        frames.push(frame);
+    }
+    frames
+}
+
+// Messages carried by the frames, unpacking batches
+fn unpack(frames: &[String]) -> Vec<Value> {
+    let mut messages = Vec::new();
+    for frame in frames {
+        match serde_json::from_str::<Value>(frame).unwrap() {
+            Value::Array(batch) => messages.extend(batch),
+            msg => messages.push(msg),
+        }
+    }
+    messages
+}
+
+fn put_message(i: usize) -> Value {
+    json!({ "put": { "counter": { "n": i } } })
+}
+
+// ========== Batching Tests ==========
+
+#[tokio::test]
+async fn test_mesh_batches_rapid_messages_within_gap() {
+    let mesh = create_mesh(1, MeshOptions {
+        gap: 50,
+        pack_size: 100_000,
+        ..Default::default()
+    });
+    let (peer, mut rx) = add_mock_peer(&mesh, "ws://mock/batch").await;
+
+    // Let the window opened by the "hi" message close
+    sleep(Duration::from_millis(100)).await;
+    drain_frames(&mut rx);
+
+    let count = 20;
+    for i in 0..count {
+        mesh.say(&put_message(i), Some(&peer)).await.unwrap();
+    }
+    sleep(Duration::from_millis(150)).await;
+
+    let frames = drain_frames(&mut rx);
+    assert!(frames.len() < count, "{} messages arrived as {} frames", count, frames.len());
+    assert_eq!(frames.len(), 2, "first message is sent at once, the rest in one batch");
+    assert!(frames[1].starts_with('['));
+
+    let messages = unpack(&frames);
+    assert_eq!(messages.len(), count);
+    for (i, msg) in messages.iter().enumerate() {
+        assert_eq!(msg["put"], put_message(i)["put"]);
+    }
+
+    // A batch frame is heard like the individual messages
+    let receiver = create_mesh(2, MeshOptions::default());
+    receiver.hear(&frames[1], None).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_mesh_batch_flushes_at_pack_size() {
+    let sample_len = {
+        let mesh = create_mesh(3, MeshOptions::default());
+        let (peer, mut rx) = add_mock_peer(&mesh, "ws://mock/size").await;
+        drain_frames(&mut rx);
+        mesh.say(&put_message(0), Some(&peer)).await.unwrap();
+        drain_frames(&mut rx)[0].len()
+    };
+
+    // Room for three messages per batch
+    let mesh = create_mesh(3, MeshOptions {
+        gap: 1000,
+        pack_size: sample_len * 3 + 2,
+        ..Default::default()
+    });
+    let (peer, mut rx) = add_mock_peer(&mesh, "ws://mock/size").await;
+    for i in 0..7 {
+        mesh.say(&put_message(i), Some(&peer)).await.unwrap();
+    }
+
+    // hi, then full batches of three go out without waiting for the window
+    let frames = drain_frames(&mut rx);
+    let messages = unpack(&frames);
+    assert_eq!(frames.len(), 3);
+    assert_eq!(messages.len(), 5);
+    assert_eq!(messages[1]["put"], put_message(0)["put"]);
+    assert_eq!(messages[4]["put"], put_message(3)["put"]);
+}
+
+#[tokio::test]
+async fn test_mesh_without_gap_sends_each_message() {
+    let mesh = create_mesh(4, MeshOptions::default());
+    let (peer, mut rx) = add_mock_peer(&mesh, "ws://mock/immediate").await;
+    drain_frames(&mut rx);
+
+    for i in 0..5 {
+        mesh.say(&put_message(i), Some(&peer)).await.unwrap();
+    }
+    let frames = drain_frames(&mut rx);
+    assert_eq!(frames.len(), 5);
+    assert!(frames.iter().all(|frame| frame.starts_with('{')));
+}
+
+// ========== Reply Routing Tests ==========
+
+#[tokio::test]
+async fn test_mesh_get_response_carries_request_id() {
+    let a = Arc::new(create_mesh(5, MeshOptions::default()));
+    let b_core = Arc::new(GunCore::new());
+    let b_key = SecretKey::from_seed(&[6; 32]);
+    let b = Arc::new(Mesh::new(b_core.clone(), b_key.clone(), b_key.public_key(), None));
+    let mut node = gun::state::Node::with_soul("user".to_string());
+    node.data.insert("name".to_string(), json!("Alice"));
+    b_core.graph.put("user", node).unwrap();
+    link(&a, &b).await;
+
+    let (id, reply) = a.ask(&json!({ "get": { "#": "user" } })).await.unwrap();
+    let response = tokio::time::timeout(Duration::from_secs(5), reply).await.unwrap().unwrap();
+    assert_eq!(response["@"], json!(id));
+    assert_eq!(response["put"]["user"]["name"], json!("Alice"));
+}
+
+// Response of a Gun.js relay to `{"get": {"#": "alice"}}`
+const GUNJS_GET_RESPONSE: &str = include_str!("fixtures/gunjs_get_response.json");
+
+// Graph node for "alice" with the states used in the recorded response
+fn store_alice(core: &GunCore) {
+    let mut node = gun::state::Node::with_soul("alice".to_string());
+    gun::state::State::ify(&mut node, Some("name"), Some(1700000000000.0), Some(json!("Alice")), None);
+    gun::state::State::ify(&mut node, Some("age"), Some(1700000000123.0), Some(json!(30)), None);
+    core.graph.put("alice", node).unwrap();
+}
+
+#[tokio::test]
+async fn test_mesh_get_response_matches_gunjs_format() {
+    let (a_core, a) = mesh_with_core(18, |mesh| mesh);
+    let (b_core, b) = mesh_with_core(19, |mesh| mesh);
+    let (a, b) = (Arc::new(a), Arc::new(b));
+    store_alice(&b_core);
+    link(&a, &b).await;
+
+    let (id, reply) = a.ask(&json!({ "get": { "#": "alice" } })).await.unwrap();
+    let response = tokio::time::timeout(Duration::from_secs(5), reply).await.unwrap().unwrap();
+
+    let recorded: Value = serde_json::from_str(GUNJS_GET_RESPONSE).unwrap();
+    assert_eq!(response["put"], recorded["put"]);
+    assert_eq!(response["@"], json!(id));
+
+    // The receiver keeps the sender's states rather than stamping its own
+    let node = a_core.graph.get("alice").unwrap();
+    assert_eq!(gun::state::State::is(&Some(node.clone()), "name"), Some(1700000000000.0));
+    assert_eq!(gun::state::State::is(&Some(node), "age"), Some(1700000000123.0));
+}
+
+#[tokio::test]
+async fn test_mesh_get_key_response_carries_only_that_key() {
+    let a = Arc::new(create_mesh(20, MeshOptions::default()));
+    let (b_core, b) = mesh_with_core(21, |mesh| mesh);
+    let b = Arc::new(b);
+    store_alice(&b_core);
+    link(&a, &b).await;
+
+    let (_id, reply) = a.ask(&json!({ "get": { "#": "alice", ".": "age" } })).await.unwrap();
+    let response = tokio::time::timeout(Duration::from_secs(5), reply).await.unwrap().unwrap();
+
+    assert_eq!(
+        response["put"],
+        json!({ "alice": { "_": { "#": "alice", ">": { "age": 1700000000123u64 } }, "age": 30 } })
+    );
+}
+
+#[tokio::test]
+async fn test_mesh_unrelated_put_does_not_answer_request() {
+    let a = Arc::new(create_mesh(7, MeshOptions::default()));
+    let b = Arc::new(create_mesh(8, MeshOptions::default()));
+    link(&a, &b).await;
+
+    // b has nothing for "user", so the get stays unanswered
+    let (_id, mut reply) = a.ask(&json!({ "get": { "#": "user" } })).await.unwrap();
+
+    // A put for the same soul is merged but isn't the answer
+    b.say(&json!({ "put": { "user": { "name": "Bob" } } }), None).await.unwrap();
+    sleep(Duration::from_millis(200)).await;
+    assert!(reply.try_recv().is_err());
+
+    // The put itself is acked to its sender
+    let (id, ack) = b.ask(&json!({ "put": { "user": { "name": "Carol" } } })).await.unwrap();
+    let ack = tokio::time::timeout(Duration::from_secs(5), ack).await.unwrap().unwrap();
+    assert_eq!(ack["@"], json!(id));
+    assert_eq!(ack["ok"], json!({ "": 1 }));
+    assert!(reply.try_recv().is_err());
+}
+
+// ========== Get Matching Tests ==========
+
+// A "wide" node with keys k00..k49 plus a few others, each with state 1000 + i
+async fn wide_relay(seed: u8) -> (Arc<Mesh>, Arc<Mesh>) {
+    let (b_core, b) = mesh_with_core(seed, |mesh| mesh);
+    let mut node = gun::state::Node::with_soul("wide".to_string());
+    let keys = (0..50).map(|i| format!("k{:02}", i)).chain(["alpha".to_string(), "zeta".to_string()]);
+    for (i, key) in keys.enumerate() {
+        gun::state::State::ify(&mut node, Some(&key), Some(1000.0 + i as f64), Some(json!(format!("value {}", i))), None);
+    }
+    b_core.graph.put("wide", node).unwrap();
+
+    let a = Arc::new(create_mesh(seed + 1, MeshOptions::default()));
+    let b = Arc::new(b);
+    link(&a, &b).await;
+    (a, b)
+}
+
+// Keys (in order) and states of the node returned for a get
+async fn get_keys(a: &Mesh, get: Value) -> (Vec<String>, Value) {
+    let (_id, reply) = a.ask(&json!({ "get": get })).await.unwrap();
+    let response = tokio::time::timeout(Duration::from_secs(5), reply).await.unwrap().unwrap();
+    let node = response["put"]["wide"].as_object().unwrap().clone();
+    let keys = node.keys().filter(|key| *key != "_").cloned().collect();
+    (keys, node["_"][">"].clone())
+}
+
+#[tokio::test]
+async fn test_mesh_get_exact_key() {
+    let (a, _b) = wide_relay(30).await;
+    let (keys, states) = get_keys(&a, json!({ "#": "wide", ".": "k07" })).await;
+    assert_eq!(keys, vec!["k07"]);
+    assert_eq!(states, json!({ "k07": 1007 }));
+}
+
+#[tokio::test]
+async fn test_mesh_get_key_prefix() {
+    let (a, _b) = wide_relay(32).await;
+    let (keys, states) = get_keys(&a, json!({ "#": "wide", ".": { "*": "k1" } })).await;
+    let expected: Vec<String> = (10..20).map(|i| format!("k{}", i)).collect();
+    assert_eq!(keys, expected);
+    assert_eq!(states.as_object().unwrap().len(), 10);
+    assert_eq!(states["k15"], json!(1015));
+}
+
+#[tokio::test]
+async fn test_mesh_get_key_range() {
+    let (a, _b) = wide_relay(34).await;
+    let (keys, _) = get_keys(&a, json!({ "#": "wide", ".": { ">": "k45", "<": "k48" } })).await;
+    assert_eq!(keys, vec!["k45", "k46", "k47", "k48"]);
+
+    // Open-ended ranges
+    let (keys, _) = get_keys(&a, json!({ "#": "wide", ".": { ">": "k49" } })).await;
+    assert_eq!(keys, vec!["k49", "zeta"]);
+    let (keys, _) = get_keys(&a, json!({ "#": "wide", ".": { "<": "k00" } })).await;
+    assert_eq!(keys, vec!["alpha", "k00"]);
+}
+
+#[tokio::test]
+async fn test_mesh_get_byte_limit() {
+    let (a, _b) = wide_relay(36).await;
+    // Each entry is a 3-byte key and a 10-byte value: room for five
+    let (keys, states) = get_keys(&a, json!({ "#": "wide", ".": { "*": "k" }, "%": 65 })).await;
+    assert_eq!(keys, vec!["k00", "k01", "k02", "k03", "k04"]);
+    assert_eq!(states.as_object().unwrap().len(), 5);
+
+    // A limit smaller than one entry still sends the first key
+    let (keys, _) = get_keys(&a, json!({ "#": "wide", "%": 1 })).await;
+    assert_eq!(keys, vec!["alpha"]);
+}
+
+#[tokio::test]
+async fn test_mesh_get_without_matching_keys_is_not_answered() {
+    let (a, _b) = wide_relay(38).await;
+    let (_id, mut reply) = a.ask(&json!({ "get": { "#": "wide", ".": { "*": "nope" } } })).await.unwrap();
+    sleep(Duration::from_millis(200)).await;
+    assert!(reply.try_recv().is_err());
+}
+
+// ========== Batched Get Tests ==========
+
+fn souls(count: usize) -> Vec<String> {
+    (0..count).map(|i| format!("item{}", i)).collect()
+}
+
+#[tokio::test]
+async fn test_mesh_ask_get_sends_one_message() {
+    let mesh = create_mesh(40, MeshOptions {
+        get_gap: 20,
+        ..Default::default()
+    });
+    let (_, mut rx) = add_mock_peer(&mesh, "ws://mock/relay").await;
+    drain_frames(&mut rx);
+
+    // Twelve gets, one of them asked twice
+    let mut gets: Vec<Value> = souls(12).into_iter().map(|soul| json!({ "#": soul })).collect();
+    gets.push(json!({ "#": "item0" }));
+    let asked = futures::future::join_all(gets.into_iter().map(|get| mesh.ask_get(get))).await;
+    assert!(asked.iter().all(|ask| ask.is_ok()));
+
+    let messages = unpack(&drain_frames(&mut rx));
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0]["get"].as_array().unwrap().len(), 12);
+}
+
+#[tokio::test]
+async fn test_mesh_batched_get_answers_found_souls() {
+    let a = Arc::new(create_mesh(42, MeshOptions {
+        get_gap: 20,
+        ..Default::default()
+    }));
+    let (b_core, b) = mesh_with_core(43, |mesh| mesh);
+    let b = Arc::new(b);
+    for soul in souls(10) {
+        let mut node = gun::state::Node::with_soul(soul.clone());
+        gun::state::State::ify(&mut node, Some("name"), Some(1.0), Some(json!(soul)), None);
+        b_core.graph.put(&soul, node).unwrap();
+    }
+    link(&a, &b).await;
+
+    // Twelve requests, two for souls the relay doesn't have, share one response
+    let replies = futures::future::join_all(souls(12).into_iter().map(|soul| a.ask_get(json!({ "#": soul })))).await;
+    for reply in replies {
+        let response = tokio::time::timeout(Duration::from_secs(5), reply.unwrap()).await.unwrap().unwrap();
+        let put = response["put"].as_object().unwrap();
+        assert_eq!(put.len(), 10);
+        assert_eq!(put["item9"]["name"], json!("item9"));
+        assert!(!put.contains_key("item10") && !put.contains_key("item11"));
+    }
+
+    // The soul-array form gets the same answer
+    let (id, reply) = a.ask(&json!({ "get": { "#": souls(12) } })).await.unwrap();
+    let response = tokio::time::timeout(Duration::from_secs(5), reply).await.unwrap().unwrap();
+    assert_eq!(response["@"], json!(id));
+    assert_eq!(response["put"].as_object().unwrap().len(), 10);
+}
+
+// ========== Forwarding Tests ==========
+
+#[tokio::test]
+async fn test_mesh_forwards_duplicate_message_once() {
+    // A signed message, as it would arrive on the wire
+    let origin = create_mesh(11, MeshOptions::default());
+    let (_, mut origin_rx) = add_mock_peer(&origin, "ws://mock/origin").await;
+    drain_frames(&mut origin_rx);
+    origin.say(&put_message(1), None).await.unwrap();
+    let raw = drain_frames(&mut origin_rx).remove(0);
+
+    let relay = create_mesh(12, MeshOptions::default());
+    let (from_a, mut rx_a) = add_mock_peer(&relay, "ws://mock/a").await;
+    let (from_b, mut rx_b) = add_mock_peer(&relay, "ws://mock/b").await;
+    let (_, mut rx_c) = add_mock_peer(&relay, "ws://mock/c").await;
+    for rx in [&mut rx_a, &mut rx_b, &mut rx_c] {
+        drain_frames(rx);
+    }
+
+    // The same message reaches the relay through two of its peers
+    relay.hear(&raw, Some(&from_a)).await.unwrap();
+    relay.hear(&raw, Some(&from_b)).await.unwrap();
+
+    // Count copies of the message itself, not the put ack sent back to its sender
+    let id = serde_json::from_str::<Value>(&raw).unwrap()["#"].clone();
+    let forwarded = |rx: &mut mpsc::UnboundedReceiver<String>| {
+        unpack(&drain_frames(rx)).iter().filter(|msg| msg["#"] == id).count()
+    };
+    let sent_a = forwarded(&mut rx_a);
+    let sent_b = forwarded(&mut rx_b);
+    let sent_c = forwarded(&mut rx_c);
+    assert_eq!(sent_a, 0, "not sent back to the peer it came from");
+    assert_eq!(sent_b, 1);
+    assert_eq!(sent_c, 1, "forwarded once, not once per copy");
+}
+
+#[tokio::test]
+async fn test_mesh_put_acks_retrace_the_put() {
+    // A - B - C - D, with E off to the side of B and a client X of A
+    let nodes: Vec<Arc<Mesh>> = (0..5).map(|i| Arc::new(create_mesh(110 + i, MeshOptions::default()))).collect();
+    let (a, b, c, d, e) = (&nodes[0], &nodes[1], &nodes[2], &nodes[3], &nodes[4]);
+    for (x, y) in [(a, b), (b, c), (c, d), (b, e)] {
+        link(x, y).await;
+    }
+    let (_, mut x_rx) = add_mock_peer(a, "ws://mock/x").await;
+    sleep(Duration::from_millis(200)).await;
+    drain_frames(&mut x_rx);
+    let forwarded = || nodes.iter().map(|n| n.stats().messages_forwarded).sum::<u64>();
+    let before = forwarded();
+
+    a.say(&put_message(1), None).await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+
+    // The put: B to C and E, C to D. The acks of C, D and E: one forward per
+    // hop back to A, and none past A
+    assert_eq!(forwarded() - before, 3 + 4);
+    let to_x = unpack(&drain_frames(&mut x_rx));
+    assert!(to_x.iter().all(|msg| msg.get("@").is_none()), "A keeps the acks to itself");
+}
+
+// ========== Peer Scoring Tests ==========
+
+#[tokio::test]
+async fn test_mesh_disconnects_misbehaving_peer() {
+    let core = Arc::new(GunCore::new());
+    let key = SecretKey::from_seed(&[13; 32]);
+    let relay = Mesh::new(core.clone(), key.clone(), key.public_key(), None).with_options(MeshOptions {
+        max_score: 10,
+        ban_time: 60_000,
+        ..Default::default()
+    });
+    let banned = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let banned_events = banned.clone();
+    core.events.on(
+        PEER_BANNED_EVENT,
+        Box::new(move |event: &gun::events::Event| banned_events.lock().unwrap().push(event.data.clone())),
+    );
+
+    let (bad, mut bad_rx) = add_mock_peer(&relay, "ws://bad.example:4000/gun").await;
+    let (good, _good_rx) = add_mock_peer(&relay, "ws://good.example:4000/gun").await;
+    drain_frames(&mut bad_rx);
+
+    // A valid message from the good peer
+    let origin = create_mesh(14, MeshOptions::default());
+    let (_, mut origin_rx) = add_mock_peer(&origin, "ws://mock/origin").await;
+    drain_frames(&mut origin_rx);
+    origin.say(&put_message(1), None).await.unwrap();
+    let valid = drain_frames(&mut origin_rx).remove(0);
+    relay.hear(&valid, Some(&good)).await.unwrap();
+
+    // Tampered messages from the bad one: the ID no longer matches the content
+    let tampered = |i: usize| {
+        let mut msg: Value = serde_json::from_str(&valid).unwrap();
+        msg["put"] = put_message(100 + i)["put"].clone();
+        msg.to_string()
+    };
+    for i in 0..2 {
+        relay.hear(&tampered(i), Some(&bad)).await.unwrap();
+    }
+    assert_eq!(relay.peer_scores()[&bad.id].invalid_hash, 2);
+    assert!(relay.get_peer(&bad.id).await.is_some(), "tolerated below max_score");
+
+    for i in 2..5 {
+        relay.hear(&tampered(i), Some(&bad)).await.unwrap();
+    }
+    assert!(relay.get_peer(&bad.id).await.is_none());
+    assert!(relay.get_peer(&good.id).await.is_some());
+    assert_eq!(*relay.near.read().await, 1);
+    assert!(!relay.peer_scores().contains_key(&bad.id));
+
+    // Told why before being dropped
+    let told = unpack(&drain_frames(&mut bad_rx));
+    assert!(told.iter().any(|msg| msg["dam"] == json!("!")));
+
+    // Its host is refused for ban_time, others aren't
+    assert!(relay.is_banned("ws://bad.example:5123"));
+    assert!(!relay.is_banned("ws://good.example:4000/gun"));
+
+    let banned = banned.lock().unwrap();
+    assert_eq!(banned.len(), 1);
+    assert_eq!(banned[0]["peer"], json!(bad.id));
+    assert_eq!(banned[0]["invalid_hash"], json!(5));
+    assert_eq!(banned[0]["score"], json!(10));
+}
+
+// ========== Unsigned Mode Tests ==========
+
+// A put as sent by a stock Gun.js peer: random "#", no "sigs"
+const GUNJS_PUT: &str = include_str!("fixtures/gunjs_put_message.json");
+
+#[tokio::test]
+async fn test_mesh_unsigned_mode_accepts_gunjs_message() {
+    let (core, mesh) = mesh_with_core(15, |mesh| mesh.with_require_signatures(false));
+    let (gunjs, _) = add_mock_peer(&mesh, "ws://gunjs.example/gun").await;
+    let (_, mut other_rx) = add_mock_peer(&mesh, "ws://other.example/gun").await;
+    drain_frames(&mut other_rx);
+
+    mesh.hear(GUNJS_PUT.trim(), Some(&gunjs)).await.unwrap();
+
+    let node = core.graph.get("alice").expect("put from Gun.js peer applied");
+    assert_eq!(node.data.get("name"), Some(&json!("Alice")));
+    assert_eq!(node.data.get("age"), Some(&json!(30)));
+
+    // Forwarded unchanged, and only once
+    mesh.hear(GUNJS_PUT.trim(), Some(&gunjs)).await.unwrap();
+    let forwarded = unpack(&drain_frames(&mut other_rx));
+    let copies: Vec<_> = forwarded.iter().filter(|msg| msg["#"] == json!("kl5tq9xmeop")).collect();
+    assert_eq!(copies.len(), 1);
+    assert!(copies[0].get("sigs").is_none());
+
+    // Our own messages go out without signatures
+    mesh.say(&put_message(1), None).await.unwrap();
+    let ours = unpack(&drain_frames(&mut other_rx));
+    assert!(ours[0].get("#").is_some());
+    assert!(ours[0].get("sigs").is_none());
+}
+
+#[tokio::test]
+async fn test_mesh_signed_mode_rejects_gunjs_message() {
+    let (core, mesh) = mesh_with_core(16, |mesh| mesh);
+    let (gunjs, _) = add_mock_peer(&mesh, "ws://gunjs.example/gun").await;
+
+    mesh.hear(GUNJS_PUT.trim(), Some(&gunjs)).await.unwrap();
+
+    assert!(core.graph.get("alice").is_none());
+    // Its random "#" isn't the hash a signed message must carry
+    assert_eq!(mesh.peer_scores()[&gunjs.id].invalid_hash, 1);
+}
+
+#[tokio::test]
+async fn test_mesh_signed_peers_must_sign_in_unsigned_mode() {
+    let (core, mesh) = mesh_with_core(17, |mesh| {
+        mesh.with_require_signatures(false)
+            .with_signed_peers(vec!["wss://relay.example:8765/gun".to_string()])
+    });
+    let (relay, mut relay_rx) = add_mock_peer(&mesh, "wss://relay.example:8765/gun").await;
+    let (gunjs, _) = add_mock_peer(&mesh, "ws://gunjs.example/gun").await;
+    drain_frames(&mut relay_rx);
+
+    mesh.hear(GUNJS_PUT.trim(), Some(&relay)).await.unwrap();
+    assert!(core.graph.get("alice").is_none(), "the relay must sign");
+
+    mesh.hear(GUNJS_PUT.trim(), Some(&gunjs)).await.unwrap();
+    assert!(core.graph.get("alice").is_some());
+
+    // Unsigned messages aren't passed on to a peer that would reject them,
+    // and with signed peers around our own messages are signed
+    let to_relay = unpack(&drain_frames(&mut relay_rx));
+    assert!(to_relay.iter().all(|msg| msg.get("sigs").is_some()));
+    mesh.say(&put_message(1), Some(&relay)).await.unwrap();
+    assert!(unpack(&drain_frames(&mut relay_rx))[0].get("sigs").is_some());
+}
+
+// ========== Peer Exchange Tests ==========
+
+fn pex_options(allow_private: bool) -> MeshOptions {
+    MeshOptions {
+        pex: PexOptions {
+            enabled: true,
+            max_dials: 2,
+            allow_private,
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_mesh_shares_known_addresses_on_hi() {
+    let mesh = create_mesh(18, pex_options(false));
+    mesh.record_dial("wss://relay1.example/gun", true);
+    mesh.record_dial("http://relay2.example/gun", true);
+    mesh.record_dial("ws://192.168.1.20:8765/gun", true); // private, not shared
+    for _ in 0..3 {
+        mesh.record_dial("wss://down.example/gun", false);
+    }
+
+    let (_, mut rx) = add_mock_peer(&mesh, "wss://relay1.example/gun").await;
+    let messages = unpack(&drain_frames(&mut rx));
+    let pex = messages.iter().find(|msg| msg["dam"] == json!("pex")).expect("pex sent on hi");
+    // Not the peer's own address
+    assert_eq!(pex["peers"], json!(["ws://relay2.example/gun"]));
+
+    // Off by default
+    let quiet = create_mesh(19, MeshOptions::default());
+    quiet.record_dial("wss://relay1.example/gun", true);
+    assert!(quiet.known_addresses().is_empty());
+    let (_, mut rx) = add_mock_peer(&quiet, "ws://mock/quiet").await;
+    assert!(unpack(&drain_frames(&mut rx)).iter().all(|msg| msg["dam"] != json!("pex")));
+}
+
+#[tokio::test]
+async fn test_mesh_dials_only_valid_exchanged_addresses() {
+    let (core, mesh) = mesh_with_core(20, |mesh| mesh.with_options(pex_options(false)));
+    let dialed = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let dialed_events = dialed.clone();
+    core.events.on(
+        PEX_DIAL_EVENT,
+        Box::new(move |event: &gun::events::Event| {
+            dialed_events.lock().unwrap().push(event.data["url"].as_str().unwrap().to_string())
+        }),
+    );
+    let (sharer, _) = add_mock_peer(&mesh, "wss://sharer.example/gun").await;
+    let (_, mut other_rx) = add_mock_peer(&mesh, "ws://mock/other").await;
+    drain_frames(&mut other_rx);
+
+    let origin = create_mesh(21, MeshOptions::default());
+    let (_, mut origin_rx) = add_mock_peer(&origin, "ws://mock/origin").await;
+    drain_frames(&mut origin_rx);
+    let urls = [
+        "ws://127.0.0.1:8765/gun",
+        "ws://10.1.2.3/gun",
+        "ws://[fe80::1]:8765/gun",
+        "ws://224.0.0.251:8765/gun",
+        "ws://localhost:8765/gun",
+        "ftp://files.example/gun",
+        "wss://sharer.example/gun", // already connected
+        "wss://one.example/gun",
+        "https://two.example/gun",
+        "wss://three.example/gun", // over max_dials
+    ];
+    origin.say(&json!({ "dam": "pex", "peers": urls }), None).await.unwrap();
+    let pex = drain_frames(&mut origin_rx).remove(0);
+    mesh.hear(&pex, Some(&sharer)).await.unwrap();
+
+    assert_eq!(*dialed.lock().unwrap(), vec!["wss://one.example/gun", "wss://two.example/gun"]);
+    // Exchanged addresses stay on the link they arrived on
+    assert!(unpack(&drain_frames(&mut other_rx)).iter().all(|msg| msg["dam"] != json!("pex")));
+
+    // Not dialed again while the first dial is recent
+    origin.say(&json!({ "dam": "pex", "peers": ["wss://one.example/gun", "wss://three.example/gun"] }), None)
+        .await
+        .unwrap();
+    mesh.hear(&drain_frames(&mut origin_rx).remove(0), Some(&sharer)).await.unwrap();
+    assert_eq!(dialed.lock().unwrap().len(), 3);
+    assert_eq!(dialed.lock().unwrap()[2], "wss://three.example/gun");
+
+    // Private addresses only when allowed; multicast never
+    let (core, open) = mesh_with_core(22, |mesh| mesh.with_options(pex_options(true)));
+    let dialed = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let dialed_events = dialed.clone();
+    core.events.on(
+        PEX_DIAL_EVENT,
+        Box::new(move |event: &gun::events::Event| {
+            dialed_events.lock().unwrap().push(event.data["url"].as_str().unwrap().to_string())
+        }),
+    );
+    let (sharer, _) = add_mock_peer(&open, "wss://sharer.example/gun").await;
+    origin.say(&json!({ "dam": "pex", "peers": ["ws://224.0.0.251:8765/gun", "ws://10.1.2.3/gun"] }), None)
+        .await
+        .unwrap();
+    open.hear(&drain_frames(&mut origin_rx).remove(0), Some(&sharer)).await.unwrap();
+    assert_eq!(*dialed.lock().unwrap(), vec!["ws://10.1.2.3/gun"]);
+}
+
+// ========== Logging Tests ==========
+
+/// Collects everything a tracing subscriber writes
+#[derive(Clone, Default)]
+struct LogBuffer(Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl std::io::Write for LogBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// Hear one put from another mesh with RUST_LOG set to `filter`, returning the log output
+async fn log_put_with(filter: &str) -> String {
+    std::env::set_var("RUST_LOG", filter);
+    let logs = LogBuffer::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .with_writer(move || writer.clone())
+        .with_ansi(false)
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let origin = create_mesh(23, MeshOptions::default());
+    let (_, mut origin_rx) = add_mock_peer(&origin, "ws://mock/origin").await;
+    drain_frames(&mut origin_rx);
+    origin.say(&json!({ "put": { "logged": { "n": 1 } } }), None).await.unwrap();
+    let frame = drain_frames(&mut origin_rx).remove(0);
+
+    let mesh = create_mesh(24, MeshOptions::default());
+    let (peer, _rx) = add_mock_peer(&mesh, "ws://mock/logged").await;
+    mesh.hear(&frame, Some(&peer)).await.unwrap();
+
+    let output = logs.0.lock().unwrap().clone();
+    String::from_utf8(output).unwrap()
+}
+
+#[tokio::test]
+async fn test_mesh_trace_logging_follows_rust_log() {
+    let output = log_put_with("gun=trace").await;
+    assert!(output.contains("Received message"), "{}", output);
+    assert!(output.contains("Received put message"), "{}", output);
+    assert!(output.contains("hear_one"), "span around hear_one: {}", output);
+    assert!(output.contains("peer_id="), "{}", output);
+    assert!(output.contains("msg_id="), "{}", output);
+    assert!(output.contains("soul=logged"), "{}", output);
+
+    // Quiet unless asked for
+    let output = log_put_with("gun=warn").await;
+    assert!(!output.contains("Received put message"), "{}", output);
+}
+
+// ========== Stats Tests ==========
+
+#[tokio::test]
+async fn test_mesh_stats_count_sync_traffic() {
+    let a = Arc::new(create_mesh(25, MeshOptions::default()));
+    let b = Arc::new(create_mesh(26, MeshOptions::default()));
+    link(&a, &b).await;
+
+    for i in 0..5 {
+        a.say(&put_message(i), None).await.unwrap();
+    }
+    let (_, reply) = a.ask(&json!({ "get": { "#": "counter" } })).await.unwrap();
+    sleep(Duration::from_millis(100)).await;
+    assert!(reply.await.is_ok());
+
+    let (sent, received) = (a.stats(), b.stats());
+    assert_eq!(sent.puts_sent, 5);
+    assert_eq!(received.puts_applied, sent.puts_sent);
+    assert_eq!(received.gets_answered, 1);
+    assert_eq!(sent.puts_applied, 1, "the get response");
+    // Everything one side sends over the link, the other side hears
+    assert!(sent.bytes_out > 0);
+    assert_eq!(sent.bytes_out, received.bytes_in);
+    assert_eq!(received.bytes_out, sent.bytes_in);
+    assert_eq!(sent.messages_said, received.messages_heard);
+    assert_eq!(received.messages_said, sent.messages_heard);
+    assert_eq!(received.duplicates, 0);
+    assert_eq!(received.signature_failures + received.hash_failures, 0);
+
+    // Replayed and tampered messages
+    let origin = create_mesh(27, MeshOptions::default());
+    let (_, mut origin_rx) = add_mock_peer(&origin, "ws://mock/origin").await;
+    drain_frames(&mut origin_rx);
+    origin.say(&put_message(9), None).await.unwrap();
+    let frame = drain_frames(&mut origin_rx).remove(0);
+    let mesh = create_mesh(28, MeshOptions::default());
+    let (peer, _rx) = add_mock_peer(&mesh, "ws://mock/replay").await;
+    mesh.hear(&frame, Some(&peer)).await.unwrap();
+    mesh.hear(&frame, Some(&peer)).await.unwrap();
+    let mut tampered: Value = serde_json::from_str(&frame).unwrap();
+    tampered["put"] = put_message(10)["put"].clone();
+    mesh.hear(&tampered.to_string(), Some(&peer)).await.unwrap();
+    let stats = mesh.stats();
+    assert_eq!(stats.messages_heard, 3);
+    assert_eq!(stats.duplicates, 1);
+    assert_eq!(stats.hash_failures, 1);
+    assert_eq!(stats.puts_applied, 1);
+
+    // Messages for a disconnected peer wait in its queue
+    let offline = Peer::new("ws://mock/offline".to_string());
+    mesh.hi(offline.clone()).await.unwrap();
+    mesh.say(&put_message(11), Some(&offline)).await.unwrap();
+    assert_eq!(mesh.stats().queued[&offline.id], 2, "hi and the put");
+}
+
+// ========== Pid Routing Tests ==========
+
+#[tokio::test]
+async fn test_mesh_send_to_pid_follows_reconnect() {
+    let relay = Arc::new(create_mesh(29, MeshOptions::default()));
+    let (client_core, client) = mesh_with_core(30, |mesh| mesh);
+    let client = Arc::new(client);
+    let (_, mut other_rx) = add_mock_peer(&relay, "ws://mock/other").await;
+
+    link(&relay, &client).await;
+    sleep(Duration::from_millis(50)).await;
+    let first = relay.peer_id_for_pid(&client.pid).expect("pid learned from ?");
+    // The handshake isn't passed on, or the other peer would map the client's pid to us
+    let passed_on = unpack(&drain_frames(&mut other_rx));
+    assert!(passed_on.iter().all(|msg| msg["pid"] != json!(client.pid)));
+
+    // The connection drops on both sides and a new one is made
+    relay.bye(&first).await.unwrap();
+    assert!(relay.peer_id_for_pid(&client.pid).is_none());
+    assert!(relay.send_to_pid(&client.pid, &put_message(1)).await.is_err());
+    let relay_on_client = client.peer_id_for_pid(&relay.pid).unwrap();
+    client.bye(&relay_on_client).await.unwrap();
+
+    link(&relay, &client).await;
+    sleep(Duration::from_millis(50)).await;
+    let second = relay.peer_id_for_pid(&client.pid).expect("same ? again from the new connection");
+    assert_ne!(first, second);
+
+    relay.send_to_pid(&client.pid, &json!({ "put": { "routed": { "n": 2 } } })).await.unwrap();
+    sleep(Duration::from_millis(50)).await;
+    let node = client_core.graph.get("routed").expect("delivered over the new connection");
+    assert_eq!(node.data.get("n"), Some(&json!(2)));
+}
+
+#[tokio::test]
+async fn test_mesh_hands_rtc_signaling_to_listeners() {
+    let a = Arc::new(create_mesh(35, MeshOptions::default()));
+    let (b_core, b) = mesh_with_core(36, |mesh| mesh);
+    let b = Arc::new(b);
+    let (signal_tx, mut signal_rx) = mpsc::unbounded_channel::<Value>();
+    b_core.events.on(
+        RTC_SIGNAL_EVENT,
+        Box::new(move |event| {
+            let _ = signal_tx.send(event.data.clone());
+        }),
+    );
+
+    link(&a, &b).await;
+    sleep(Duration::from_millis(50)).await;
+    let candidate = json!({ "candidate": "candidate:1 1 UDP 2130706431 10.0.0.1 54321 typ host", "sdpMid": "0", "sdpMLineIndex": 0 });
+    a.send_to_pid(&b.pid, &json!({ "ok": { "rtc": { "id": a.pid, "candidate": candidate } } }))
+        .await
+        .unwrap();
+    let signal = tokio::time::timeout(Duration::from_secs(5), signal_rx.recv()).await.unwrap().unwrap();
+    assert_eq!(signal["ok"]["rtc"]["id"], json!(a.pid));
+    assert_eq!(signal["ok"]["rtc"]["candidate"], candidate);
+
+    // Other messages aren't signaling
+    a.send_to_pid(&b.pid, &put_message(1)).await.unwrap();
+    sleep(Duration::from_millis(50)).await;
+    assert!(signal_rx.try_recv().is_err());
+}
+
+// ========== Offline Queue Tests ==========
+
+// A peer the mesh knows but has no connection to yet
+async fn add_offline_peer(mesh: &Mesh, url: &str) -> Peer {
+    let peer = Peer::new(url.to_string());
+    mesh.hi(peer.clone()).await.unwrap();
+    peer
+}
+
+#[tokio::test]
+async fn test_mesh_queue_drops_oldest_past_cap() {
+    let mesh = create_mesh(31, MeshOptions {
+        queue_max: 10,
+        ..Default::default()
+    });
+    let offline = add_offline_peer(&mesh, "ws://mock/offline").await;
+    for i in 0..25 {
+        mesh.say(&json!({ "put": { format!("s{}", i): { "n": i } } }), Some(&offline)).await.unwrap();
+    }
+
+    let stats = mesh.stats();
+    assert_eq!(stats.queued[&offline.id], 10);
+    assert_eq!(stats.queue_dropped, 16, "the hi and the first 15 puts");
+
+    // The ten newest puts arrive as one, and it's a valid message
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    mesh.set_peer_sender(&offline.id, tx).await.unwrap();
+    let flushed = drain_frames(&mut rx);
+    assert_eq!(flushed.len(), 1);
+    assert_eq!(mesh.stats().queue_coalesced, 9);
+
+    let (core, receiver) = mesh_with_core(32, |mesh| mesh);
+    receiver.hear(&flushed[0], None).await.unwrap();
+    for i in 15..25 {
+        assert_eq!(core.graph.get(&format!("s{}", i)).unwrap().data.get("n"), Some(&json!(i)));
+    }
+    assert!(core.graph.get("s14").is_none());
+}
+
+#[tokio::test]
+async fn test_mesh_queue_collapses_puts_per_soul() {
+    let mesh = create_mesh(33, MeshOptions {
+        queue_max: 5,
+        queue_policy: QueuePolicy::CollapsePuts,
+        ..Default::default()
+    });
+    let offline = add_offline_peer(&mesh, "ws://mock/offline").await;
+    let state_put = |a: i64, b: i64, state_a: i64, state_b: i64| {
+        json!({ "put": { "doc": { "_": { "#": "doc", ">": { "a": state_a, "b": state_b } }, "a": a, "b": b } } })
+    };
+    mesh.say(&state_put(1, 2, 10, 20), Some(&offline)).await.unwrap();
+    for i in 0..50 {
+        mesh.say(&json!({ "put": { "counter": { "n": i } } }), Some(&offline)).await.unwrap();
+    }
+    mesh.say(&json!({ "get": { "#": "other" } }), Some(&offline)).await.unwrap();
+    mesh.say(&state_put(3, 0, 30, 5), Some(&offline)).await.unwrap(); // "b" is older
+
+    let stats = mesh.stats();
+    assert!(stats.queued[&offline.id] <= 5);
+    assert_eq!(stats.queue_dropped, 0, "collapsing made room without dropping anything");
+    assert!(stats.queue_coalesced > 0);
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    mesh.set_peer_sender(&offline.id, tx).await.unwrap();
+    let flushed = unpack(&drain_frames(&mut rx));
+    assert_eq!(flushed.len(), 3, "hi, the merged put and the get");
+    assert_eq!(flushed[0]["dam"], json!("?"));
+    assert!(flushed[2].get("get").is_some());
+
+    let (core, receiver) = mesh_with_core(34, |mesh| mesh);
+    receiver.hear(&flushed[1].to_string(), None).await.unwrap();
+    assert_eq!(core.graph.get("counter").unwrap().data.get("n"), Some(&json!(49)));
+    let doc = core.graph.get("doc").unwrap();
+    assert_eq!(doc.data.get("a"), Some(&json!(3)));
+    assert_eq!(doc.data.get("b"), Some(&json!(2)));
+}
+
+// ========== Message Predicate Tests ==========
+
+// Lets each signer write only to its own souls
+struct SoulAllowlist {
+    allowed: HashMap<String, Vec<String>>, // signer pubkey hex -> souls
+}
+
+#[async_trait::async_trait]
+impl MessageFilter for SoulAllowlist {
+    async fn check(&self, msg: &Value, ctx: &PredicateContext) -> PredicateDecision {
+        let Some(put) = msg.get("put").and_then(|v| v.as_object()) else {
+            return PredicateDecision::Accept;
+        };
+        tokio::task::yield_now().await;
+        let signer = ctx.public_keys.first().map(|pk| hex::encode(pk.to_bytes()));
+        let souls = signer.and_then(|pk| self.allowed.get(&pk));
+        match souls {
+            Some(souls) if put.keys().all(|soul| souls.contains(soul)) => PredicateDecision::Accept,
+            Some(_) => PredicateDecision::RejectWithError("Soul not allowed for this key".to_string()),
+            None => PredicateDecision::Reject,
+        }
+    }
+}
+
+// A signed frame as `seed`'s mesh would send it
+async fn signed_frame(seed: u8, msg: &Value) -> String {
+    let origin = create_mesh(seed, MeshOptions::default());
+    let (_, mut rx) = add_mock_peer(&origin, "ws://mock/origin").await;
+    drain_frames(&mut rx);
+    origin.say(msg, None).await.unwrap();
+    drain_frames(&mut rx).remove(0)
+}
+
+#[tokio::test]
+async fn test_mesh_async_predicate_allowlists_souls_by_signer() {
+    let alice_key = hex::encode(SecretKey::from_seed(&[21; 32]).public_key().to_bytes());
+    let predicate: MessagePredicate = Arc::new(SoulAllowlist {
+        allowed: HashMap::from([(alice_key, vec!["alice".to_string()])]),
+    });
+    let core = Arc::new(GunCore::new());
+    let key = SecretKey::from_seed(&[20; 32]);
+    let relay = Mesh::new(core.clone(), key.clone(), key.public_key(), Some(predicate));
+    let (peer, mut peer_rx) = add_mock_peer(&relay, "ws://peer.example/gun").await;
+    drain_frames(&mut peer_rx);
+
+    let alice_put = |soul: &str| json!({ "put": { soul: { "name": "Alice" } } });
+    relay.hear(&signed_frame(21, &alice_put("alice")).await, Some(&peer)).await.unwrap();
+    assert!(core.graph.get("alice").is_some());
+
+    // Outside the signer's allowlist: dropped, and the peer is told why
+    let frame = signed_frame(21, &alice_put("bob")).await;
+    let msg_id = serde_json::from_str::<Value>(&frame).unwrap()["#"].clone();
+    relay.hear(&frame, Some(&peer)).await.unwrap();
+    assert!(core.graph.get("bob").is_none());
+    let told = unpack(&drain_frames(&mut peer_rx));
+    let error = told.iter().find(|msg| msg["dam"] == json!("!")).expect("dam error sent");
+    assert_eq!(error["err"], json!("Soul not allowed for this key"));
+    assert_eq!(error["@"], msg_id);
+
+    // Unknown signer: dropped silently
+    relay.hear(&signed_frame(22, &alice_put("carol")).await, Some(&peer)).await.unwrap();
+    assert!(core.graph.get("carol").is_none());
+    assert!(unpack(&drain_frames(&mut peer_rx)).iter().all(|msg| msg["dam"] != json!("!")));
+    assert_eq!(relay.peer_scores()[&peer.id].rejected, 2);
+}
+
+#[tokio::test]
+async fn test_mesh_sync_closure_predicate_still_filters() {
+    let predicate: MessagePredicate = Arc::new(|msg: &Value| msg.get("put").is_none());
+    let core = Arc::new(GunCore::new());
+    let key = SecretKey::from_seed(&[23; 32]);
+    let relay = Mesh::new(core.clone(), key.clone(), key.public_key(), Some(predicate));
+    let (peer, _) = add_mock_peer(&relay, "ws://peer.example/gun").await;
+
+    relay.hear(&signed_frame(24, &put_message(1)).await, Some(&peer)).await.unwrap();
+    assert!(core.graph.get("counter").is_none());
+    assert_eq!(relay.peer_scores()[&peer.id].rejected, 1);
+}
+
+// ========== Peer Error Tests ==========
+
+// A local mesh linked to a relay that only lets it write to "alice"
+async fn allowlisted_relay(seed: u8) -> (Arc<Mesh>, Arc<GunCore>) {
+    let local_key = hex::encode(SecretKey::from_seed(&[seed; 32]).public_key().to_bytes());
+    let predicate: MessagePredicate = Arc::new(SoulAllowlist {
+        allowed: HashMap::from([(local_key, vec!["alice".to_string()])]),
+    });
+    let core = Arc::new(GunCore::new());
+    let key = SecretKey::from_seed(&[seed + 1; 32]);
+    let relay = Arc::new(Mesh::new(core, key.clone(), key.public_key(), Some(predicate)));
+    let (local_core, local) = mesh_with_core(seed, |mesh| mesh);
+    let local = Arc::new(local);
+    link(&local, &relay).await;
+    (local, local_core)
+}
+
+fn node_put(soul: &str) -> Value {
+    json!({ soul: { "_": { "#": soul, ">": { "name": 1 } }, "name": "Alice" } })
+}
+
+#[tokio::test]
+async fn test_mesh_get_answer_with_older_states_keeps_newer_data() {
+    let (core, mesh) = mesh_with_core(28, |mesh| mesh.with_require_signatures(false));
+    let (lagging, _rx) = add_mock_peer(&mesh, "ws://lagging.example/gun").await;
+    let doc = |id: &str, states: Value, data: Value| {
+        let mut node = data;
+        node["_"] = json!({ "#": "doc", ">": states });
+        json!({ "#": id, "put": { "doc": node } }).to_string()
+    };
+
+    // Written here at state 200
+    mesh.hear(&doc("local", json!({ "title": 200 }), json!({ "title": "new" })), Some(&lagging)).await.unwrap();
+
+    // A lagging peer answers a get with what it had at state 100, and a key we lack
+    let answer = doc("answer", json!({ "title": 100, "body": 100 }), json!({ "title": "old", "body": "text" }));
+    let mut answer: Value = serde_json::from_str(&answer).unwrap();
+    answer["@"] = json!("local-get");
+    mesh.hear(&answer.to_string(), Some(&lagging)).await.unwrap();
+    let node = core.graph.get("doc").unwrap();
+    assert_eq!(node.data.get("title"), Some(&json!("new")));
+    assert_eq!(node.meta[">"]["title"], json!(200.0));
+    assert_eq!(node.data.get("body"), Some(&json!("text")));
+
+    // Same state: the lexically higher value wins, whichever arrives first
+    mesh.hear(&doc("tie-a", json!({ "title": 200 }), json!({ "title": "a" })), Some(&lagging)).await.unwrap();
+    assert_eq!(core.graph.get("doc").unwrap().data.get("title"), Some(&json!("new")));
+    mesh.hear(&doc("tie-z", json!({ "title": 200 }), json!({ "title": "z" })), Some(&lagging)).await.unwrap();
+    assert_eq!(core.graph.get("doc").unwrap().data.get("title"), Some(&json!("z")));
+}
+
+#[tokio::test]
+async fn test_mesh_put_with_ack_fails_fast_on_peer_error() {
+    let (local, core) = allowlisted_relay(25).await;
+    let errors = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let errors_seen = errors.clone();
+    core.events.on(
+        PEER_ERROR_EVENT,
+        Box::new(move |event: &gun::events::Event| errors_seen.lock().unwrap().push(event.data.clone())),
+    );
+
+    let ack = local.put_with_ack(&node_put("alice")).await.unwrap();
+    assert_eq!(ack["ok"], json!({ "": 1 }));
+    assert!(errors.lock().unwrap().is_empty());
+
+    let started = std::time::Instant::now();
+    let err = local.put_with_ack(&node_put("bob")).await.unwrap_err();
+    assert!(started.elapsed() < Duration::from_secs(1), "failed without waiting for lack");
+    assert!(err.to_string().contains("Soul not allowed for this key"), "{}", err);
+
+    let errors = errors.lock().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0]["err"], json!("Soul not allowed for this key"));
+    assert!(errors[0]["peer"].is_string());
+    assert!(errors[0]["@"].is_string());
+}
+
+#[tokio::test]
+async fn test_mesh_peer_error_is_not_forwarded() {
+    let (mesh, mut rx) = {
+        let mesh = create_mesh(27, MeshOptions::default());
+        let (_, rx) = add_mock_peer(&mesh, "ws://other.example/gun").await;
+        (mesh, rx)
+    };
+    let (relay, _) = add_mock_peer(&mesh, "ws://relay.example/gun").await;
+    drain_frames(&mut rx);
+
+    let error = signed_frame(28, &json!({ "dam": "!", "err": "Message too large" })).await;
+    mesh.hear(&error, Some(&relay)).await.unwrap();
+    assert!(drain_frames(&mut rx).is_empty());
+}
+
+// ========== Signature Aggregation Tests ==========
+
+// Pass a frame through a relay made from `seed`, returning what it forwards
+async fn relay_hop(seed: u8, frame: &str, opt: MeshOptions) -> String {
+    let relay = create_mesh(seed, opt);
+    let (upstream, _) = add_mock_peer(&relay, "ws://mock/upstream").await;
+    let (_, mut downstream_rx) = add_mock_peer(&relay, "ws://mock/downstream").await;
+    drain_frames(&mut downstream_rx);
+    relay.hear(frame, Some(&upstream)).await.unwrap();
+    drain_frames(&mut downstream_rx).remove(0)
+}
+
+// Whether a fresh mesh accepts the frame, and its score for the sender
+async fn accepts(frame: &str) -> (bool, u64) {
+    let (core, mesh) = mesh_with_core(40, |mesh| mesh);
+    let (peer, _) = add_mock_peer(&mesh, "ws://mock/sender").await;
+    mesh.hear(frame, Some(&peer)).await.unwrap();
+    let invalid_sig = mesh.peer_scores().get(&peer.id).map_or(0, |score| score.invalid_sig);
+    (core.graph.get("counter").is_some(), invalid_sig)
+}
+
+// Five signers: the origin and four relays
+async fn five_signer_frame() -> Value {
+    let mut frame = signed_frame(30, &put_message(7)).await;
+    for seed in 31..35 {
+        frame = relay_hop(seed, &frame, MeshOptions::default()).await;
+    }
+    serde_json::from_str(&frame).unwrap()
+}
+
+#[tokio::test]
+async fn test_mesh_aggregates_signatures_across_hops() {
+    let msg = five_signer_frame().await;
+    let sigs = &msg["sigs"];
+    assert!(sigs["sig"].is_string(), "one aggregate signature");
+    let pubkeys: Vec<String> = serde_json::from_value(sigs["pubkeys"].clone()).unwrap();
+    let expected: Vec<String> = (30..35u8)
+        .map(|seed| hex::encode(SecretKey::from_seed(&[seed; 32]).public_key().to_bytes()))
+        .collect();
+    assert_eq!(pubkeys, expected);
+
+    assert_eq!(accepts(&msg.to_string()).await, (true, 0));
+}
+
+#[tokio::test]
+async fn test_mesh_rejects_aggregate_missing_a_signer() {
+    let mut msg = five_signer_frame().await;
+    msg["sigs"]["pubkeys"].as_array_mut().unwrap().remove(2);
+    assert_eq!(accepts(&msg.to_string()).await, (false, 1));
+
+    // Claiming a signer who didn't sign fails the same way
+    let mut msg = five_signer_frame().await;
+    let stranger = hex::encode(SecretKey::from_seed(&[39; 32]).public_key().to_bytes());
+    msg["sigs"]["pubkeys"].as_array_mut().unwrap().push(json!(stranger));
+    assert_eq!(accepts(&msg.to_string()).await, (false, 1));
+}
+
+#[tokio::test]
+async fn test_mesh_rejects_altered_aggregate_message() {
+    use sha2::{Digest, Sha256};
+
+    // New content with a matching "#", so only the signature gives it away
+    let mut msg = five_signer_frame().await;
+    msg["put"] = put_message(8)["put"].clone();
+    let mut content = msg.clone();
+    content.as_object_mut().unwrap().remove("sigs");
+    content.as_object_mut().unwrap().remove("#");
+    content.as_object_mut().unwrap().remove("ttl");
+    msg["#"] = json!(hex::encode(Sha256::digest(serde_json::to_vec(&content).unwrap())));
+    assert_eq!(accepts(&msg.to_string()).await, (false, 1));
+}
+
+#[tokio::test]
+async fn test_mesh_legacy_sigs_keep_the_array_format() {
+    let legacy = MeshOptions { legacy_sigs: true, ..Default::default() };
+    let origin = create_mesh(41, legacy.clone());
+    let (_, mut rx) = add_mock_peer(&origin, "ws://mock/origin").await;
+    drain_frames(&mut rx);
+    origin.say(&put_message(1), None).await.unwrap();
+    let frame = drain_frames(&mut rx).remove(0);
+
+    // A legacy relay appends to the array
+    let hop: Value = serde_json::from_str(&relay_hop(42, &frame, legacy).await).unwrap();
+    assert_eq!(hop["sigs"].as_array().map(Vec::len), Some(2));
+    assert_eq!(accepts(&hop.to_string()).await, (true, 0));
+
+    // An upgraded one reads it and passes it on aggregated
+    let hop: Value = serde_json::from_str(&relay_hop(43, &hop.to_string(), MeshOptions::default()).await).unwrap();
+    assert_eq!(hop["sigs"]["pubkeys"].as_array().map(Vec::len), Some(3));
+    assert_eq!(accepts(&hop.to_string()).await, (true, 0));
+}
+
+// ========== Chunking Tests ==========
+
+#[tokio::test]
+async fn test_mesh_chunks_large_put_between_instances() {
+    let opt = MeshOptions { chunk_size: 64 * 1024, ..Default::default() };
+    let (_, a) = mesh_with_core(44, |mesh| mesh.with_options(opt.clone()));
+    let (core_b, b) = mesh_with_core(45, |mesh| mesh.with_options(opt));
+    let (a, b) = (Arc::new(a), Arc::new(b));
+    link(&a, &b).await;
+
+    let blob = "x".repeat(5 * 1024 * 1024);
+    let put = json!({ "put": { "big": { "_": { "#": "big", ">": { "blob": 1 } }, "blob": blob } } });
+    a.say(&put, None).await.unwrap();
+
+    let mut arrived = None;
+    for _ in 0..100 {
+        arrived = core_b.graph.get("big");
+        if arrived.is_some() {
+            break;
+        }
+        sleep(Duration::from_millis(50)).await;
+    }
+    let node = arrived.expect("5MB put arrived");
+    assert_eq!(node.data.get("blob"), Some(&json!(blob)));
+    assert_eq!(a.stats().chunked_sent, 1);
+    assert_eq!(b.stats().chunked_received, 1);
+}
+
+#[tokio::test]
+async fn test_mesh_missing_chunk_times_out() {
+    let sender = create_mesh(46, MeshOptions { chunk_size: 1024, ..Default::default() });
+    let (_, mut rx) = add_mock_peer(&sender, "ws://mock/receiver").await;
+    drain_frames(&mut rx);
+    sender.say(&json!({ "put": { "big": { "blob": "y".repeat(10_000) } } }), None).await.unwrap();
+    let frames = drain_frames(&mut rx);
+    assert!(frames.len() > 2);
+    assert!(frames.iter().all(|frame| frame.len() < 1024 + 100), "each chunk fits");
+
+    let (core, receiver) = mesh_with_core(47, |mesh| {
+        mesh.with_options(MeshOptions { chunk_timeout: 100, ..Default::default() })
+    });
+    let (peer, _) = add_mock_peer(&receiver, "ws://mock/sender").await;
+    for frame in &frames[1..] {
+        receiver.hear(frame, Some(&peer)).await.unwrap();
+    }
+    sleep(Duration::from_millis(250)).await;
+
+    assert!(core.graph.get("big").is_none());
+    let stats = receiver.stats();
+    assert_eq!(stats.chunked_expired, 1);
+    assert_eq!(stats.chunked_received, 0);
+    assert!(!receiver.peer_scores().contains_key(&peer.id), "not held against the sender");
+
+    // The missing chunk showing up late doesn't revive the message
+    receiver.hear(&frames[0], Some(&peer)).await.unwrap();
+    assert!(core.graph.get("big").is_none());
+}
+
+// ========== Priority Lane Tests ==========
+
+#[tokio::test]
+async fn test_mesh_get_overtakes_queued_puts() {
+    let mesh = create_mesh(48, MeshOptions::default());
+    let (sender, mut outbox) = Outbox::new();
+    let mut peer = Peer::new("ws://mock/slow".to_string());
+    peer.set_sender(sender);
+    mesh.hi(peer.clone()).await.unwrap();
+    assert_eq!(outbox.recv().await.map(|frame| frame.contains(r#""dam":"?""#)), Some(true));
+
+    // The writer is stuck while 1000 puts and then a get pile up
+    for i in 0..1000 {
+        mesh.say(&put_message(i), Some(&peer)).await.unwrap();
+    }
+    mesh.say(&json!({ "get": { "#": "alice" } }), Some(&peer)).await.unwrap();
+    assert_eq!(mesh.stats().lanes[&peer.id], LaneStats { priority: 1, bulk: 1000 });
+
+    let first: Value = serde_json::from_str(&outbox.recv().await.unwrap()).unwrap();
+    assert_eq!(first["get"], json!({ "#": "alice" }));
+    let second: Value = serde_json::from_str(&outbox.recv().await.unwrap()).unwrap();
+    assert_eq!(second["put"], put_message(0)["put"]);
+    assert_eq!(mesh.stats().lanes[&peer.id], LaneStats { priority: 0, bulk: 999 });
+
+    // Puts keep their order
+    for i in 1..1000 {
+        let put: Value = serde_json::from_str(&outbox.recv().await.unwrap()).unwrap();
+        assert_eq!(put["put"], put_message(i)["put"]);
+    }
+    assert_eq!(mesh.stats().lanes[&peer.id], LaneStats::default());
+
+    // Dropping the peer closes both lanes
+    mesh.bye(&peer.id).await.unwrap();
+    drop(peer);
+    assert_eq!(outbox.recv().await, None);
+}
+
+// ========== Key Access Tests ==========
+
+fn key_bytes(seed: u8) -> PublicKeyBytes {
+    SecretKey::from_seed(&[seed; 32]).public_key().to_bytes()
+}
+
+#[tokio::test]
+async fn test_mesh_allowlist_accepts_only_listed_origins() {
+    let (core, relay) = mesh_with_core(50, |mesh| mesh.with_peer_allowlist(Some(HashSet::from([key_bytes(51)]))));
+    let (upstream, _) = add_mock_peer(&relay, "ws://mock/upstream").await;
+    let (_, mut downstream_rx) = add_mock_peer(&relay, "ws://mock/downstream").await;
+    drain_frames(&mut downstream_rx);
+
+    let put = |soul: &str| json!({ "put": { soul: { "name": soul } } });
+    relay.hear(&signed_frame(51, &put("alice")).await, Some(&upstream)).await.unwrap();
+    assert!(core.graph.get("alice").is_some());
+    assert_eq!(drain_frames(&mut downstream_rx).len(), 1, "allowed origin is forwarded");
+
+    relay.hear(&signed_frame(52, &put("mallory")).await, Some(&upstream)).await.unwrap();
+    assert!(core.graph.get("mallory").is_none());
+    assert!(drain_frames(&mut downstream_rx).is_empty(), "rejected origin is not forwarded");
+    assert_eq!(relay.stats().origin_rejected, 1);
+    // Upstream may only be relaying it, so it isn't penalised
+    assert_eq!(relay.peer_scores().get(&upstream.id).map_or(0, |score| score.score()), 0);
+
+    // An admin lets the key in without a restart
+    relay.allow_key(key_bytes(52));
+    relay.hear(&signed_frame(52, &put("bob")).await, Some(&upstream)).await.unwrap();
+    assert!(core.graph.get("bob").is_some());
+}
+
+#[tokio::test]
+async fn test_mesh_denied_origin_is_dropped_even_through_a_relay() {
+    let (core, mesh) = mesh_with_core(53, |mesh| mesh);
+    let (peer, _) = add_mock_peer(&mesh, "ws://mock/relay").await;
+    mesh.deny_key(key_bytes(54));
+
+    // The relay's signature is appended; the origin is still the first signer
+    let frame = relay_hop(55, &signed_frame(54, &put_message(1)).await, MeshOptions::default()).await;
+    mesh.hear(&frame, Some(&peer)).await.unwrap();
+    assert!(core.graph.get("counter").is_none());
+    assert_eq!(mesh.stats().origin_rejected, 1);
+
+    mesh.allow_key(key_bytes(54));
+    let frame = relay_hop(55, &signed_frame(54, &put_message(2)).await, MeshOptions::default()).await;
+    mesh.hear(&frame, Some(&peer)).await.unwrap();
+    assert!(core.graph.get("counter").is_some());
+}
+
+#[tokio::test]
+async fn test_mesh_reordered_signers_dont_change_the_origin() {
+    // 52 writes, allowlisted 51 relays it, and someone puts 51's key first
+    let frame = relay_hop(51, &signed_frame(52, &put_message(1)).await, MeshOptions::default()).await;
+    let mut reordered: Value = serde_json::from_str(&frame).unwrap();
+    let pubkeys = reordered["sigs"]["pubkeys"].as_array_mut().unwrap();
+    assert_eq!(pubkeys.len(), 2);
+    pubkeys.reverse();
+    let (core, relay) = mesh_with_core(50, |mesh| mesh.with_peer_allowlist(Some(HashSet::from([key_bytes(51)]))));
+    let (upstream, _) = add_mock_peer(&relay, "ws://mock/upstream").await;
+    relay.hear(&reordered.to_string(), Some(&upstream)).await.unwrap();
+    assert!(core.graph.get("counter").is_none());
+    assert_eq!(relay.stats().origin_rejected, 1);
+
+    // Naming the relay as origin breaks the hash instead
+    let mut renamed: Value = serde_json::from_str(&frame).unwrap();
+    renamed["from"] = json!(hex::encode(key_bytes(51)));
+    relay.hear(&renamed.to_string(), Some(&upstream)).await.unwrap();
+    assert!(core.graph.get("counter").is_none());
+
+    // And a denied relay's signature is refused whoever the origin is
+    let (core, mesh) = mesh_with_core(53, |mesh| mesh);
+    let (peer, _) = add_mock_peer(&mesh, "ws://mock/relay").await;
+    mesh.deny_key(key_bytes(55));
+    let frame = relay_hop(55, &signed_frame(54, &put_message(1)).await, MeshOptions::default()).await;
+    mesh.hear(&frame, Some(&peer)).await.unwrap();
+    assert!(core.graph.get("counter").is_none());
+    assert_eq!(mesh.stats().origin_rejected, 1);
+}
+
+// ========== Hop Limit Tests ==========
+
+// Meshes linked in a line, each with its core; the first uses `opt`
+async fn line(seeds: [u8; 4], opt: MeshOptions) -> Vec<(Arc<GunCore>, Arc<Mesh>)> {
+    let mut nodes = Vec::new();
+    for (i, seed) in seeds.into_iter().enumerate() {
+        let opt = if i == 0 { opt.clone() } else { MeshOptions::default() };
+        let (core, mesh) = mesh_with_core(seed, |mesh| mesh.with_options(opt));
+        nodes.push((core, Arc::new(mesh)));
+    }
+    for pair in nodes.windows(2) {
+        link(&pair[0].1, &pair[1].1).await;
+    }
+    nodes
+}
+
+#[tokio::test]
+async fn test_mesh_ttl_stops_message_after_configured_hops() {
+    for (ttl, reached) in [(0, 2), (1, 3), (2, 4)] {
+        let nodes = line([60, 61, 62, 63], MeshOptions { ttl, ..Default::default() }).await;
+        nodes[0].1.say(&put_message(ttl as usize), None).await.unwrap();
+        sleep(Duration::from_millis(300)).await;
+
+        let received: Vec<bool> = nodes.iter().skip(1).map(|(core, _)| core.graph.get("counter").is_some()).collect();
+        let expected: Vec<bool> = (1..4).map(|node| node < reached).collect();
+        assert_eq!(received, expected, "ttl {}", ttl);
+        assert_eq!(nodes[reached - 1].1.stats().ttl_expired, 1, "ttl {}", ttl);
+    }
+}
+
+#[tokio::test]
+async fn test_mesh_ttl_defaults_when_missing_and_is_capped() {
+    let frame: Value = serde_json::from_str(&signed_frame(64, &put_message(1)).await).unwrap();
+    let frame_with_ttl = |ttl: Option<u64>| {
+        let mut msg = frame.clone();
+        match ttl {
+            Some(ttl) => msg["ttl"] = json!(ttl),
+            None => {
+                msg.as_object_mut().unwrap().remove("ttl");
+            }
+        }
+        msg.to_string()
+    };
+    let opt = MeshOptions { ttl: 3, ..Default::default() };
+
+    // An older peer's message without a ttl still verifies and gets ours
+    let forwarded: Value = serde_json::from_str(&relay_hop(65, &frame_with_ttl(None), opt.clone()).await).unwrap();
+    assert_eq!(forwarded["ttl"], json!(2));
+
+    let forwarded: Value = serde_json::from_str(&relay_hop(65, &frame_with_ttl(Some(1000)), opt).await).unwrap();
+    assert_eq!(forwarded["ttl"], json!(2), "lowered to our ttl");
+}
+
+// ========== Idle Peer Tests ==========
+
+#[tokio::test]
+async fn test_mesh_prunes_peers_idle_beyond_lack() {
+    let (core, mesh) = mesh_with_core(70, |mesh| mesh.with_options(MeshOptions { lack: 100, ..Default::default() }));
+    let timeouts = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let timeouts_seen = timeouts.clone();
+    core.events.on(
+        PEER_TIMEOUT_EVENT,
+        Box::new(move |event: &gun::events::Event| timeouts_seen.lock().unwrap().push(event.data.clone())),
+    );
+    let (chatty, _) = add_mock_peer(&mesh, "ws://mock/chatty").await;
+    let (silent, mut silent_rx) = add_mock_peer(&mesh, "ws://mock/silent").await;
+    drain_frames(&mut silent_rx);
+
+    sleep(Duration::from_millis(70)).await;
+    mesh.hear(&signed_frame(71, &put_message(1)).await, Some(&chatty)).await.unwrap();
+    sleep(Duration::from_millis(70)).await;
+    assert_eq!(mesh.prune_idle().await.unwrap(), vec![silent.id.clone()]);
+
+    assert_eq!(*mesh.near.read().await, 1);
+    assert!(mesh.get_peer(&chatty.id).await.is_some());
+    let timeouts = timeouts.lock().unwrap().clone();
+    assert_eq!(timeouts.len(), 1);
+    assert_eq!(timeouts[0]["peer"], json!(silent.id));
+    assert!(timeouts[0]["idle_ms"].as_u64().unwrap() > 100);
+    // Its connection is closed
+    drop(silent);
+    drain_frames(&mut silent_rx);
+    assert_eq!(silent_rx.recv().await, None);
+}
+
+#[tokio::test]
+async fn test_mesh_peer_lifecycle_events_pair_up() {
+    let (core, mesh) = mesh_with_core(74, |mesh| mesh.with_options(MeshOptions { lack: 100, ..Default::default() }));
+    let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let events_seen = events.clone();
+    core.events.on(
+        PEER_LIFECYCLE_EVENT,
+        Box::new(move |event: &gun::events::Event| {
+            let event = PeerLifecycleEvent::from_event_data(&event.data).unwrap();
+            events_seen.lock().unwrap().push((event.url, event.transition));
+        }),
+    );
+    let (silent, _silent_rx) = add_mock_peer(&mesh, "ws://mock/silent").await;
+    let (dropped, _dropped_rx) = add_mock_peer(&mesh, "ws://mock/dropped").await;
+    let (mute, _mute_rx) = add_mock_peer(&mesh, "ws://mock/mute").await;
+    for (peer, pid) in [(&silent, "pid-silent"), (&dropped, "pid-dropped")] {
+        let hi = signed_frame(75, &json!({ "dam": "?", "pid": pid })).await;
+        mesh.hear(&hi, Some(peer)).await.unwrap();
+        // Saying `?` again doesn't make it any more online
+        mesh.hear(&signed_frame(75, &json!({ "dam": "?", "pid": pid, "@": "x" })).await, Some(peer)).await.unwrap();
+    }
+
+    mesh.disconnect(&dropped).await;
+    let (tx, _rx) = mpsc::unbounded_channel::<String>();
+    mesh.reconnect(&dropped.id, tx, 2).await.unwrap();
+    sleep(Duration::from_millis(150)).await;
+    mesh.touch(&dropped.id).await;
+    mesh.prune_idle().await.unwrap();
+    // Never said `?`: never online, so never offline either
+    assert!(mesh.get_peer(&mute.id).await.is_none());
+    mesh.bye(&dropped.id).await.unwrap();
+    mesh.bye(&dropped.id).await.unwrap();
+
+    let events = events.lock().unwrap().clone();
+    let url = |name: &str| format!("ws://mock/{}", name);
+    assert_eq!(
+        events,
+        vec![
+            (url("silent"), PeerTransition::Connected),
+            (url("dropped"), PeerTransition::Connected),
+            (url("dropped"), PeerTransition::Disconnected),
+            (url("dropped"), PeerTransition::Reconnected { attempts: 2 }),
+            (url("silent"), PeerTransition::TimedOut),
+            (url("dropped"), PeerTransition::Disconnected),
+        ]
+    );
+    assert_eq!(
+        PeerLifecycleEvent::from_event_data(&json!({ "peer": "peer_1", "url": "ws://a", "pid": null, "state": "timeout" })),
+        Some(PeerLifecycleEvent {
+            peer: "peer_1".to_string(),
+            url: "ws://a".to_string(),
+            pid: None,
+            transition: PeerTransition::TimedOut,
+        })
+    );
+    assert_eq!(PeerLifecycleEvent::from_event_data(&json!({ "peer": "peer_1", "url": "ws://a", "state": "gone" })), None);
+}
+
+#[tokio::test]
+async fn test_mesh_pruning_task_drops_silent_peer() {
+    let mesh = Arc::new(create_mesh(72, MeshOptions { lack: 50, ..Default::default() }));
+    let pruning = Mesh::start_pruning(&mesh).unwrap();
+    let (peer, _) = add_mock_peer(&mesh, "ws://mock/vanished").await;
+    // A disconnected peer is left to its reconnect attempts
+    let (offline, _) = add_mock_peer(&mesh, "ws://mock/offline").await;
+    mesh.disconnect(&offline).await;
+
+    sleep(Duration::from_millis(200)).await;
+    assert!(mesh.get_peer(&peer.id).await.is_none());
+    assert!(mesh.get_peer(&offline.id).await.is_some());
+    assert_eq!(*mesh.near.read().await, 1);
+
+    mesh.shutdown().await;
+    sleep(Duration::from_millis(100)).await;
+    assert!(pruning.is_finished());
+}
+
+#[tokio::test]
+async fn test_mesh_lack_zero_never_prunes() {
+    let mesh = Arc::new(create_mesh(73, MeshOptions { lack: 0, ..Default::default() }));
+    assert!(Mesh::start_pruning(&mesh).is_none());
+    let (peer, _) = add_mock_peer(&mesh, "ws://mock/quiet").await;
+    sleep(Duration::from_millis(20)).await;
+    assert!(mesh.prune_idle().await.unwrap().is_empty());
+    assert!(mesh.get_peer(&peer.id).await.is_some());
+}
+
+// ========== Get Coalescing Tests ==========
+
+#[tokio::test]
+async fn test_mesh_coalesces_gets_in_flight() {
+    let mesh = create_mesh(80, MeshOptions::default());
+    let (peer, mut rx) = add_mock_peer(&mesh, "ws://mock/relay").await;
+    drain_frames(&mut rx);
+
+    // Ten callers, well apart from each other's batching windows
+    let mut replies = Vec::new();
+    for _ in 0..10 {
+        replies.push(mesh.ask_get(json!({ "#": "alice", ".": "name" })).await.unwrap());
+        sleep(Duration::from_millis(10)).await;
+    }
+    let gets = unpack(&drain_frames(&mut rx));
+    assert_eq!(gets.len(), 1, "one get on the wire");
+    assert_eq!(mesh.stats().gets_sent, 1);
+    assert_eq!(mesh.stats().gets_coalesced, 9);
+
+    let answer = json!({ "@": gets[0]["#"], "put": node_put("alice") });
+    mesh.hear(&signed_frame(81, &answer).await, Some(&peer)).await.unwrap();
+    for reply in replies {
+        let response = tokio::time::timeout(Duration::from_secs(1), reply).await.unwrap().unwrap();
+        assert_eq!(response["put"]["alice"]["name"], json!("Alice"));
+    }
+
+    // Once answered, a new get goes out again
+    let _reply = mesh.ask_get(json!({ "#": "alice", ".": "name" })).await.unwrap();
+    assert_eq!(unpack(&drain_frames(&mut rx)).len(), 1);
+}
+
+#[tokio::test]
+async fn test_mesh_caches_unanswered_gets() {
+    let mesh = create_mesh(82, MeshOptions { lack: 100, miss_ttl: 60_000, ..Default::default() });
+    let (peer, mut rx) = add_mock_peer(&mesh, "ws://mock/relay").await;
+    drain_frames(&mut rx);
+
+    let reply = mesh.ask_get(json!({ "#": "ghost" })).await.unwrap();
+    assert!(tokio::time::timeout(Duration::from_secs(1), reply).await.unwrap().is_err(), "closed after lack");
+    assert_eq!(unpack(&drain_frames(&mut rx)).len(), 1);
+
+    // Known missing: answered at once, without asking peers
+    let started = std::time::Instant::now();
+    assert!(mesh.ask_get(json!({ "#": "ghost" })).await.unwrap().await.is_err());
+    assert!(started.elapsed() < Duration::from_millis(50));
+    assert!(drain_frames(&mut rx).is_empty());
+    assert_eq!(mesh.stats().gets_known_missing, 1);
+
+    // Data arriving for the soul lifts it
+    mesh.hear(&signed_frame(83, &json!({ "put": node_put("ghost") })).await, Some(&peer)).await.unwrap();
+    drain_frames(&mut rx); // the put's ack
+    let _reply = mesh.ask_get(json!({ "#": "ghost" })).await.unwrap();
+    assert_eq!(unpack(&drain_frames(&mut rx)).len(), 1);
+}
+
+// ========== Subscription Tests ==========
+
+#[tokio::test]
+async fn test_mesh_asks_new_and_reconnected_peers_for_subscribed_souls() {
+    let (core, mesh) = mesh_with_core(90, |mesh| mesh);
+    let node = |soul: &str| gun::chain::Chain::with_soul(core.clone(), soul.to_string(), None);
+    let chains: Vec<_> = ["alice", "bob", "carol"].into_iter().map(|soul| node(soul).on(|_, _| {})).collect();
+    node("dave").on(|_, _| {}).off();
+    assert_eq!(core.subscriptions(), vec!["alice", "bob", "carol"]);
+
+    let subscription_gets = |rx: &mut mpsc::UnboundedReceiver<String>| -> Vec<Value> {
+        unpack(&drain_frames(rx)).into_iter().filter_map(|msg| msg.get("get").cloned()).collect()
+    };
+    let (peer, mut rx) = add_mock_peer(&mesh, "ws://mock/relay").await;
+    assert_eq!(subscription_gets(&mut rx), vec![json!({ "#": ["alice", "bob", "carol"] })]);
+
+    // The connection drops and comes back
+    mesh.disconnect(&peer).await;
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    mesh.reconnect(&peer.id, tx, 1).await.unwrap();
+    assert_eq!(subscription_gets(&mut rx), vec![json!({ "#": ["alice", "bob", "carol"] })]);
+
+    // Nothing to ask for once every listener is gone
+    for chain in chains {
+        chain.off();
+    }
+    assert!(core.subscriptions().is_empty());
+    let (_, mut rx) = add_mock_peer(&mesh, "ws://mock/other").await;
+    assert!(subscription_gets(&mut rx).is_empty());
+}
+
+#[tokio::test]
+async fn test_mesh_reconnect_sends_hi_then_queue_and_retries_pending_gets() {
+    let mesh = create_mesh(96, MeshOptions::default());
+    let (peer, mut rx) = add_mock_peer(&mesh, "ws://mock/relay").await;
+    drain_frames(&mut rx);
+
+    // A get goes out, then the connection drops before any reply
+    let reply = mesh.ask_get(json!({ "#": "alice" })).await.unwrap();
+    let lost = unpack(&drain_frames(&mut rx));
+    assert_eq!(lost.len(), 1);
+    mesh.disconnect(&peer).await;
+    mesh.say(&put_message(1), Some(&peer)).await.unwrap();
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    mesh.reconnect(&peer.id, tx, 1).await.unwrap();
+    let frames = drain_frames(&mut rx);
+    let sent = unpack(&frames);
+    assert_eq!(sent.len(), 3);
+    assert_eq!(sent[0]["dam"], json!("?"));
+    assert_eq!(sent[1]["put"], put_message(1)["put"]);
+    assert_eq!(sent[2], lost[0], "the same get, same ID");
+
+    // Answered on the new connection
+    let (relay_core, relay) = mesh_with_core(97, |mesh| mesh);
+    store_alice(&relay_core);
+    let (client, mut to_client) = add_mock_peer(&relay, "ws://mock/client").await;
+    drain_frames(&mut to_client);
+    relay.hear(&frames[2], Some(&client)).await.unwrap();
+    for frame in drain_frames(&mut to_client) {
+        mesh.hear(&frame, Some(&peer)).await.unwrap();
+    }
+    let response = tokio::time::timeout(Duration::from_secs(1), reply).await.unwrap().unwrap();
+    assert_eq!(response["put"]["alice"]["name"], json!("Alice"));
+}
+
+// ========== Room Tests ==========
+
+#[tokio::test]
+async fn test_mesh_say_to_tag_reaches_only_peers_in_the_room() {
+    let (_, relay) = mesh_with_core(92, |mesh| mesh);
+    let relay = Arc::new(relay);
+    // Joined before connecting: announced to each peer on hi
+    relay.join("presence").await.unwrap();
+    let mut members = Vec::new();
+    for seed in 93..96 {
+        let (core, mesh) = mesh_with_core(seed, |mesh| mesh);
+        let mesh = Arc::new(mesh);
+        link(&relay, &mesh).await;
+        members.push((core, mesh));
+    }
+    let [(a_core, a), (b_core, b), (c_core, _)] = &members[..] else { unreachable!() };
+    a.join("presence").await.unwrap();
+    b.join("presence").await.unwrap();
+    for _ in 0..50 {
+        if relay.peers_tagged("presence").await.len() == 2 {
+            break;
+        }
+        sleep(Duration::from_millis(10)).await;
+    }
+
+    let online = json!({ "put": { "presence": { "alice": "online" } } });
+    assert_eq!(relay.say_to_tag("presence", &online).await.unwrap(), 2);
+    sleep(Duration::from_millis(100)).await;
+    assert!(a_core.graph.get("presence").is_some());
+    assert!(b_core.graph.get("presence").is_some());
+    assert!(c_core.graph.get("presence").is_none());
+
+    // Relayed room messages stay in the room
+    assert_eq!(a.say_to_tag("presence", &json!({ "put": { "typing": { "alice": true } } })).await.unwrap(), 1);
+    sleep(Duration::from_millis(100)).await;
+    assert!(b_core.graph.get("typing").is_some());
+    assert!(c_core.graph.get("typing").is_none());
+
+    // Untagged messages still go everywhere
+    relay.say(&json!({ "put": { "news": { "headline": "hi" } } }), None).await.unwrap();
+    sleep(Duration::from_millis(100)).await;
+    assert!(c_core.graph.get("news").is_some());
+
+    b.leave("presence").await.unwrap();
+    sleep(Duration::from_millis(100)).await;
+    assert_eq!(relay.say_to_tag("presence", &online).await.unwrap(), 1);
+}
+
+// ========== Malformed Input Tests ==========
+
+#[tokio::test]
+async fn test_mesh_survives_malformed_input() {
+    let (core, mesh) = mesh_with_core(91, |mesh| mesh.with_require_signatures(false));
+    let (peer, mut rx) = add_mock_peer(&mesh, "ws://mock/fuzzer").await;
+    drain_frames(&mut rx);
+
+    let deep_array = format!("{}{}", "[".repeat(10_000), "]".repeat(10_000));
+    let deep_object = format!(r##"{{"#":"deep","put":{}1{}}}"##, r#"{"a":"#.repeat(200), "}".repeat(200));
+    let huge_string = format!("\"{}\"", "x".repeat(1_000_000));
+    let not_objects = ["42", "\"hello\"", "null", "true", "[1, \"x\", null, [[]]]", "{}"];
+    let not_json = ["{", "}{", "[1,", r#""\x41""#, r##"{"#":"\ud800"}"##, "\u{0}", "NaN"];
+    let odd_shapes = [
+        r##"{"#":1}"##,
+        r##"{"#":"a","put":"x"}"##,
+        r##"{"#":"b","put":{"s":5}}"##,
+        r##"{"#":"c","put":{"s":{"_":5,"k":1}}}"##,
+        r##"{"#":"d","put":{"s":{"_":{"#":7,">":"x"},"k":1}}}"##,
+        r##"{"#":"e","get":5}"##,
+        r##"{"#":"f","get":{"#":5,".":[]}}"##,
+        r##"{"#":"g","dam":5}"##,
+        r##"{"#":"h","dam":"chunk","id":1,"seq":"x"}"##,
+        r##"{"#":"i","dam":"chunk","id":"i","seq":0,"of":1,"data":"{not json"}"##,
+        r##"{"#":"j","@":5,"put":null}"##,
+        r##"{"#":"k","ttl":"x","put":{}}"##,
+        r##"{"#":"l","dam":"pex","peers":"x"}"##,
+        r##"{"#":"m","sigs":[1,2],"put":{}}"##,
+    ];
+    let inputs = not_objects
+        .iter()
+        .chain(&not_json)
+        .chain(&odd_shapes)
+        .map(|raw| raw.to_string())
+        .chain([deep_array, deep_object, huge_string]);
+    for raw in inputs {
+        let _ = mesh.hear(&raw, Some(&peer)).await;
+    }
+
+    // Dropped and reported without taking anything down
+    assert!(mesh.stats().invalid_messages >= (not_objects.len() + not_json.len()) as u64);
+    let replies = unpack(&drain_frames(&mut rx));
+    assert!(replies.iter().any(|msg| msg["dam"] == json!("!")));
+    assert!(core.graph.get("s").is_none_or(|node| node.data.get("k") == Some(&json!(1))));
+    assert!(mesh.get_peer(&peer.id).await.is_some());
+
+    // The same connection still works
+    mesh.hear(GUNJS_PUT.trim(), Some(&peer)).await.unwrap();
+    let node = core.graph.get("alice").expect("valid put still applied");
+    assert_eq!(node.data.get("name"), Some(&json!("Alice")));
+}
+
+#[tokio::test]
+async fn test_mesh_batch_continues_past_a_failing_message() {
+    let (core, mesh) = mesh_with_core(92, |mesh| mesh.with_require_signatures(false));
+    let (peer, rx) = add_mock_peer(&mesh, "ws://mock/gone").await;
+    // Telling the peer about the bad message fails on the closed connection
+    drop(rx);
+
+    let put: Value = serde_json::from_str(GUNJS_PUT.trim()).unwrap();
+    let batch = json!([42, put]).to_string();
+    mesh.hear(&batch, Some(&peer)).await.unwrap();
+
+    assert_eq!(mesh.stats().messages_failed, 1);
+    let node = core.graph.get("alice").expect("the rest of the batch still applied");
+    assert_eq!(node.data.get("name"), Some(&json!("Alice")));
+}
+
+// ========== Shutdown Tests ==========
+
+#[tokio::test]
+async fn test_mesh_bye_removes_sender() {
+    let a = Arc::new(create_mesh(9, MeshOptions::default()));
+    let b = Arc::new(create_mesh(10, MeshOptions::default()));
+    link(&a, &b).await;
+    assert_eq!(*b.near.read().await, 1);
+    assert_eq!(b.connected_peer_count().await, 1);
+
+    a.shutdown().await;
+    assert!(a.is_shut_down());
+    assert_eq!(*a.near.read().await, 0);
+
+    let removed = tokio::time::timeout(Duration::from_secs(5), async {
+        while *b.near.read().await != 0 {
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await;
+    assert!(removed.is_ok(), "b should drop a when it says bye");
+    assert_eq!(b.connected_peer_count().await, 0);
+}
+
+// ========== Alternate Transport Tests ==========
+
+// Transport handing frames straight to `to`, as from the peer with `from_pid`
+fn direct_transport(to: &Arc<Mesh>, from_pid: &str) -> PeerTransport {
+    let (to, from_pid) = (to.clone(), from_pid.to_string());
+    Arc::new(move |frame| {
+        let (to, from_pid) = (to.clone(), from_pid.clone());
+        Box::pin(async move {
+            let from = to.peer_for_pid(&from_pid).await;
+            to.hear(&frame, from.as_ref()).await
+        })
+    })
+}
+
+#[tokio::test]
+async fn test_mesh_sends_over_alternate_transport_first() {
+    let (_, relay) = mesh_with_core(98, |mesh| mesh);
+    let relay = Arc::new(relay);
+    let (a_core, a) = mesh_with_core(99, |mesh| mesh);
+    let (b_core, b) = mesh_with_core(100, |mesh| mesh);
+    let (a, b) = (Arc::new(a), Arc::new(b));
+    link(&relay, &a).await;
+    link(&relay, &b).await;
+    sleep(Duration::from_millis(100)).await;
+    assert!(a.peer_id_for_pid(&b.pid).is_none());
+
+    // A pid only reached through the relay becomes a peer of its own
+    a.add_transport(&b.pid, direct_transport(&b, &a.pid)).await;
+    b.add_transport(&a.pid, direct_transport(&a, &b.pid)).await;
+    assert_eq!(a.peer_count().await, 2);
+    assert!(a.peer_for_pid(&b.pid).await.is_some());
+
+    a.say(&json!({ "put": { "direct": { "from": "a" } } }), None).await.unwrap();
+    sleep(Duration::from_millis(100)).await;
+    assert!(b_core.graph.get("direct").is_some());
+    assert!(a.stats().transport_sent >= 1);
+    b.say(&json!({ "put": { "back": { "from": "b" } } }), None).await.unwrap();
+    sleep(Duration::from_millis(100)).await;
+    assert!(a_core.graph.get("back").is_some());
+
+    a.remove_transport(&b.pid).await;
+    assert_eq!(a.peer_count().await, 1);
+    assert!(a.peer_for_pid(&b.pid).await.is_none());
+}
+
+#[tokio::test]
+async fn test_mesh_falls_back_to_websocket_when_transport_fails() {
+    let (_, a) = mesh_with_core(101, |mesh| mesh);
+    let (b_core, b) = mesh_with_core(102, |mesh| mesh);
+    let (a, b) = (Arc::new(a), Arc::new(b));
+    link(&a, &b).await;
+    for _ in 0..50 {
+        if a.peer_id_for_pid(&b.pid).is_some() {
+            break;
+        }
+        sleep(Duration::from_millis(10)).await;
+    }
+
+    // Connected directly: no extra peer, and the connection takes over on errors
+    let failing: PeerTransport = Arc::new(|_| Box::pin(async { Err(GunError::Network("channel closed".to_string())) }));
+    a.add_transport(&b.pid, failing).await;
+    assert_eq!(a.peer_count().await, 1);
+    a.say(&json!({ "put": { "fallback": { "n": 1 } } }), None).await.unwrap();
+    sleep(Duration::from_millis(100)).await;
+    assert!(b_core.graph.get("fallback").is_some());
+    let stats = a.stats();
+    assert!(stats.transport_fallbacks >= 1);
+    assert_eq!(stats.transport_sent, 0);
+}
+
+// ========== User Space Tests ==========
+
+// A Gun.js put of `key` = `value` into `soul`, as it arrives unsigned on the wire
+fn user_space_put(id: &str, soul: &str, key: &str, value: &Value) -> String {
+    json!({
+        "#": id,
+        "put": { soul: { "_": { "#": soul, ">": { key: 1_700_000_000_000u64 } }, key: value } }
+    })
+    .to_string()
+}
+
+fn put_error(frames: &[String], id: &str) -> Option<Value> {
+    unpack(frames).into_iter().find(|msg| msg["@"] == json!(id)).map(|ack| ack["err"].clone())
+}
+
+#[tokio::test]
+async fn test_mesh_secure_user_space_merges_only_owner_signed_writes() {
+    let (core, mesh) = mesh_with_core(80, |mesh| mesh.with_require_signatures(false).with_secure_user_space(true));
+    let (gunjs, mut rx) = add_mock_peer(&mesh, "ws://gunjs.example/gun").await;
+    drain_frames(&mut rx);
+    let alice = gun::sea::pair().await.unwrap();
+    let mallory = gun::sea::pair().await.unwrap();
+    let soul = format!("~{}", alice.pub_key);
+    let state = 1_700_000_000_000.0;
+
+    // Unsigned, and signed by someone else: dropped, and the sender is told
+    mesh.hear(&user_space_put("unsigned", &soul, "name", &json!("Mallory")), Some(&gunjs)).await.unwrap();
+    let forged = gun::sea::sign_put(&soul, "name", &json!("Mallory"), state, &mallory, None).await.unwrap();
+    mesh.hear(&user_space_put("forged", &soul, "name", &forged), Some(&gunjs)).await.unwrap();
+    assert!(core.graph.get(&soul).is_none());
+    let frames = drain_frames(&mut rx);
+    for id in ["unsigned", "forged"] {
+        let err = put_error(&frames, id).expect("put acked");
+        assert!(err.as_str().unwrap().contains(&format!("{}.name", soul)), "{}: {}", id, err);
+    }
+
+    // Signed by alice: merged as Gun.js writes it
+    let signed = gun::sea::sign_put(&soul, "name", &json!("Alice"), state, &alice, None).await.unwrap();
+    mesh.hear(&user_space_put("signed", &soul, "name", &signed), Some(&gunjs)).await.unwrap();
+    assert_eq!(core.graph.get(&soul).unwrap().data.get("name"), Some(&signed));
+    assert_eq!(put_error(&drain_frames(&mut rx), "signed"), Some(Value::Null));
+
+    // Replayed at another state: the signature no longer matches
+    let replayed = json!({
+        "#": "replayed",
+        "put": { &soul: { "_": { "#": &soul, ">": { "name": 1_800_000_000_000u64 } }, "name": signed } }
+    });
+    mesh.hear(&replayed.to_string(), Some(&gunjs)).await.unwrap();
+    assert!(put_error(&drain_frames(&mut rx), "replayed").unwrap().is_string());
+
+    // Souls outside user space are not checked
+    mesh.hear(&user_space_put("public", "notes", "text", &json!("hi")), Some(&gunjs)).await.unwrap();
+    assert!(core.graph.get("notes").is_some());
+}
+
+#[tokio::test]
+async fn test_mesh_secure_user_space_forwards_only_accepted_puts() {
+    let (_, mesh) = mesh_with_core(83, |mesh| mesh.with_require_signatures(false).with_secure_user_space(true));
+    let (gunjs, _) = add_mock_peer(&mesh, "ws://gunjs.example/gun").await;
+    let (_, mut other) = add_mock_peer(&mesh, "ws://other.example/gun").await;
+    drain_frames(&mut other);
+    let alice = gun::sea::pair().await.unwrap();
+    let soul = format!("~{}", alice.pub_key);
+
+    mesh.hear(&user_space_put("unsigned", &soul, "name", &json!("Mallory")), Some(&gunjs)).await.unwrap();
+    let signed = gun::sea::sign_put(&soul, "name", &json!("Alice"), 1_700_000_000_000.0, &alice, None).await.unwrap();
+    mesh.hear(&user_space_put("signed", &soul, "name", &signed), Some(&gunjs)).await.unwrap();
+
+    let forwarded: Vec<Value> = unpack(&drain_frames(&mut other)).into_iter().filter(|msg| msg.get("put").is_some()).collect();
+    assert_eq!(forwarded.len(), 1, "{:?}", forwarded);
+    assert_eq!(forwarded[0]["put"][&soul]["name"], signed);
+}
+
+#[tokio::test]
+async fn test_mesh_secure_user_space_replayed_older_value_keeps_newer() {
+    let (core, mesh) = mesh_with_core(87, |mesh| mesh.with_require_signatures(false).with_secure_user_space(true));
+    let (gunjs, _) = add_mock_peer(&mesh, "ws://gunjs.example/gun").await;
+    let (_, mut other) = add_mock_peer(&mesh, "ws://other.example/gun").await;
+    let alice = gun::sea::pair().await.unwrap();
+    let soul = format!("~{}", alice.pub_key);
+    let put_at = |id: &str, value: &Value, state: u64| {
+        json!({ "#": id, "put": { &soul: { "_": { "#": &soul, ">": { "name": state } }, "name": value } } }).to_string()
+    };
+
+    // Both signed by alice, each at its own state
+    let old = gun::sea::sign_put(&soul, "name", &json!("Old"), 1_700_000_000_000.0, &alice, None).await.unwrap();
+    let new = gun::sea::sign_put(&soul, "name", &json!("New"), 1_800_000_000_000.0, &alice, None).await.unwrap();
+    mesh.hear(&put_at("new", &new, 1_800_000_000_000), Some(&gunjs)).await.unwrap();
+    drain_frames(&mut other);
+
+    // The old one, replayed later: valid, but not newer
+    mesh.hear(&put_at("replayed", &old, 1_700_000_000_000), Some(&gunjs)).await.unwrap();
+    assert_eq!(core.graph.get(&soul).unwrap().data.get("name"), Some(&new));
+    let forwarded = unpack(&drain_frames(&mut other)).into_iter().filter(|msg| msg.get("put").is_some()).count();
+    assert_eq!(forwarded, 0, "stale replay forwarded");
+}
+
+#[tokio::test]
+async fn test_mesh_secure_user_space_merges_certified_writes() {
+    use gun::sea::{certify, Certificants, CertifyOptions, Policy};
+
+    let (core, mesh) = mesh_with_core(81, |mesh| mesh.with_require_signatures(false).with_secure_user_space(true));
+    let (gunjs, mut rx) = add_mock_peer(&mesh, "ws://gunjs.example/gun").await;
+    drain_frames(&mut rx);
+    let alice = gun::sea::pair().await.unwrap();
+    let bob = gun::sea::pair().await.unwrap();
+    let soul = format!("~{}", alice.pub_key);
+    let state = 1_700_000_000_000.0;
+    let cert = certify(
+        Certificants::List(vec![bob.pub_key.clone()]),
+        Policy::String("inbox".to_string()),
+        &alice,
+        CertifyOptions::default(),
+    )
+    .await
+    .unwrap();
+
+    // Where the certificate lets bob write
+    let message = gun::sea::sign_put(&soul, "inbox", &json!("hi alice"), state, &bob, Some(&cert)).await.unwrap();
+    mesh.hear(&user_space_put("inbox", &soul, "inbox", &message), Some(&gunjs)).await.unwrap();
+    assert_eq!(core.graph.get(&soul).unwrap().data.get("inbox"), Some(&message));
+
+    // Anywhere else, or without the certificate
+    let name = gun::sea::sign_put(&soul, "name", &json!("Bob"), state, &bob, Some(&cert)).await.unwrap();
+    mesh.hear(&user_space_put("name", &soul, "name", &name), Some(&gunjs)).await.unwrap();
+    let uncertified = gun::sea::sign_put(&soul, "inbox", &json!("hi"), state, &bob, None).await.unwrap();
+    mesh.hear(&user_space_put("uncertified", &soul, "inbox", &uncertified), Some(&gunjs)).await.unwrap();
+    let node = core.graph.get(&soul).unwrap();
+    assert!(node.data.get("name").is_none());
+    assert_eq!(node.data.get("inbox"), Some(&message));
+    let frames = drain_frames(&mut rx);
+    assert!(put_error(&frames, "name").unwrap().is_string());
+    assert!(put_error(&frames, "uncertified").unwrap().is_string());
+
+    // Aliases only link to the account they name
+    let alias = "~@alice";
+    let link = json!({ "#": &soul });
+    mesh.hear(&user_space_put("alias", alias, &soul, &link), Some(&gunjs)).await.unwrap();
+    mesh.hear(&user_space_put("hijack", alias, "name", &json!("Mallory")), Some(&gunjs)).await.unwrap();
+    let node = core.graph.get(alias).unwrap();
+    assert_eq!(node.data.get(&soul), Some(&link));
+    assert!(node.data.get("name").is_none());
+}
+
+#[tokio::test]
+async fn test_mesh_user_space_is_open_by_default() {
+    let (core, mesh) = mesh_with_core(82, |mesh| mesh.with_require_signatures(false));
+    let (gunjs, _) = add_mock_peer(&mesh, "ws://gunjs.example/gun").await;
+
+    mesh.hear(&user_space_put("unsigned", "~alice.pub", "name", &json!("Mallory")), Some(&gunjs)).await.unwrap();
+    assert_eq!(core.graph.get("~alice.pub").unwrap().data.get("name"), Some(&json!("Mallory")));
+}
+
+// A Gun instance on `port` with nothing persisted, or a peer of it if it isn't the relay
+async fn gun_on(seed: u8, port: u16, relay: bool, secure_user_space: bool) -> Gun {
+    let key = SecretKey::from_seed(&[seed; 32]);
+    let base = if relay {
+        GunOptions::relay_server(port)
+    } else {
+        GunOptions::with_relay(&format!("ws://127.0.0.1:{}/gun", port))
+    };
+    let options = GunOptions { localStorage: false, radisk: false, secure_user_space, ..base };
+    Gun::with_options(key.clone(), key.public_key(), options).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_mesh_secure_user_space_relays_account_writes() {
+    let port = 18858;
+    let mut relay = gun_on(90, port, true, true).await;
+    sleep(Duration::from_millis(200)).await;
+    let mut alice = gun_on(91, port, false, false).await;
+    let mut other = gun_on(92, port, false, false).await;
+    assert!(alice.wait_for_connection(5000).await && other.wait_for_connection(5000).await);
+
+    // Created on one peer, signed in on another through the relay
+    let pub_key = alice.user().create("alice", "alicepass").await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+    let soul = format!("~{}", pub_key);
+    assert!(relay.root().core.graph.get(&soul).is_some_and(|node| node.data.contains_key("hash")));
+    let auth = other.user().auth("alice", "alicepass").await.unwrap();
+    assert_eq!(auth.pair.pub_key, pub_key);
+
+    // The account's other writes are signed too
+    gun::sea::change_password(alice.root(), "alice", "alicepass", "newpass").await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+    assert!(other.user().auth("alice", "newpass").await.is_ok());
+    gun::sea::delete_user(alice.root(), "alice", "newpass").await.unwrap();
+    sleep(Duration::from_millis(300)).await;
+    let relayed = relay.root().core.graph.get("~@alice").unwrap();
+    assert_eq!(relayed.data.get(&soul).and_then(|link| link.get("#")), None, "unlinked on the relay");
+    assert!(other.user().auth("alice", "newpass").await.is_err());
+
+    other.shutdown().await.unwrap();
+    alice.shutdown().await.unwrap();
+    relay.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_mesh_pow_accepts_worked_messages() {
+    let (_, a) = mesh_with_core(84, |mesh| mesh.with_pow_bits(Some(8)).unwrap());
+    let (b_core, b) = mesh_with_core(85, |mesh| mesh.with_pow_bits(Some(8)).unwrap());
+    let (a, b) = (Arc::new(a), Arc::new(b));
+    link(&a, &b).await;
+
+    a.say(&json!({ "put": node_put("worked") }), None).await.unwrap();
+    for _ in 0..50 {
+        if b_core.graph.get("worked").is_some() {
+            break;
+        }
+        sleep(Duration::from_millis(20)).await;
+    }
+    assert!(b_core.graph.get("worked").is_some());
+}
+
+#[tokio::test]
+async fn test_mesh_pow_rejects_messages_without_enough_work() {
+    let (core, relay) = mesh_with_core(86, |mesh| mesh.with_pow_bits(Some(8)).unwrap());
+    let (peer, mut rx) = add_mock_peer(&relay, "ws://spammer.example/gun").await;
+    drain_frames(&mut rx);
+
+    // No work at all: dropped, and the sender is told the difficulty
+    let frame = signed_frame(87, &json!({ "put": node_put("spam") })).await;
+    let msg_id = serde_json::from_str::<Value>(&frame).unwrap()["#"].clone();
+    relay.hear(&frame, Some(&peer)).await.unwrap();
+    assert!(core.graph.get("spam").is_none());
+    let told = unpack(&drain_frames(&mut rx));
+    let error = told.iter().find(|msg| msg["dam"] == json!("!")).expect("dam error sent");
+    assert_eq!(error["pow"], json!(8));
+    assert_eq!(error["@"], msg_id);
+    assert!(error["err"].as_str().unwrap().contains("8 bits"));
+
+    // A nonce that isn't the work: dropped too
+    let mut msg: Value = serde_json::from_str(&frame).unwrap();
+    let id = msg_id.as_str().unwrap();
+    let short = (0u64..)
+        .find(|n| {
+            use sha2::{Digest, Sha256};
+            Sha256::new().chain_update(id).chain_update(n.to_string()).finalize()[0] != 0
+        })
+        .unwrap();
+    msg["work"] = json!(short);
+    relay.hear(&msg.to_string(), Some(&peer)).await.unwrap();
+    assert!(core.graph.get("spam").is_none());
+
+    // Sent by a mesh doing the work: merged
+    let origin = create_mesh(88, MeshOptions::default()).with_pow_bits(Some(8)).unwrap();
+    let (_, mut origin_rx) = add_mock_peer(&origin, "ws://mock/origin").await;
+    drain_frames(&mut origin_rx);
+    origin.say(&json!({ "put": node_put("worked") }), None).await.unwrap();
+    let worked = drain_frames(&mut origin_rx).remove(0);
+    assert!(serde_json::from_str::<Value>(&worked).unwrap()["work"].is_u64());
+    relay.hear(&worked, Some(&peer)).await.unwrap();
+    assert!(core.graph.get("worked").is_some());
+}
+
+#[test]
+fn test_mesh_pow_bits_above_the_maximum_is_an_error() {
+    let bits = gun::dam::MAX_POW_BITS + 1;
+    let err = create_mesh(89, MeshOptions::default()).with_pow_bits(Some(bits)).err().unwrap();
+    assert!(matches!(err, GunError::Config(gun::ConfigError::PowBitsTooHigh(b)) if b == bits));
+    assert!(create_mesh(89, MeshOptions::default()).with_pow_bits(Some(gun::dam::MAX_POW_BITS)).is_ok());
+}
+
+#[tokio::test]
+async fn test_mesh_verify_cache_skips_repeated_checks_only() {
+    let (core, relay) = mesh_with_core(100, |mesh| mesh);
+    let (first, mut first_rx) = add_mock_peer(&relay, "ws://first.example/gun").await;
+    let (second, mut second_rx) = add_mock_peer(&relay, "ws://second.example/gun").await;
+    drain_frames(&mut first_rx);
+    drain_frames(&mut second_rx);
+
+    // The same message from two peers: verified once
+    let frame = signed_frame(101, &json!({ "put": node_put("cached") })).await;
+    relay.hear(&frame, Some(&first)).await.unwrap();
+    relay.hear(&frame, Some(&second)).await.unwrap();
+    assert!(core.graph.get("cached").is_some());
+    let stats = relay.stats();
+    assert_eq!(stats.signature_cache_hits, 1);
+    assert_eq!(stats.signature_failures, 0);
+
+    // Another key's signature over the same bytes, claimed by the first
+    // signer: checked, and rejected, despite the cached result
+    let mut forged: Value = serde_json::from_str(&frame).unwrap();
+    let claimed = json!({ "put": node_put("cached"), "from": forged["from"] });
+    let other: Value = serde_json::from_str(&signed_frame(102, &claimed).await).unwrap();
+    assert_eq!(forged["#"], other["#"]);
+    forged["sigs"]["sig"] = other["sigs"]["sig"].clone();
+    relay.hear(&forged.to_string(), Some(&second)).await.unwrap();
+    let stats = relay.stats();
+    assert_eq!(stats.signature_cache_hits, 1);
+    assert_eq!(stats.signature_failures, 1);
+
+    // Without the cache every copy is checked
+    let (_, uncached) = mesh_with_core(103, |mesh| mesh.with_verify_cache(0));
+    let (peer, _rx) = add_mock_peer(&uncached, "ws://first.example/gun").await;
+    uncached.hear(&frame, Some(&peer)).await.unwrap();
+    uncached.hear(&frame, Some(&peer)).await.unwrap();
+    assert_eq!(uncached.stats().signature_cache_hits, 0);
+}
+
+#[tokio::test]
+async fn test_mesh_verifies_content_addressed_puts() {
+    let (core, relay) = mesh_with_core(104, |mesh| mesh);
+    let (peer, mut rx) = add_mock_peer(&relay, "ws://peer.example/gun").await;
+    let (_other, mut other_rx) = add_mock_peer(&relay, "ws://other.example/gun").await;
+    drain_frames(&mut rx);
+    drain_frames(&mut other_rx);
+    let mismatches = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let events = mismatches.clone();
+    core.events.on(
+        CONTENT_HASH_MISMATCH_EVENT,
+        Box::new(move |event: &gun::events::Event| events.lock().unwrap().push(event.data.clone())),
+    );
+
+    let data = json!({ "name": "frozen", "value": 42 });
+    let frozen = |soul: &str, data: &Value| {
+        let mut node = data.clone();
+        node["_"] = json!({ "#": soul, ">": { "name": 1, "value": 1 } });
+        json!({ "put": { soul: node } })
+    };
+
+    // Data that hashes to its soul, base64 or hex: merged
+    let soul = format!("#{}", gun::valid::content_hash(&data));
+    relay.hear(&signed_frame(105, &frozen(&soul, &data)).await, Some(&peer)).await.unwrap();
+    assert_eq!(core.graph.get(&soul).unwrap().data.get("value"), Some(&json!(42)));
+    let hex_soul = {
+        use sha2::{Digest, Sha256};
+        format!("#{}", hex::encode(Sha256::digest(data.to_string().as_bytes())))
+    };
+    relay.hear(&signed_frame(105, &frozen(&hex_soul, &data)).await, Some(&peer)).await.unwrap();
+    assert!(core.graph.get(&hex_soul).is_some());
+    assert!(mismatches.lock().unwrap().is_empty());
+    drain_frames(&mut rx);
+    drain_frames(&mut other_rx);
+
+    // Tampered: dropped, not forwarded, and reported
+    let tampered = json!({ "name": "frozen", "value": 43 });
+    let frame = signed_frame(105, &frozen(&soul, &tampered)).await;
+    let msg_id = serde_json::from_str::<Value>(&frame).unwrap()["#"].clone();
+    relay.hear(&frame, Some(&peer)).await.unwrap();
+    assert_eq!(core.graph.get(&soul).unwrap().data.get("value"), Some(&json!(42)));
+    assert!(unpack(&drain_frames(&mut other_rx)).iter().all(|msg| msg.get("put").is_none()));
+    let acks = unpack(&drain_frames(&mut rx));
+    let ack = acks.iter().find(|msg| msg["@"] == msg_id).expect("error ack sent");
+    assert!(ack["err"].as_str().unwrap().contains("Content hash mismatch"));
+    assert_eq!(relay.stats().hash_failures, 1);
+
+    let mismatches = mismatches.lock().unwrap();
+    assert_eq!(mismatches.len(), 1);
+    assert_eq!(mismatches[0]["soul"], json!(soul));
+    assert_eq!(mismatches[0]["hash"], json!(gun::valid::content_hash(&tampered)));
+    assert_eq!(mismatches[0]["peer"], json!(peer.id));
+}
@@ -0,0 +1,86 @@
+//! Integration tests for RedisStorage
+//! Requires the `redis-storage` feature and a running Redis server:
+//! `REDIS_URL=redis://127.0.0.1/ cargo test --features redis-storage --test redis_storage_tests`
+//! Tests needing a server are skipped when `REDIS_URL` is not set.
+
+#![cfg(feature = "redis-storage")]
+
+use gun::state::Node;
+use gun::storage::{RedisStorage, Storage};
+use serde_json::json;
+use std::time::Duration;
+
+// Connect to the server named by REDIS_URL, or None to skip
+async fn connect() -> Option<RedisStorage> {
+    let url = match std::env::var("REDIS_URL") {
+        Ok(url) => url,
+        Err(_) => {
+            eprintln!("REDIS_URL not set, skipping Redis storage test");
+            return None;
+        }
+    };
+    Some(RedisStorage::new(&url).await.expect("connect to Redis"))
+}
+
+// Unique soul so parallel runs against a shared server don't collide
+fn unique_soul(name: &str) -> String {
+    format!("redis_test_{}_{}", name, uuid::Uuid::new_v4())
+}
+
+fn create_test_node(soul: &str, key: &str, value: &serde_json::Value) -> Node {
+    let mut node = Node::with_soul(soul.to_string());
+    node.data.insert(key.to_string(), value.clone());
+    node
+}
+
+#[tokio::test]
+async fn test_redis_storage_put_get_list_delete() {
+    let Some(storage) = connect().await else {
+        return;
+    };
+    let soul = unique_soul("crud");
+    let node = create_test_node(&soul, "name", &json!("Alice"));
+
+    // Put / get
+    storage.put(&soul, &node).await.unwrap();
+    assert!(storage.has(&soul).await.unwrap());
+    let retrieved = storage.get(&soul).await.unwrap().unwrap();
+    assert_eq!(retrieved.get_soul(), Some(soul.clone()));
+    assert_eq!(retrieved.data.get("name"), Some(&json!("Alice")));
+
+    // List (souls are returned without the key prefix)
+    let souls = storage.list().await.unwrap();
+    assert!(souls.contains(&soul));
+
+    // Delete
+    storage.delete(&soul).await.unwrap();
+    assert!(!storage.has(&soul).await.unwrap());
+    assert!(storage.get(&soul).await.unwrap().is_none());
+    assert!(!storage.list().await.unwrap().contains(&soul));
+
+    // Deleting a missing soul is not an error
+    storage.delete(&soul).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_redis_storage_ttl() {
+    let Some(storage) = connect().await else {
+        return;
+    };
+    let storage = storage.with_ttl(Duration::from_millis(200));
+    let soul = unique_soul("ttl");
+    let node = create_test_node(&soul, "value", &json!(1));
+
+    storage.put(&soul, &node).await.unwrap();
+    assert!(storage.has(&soul).await.unwrap());
+
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert!(!storage.has(&soul).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_redis_storage_unreachable_server() {
+    // Nothing listens on port 1; connecting must fail with a storage error
+    let result = RedisStorage::new("redis://127.0.0.1:1/").await;
+    assert!(matches!(result, Err(gun::GunError::StorageBackend(_))));
+}
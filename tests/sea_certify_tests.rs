@@ -1,6 +1,10 @@
 //! Tests for SEA.certify() - Certificate-Based Access Control
 
-use gun::sea::{certify, pair, Certificants, CertifyOptions, Policy, RadixPolicy};
+use gun::sea::{
+    certify, pair, sign, verify_certificate, verify_write, Certificants, Certificate, CertifyOptions, KeyPair, LexPolicy,
+    Policy, RadixPolicy, SeaError,
+};
+use serde_json::json;
 use std::collections::HashMap;
 
 #[tokio::test]
@@ -174,9 +178,9 @@ async fn test_certify_verify_permissions() {
     use gun::sea::{verify_certificate, check_permission};
     let certificate = verify_certificate(&cert, &authority.pub_key).await.unwrap();
     
-    // Check permissions
+    // Check permissions: a string policy is exactly that path, as in Gun.js
     assert!(check_permission(&certificate, "inbox", "write"));
-    assert!(check_permission(&certificate, "inbox/messages", "write"));
+    assert!(!check_permission(&certificate, "inbox/messages", "write"));
     assert!(!check_permission(&certificate, "other", "write"));
 }
 
@@ -203,3 +207,292 @@ async fn test_certify_verify_expired() {
     // Check permission should fail for expired certificate
     assert!(!check_permission(&certificate, "test", "write"));
 }
+
+// A certificate from a fresh authority, verified, and the authority
+async fn certified(certificants: Certificants, policy: Policy, expiry: Option<f64>) -> (KeyPair, Certificate) {
+    let authority = pair().await.unwrap();
+    let cert = certify(certificants, policy, &authority, CertifyOptions { expiry, ..Default::default() })
+        .await
+        .unwrap();
+    let cert = verify_certificate(&cert, &authority.pub_key).await.unwrap();
+    (authority, cert)
+}
+
+#[tokio::test]
+async fn test_certify_policy_round_trips_in_gunjs_format() {
+    let policies = [
+        (Policy::String("inbox".to_string()), json!("inbox")),
+        (Policy::Radix(RadixPolicy::prefix("inbox").with_certificant()), json!({"*": "inbox", "+": "*"})),
+        (
+            Policy::Lex(LexPolicy::default().with_soul(RadixPolicy::prefix("inbox")).with_key(RadixPolicy::exact("a"))),
+            json!({"#": {"*": "inbox"}, ".": {"=": "a"}}),
+        ),
+        (
+            Policy::Array(vec![Policy::String("inbox".to_string()), Policy::Radix(RadixPolicy::prefix("profile"))]),
+            json!(["inbox", {"*": "profile"}]),
+        ),
+    ];
+    for (policy, value) in policies {
+        assert_eq!(policy.to_value(), value);
+        let (_, cert) = certified(Certificants::Wildcard, policy, None).await;
+        assert_eq!(cert.write_policy.unwrap().to_value(), value);
+    }
+
+    // A string in "#" or "." is an exact match
+    let Some(Policy::Lex(lex)) = Policy::from_value(&json!({"#": "inbox"})) else {
+        panic!("not a LEX policy");
+    };
+    assert_eq!(lex.soul.unwrap().patterns.get("="), Some(&"inbox".to_string()));
+}
+
+#[tokio::test]
+async fn test_verify_write_string_policy_is_exact() {
+    // "inbox": only the key "inbox" of alice's root
+    let (alice, cert) = certified(Certificants::Wildcard, Policy::String("inbox".to_string()), None).await;
+    let root = format!("~{}", alice.pub_key);
+    let inbox = format!("~{}/inbox", alice.pub_key);
+
+    assert!(verify_write(&cert, "bob", &root, "inbox"));
+    assert!(!verify_write(&cert, "bob", &root, "inbox2"));
+    assert!(!verify_write(&cert, "bob", &inbox, "message"));
+    assert!(!verify_write(&cert, "bob", &root, "profile"));
+}
+
+#[tokio::test]
+async fn test_verify_write_prefix_policy() {
+    // {"*": "inbox"}: paths starting with "inbox"
+    let (alice, cert) = certified(Certificants::Wildcard, Policy::Radix(RadixPolicy::prefix("inbox")), None).await;
+    let root = format!("~{}", alice.pub_key);
+
+    assert!(verify_write(&cert, "bob", &format!("{}/inbox", root), "message"));
+    assert!(verify_write(&cert, "bob", &format!("{}/inbox/deeper", root), "message"));
+    assert!(verify_write(&cert, "bob", &root, "inbox-bob"));
+    assert!(!verify_write(&cert, "bob", &format!("{}/profile", root), "inbox"));
+}
+
+#[tokio::test]
+async fn test_verify_write_range_policy() {
+    // {">": "b", "<": "d"}: paths from "b" to "d"
+    let (alice, cert) = certified(Certificants::Wildcard, Policy::Radix(RadixPolicy::range("b", "d")), None).await;
+    let root = format!("~{}", alice.pub_key);
+
+    assert!(verify_write(&cert, "bob", &root, "b"));
+    assert!(verify_write(&cert, "bob", &root, "c-anything"));
+    assert!(verify_write(&cert, "bob", &root, "d"));
+    assert!(!verify_write(&cert, "bob", &root, "a"));
+    assert!(!verify_write(&cert, "bob", &root, "e"));
+}
+
+#[tokio::test]
+async fn test_verify_write_soul_and_key_policies() {
+    // {"#": {"*": "inbox"}}: any key in souls whose path starts with "inbox"
+    let soul = Policy::Lex(LexPolicy::default().with_soul(RadixPolicy::prefix("inbox")));
+    let (alice, cert) = certified(Certificants::Wildcard, soul, None).await;
+    let root = format!("~{}", alice.pub_key);
+    assert!(verify_write(&cert, "bob", &format!("{}/inbox", root), "anything"));
+    assert!(verify_write(&cert, "bob", &format!("{}/inbox2", root), "anything"));
+    assert!(!verify_write(&cert, "bob", &format!("{}/profile", root), "inbox"));
+
+    // {".": {"*": "notifications"}}: keys starting with "notifications", anywhere
+    let key = Policy::Lex(LexPolicy::default().with_key(RadixPolicy::prefix("notifications")));
+    let (alice, cert) = certified(Certificants::Wildcard, key, None).await;
+    let root = format!("~{}", alice.pub_key);
+    assert!(verify_write(&cert, "bob", &format!("{}/profile", root), "notifications-1"));
+    assert!(verify_write(&cert, "bob", &root, "notifications"));
+    assert!(!verify_write(&cert, "bob", &root, "name"));
+
+    // Both: keys starting with "notifications" in souls starting with "inbox"
+    let both = Policy::Lex(
+        LexPolicy::default().with_soul(RadixPolicy::prefix("inbox")).with_key(RadixPolicy::prefix("notifications")),
+    );
+    let (alice, cert) = certified(Certificants::Wildcard, both, None).await;
+    let root = format!("~{}", alice.pub_key);
+    assert!(verify_write(&cert, "bob", &format!("{}/inbox", root), "notifications-1"));
+    assert!(!verify_write(&cert, "bob", &format!("{}/inbox", root), "name"));
+    assert!(!verify_write(&cert, "bob", &format!("{}/profile", root), "notifications-1"));
+}
+
+#[tokio::test]
+async fn test_verify_write_certificant_in_path() {
+    // {"*": "inbox", "+": "*"}: anyone, into alice's inbox, under their own pub
+    let bob = pair().await.unwrap();
+    let policy = Policy::Radix(RadixPolicy::prefix("inbox").with_certificant());
+    let (alice, cert) = certified(Certificants::Wildcard, policy, None).await;
+    let inbox = format!("~{}/inbox", alice.pub_key);
+
+    assert!(verify_write(&cert, &bob.pub_key, &inbox, &bob.pub_key));
+    assert!(verify_write(&cert, &bob.pub_key, &inbox, &format!("deeper{}", bob.pub_key)));
+    assert!(verify_write(&cert, &bob.pub_key, &format!("{}/{}", inbox, bob.pub_key), "message"));
+    assert!(!verify_write(&cert, &bob.pub_key, &inbox, "message"));
+    assert!(!verify_write(&cert, &bob.pub_key, &inbox, &alice.pub_key));
+}
+
+#[tokio::test]
+async fn test_verify_write_array_policy() {
+    // ["inbox", {"*": "profile"}]: either
+    let policy = Policy::Array(vec![Policy::String("inbox".to_string()), Policy::Radix(RadixPolicy::prefix("profile"))]);
+    let (alice, cert) = certified(Certificants::Wildcard, policy, None).await;
+    let root = format!("~{}", alice.pub_key);
+
+    assert!(verify_write(&cert, "bob", &root, "inbox"));
+    assert!(verify_write(&cert, "bob", &format!("{}/profile", root), "name"));
+    assert!(!verify_write(&cert, "bob", &format!("{}/settings", root), "name"));
+}
+
+#[tokio::test]
+async fn test_verify_write_certificants() {
+    let (bob, dave, eve) = (pair().await.unwrap(), pair().await.unwrap(), pair().await.unwrap());
+    let policy = || Policy::Radix(RadixPolicy::prefix("inbox"));
+
+    // One certificant
+    let (alice, cert) = certified(Certificants::List(vec![bob.pub_key.clone()]), policy(), None).await;
+    let inbox = format!("~{}/inbox", alice.pub_key);
+    assert!(verify_write(&cert, &bob.pub_key, &inbox, "message"));
+    assert!(!verify_write(&cert, &eve.pub_key, &inbox, "message"));
+
+    // Several
+    let list = Certificants::List(vec![bob.pub_key.clone(), dave.pub_key.clone()]);
+    let (alice, cert) = certified(list, policy(), None).await;
+    let inbox = format!("~{}/inbox", alice.pub_key);
+    assert!(verify_write(&cert, &bob.pub_key, &inbox, "message"));
+    assert!(verify_write(&cert, &dave.pub_key, &inbox, "message"));
+    assert!(!verify_write(&cert, &eve.pub_key, &inbox, "message"));
+
+    // A "*" among them is anyone
+    let list = Certificants::List(vec![bob.pub_key.clone(), "*".to_string()]);
+    let (alice, cert) = certified(list, policy(), None).await;
+    assert!(matches!(cert.certificants, Certificants::Wildcard));
+    assert!(verify_write(&cert, &eve.pub_key, &format!("~{}/inbox", alice.pub_key), "message"));
+}
+
+#[tokio::test]
+async fn test_verify_write_expiry() {
+    let now = chrono::Utc::now().timestamp_millis() as f64;
+    let policy = || Policy::Radix(RadixPolicy::prefix("inbox"));
+
+    let (alice, cert) = certified(Certificants::Wildcard, policy(), Some(now + 3600000.0)).await;
+    assert!(verify_write(&cert, "bob", &format!("~{}/inbox", alice.pub_key), "message"));
+
+    let (alice, cert) = certified(Certificants::Wildcard, policy(), Some(now - 1.0)).await;
+    assert!(!verify_write(&cert, "bob", &format!("~{}/inbox", alice.pub_key), "message"));
+}
+
+// A certificate `issuer` grants `certificant` under `parent`, the issuer's own
+async fn delegated(certificant: &KeyPair, policy: Policy, issuer: &KeyPair, parent: &str, expiry: Option<f64>) -> String {
+    let opt = CertifyOptions { expiry, parent: Some(parent.to_string()), ..Default::default() };
+    certify(Certificants::List(vec![certificant.pub_key.clone()]), policy, issuer, opt).await.unwrap()
+}
+
+#[tokio::test]
+async fn test_certificate_chain_delegates_within_scope() {
+    let (owner, admin, bob, carol) = (pair().await.unwrap(), pair().await.unwrap(), pair().await.unwrap(), pair().await.unwrap());
+    let admin_cert = certify(
+        Certificants::List(vec![admin.pub_key.clone()]),
+        Policy::Radix(RadixPolicy::prefix("channels")),
+        &owner,
+        CertifyOptions::default(),
+    )
+    .await
+    .unwrap();
+    let bob_cert = delegated(&bob, Policy::Radix(RadixPolicy::prefix("channels/general")), &admin, &admin_cert, None).await;
+
+    let cert = verify_certificate(&bob_cert, &owner.pub_key).await.unwrap();
+    assert_eq!(cert.issuer, admin.pub_key);
+    assert_eq!(cert.parent.as_ref().unwrap().issuer, owner.pub_key);
+    let general = format!("~{}/channels/general", owner.pub_key);
+    assert!(verify_write(&cert, &bob.pub_key, &general, "message"));
+    assert!(!verify_write(&cert, &bob.pub_key, &format!("~{}/channels/random", owner.pub_key), "message"));
+    assert!(!verify_write(&cert, &carol.pub_key, &general, "message"));
+
+    // Only the owner's chain counts, and only a certificant may delegate
+    assert!(verify_certificate(&bob_cert, &carol.pub_key).await.is_err());
+    let forged = delegated(&bob, Policy::Radix(RadixPolicy::prefix("channels/general")), &carol, &admin_cert, None).await;
+    let err = verify_certificate(&forged, &owner.pub_key).await.unwrap_err();
+    assert!(matches!(err, SeaError::CertInvalid { ref reason } if reason.contains("isn't certified to delegate")), "{}", err);
+
+    // Chains are capped
+    let mut chain = bob_cert;
+    let mut holder = bob;
+    for _ in 0..3 {
+        let next = pair().await.unwrap();
+        chain = delegated(&next, Policy::Radix(RadixPolicy::prefix("channels/general")), &holder, &chain, None).await;
+        holder = next;
+    }
+    let err = verify_certificate(&chain, &owner.pub_key).await.unwrap_err();
+    assert!(matches!(err, SeaError::CertInvalid { ref reason } if reason.contains("longer than 4")), "{}", err);
+}
+
+#[tokio::test]
+async fn test_certificate_chain_with_expired_intermediate_grants_nothing() {
+    let (owner, admin, bob) = (pair().await.unwrap(), pair().await.unwrap(), pair().await.unwrap());
+    let now = chrono::Utc::now().timestamp_millis() as f64;
+    let admin_cert = certify(
+        Certificants::List(vec![admin.pub_key.clone()]),
+        Policy::Radix(RadixPolicy::prefix("channels")),
+        &owner,
+        CertifyOptions { expiry: Some(now - 1.0), ..Default::default() },
+    )
+    .await
+    .unwrap();
+    let bob_cert = delegated(&bob, Policy::Radix(RadixPolicy::prefix("channels")), &admin, &admin_cert, Some(now + 3600000.0)).await;
+
+    let cert = verify_certificate(&bob_cert, &owner.pub_key).await.unwrap();
+    assert!(!verify_write(&cert, &bob.pub_key, &format!("~{}/channels/general", owner.pub_key), "message"));
+}
+
+#[tokio::test]
+async fn test_certificate_chain_rejects_child_exceeding_parent() {
+    let (owner, admin, bob) = (pair().await.unwrap(), pair().await.unwrap(), pair().await.unwrap());
+    let admin_cert = certify(
+        Certificants::List(vec![admin.pub_key.clone()]),
+        Policy::Radix(RadixPolicy::prefix("channels/general")),
+        &owner,
+        CertifyOptions::default(),
+    )
+    .await
+    .unwrap();
+
+    for policy in [
+        Policy::Radix(RadixPolicy::prefix("channels")),
+        Policy::String("profile".to_string()),
+        Policy::Array(vec![Policy::Radix(RadixPolicy::prefix("channels/general/a")), Policy::Radix(RadixPolicy::prefix("inbox"))]),
+        Policy::Radix(RadixPolicy::range("channels/general", "channels/random")),
+    ] {
+        let cert = delegated(&bob, policy, &admin, &admin_cert, None).await;
+        let err = verify_certificate(&cert, &owner.pub_key).await.unwrap_err();
+        assert!(matches!(err, SeaError::CertInvalid { ref reason } if reason.contains("exceeds its parent")), "{}", err);
+    }
+
+    // Narrower ones are fine
+    for policy in [
+        Policy::String("channels/general/topic".to_string()),
+        Policy::Radix(RadixPolicy::range("channels/general/a", "channels/general/m")),
+    ] {
+        let cert = delegated(&bob, policy, &admin, &admin_cert, None).await;
+        assert!(verify_certificate(&cert, &owner.pub_key).await.is_ok());
+    }
+}
+
+#[tokio::test]
+async fn test_certificate_errors_say_what_is_wrong() {
+    let (owner, mallory) = (pair().await.unwrap(), pair().await.unwrap());
+
+    // Not a certificate at all, or signed data granting nobody
+    let no_certificants = sign(&json!({"w": "inbox"}), &owner).await.unwrap();
+    for cert in ["not a certificate", no_certificants.as_str().unwrap()] {
+        let err = verify_certificate(cert, &owner.pub_key).await.unwrap_err();
+        assert!(matches!(err, SeaError::CertInvalid { .. }), "{}: {}", cert, err);
+    }
+
+    // Signed, but not by the authority
+    let cert = certify(
+        Certificants::Wildcard,
+        Policy::String("inbox".to_string()),
+        &mallory,
+        CertifyOptions::default(),
+    )
+    .await
+    .unwrap();
+    let err = verify_certificate(&cert, &owner.pub_key).await.unwrap_err();
+    assert!(matches!(err, SeaError::VerificationFailed), "{}", err);
+}
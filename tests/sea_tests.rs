@@ -2,8 +2,14 @@
 //! Tests pair, sign, verify, encrypt, decrypt, secret, user functions
 
 use gun::sea::secret;
-use gun::sea::{create_user, decrypt, encrypt, pair, sign, verify};
+use gun::sea::{
+    alias_soul, create_user, decrypt, decrypt_from_envelope, decrypt_with, encrypt, encrypt_for_many, encrypt_with, pair,
+    pair_from_seed, parse_user_soul, set_crypto_provider, sign, user_soul, verify, verify_cache, CryptoProvider, KeyPair,
+    ParsedUserSoul, RustCryptoProvider, SeaError,
+};
 use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 #[tokio::test]
 async fn test_sea_pair_generation() {
@@ -41,6 +47,51 @@ async fn test_sea_pair_unique() {
     assert_ne!(pair1.priv_key, pair2.priv_key);
 }
 
+#[tokio::test]
+async fn test_sea_pair_from_seed() {
+    let seed = b"correct horse battery staple seed";
+    let pair1 = pair_from_seed(seed).await.unwrap();
+    let pair2 = pair_from_seed(seed).await.unwrap();
+    assert_eq!(pair1.pub_key, pair2.pub_key);
+    assert_eq!(pair1.priv_key, pair2.priv_key);
+    assert_eq!(pair1.epub_key, pair2.epub_key);
+    assert_eq!(pair1.epriv_key, pair2.epriv_key);
+
+    // Version 1 of the derivation, as every run and release must give it
+    assert_eq!(pair1.pub_key, "8pyveusSMn4DD13fzAPlKbbgnUFsYDm-Mgp1tgW0pa8.VjNuXz0Sr6XkZcBr8TalWceRtPVzTFwk9V5Kq8-i-Zk");
+    assert_eq!(pair1.epub_key.as_deref(), Some("hFNBXWAWuugKl3y3zGu0EKlPqhHUDqPvqGzJ7P--xgo.aGb-OaQKCtwdPD5zHdq73mh15gRKRQu83r3UyOzfZxM"));
+
+    // The pair works like a generated one
+    let signed = sign(&json!("recovered"), &pair1).await.unwrap();
+    assert_eq!(verify(&signed, &pair2.pub_key).await.unwrap(), json!("recovered"));
+
+    let other = pair_from_seed(b"correct horse battery staple seee").await.unwrap();
+    assert_ne!(other.pub_key, pair1.pub_key);
+    assert_ne!(other.epub_key, pair1.epub_key);
+    assert_ne!(pair1.priv_key, pair1.epriv_key.unwrap());
+
+    assert!(pair_from_seed(b"too short").await.is_err());
+}
+
+#[cfg(feature = "mnemonic")]
+#[tokio::test]
+async fn test_sea_pair_from_mnemonic() {
+    use gun::sea::{generate_mnemonic, pair_from_mnemonic};
+
+    let phrase = generate_mnemonic();
+    assert_eq!(phrase.split_whitespace().count(), 12);
+    let pair1 = pair_from_mnemonic(&phrase, "").await.unwrap();
+    let pair2 = pair_from_mnemonic(&phrase, "").await.unwrap();
+    assert_eq!(pair1.priv_key, pair2.priv_key);
+    assert_eq!(pair1.epriv_key, pair2.epriv_key);
+
+    // The passphrase is part of the seed
+    let other = pair_from_mnemonic(&phrase, "passphrase").await.unwrap();
+    assert_ne!(other.pub_key, pair1.pub_key);
+
+    assert!(pair_from_mnemonic("not a valid mnemonic", "").await.is_err());
+}
+
 #[tokio::test]
 async fn test_sea_sign_data() {
     let keypair = pair().await.unwrap();
@@ -48,14 +99,14 @@ async fn test_sea_sign_data() {
 
     let signed = sign(&data, &keypair).await.unwrap();
 
-    // Should have "m" (message) and "s" (signature) fields
-    assert!(signed.get("m").is_some());
-    assert!(signed.get("s").is_some());
+    // Gun.js format: "SEA" followed by {"m": message, "s": signature}
+    let signed = signed.as_str().unwrap();
+    assert!(signed.starts_with("SEA{"));
+    let envelope: serde_json::Value = serde_json::from_str(&signed[3..]).unwrap();
+    assert!(envelope.get("s").unwrap().is_string());
 
-    // Message should match original data (serialized)
-    let message_str = signed.get("m").unwrap().as_str().unwrap();
-    let original_str = serde_json::to_string(&data).unwrap();
-    assert_eq!(message_str, original_str);
+    // Message should match original data
+    assert_eq!(envelope.get("m").unwrap(), &data);
 }
 
 #[tokio::test]
@@ -94,18 +145,167 @@ async fn test_sea_verify_tampered_data() {
     let keypair = pair().await.unwrap();
     let data = json!({"test": "data"});
 
-    let mut signed = sign(&data, &keypair).await.unwrap();
+    let signed = sign(&data, &keypair).await.unwrap();
 
     // Tamper with the message
-    if let Some(m) = signed.get_mut("m") {
-        *m = json!("tampered");
-    }
+    let mut envelope: serde_json::Value = serde_json::from_str(&signed.as_str().unwrap()[3..]).unwrap();
+    envelope["m"] = json!("tampered");
+    let signed = json!(format!("SEA{}", envelope));
 
     // Verification should fail (tampered data)
     let verified = verify(&signed, &keypair.pub_key).await;
     assert!(verified.is_err());
 }
 
+#[tokio::test]
+async fn test_sea_verify_cache_keeps_results_exact() {
+    let keypair = pair().await.unwrap();
+    let other = pair().await.unwrap();
+    let signed = sign(&json!({"test": "cached"}), &keypair).await.unwrap();
+
+    // The second check is answered from the cache, with the same result
+    let first = verify(&signed, &keypair.pub_key).await.unwrap();
+    let hits = verify_cache().stats().hits;
+    assert_eq!(verify(&signed, &keypair.pub_key).await.unwrap(), first);
+    assert!(verify_cache().stats().hits > hits);
+
+    // Another key, or other text under the same signature, is checked anew
+    assert!(verify(&signed, &other.pub_key).await.is_err());
+    assert!(verify(&signed, &other.pub_key).await.is_err());
+    let mut envelope: serde_json::Value = serde_json::from_str(&signed.as_str().unwrap()[3..]).unwrap();
+    envelope["m"] = json!({"test": "tampered"});
+    assert!(verify(&envelope, &keypair.pub_key).await.is_err());
+    assert!(verify(&signed, &keypair.pub_key).await.is_ok());
+}
+
+/// RustCrypto, counting the calls SEA makes
+#[derive(Default)]
+struct CountingProvider {
+    random: AtomicUsize,
+    signs: AtomicUsize,
+    verifies: AtomicUsize,
+    ecdh: AtomicUsize,
+    aes: AtomicUsize,
+}
+
+impl CryptoProvider for CountingProvider {
+    fn fill_random(&self, buf: &mut [u8]) -> Result<(), SeaError> {
+        self.random.fetch_add(1, Ordering::SeqCst);
+        RustCryptoProvider.fill_random(buf)
+    }
+    fn sha256(&self, data: &[u8]) -> [u8; 32] {
+        RustCryptoProvider.sha256(data)
+    }
+    fn pbkdf2_sha256(&self, password: &[u8], salt: &[u8], iterations: u32, out: &mut [u8]) {
+        RustCryptoProvider.pbkdf2_sha256(password, salt, iterations, out)
+    }
+    fn p256_public_key(&self, secret: &[u8]) -> Result<[u8; 65], SeaError> {
+        RustCryptoProvider.p256_public_key(secret)
+    }
+    fn p256_sign(&self, secret: &[u8], message: &[u8]) -> Result<[u8; 64], SeaError> {
+        self.signs.fetch_add(1, Ordering::SeqCst);
+        RustCryptoProvider.p256_sign(secret, message)
+    }
+    fn p256_verify(&self, public_key: &[u8], message: &[u8], signature: &[u8]) -> Result<bool, SeaError> {
+        self.verifies.fetch_add(1, Ordering::SeqCst);
+        RustCryptoProvider.p256_verify(public_key, message, signature)
+    }
+    fn p256_ecdh(&self, secret: &[u8], public_key: &[u8]) -> Result<[u8; 32], SeaError> {
+        self.ecdh.fetch_add(1, Ordering::SeqCst);
+        RustCryptoProvider.p256_ecdh(secret, public_key)
+    }
+    fn aes_gcm_encrypt(&self, key: &[u8], iv: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, SeaError> {
+        self.aes.fetch_add(1, Ordering::SeqCst);
+        RustCryptoProvider.aes_gcm_encrypt(key, iv, plaintext)
+    }
+    fn aes_gcm_decrypt(&self, key: &[u8], iv: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, SeaError> {
+        self.aes.fetch_add(1, Ordering::SeqCst);
+        RustCryptoProvider.aes_gcm_decrypt(key, iv, ciphertext)
+    }
+}
+
+#[tokio::test]
+async fn test_sea_crypto_provider_does_the_crypto() {
+    let provider = Arc::new(CountingProvider::default());
+    set_crypto_provider(provider.clone());
+
+    let alice = pair().await.unwrap();
+    let bob = pair().await.unwrap();
+    let signed = sign(&json!({"test": "provider"}), &alice).await.unwrap();
+    let unique = sign(&json!(format!("provider {}", alice.pub_key)), &alice).await.unwrap();
+    assert!(verify(&unique, &alice.pub_key).await.is_ok());
+    let message = encrypt(&json!("hi bob"), &alice, bob.epub_key.as_deref()).await.unwrap();
+    let opened = decrypt(&message, &bob, alice.epub_key.as_deref()).await.unwrap();
+    set_crypto_provider(Arc::new(RustCryptoProvider));
+
+    // Keys, IVs and salts are its randomness, and every primitive is its
+    assert_eq!(opened, json!("hi bob"));
+    assert!(verify(&signed, &alice.pub_key).await.is_ok());
+    assert!(provider.random.load(Ordering::SeqCst) >= 6);
+    assert!(provider.signs.load(Ordering::SeqCst) >= 2);
+    assert!(provider.verifies.load(Ordering::SeqCst) >= 1);
+    assert!(provider.ecdh.load(Ordering::SeqCst) >= 2);
+    assert!(provider.aes.load(Ordering::SeqCst) >= 2);
+}
+
+// Signed by Gun.js SEA (see tests/interop/sea_interop.js)
+const GUNJS_SEA_SIGNED: &str = include_str!("fixtures/gunjs_sea_signed.json");
+
+#[tokio::test]
+async fn test_sea_verify_gunjs_signed_data() {
+    let fixture: serde_json::Value = serde_json::from_str(GUNJS_SEA_SIGNED).unwrap();
+    let pub_key = fixture["pub"].as_str().unwrap();
+
+    for entry in fixture["signed"].as_array().unwrap() {
+        let verified = verify(&entry["sea"], pub_key).await.unwrap();
+        assert_eq!(verified, entry["data"], "{}", entry["sea"]);
+    }
+
+    // Someone else's key doesn't verify them
+    let other = pair().await.unwrap();
+    assert!(verify(&fixture["signed"][0]["sea"], &other.pub_key).await.is_err());
+}
+
+#[tokio::test]
+async fn test_sea_signed_data_verifies_in_gunjs() {
+    if std::process::Command::new("node").arg("--version").output().is_err() {
+        eprintln!("node not found, skipping Gun.js SEA interop test");
+        return;
+    }
+
+    let keypair = pair().await.unwrap();
+    let samples = vec![
+        json!("hello world"),
+        json!(42),
+        json!(-7),
+        json!(1.5),
+        json!(0.1),
+        json!(1e21),
+        json!(true),
+        json!(null),
+        json!([1, "two", {"three": 3}]),
+        json!({"message": "hello", "n": 30}),
+        json!({"nested": {"list": [1, 2, 3], "flag": false}, "ünïcödé": "é\"\n"}),
+    ];
+    let mut signed = Vec::new();
+    for data in samples {
+        let sea = sign(&data, &keypair).await.unwrap();
+        signed.push(json!({"data": data, "sea": sea}));
+    }
+
+    let file = std::env::temp_dir().join(format!("gun_sea_interop_{}.json", std::process::id()));
+    std::fs::write(&file, json!({"pub": keypair.pub_key, "signed": signed}).to_string()).unwrap();
+    let output = std::process::Command::new("node")
+        .arg("tests/interop/sea_interop.js")
+        .arg("verify")
+        .arg(&file)
+        .output()
+        .unwrap();
+    let _ = std::fs::remove_file(&file);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
 #[tokio::test]
 async fn test_sea_encrypt_decrypt() {
     let keypair = pair().await.unwrap();
@@ -114,10 +314,11 @@ async fn test_sea_encrypt_decrypt() {
     // Encrypt (self-encryption, no their_epub)
     let encrypted = encrypt(&data, &keypair, None).await.unwrap();
 
-    // Should have ct, iv, s fields
-    assert!(encrypted.get("ct").is_some());
-    assert!(encrypted.get("iv").is_some());
-    assert!(encrypted.get("s").is_some());
+    // Gun.js format: "SEA" followed by {"ct": …, "iv": …, "s": …}
+    let envelope: serde_json::Value = serde_json::from_str(&encrypted.as_str().unwrap()[3..]).unwrap();
+    assert!(envelope.get("ct").is_some());
+    assert!(envelope.get("iv").is_some());
+    assert!(envelope.get("s").is_some());
 
     // Decrypt
     let decrypted = decrypt(&encrypted, &keypair, None).await.unwrap();
@@ -164,24 +365,149 @@ async fn test_sea_encrypt_wrong_key() {
     assert!(result.is_err());
 }
 
+// Encrypted by Gun.js SEA (see tests/interop/sea_interop.js): `own` by
+// alice for herself, `shared` by alice for bob
+const GUNJS_SEA_ENCRYPTED: &str = include_str!("fixtures/gunjs_sea_encrypted.json");
+
+// Encrypted in the `{ct, iv, s}` layout of earlier versions of this crate
+const LEGACY_SEA_ENCRYPTED: &str = include_str!("fixtures/sea_legacy_encrypted.json");
+
+#[tokio::test]
+async fn test_sea_encrypt_for_many_recipients() {
+    let alice = pair().await.unwrap();
+    let recipients = [pair().await.unwrap(), pair().await.unwrap(), pair().await.unwrap()];
+    let outsider = pair().await.unwrap();
+    let epubs: Vec<&str> = recipients.iter().map(|p| p.epub_key.as_deref().unwrap()).collect();
+    let doc = json!({ "title": "Plans", "body": "Meet at noon" });
+
+    let envelope = encrypt_for_many(&doc, &epubs, &alice).await.unwrap();
+    assert_eq!(envelope["v"], json!(1));
+    assert_eq!(envelope["keys"].as_object().unwrap().len(), 3);
+    assert!(!envelope.to_string().contains("Meet at noon"));
+
+    for recipient in &recipients {
+        assert_eq!(decrypt_from_envelope(&envelope, recipient).await.unwrap(), doc);
+    }
+    // Also as the JSON text it's stored as
+    let text = json!(envelope.to_string());
+    assert_eq!(decrypt_from_envelope(&text, &recipients[0]).await.unwrap(), doc);
+
+    assert!(decrypt_from_envelope(&envelope, &outsider).await.is_err());
+
+    // One byte of the ciphertext changed: authentication fails
+    let ct: serde_json::Value = serde_json::from_str(&envelope["ct"].as_str().unwrap()[3..]).unwrap();
+    let mut bytes = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, ct["ct"].as_str().unwrap()).unwrap();
+    bytes[0] ^= 1;
+    let mut tampered_ct = ct.clone();
+    tampered_ct["ct"] = json!(base64::Engine::encode(&base64::engine::general_purpose::STANDARD, bytes));
+    let mut tampered = envelope.clone();
+    tampered["ct"] = json!(format!("SEA{}", tampered_ct));
+    assert!(decrypt_from_envelope(&tampered, &recipients[1]).await.is_err());
+}
+
+fn fixture_pair(keys: &serde_json::Value) -> KeyPair {
+    KeyPair {
+        pub_key: keys["pub"].as_str().unwrap().to_string(),
+        priv_key: keys["priv"].as_str().unwrap().to_string(),
+        epub_key: keys["epub"].as_str().map(str::to_string),
+        epriv_key: keys["epriv"].as_str().map(str::to_string),
+    }
+}
+
+// Decrypt `own` as alice and `shared` as bob
+async fn decrypt_fixture(fixture: &str) {
+    let fixture: serde_json::Value = serde_json::from_str(fixture).unwrap();
+    let alice = fixture_pair(&fixture["alice"]);
+    let bob = fixture_pair(&fixture["bob"]);
+
+    for entry in fixture["own"].as_array().unwrap() {
+        let decrypted = decrypt(&entry["sea"], &alice, None).await.unwrap();
+        assert_eq!(decrypted, entry["data"], "{}", entry["sea"]);
+    }
+    for entry in fixture["shared"].as_array().unwrap() {
+        let decrypted = decrypt(&entry["sea"], &bob, alice.epub_key.as_deref()).await.unwrap();
+        assert_eq!(decrypted, entry["data"], "{}", entry["sea"]);
+    }
+
+    // Not for someone else
+    let other = pair().await.unwrap();
+    assert!(decrypt(&fixture["shared"][0]["sea"], &other, alice.epub_key.as_deref()).await.is_err());
+}
+
+#[tokio::test]
+async fn test_sea_decrypt_gunjs_encrypted_data() {
+    decrypt_fixture(GUNJS_SEA_ENCRYPTED).await;
+}
+
+#[tokio::test]
+async fn test_sea_decrypt_legacy_layout() {
+    decrypt_fixture(LEGACY_SEA_ENCRYPTED).await;
+}
+
+#[tokio::test]
+async fn test_sea_encrypted_data_decrypts_in_gunjs() {
+    if std::process::Command::new("node").arg("--version").output().is_err() {
+        eprintln!("node not found, skipping Gun.js SEA interop test");
+        return;
+    }
+
+    let alice = pair().await.unwrap();
+    let bob = pair().await.unwrap();
+    let samples = vec![
+        json!("hello world"),
+        json!(""),
+        json!(42),
+        json!(1.5),
+        json!(true),
+        json!(null),
+        json!([1, "two", {"three": 3}]),
+        json!({"nested": {"list": [1, 2, 3], "flag": false}, "ünïcödé": "é\"\n"}),
+    ];
+    let (mut own, mut shared) = (Vec::new(), Vec::new());
+    for data in samples {
+        let sea = encrypt(&data, &alice, None).await.unwrap();
+        own.push(json!({"data": data, "sea": sea}));
+        let sea = encrypt(&data, &alice, bob.epub_key.as_deref()).await.unwrap();
+        shared.push(json!({"data": data, "sea": sea}));
+    }
+
+    let keys = |pair: &KeyPair| {
+        json!({"pub": pair.pub_key, "priv": pair.priv_key, "epub": pair.epub_key, "epriv": pair.epriv_key})
+    };
+    let file = std::env::temp_dir().join(format!("gun_sea_decrypt_interop_{}.json", std::process::id()));
+    let fixture = json!({"alice": keys(&alice), "bob": keys(&bob), "own": own, "shared": shared});
+    std::fs::write(&file, fixture.to_string()).unwrap();
+    let output = std::process::Command::new("node")
+        .arg("tests/interop/sea_interop.js")
+        .arg("decrypt")
+        .arg(&file)
+        .output()
+        .unwrap();
+    let _ = std::fs::remove_file(&file);
+
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+}
+
 #[tokio::test]
 async fn test_sea_secret_derivation() {
     let alice = pair().await.unwrap();
     let bob = pair().await.unwrap();
 
     let alice_epub = alice.epub_key.as_ref().unwrap();
-    let alice_epriv = alice.epriv_key.as_ref().unwrap();
     let bob_epub = bob.epub_key.as_ref().unwrap();
-    let bob_epriv = bob.epriv_key.as_ref().unwrap();
 
     // Alice derives secret from Bob's pub key
-    let secret1 = secret(bob_epub, alice_epriv, alice_epub).await.unwrap();
+    let secret1 = secret(bob_epub, &alice).await.unwrap();
 
     // Bob derives secret from Alice's pub key
-    let secret2 = secret(alice_epub, bob_epriv, bob_epub).await.unwrap();
+    let secret2 = secret(alice_epub, &bob).await.unwrap();
 
     // Should be the same (ECDH property)
     assert_eq!(secret1, secret2);
+
+    // And usable as a key as it is
+    let encrypted = encrypt_with(&json!({"for": "bob"}), &secret1).await.unwrap();
+    assert_eq!(decrypt_with(&encrypted, &secret2).await.unwrap(), json!({"for": "bob"}));
 }
 
 #[tokio::test]
@@ -190,24 +516,37 @@ async fn test_sea_secret_different_peers() {
     let bob = pair().await.unwrap();
     let charlie = pair().await.unwrap();
 
-    let alice_epub = alice.epub_key.as_ref().unwrap();
-    let alice_epriv = alice.epriv_key.as_ref().unwrap();
     let bob_epub = bob.epub_key.as_ref().unwrap();
     let charlie_epub = charlie.epub_key.as_ref().unwrap();
 
-    let secret_alice_bob = secret(bob_epub, alice_epriv, alice_epub).await.unwrap();
-    let secret_alice_charlie = secret(charlie_epub, alice_epriv, alice_epub).await.unwrap();
+    let secret_alice_bob = secret(bob_epub, &alice).await.unwrap();
+    let secret_alice_charlie = secret(charlie_epub, &alice).await.unwrap();
 
     // Should be different
     assert_ne!(secret_alice_bob, secret_alice_charlie);
 }
 
+#[tokio::test]
+async fn test_sea_secret_decrypts_gunjs_shared_data() {
+    // `shared` was encrypted with SEA.encrypt(data, await SEA.secret(bob.epub, alice))
+    let fixture: serde_json::Value = serde_json::from_str(GUNJS_SEA_ENCRYPTED).unwrap();
+    let alice = fixture_pair(&fixture["alice"]);
+    let bob = fixture_pair(&fixture["bob"]);
+
+    let shared = secret(alice.epub_key.as_deref().unwrap(), &bob).await.unwrap();
+    assert_eq!(shared, fixture["secret"].as_str().unwrap(), "the secret Gun.js derived");
+    assert_eq!(secret(bob.epub_key.as_deref().unwrap(), &alice).await.unwrap(), shared);
+    for entry in fixture["shared"].as_array().unwrap() {
+        assert_eq!(decrypt_with(&entry["sea"], &shared).await.unwrap(), entry["data"]);
+    }
+}
+
 #[tokio::test]
 async fn test_sea_user_create() {
     use gun::Gun;
-    use std::sync::Arc;
-    
-    let gun = Gun::new();
+
+    let secret_key = chia_bls::SecretKey::from_seed(&[1u8; 32]);
+    let gun = Gun::new(secret_key.clone(), secret_key.public_key());
     let chain = gun.root();
     let user = create_user(chain, Some("alice".to_string()), "password123").await.unwrap();
 
@@ -218,9 +557,9 @@ async fn test_sea_user_create() {
 #[tokio::test]
 async fn test_sea_user_create_no_alias() {
     use gun::Gun;
-    use std::sync::Arc;
-    
-    let gun = Gun::new();
+
+    let secret_key = chia_bls::SecretKey::from_seed(&[2u8; 32]);
+    let gun = Gun::new(secret_key.clone(), secret_key.public_key());
     let chain = gun.root();
     let user = create_user(chain, None, "password123").await.unwrap();
 
@@ -260,3 +599,44 @@ async fn test_sea_encrypt_various_types() {
     let dec_null = decrypt(&enc_null, &keypair, None).await.unwrap();
     assert_eq!(dec_null, null_data);
 }
+
+#[tokio::test]
+async fn test_sea_user_souls() {
+    let keypair = pair().await.unwrap();
+    let soul = user_soul(&keypair.pub_key).unwrap();
+    assert_eq!(soul, format!("~{}", keypair.pub_key));
+    assert_eq!(
+        parse_user_soul(&soul),
+        Some(ParsedUserSoul::User { pub_key: keypair.pub_key.clone(), path: None })
+    );
+    assert_eq!(
+        parse_user_soul(&format!("{}/inbox/1", soul)),
+        Some(ParsedUserSoul::User { pub_key: keypair.pub_key.clone(), path: Some("inbox/1".to_string()) })
+    );
+
+    // The alias round trip
+    let alias = alias_soul("alice").unwrap();
+    assert_eq!(alias, "~@alice");
+    assert_eq!(parse_user_soul(&alias), Some(ParsedUserSoul::Alias("alice".to_string())));
+    assert!(alias_soul("").is_err());
+    assert_eq!(parse_user_soul("~@"), None);
+
+    // Malformed pubs
+    let (x, y) = keypair.pub_key.split_once('.').unwrap();
+    for bad in [
+        "alice.pub".to_string(),
+        format!("{}.{}", x, &y[1..]),
+        format!("{}.{}=", x, y),
+        format!("{}.{}", x.replace(|c: char| c.is_ascii_alphanumeric(), "+"), y),
+        format!("{}.{}.{}", x, y, x),
+        String::new(),
+    ] {
+        assert!(matches!(user_soul(&bad), Err(SeaError::InvalidKey)), "{}", bad);
+        assert_eq!(parse_user_soul(&format!("~{}", bad)), None, "{}", bad);
+    }
+
+    // Missing separators: no `.` in the pub, no `~`, nothing after `/`
+    assert!(user_soul(&format!("{}{}", x, y)).is_err());
+    assert_eq!(parse_user_soul(&keypair.pub_key), None);
+    assert_eq!(parse_user_soul(&format!("{}/", soul)), None);
+}
@@ -1,14 +1,23 @@
 //! Comprehensive tests for SEA user authentication and recall
 //! Tests user creation, authentication, and session recall with graph storage
 
-use gun::Gun;
-use gun::sea::{authenticate, create_user, recall};
+use gun::{Gun, GunOptions};
+use gun::sea::{
+    authenticate, change_password, create_user, delete_user, generate_salt, hash_password, recall, remember, remember_on_device,
+    rotate_keys,
+    verify_password, verify_user_put, SeaError,
+};
 use serde_json::json;
 use std::sync::Arc;
 
+fn new_gun() -> Gun {
+    let secret_key = chia_bls::SecretKey::from_seed(&[3u8; 32]);
+    Gun::new(secret_key.clone(), secret_key.public_key())
+}
+
 #[tokio::test]
 async fn test_user_create_with_graph() {
-    let gun = Gun::new();
+    let gun = new_gun();
     let chain = gun.root();
     
     let user = create_user(chain.clone(), Some("testuser".to_string()), "password123")
@@ -35,7 +44,7 @@ async fn test_user_create_with_graph() {
 
 #[tokio::test]
 async fn test_user_authenticate_success() {
-    let gun = Gun::new();
+    let gun = new_gun();
     let chain = gun.root();
     
     // Create user
@@ -55,7 +64,7 @@ async fn test_user_authenticate_success() {
 
 #[tokio::test]
 async fn test_user_authenticate_wrong_password() {
-    let gun = Gun::new();
+    let gun = new_gun();
     let chain = gun.root();
     
     // Create user
@@ -67,19 +76,280 @@ async fn test_user_authenticate_wrong_password() {
     let result = authenticate(chain.clone(), "wrongpass", "wrongpass")
         .await;
     
-    assert!(result.is_err(), "Authentication should fail with wrong password");
+    assert!(matches!(result, Err(SeaError::WrongPassword)), "Authentication should fail with wrong password");
+}
+
+#[test]
+fn test_verify_password_matches_only_its_hash() {
+    let salt = generate_salt();
+    let hash = hash_password("correct horse", &salt);
+
+    assert!(verify_password("correct horse", &salt, &hash));
+    assert!(!verify_password("correct horsf", &salt, &hash));
+    assert!(!verify_password("correct horse", &generate_salt(), &hash));
+    assert!(!verify_password("correct horse", &salt, &hash[..hash.len() - 4]));
+    assert!(!verify_password("correct horse", &salt, "not base64!"));
+    assert!(!verify_password("correct horse", &salt, ""));
 }
 
 #[tokio::test]
 async fn test_user_authenticate_nonexistent() {
-    let gun = Gun::new();
+    let gun = new_gun();
     let chain = gun.root();
     
     // Try to authenticate non-existent user
     let result = authenticate(chain.clone(), "nonexistent", "password")
         .await;
     
-    assert!(result.is_err(), "Authentication should fail for non-existent user");
+    assert!(matches!(result, Err(SeaError::UserNotFound)), "Authentication should fail for non-existent user");
+}
+
+#[tokio::test]
+async fn test_user_authenticate_finds_alias_over_network() {
+    let port = 18853;
+    let relay_key = chia_bls::SecretKey::from_seed(&[20u8; 32]);
+    let mut relay = Gun::with_options(relay_key.clone(), relay_key.public_key(), GunOptions::relay_server(port))
+        .await
+        .unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+    let peer = |seed: u8| {
+        let key = chia_bls::SecretKey::from_seed(&[seed; 32]);
+        Gun::with_options(key.clone(), key.public_key(), GunOptions {
+            localStorage: false,
+            radisk: false,
+            ..GunOptions::with_relay(&format!("ws://127.0.0.1:{}/gun", port))
+        })
+    };
+    let mut alice = peer(21).await.unwrap();
+    let mut bob = peer(22).await.unwrap();
+    assert!(alice.wait_for_connection(5000).await);
+    assert!(bob.wait_for_connection(5000).await);
+
+    let alice_pub = alice.user().create("alice", "alicepass").await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+
+    // Bob's graph never held the account: ~@alice and ~pub come from the relay
+    let auth = authenticate(bob.root(), "alice", "alicepass").await.unwrap();
+    assert_eq!(auth.pair.pub_key, alice_pub);
+    assert!(authenticate(bob.root(), "alice", "wrongpass").await.is_err());
+
+    alice.shutdown().await.unwrap();
+    bob.shutdown().await.unwrap();
+    relay.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_user_authenticate_rejects_spoofed_alias() {
+    let gun = new_gun();
+    let chain = gun.root();
+    let alice = create_user(chain.clone(), Some("alice".to_string()), "alicepass").await.unwrap();
+    let mallory = create_user(chain.clone(), Some("mallory".to_string()), "mallorypass").await.unwrap();
+
+    // Mallory's account copied under a soul that isn't its pub, and linked
+    // from ~@alice
+    let mut account = json!(null);
+    chain.get(&format!("~{}", mallory.pair.pub_key)).once(|data, _key| account = data).await.unwrap();
+    let fake_soul = "~fake.pub";
+    chain.get(fake_soul).put(account.clone()).await.unwrap();
+    chain.get("~@alice").put(json!({ fake_soul: { "#": fake_soul } })).await.unwrap();
+
+    // A node claiming the alias anywhere else isn't looked at
+    let mut claim = account;
+    claim["alias"] = json!("alice");
+    chain.get("not_an_account").put(claim).await.unwrap();
+
+    assert!(matches!(authenticate(chain.clone(), "alice", "mallorypass").await, Err(SeaError::WrongPassword)));
+    let auth = authenticate(chain.clone(), "alice", "alicepass").await.unwrap();
+    assert_eq!(auth.pair.pub_key, alice.pair.pub_key);
+}
+
+#[tokio::test]
+async fn test_user_change_password() {
+    let gun = new_gun();
+    let user = gun.user();
+    let pub_key = user.create("alice", "oldpass").await.unwrap();
+    user.auth("alice", "oldpass").await.unwrap();
+    user.get("name").unwrap().put(json!("Alice")).await.unwrap();
+    user.leave();
+
+    assert!(change_password(gun.root(), "alice", "wrongpass", "newpass").await.is_err());
+    let auth = change_password(gun.root(), "alice", "oldpass", "newpass").await.unwrap();
+    assert_eq!(auth.pair.pub_key, pub_key);
+
+    assert!(authenticate(gun.root(), "alice", "oldpass").await.is_err());
+    let auth = user.auth("alice", "newpass").await.unwrap();
+    assert_eq!(auth.pair.pub_key, pub_key);
+
+    let mut read = None;
+    user.get("name").unwrap().once_with_meta(|value, _key, meta| read = Some((value, meta.verified))).await.unwrap();
+    assert_eq!(read, Some((json!("Alice"), true)));
+}
+
+#[tokio::test]
+async fn test_user_rotate_keys() {
+    let gun = new_gun();
+    let user = gun.user();
+    let old_pub = user.create("alice", "alicepass").await.unwrap();
+    user.auth("alice", "alicepass").await.unwrap();
+    user.get("name").unwrap().put(json!("Alice")).await.unwrap();
+    user.leave();
+
+    let rotated = rotate_keys(gun.root(), "alice", "alicepass").await.unwrap();
+    let new_pub = rotated.pair.pub_key.clone();
+    assert_ne!(new_pub, old_pub);
+
+    // Signing in gives the new pair, which writes its own space
+    let auth = user.auth("alice", "alicepass").await.unwrap();
+    assert_eq!(auth.pair.pub_key, new_pub);
+    assert_eq!(auth.pair.priv_key, rotated.pair.priv_key);
+    user.get("name").unwrap().put(json!("Alice 2")).await.unwrap();
+
+    // The old account points at the new one, and its data still verifies
+    let old_space = gun.get(&format!("~{}", old_pub));
+    let mut read = None;
+    old_space.get("rotated").once_with_meta(|value, _key, meta| read = Some((value, meta.verified))).await.unwrap();
+    assert_eq!(read, Some((json!(format!("~{}", new_pub)), true)));
+    old_space.get("name").once_with_meta(|value, _key, meta| read = Some((value, meta.verified))).await.unwrap();
+    assert_eq!(read, Some((json!("Alice"), true)));
+}
+
+#[tokio::test]
+async fn test_user_delete_then_recreate_alias() {
+    use std::io::Write;
+
+    let gun = new_gun();
+    let chain = gun.root();
+    let old = create_user(chain.clone(), Some("alice".to_string()), "alicepass").await.unwrap();
+
+    // A session stored before the account is deleted
+    let mut session = tempfile::NamedTempFile::new().unwrap();
+    let session_data = json!({
+        "pub": old.pair.pub_key,
+        "priv": old.pair.priv_key,
+        "alias": "alice",
+        "exp": chrono::Utc::now().timestamp_millis() as f64
+    });
+    writeln!(session, "{}", session_data).unwrap();
+    let session_path = session.path().to_str().unwrap();
+    assert!(recall(Some(chain.clone()), Some(session_path), None).await.unwrap().is_some());
+
+    assert!(delete_user(chain.clone(), "alice", "wrongpass").await.is_err());
+    delete_user(chain.clone(), "alice", "alicepass").await.unwrap();
+
+    let mut account = json!(null);
+    chain.get(&format!("~{}", old.pair.pub_key)).once(|data, _key| account = data).await.unwrap();
+    for field in ["hash", "salt", "priv", "epriv"] {
+        assert_eq!(account[field], json!(null), "{} should be tombstoned", field);
+    }
+
+    let err = authenticate(chain.clone(), "alice", "alicepass").await.err().expect("deleted user signed in");
+    assert!(matches!(err, SeaError::UserNotFound), "{}", err);
+    assert!(err.to_string().contains("not found"), "{}", err);
+    assert!(matches!(recall(Some(chain.clone()), Some(session_path), None).await, Err(SeaError::UserNotFound)));
+
+    // The alias is free for a new account
+    let new = create_user(chain.clone(), Some("alice".to_string()), "newpass").await.unwrap();
+    assert_ne!(new.pair.pub_key, old.pair.pub_key);
+    let auth = authenticate(chain.clone(), "alice", "newpass").await.unwrap();
+    assert_eq!(auth.pair.pub_key, new.pair.pub_key);
+    assert!(authenticate(chain.clone(), "alice", "alicepass").await.is_err());
+}
+
+#[tokio::test]
+async fn test_user_remember_and_recall_encrypted() {
+    let gun = new_gun();
+    let chain = gun.root();
+    let user = create_user(chain.clone(), Some("alice".to_string()), "alicepass").await.unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("session.json");
+    let path = path.to_str().unwrap();
+    remember(&user, path, "device passphrase").await.unwrap();
+
+    // The private keys aren't on disk in the clear
+    let stored = std::fs::read_to_string(path).unwrap();
+    assert!(!stored.contains(&user.pair.priv_key));
+    assert!(!stored.contains(user.pair.epriv_key.as_deref().unwrap()));
+
+    let recalled = recall(Some(chain.clone()), Some(path), Some("device passphrase")).await.unwrap().unwrap();
+    assert_eq!(recalled.pair.pub_key, user.pair.pub_key);
+    assert_eq!(recalled.pair.priv_key, user.pair.priv_key);
+    assert_eq!(recalled.pair.epriv_key, user.pair.epriv_key);
+    assert_eq!(recalled.alias, Some("alice".to_string()));
+
+    assert!(recall(Some(chain.clone()), Some(path), Some("wrong passphrase")).await.is_err());
+    assert!(recall(Some(chain.clone()), Some(path), None).await.is_err());
+
+    // Past its expiry the session is gone, whatever the key
+    let mut session: serde_json::Value = serde_json::from_str(&stored).unwrap();
+    session["expires"] = json!(chrono::Utc::now().timestamp_millis() as f64 - 1000.0);
+    std::fs::write(path, session.to_string()).unwrap();
+    assert!(matches!(recall(Some(chain.clone()), Some(path), Some("device passphrase")).await, Err(SeaError::Expired)));
+}
+
+#[tokio::test]
+async fn test_user_remember_reencrypts_plaintext_session() {
+    let gun = new_gun();
+    let chain = gun.root();
+    let user = create_user(chain.clone(), Some("alice".to_string()), "alicepass").await.unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("session.json");
+    let path = path.to_str().unwrap();
+    let legacy = json!({
+        "pub": user.pair.pub_key,
+        "priv": user.pair.priv_key,
+        "alias": "alice",
+        "exp": chrono::Utc::now().timestamp_millis() as f64
+    });
+    std::fs::write(path, legacy.to_string()).unwrap();
+
+    let recalled = recall(Some(chain.clone()), Some(path), None).await.unwrap().unwrap();
+    assert_eq!(recalled.pair.priv_key, user.pair.priv_key);
+
+    remember(&recalled, path, "device passphrase").await.unwrap();
+    assert!(!std::fs::read_to_string(path).unwrap().contains(&user.pair.priv_key));
+    let recalled = recall(Some(chain.clone()), Some(path), Some("device passphrase")).await.unwrap().unwrap();
+    assert_eq!(recalled.pair.priv_key, user.pair.priv_key);
+}
+
+#[tokio::test]
+async fn test_user_remember_on_device_survives_restart() {
+    use gun::storage::Storage;
+
+    let dir = tempfile::tempdir().unwrap();
+    let options = || GunOptions {
+        storage_path: Some(dir.path().to_str().unwrap().to_string()),
+        ..Default::default()
+    };
+    let secret_key = chia_bls::SecretKey::from_seed(&[3u8; 32]);
+
+    let user = {
+        let gun = Gun::with_options(secret_key.clone(), secret_key.public_key(), options()).await.unwrap();
+        let user = create_user(gun.root(), Some("alice".to_string()), "alicepass").await.unwrap();
+        remember_on_device(gun.root(), &user, "laptop", "device passphrase").await.unwrap();
+        gun.flush().await.unwrap();
+        user
+    };
+
+    // A new instance on the same storage, with no session file
+    let gun = Gun::with_options(secret_key.clone(), secret_key.public_key(), options()).await.unwrap();
+    let soul = format!("~{}/session/laptop", user.pair.pub_key);
+    assert!(gun.root().core.graph.get(&soul).is_none());
+    let recalled = recall(Some(gun.root()), None, Some("device passphrase")).await.unwrap().unwrap();
+    assert_eq!(recalled.pair.pub_key, user.pair.pub_key);
+    assert_eq!(recalled.pair.priv_key, user.pair.priv_key);
+    assert_eq!(recalled.pair.epriv_key, user.pair.epriv_key);
+    assert_eq!(recalled.alias, Some("alice".to_string()));
+
+    // Another key opens nothing; past its expiry the session is gone
+    assert!(recall(Some(gun.root()), None, Some("wrong passphrase")).await.unwrap().is_none());
+    let storage = gun.root().core.storage();
+    let mut node = storage.get(&soul).await.unwrap().unwrap();
+    node.data.insert("expires".to_string(), json!(chrono::Utc::now().timestamp_millis() as f64 - 1000.0));
+    storage.put(&soul, &node).await.unwrap();
+    assert!(recall(Some(gun.root()), None, Some("device passphrase")).await.unwrap().is_none());
 }
 
 #[tokio::test]
@@ -100,11 +370,11 @@ async fn test_user_recall_from_file() {
     writeln!(temp_file, "{}", serde_json::to_string(&session_data).unwrap()).unwrap();
     let file_path = temp_file.path().to_str().unwrap();
     
-    let gun = Gun::new();
+    let gun = new_gun();
     let chain = gun.root();
     
     // Recall session
-    let recalled = recall(Some(chain.clone()), Some(file_path))
+    let recalled = recall(Some(chain.clone()), Some(file_path), None)
         .await
         .unwrap();
     
@@ -133,24 +403,23 @@ async fn test_user_recall_expired() {
     writeln!(temp_file, "{}", serde_json::to_string(&session_data).unwrap()).unwrap();
     let file_path = temp_file.path().to_str().unwrap();
     
-    let gun = Gun::new();
+    let gun = new_gun();
     let chain = gun.root();
     
     // Recall expired session
-    let recalled = recall(Some(chain.clone()), Some(file_path))
-        .await
-        .unwrap();
+    let recalled = recall(Some(chain.clone()), Some(file_path), None)
+        .await;
     
-    assert!(recalled.is_none(), "Expired session should return None");
+    assert!(matches!(recalled, Err(SeaError::Expired)), "Expired session should be SeaError::Expired");
 }
 
 #[tokio::test]
 async fn test_user_recall_no_file() {
-    let gun = Gun::new();
+    let gun = new_gun();
     let chain = gun.root();
     
     // Recall with no file path
-    let recalled = recall(Some(chain.clone()), None)
+    let recalled = recall(Some(chain.clone()), None, None)
         .await
         .unwrap();
     
@@ -159,7 +428,7 @@ async fn test_user_recall_no_file() {
 
 #[tokio::test]
 async fn test_user_create_and_authenticate_flow() {
-    let gun = Gun::new();
+    let gun = new_gun();
     let chain = gun.root();
     
     // Create user
@@ -184,3 +453,165 @@ async fn test_user_create_and_authenticate_flow() {
     assert_eq!(verified, data);
 }
 
+
+#[tokio::test]
+async fn test_user_chain_create_leave_auth_reads_own_data() {
+    let gun = new_gun();
+    let user = gun.user();
+    assert!(user.is().is_none());
+    assert!(user.get("name").is_err(), "nobody signed in");
+
+    // Creating an account doesn't sign in
+    let pub_key = user.create("chainuser", "chainpass").await.unwrap();
+    assert!(user.is().is_none());
+
+    let auth = user.auth("chainuser", "chainpass").await.unwrap();
+    assert_eq!(auth.pair.pub_key, pub_key);
+    assert_eq!(user.is(), Some(pub_key.clone()));
+    user.get("name").unwrap().put(json!("Chain User")).await.unwrap();
+
+    // Written under the user's own soul
+    let node = gun.root().core.graph.get(&format!("~{}", pub_key)).expect("user node");
+    assert!(node.data.contains_key("name"));
+
+    user.leave();
+    assert!(user.is().is_none());
+    assert!(user.get("name").is_err());
+
+    // Signed in again, through another handle: reads what it wrote
+    assert!(user.auth("chainuser", "wrongpass").await.is_err());
+    assert!(gun.user().is().is_none());
+    gun.user().auth("chainuser", "chainpass").await.unwrap();
+    assert_eq!(user.is(), Some(pub_key));
+    let mut name = None;
+    user.get("name").unwrap().once(|data, _key| name = Some(data)).await.unwrap();
+    assert_eq!(name, Some(json!("Chain User")));
+}
+
+#[tokio::test]
+async fn test_user_chain_signs_writes_and_verifies_reads() {
+    let gun = new_gun();
+    let user = gun.user();
+    user.create("signer", "signerpass").await.unwrap();
+    let auth = user.auth("signer", "signerpass").await.unwrap();
+    let soul = format!("~{}", auth.pair.pub_key);
+
+    user.get("name").unwrap().put(json!("Signer")).await.unwrap();
+    user.get("age").unwrap().put(json!(42)).await.unwrap();
+
+    // Stored as Gun.js writes it, so relays enforcing user space accept it
+    let node = gun.root().core.graph.get(&soul).unwrap();
+    let stored = node.data.get("name").unwrap();
+    assert!(stored.as_str().unwrap().contains("\"~\":"), "signed: {}", stored);
+    let state = node.meta.get(">").and_then(|s| s.get("name"));
+    assert!(verify_user_put(&soul, "name", stored, state).await.is_ok());
+
+    // Read back unwrapped, and verified
+    let mut read = None;
+    user.get("name").unwrap().once_with_meta(|data, _key, meta| read = Some((data, meta.verified))).await.unwrap();
+    assert_eq!(read, Some((json!("Signer"), true)));
+    let mut name = None;
+    gun.get(&soul).get("name").once(|data, _key| name = Some(data)).await.unwrap();
+    assert_eq!(name, Some(json!("Signer")), "any chain into user space unwraps");
+    let mut node = None;
+    gun.get(&soul).once_with_meta(|data, _key, meta| node = Some((data, meta.verified))).await.unwrap();
+    let (node, _) = node.unwrap();
+    assert_eq!(node["name"], json!("Signer"));
+    assert_eq!(node["age"], json!(42));
+
+    // Subscribers see signed updates unwrapped too
+    let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let seen_cb = seen.clone();
+    user.get("age").unwrap().on_with_meta(move |data, _key, meta| seen_cb.lock().unwrap().push((data, meta.verified)));
+    user.get("age").unwrap().put(json!(43)).await.unwrap();
+    let seen = seen.lock().unwrap().clone();
+    assert_eq!(seen.first(), Some(&(json!(42), true)));
+    assert_eq!(seen.last(), Some(&(json!(43), true)));
+}
+
+#[tokio::test]
+async fn test_verify_user_put_binds_signature_to_key_and_state() {
+    let gun = new_gun();
+    let user = gun.user();
+    user.create("binder", "binderpass").await.unwrap();
+    let auth = user.auth("binder", "binderpass").await.unwrap();
+    let soul = format!("~{}", auth.pair.pub_key);
+    user.get("name").unwrap().put(json!("Alice")).await.unwrap();
+
+    let node = gun.root().core.graph.get(&soul).unwrap();
+    let stored = node.data.get("name").unwrap().clone();
+    let state = node.meta.get(">").and_then(|s| s.get("name")).cloned().unwrap();
+    assert!(verify_user_put(&soul, "name", &stored, Some(&state)).await.is_ok());
+
+    // The same signed value copied to another key
+    assert!(verify_user_put(&soul, "nickname", &stored, Some(&state)).await.is_err());
+    // Or replayed at a newer state
+    let newer = json!(state.as_f64().unwrap() + 1.0);
+    assert!(verify_user_put(&soul, "name", &stored, Some(&newer)).await.is_err());
+    // A bare SEA signature binds neither
+    let bare = gun::sea::sign(&json!("Alice"), &auth.pair).await.unwrap();
+    assert!(verify_user_put(&soul, "name", &bare, Some(&state)).await.is_err());
+}
+
+#[tokio::test]
+async fn test_user_chain_surfaces_broken_signature_as_unverified() {
+    let gun = new_gun();
+    let user = gun.user();
+    user.create("tampered", "tamperedpass").await.unwrap();
+    let auth = user.auth("tampered", "tamperedpass").await.unwrap();
+    let soul = format!("~{}", auth.pair.pub_key);
+    user.get("name").unwrap().put(json!("Original")).await.unwrap();
+
+    // Someone swaps the data but keeps the signature
+    let stored = gun.root().core.graph.get(&soul).unwrap().data["name"].as_str().unwrap().to_string();
+    let forged = stored.replace("Original", "Forged");
+    gun.get(&soul).put(json!({ "name": forged })).await.unwrap();
+
+    let mut read = None;
+    user.get("name").unwrap().once_with_meta(|data, _key, meta| read = Some((data, meta.verified))).await.unwrap();
+    assert_eq!(read, Some((json!("Forged"), false)));
+
+    // Unsigned values aren't verified either
+    gun.get(&soul).put(json!({ "bio": "plain" })).await.unwrap();
+    let mut bio = None;
+    user.get("bio").unwrap().once_with_meta(|data, _key, meta| bio = Some((data, meta.verified))).await.unwrap();
+    assert_eq!(bio, Some((json!("plain"), false)));
+}
+
+#[tokio::test]
+async fn test_user_chain_encrypted_field_for_another_user() {
+    let gun = new_gun();
+    let user = gun.user();
+    user.create("sender", "senderpass").await.unwrap();
+    user.create("recipient", "recipientpass").await.unwrap();
+    let recipient = user.auth("recipient", "recipientpass").await.unwrap();
+    let sender = user.auth("sender", "senderpass").await.unwrap();
+    let recipient_epub = recipient.pair.epub_key.clone().unwrap();
+
+    let note = user.get("note").unwrap();
+    note.put_encrypted_for(&recipient_epub, json!({"text": "for your eyes only"})).await.unwrap();
+
+    // Stored as ciphertext, signed by the sender
+    let mut stored = None;
+    note.once_with_meta(|data, _key, meta| stored = Some((data, meta.verified))).await.unwrap();
+    let (stored, verified) = stored.unwrap();
+    assert!(stored.as_str().unwrap().starts_with("SEA{\"ct\""), "{}", stored);
+    assert!(verified);
+
+    // The recipient reads it from the sender's space
+    let mut read = None;
+    gun.get(&format!("~{}", sender.pair.pub_key))
+        .get("note")
+        .once_decrypted(&recipient.pair, |data, _key| read = Some(data))
+        .await
+        .unwrap();
+    assert_eq!(read, Some(json!({"text": "for your eyes only"})));
+
+    // Nobody else can
+    let eve = gun::sea::pair().await.unwrap();
+    assert!(note.once_decrypted(&eve, |_, _| {}).await.is_err());
+
+    // Without a signed-in user there is nobody to encrypt from
+    user.leave();
+    assert!(gun.get("public").get("note").put_encrypted_for(&recipient_epub, json!("x")).await.is_err());
+}
@@ -0,0 +1,90 @@
+//! SEA under wasm32, run by wasm-bindgen-test
+//!
+//! CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER=wasm-bindgen-test-runner \
+//!   cargo test --target wasm32-unknown-unknown --no-default-features --features wasm --test sea_wasm_tests
+
+#![cfg(target_arch = "wasm32")]
+
+use gun::sea::{
+    certify, decrypt, decrypt_with, encrypt, encrypt_for_many, decrypt_from_envelope, pair, pair_from_seed, secret,
+    sign, verify, verify_certificate, verify_write, work, Certificants, CertifyOptions, KeyPair, Policy, WorkOptions,
+};
+use serde_json::json;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+// Encrypted by Gun.js SEA (see tests/interop/sea_interop.js)
+const GUNJS_SEA_ENCRYPTED: &str = include_str!("fixtures/gunjs_sea_encrypted.json");
+
+fn fixture_pair(keys: &serde_json::Value) -> KeyPair {
+    KeyPair {
+        pub_key: keys["pub"].as_str().unwrap().to_string(),
+        priv_key: keys["priv"].as_str().unwrap().to_string(),
+        epub_key: keys["epub"].as_str().map(str::to_string),
+        epriv_key: keys["epriv"].as_str().map(str::to_string),
+    }
+}
+
+#[wasm_bindgen_test]
+async fn test_wasm_pair_sign_verify() {
+    let keypair = pair().await.unwrap();
+    let other = pair().await.unwrap();
+    assert_ne!(keypair.pub_key, other.pub_key);
+
+    let data = json!({"message": "from wasm"});
+    let signed = sign(&data, &keypair).await.unwrap();
+    assert_eq!(verify(&signed, &keypair.pub_key).await.unwrap(), data);
+    assert!(verify(&signed, &other.pub_key).await.is_err());
+}
+
+#[wasm_bindgen_test]
+async fn test_wasm_pair_from_seed_is_version_1() {
+    let pair = pair_from_seed(b"correct horse battery staple seed").await.unwrap();
+    assert_eq!(pair.pub_key, "8pyveusSMn4DD13fzAPlKbbgnUFsYDm-Mgp1tgW0pa8.VjNuXz0Sr6XkZcBr8TalWceRtPVzTFwk9V5Kq8-i-Zk");
+}
+
+#[wasm_bindgen_test]
+async fn test_wasm_encrypt_decrypt() {
+    let alice = pair().await.unwrap();
+    let bob = pair().await.unwrap();
+
+    let own = encrypt(&json!({"secret": 42}), &alice, None).await.unwrap();
+    assert_eq!(decrypt(&own, &alice, None).await.unwrap(), json!({"secret": 42}));
+
+    let shared = secret(bob.epub_key.as_deref().unwrap(), &alice).await.unwrap();
+    assert_eq!(secret(alice.epub_key.as_deref().unwrap(), &bob).await.unwrap(), shared);
+    let to_bob = encrypt(&json!("hi bob"), &alice, bob.epub_key.as_deref()).await.unwrap();
+    assert_eq!(decrypt_with(&to_bob, &shared).await.unwrap(), json!("hi bob"));
+
+    let envelope = encrypt_for_many(&json!("hi all"), &[bob.epub_key.as_deref().unwrap()], &alice).await.unwrap();
+    assert_eq!(decrypt_from_envelope(&envelope, &bob).await.unwrap(), json!("hi all"));
+}
+
+#[wasm_bindgen_test]
+async fn test_wasm_decrypt_gunjs_encrypted_data() {
+    let fixture: serde_json::Value = serde_json::from_str(GUNJS_SEA_ENCRYPTED).unwrap();
+    let alice = fixture_pair(&fixture["alice"]);
+    for entry in fixture["own"].as_array().unwrap() {
+        assert_eq!(decrypt(&entry["sea"], &alice, None).await.unwrap(), entry["data"]);
+    }
+}
+
+#[wasm_bindgen_test]
+async fn test_wasm_work_and_certify() {
+    let sha = work(b"hello world", None, WorkOptions { name: Some("SHA-256".to_string()), ..Default::default() })
+        .await
+        .unwrap();
+    assert_eq!(sha, "uU0nuZNNPgilLlLX2n2r+sSE7+N6U4DukIj3rOLvzek");
+
+    let owner = pair().await.unwrap();
+    let writer = pair().await.unwrap();
+    let cert = certify(
+        Certificants::List(vec![writer.pub_key.clone()]),
+        Policy::String("inbox".to_string()),
+        &owner,
+        CertifyOptions::default(),
+    )
+    .await
+    .unwrap();
+    let cert = verify_certificate(&cert, &owner.pub_key).await.unwrap();
+    assert!(verify_write(&cert, &writer.pub_key, &format!("~{}", owner.pub_key), "inbox"));
+}
@@ -2,7 +2,12 @@
 //! Tests MemoryStorage, SledStorage, and LocalStorage
 
 use gun::state::Node;
-use gun::storage::{LocalStorage, MemoryStorage, SledStorage, Storage};
+use gun::storage::mock::{MockStorage, StorageOp};
+use gun::storage::{
+    export_gunjs_localstorage, import_gunjs_localstorage, migrate, parse_gunjs_graph,
+    InstrumentedStorage, LocalStorage, MemoryStorage, MigrationOptions, NamespacedStorage,
+    SledStorage, Storage,
+};
 use serde_json::json;
 
 // Helper to create a test node
@@ -311,3 +316,760 @@ async fn test_local_storage_special_characters_in_soul() {
     assert_eq!(retrieved.unwrap().data.get("name"), Some(&json!("Test")));
     let _ = std::fs::remove_dir_all(&temp_dir);
 }
+
+#[tokio::test]
+async fn test_local_storage_quarantines_corrupt_files() {
+    let temp_dir = std::env::temp_dir().join("gun_test_local_corrupt");
+    let path = temp_dir.to_str().unwrap();
+    let _ = std::fs::remove_dir_all(&temp_dir);
+
+    {
+        let storage = LocalStorage::new(path).unwrap();
+        storage
+            .put("good", &create_test_node("good", "name", &json!("Alice")))
+            .await
+            .unwrap();
+        storage
+            .put("bad", &create_test_node("bad", "name", &json!("Bob")))
+            .await
+            .unwrap();
+        assert!(storage.load_report().corrupted.is_empty());
+    }
+
+    // Simulate a torn write: truncate one node file and leave a stray temp file
+    let bad_file = temp_dir.join("bad");
+    let len = std::fs::metadata(&bad_file).unwrap().len();
+    std::fs::OpenOptions::new()
+        .write(true)
+        .open(&bad_file)
+        .unwrap()
+        .set_len(len / 2)
+        .unwrap();
+    std::fs::write(temp_dir.join("#good.0.tmp"), b"{").unwrap();
+
+    let storage = LocalStorage::new(path).unwrap();
+    let report = storage.load_report();
+    assert_eq!(report.loaded, 1);
+    assert_eq!(report.removed_temp_files, 1);
+    assert_eq!(report.corrupted.len(), 1);
+    assert_eq!(report.corrupted[0].soul, "bad");
+    assert_eq!(report.corrupted[0].quarantined_to, temp_dir.join("bad.corrupt"));
+    assert!(temp_dir.join("bad.corrupt").exists());
+    assert!(!bad_file.exists());
+    assert!(!temp_dir.join("#good.0.tmp").exists());
+
+    assert!(storage.get("good").await.unwrap().is_some());
+    assert!(storage.get("bad").await.unwrap().is_none());
+
+    // Quarantined files are left alone on the next open
+    let storage = LocalStorage::new(path).unwrap();
+    assert!(storage.load_report().corrupted.is_empty());
+    assert_eq!(storage.load_report().loaded, 1);
+    let _ = std::fs::remove_dir_all(&temp_dir);
+}
+
+#[tokio::test]
+async fn test_local_storage_loads_past_a_failed_quarantine() {
+    let temp_dir = std::env::temp_dir().join("gun_test_local_failed_quarantine");
+    let path = temp_dir.to_str().unwrap();
+    let _ = std::fs::remove_dir_all(&temp_dir);
+    std::fs::create_dir_all(&temp_dir).unwrap();
+
+    // The quarantine name is taken by a directory, so the rename fails
+    std::fs::write(temp_dir.join("bad"), b"{").unwrap();
+    std::fs::create_dir_all(temp_dir.join("bad.corrupt").join("taken")).unwrap();
+    let good = create_test_node("good", "name", &json!("Alice"));
+    std::fs::write(temp_dir.join("good"), serde_json::to_string(&good).unwrap()).unwrap();
+
+    let storage = LocalStorage::new(path).unwrap();
+    let report = storage.load_report();
+    assert_eq!(report.loaded, 1);
+    assert_eq!(report.corrupted.len(), 1);
+    assert_eq!(report.corrupted[0].soul, "bad");
+    assert_eq!(report.corrupted[0].quarantined_to, temp_dir.join("bad"));
+    assert!(report.corrupted[0].error.contains("couldn't quarantine"));
+    assert!(storage.get("good").await.unwrap().is_some());
+    let _ = std::fs::remove_dir_all(&temp_dir);
+}
+
+#[tokio::test]
+async fn test_local_storage_dotted_soul() {
+    let temp_dir = std::env::temp_dir().join("gun_test_local_dotted");
+    let path = temp_dir.to_str().unwrap();
+    let _ = std::fs::remove_dir_all(&temp_dir);
+
+    {
+        let storage = LocalStorage::new(path).unwrap();
+        let node = create_test_node("file.tmp", "name", &json!("Test"));
+        storage.put("file.tmp", &node).await.unwrap();
+    }
+
+    // A soul ending in a reserved suffix must not be mistaken for a temp file
+    let storage = LocalStorage::new(path).unwrap();
+    assert_eq!(storage.load_report().removed_temp_files, 0);
+    assert!(storage.get("file.tmp").await.unwrap().is_some());
+    let _ = std::fs::remove_dir_all(&temp_dir);
+}
+
+#[tokio::test]
+async fn test_local_storage_loads_pre_upgrade_dotted_files() {
+    let temp_dir = std::env::temp_dir().join("gun_test_local_pre_upgrade_dotted");
+    let path = temp_dir.to_str().unwrap();
+    let _ = std::fs::remove_dir_all(&temp_dir);
+    std::fs::create_dir_all(&temp_dir).unwrap();
+
+    // Written by a version that didn't escape `.` in file names
+    for soul in ["a.tmp", "b.corrupt", "c.1.tmp"] {
+        let node = create_test_node(soul, "name", &json!(soul));
+        std::fs::write(temp_dir.join(soul), serde_json::to_string(&node).unwrap()).unwrap();
+    }
+
+    let storage = LocalStorage::new(path).unwrap();
+    let report = storage.load_report();
+    assert_eq!(report.loaded, 3);
+    assert_eq!(report.removed_temp_files, 0);
+    assert!(report.corrupted.is_empty());
+    let node = storage.get("a.tmp").await.unwrap().unwrap();
+    assert_eq!(node.data.get("name"), Some(&json!("a.tmp")));
+    assert!(storage.get("b.corrupt").await.unwrap().is_some());
+    assert!(storage.get("c.1.tmp").await.unwrap().is_some(), "named like an old temp file");
+
+    // Renamed to their escaped names, so they load the same way next time
+    assert!(temp_dir.join("a%2Etmp").exists());
+    assert!(!temp_dir.join("a.tmp").exists());
+    let storage = LocalStorage::new(path).unwrap();
+    assert_eq!(storage.load_report().loaded, 3);
+    let _ = std::fs::remove_dir_all(&temp_dir);
+}
+
+// ========== List / Delete Tests ==========
+
+async fn assert_list_delete(storage: &dyn Storage) {
+    for soul in ["a", "b", "c"] {
+        storage
+            .put(soul, &create_test_node(soul, "v", &json!(soul)))
+            .await
+            .unwrap();
+    }
+
+    let mut souls = storage.list().await.unwrap();
+    souls.sort();
+    assert_eq!(souls, vec!["a", "b", "c"]);
+
+    storage.delete("b").await.unwrap();
+    assert!(!storage.has("b").await.unwrap());
+    assert!(storage.get("b").await.unwrap().is_none());
+    let mut souls = storage.list().await.unwrap();
+    souls.sort();
+    assert_eq!(souls, vec!["a", "c"]);
+
+    // Deleting a missing soul is not an error
+    storage.delete("missing").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_memory_storage_list_delete() {
+    assert_list_delete(&MemoryStorage::new()).await;
+}
+
+#[tokio::test]
+async fn test_sled_storage_list_delete() {
+    let temp_dir = std::env::temp_dir().join("gun_test_sled_list_delete");
+    let path = temp_dir.to_str().unwrap();
+    let _ = std::fs::remove_dir_all(&temp_dir);
+    assert_list_delete(&SledStorage::new(path).unwrap()).await;
+    let _ = std::fs::remove_dir_all(&temp_dir);
+}
+
+#[tokio::test]
+async fn test_local_storage_list_delete() {
+    let temp_dir = std::env::temp_dir().join("gun_test_local_list_delete");
+    let path = temp_dir.to_str().unwrap();
+    let _ = std::fs::remove_dir_all(&temp_dir);
+    {
+        let storage = LocalStorage::new(path).unwrap();
+        assert_list_delete(&storage).await;
+    }
+
+    // Deleted souls stay deleted after reopening
+    let storage = LocalStorage::new(path).unwrap();
+    let mut souls = storage.list().await.unwrap();
+    souls.sort();
+    assert_eq!(souls, vec!["a", "c"]);
+    let _ = std::fs::remove_dir_all(&temp_dir);
+}
+
+// ========== Delta Write Tests ==========
+
+async fn assert_put_delta(storage: &dyn Storage) {
+    let mut node = create_test_node("user", "name", &json!("Alice"));
+    node.data.insert("age".to_string(), json!(30));
+    storage.put("user", &node).await.unwrap();
+
+    storage
+        .put_delta("user", &[("age".to_string(), json!(31), 10.0)])
+        .await
+        .unwrap();
+    let node = storage.get("user").await.unwrap().unwrap();
+    assert_eq!(node.data.get("name"), Some(&json!("Alice")));
+    assert_eq!(node.data.get("age"), Some(&json!(31)));
+    assert_eq!(gun::state::State::is(&Some(node), "age"), Some(10.0));
+
+    // A delta older than the stored state is ignored
+    storage
+        .put_delta("user", &[("age".to_string(), json!(29), 5.0)])
+        .await
+        .unwrap();
+    let node = storage.get("user").await.unwrap().unwrap();
+    assert_eq!(node.data.get("age"), Some(&json!(31)));
+
+    // Deltas create nodes that don't exist yet
+    storage
+        .put_delta("new", &[("k".to_string(), json!("v"), 1.0)])
+        .await
+        .unwrap();
+    assert!(storage.has("new").await.unwrap());
+    let node = storage.get("new").await.unwrap().unwrap();
+    assert_eq!(node.get_soul(), Some("new".to_string()));
+    assert_eq!(node.data.get("k"), Some(&json!("v")));
+    let mut souls = storage.list().await.unwrap();
+    souls.sort();
+    assert_eq!(souls, vec!["new", "user"]);
+
+    // A whole-node put replaces earlier deltas
+    storage
+        .put("user", &create_test_node("user", "name", &json!("Bob")))
+        .await
+        .unwrap();
+    let node = storage.get("user").await.unwrap().unwrap();
+    assert_eq!(node.data.get("name"), Some(&json!("Bob")));
+    assert_eq!(node.data.get("age"), None);
+
+    storage.delete("new").await.unwrap();
+    assert!(!storage.has("new").await.unwrap());
+    assert!(storage.get("new").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_memory_storage_put_delta() {
+    assert_put_delta(&MemoryStorage::new()).await;
+}
+
+#[tokio::test]
+async fn test_sled_storage_put_delta() {
+    let temp_dir = std::env::temp_dir().join("gun_test_sled_put_delta");
+    let path = temp_dir.to_str().unwrap();
+    let _ = std::fs::remove_dir_all(&temp_dir);
+    assert_put_delta(&SledStorage::new(path).unwrap()).await;
+    let _ = std::fs::remove_dir_all(&temp_dir);
+}
+
+#[tokio::test]
+async fn test_local_storage_put_delta() {
+    let temp_dir = std::env::temp_dir().join("gun_test_local_put_delta");
+    let path = temp_dir.to_str().unwrap();
+    let _ = std::fs::remove_dir_all(&temp_dir);
+    assert_put_delta(&LocalStorage::new(path).unwrap()).await;
+    let _ = std::fs::remove_dir_all(&temp_dir);
+}
+
+#[tokio::test]
+async fn test_sled_storage_put_delta_compaction_and_persistence() {
+    let temp_dir = std::env::temp_dir().join("gun_test_sled_delta_compact");
+    let path = temp_dir.to_str().unwrap();
+    let _ = std::fs::remove_dir_all(&temp_dir);
+
+    {
+        let storage = SledStorage::new(path).unwrap();
+        // Enough single-key updates to trigger folding deltas into the node
+        for i in 0..600 {
+            let key = format!("key{}", i % 300);
+            storage
+                .put_delta("index", &[(key, json!(i), i as f64)])
+                .await
+                .unwrap();
+        }
+    }
+
+    let storage = SledStorage::new(path).unwrap();
+    let node = storage.get("index").await.unwrap().unwrap();
+    assert_eq!(node.data.len(), 300);
+    assert_eq!(node.data.get("key0"), Some(&json!(300)));
+    assert_eq!(node.data.get("key299"), Some(&json!(599)));
+    assert_eq!(storage.list().await.unwrap(), vec!["index"]);
+    let _ = std::fs::remove_dir_all(&temp_dir);
+}
+
+// ========== Migration Tests ==========
+
+#[tokio::test]
+async fn test_migrate_local_to_sled() {
+    let local_dir = std::env::temp_dir().join("gun_test_migrate_local");
+    let sled_dir = std::env::temp_dir().join("gun_test_migrate_sled");
+    let _ = std::fs::remove_dir_all(&local_dir);
+    let _ = std::fs::remove_dir_all(&sled_dir);
+
+    let from = LocalStorage::new(local_dir.to_str().unwrap()).unwrap();
+    for i in 0..1000 {
+        let soul = format!("node/{}", i);
+        let mut node = create_test_node(&soul, "value", &json!(i));
+        node.data.insert("label".to_string(), json!(format!("item {}", i)));
+        from.put(&soul, &node).await.unwrap();
+    }
+    let to = SledStorage::new(sled_dir.to_str().unwrap()).unwrap();
+
+    let progress_calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let progress_clone = progress_calls.clone();
+    let opts = MigrationOptions {
+        batch_size: 64,
+        progress: Some(std::sync::Arc::new(move |done, total| {
+            assert!(done <= total);
+            progress_clone.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        })),
+        ..Default::default()
+    };
+
+    let report = migrate(&from, &to, opts).await.unwrap();
+    assert!(report.is_complete());
+    assert_eq!(report.source_count, 1000);
+    assert_eq!(report.copied, 1000);
+    assert_eq!(report.verified, 1000);
+    assert_eq!(report.target_checksum.as_ref(), Some(&report.source_checksum));
+    assert_eq!(progress_calls.load(std::sync::atomic::Ordering::SeqCst), 16);
+
+    // Node-by-node equality
+    assert_eq!(to.list().await.unwrap().len(), 1000);
+    for i in 0..1000 {
+        let soul = format!("node/{}", i);
+        let original = from.get(&soul).await.unwrap().unwrap();
+        let copy = to.get(&soul).await.unwrap().unwrap();
+        assert_eq!(
+            serde_json::to_value(&original).unwrap(),
+            serde_json::to_value(&copy).unwrap()
+        );
+    }
+
+    drop(to);
+    let _ = std::fs::remove_dir_all(&local_dir);
+    let _ = std::fs::remove_dir_all(&sled_dir);
+}
+
+#[tokio::test]
+async fn test_migrate_dry_run() {
+    let from = MemoryStorage::new();
+    for i in 0..10 {
+        let soul = format!("soul{}", i);
+        from.put(&soul, &create_test_node(&soul, "value", &json!(i))).await.unwrap();
+    }
+    let to = MemoryStorage::new();
+
+    let opts = MigrationOptions {
+        dry_run: true,
+        ..Default::default()
+    };
+    let report = migrate(&from, &to, opts).await.unwrap();
+    assert!(report.dry_run);
+    assert_eq!(report.source_count, 10);
+    assert_eq!(report.copied, 0);
+    assert!(report.target_checksum.is_none());
+    assert!(to.list().await.unwrap().is_empty());
+
+    // Checksum of a dry run matches the checksum of a real run
+    let real = migrate(&from, &to, MigrationOptions::default()).await.unwrap();
+    assert_eq!(real.source_checksum, report.source_checksum);
+}
+
+// ========== Gun.js localStorage Tests ==========
+
+const GUNJS_FIXTURE: &str = "tests/fixtures/gunjs_localstorage_todo.json";
+
+#[tokio::test]
+async fn test_import_gunjs_localstorage() {
+    let storage = MemoryStorage::new();
+    let imported = import_gunjs_localstorage(GUNJS_FIXTURE, &storage).await.unwrap();
+    assert_eq!(imported, 6);
+
+    // Set entries stay soul references, removed entries stay tombstoned
+    let todo = storage.get("todo").await.unwrap().unwrap();
+    assert_eq!(todo.get_soul(), Some("todo".to_string()));
+    assert_eq!(
+        todo.data.get("lx8k2q1a0FqDtY3Jb2Rz"),
+        Some(&json!({"#": "lx8k2q1a0FqDtY3Jb2Rz"}))
+    );
+    assert_eq!(todo.data.get("lx8k4c9d0aHsLtQ7eUoM"), Some(&json!(null)));
+    assert_eq!(
+        gun::state::State::is(&Some(todo), "lx8k2q1a0FqDtY3Jb2Rz"),
+        Some(1718035287543.001)
+    );
+
+    let item = storage.get("lx8k2q1a0FqDtY3Jb2Rz").await.unwrap().unwrap();
+    assert_eq!(item.data.get("title"), Some(&json!("Buy milk")));
+    assert_eq!(item.data.get("done"), Some(&json!(true)));
+    assert!(!item.data.contains_key("_"));
+
+    let alias = storage.get("~@alice").await.unwrap().unwrap();
+    assert_eq!(alias.data.len(), 1);
+    let (pub_key, reference) = alias.data.iter().next().unwrap();
+    assert_eq!(reference, &json!({"#": pub_key}));
+    assert!(storage.has(pub_key).await.unwrap());
+
+    // The account's values still verify as Gun.js signed them
+    for soul in ["~@alice", pub_key.as_str()] {
+        let node = storage.get(soul).await.unwrap().unwrap();
+        for (key, value) in &node.data {
+            let state = node.meta.get(">").and_then(|states| states.get(key));
+            gun::sea::verify_user_put(soul, key, value, state)
+                .await
+                .unwrap_or_else(|e| panic!("{} of {} doesn't verify: {}", key, soul, e));
+        }
+    }
+    let account = storage.get(pub_key).await.unwrap().unwrap();
+    let alias_state = account.meta.get(">").and_then(|states| states.get("alias"));
+    let (name, verified) = gun::sea::open_user_value(pub_key, "alias", &account.data["alias"], alias_state).await;
+    assert_eq!(name, json!("alice"));
+    assert!(verified);
+}
+
+#[tokio::test]
+async fn test_import_gunjs_localstorage_keeps_newer_local_data() {
+    let storage = MemoryStorage::new();
+    storage
+        .put_delta(
+            "lx8k2q1a0FqDtY3Jb2Rz",
+            &[("title".to_string(), json!("Buy oat milk"), 1818035287542.0)],
+        )
+        .await
+        .unwrap();
+
+    import_gunjs_localstorage(GUNJS_FIXTURE, &storage).await.unwrap();
+    let item = storage.get("lx8k2q1a0FqDtY3Jb2Rz").await.unwrap().unwrap();
+    assert_eq!(item.data.get("title"), Some(&json!("Buy oat milk")));
+    assert_eq!(item.data.get("done"), Some(&json!(true)));
+}
+
+#[tokio::test]
+async fn test_export_gunjs_localstorage_round_trip() {
+    let storage = MemoryStorage::new();
+    import_gunjs_localstorage(GUNJS_FIXTURE, &storage).await.unwrap();
+
+    let export_path = std::env::temp_dir().join("gun_test_gunjs_export.json");
+    let exported = export_gunjs_localstorage(&export_path, &storage).await.unwrap();
+    assert_eq!(exported, 6);
+
+    // The export is the raw graph blob Gun.js stores under `gun/`
+    let blob: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&export_path).unwrap()).unwrap();
+    assert_eq!(blob["lx8k3b7f02kPmWnX5vCe"]["_"]["#"], json!("lx8k3b7f02kPmWnX5vCe"));
+    assert_eq!(blob["lx8k3b7f02kPmWnX5vCe"]["_"][">"]["priority"], json!(1718035301119.0));
+    assert_eq!(blob["lx8k3b7f02kPmWnX5vCe"]["priority"], json!(2));
+
+    let original = parse_gunjs_graph(&std::fs::read_to_string(GUNJS_FIXTURE).unwrap()).unwrap();
+    let round_trip = parse_gunjs_graph(&blob.to_string()).unwrap();
+    assert_eq!(round_trip.len(), original.len());
+    for node in original {
+        let soul = node.get_soul().unwrap();
+        let copy = round_trip
+            .iter()
+            .find(|n| n.get_soul().as_deref() == Some(soul.as_str()))
+            .unwrap();
+        assert_eq!(copy.data, node.data);
+        for key in node.data.keys() {
+            assert_eq!(
+                gun::state::State::is(&Some(copy.clone()), key),
+                gun::state::State::is(&Some(node.clone()), key)
+            );
+        }
+    }
+    let _ = std::fs::remove_file(&export_path);
+}
+
+#[test]
+fn test_parse_gunjs_graph_rejects_invalid_nodes() {
+    assert!(parse_gunjs_graph("[]").is_err());
+    assert!(parse_gunjs_graph(r#"{"a": "not a node"}"#).is_err());
+    assert!(parse_gunjs_graph(r##"{"a": {"_": {"#": "b"}}}"##).is_err());
+    assert_eq!(parse_gunjs_graph(r#"{"a": null}"#).unwrap().len(), 0);
+}
+
+// ========== Instrumented Storage Tests ==========
+
+#[tokio::test]
+async fn test_instrumented_storage_counters() {
+    let storage = InstrumentedStorage::new(MockStorage::new());
+    let node = create_test_node("a", "name", &json!("Alice"));
+
+    storage.put("a", &node).await.unwrap();
+    storage.put_delta("a", &[("age".to_string(), json!(30), 1.0)]).await.unwrap();
+    storage.get("a").await.unwrap();
+    storage.get("missing").await.unwrap();
+    storage.has("a").await.unwrap();
+    storage.list().await.unwrap();
+    storage.inner().fail_next(StorageOp::Delete, 1);
+    assert!(storage.delete("a").await.is_err());
+    storage.delete("a").await.unwrap();
+
+    let stats = storage.stats();
+    assert_eq!(stats.puts, 2);
+    assert_eq!(stats.gets, 2);
+    assert_eq!(stats.has, 1);
+    assert_eq!(stats.lists, 1);
+    assert_eq!(stats.deletes, 2);
+    assert_eq!(stats.errors, 1);
+    assert_eq!(stats.slow_ops, 0);
+    assert!(stats.bytes_written > serde_json::to_vec(&node).unwrap().len() as u64);
+    assert!(stats.bytes_read > 0);
+    assert_eq!(stats.put_latency.count, 2);
+    assert_eq!(stats.get_latency.count, 2);
+    assert_eq!(stats.delete_latency.count, 2);
+    assert_eq!(stats.get_latency.buckets.iter().sum::<u64>(), 2);
+}
+
+/// Collects everything a tracing subscriber writes
+#[derive(Clone, Default)]
+struct LogBuffer(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl std::io::Write for LogBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_instrumented_storage_logs_slow_operations() {
+    let logs = LogBuffer::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(move || writer.clone())
+        .with_ansi(false)
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let mock = MockStorage::new();
+    mock.set_latency(Some(std::time::Duration::from_millis(30)));
+    let storage = InstrumentedStorage::new(mock)
+        .with_slow_threshold(std::time::Duration::from_millis(10));
+
+    storage
+        .put("slow", &create_test_node("slow", "k", &json!(1)))
+        .await
+        .unwrap();
+
+    let stats = storage.stats();
+    assert_eq!(stats.slow_ops, 1);
+    assert!(stats.put_latency.max >= std::time::Duration::from_millis(30));
+    let output = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+    assert!(output.contains("Slow storage operation"), "{}", output);
+    assert!(output.contains("soul=\"slow\"") || output.contains("soul=slow"), "{}", output);
+}
+
+// ========== Namespaced Storage Tests ==========
+
+#[tokio::test]
+async fn test_namespaced_storage_isolation() {
+    let shared = std::sync::Arc::new(MemoryStorage::new());
+    let app = NamespacedStorage::new(shared.clone(), "app").unwrap();
+    let test = NamespacedStorage::new(shared.clone(), "test").unwrap();
+    assert_eq!(app.namespace(), "app");
+
+    app.put("user", &create_test_node("user", "name", &json!("Alice"))).await.unwrap();
+    test.put("user", &create_test_node("user", "name", &json!("Bob"))).await.unwrap();
+    test.put_delta("settings", &[("theme".to_string(), json!("dark"), 1.0)]).await.unwrap();
+
+    assert_eq!(app.get("user").await.unwrap().unwrap().data["name"], json!("Alice"));
+    assert_eq!(test.get("user").await.unwrap().unwrap().data["name"], json!("Bob"));
+    assert_eq!(app.list().await.unwrap(), vec!["user".to_string()]);
+    let mut test_souls = test.list().await.unwrap();
+    test_souls.sort();
+    assert_eq!(test_souls, vec!["settings".to_string(), "user".to_string()]);
+    assert!(!app.has("settings").await.unwrap());
+
+    // The prefix stays out of the nodes handed back
+    let settings = test.get("settings").await.unwrap().unwrap();
+    assert_eq!(settings.get_soul(), Some("settings".to_string()));
+    assert!(shared.has("test/settings").await.unwrap());
+
+    app.delete("user").await.unwrap();
+    assert!(app.get("user").await.unwrap().is_none());
+    assert!(test.get("user").await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn test_namespaced_storage_rejects_overlapping_namespaces() {
+    let shared = std::sync::Arc::new(MemoryStorage::new());
+    // "app/x" would store under "app/x/", which "app" would list as its own
+    assert!(matches!(NamespacedStorage::new(shared.clone(), "app/x"), Err(gun::GunError::InvalidData(_))));
+    assert!(matches!(NamespacedStorage::new(shared.clone(), ""), Err(gun::GunError::InvalidData(_))));
+
+    // A soul with a `/` stays inside its namespace
+    let app = NamespacedStorage::new(shared.clone(), "app").unwrap();
+    let other = NamespacedStorage::new(shared.clone(), "other").unwrap();
+    app.put("x/secret", &create_test_node("x/secret", "v", &json!(1))).await.unwrap();
+    assert_eq!(app.list().await.unwrap(), vec!["x/secret".to_string()]);
+    assert!(other.list().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_sled_storage_flush_every_ms() {
+    let temp_dir = std::env::temp_dir().join("gun_test_sled_flush_every_ms");
+    let _ = std::fs::remove_dir_all(&temp_dir);
+    let path = temp_dir.to_str().unwrap();
+
+    {
+        let storage = SledStorage::with_flush_every_ms(path, Some(10)).unwrap();
+        storage.put("a", &create_test_node("a", "k", &json!(1))).await.unwrap();
+        storage.put_delta("a", &[("j".to_string(), json!(2), 1.0)]).await.unwrap();
+        storage.flush().await.unwrap();
+        Storage::flush(&storage).await.unwrap();
+    }
+
+    let storage = SledStorage::new(path).unwrap();
+    let node = storage.get("a").await.unwrap().unwrap();
+    assert_eq!(node.data.get("k"), Some(&json!(1)));
+    assert_eq!(node.data.get("j"), Some(&json!(2)));
+    drop(storage);
+    let _ = std::fs::remove_dir_all(&temp_dir);
+}
+
+// ========== Capped MemoryStorage Tests ==========
+
+#[tokio::test]
+async fn test_memory_storage_lru_eviction_order() {
+    let storage = MemoryStorage::new().with_max_entries(3);
+    for soul in ["a", "b", "c"] {
+        storage.put(soul, &create_test_node(soul, "k", &json!(soul))).await.unwrap();
+    }
+
+    // Reading and delta-writing count as use: "b" is now the least recently used
+    storage.get("a").await.unwrap();
+    storage.put_delta("c", &[("j".to_string(), json!(1), 1.0)]).await.unwrap();
+    storage.put("d", &create_test_node("d", "k", &json!("d"))).await.unwrap();
+
+    assert!(!storage.has("b").await.unwrap());
+    for soul in ["a", "c", "d"] {
+        assert!(storage.has(soul).await.unwrap(), "{} should be kept", soul);
+    }
+    assert_eq!(storage.len(), 3);
+    assert_eq!(storage.evictions(), 1);
+
+    storage.put("e", &create_test_node("e", "k", &json!("e"))).await.unwrap();
+    assert!(!storage.has("a").await.unwrap());
+    assert_eq!(storage.evictions(), 2);
+}
+
+#[tokio::test]
+async fn test_memory_storage_byte_cap() {
+    let node = create_test_node("n0", "text", &json!("x".repeat(1000)));
+    let node_size = "n0".len() + serde_json::to_vec(&node).unwrap().len();
+    let storage = MemoryStorage::new().with_max_bytes(node_size * 5 / 2);
+
+    for i in 0..5 {
+        let soul = format!("n{}", i);
+        storage.put(&soul, &create_test_node(&soul, "text", &json!("x".repeat(1000)))).await.unwrap();
+        assert!(storage.size_bytes() <= node_size * 5 / 2);
+    }
+    assert_eq!(storage.len(), 2);
+    assert!(storage.has("n3").await.unwrap());
+    assert!(storage.has("n4").await.unwrap());
+
+    // Deleting frees its bytes
+    storage.delete("n3").await.unwrap();
+    assert!(storage.size_bytes() <= node_size + 1);
+
+    // A node bigger than the whole cap isn't kept
+    let huge = create_test_node("huge", "text", &json!("x".repeat(10_000)));
+    storage.put("huge", &huge).await.unwrap();
+    assert!(!storage.has("huge").await.unwrap());
+    assert!(storage.size_bytes() <= node_size * 5 / 2);
+}
+
+#[tokio::test]
+async fn test_memory_storage_unbounded_by_default() {
+    let storage = MemoryStorage::new();
+    for i in 0..1000 {
+        let soul = format!("n{}", i);
+        storage.put(&soul, &create_test_node(&soul, "k", &json!(i))).await.unwrap();
+    }
+    assert_eq!(storage.len(), 1000);
+    assert_eq!(storage.evictions(), 0);
+}
+
+
+// ========== Compressed Storage Tests ==========
+
+#[cfg(feature = "compression-deflate")]
+#[tokio::test]
+async fn test_compressed_storage_round_trip() {
+    use gun::storage::{CompressedStorage, Compression, COMPRESSED_META_KEY};
+
+    let temp_dir = std::env::temp_dir().join("gun_test_compressed_round_trip");
+    let _ = std::fs::remove_dir_all(&temp_dir);
+    let sled = std::sync::Arc::new(SledStorage::new(temp_dir.to_str().unwrap()).unwrap());
+    let storage = CompressedStorage::new(sled.clone(), Compression::Deflate).with_threshold(256);
+
+    let large = create_test_node("doc", "text", &json!("lorem ipsum dolor sit amet ".repeat(200)));
+    let small = create_test_node("tiny", "k", &json!("v"));
+    storage.put("doc", &large).await.unwrap();
+    storage.put("tiny", &small).await.unwrap();
+
+    // The large node is stored as a much smaller envelope, the small one untouched
+    let raw = sled.get("doc").await.unwrap().unwrap();
+    assert!(raw.data.is_empty());
+    let encoded = raw.meta[COMPRESSED_META_KEY].as_str().unwrap();
+    assert!(encoded.len() * 5 < serde_json::to_vec(&large).unwrap().len());
+    let raw_small = sled.get("tiny").await.unwrap().unwrap();
+    assert!(!raw_small.meta.contains_key(COMPRESSED_META_KEY));
+
+    let loaded = storage.get("doc").await.unwrap().unwrap();
+    assert_eq!(loaded.data, large.data);
+    assert_eq!(loaded.get_soul(), Some("doc".to_string()));
+    assert_eq!(storage.get("tiny").await.unwrap().unwrap().data, small.data);
+
+    // Deltas are merged into the decompressed node
+    storage.put_delta("doc", &[("title".to_string(), json!("Notes"), 1.0)]).await.unwrap();
+    let loaded = storage.get("doc").await.unwrap().unwrap();
+    assert_eq!(loaded.data["title"], json!("Notes"));
+    assert_eq!(loaded.data["text"], large.data["text"]);
+    assert!(sled.get("doc").await.unwrap().unwrap().meta.contains_key(COMPRESSED_META_KEY));
+
+    drop(storage);
+    drop(sled);
+    let _ = std::fs::remove_dir_all(&temp_dir);
+}
+
+#[cfg(feature = "compression-deflate")]
+#[tokio::test]
+async fn test_compressed_storage_reads_uncompressed_store() {
+    use gun::storage::{CompressedStorage, Compression};
+
+    let temp_dir = std::env::temp_dir().join("gun_test_compressed_legacy");
+    let _ = std::fs::remove_dir_all(&temp_dir);
+    let path = temp_dir.to_str().unwrap();
+    let large = create_test_node("doc", "text", &json!("x".repeat(5000)));
+
+    // Written before compression was enabled
+    {
+        let storage = LocalStorage::new(path).unwrap();
+        storage.put("doc", &large).await.unwrap();
+        storage.put_delta("note", &[("k".to_string(), json!("v"), 1.0)]).await.unwrap();
+    }
+
+    let storage = CompressedStorage::new(LocalStorage::new(path).unwrap(), Compression::Deflate);
+    assert_eq!(storage.get("doc").await.unwrap().unwrap().data, large.data);
+    assert_eq!(storage.get("note").await.unwrap().unwrap().data["k"], json!("v"));
+    let mut souls = storage.list().await.unwrap();
+    souls.sort();
+    assert_eq!(souls, vec!["doc".to_string(), "note".to_string()]);
+
+    // Rewriting a legacy node compresses it
+    storage.put("doc", &large).await.unwrap();
+    assert!(storage.inner().get("doc").await.unwrap().unwrap().data.is_empty());
+    assert_eq!(storage.get("doc").await.unwrap().unwrap().data, large.data);
+
+    drop(storage);
+    let _ = std::fs::remove_dir_all(&temp_dir);
+}
@@ -133,6 +133,14 @@ fn test_valid_soul_function() {
     // Regular object (not soul reference)
     let obj = json!({"key": "value"});
     assert_eq!(valid_soul(&obj), None);
+
+    // User space souls must be ~pub, ~pub/path or ~@alias
+    let pub_key = "8pyveusSMn4DD13fzAPlKbbgnUFsYDm-Mgp1tgW0pa8.VjNuXz0Sr6XkZcBr8TalWceRtPVzTFwk9V5Kq8-i-Zk";
+    assert!(valid_soul(&json!(format!("~{}", pub_key))).is_some());
+    assert!(valid_soul(&json!({"#": format!("~{}/inbox", pub_key)})).is_some());
+    assert_eq!(valid_soul(&json!("~@alice")), Some("~@alice".to_string()));
+    assert_eq!(valid_soul(&json!("~alice.pub")), None);
+    assert_eq!(valid_soul(&json!({"#": "~@"})), None);
 }
 
 #[test]
@@ -1,11 +1,47 @@
+use chia_bls::SecretKey;
 use futures::future;
 use gun::core::GunCore;
-use gun::dam::Mesh;
-use gun::webrtc::{WebRTCManager, WebRTCOptions, WebRTCPeer};
+use gun::dam::{Mesh, Peer, RTC_SIGNAL_EVENT};
+use gun::webrtc::{
+    PeerEvent, Transport, WebRTCManager, WebRTCOptions, WebRTCPeer, WEBRTC_CONNECTED_EVENT, WEBRTC_DISCONNECTED_EVENT,
+};
 use gun::{Gun, GunOptions};
 use serde_json::json;
 use std::sync::Arc;
+use tokio::sync::mpsc;
 use tokio::time::{sleep, timeout, Duration};
+use webrtc::peer_connection::peer_connection_state::RTCPeerConnectionState;
+
+fn test_key(seed: u8) -> SecretKey {
+    SecretKey::from_seed(&[seed; 32])
+}
+
+// Mesh on `core` with its own key pair
+fn test_mesh(core: &Arc<GunCore>, seed: u8) -> Arc<Mesh> {
+    let key = test_key(seed);
+    Arc::new(Mesh::new(core.clone(), key.clone(), key.public_key(), None))
+}
+
+// Connect two meshes with in-memory channels, delivering each frame to the other side's hear()
+async fn link(a: &Arc<Mesh>, b: &Arc<Mesh>) {
+    let mut ends = Vec::new();
+    for (mesh, url) in [(a, "ws://mock/b"), (b, "ws://mock/a")] {
+        let (tx, rx) = mpsc::unbounded_channel::<String>();
+        let mut peer = Peer::new(url.to_string());
+        peer.set_sender(tx);
+        mesh.hi(peer.clone()).await.unwrap();
+        ends.push((peer, rx));
+    }
+    let (peer_a, rx_to_a) = ends.pop().unwrap(); // b's view of a
+    let (peer_b, rx_to_b) = ends.pop().unwrap(); // a's view of b
+    for (to, from, mut rx) in [(b.clone(), peer_a, rx_to_b), (a.clone(), peer_b, rx_to_a)] {
+        tokio::spawn(async move {
+            while let Some(frame) = rx.recv().await {
+                let _ = to.hear(&frame, Some(&from)).await;
+            }
+        });
+    }
+}
 
 /// Test WebRTCOptions default configuration
 #[test]
@@ -24,6 +60,8 @@ fn test_webrtc_options_default() {
         .contains(&"stun:stun.cloudflare.com:3478".to_string())));
     assert_eq!(options.data_channel.ordered, Some(false));
     assert_eq!(options.data_channel.max_retransmits, Some(2));
+    assert_eq!(options.room, None);
+    assert_eq!(options.announce_interval, Duration::from_secs(30));
 }
 
 /// Test WebRTCOptions cloning
@@ -138,7 +176,7 @@ async fn test_webrtc_peer_close() {
 #[tokio::test]
 async fn test_webrtc_manager_creation() {
     let core = Arc::new(GunCore::new());
-    let mesh = Arc::new(Mesh::new(core.clone()));
+    let mesh = test_mesh(&core, 0);
     let options = WebRTCOptions::default();
 
     let manager = WebRTCManager::new(core, mesh, options);
@@ -151,7 +189,7 @@ async fn test_webrtc_manager_creation() {
 #[tokio::test]
 async fn test_webrtc_manager_handle_offer() {
     let core = Arc::new(GunCore::new());
-    let mesh = Arc::new(Mesh::new(core.clone()));
+    let mesh = test_mesh(&core, 0);
     let options = WebRTCOptions::default();
 
     let manager = WebRTCManager::new(core, mesh, options);
@@ -187,7 +225,7 @@ async fn test_webrtc_manager_handle_offer() {
 #[tokio::test]
 async fn test_webrtc_manager_handle_answer() {
     let core = Arc::new(GunCore::new());
-    let mesh = Arc::new(Mesh::new(core.clone()));
+    let mesh = test_mesh(&core, 0);
     let options = WebRTCOptions::default();
 
     let manager = WebRTCManager::new(core, mesh, options);
@@ -252,7 +290,7 @@ async fn test_webrtc_manager_handle_answer() {
 #[tokio::test]
 async fn test_webrtc_manager_handle_ice_candidate() {
     let core = Arc::new(GunCore::new());
-    let mesh = Arc::new(Mesh::new(core.clone()));
+    let mesh = test_mesh(&core, 0);
     let options = WebRTCOptions::default();
 
     let manager = WebRTCManager::new(core, mesh, options);
@@ -309,7 +347,7 @@ async fn test_webrtc_manager_handle_ice_candidate() {
 #[tokio::test]
 async fn test_webrtc_manager_handle_peer_discovery() {
     let core = Arc::new(GunCore::new());
-    let mesh = Arc::new(Mesh::new(core.clone()));
+    let mesh = test_mesh(&core, 0);
     let options = WebRTCOptions {
         max_connections: 10, // Set a reasonable limit for testing
         ..Default::default()
@@ -344,7 +382,7 @@ async fn test_webrtc_manager_handle_peer_discovery() {
 #[tokio::test]
 async fn test_webrtc_manager_ignore_own_messages() {
     let core = Arc::new(GunCore::new());
-    let mesh = Arc::new(Mesh::new(core.clone()));
+    let mesh = test_mesh(&core, 0);
     let options = WebRTCOptions::default();
 
     let manager = WebRTCManager::new(core, mesh, options);
@@ -372,7 +410,7 @@ async fn test_webrtc_manager_ignore_own_messages() {
 #[tokio::test]
 async fn test_webrtc_manager_handle_invalid_message() {
     let core = Arc::new(GunCore::new());
-    let mesh = Arc::new(Mesh::new(core.clone()));
+    let mesh = test_mesh(&core, 0);
     let options = WebRTCOptions::default();
 
     let manager = WebRTCManager::new(core, mesh, options);
@@ -406,7 +444,7 @@ async fn test_webrtc_manager_handle_invalid_message() {
 #[tokio::test]
 async fn test_webrtc_manager_connection_limit() {
     let core = Arc::new(GunCore::new());
-    let mesh = Arc::new(Mesh::new(core.clone()));
+    let mesh = test_mesh(&core, 0);
     let options = WebRTCOptions {
         max_connections: 2, // Set low limit for testing
         ..Default::default()
@@ -444,7 +482,7 @@ async fn test_gun_with_webrtc_enabled() {
     let mut options = GunOptions::default();
     options.webrtc.enabled = true;
 
-    let gun = Gun::with_options(options).await;
+    let gun = Gun::with_options(test_key(0), test_key(0).public_key(), options).await;
     assert!(
         gun.is_ok(),
         "Failed to create Gun with WebRTC: {:?}",
@@ -463,7 +501,7 @@ async fn test_gun_with_webrtc_disabled() {
     let mut options = GunOptions::default();
     options.webrtc.enabled = false;
 
-    let gun = Gun::with_options(options).await;
+    let gun = Gun::with_options(test_key(0), test_key(0).public_key(), options).await;
     assert!(
         gun.is_ok(),
         "Failed to create Gun without WebRTC: {:?}",
@@ -475,7 +513,7 @@ async fn test_gun_with_webrtc_disabled() {
 #[tokio::test]
 async fn test_webrtc_message_forwarding() {
     let core = Arc::new(GunCore::new());
-    let mesh = Arc::new(Mesh::new(core.clone()));
+    let mesh = test_mesh(&core, 0);
     let options = WebRTCOptions::default();
 
     let manager = WebRTCManager::new(core, mesh.clone(), options);
@@ -516,7 +554,7 @@ async fn test_webrtc_custom_ice_servers() {
     assert_eq!(options.ice_servers.len(), 3);
 
     let core = Arc::new(GunCore::new());
-    let mesh = Arc::new(Mesh::new(core.clone()));
+    let mesh = test_mesh(&core, 0);
     let manager = WebRTCManager::new(core, mesh, options);
 
     // Manager should be created with custom ICE servers
@@ -586,7 +624,7 @@ async fn test_webrtc_custom_data_channel_config() {
 #[tokio::test]
 async fn test_webrtc_invalid_sdp_handling() {
     let core = Arc::new(GunCore::new());
-    let mesh = Arc::new(Mesh::new(core.clone()));
+    let mesh = test_mesh(&core, 0);
     let options = WebRTCOptions::default();
 
     let manager = WebRTCManager::new(core, mesh, options);
@@ -627,3 +665,757 @@ async fn test_webrtc_connection_timeout() {
     .await
     .expect("WebRTC operations should complete within timeout");
 }
+
+// Host candidates only, so no STUN server is needed between local peers
+fn local_options() -> WebRTCOptions {
+    WebRTCOptions {
+        ice_servers: vec![],
+        ..Default::default()
+    }
+}
+
+// Link the meshes, give each a signaling manager and have A send B an offer
+async fn start_managers(
+    (core_a, mesh_a): (Arc<GunCore>, Arc<Mesh>),
+    (core_b, mesh_b): (Arc<GunCore>, Arc<Mesh>),
+    options: WebRTCOptions,
+) -> (Arc<WebRTCManager>, Arc<WebRTCManager>) {
+    link(&mesh_a, &mesh_b).await;
+    timeout(Duration::from_secs(5), async {
+        while mesh_a.peer_id_for_pid(&mesh_b.pid).is_none() || mesh_b.peer_id_for_pid(&mesh_a.pid).is_none() {
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("pids exchanged in the ? handshake");
+
+    let manager_a = Arc::new(WebRTCManager::new(core_a, mesh_a, options.clone()));
+    let manager_b = Arc::new(WebRTCManager::new(core_b, mesh_b.clone(), options));
+    WebRTCManager::start_signaling(&manager_a);
+    WebRTCManager::start_signaling(&manager_b);
+
+    // A hears of B and sends it an offer; everything after that is signaling
+    manager_a
+        .handle_rtc_message(&json!({ "ok": { "rtc": { "id": mesh_b.pid } } }))
+        .await
+        .unwrap();
+    (manager_a, manager_b)
+}
+
+/// Test two managers connecting, with offer, answer and trickled ICE
+/// candidates all going through linked meshes
+#[tokio::test]
+async fn test_webrtc_managers_connect_over_mesh_signaling() {
+    let (core_a, core_b) = (Arc::new(GunCore::new()), Arc::new(GunCore::new()));
+    let (mesh_a, mesh_b) = (test_mesh(&core_a, 1), test_mesh(&core_b, 2));
+    let (manager_a, manager_b) = start_managers((core_a, mesh_a.clone()), (core_b, mesh_b.clone()), local_options()).await;
+
+    let connected = timeout(Duration::from_secs(20), async {
+        loop {
+            let states = (manager_a.peer_state(&mesh_b.pid).await, manager_b.peer_state(&mesh_a.pid).await);
+            if states == (Some(RTCPeerConnectionState::Connected), Some(RTCPeerConnectionState::Connected)) {
+                return;
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await;
+    assert!(connected.is_ok(), "WebRTC peers should connect with trickled candidates");
+
+    manager_a.close_all().await;
+    manager_b.close_all().await;
+}
+
+/// Test DAM messages crossing the data channel both ways, the answerer
+/// using the channel the offerer created, with the connected and
+/// disconnected events around it
+#[tokio::test]
+async fn test_webrtc_data_channel_carries_messages_both_ways() {
+    // Unsigned, like browsers, so the test can write frames itself
+    let unsigned_mesh = |core: &Arc<GunCore>, seed: u8| {
+        let key = test_key(seed);
+        Arc::new(Mesh::new(core.clone(), key.clone(), key.public_key(), None).with_require_signatures(false))
+    };
+    let (core_a, core_b) = (Arc::new(GunCore::new()), Arc::new(GunCore::new()));
+    let (mesh_a, mesh_b) = (unsigned_mesh(&core_a, 3), unsigned_mesh(&core_b, 4));
+    let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+    for event in [WEBRTC_CONNECTED_EVENT, WEBRTC_DISCONNECTED_EVENT] {
+        let events_tx = events_tx.clone();
+        core_a.events.on(
+            &format!("{}:{}", event, mesh_b.pid),
+            Box::new(move |e: &gun::events::Event| {
+                let _ = events_tx.send((e.event_type.clone(), e.data.clone()));
+            }),
+        );
+    }
+    let (manager_a, manager_b) =
+        start_managers((core_a.clone(), mesh_a.clone()), (core_b.clone(), mesh_b.clone()), local_options()).await;
+
+    let open = timeout(Duration::from_secs(20), async {
+        while !(manager_a.is_open(&mesh_b.pid).await && manager_b.is_open(&mesh_a.pid).await) {
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await;
+    assert!(open.is_ok(), "both ends of the data channel should open");
+    let (event, data) = timeout(Duration::from_secs(5), events_rx.recv()).await.unwrap().unwrap();
+    assert_eq!(event, format!("webrtc_connected:{}", mesh_b.pid));
+    assert_eq!(data["pid"], json!(mesh_b.pid));
+
+    // Cut the mesh link, so nothing can take the WebSocket fallback
+    let link_on_a = mesh_a.peer_id_for_pid(&mesh_b.pid).unwrap();
+    let link_on_b = mesh_b.peer_id_for_pid(&mesh_a.pid).unwrap();
+    mesh_a.bye(&link_on_a).await.unwrap();
+    mesh_b.bye(&link_on_b).await.unwrap();
+
+    let put = |id: &str, soul: &str| {
+        json!({ "#": id, "put": { soul: { "_": { "#": soul, ">": { "from": 1 } }, "from": soul } } }).to_string()
+    };
+    manager_a.send_message(&mesh_b.pid, &put("rtc-1", "from-offerer")).await.unwrap();
+    manager_b.send_message(&mesh_a.pid, &put("rtc-2", "from-answerer")).await.unwrap();
+    let delivered = timeout(Duration::from_secs(5), async {
+        while core_b.graph.get("from-offerer").is_none() || core_a.graph.get("from-answerer").is_none() {
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await;
+    assert!(delivered.is_ok(), "messages should arrive over the data channel");
+
+    manager_a.close_all().await;
+    let (event, data) = timeout(Duration::from_secs(10), events_rx.recv()).await.unwrap().unwrap();
+    assert_eq!(event, format!("webrtc_disconnected:{}", mesh_b.pid));
+    assert!(data["reason"].is_string());
+    manager_b.close_all().await;
+}
+
+/// Test two Gun instances behind one relay finding each other and opening
+/// a data channel with signaling relayed between them, then a put crossing
+/// the channel after the relay is gone
+#[tokio::test]
+async fn test_gun_peers_connect_over_webrtc_through_relay() {
+    let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let relay_key = test_key(5);
+    let mut relay = Gun::with_options(
+        relay_key.clone(),
+        relay_key.public_key(),
+        GunOptions {
+            super_peer: true,
+            port: Some(port),
+            localStorage: false,
+            require_signatures: false,
+            webrtc: WebRTCOptions {
+                enabled: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    sleep(Duration::from_millis(200)).await; // let the listener bind
+
+    let mut peers = Vec::new();
+    for seed in [6, 7] {
+        let key = test_key(seed);
+        let gun = Gun::with_options(
+            key.clone(),
+            key.public_key(),
+            GunOptions {
+                localStorage: false,
+                radisk: false,
+                require_signatures: false,
+                webrtc: WebRTCOptions {
+                    ice_servers: vec![],
+                    ..Default::default()
+                },
+                ..GunOptions::with_relay(&format!("ws://127.0.0.1:{}/gun", port))
+            },
+        )
+        .await
+        .unwrap();
+        assert!(gun.wait_for_connection(5000).await);
+        peers.push(gun);
+    }
+    let (a, b) = (peers[0].webrtc().unwrap().clone(), peers[1].webrtc().unwrap().clone());
+    let (peer_events_tx, mut peer_events_rx) = mpsc::unbounded_channel();
+    peers[0].on_peer_event(move |event| {
+        let _ = peer_events_tx.send(event);
+    });
+
+    let open = timeout(Duration::from_secs(20), async {
+        while !(a.is_open(b.pid()).await && b.is_open(a.pid()).await) {
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await;
+    assert!(open.is_ok(), "peers behind a relay should open a data channel");
+    let event = timeout(Duration::from_secs(5), peer_events_rx.recv()).await.unwrap().unwrap();
+    assert_eq!(event, PeerEvent::WebRTCConnected { pid: b.pid().to_string() });
+    assert_eq!(peers[0].transport_for(b.pid()).await, Transport::WebRTC);
+    assert_eq!(peers[0].transport_for("unknown-pid").await, Transport::Relay);
+
+    // Without the relay, the data channel is the only way across
+    relay.shutdown().await.unwrap();
+    let (data_tx, mut data_rx) = mpsc::unbounded_channel();
+    peers[1].get("over-rtc").get("v").on(move |data, _key| {
+        let _ = data_tx.send(data);
+    });
+    let put = json!({ "#": "rtc-put", "put": { "over-rtc": { "_": { "#": "over-rtc", ">": { "v": 1 } }, "v": "hello" } } });
+    a.send_message(b.pid(), &put.to_string()).await.unwrap();
+    let data = timeout(Duration::from_secs(5), data_rx.recv()).await.unwrap().unwrap();
+    assert_eq!(data, json!("hello"));
+
+    for mut gun in peers {
+        gun.shutdown().await.unwrap();
+    }
+}
+
+/// Test puts made with the Gun API crossing an open data channel by
+/// themselves, once the relay the two peers met through is gone
+#[tokio::test]
+async fn test_gun_puts_prefer_webrtc_over_the_relay() {
+    let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let relay_key = test_key(14);
+    let mut relay = Gun::with_options(
+        relay_key.clone(),
+        relay_key.public_key(),
+        GunOptions {
+            super_peer: true,
+            port: Some(port),
+            localStorage: false,
+            require_signatures: false,
+            webrtc: WebRTCOptions {
+                enabled: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    sleep(Duration::from_millis(200)).await; // let the listener bind
+
+    let mut peers = Vec::new();
+    for seed in [15, 16] {
+        let key = test_key(seed);
+        let gun = Gun::with_options(
+            key.clone(),
+            key.public_key(),
+            GunOptions {
+                localStorage: false,
+                radisk: false,
+                require_signatures: false,
+                webrtc: local_options(),
+                ..GunOptions::with_relay(&format!("ws://127.0.0.1:{}/gun", port))
+            },
+        )
+        .await
+        .unwrap();
+        assert!(gun.wait_for_connection(5000).await);
+        peers.push(gun);
+    }
+    let (a, b) = (peers[0].webrtc().unwrap().clone(), peers[1].webrtc().unwrap().clone());
+    let open = timeout(Duration::from_secs(20), async {
+        while !(a.is_open(b.pid()).await && b.is_open(a.pid()).await) {
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await;
+    assert!(open.is_ok(), "peers behind a relay should open a data channel");
+
+    relay.shutdown().await.unwrap();
+    let (data_tx, mut data_rx) = mpsc::unbounded_channel();
+    peers[1].get("direct").get("v").on(move |data, _key| {
+        let _ = data_tx.send(data);
+    });
+    peers[0].get("direct").get("v").put(json!("over-rtc")).await.unwrap();
+    let data = timeout(Duration::from_secs(5), data_rx.recv()).await.unwrap().unwrap();
+    assert_eq!(data, json!("over-rtc"));
+    assert!(peers[0].stats().mesh.transport_sent >= 1);
+
+    for mut gun in peers {
+        gun.shutdown().await.unwrap();
+    }
+}
+
+/// Test TURN servers added in code and from the environment, and the ICE
+/// server checks made before a connection is created
+#[tokio::test]
+async fn test_webrtc_turn_options_and_ice_server_checks() {
+    use webrtc::peer_connection::policy::ice_transport_policy::RTCIceTransportPolicy;
+
+    let options = WebRTCOptions::default()
+        .with_turn("turn:turn.example.com:3478", "user", "secret")
+        .with_turn("turns:turn.example.com:5349?transport=tcp", "user", "secret");
+    assert_eq!(options.ice_servers.len(), 4);
+    assert_eq!(options.ice_servers[3].urls, vec!["turns:turn.example.com:5349?transport=tcp".to_string()]);
+    assert_eq!(options.ice_servers[3].username, "user");
+    assert_eq!(options.ice_servers[3].credential, "secret");
+    assert_eq!(options.ice_transport_policy, RTCIceTransportPolicy::All);
+
+    let added = std::env::var(gun::webrtc::TURN_URL_ENV).is_ok_and(|url| !url.is_empty());
+    let from_env = WebRTCOptions::default().with_turn_from_env();
+    assert_eq!(from_env.ice_servers.len(), 2 + added as usize);
+
+    // Rejected before any connection is attempted
+    let invalid = [
+        WebRTCOptions::default().with_turn("http://turn.example.com", "user", "secret"),
+        WebRTCOptions::default().with_turn("turn:turn.example.com:3478", "", ""),
+        WebRTCOptions::default().with_ice_transport_policy(RTCIceTransportPolicy::Relay),
+    ];
+    for options in invalid {
+        match WebRTCPeer::new("peer".to_string(), &options).await {
+            Err(gun::GunError::WebRTC(_)) => {}
+            other => panic!("expected an ICE server error, got {:?}", other.map(|(peer, _)| peer)),
+        }
+    }
+}
+
+/// Test a relay-only connection through the TURN server in GUN_TURN_URL,
+/// GUN_TURN_USERNAME and GUN_TURN_CREDENTIAL; skipped without one
+#[tokio::test]
+async fn test_webrtc_relay_only_connection_through_turn() {
+    use webrtc::peer_connection::policy::ice_transport_policy::RTCIceTransportPolicy;
+
+    let options = WebRTCOptions {
+        ice_servers: vec![],
+        ..Default::default()
+    }
+    .with_turn_from_env()
+    .with_ice_transport_policy(RTCIceTransportPolicy::Relay);
+    if options.ice_servers.is_empty() {
+        eprintln!("{} not set, skipping", gun::webrtc::TURN_URL_ENV);
+        return;
+    }
+
+    let (core_a, core_b) = (Arc::new(GunCore::new()), Arc::new(GunCore::new()));
+    let (mesh_a, mesh_b) = (test_mesh(&core_a, 8), test_mesh(&core_b, 9));
+    let (manager_a, manager_b) = start_managers((core_a, mesh_a.clone()), (core_b, mesh_b.clone()), options).await;
+
+    let connected = timeout(Duration::from_secs(30), async {
+        loop {
+            let states = (manager_a.peer_state(&mesh_b.pid).await, manager_b.peer_state(&mesh_a.pid).await);
+            if states == (Some(RTCPeerConnectionState::Connected), Some(RTCPeerConnectionState::Connected)) {
+                return;
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await;
+    assert!(connected.is_ok(), "peers should connect through the TURN relay");
+
+    manager_a.close_all().await;
+    manager_b.close_all().await;
+}
+
+/// Test a connection closed on one side being dropped on the other, which
+/// then connects again
+#[tokio::test]
+async fn test_webrtc_lost_connection_is_pruned_and_reconnects() {
+    let (core_a, core_b) = (Arc::new(GunCore::new()), Arc::new(GunCore::new()));
+    let (mesh_a, mesh_b) = (test_mesh(&core_a, 10), test_mesh(&core_b, 11));
+    let (lost_tx, mut lost_rx) = mpsc::unbounded_channel();
+    core_b.events.on(
+        gun::webrtc::WEBRTC_PEER_LOST_EVENT,
+        Box::new(move |event: &gun::events::Event| {
+            let _ = lost_tx.send(event.data.clone());
+        }),
+    );
+    let options = WebRTCOptions {
+        disconnect_grace: Duration::from_millis(500),
+        ..local_options()
+    };
+    let (manager_a, manager_b) =
+        start_managers((core_a, mesh_a.clone()), (core_b, mesh_b.clone()), options).await;
+
+    let both_open = || async {
+        timeout(Duration::from_secs(20), async {
+            while !(manager_a.is_open(&mesh_b.pid).await && manager_b.is_open(&mesh_a.pid).await) {
+                sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+    };
+    assert!(both_open().await.is_ok(), "peers should connect");
+    assert_eq!(manager_b.peer_states().await.len(), 1);
+
+    // A drops its connection; B notices, frees the slot and says so
+    manager_a.close_all().await;
+    let lost = timeout(Duration::from_secs(30), lost_rx.recv()).await.unwrap().unwrap();
+    assert_eq!(lost["pid"], json!(mesh_a.pid));
+
+    // B announces itself again and the two reconnect
+    assert!(both_open().await.is_ok(), "peers should reconnect after the loss");
+    assert_eq!(manager_b.peer_states().await.len(), 1);
+
+    manager_a.close_all().await;
+    manager_b.close_all().await;
+}
+
+/// Test the data of WebRTC peer events read as `PeerEvent`s
+#[test]
+fn test_webrtc_peer_event_from_event_data() {
+    assert_eq!(
+        PeerEvent::from_event_data(&json!({ "pid": "p1", "state": "connected" })),
+        Some(PeerEvent::WebRTCConnected { pid: "p1".to_string() })
+    );
+    assert_eq!(
+        PeerEvent::from_event_data(&json!({ "pid": "p1", "state": "failed", "reason": "failed" })),
+        Some(PeerEvent::WebRTCFailed { pid: "p1".to_string(), reason: "failed".to_string() })
+    );
+    assert_eq!(
+        PeerEvent::from_event_data(&json!({ "pid": "p1", "state": "relay_only", "reason": "closed" })),
+        Some(PeerEvent::RelayOnly { pid: "p1".to_string() })
+    );
+    assert_eq!(PeerEvent::from_event_data(&json!({ "pid": "p1", "state": "other" })), None);
+    assert_eq!(PeerEvent::from_event_data(&json!({ "state": "connected" })), None);
+}
+
+/// Test offers to a peer that never answers timing out, being retried and
+/// then given up on for the cooldown
+#[tokio::test]
+async fn test_webrtc_unanswered_offers_expire_and_stop_after_retries() {
+    let (core_a, core_b) = (Arc::new(GunCore::new()), Arc::new(GunCore::new()));
+    let (mesh_a, mesh_b) = (test_mesh(&core_a, 17), test_mesh(&core_b, 18));
+    link(&mesh_a, &mesh_b).await;
+    timeout(Duration::from_secs(5), async {
+        while mesh_a.peer_id_for_pid(&mesh_b.pid).is_none() {
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("pids exchanged in the ? handshake");
+
+    // B has no manager: offers reach it and are never answered
+    let (offers_tx, mut offers_rx) = mpsc::unbounded_channel();
+    core_b.events.on(
+        gun::dam::RTC_SIGNAL_EVENT,
+        Box::new(move |event: &gun::events::Event| {
+            if event.data["ok"]["rtc"].get("offer").is_some() {
+                let _ = offers_tx.send(());
+            }
+        }),
+    );
+    let (failed_tx, mut failed_rx) = mpsc::unbounded_channel();
+    core_a.events.on(
+        gun::webrtc::WEBRTC_PEER_EVENT,
+        Box::new(move |event: &gun::events::Event| {
+            let _ = failed_tx.send(event.data.clone());
+        }),
+    );
+    let options = WebRTCOptions {
+        offer_timeout: Duration::from_millis(300),
+        offer_attempts: 2,
+        ..local_options()
+    };
+    let manager_a = Arc::new(WebRTCManager::new(core_a, mesh_a, options));
+    WebRTCManager::start_signaling(&manager_a);
+
+    manager_a.connect(&mesh_b.pid).await.unwrap();
+    assert_eq!(manager_a.peer_states().await.len(), 1);
+    for _ in 0..2 {
+        timeout(Duration::from_secs(5), offers_rx.recv()).await.unwrap().unwrap();
+        let failed = timeout(Duration::from_secs(5), failed_rx.recv()).await.unwrap().unwrap();
+        assert_eq!(failed["state"], json!("failed"));
+        assert_eq!(failed["reason"], json!("timeout"));
+        assert!(manager_a.peer_states().await.is_empty(), "timed out connections free their slot");
+    }
+    assert!(manager_a.is_relay_only(&mesh_b.pid));
+
+    // No more offers during the cooldown
+    manager_a.connect(&mesh_b.pid).await.unwrap();
+    assert!(manager_a.peer_states().await.is_empty());
+    sleep(Duration::from_millis(1500)).await;
+    assert!(offers_rx.try_recv().is_err());
+}
+
+/// Test three instances behind one relay, two in one room and one in
+/// another: only the pair in the same room connects directly
+#[tokio::test]
+async fn test_webrtc_peers_only_connect_within_their_room() {
+    let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let relay_key = test_key(19);
+    let mut relay = Gun::with_options(
+        relay_key.clone(),
+        relay_key.public_key(),
+        GunOptions {
+            super_peer: true,
+            port: Some(port),
+            localStorage: false,
+            require_signatures: false,
+            webrtc: WebRTCOptions {
+                enabled: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    sleep(Duration::from_millis(200)).await; // let the listener bind
+
+    let mut peers = Vec::new();
+    for (seed, room) in [(20, "doc-1"), (21, "doc-1"), (22, "doc-2")] {
+        let key = test_key(seed);
+        let gun = Gun::with_options(
+            key.clone(),
+            key.public_key(),
+            GunOptions {
+                localStorage: false,
+                radisk: false,
+                require_signatures: false,
+                webrtc: local_options().with_room(room),
+                ..GunOptions::with_relay(&format!("ws://127.0.0.1:{}/gun", port))
+            },
+        )
+        .await
+        .unwrap();
+        assert!(gun.wait_for_connection(5000).await);
+        peers.push(gun);
+    }
+    let managers: Vec<Arc<WebRTCManager>> = peers.iter().map(|gun| gun.webrtc().unwrap().clone()).collect();
+    let (a, b, c) = (&managers[0], &managers[1], &managers[2]);
+
+    let open = timeout(Duration::from_secs(20), async {
+        while !(a.is_open(b.pid()).await && b.is_open(a.pid()).await) {
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await;
+    assert!(open.is_ok(), "peers in the same room should connect");
+
+    // Give any offer across rooms time to have been made
+    sleep(Duration::from_secs(1)).await;
+    assert!(c.peer_states().await.is_empty(), "a peer alone in its room connects to no one");
+    assert_eq!(a.peer_states().await.keys().collect::<Vec<_>>(), vec![b.pid()]);
+    assert_eq!(b.peer_states().await.keys().collect::<Vec<_>>(), vec![a.pid()]);
+    assert_eq!(peers[0].transport_for(c.pid()).await, Transport::Relay);
+
+    for mut gun in peers {
+        gun.shutdown().await.unwrap();
+    }
+    relay.shutdown().await.unwrap();
+}
+
+// Two linked meshes with signaling managers, each with its own options,
+// and every signaling message each side receives
+async fn signaling_pair(
+    seeds: (u8, u8),
+    options: (WebRTCOptions, WebRTCOptions),
+) -> (Arc<WebRTCManager>, Arc<WebRTCManager>, mpsc::UnboundedReceiver<serde_json::Value>) {
+    let (core_a, core_b) = (Arc::new(GunCore::new()), Arc::new(GunCore::new()));
+    let (mesh_a, mesh_b) = (test_mesh(&core_a, seeds.0), test_mesh(&core_b, seeds.1));
+    let (signals_tx, signals_rx) = mpsc::unbounded_channel();
+    for core in [&core_a, &core_b] {
+        let signals_tx = signals_tx.clone();
+        core.events.on(
+            RTC_SIGNAL_EVENT,
+            Box::new(move |e: &gun::events::Event| {
+                let _ = signals_tx.send(e.data.clone());
+            }),
+        );
+    }
+    link(&mesh_a, &mesh_b).await;
+    timeout(Duration::from_secs(5), async {
+        while mesh_a.peer_id_for_pid(&mesh_b.pid).is_none() || mesh_b.peer_id_for_pid(&mesh_a.pid).is_none() {
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("pids exchanged in the ? handshake");
+
+    let manager_a = Arc::new(WebRTCManager::new(core_a, mesh_a, options.0));
+    let manager_b = Arc::new(WebRTCManager::new(core_b, mesh_b, options.1));
+    // Each announces itself, and the lower pid offers
+    WebRTCManager::start_signaling(&manager_a);
+    WebRTCManager::start_signaling(&manager_b);
+    (manager_a, manager_b, signals_rx)
+}
+
+async fn wait_connected(a: &WebRTCManager, b: &WebRTCManager) -> bool {
+    timeout(Duration::from_secs(20), async {
+        while !(a.is_open(b.pid()).await && b.is_open(a.pid()).await) {
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .is_ok()
+}
+
+/// Test offers, answers and candidates crossing the mesh encrypted between
+/// two managers with signaling pairs, and the connection they make
+#[tokio::test]
+async fn test_webrtc_signaling_is_encrypted_between_peers_with_pairs() {
+    let options = local_options().with_signaling_pair(gun::sea::pair().await.unwrap());
+    let other = local_options().with_signaling_pair(gun::sea::pair().await.unwrap());
+    let (a, b, mut signals) = signaling_pair((23, 24), (options, other)).await;
+    assert!(wait_connected(&a, &b).await, "peers should connect with encrypted signaling");
+
+    let (mut announced, mut sealed) = (0, 0);
+    while let Ok(msg) = signals.try_recv() {
+        let rtc = &msg["ok"]["rtc"];
+        for readable in ["offer", "answer", "candidate"] {
+            assert!(rtc.get(readable).is_none(), "{} sent in plaintext: {}", readable, msg);
+        }
+        if rtc.get("sea").is_some() {
+            sealed += 1;
+        } else if rtc.get("epub").is_some() {
+            announced += 1;
+        }
+    }
+    assert!(announced >= 2, "both managers announce their epub");
+    assert!(sealed >= 2, "at least the offer and the answer are encrypted");
+
+    a.close_all().await;
+    b.close_all().await;
+}
+
+/// Test signaling staying in plaintext, and still connecting, when only one
+/// of the two managers has a signaling pair
+#[tokio::test]
+async fn test_webrtc_signaling_falls_back_to_plaintext_without_a_pair() {
+    let options = local_options().with_signaling_pair(gun::sea::pair().await.unwrap());
+    let (a, b, mut signals) = signaling_pair((25, 26), (options, local_options())).await;
+    assert!(wait_connected(&a, &b).await, "peers should connect with plaintext signaling");
+
+    let (mut readable, mut sealed) = (0, 0);
+    while let Ok(msg) = signals.try_recv() {
+        let rtc = &msg["ok"]["rtc"];
+        if rtc.get("sea").is_some() {
+            sealed += 1;
+        }
+        if rtc.get("offer").is_some() || rtc.get("answer").is_some() {
+            readable += 1;
+        }
+    }
+    assert_eq!(sealed, 0, "nothing can be encrypted for a peer without an epub");
+    assert!(readable >= 2, "the offer and answer go in plaintext");
+
+    a.close_all().await;
+    b.close_all().await;
+}
+
+/// Test chunk frames put back together whatever order they arrive in, with
+/// duplicates, whole messages in between and a message that never completes
+#[test]
+fn test_webrtc_chunks_reassemble_out_of_order() {
+    use gun::webrtc::{chunk_frames, Reassembly, CHUNK_HEADER_LEN};
+
+    let big = "ü".repeat(300_000); // 600 KB, split mid-character
+    let small = "b".repeat(5_000);
+    assert!(chunk_frames(&small, 0, 0).is_none());
+    assert!(chunk_frames(&small, 5_000, 0).is_none());
+    let frames = chunk_frames(&big, 16 * 1024, 1).unwrap();
+    assert_eq!(frames.len(), big.len().div_ceil(16 * 1024));
+    assert!(frames.iter().all(|frame| frame.len() <= CHUNK_HEADER_LEN + 16 * 1024));
+    let other = chunk_frames(&small, 1_000, 2).unwrap();
+
+    let mut reassembly = Reassembly::new(Duration::from_secs(30));
+    let mut completed = Vec::new();
+    for (i, frame) in frames.iter().rev().enumerate() {
+        completed.extend(reassembly.add(frame));
+        if let Some(other) = other.get(i) {
+            completed.extend(reassembly.add(other));
+        }
+        if i == 3 {
+            completed.extend(reassembly.add(frame)); // a duplicate
+            completed.extend(reassembly.add(br#"{"dam":"?"}"#)); // not chunked
+            completed.extend(reassembly.add(&frame[..CHUNK_HEADER_LEN - 1])); // truncated
+        }
+    }
+    assert_eq!(completed, vec![r#"{"dam":"?"}"#.to_string(), small, big]);
+
+    // Chunks of a message that took too long are dropped
+    let mut reassembly = Reassembly::new(Duration::ZERO);
+    let frames = chunk_frames(&"c".repeat(100), 40, 3).unwrap();
+    assert_eq!(reassembly.add(&frames[0]), None);
+    assert_eq!(reassembly.add(&frames[1]), None);
+    assert_eq!(reassembly.add(&frames[2]), None);
+}
+
+/// Test a 1 MB message crossing a data channel between two local peers
+#[tokio::test]
+async fn test_webrtc_large_message_over_loopback_channel() {
+    use gun::webrtc::WebRTCRole;
+
+    let options = local_options();
+    let (offerer, _) = WebRTCPeer::new("answerer".to_string(), &options).await.unwrap();
+    let (answerer, mut received) = WebRTCPeer::with_role("offerer".to_string(), &options, WebRTCRole::Answerer)
+        .await
+        .unwrap();
+    let (offerer, answerer) = (Arc::new(offerer), Arc::new(answerer));
+    for (from, to) in [(&offerer, &answerer), (&answerer, &offerer)] {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        from.on_local_candidate(tx);
+        let to = to.clone();
+        tokio::spawn(async move {
+            while let Some(candidate) = rx.recv().await {
+                let _ = to.add_ice_candidate(candidate).await;
+            }
+        });
+    }
+    let offer = offerer.create_offer().await.unwrap();
+    answerer.set_remote_description(offer).await.unwrap();
+    let answer = answerer.create_answer().await.unwrap();
+    offerer.set_remote_description(answer).await.unwrap();
+
+    let open = timeout(Duration::from_secs(20), async {
+        while !(offerer.is_open() && answerer.is_open()) {
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await;
+    assert!(open.is_ok(), "the loopback data channel should open");
+
+    let message = json!({ "#": "big", "put": { "big": { "_": { "#": "big", ">": { "v": 1 } }, "v": "x".repeat(1 << 20) } } })
+        .to_string();
+    offerer.send(&message).await.unwrap();
+    let got = timeout(Duration::from_secs(10), received.recv()).await.unwrap().unwrap();
+    assert_eq!(got.len(), message.len());
+    assert_eq!(got, message);
+
+    offerer.close().await.unwrap();
+    answerer.close().await.unwrap();
+}
+
+/// Test both managers offering to each other at the same moment: one offer
+/// is rolled back and a single connection comes up
+#[tokio::test]
+async fn test_webrtc_simultaneous_offers_settle_on_one_connection() {
+    let (core_a, core_b) = (Arc::new(GunCore::new()), Arc::new(GunCore::new()));
+    let (mesh_a, mesh_b) = (test_mesh(&core_a, 12), test_mesh(&core_b, 13));
+    link(&mesh_a, &mesh_b).await;
+    timeout(Duration::from_secs(5), async {
+        while mesh_a.peer_id_for_pid(&mesh_b.pid).is_none() || mesh_b.peer_id_for_pid(&mesh_a.pid).is_none() {
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("pids exchanged in the ? handshake");
+    let manager_a = Arc::new(WebRTCManager::new(core_a, mesh_a.clone(), local_options()));
+    let manager_b = Arc::new(WebRTCManager::new(core_b, mesh_b.clone(), local_options()));
+    WebRTCManager::start_signaling(&manager_a);
+    WebRTCManager::start_signaling(&manager_b);
+
+    let (a, b) = tokio::join!(manager_a.connect(&mesh_b.pid), manager_b.connect(&mesh_a.pid));
+    a.unwrap();
+    b.unwrap();
+
+    let connected = timeout(Duration::from_secs(20), async {
+        loop {
+            let states = (manager_a.peer_state(&mesh_b.pid).await, manager_b.peer_state(&mesh_a.pid).await);
+            if states == (Some(RTCPeerConnectionState::Connected), Some(RTCPeerConnectionState::Connected)) {
+                return;
+            }
+            sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await;
+    assert!(connected.is_ok(), "colliding offers should still end in a connection");
+    assert_eq!(manager_a.peer_states().await.len(), 1);
+    assert_eq!(manager_b.peer_states().await.len(), 1);
+    assert!(manager_a.is_open(&mesh_b.pid).await && manager_b.is_open(&mesh_a.pid).await);
+
+    manager_a.close_all().await;
+    manager_b.close_all().await;
+}